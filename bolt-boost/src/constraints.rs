@@ -1,7 +1,8 @@
 use alloy::eips::eip2718::Eip2718Error;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use std::{collections::HashMap, sync::Arc};
-use tracing::error;
+use tokio::{sync::watch, task::JoinHandle};
+use tracing::{debug, error};
 
 use crate::metrics;
 
@@ -9,10 +10,17 @@ use super::types::{ConstraintsMessage, ConstraintsWithProofData};
 
 pub(crate) const MAX_CONSTRAINTS_PER_SLOT: usize = 128;
 
+/// The constraints saved for a single slot, behind their own lock.
+type SlotConstraints = Arc<Mutex<Vec<ConstraintsWithProofData>>>;
+
 /// A concurrent cache of constraints.
+///
+/// Every slot has its own lock, so that inserts for different slots can proceed
+/// concurrently while inserts for the same slot are serialized. The outer lock is
+/// only taken for writing when a slot is created or removed.
 #[derive(Clone, Default, Debug)]
 pub struct ConstraintsCache {
-    cache: Arc<RwLock<HashMap<u64, Vec<ConstraintsWithProofData>>>>,
+    cache: Arc<RwLock<HashMap<u64, SlotConstraints>>>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -45,47 +53,37 @@ impl ConstraintsCache {
     /// - Multiple ToB constraints per slot
     /// - Duplicates of the same transaction per slot
     pub fn conflicts_with(&self, slot: &u64, constraints: &ConstraintsMessage) -> Option<Conflict> {
-        if let Some(saved_constraints) = self.cache.read().get(slot) {
-            for saved_constraint in saved_constraints {
-                // Only 1 ToB constraint per slot
-                if constraints.top && saved_constraint.message.top {
-                    return Some(Conflict::TopOfBlock);
-                }
-
-                // Check if the transactions are the same
-                for tx in &constraints.transactions {
-                    if saved_constraint.message.transactions.iter().any(|existing| tx == existing) {
-                        return Some(Conflict::DuplicateTransaction);
-                    }
-                }
-            }
-        }
+        let cache = self.cache.read();
+        let saved_constraints = cache.get(slot)?.lock();
 
-        None
+        find_conflict(&saved_constraints, constraints)
     }
 
     /// Inserts the constraints for the given slot. Also decodes the raw transactions to save their
     /// transaction hashes and hash tree roots for later use. Will first check for conflicts, and
     /// return an error if there are any.
+    ///
+    /// The conflict check and the insertion happen atomically under the lock of the slot.
     pub fn insert(&self, slot: u64, constraints: ConstraintsMessage) -> Result<(), Error> {
-        if let Some(conflict) = self.conflicts_with(&slot, &constraints) {
-            return Err(conflict.into());
-        }
-
+        // Decode outside of any lock, this is the expensive part
         let message_with_data = ConstraintsWithProofData::try_from(constraints)?;
 
-        let mut cache = self.cache.write();
-        if let Some(cs) = cache.get_mut(&slot) {
-            if cs.len() >= MAX_CONSTRAINTS_PER_SLOT {
-                error!("Max constraints per slot reached for slot {}", slot);
-                return Err(Error::LimitReached(slot));
-            }
+        // Hold the outer read lock while working on the slot, so that it can't be removed
+        // from under us. Other slots can still be read and written concurrently.
+        let cache = self.slot_entry(slot);
+        let mut saved_constraints = cache.get(&slot).expect("slot entry exists").lock();
+
+        if let Some(conflict) = find_conflict(&saved_constraints, &message_with_data.message) {
+            return Err(conflict.into());
+        }
 
-            cs.push(message_with_data);
-        } else {
-            cache.insert(slot, vec![message_with_data]);
+        if saved_constraints.len() >= MAX_CONSTRAINTS_PER_SLOT {
+            error!("Max constraints per slot reached for slot {}", slot);
+            return Err(Error::LimitReached(slot));
         }
 
+        saved_constraints.push(message_with_data);
+
         metrics::CONSTRAINTS_CACHE_SIZE.inc();
 
         Ok(())
@@ -97,20 +95,79 @@ impl ConstraintsCache {
         metrics::CONSTRAINTS_CACHE_SIZE.set(self.total_constraints() as i64);
     }
 
+    /// Spawns a task that prunes the constraints of all slots up to and including the head
+    /// slot, every time the head advances. Those slots have already been proposed, so their
+    /// constraints are only dead weight for duplicate checks.
+    pub fn spawn_pruner(&self, mut head_slot: watch::Receiver<u64>) -> JoinHandle<()> {
+        let cache = self.clone();
+        tokio::spawn(async move {
+            while head_slot.changed().await.is_ok() {
+                let slot = *head_slot.borrow_and_update();
+                debug!(slot, "Pruning constraints up to head slot");
+                cache.remove_before(slot + 1);
+            }
+        })
+    }
+
     /// Gets and removes the constraints for the given slot.
     pub fn remove(&self, slot: u64) -> Option<Vec<ConstraintsWithProofData>> {
-        self.cache.write().remove(&slot).inspect(|c| {
-            metrics::CONSTRAINTS_CACHE_SIZE.sub(c.len() as i64);
-        })
+        let removed = self.cache.write().remove(&slot)?;
+
+        // No insert can be holding the slot lock at this point, as inserts hold the outer lock.
+        let constraints = std::mem::take(&mut *removed.lock());
+        metrics::CONSTRAINTS_CACHE_SIZE.sub(constraints.len() as i64);
+
+        // The entry may have been created by an insert which then failed
+        (!constraints.is_empty()).then_some(constraints)
+    }
+
+    /// Returns a read guard over the cache in which the entry for the given slot exists,
+    /// creating it if needed.
+    fn slot_entry(&self, slot: u64) -> RwLockReadGuard<'_, HashMap<u64, SlotConstraints>> {
+        let cache = self.cache.read();
+        if cache.contains_key(&slot) {
+            return cache;
+        }
+        drop(cache);
+
+        let mut cache = self.cache.write();
+        cache.entry(slot).or_default();
+        RwLockWriteGuard::downgrade(cache)
     }
 
     fn total_constraints(&self) -> usize {
-        self.cache.read().values().map(|v| v.len()).sum()
+        self.cache.read().values().map(|v| v.lock().len()).sum()
     }
 }
 
+/// Checks the new constraints against the ones already saved for the same slot.
+fn find_conflict(
+    saved_constraints: &[ConstraintsWithProofData],
+    constraints: &ConstraintsMessage,
+) -> Option<Conflict> {
+    for saved_constraint in saved_constraints {
+        // Only 1 ToB constraint per slot
+        if constraints.top && saved_constraint.message.top {
+            return Some(Conflict::TopOfBlock);
+        }
+
+        // Check if the transactions are the same
+        for tx in &constraints.transactions {
+            if saved_constraint.message.transactions.iter().any(|existing| tx == existing) {
+                return Some(Conflict::DuplicateTransaction);
+            }
+        }
+    }
+
+    None
+}
+
 #[cfg(test)]
 mod tests {
+    use std::{sync::mpsc, thread, time::Duration};
+
+    use tokio::{sync::watch, time::sleep};
+
     use alloy::{primitives::bytes, rpc::types::beacon::BlsPublicKey};
 
     use super::*;
@@ -139,4 +196,81 @@ mod tests {
 
         assert!(cache.conflicts_with(&1, &constraints).is_none());
     }
+
+    #[test]
+    fn test_constraints_cache_per_slot_locking() {
+        let cache = ConstraintsCache::new();
+
+        let tx = bytes!("f86481d8088302088a808090435b8080556001015a6161a8106001578718e5bb3abd109fa0ea5ad6553fb67639cec694e6697ac7b718bd7044fcdf5608fa64f6058e67db93a03953b5792d7d9ef7fc602fbe260e7a290760e8adc634f99ab1896e2c0d55afcb");
+        let constraints = |slot| ConstraintsMessage {
+            pubkey: BlsPublicKey::default(),
+            slot,
+            top: false,
+            transactions: vec![tx.clone()],
+        };
+
+        cache.insert(1, constraints(1)).unwrap();
+
+        // Simulate a long-running insert on slot 1 by holding its lock
+        let slot_one = cache.cache.read().get(&1).cloned().unwrap();
+        let slot_one_guard = slot_one.lock();
+
+        // An insert for a different slot completes while slot 1 is locked
+        let (tx_other, rx_other) = mpsc::channel();
+        let other_cache = cache.clone();
+        let other = constraints(2);
+        thread::spawn(move || tx_other.send(other_cache.insert(2, other).is_ok()).unwrap());
+        assert!(rx_other.recv_timeout(Duration::from_secs(5)).unwrap());
+
+        // An insert for the same slot waits for the lock to be released
+        let (tx_same, rx_same) = mpsc::channel();
+        let same_cache = cache.clone();
+        let same = constraints(1);
+        thread::spawn(move || tx_same.send(same_cache.insert(1, same)).unwrap());
+        assert!(rx_same.recv_timeout(Duration::from_millis(100)).is_err());
+
+        drop(slot_one_guard);
+        let res = rx_same.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(res, Err(Error::Conflict(Conflict::DuplicateTransaction))));
+
+        assert_eq!(cache.remove(1).unwrap().len(), 1);
+        assert_eq!(cache.remove(2).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_constraints_cache_pruned_on_head_slot() {
+        let cache = ConstraintsCache::new();
+
+        let tx = bytes!("f86481d8088302088a808090435b8080556001015a6161a8106001578718e5bb3abd109fa0ea5ad6553fb67639cec694e6697ac7b718bd7044fcdf5608fa64f6058e67db93a03953b5792d7d9ef7fc602fbe260e7a290760e8adc634f99ab1896e2c0d55afcb");
+        for slot in 1..=3 {
+            let constraints = ConstraintsMessage {
+                pubkey: BlsPublicKey::default(),
+                slot,
+                top: false,
+                transactions: vec![tx.clone()],
+            };
+            cache.insert(slot, constraints).unwrap();
+        }
+
+        let (head_tx, head_rx) = watch::channel(0);
+        let pruner = cache.spawn_pruner(head_rx);
+
+        let has_slot = |slot| cache.cache.read().contains_key(&slot);
+
+        head_tx.send(2).unwrap();
+        for _ in 0..100 {
+            if !has_slot(2) {
+                break;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+
+        assert!(!has_slot(1));
+        assert!(!has_slot(2));
+        assert!(has_slot(3));
+
+        // The pruner stops once the head slot source is gone
+        drop(head_tx);
+        pruner.await.unwrap();
+    }
 }
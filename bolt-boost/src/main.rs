@@ -7,8 +7,10 @@ use cb_pbs::{PbsService, PbsState};
 mod constraints;
 mod error;
 mod metrics;
+mod migration;
 mod proofs;
 mod server;
+mod slot_clock;
 mod types;
 
 #[cfg(test)]
@@ -31,7 +33,19 @@ async fn main() -> Result<()> {
         tracing::info!("ID: {} - URI: {}", relay.id, relay.config.entry.url);
     }
 
-    let custom_state = BuilderState::from_config(extra);
+    if let Some(migration) = &extra.relay_migration {
+        tracing::info!(
+            ends_at = migration.ends_at,
+            "Migrating relays, also posting to the following old relays:"
+        );
+
+        for relay in &migration.old_relays {
+            tracing::info!("ID: {} - URI: {}", relay.entry.id, relay.entry.url);
+        }
+    }
+
+    let custom_state = BuilderState::from_config(extra)?;
+    custom_state.spawn_constraints_pruner(chain);
     let state = PbsState::new(pbs_config).with_data(custom_state);
 
     metrics::init_metrics()?;
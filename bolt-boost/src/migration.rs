@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use cb_common::{config::RelayConfig, pbs::RelayClient};
+use serde::Deserialize;
+
+/// Configuration of a relay migration, set in the `[pbs.relay_migration]` section.
+///
+/// The `[[relays]]` of the PBS module are the new relay set, while the old set keeps receiving
+/// the constraints, delegations and revocations until the end of the migration window.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RelayMigrationConfig {
+    /// The relays the operator is migrating away from.
+    pub old_relays: Vec<RelayConfig>,
+    /// The UNIX timestamp in seconds at which the migration window ends. It is absolute, so
+    /// that restarting bolt-boost doesn't extend the window.
+    pub ends_at: u64,
+}
+
+/// A migration from an old relay set to the one of the PBS module.
+#[derive(Clone)]
+pub struct RelayMigration {
+    old_relays: Vec<RelayClient>,
+    ends_at: Duration,
+}
+
+impl RelayMigration {
+    /// Create a new migration from the given configuration.
+    pub fn from_config(config: RelayMigrationConfig) -> eyre::Result<Self> {
+        let old_relays =
+            config.old_relays.into_iter().map(RelayClient::new).collect::<eyre::Result<_>>()?;
+        Ok(Self::new(old_relays, Duration::from_secs(config.ends_at)))
+    }
+
+    /// Create a new migration posting to the old relays until the given UNIX time.
+    pub fn new(old_relays: Vec<RelayClient>, ends_at: Duration) -> Self {
+        Self { old_relays, ends_at }
+    }
+
+    /// Returns the old relays if the migration window is still open at the given UNIX time,
+    /// otherwise none.
+    pub fn old_relays_at(&self, now: Duration) -> &[RelayClient] {
+        if now < self.ends_at {
+            &self.old_relays
+        } else {
+            &[]
+        }
+    }
+}
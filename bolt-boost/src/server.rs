@@ -12,13 +12,14 @@ use axum::{
     Json, Router,
 };
 use eyre::Result;
-use futures::{future::join_all, stream::FuturesUnordered, StreamExt};
+use futures::{future::join_all, stream, StreamExt};
 use serde::Serialize;
 use std::{
     collections::HashMap,
+    num::NonZeroUsize,
     time::{Duration, Instant},
 };
-use tokio::time::sleep;
+use tokio::{sync::watch, time::sleep};
 use tracing::{debug, error, info, warn, Instrument};
 
 use cb_common::{
@@ -42,7 +43,9 @@ use crate::metrics::{
 use super::{
     constraints::ConstraintsCache,
     error::PbsClientError,
+    migration::RelayMigration,
     proofs::verify_multiproofs,
+    slot_clock::{SlotClock, SystemTimeSource, TimeSource},
     types::{
         Config, GetHeaderParams, GetHeaderWithProofsResponse, RequestConfig, SignedConstraints,
         SignedDelegation, SignedExecutionPayloadHeaderWithProofs, SignedRevocation,
@@ -60,17 +63,51 @@ const TIMEOUT_ERROR_CODE: u16 = 555;
 // Extra state available at runtime
 #[derive(Clone)]
 pub struct BuilderState {
-    #[allow(unused)]
     config: Config,
     constraints: ConstraintsCache,
+    /// The migration from an old relay set, if any.
+    migration: Option<RelayMigration>,
 }
 
 impl BuilderApiState for BuilderState {}
 
 impl BuilderState {
-    pub fn from_config(config: Config) -> Self {
-        Self { config, constraints: ConstraintsCache::new() }
+    pub fn from_config(config: Config) -> Result<Self> {
+        let migration = config.relay_migration.clone().map(RelayMigration::from_config);
+        Ok(Self { config, constraints: ConstraintsCache::new(), migration: migration.transpose()? })
     }
+
+    /// Returns the relays to post to: the given ones of the PBS module, and the old relays
+    /// while a migration window is open at the UNIX time `now`.
+    fn post_targets(&self, relays: &[RelayClient], now: Duration) -> Vec<RelayClient> {
+        let old_relays = self.migration.as_ref().map(|m| m.old_relays_at(now)).unwrap_or_default();
+        relays.iter().chain(old_relays).cloned().collect()
+    }
+
+    /// Starts pruning the constraints of past slots as the head slot of the chain advances.
+    pub fn spawn_constraints_pruner(&self, chain: Chain) {
+        self.constraints.spawn_pruner(spawn_head_slot_clock(SlotClock::for_chain(chain)));
+    }
+}
+
+/// Spawns a task that tracks the head slot of the chain from the given slot clock, and
+/// returns a channel that is updated at the start of every slot.
+///
+/// The slot that just started is still being proposed, so the head is the one before it.
+fn spawn_head_slot_clock<T: TimeSource>(clock: SlotClock<T>) -> watch::Receiver<u64> {
+    let (head_tx, head_rx) = watch::channel(0);
+
+    tokio::spawn(async move {
+        loop {
+            if head_tx.send(clock.current_slot().saturating_sub(1)).is_err() {
+                break;
+            }
+
+            sleep(clock.until_next_slot()).await;
+        }
+    });
+
+    head_rx
 }
 
 /// An extended builder-API that implements the constraints-API as defined in
@@ -527,15 +564,32 @@ async fn post_request<T>(
 where
     T: Serialize,
 {
-    debug!("Sending POST request to {} relays", state.relays().len());
-    // Forward constraints to all relays.
-    let mut responses = FuturesUnordered::new();
+    let relays = state.data.post_targets(state.relays(), SystemTimeSource.now());
+    let max_concurrency = state.data.config.max_concurrent_relay_requests;
+    post_to_relays(&relays, path, body, max_concurrency).await
+}
 
-    for relay in state.relays() {
+/// Send a POST request to each of the relays, succeeding if at least one of them accepted it.
+/// At most `max_concurrency` requests are in flight at once.
+async fn post_to_relays<T>(
+    relays: &[RelayClient],
+    path: &str,
+    body: &T,
+    max_concurrency: NonZeroUsize,
+) -> Result<(), PbsClientError>
+where
+    T: Serialize,
+{
+    debug!("Sending POST request to {} relays", relays.len());
+    // Forward constraints to all relays.
+    let mut requests = Vec::with_capacity(relays.len());
+    for relay in relays {
         let url = relay.get_url(path).map_err(|_| PbsClientError::BadRequest)?;
-        responses.push(relay.client.post(url).json(&body).send());
+        requests.push(relay.client.post(url).json(&body).send());
     }
 
+    let mut responses = stream::iter(requests).buffer_unordered(max_concurrency.get());
+
     let mut success = false;
     while let Some(res) = responses.next().await {
         match res {
@@ -560,3 +614,129 @@ where
         Err(PbsClientError::NoResponse)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        num::NonZeroUsize,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use axum::{extract::State, http::StatusCode, routing::post, Router};
+    use cb_common::{config::RelayConfig, pbs::RelayClient};
+    use serde_json::json;
+    use tokio::net::TcpListener;
+
+    use super::{post_to_relays, BuilderState, SUBMIT_CONSTRAINTS_PATH};
+    use crate::{
+        migration::RelayMigration,
+        types::{Config, SignedConstraints, DEFAULT_MAX_CONCURRENT_RELAY_REQUESTS},
+    };
+
+    const RELAY_PUBKEY: &str = "0xa55c1285d84ba83a5ad26420cd5ad3091e49c55a813eee651cd467db38a8c8e63192f47955e9376f6b42f6d190571cb5";
+
+    /// Serves the router as a mock relay, and returns a client to it.
+    async fn serve_relay(id: &str, router: Router) -> RelayClient {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{RELAY_PUBKEY}@{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let config: RelayConfig = serde_json::from_value(json!({ "id": id, "url": url })).unwrap();
+        RelayClient::new(config).unwrap()
+    }
+
+    /// Spawns a mock relay counting the constraints submissions it receives.
+    async fn spawn_relay(id: &str) -> (RelayClient, Arc<Mutex<usize>>) {
+        let received = Arc::new(Mutex::new(0));
+        let router = Router::new()
+            .route(
+                SUBMIT_CONSTRAINTS_PATH,
+                post(|State(received): State<Arc<Mutex<usize>>>| async move {
+                    *received.lock().unwrap() += 1;
+                    StatusCode::OK
+                }),
+            )
+            .with_state(received.clone());
+
+        (serve_relay(id, router).await, received)
+    }
+
+    #[tokio::test]
+    async fn test_relay_migration_window() {
+        let (new_relay, new_received) = spawn_relay("new").await;
+        let (old_relay, old_received) = spawn_relay("old").await;
+
+        let config = Config {
+            relay_migration: None,
+            max_concurrent_relay_requests: DEFAULT_MAX_CONCURRENT_RELAY_REQUESTS,
+        };
+        let mut state = BuilderState::from_config(config).unwrap();
+        let max_concurrency = state.config.max_concurrent_relay_requests;
+        let ends_at = Duration::from_secs(1_700_000_000);
+        state.migration = Some(RelayMigration::new(vec![old_relay], ends_at));
+
+        let constraints: Vec<SignedConstraints> = Vec::new();
+        let new_relays = [new_relay];
+
+        // During the migration window, both relay sets receive the constraints
+        let relays = state.post_targets(&new_relays, ends_at - Duration::from_secs(1));
+        post_to_relays(&relays, SUBMIT_CONSTRAINTS_PATH, &constraints, max_concurrency)
+            .await
+            .unwrap();
+        assert_eq!(*new_received.lock().unwrap(), 1);
+        assert_eq!(*old_received.lock().unwrap(), 1);
+
+        // Once it has ended, only the new set does, whenever bolt-boost was started
+        let relays = state.post_targets(&new_relays, ends_at);
+        post_to_relays(&relays, SUBMIT_CONSTRAINTS_PATH, &constraints, max_concurrency)
+            .await
+            .unwrap();
+        assert_eq!(*new_received.lock().unwrap(), 2);
+        assert_eq!(*old_received.lock().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_relay_fan_out_concurrency_limit() {
+        /// The number of requests in flight across the relays, and the highest it got.
+        #[derive(Default)]
+        struct InFlight {
+            current: usize,
+            max: usize,
+            total: usize,
+        }
+
+        let in_flight = Arc::new(Mutex::new(InFlight::default()));
+        let mut relays = Vec::new();
+        for i in 0..6 {
+            let router = Router::new()
+                .route(
+                    SUBMIT_CONSTRAINTS_PATH,
+                    post(|State(in_flight): State<Arc<Mutex<InFlight>>>| async move {
+                        {
+                            let mut in_flight = in_flight.lock().unwrap();
+                            in_flight.current += 1;
+                            in_flight.total += 1;
+                            in_flight.max = in_flight.max.max(in_flight.current);
+                        }
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        in_flight.lock().unwrap().current -= 1;
+                        StatusCode::OK
+                    }),
+                )
+                .with_state(in_flight.clone());
+            relays.push(serve_relay(&format!("relay-{i}"), router).await);
+        }
+
+        let constraints: Vec<SignedConstraints> = Vec::new();
+        let max_concurrency = NonZeroUsize::new(2).unwrap();
+        post_to_relays(&relays, SUBMIT_CONSTRAINTS_PATH, &constraints, max_concurrency)
+            .await
+            .unwrap();
+
+        // Every relay was hit, but never more than the configured number at once
+        let in_flight = in_flight.lock().unwrap();
+        assert_eq!(in_flight.total, 6);
+        assert_eq!(in_flight.max, 2);
+    }
+}
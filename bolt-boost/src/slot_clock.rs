@@ -0,0 +1,109 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use cb_common::{types::Chain, utils::timestamp_of_slot_start_millis};
+
+/// A source of the current UNIX time.
+pub trait TimeSource: Clone + Send + Sync + 'static {
+    /// Returns the time elapsed since the UNIX epoch.
+    fn now(&self) -> Duration;
+}
+
+/// The system clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemTimeSource;
+
+impl TimeSource for SystemTimeSource {
+    fn now(&self) -> Duration {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default()
+    }
+}
+
+/// A clock of the beacon chain slots, following the given time source.
+///
+/// Before genesis, the clock stays at the start of slot 0.
+#[derive(Debug, Clone)]
+pub struct SlotClock<T = SystemTimeSource> {
+    /// The UNIX time at which the chain started.
+    genesis_time: Duration,
+    /// The slot time of the chain.
+    slot_time: Duration,
+    /// The source of the current time.
+    time: T,
+}
+
+impl SlotClock {
+    /// Create a new slot clock with the genesis and slot times of the given chain, following
+    /// the system clock.
+    pub fn for_chain(chain: Chain) -> Self {
+        let genesis_time_ms = timestamp_of_slot_start_millis(0, chain);
+        let slot_time_ms = timestamp_of_slot_start_millis(1, chain) - genesis_time_ms;
+
+        Self::with_time_source(
+            Duration::from_millis(genesis_time_ms),
+            Duration::from_millis(slot_time_ms),
+            SystemTimeSource,
+        )
+    }
+}
+
+impl<T: TimeSource> SlotClock<T> {
+    /// Create a new slot clock following the given time source.
+    pub fn with_time_source(genesis_time: Duration, slot_time: Duration, time: T) -> Self {
+        Self { genesis_time, slot_time, time }
+    }
+
+    /// Returns the current slot.
+    pub fn current_slot(&self) -> u64 {
+        self.since_genesis().as_millis() as u64 / self.slot_time.as_millis() as u64
+    }
+
+    /// Returns the time left until the start of the next slot.
+    pub fn until_next_slot(&self) -> Duration {
+        let into_slot = self.since_genesis().as_millis() % self.slot_time.as_millis();
+        self.slot_time - Duration::from_millis(into_slot as u64)
+    }
+
+    /// Returns the time elapsed since genesis.
+    fn since_genesis(&self) -> Duration {
+        self.time.now().saturating_sub(self.genesis_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use super::{SlotClock, TimeSource};
+
+    /// A time source set manually.
+    #[derive(Debug, Clone, Default)]
+    struct MockTime(Arc<Mutex<Duration>>);
+
+    impl TimeSource for MockTime {
+        fn now(&self) -> Duration {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    #[test]
+    fn test_slot_clock() {
+        let time = MockTime::default();
+        let genesis = Duration::from_secs(1_000);
+        let clock = SlotClock::with_time_source(genesis, Duration::from_secs(6), time.clone());
+
+        // Before genesis, the clock stays at the start of slot 0
+        *time.0.lock().unwrap() = Duration::from_secs(10);
+        assert_eq!(clock.current_slot(), 0);
+
+        *time.0.lock().unwrap() = genesis + Duration::from_millis(6_000 * 10 + 2_500);
+        assert_eq!(clock.current_slot(), 10);
+        assert_eq!(clock.until_next_slot(), Duration::from_millis(3_500));
+
+        *time.0.lock().unwrap() = genesis + Duration::from_secs(6 * 11);
+        assert_eq!(clock.current_slot(), 11);
+        assert_eq!(clock.until_next_slot(), Duration::from_secs(6));
+    }
+}
@@ -9,7 +9,7 @@ use axum::http::HeaderMap;
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
 use ssz_derive::{Decode, Encode};
-use std::ops::Deref;
+use std::{num::NonZeroUsize, ops::Deref};
 use tracing::error;
 use tree_hash::TreeHash;
 
@@ -21,11 +21,29 @@ use cb_common::{
     types::Chain,
 };
 
+use crate::migration::RelayMigrationConfig;
+
 /// A hash tree root.
 pub type HashTreeRoot = tree_hash::Hash256;
 
+/// The default maximum number of concurrent requests to the relays.
+pub const DEFAULT_MAX_CONCURRENT_RELAY_REQUESTS: NonZeroUsize = match NonZeroUsize::new(16) {
+    Some(max) => max,
+    None => unreachable!(),
+};
+
 #[derive(Debug, Clone, Deserialize)]
-pub struct Config {}
+pub struct Config {
+    /// The migration from an old relay set, if the operator is changing relays.
+    pub relay_migration: Option<RelayMigrationConfig>,
+    /// The maximum number of requests in flight at once when posting to the relays.
+    #[serde(default = "default_max_concurrent_relay_requests")]
+    pub max_concurrent_relay_requests: NonZeroUsize,
+}
+
+fn default_max_concurrent_relay_requests() -> NonZeroUsize {
+    DEFAULT_MAX_CONCURRENT_RELAY_REQUESTS
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy)]
 pub struct GetHeaderParams {
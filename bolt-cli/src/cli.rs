@@ -1,13 +1,19 @@
 use std::path::PathBuf;
 
-use alloy::primitives::{Address, B256, U256};
+use alloy::primitives::{Address, Bytes, B256, U256};
+use bolt_sidecar::{
+    common::secrets::JwtSecretConfig, config::ChainConfig, primitives::MessageEncoding,
+};
 use clap::{
     builder::styling::{AnsiColor, Color, Style},
     Parser, Subcommand, ValueEnum,
 };
 use reqwest::Url;
 
-use crate::{common::keystore::DEFAULT_KEYSTORE_PASSWORD, contracts::EigenLayerStrategy};
+use crate::{
+    common::{keystore::DEFAULT_KEYSTORE_PASSWORD, signer::SignerUri},
+    contracts::EigenLayerStrategy,
+};
 
 /// `bolt` is a CLI tool to interact with bolt Protocol ✨
 #[derive(Parser, Debug, Clone)]
@@ -23,6 +29,9 @@ pub enum Cmd {
     /// Generate BLS delegation or revocation messages.
     Delegate(DelegateCommand),
 
+    /// Revoke previously issued BLS delegations.
+    Revoke(RevokeCommand),
+
     /// Output a list of pubkeys in JSON format.
     Pubkeys(PubkeysCommand),
 
@@ -34,6 +43,12 @@ pub enum Cmd {
 
     /// Handle operators in the bolt network.
     Operators(OperatorsCommand),
+
+    /// Compute the signing root of a constraints message.
+    SigningRoot(SigningRootCommand),
+
+    /// Build a fallback payload against an engine API, to test the engine setup of a sidecar.
+    BuildTest(BuildTestCommand),
 }
 
 impl Cmd {
@@ -41,10 +56,13 @@ impl Cmd {
     pub async fn run(self) -> eyre::Result<()> {
         match self {
             Self::Delegate(cmd) => cmd.run().await,
+            Self::Revoke(cmd) => cmd.run().await,
             Self::Pubkeys(cmd) => cmd.run().await,
             Self::Send(cmd) => cmd.run().await,
             Self::Validators(cmd) => cmd.run().await,
             Self::Operators(cmd) => cmd.run().await,
+            Self::SigningRoot(cmd) => cmd.run().await,
+            Self::BuildTest(cmd) => cmd.run().await,
         }
     }
 }
@@ -60,6 +78,11 @@ pub struct DelegateCommand {
     #[clap(long, env = "OUTPUT_FILE_PATH", default_value = "delegations.json")]
     pub out: String,
 
+    /// The encoding of the output file. Only JSON delegations can be read back by the
+    /// `revoke` command.
+    #[clap(long, env = "OUTPUT_FORMAT", default_value = "json")]
+    pub format: MessageEncoding,
+
     /// The chain for which the delegation message is intended.
     #[clap(long, env = "CHAIN", default_value = "mainnet")]
     pub chain: Chain,
@@ -69,9 +92,64 @@ pub struct DelegateCommand {
     #[clap(long, env = "ACTION", default_value = "delegate")]
     pub action: Action,
 
+    /// The URLs of the relays to post the signed messages to, through the Constraints API.
+    /// Multiple URLs must be separated by commas. If empty, the messages are only saved.
+    #[clap(long, env = "RELAY_URLS", value_delimiter = ',')]
+    pub relay_urls: Vec<Url>,
+
+    /// The URLs of the relays expecting SSZ-encoded messages instead of JSON ones.
+    /// Multiple URLs must be separated by commas.
+    #[clap(long, env = "SSZ_RELAY_URLS", value_delimiter = ',')]
+    pub ssz_relay_urls: Vec<Url>,
+
+    /// The URI of the signing backend, as an alternative to the key source subcommands:
+    /// `keystore://<path>`, `dirk://<host>:<port>` or `commit-boost://<host>:<port>`.
+    #[clap(long, env = "SIGNER")]
+    pub signer: Option<SignerUri>,
+
     /// The source of the private key.
     #[clap(subcommand)]
-    pub source: SecretsSource,
+    pub source: Option<SecretsSource>,
+}
+
+/// Command for revoking the delegations saved by the `delegate` command, e.g. when rotating
+/// the sidecar key.
+#[derive(Debug, Clone, Parser)]
+pub struct RevokeCommand {
+    /// The path to the JSON file of the signed delegations to revoke.
+    #[clap(long, env = "DELEGATIONS_PATH", default_value = "delegations.json")]
+    pub delegations_path: PathBuf,
+
+    /// The output file for the revocations.
+    #[clap(long, env = "OUTPUT_FILE_PATH", default_value = "revocations.json")]
+    pub out: String,
+
+    /// The encoding of the output file.
+    #[clap(long, env = "OUTPUT_FORMAT", default_value = "json")]
+    pub format: MessageEncoding,
+
+    /// The chain for which the delegations were issued.
+    #[clap(long, env = "CHAIN", default_value = "mainnet")]
+    pub chain: Chain,
+
+    /// The URLs of the relays to post the revocations to, through the Constraints API.
+    /// Multiple URLs must be separated by commas. If empty, the revocations are only saved.
+    #[clap(long, env = "RELAY_URLS", value_delimiter = ',')]
+    pub relay_urls: Vec<Url>,
+
+    /// The URLs of the relays expecting SSZ-encoded revocations instead of JSON ones.
+    /// Multiple URLs must be separated by commas.
+    #[clap(long, env = "SSZ_RELAY_URLS", value_delimiter = ',')]
+    pub ssz_relay_urls: Vec<Url>,
+
+    /// The URI of the signing backend, as an alternative to the key source subcommands:
+    /// `keystore://<path>`, `dirk://<host>:<port>` or `commit-boost://<host>:<port>`.
+    #[clap(long, env = "SIGNER")]
+    pub signer: Option<SignerUri>,
+
+    /// The source of the private keys that issued the delegations.
+    #[clap(subcommand)]
+    pub source: Option<SecretsSource>,
 }
 
 /// Command for outputting a list of pubkeys in JSON format.
@@ -86,6 +164,55 @@ pub struct PubkeysCommand {
     pub source: KeysSource,
 }
 
+/// Command for computing the signing root of a constraints message, as signed by the
+/// bolt sidecar.
+#[derive(Debug, Clone, Parser)]
+pub struct SigningRootCommand {
+    /// The path to the JSON constraints message, with the same format as the `message`
+    /// field of the signed constraints posted to the relays.
+    #[clap(long, env = "CONSTRAINTS_MESSAGE_PATH")]
+    pub message_path: PathBuf,
+
+    /// The chain on which the constraints message is signed.
+    #[clap(long, env = "CHAIN", default_value = "mainnet")]
+    pub chain: Chain,
+}
+
+/// Command for building a fallback payload for the next slot against an engine API, without
+/// proposing it, to check that a sidecar engine setup can build valid payloads. The options
+/// are read from the same environment variables as the sidecar ones.
+#[derive(Debug, Clone, Parser)]
+pub struct BuildTestCommand {
+    /// Execution client API URL.
+    #[clap(long, env = "BOLT_SIDECAR_EXECUTION_API_URL", default_value = "http://localhost:8545")]
+    pub execution_api_url: Url,
+
+    /// Beacon client API URL.
+    #[clap(long, env = "BOLT_SIDECAR_BEACON_API_URL", default_value = "http://localhost:5052")]
+    pub beacon_api_url: Url,
+
+    /// Execution client Engine API URL.
+    #[clap(long, env = "BOLT_SIDECAR_ENGINE_API_URL", default_value = "http://localhost:8551")]
+    pub engine_api_url: Url,
+
+    /// The engine API JWT secret, either hex-encoded or the path to a file containing it.
+    #[clap(long, env = "BOLT_SIDECAR_ENGINE_JWT_HEX", hide_env_values = true)]
+    pub engine_jwt_hex: JwtSecretConfig,
+
+    /// The fee recipient of the payload.
+    #[clap(long, env = "BOLT_SIDECAR_FEE_RECIPIENT", default_value_t = Address::ZERO)]
+    pub fee_recipient: Address,
+
+    /// Comma-separated EIP-2718 encoded signed transactions to include in the payload.
+    /// If none are given, an empty payload is built.
+    #[clap(long = "tx", value_delimiter = ',')]
+    pub transactions: Vec<Bytes>,
+
+    /// The chain configuration of the sidecar.
+    #[clap(flatten)]
+    pub chain: ChainConfig,
+}
+
 /// Command for sending a preconfirmation request to a bolt proposer.
 #[derive(Debug, Clone, Parser)]
 pub struct SendCommand {
@@ -349,6 +476,25 @@ pub enum SecretsSource {
         #[clap(flatten)]
         opts: DirkOpts,
     },
+
+    /// Use the consensus keys of a commit-boost signer module to generate the signed messages.
+    CommitBoost {
+        /// The options for connecting to the commit-boost signer.
+        #[clap(flatten)]
+        opts: CommitBoostOpts,
+    },
+}
+
+/// Options for connecting to a commit-boost signer module.
+#[derive(Debug, Clone, Parser)]
+pub struct CommitBoostOpts {
+    /// The URL of the commit-boost signer.
+    #[clap(long, env = "CB_SIGNER_URL")]
+    pub signer_url: Url,
+
+    /// The JWT of the module to authenticate with the commit-boost signer.
+    #[clap(long, env = "CB_SIGNER_JWT", hide_env_values = true)]
+    pub jwt: String,
 }
 
 /// Options for reading a keystore folder.
@@ -0,0 +1,172 @@
+use alloy::primitives::B256;
+use bolt_sidecar::{
+    builder::{payload_builder::FallbackPayloadBuilder, InclusionKind, PayloadTransaction},
+    primitives::FullTransaction,
+};
+use eyre::{eyre, Context, Result};
+
+use crate::cli::BuildTestCommand;
+
+impl BuildTestCommand {
+    /// Run the `build-test` command.
+    pub async fn run(self) -> Result<()> {
+        let payload = self.build_payload().await?;
+
+        println!("Fallback payload built for slot {}", payload.slot);
+        println!("Block hash: {}", payload.block_hash);
+        println!("Gas used: {}", payload.gas_used);
+        println!("Transactions: {}", payload.transactions);
+        Ok(())
+    }
+
+    /// Build a fallback payload for the next slot with the given transactions, the same way
+    /// the sidecar does when its proposer has to self-build a block.
+    pub async fn build_payload(&self) -> Result<BuiltPayload> {
+        let transactions = self
+            .transactions
+            .iter()
+            .map(|raw| {
+                let tx = FullTransaction::decode_enveloped(raw)
+                    .wrap_err("Failed to decode transaction")?;
+                Ok(PayloadTransaction::new(tx, InclusionKind::Committed))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let builder = FallbackPayloadBuilder::connect(
+            self.chain,
+            self.execution_api_url.clone(),
+            self.beacon_api_url.clone(),
+            self.engine_api_url.clone(),
+            &self.engine_jwt_hex,
+            self.fee_recipient,
+        )
+        .await
+        .map_err(|err| eyre!("Failed to fetch the genesis time from the beacon node: {err}"))?;
+
+        let slot = builder.slot_clock().current_slot() + 1;
+        let block = builder
+            .build_fallback_payload(slot, None, transactions)
+            .await
+            .map_err(|err| eyre!("Failed to build a fallback payload: {err}"))?;
+
+        Ok(BuiltPayload {
+            slot,
+            block_hash: block.hash(),
+            gas_used: block.header.gas_used,
+            transactions: block.body.transactions.len(),
+        })
+    }
+}
+
+/// A fallback payload built by the `build-test` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuiltPayload {
+    /// The slot the payload was built for.
+    pub slot: u64,
+    /// The hash of the block, as validated by the engine.
+    pub block_hash: B256,
+    /// The gas used by the transactions of the block.
+    pub gas_used: u64,
+    /// The number of transactions in the block.
+    pub transactions: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use alloy::primitives::{Address, B256};
+    use alloy_node_bindings::Anvil;
+    use axum::{
+        extract::State,
+        routing::{get, post},
+        Json, Router,
+    };
+    use bolt_sidecar::{common::secrets::JwtSecretConfig, config::ChainConfig};
+    use reqwest::Url;
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
+
+    use crate::cli::BuildTestCommand;
+
+    async fn serve(router: Router) -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_build_test_payload() -> eyre::Result<()> {
+        type Validated = Arc<Mutex<Vec<B256>>>;
+
+        // The mock engine accepts any payload, and records the hashes of the blocks it validated
+        async fn new_payload(
+            State(validated): State<Validated>,
+            Json(body): Json<Value>,
+        ) -> Json<Value> {
+            let block_hash = body["params"][0]["blockHash"].as_str().unwrap().parse().unwrap();
+            validated.lock().unwrap().push(block_hash);
+
+            let result = json!({ "status": "VALID", "validationError": null });
+            Json(json!({ "jsonrpc": "2.0", "id": body["id"], "result": result }))
+        }
+
+        let validated = Validated::default();
+        let engine_url =
+            serve(Router::new().route("/", post(new_payload)).with_state(validated.clone())).await;
+
+        let beacon_url = serve(
+            Router::new()
+                .route(
+                    "/eth/v1/beacon/genesis",
+                    get(|| async {
+                        Json(json!({ "data": {
+                            "genesis_time": "0",
+                            "genesis_validators_root": B256::ZERO,
+                            "genesis_fork_version": "0x00000000",
+                        }}))
+                    }),
+                )
+                .route(
+                    "/eth/v1/beacon/states/head/randao",
+                    get(|| async {
+                        Json(json!({ "data": { "randao": B256::repeat_byte(0x33) } }))
+                    }),
+                )
+                .route(
+                    "/eth/v1/builder/states/head/expected_withdrawals",
+                    get(|| async { Json(json!({ "data": [] })) }),
+                )
+                .route(
+                    "/eth/v1/beacon/blocks/head/root",
+                    get(|| async { Json(json!({ "data": { "root": B256::repeat_byte(0x44) } })) }),
+                ),
+        )
+        .await;
+
+        let anvil = Anvil::new().spawn();
+        let command = BuildTestCommand {
+            execution_api_url: anvil.endpoint_url(),
+            beacon_api_url: beacon_url,
+            engine_api_url: engine_url,
+            engine_jwt_hex: JwtSecretConfig::default(),
+            fee_recipient: Address::ZERO,
+            transactions: Vec::new(),
+            chain: ChainConfig::default(),
+        };
+
+        let payload = command.build_payload().await?;
+        assert_eq!(payload.gas_used, 0);
+        assert_eq!(payload.transactions, 0);
+        assert_eq!(*validated.lock().unwrap(), vec![payload.block_hash]);
+
+        // Errors of the engine are surfaced
+        let command =
+            BuildTestCommand { engine_api_url: Url::parse("http://127.0.0.1:1")?, ..command };
+        let err = command.build_payload().await.unwrap_err();
+        assert!(err.to_string().starts_with("Failed to build a fallback payload"), "{err}");
+
+        Ok(())
+    }
+}
@@ -1,242 +1,275 @@
-use alloy::{
-    primitives::B256,
-    signers::k256::sha2::{Digest, Sha256},
-};
-use ethereum_consensus::crypto::{
-    PublicKey as BlsPublicKey, SecretKey as BlsSecretKey, Signature as BlsSignature,
+use std::{fs, path::Path};
+
+use alloy::signers::k256::sha2::{Digest, Sha256};
+use bolt_sidecar::primitives::{signed_messages_to_ssz_bytes, MessageEncoding};
+use ethereum_consensus::{
+    crypto::{PublicKey as BlsPublicKey, Signature as BlsSignature},
+    ssz::prelude::{ssz_rs, SimpleSerialize},
 };
-use eyre::{bail, Result};
-use lighthouse_eth2_keystore::Keystore;
-use serde::Serialize;
+use eyre::{bail, Context, Result};
+use reqwest::{header::CONTENT_TYPE, Client, Url};
+use serde::{Deserialize, Serialize};
 use tracing::{debug, warn};
 
 use crate::{
     cli::{Action, Chain, DelegateCommand, SecretsSource},
     common::{
-        dirk::Dirk,
-        keystore::{keystore_paths, KeystoreError, KeystoreSecret},
         parse_bls_public_key,
-        signing::{
-            compute_commit_boost_signing_root, compute_domain_from_mask, verify_commit_boost_root,
-        },
-        write_to_file,
+        signer::{Signer, SignerUri},
+        signing::verify_commit_boost_root,
     },
 };
 
 impl DelegateCommand {
     /// Run the `delegate` command.
     pub async fn run(self) -> Result<()> {
-        match self.source {
-            SecretsSource::SecretKeys { secret_keys } => {
-                let delegatee_pubkey = parse_bls_public_key(&self.delegatee_pubkey)?;
-                let signed_messages = generate_from_local_keys(
-                    &secret_keys,
-                    delegatee_pubkey,
-                    self.chain,
-                    self.action,
-                )?;
-                debug!("Signed {} messages with local keys", signed_messages.len());
-
-                // Verify signatures
-                for message in &signed_messages {
-                    verify_message_signature(message, self.chain)?;
-                }
+        let delegatee_pubkey = parse_bls_public_key(&self.delegatee_pubkey)?;
+        let keys = KeySource::from_args(self.signer, self.source)?;
 
-                write_to_file(&self.out, &signed_messages)?;
-                println!("Signed delegation messages generated and saved to {}", self.out);
-            }
-            SecretsSource::LocalKeystore { opts } => {
-                let keystore_secret = KeystoreSecret::from_keystore_options(&opts)?;
-                let delegatee_pubkey = parse_bls_public_key(&self.delegatee_pubkey)?;
-                let signed_messages = generate_from_keystore(
-                    &opts.path,
-                    keystore_secret,
-                    delegatee_pubkey,
-                    self.chain,
-                    self.action,
-                )?;
-                debug!("Signed {} messages with keystore", signed_messages.len());
-
-                // Verify signatures
-                for message in &signed_messages {
-                    verify_message_signature(message, self.chain)?;
-                }
-
-                write_to_file(&self.out, &signed_messages)?;
-                println!("Signed delegation messages generated and saved to {}", self.out);
-            }
-            SecretsSource::Dirk { opts } => {
-                let mut dirk = Dirk::connect(opts.url, opts.tls_credentials).await?;
-
-                let delegatee_pubkey = parse_bls_public_key(&self.delegatee_pubkey)?;
-                let signed_messages = generate_from_dirk(
-                    &mut dirk,
-                    delegatee_pubkey,
-                    opts.wallet_path,
-                    opts.passphrases,
-                    self.chain,
-                    self.action,
-                )
+        let mut batch =
+            generate_signed_messages(keys, delegatee_pubkey, self.chain, self.action.clone())
                 .await?;
-                debug!("Signed {} messages with Dirk", signed_messages.len());
 
-                // Verify signatures
-                for message in &signed_messages {
-                    verify_message_signature(message, self.chain)?;
-                }
+        // Verify signatures
+        batch.verify(self.chain);
 
-                write_to_file(&self.out, &signed_messages)?;
-                println!("Signed delegation messages generated and saved to {}", self.out);
+        if !batch.messages.is_empty() {
+            write_messages(&self.out, &batch.messages, self.format)?;
+            println!(
+                "{} signed delegation messages generated and saved to {}",
+                batch.messages.len(),
+                self.out
+            );
+
+            let relays = relay_targets(&self.relay_urls, &self.ssz_relay_urls);
+            if !relays.is_empty() {
+                post_to_relays(&relays, &batch.messages, self.action).await?;
             }
         }
 
-        Ok(())
+        batch.into_result()
     }
 }
 
-/// Generate signed delegations/revocations using local BLS private keys
-///
-/// - Use the provided private keys from either CLI or env variable
-/// - Create message
-/// - Compute the signing roots and sign the messages
-/// - Return the signed messages
-pub fn generate_from_local_keys(
-    secret_keys: &[String],
-    delegatee_pubkey: BlsPublicKey,
-    chain: Chain,
-    action: Action,
-) -> Result<Vec<SignedMessage>> {
-    let mut signed_messages = Vec::with_capacity(secret_keys.len());
-
-    for sk in secret_keys {
-        let sk = BlsSecretKey::try_from(sk.trim().to_string())?;
+/// The keys to sign messages with, given either by a key source subcommand or by a signer URI.
+#[derive(Debug, Clone)]
+pub enum KeySource {
+    /// A key source subcommand.
+    Secrets(SecretsSource),
+    /// A signing backend selected by the `--signer` URI.
+    Signer(SignerUri),
+}
 
-        match action {
-            Action::Delegate => {
-                let message = DelegationMessage::new(sk.public_key(), delegatee_pubkey.clone());
-                let signing_root = compute_commit_boost_signing_root(message.digest(), &chain)?;
-                let signature = sk.sign(signing_root.0.as_ref());
-                let signed = SignedDelegation { message, signature };
-                signed_messages.push(SignedMessage::Delegation(signed))
-            }
-            Action::Revoke => {
-                let message = RevocationMessage::new(sk.public_key(), delegatee_pubkey.clone());
-                let signing_root = compute_commit_boost_signing_root(message.digest(), &chain)?;
-                let signature = sk.sign(signing_root.0.as_ref());
-                let signed = SignedRevocation { message, signature };
-                signed_messages.push(SignedMessage::Revocation(signed));
-            }
+impl KeySource {
+    /// Select the key source from the command arguments. Exactly one of them must be set.
+    pub fn from_args(signer: Option<SignerUri>, source: Option<SecretsSource>) -> Result<Self> {
+        match (signer, source) {
+            (Some(signer), None) => Ok(Self::Signer(signer)),
+            (None, Some(source)) => Ok(Self::Secrets(source)),
+            (Some(_), Some(_)) => bail!("--signer can't be used with a key source subcommand"),
+            (None, None) => bail!("Either --signer or a key source subcommand is required"),
         }
     }
 
-    Ok(signed_messages)
+    /// Connect to the signing backend of the source, or load its keys for local sources.
+    pub async fn connect(self) -> Result<Box<dyn Signer>> {
+        match self {
+            Self::Secrets(source) => source.connect().await,
+            Self::Signer(uri) => uri.connect().await,
+        }
+    }
 }
 
-/// Generate signed delegations/revocations using a keystore file
-///
-/// - Read the keystore file
-/// - Decrypt the keypairs using the password
-/// - Create messages
-/// - Compute the signing roots and sign the message
-/// - Return the signed message
-pub fn generate_from_keystore(
-    keys_path: &str,
-    keystore_secret: KeystoreSecret,
-    delegatee_pubkey: BlsPublicKey,
-    chain: Chain,
-    action: Action,
-) -> Result<Vec<SignedMessage>> {
-    let keystores_paths = keystore_paths(keys_path)?;
-    let mut signed_messages = Vec::with_capacity(keystores_paths.len());
-    debug!("Found {} keys in the keystore", keystores_paths.len());
-
-    for path in keystores_paths {
-        let ks = Keystore::from_json_file(path).map_err(KeystoreError::Eth2Keystore)?;
-        let password = keystore_secret.get(ks.pubkey()).ok_or(KeystoreError::MissingPassword)?;
-        let kp = ks.decrypt_keypair(password.as_bytes()).map_err(KeystoreError::Eth2Keystore)?;
-        let validator_pubkey = BlsPublicKey::try_from(kp.pk.serialize().to_vec().as_ref())?;
-        let validator_private_key = kp.sk;
+/// The messages signed with many keys in one run. A key failing to sign doesn't abort the
+/// run: its error is collected along with the key, and reported once all keys are done.
+#[derive(Debug, Default)]
+pub struct SignedBatch {
+    /// The successfully signed messages.
+    pub messages: Vec<SignedMessage>,
+    /// The keys that failed to sign, identified by their public key or keystore path.
+    pub failures: Vec<(String, eyre::Report)>,
+}
 
-        match action {
-            Action::Delegate => {
-                let message = DelegationMessage::new(validator_pubkey, delegatee_pubkey.clone());
-                let signing_root = compute_commit_boost_signing_root(message.digest(), &chain)?;
-                let signature = validator_private_key.sign(signing_root.0.into());
-                let signature = BlsSignature::try_from(signature.serialize().as_ref())?;
-                let signed = SignedDelegation { message, signature };
-                signed_messages.push(SignedMessage::Delegation(signed));
-            }
-            Action::Revoke => {
-                let message = RevocationMessage::new(validator_pubkey, delegatee_pubkey.clone());
-                let signing_root = compute_commit_boost_signing_root(message.digest(), &chain)?;
-                let signature = validator_private_key.sign(signing_root.0.into());
-                let signature = BlsSignature::try_from(signature.serialize().as_ref())?;
-                let signed = SignedRevocation { message, signature };
-                signed_messages.push(SignedMessage::Revocation(signed));
+impl SignedBatch {
+    /// Record the outcome of signing with the given key.
+    pub fn record(&mut self, key: impl ToString, result: Result<SignedMessage>) {
+        match result {
+            Ok(message) => self.messages.push(message),
+            Err(err) => {
+                let key = key.to_string();
+                warn!("Failed to sign with key {}: {:?}", key, err);
+                self.failures.push((key, err));
             }
         }
     }
 
-    Ok(signed_messages)
+    /// Verify the signatures of the messages, moving the invalid ones to the failures.
+    pub fn verify(&mut self, chain: Chain) {
+        for message in std::mem::take(&mut self.messages) {
+            let key = format!("{:?}", message.validator_pubkey());
+            let result = verify_message_signature(&message, chain).map(|_| message);
+            self.record(key, result);
+        }
+    }
+
+    /// Returns an error listing the failed keys, if any.
+    pub fn into_result(self) -> Result<()> {
+        if self.failures.is_empty() {
+            return Ok(());
+        }
+
+        let keys = self.failures.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>();
+        let total = self.failures.len() + self.messages.len();
+        bail!("Failed to sign with {}/{} keys: {}", self.failures.len(), total, keys.join(", "))
+    }
+}
+
+/// Generate signed delegations/revocations for `delegatee_pubkey` with all the keys of the
+/// given source.
+pub async fn generate_signed_messages(
+    keys: KeySource,
+    delegatee_pubkey: BlsPublicKey,
+    chain: Chain,
+    action: Action,
+) -> Result<SignedBatch> {
+    let mut signer = keys.connect().await?;
+    let batch = generate_with_signer(signer.as_mut(), delegatee_pubkey, chain, action).await?;
+    debug!("Signed {} messages with {}", batch.messages.len(), signer.backend());
+    Ok(batch)
 }
 
-/// Generate signed delegations/revocations using a remote Dirk signer
-pub async fn generate_from_dirk(
-    dirk: &mut Dirk,
+/// Generate signed delegations/revocations with all the keys of a signing backend
+///
+/// - List the public keys of the backend
+/// - Create the messages
+/// - Sign their digests with the Commit Boost domain of the chain
+/// - Return the signed messages, and the keys that failed to sign
+pub async fn generate_with_signer(
+    signer: &mut dyn Signer,
     delegatee_pubkey: BlsPublicKey,
-    account_path: String,
-    passphrases: Option<Vec<String>>,
     chain: Chain,
     action: Action,
-) -> Result<Vec<SignedMessage>> {
-    // first read the accounts from the remote keystore
-    let accounts = dirk.list_accounts(account_path).await?;
-    debug!("Found {} remote accounts to sign with", accounts.len());
+) -> Result<SignedBatch> {
+    let pubkeys = signer.pubkeys().await?;
+    let mut batch = SignedBatch::default();
+
+    for pubkey in pubkeys {
+        let digest = SignedMessage::digest_of(&action, &pubkey, &delegatee_pubkey);
+        let result = signer.sign_commit_boost_root(&pubkey, digest, chain).await.map(|signature| {
+            SignedMessage::new(&action, pubkey.clone(), delegatee_pubkey.clone(), signature)
+        });
+        batch.record(format!("{:?}", pubkey), result);
+    }
+
+    Ok(batch)
+}
+
+/// The path to the Constraints API delegate endpoint of the relays.
+const DELEGATE_PATH: &str = "/constraints/v1/builder/delegate";
 
-    let mut signed_messages = Vec::with_capacity(accounts.len());
+/// The path to the Constraints API revoke endpoint of the relays.
+const REVOKE_PATH: &str = "/constraints/v1/builder/revoke";
 
-    // specify the signing domain (needs to be included in the signing request)
-    let domain = B256::from(compute_domain_from_mask(chain.fork_version()));
+/// Returns the URL of the Constraints API endpoint of the relay for the given action.
+pub fn constraints_api_url(relay_url: &Url, action: &Action) -> Result<Url> {
+    let path = match action {
+        Action::Delegate => DELEGATE_PATH,
+        Action::Revoke => REVOKE_PATH,
+    };
 
-    for account in accounts {
-        // for each available pubkey we control, sign a delegation message
-        let pubkey = BlsPublicKey::try_from(account.public_key.as_slice())?;
+    Ok(relay_url.join(path)?)
+}
 
-        // Note: before signing, we must unlock the account
-        if let Some(ref passphrases) = passphrases {
-            for passphrase in passphrases {
-                if dirk.unlock_account(account.name.clone(), passphrase.clone()).await? {
-                    break;
+/// Encode the signed messages as a JSON array or an SSZ list, with the same SSZ encoding as
+/// the sidecar.
+///
+/// An SSZ list is either of delegations or of revocations, so the messages can't be mixed.
+pub fn encode_messages(
+    signed_messages: &[SignedMessage],
+    encoding: MessageEncoding,
+) -> Result<Vec<u8>> {
+    match encoding {
+        MessageEncoding::Json => Ok(serde_json::to_vec_pretty(signed_messages)?),
+        MessageEncoding::Ssz => {
+            let mut delegations = Vec::new();
+            let mut revocations = Vec::new();
+            for signed_message in signed_messages {
+                match signed_message {
+                    SignedMessage::Delegation(signed) => delegations.push(signed.clone()),
+                    SignedMessage::Revocation(signed) => revocations.push(signed.clone()),
                 }
             }
-        } else {
-            bail!("A passphrase is required in order to sign messages remotely with Dirk");
-        }
 
-        match action {
-            Action::Delegate => {
-                let message = DelegationMessage::new(pubkey.clone(), delegatee_pubkey.clone());
-                let signing_root = message.digest().into(); // Dirk does the hash tree root internally
-                let signature = dirk.request_signature(&account, signing_root, domain).await?;
-                let signed = SignedDelegation { message, signature };
-                signed_messages.push(SignedMessage::Delegation(signed));
+            if !delegations.is_empty() && !revocations.is_empty() {
+                bail!("cannot encode delegations and revocations in the same SSZ list");
             }
-            Action::Revoke => {
-                let message = RevocationMessage::new(pubkey.clone(), delegatee_pubkey.clone());
-                let signing_root = message.digest().into(); // Dirk does the hash tree root internally
-                let signature = dirk.request_signature(&account, signing_root, domain).await?;
-                let signed = SignedRevocation { message, signature };
-                signed_messages.push(SignedMessage::Revocation(signed));
+
+            let mut bytes = signed_messages_to_ssz_bytes(&delegations)?;
+            bytes.extend(signed_messages_to_ssz_bytes(&revocations)?);
+            Ok(bytes)
+        }
+    }
+}
+
+/// Save the signed messages to the given file in the given encoding.
+pub fn write_messages(
+    out: &str,
+    signed_messages: &[SignedMessage],
+    encoding: MessageEncoding,
+) -> Result<()> {
+    fs::write(out, encode_messages(signed_messages, encoding)?)?;
+    Ok(())
+}
+
+/// Pair the URLs of the relays with the encoding of the messages they expect.
+pub fn relay_targets(json_urls: &[Url], ssz_urls: &[Url]) -> Vec<(Url, MessageEncoding)> {
+    let json = json_urls.iter().map(|url| (url.clone(), MessageEncoding::Json));
+    let ssz = ssz_urls.iter().map(|url| (url.clone(), MessageEncoding::Ssz));
+    json.chain(ssz).collect()
+}
+
+/// Post the signed messages to the Constraints API of each relay, in the encoding it expects.
+/// All relays are tried, and an error is returned if any of them rejected the messages.
+pub async fn post_to_relays(
+    relays: &[(Url, MessageEncoding)],
+    signed_messages: &[SignedMessage],
+    action: Action,
+) -> Result<()> {
+    let client = Client::new();
+    let mut failures = 0;
+
+    for (relay_url, encoding) in relays {
+        let result = async {
+            let url = constraints_api_url(relay_url, &action)?;
+            let request = match encoding {
+                MessageEncoding::Json => client.post(url).json(signed_messages),
+                MessageEncoding::Ssz => client
+                    .post(url)
+                    .header(CONTENT_TYPE, "application/octet-stream")
+                    .body(encode_messages(signed_messages, MessageEncoding::Ssz)?),
+            };
+            let response = request.send().await?;
+            if !response.status().is_success() {
+                bail!("status {}: {}", response.status(), response.text().await?);
             }
+            Ok::<_, eyre::Report>(())
         }
+        .await;
 
-        // Try to lock the account back after signing
-        if let Err(err) = dirk.lock_account(account.name.clone()).await {
-            warn!("Failed to lock account after signing {}: {:?}", account.name, err);
+        match result {
+            Ok(()) => println!("Signed messages posted to {}", relay_url),
+            Err(err) => {
+                warn!("Failed to post signed messages to {}: {:?}", relay_url, err);
+                failures += 1;
+            }
         }
     }
 
-    Ok(signed_messages)
+    if failures > 0 {
+        bail!("Failed to post signed messages to {}/{} relays", failures, relays.len());
+    }
+
+    Ok(())
 }
 
 /// Event types that can be emitted by the validator pubkey to
@@ -271,13 +304,57 @@ pub enum SignedMessage {
     Revocation(SignedRevocation),
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+impl SignedMessage {
+    /// Returns the digest to sign for the message of the action from `validator_pubkey` to
+    /// `delegatee_pubkey`.
+    pub fn digest_of(
+        action: &Action,
+        validator_pubkey: &BlsPublicKey,
+        delegatee_pubkey: &BlsPublicKey,
+    ) -> [u8; 32] {
+        let (validator_pubkey, delegatee_pubkey) =
+            (validator_pubkey.clone(), delegatee_pubkey.clone());
+        match action {
+            Action::Delegate => DelegationMessage::new(validator_pubkey, delegatee_pubkey).digest(),
+            Action::Revoke => RevocationMessage::new(validator_pubkey, delegatee_pubkey).digest(),
+        }
+    }
+
+    /// Create the signed message of the action from the signature of its digest.
+    pub fn new(
+        action: &Action,
+        validator_pubkey: BlsPublicKey,
+        delegatee_pubkey: BlsPublicKey,
+        signature: BlsSignature,
+    ) -> Self {
+        match action {
+            Action::Delegate => {
+                let message = DelegationMessage::new(validator_pubkey, delegatee_pubkey);
+                Self::Delegation(SignedDelegation { message, signature })
+            }
+            Action::Revoke => {
+                let message = RevocationMessage::new(validator_pubkey, delegatee_pubkey);
+                Self::Revocation(SignedRevocation { message, signature })
+            }
+        }
+    }
+
+    /// Returns the validator public key that signed the message.
+    pub fn validator_pubkey(&self) -> &BlsPublicKey {
+        match self {
+            Self::Delegation(signed) => &signed.message.validator_pubkey,
+            Self::Revocation(signed) => &signed.message.validator_pubkey,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq, SimpleSerialize)]
 pub struct SignedDelegation {
     pub message: DelegationMessage,
     pub signature: BlsSignature,
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq, SimpleSerialize)]
 pub struct DelegationMessage {
     action: u8,
     pub validator_pubkey: BlsPublicKey,
@@ -301,13 +378,13 @@ impl DelegationMessage {
     }
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq, SimpleSerialize)]
 pub struct SignedRevocation {
     pub message: RevocationMessage,
     pub signature: BlsSignature,
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq, SimpleSerialize)]
 pub struct RevocationMessage {
     action: u8,
     pub validator_pubkey: BlsPublicKey,
@@ -331,6 +408,20 @@ impl RevocationMessage {
     }
 }
 
+/// Read the signed delegations saved by the `delegate` command in the given JSON file.
+pub fn read_delegations(path: &Path) -> Result<Vec<SignedDelegation>> {
+    let file = fs::File::open(path)
+        .wrap_err_with(|| format!("Failed to open delegations file {}", path.display()))?;
+    let delegations: Vec<SignedDelegation> = serde_json::from_reader(file)?;
+
+    // Revocations have the same format, only the action tells them apart
+    if delegations.iter().any(|d| d.message.action != SignedMessageAction::Delegation as u8) {
+        bail!("{} contains messages other than delegations", path.display());
+    }
+
+    Ok(delegations)
+}
+
 /// Verify the signature of a signed message
 pub fn verify_message_signature(message: &SignedMessage, chain: Chain) -> Result<()> {
     match message {
@@ -361,15 +452,84 @@ pub fn verify_message_signature(message: &SignedMessage, chain: Chain) -> Result
 
 #[cfg(test)]
 mod tests {
+    use bolt_sidecar::primitives::MessageEncoding;
+    use ethereum_consensus::crypto::SecretKey as BlsSecretKey;
+
     use crate::{
         cli::{Action, Chain},
-        common::{dirk, keystore, parse_bls_public_key},
+        common::{
+            dirk, keystore, parse_bls_public_key,
+            signer::{DirkSigner, KeystoreSigner, SecretKeysSigner},
+        },
     };
 
-    use super::{generate_from_dirk, generate_from_keystore, verify_message_signature};
+    use super::{
+        encode_messages, generate_with_signer, read_delegations, verify_message_signature,
+        RevocationMessage, SignedDelegation, SignedMessage, SignedRevocation,
+    };
 
     #[test]
-    fn test_delegation_keystore_signer_lighthouse() -> eyre::Result<()> {
+    fn test_delegation_encodings() -> eyre::Result<()> {
+        let test_data = env!("CARGO_MANIFEST_DIR").to_string() + "/test_data";
+        let delegations = read_delegations(format!("{test_data}/delegations.json").as_ref())?;
+        let messages =
+            delegations.iter().cloned().map(SignedMessage::Delegation).collect::<Vec<_>>();
+
+        // The JSON form round-trips
+        let json = encode_messages(&messages, MessageEncoding::Json)?;
+        assert_eq!(serde_json::from_slice::<Vec<SignedDelegation>>(&json)?, delegations);
+
+        // Delegations and revocations can't be mixed in an SSZ list
+        let revocation = SignedRevocation {
+            message: RevocationMessage::new(
+                delegations[0].message.validator_pubkey.clone(),
+                delegations[0].message.delegatee_pubkey.clone(),
+            ),
+            signature: delegations[0].signature.clone(),
+        };
+        let mixed = [messages[0].clone(), SignedMessage::Revocation(revocation)];
+        assert!(encode_messages(&mixed, MessageEncoding::Ssz).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delegation_local_keys_verifies_under_validator_key() -> eyre::Result<()> {
+        let secret_key = format!("0x{}", "11".repeat(32));
+        let validator_pubkey = BlsSecretKey::try_from(secret_key.clone())?.public_key();
+
+        let delegatee_pubkey = "0x83eeddfac5e60f8fe607ee8713efb8877c295ad9f8ca075f4d8f6f2ae241a30dd57f78f6f3863a9fe0d5b5db9d550b93";
+        let delegatee_pubkey = parse_bls_public_key(delegatee_pubkey)?;
+        let chain = Chain::Holesky;
+
+        let mut signer = SecretKeysSigner::from_secret_keys(&[secret_key])?;
+        let batch =
+            generate_with_signer(&mut signer, delegatee_pubkey.clone(), chain, Action::Delegate)
+                .await?;
+        assert!(batch.failures.is_empty());
+        let signed_messages = batch.messages;
+        assert_eq!(signed_messages.len(), 1);
+
+        let SignedMessage::Delegation(signed_delegation) = &signed_messages[0] else {
+            panic!("expected a delegation message");
+        };
+        assert_eq!(signed_delegation.message.validator_pubkey, validator_pubkey);
+        assert_eq!(signed_delegation.message.delegatee_pubkey, delegatee_pubkey);
+        verify_message_signature(&signed_messages[0], chain)?;
+
+        // The signature doesn't verify on another chain, nor under another validator key
+        assert!(verify_message_signature(&signed_messages[0], Chain::Mainnet).is_err());
+
+        let mut forged = signed_delegation.clone();
+        forged.message.validator_pubkey =
+            BlsSecretKey::try_from(format!("0x{}", "22".repeat(32)))?.public_key();
+        assert!(verify_message_signature(&SignedMessage::Delegation(forged), chain).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delegation_keystore_signer_lighthouse() -> eyre::Result<()> {
         // Read the keystore from test_data
         let keys_path = env!("CARGO_MANIFEST_DIR").to_string() + "/test_data/lighthouse/validators";
         let secrets_path = env!("CARGO_MANIFEST_DIR").to_string() + "/test_data/lighthouse/secrets";
@@ -380,21 +540,59 @@ mod tests {
         let delegatee_pubkey = parse_bls_public_key(delegatee_pubkey)?;
         let chain = Chain::Mainnet;
 
-        let signed_delegations = generate_from_keystore(
-            &keys_path,
-            keystore_secret,
-            delegatee_pubkey,
-            chain,
-            Action::Delegate,
-        )?;
+        let mut signer = KeystoreSigner::from_directory(&keys_path, keystore_secret)?;
+        let signed_delegations =
+            generate_with_signer(&mut signer, delegatee_pubkey, chain, Action::Delegate).await?;
 
-        let signed_message = signed_delegations.first().expect("to get signed delegation");
+        let signed_message = signed_delegations.messages.first().expect("to get signed delegation");
 
         verify_message_signature(signed_message, chain)?;
 
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_batch_delegation_keystore() -> eyre::Result<()> {
+        let keys_path = env!("CARGO_MANIFEST_DIR").to_string() + "/test_data/lighthouse/validators";
+        let secrets_path = env!("CARGO_MANIFEST_DIR").to_string() + "/test_data/lighthouse/secrets";
+
+        let delegatee_pubkey = "0x83eeddfac5e60f8fe607ee8713efb8877c295ad9f8ca075f4d8f6f2ae241a30dd57f78f6f3863a9fe0d5b5db9d550b93";
+        let delegatee_pubkey = parse_bls_public_key(delegatee_pubkey)?;
+        let chain = Chain::Holesky;
+
+        // Both keys of the keystore are delegated in one run
+        let keystore_secret = keystore::KeystoreSecret::from_directory(&secrets_path)?;
+        let mut signer = KeystoreSigner::from_directory(&keys_path, keystore_secret)?;
+        let mut batch =
+            generate_with_signer(&mut signer, delegatee_pubkey.clone(), chain, Action::Delegate)
+                .await?;
+        batch.verify(chain);
+
+        assert_eq!(batch.messages.len(), 2);
+        assert!(batch.failures.is_empty());
+        assert_ne!(batch.messages[0].validator_pubkey(), batch.messages[1].validator_pubkey());
+
+        // A key without password fails alone, without aborting the run
+        let keystore::KeystoreSecret::Directory(secrets) =
+            keystore::KeystoreSecret::from_directory(&secrets_path)?
+        else {
+            panic!("expected a secrets directory");
+        };
+        let (pubkey, password) = secrets.iter().next().expect("secrets in test data");
+        let keystore_secret = keystore::KeystoreSecret::Directory(
+            [(pubkey.clone(), password.clone())].into_iter().collect(),
+        );
+        let mut signer = KeystoreSigner::from_directory(&keys_path, keystore_secret)?;
+        let batch =
+            generate_with_signer(&mut signer, delegatee_pubkey, chain, Action::Delegate).await?;
+
+        assert_eq!(batch.messages.len(), 1);
+        assert_eq!(batch.failures.len(), 1);
+        assert!(batch.into_result().is_err());
+
+        Ok(())
+    }
+
     /// Test generating signed delegations using a remote Dirk signer.
     ///
     /// ```shell
@@ -405,23 +603,18 @@ mod tests {
     #[ignore = "Requires Dirk to be installed on the system"]
     async fn test_delegation_dirk() -> eyre::Result<()> {
         let _ = tracing_subscriber::fmt::try_init();
-        let (mut dirk, mut dirk_proc) = dirk::test_util::start_dirk_test_server().await?;
+        let (dirk, mut dirk_proc) = dirk::test_util::start_dirk_test_server().await?;
 
         let delegatee_pubkey = "0x83eeddfac5e60f8fe607ee8713efb8877c295ad9f8ca075f4d8f6f2ae241a30dd57f78f6f3863a9fe0d5b5db9d550b93";
         let delegatee_pubkey = parse_bls_public_key(delegatee_pubkey)?;
         let chain = Chain::Mainnet;
 
-        let signed_delegations = generate_from_dirk(
-            &mut dirk,
-            delegatee_pubkey.clone(),
-            "wallet1".to_string(),
-            Some(vec!["secret".to_string()]),
-            chain,
-            Action::Delegate,
-        )
-        .await?;
-
-        let signed_message = signed_delegations.first().expect("to get signed delegation");
+        let passphrases = Some(vec!["secret".to_string()]);
+        let mut signer = DirkSigner::new(dirk, "wallet1".to_string(), passphrases)?;
+        let signed_delegations =
+            generate_with_signer(&mut signer, delegatee_pubkey, chain, Action::Delegate).await?;
+
+        let signed_message = signed_delegations.messages.first().expect("to get signed delegation");
 
         verify_message_signature(signed_message, chain)?;
 
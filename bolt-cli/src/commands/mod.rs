@@ -2,6 +2,10 @@
 /// signed delegation and revocation messages.
 pub mod delegate;
 
+/// Module for the bolt `revoke` command to revoke
+/// previously issued delegations.
+pub mod revoke;
+
 /// Module for the bolt `pubkeys` command to generate
 /// lists of public keys from different key sources.
 pub mod pubkeys;
@@ -15,3 +19,11 @@ pub mod validators;
 
 /// Module for the operators-related commands to interact with the bolt network.
 pub mod operators;
+
+/// Module for the bolt `signing-root` command to compute the signing
+/// root of constraints messages.
+pub mod signing_root;
+
+/// Module for the bolt `build-test` command to check that an engine
+/// setup can build fallback payloads.
+pub mod build_test;
@@ -7,7 +7,7 @@ use crate::{
     common::{
         dirk::Dirk,
         keystore::{keystore_paths, KeystoreError},
-        write_to_file,
+        parse_unique_bls_public_keys, write_to_file,
     },
     pb::eth2_signer_api::Account,
 };
@@ -16,6 +16,9 @@ impl PubkeysCommand {
     pub async fn run(self) -> Result<()> {
         match self.source {
             KeysSource::PublicKeys { public_keys } => {
+                let public_keys = public_keys.iter().map(String::as_str).collect::<Vec<_>>();
+                let public_keys = parse_unique_bls_public_keys(&public_keys)?;
+
                 write_to_file(&self.out, &public_keys)?;
                 println!("Pubkeys saved to {}", self.out);
             }
@@ -0,0 +1,155 @@
+use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
+use eyre::{bail, Result};
+use tracing::{debug, warn};
+
+use crate::{
+    cli::{Action, Chain, RevokeCommand},
+    commands::delegate::{
+        generate_signed_messages, post_to_relays, read_delegations, relay_targets,
+        verify_message_signature, write_messages, KeySource, SignedDelegation, SignedMessage,
+    },
+};
+
+impl RevokeCommand {
+    /// Run the `revoke` command.
+    pub async fn run(self) -> Result<()> {
+        let delegations = read_delegations(&self.delegations_path)?;
+        debug!("Read {} delegations to revoke", delegations.len());
+
+        let keys = KeySource::from_args(self.signer, self.source)?;
+        let revocations = generate_revocations(&delegations, keys, self.chain).await?;
+
+        write_messages(&self.out, &revocations, self.format)?;
+        println!("Signed revocation messages generated and saved to {}", self.out);
+
+        let relays = relay_targets(&self.relay_urls, &self.ssz_relay_urls);
+        if !relays.is_empty() {
+            post_to_relays(&relays, &revocations, Action::Revoke).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Generate the signed revocations of the given delegations with the keys of the source that
+/// issued them. Each revocation references the validator and delegatee keys of a delegation.
+///
+/// Delegations issued by keys that are not available in the source are skipped with a warning.
+pub async fn generate_revocations(
+    delegations: &[SignedDelegation],
+    keys: KeySource,
+    chain: Chain,
+) -> Result<Vec<SignedMessage>> {
+    let mut delegatees: Vec<BlsPublicKey> = Vec::new();
+    for delegation in delegations {
+        // Only revoke delegations that are valid on the chain
+        verify_message_signature(&SignedMessage::Delegation(delegation.clone()), chain)?;
+
+        if !delegatees.contains(&delegation.message.delegatee_pubkey) {
+            delegatees.push(delegation.message.delegatee_pubkey.clone());
+        }
+    }
+
+    let is_revoked = |revocation: &SignedMessage, delegation: &SignedDelegation| {
+        matches!(revocation, SignedMessage::Revocation(r)
+            if r.message.validator_pubkey == delegation.message.validator_pubkey &&
+                r.message.delegatee_pubkey == delegation.message.delegatee_pubkey)
+    };
+
+    let mut revocations = Vec::with_capacity(delegations.len());
+    for delegatee_pubkey in delegatees {
+        let batch =
+            generate_signed_messages(keys.clone(), delegatee_pubkey, chain, Action::Revoke).await?;
+
+        // The source may hold keys that never delegated to this delegatee. The keys that
+        // failed to sign are reported below if they did.
+        revocations.extend(
+            batch.messages.into_iter().filter(|m| delegations.iter().any(|d| is_revoked(m, d))),
+        );
+    }
+
+    for delegation in delegations {
+        if !revocations.iter().any(|r| is_revoked(r, delegation)) {
+            warn!(
+                "No key found to revoke the delegation of {:?} to {:?}",
+                delegation.message.validator_pubkey, delegation.message.delegatee_pubkey
+            );
+        }
+    }
+
+    if revocations.is_empty() {
+        bail!("None of the delegations can be revoked with the given keys");
+    }
+
+    for revocation in &revocations {
+        verify_message_signature(revocation, chain)?;
+    }
+
+    Ok(revocations)
+}
+
+#[cfg(test)]
+mod tests {
+    use ethereum_consensus::crypto::SecretKey as BlsSecretKey;
+    use reqwest::Url;
+
+    use crate::{
+        cli::{Action, Chain, SecretsSource},
+        commands::delegate::{
+            constraints_api_url, generate_with_signer, read_delegations, verify_message_signature,
+            KeySource, SignedMessage,
+        },
+        common::{parse_bls_public_key, signer::SecretKeysSigner, write_to_file},
+    };
+
+    use super::generate_revocations;
+
+    #[tokio::test]
+    async fn test_revoke_delegation() -> eyre::Result<()> {
+        let secret_keys = vec![format!("0x{}", "11".repeat(32)), format!("0x{}", "22".repeat(32))];
+        let validator_pubkey = BlsSecretKey::try_from(secret_keys[0].clone())?.public_key();
+
+        let delegatee_pubkey = "0x83eeddfac5e60f8fe607ee8713efb8877c295ad9f8ca075f4d8f6f2ae241a30dd57f78f6f3863a9fe0d5b5db9d550b93";
+        let delegatee_pubkey = parse_bls_public_key(delegatee_pubkey)?;
+        let chain = Chain::Holesky;
+
+        // Both validators delegated to the sidecar key
+        let mut signer = SecretKeysSigner::from_secret_keys(&secret_keys)?;
+        let delegations =
+            generate_with_signer(&mut signer, delegatee_pubkey.clone(), chain, Action::Delegate)
+                .await?
+                .messages;
+        let dir = tempfile::tempdir()?;
+        let delegations_path = dir.path().join("delegations.json");
+        write_to_file(delegations_path.to_str().expect("utf-8 path"), &delegations)?;
+        let delegations = read_delegations(&delegations_path)?;
+
+        // Only the first key is available to revoke its delegation
+        let keys = KeySource::Secrets(SecretsSource::SecretKeys {
+            secret_keys: vec![secret_keys[0].clone()],
+        });
+        let revocations = generate_revocations(&delegations, keys, chain).await?;
+        assert_eq!(revocations.len(), 1);
+
+        let SignedMessage::Revocation(revocation) = &revocations[0] else {
+            panic!("expected a revocation message");
+        };
+        assert_eq!(revocation.message.validator_pubkey, validator_pubkey);
+        assert_eq!(revocation.message.delegatee_pubkey, delegatee_pubkey);
+        verify_message_signature(&revocations[0], chain)?;
+
+        // Revocations can't be mistaken for delegations
+        let revocations_path = dir.path().join("revocations.json");
+        write_to_file(revocations_path.to_str().expect("utf-8 path"), &revocations)?;
+        assert!(read_delegations(&revocations_path).is_err());
+
+        // Revocations are posted to the revoke endpoint of the relays
+        let relay_url = Url::parse("https://relay.example.com")?;
+        assert_eq!(
+            constraints_api_url(&relay_url, &Action::Revoke)?.as_str(),
+            "https://relay.example.com/constraints/v1/builder/revoke"
+        );
+
+        Ok(())
+    }
+}
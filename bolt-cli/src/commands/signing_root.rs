@@ -0,0 +1,87 @@
+use alloy::{
+    primitives::{keccak256, Bytes, B256},
+    signers::k256::sha2::{Digest, Sha256},
+};
+use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
+use eyre::{Context, Result};
+use serde::Deserialize;
+
+use crate::{
+    cli::{Chain, SigningRootCommand},
+    common::signing::compute_commit_boost_signing_root,
+};
+
+impl SigningRootCommand {
+    /// Run the `signing-root` command.
+    pub async fn run(self) -> Result<()> {
+        let contents = std::fs::read_to_string(&self.message_path)
+            .wrap_err("Failed to read constraints message file")?;
+        let message: ConstraintsMessage =
+            serde_json::from_str(&contents).wrap_err("Failed to parse constraints message")?;
+
+        println!("{}", message.signing_root(&self.chain)?);
+        Ok(())
+    }
+}
+
+/// A constraints message, as signed by the bolt sidecar.
+///
+/// Reference: https://chainbound.github.io/bolt-docs/api/builder#constraints
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConstraintsMessage {
+    pub pubkey: BlsPublicKey,
+    pub slot: u64,
+    pub top: bool,
+    /// The EIP-2718 encoded signed transactions.
+    pub transactions: Vec<Bytes>,
+}
+
+impl ConstraintsMessage {
+    /// Compute the digest of the constraints message.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.pubkey.to_vec());
+        hasher.update(self.slot.to_le_bytes());
+        hasher.update((self.top as u8).to_le_bytes());
+
+        for tx in &self.transactions {
+            hasher.update(keccak256(tx));
+        }
+
+        hasher.finalize().into()
+    }
+
+    /// Compute the signing root of the constraints message with the Commit Boost domain.
+    pub fn signing_root(&self, chain: &Chain) -> Result<B256> {
+        compute_commit_boost_signing_root(self.digest(), chain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::b256;
+
+    use crate::cli::Chain;
+
+    use super::ConstraintsMessage;
+
+    #[test]
+    fn test_constraints_signing_root() -> eyre::Result<()> {
+        // Fixed vector, shared with the sidecar `ConstraintsMessage::signing_root` tests
+        let message: ConstraintsMessage = serde_json::from_str(
+            r#"{
+                "pubkey": "0x83eeddfac5e60f8fe607ee8713efb8877c295ad9f8ca075f4d8f6f2ae241a30dd57f78f6f3863a9fe0d5b5db9d550b93",
+                "slot": 42,
+                "top": false,
+                "transactions": ["0x02f86c870c72dd9d5e883e4d0183408f2382520894d2e2adf7177b7a8afddbc12d1634cf23ea1a71020180c001a08556dcfea479b34675db3fe08e29486fe719c2b22f6b0c1741ecbbdce4575cc6a01cd48009ccafd6b9f1290bbe2ceea268f94101d1d322c787018423ebcbc87ab4"]
+            }"#,
+        )?;
+
+        assert_eq!(
+            message.signing_root(&Chain::Mainnet)?,
+            b256!("6eff58a4b1052a84feccdbe6efa11a48df1e57b5e7f9e754c0d32b67b9e39c2f")
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+use alloy::primitives::B256;
+use ethereum_consensus::crypto::bls::{PublicKey as BlsPublicKey, Signature as BlsSignature};
+use eyre::{bail, Context, Result};
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// The path to the endpoint listing the keys of the commit-boost signer.
+const GET_PUBKEYS_PATH: &str = "/signer/v1/get_pubkeys";
+
+/// The path to the endpoint requesting a signature from the commit-boost signer.
+const REQUEST_SIGNATURE_PATH: &str = "/signer/v1/request_signature";
+
+/// A commit-boost signer module, reached through its HTTP API.
+///
+/// Only the consensus keys are used: the signer computes the signing root of the object
+/// root with the Commit Boost domain before signing it.
+///
+/// Reference: https://commit-boost.github.io/commit-boost-client/api
+#[derive(Debug, Clone)]
+pub struct CommitBoostSigner {
+    client: Client,
+    url: Url,
+    jwt: String,
+}
+
+/// The keys of the commit-boost signer, as returned by the `get_pubkeys` endpoint.
+#[derive(Debug, Deserialize)]
+struct GetPubkeysResponse {
+    keys: Vec<ConsensusProxyMap>,
+}
+
+/// A consensus key of the commit-boost signer. Its proxy keys are not used.
+#[derive(Debug, Deserialize)]
+struct ConsensusProxyMap {
+    consensus: BlsPublicKey,
+}
+
+/// A request for a signature of a consensus key.
+#[derive(Debug, Serialize)]
+struct SignConsensusRequest<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    pubkey: &'a BlsPublicKey,
+    object_root: B256,
+}
+
+impl CommitBoostSigner {
+    /// Create a new signer client for the module at the given URL, authenticated with the
+    /// JWT of the module.
+    pub fn new(url: Url, jwt: String) -> Self {
+        Self { client: Client::new(), url, jwt }
+    }
+
+    /// List the consensus public keys of the signer.
+    pub async fn list_pubkeys(&self) -> Result<Vec<BlsPublicKey>> {
+        let response = self
+            .client
+            .get(self.url.join(GET_PUBKEYS_PATH)?)
+            .bearer_auth(&self.jwt)
+            .send()
+            .await
+            .wrap_err("Failed to list commit-boost signer keys")?;
+
+        if !response.status().is_success() {
+            bail!("Failed to list commit-boost signer keys: {}", response.text().await?);
+        }
+
+        let keys = response.json::<GetPubkeysResponse>().await?.keys;
+        debug!("{} consensus keys listed successfully", keys.len());
+        Ok(keys.into_iter().map(|k| k.consensus).collect())
+    }
+
+    /// Request a signature of the given object root with the given consensus key.
+    pub async fn request_signature(
+        &self,
+        pubkey: &BlsPublicKey,
+        object_root: B256,
+    ) -> Result<BlsSignature> {
+        let request = SignConsensusRequest { kind: "consensus", pubkey, object_root };
+        let response = self
+            .client
+            .post(self.url.join(REQUEST_SIGNATURE_PATH)?)
+            .bearer_auth(&self.jwt)
+            .json(&request)
+            .send()
+            .await
+            .wrap_err("Failed to request a commit-boost signature")?;
+
+        if !response.status().is_success() {
+            bail!("Failed to sign with commit-boost key {:?}: {}", pubkey, response.text().await?);
+        }
+
+        Ok(response.json::<BlsSignature>().await?)
+    }
+}
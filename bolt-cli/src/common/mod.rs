@@ -1,13 +1,16 @@
 use std::{fs, io::Write, path::PathBuf};
 
 use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
-use eyre::{Context, Result};
+use eyre::{bail, Context, Result};
 use serde::Serialize;
 use tracing::info;
 
 /// BoltManager contract bindings.
 pub mod bolt_manager;
 
+/// Utilities for working with commit-boost signer modules.
+pub mod commit_boost;
+
 /// Utilities for working with DIRK remote keystores.
 pub mod dirk;
 
@@ -17,6 +20,9 @@ pub mod keystore;
 /// Utilities for signing and verifying messages.
 pub mod signing;
 
+/// Signing backends selected by a URI scheme.
+pub mod signer;
+
 /// Utilities for hashing messages and custom types.
 pub mod hash;
 
@@ -29,6 +35,33 @@ pub fn parse_bls_public_key(delegatee_pubkey: &str) -> Result<BlsPublicKey> {
     .map_err(|e| eyre::eyre!("Failed to parse delegatee public key '{}': {}", hex_pk, e))
 }
 
+/// Parse a list of BLS public keys from strings, reporting the index of the first one that
+/// fails to parse. Duplicates are accepted, see [parse_unique_bls_public_keys].
+pub fn parse_bls_public_keys(pubkeys: &[&str]) -> Result<Vec<BlsPublicKey>> {
+    pubkeys
+        .iter()
+        .enumerate()
+        .map(|(index, pubkey)| {
+            parse_bls_public_key(pubkey.trim())
+                .wrap_err_with(|| format!("Invalid BLS public key at index {index}"))
+        })
+        .collect()
+}
+
+/// Parse a list of BLS public keys from strings like [parse_bls_public_keys], and reject
+/// the list if the same key appears more than once.
+pub fn parse_unique_bls_public_keys(pubkeys: &[&str]) -> Result<Vec<BlsPublicKey>> {
+    let parsed = parse_bls_public_keys(pubkeys)?;
+
+    for (index, pubkey) in parsed.iter().enumerate() {
+        if let Some(first) = parsed[..index].iter().position(|pk| pk == pubkey) {
+            bail!("Duplicate BLS public key at index {index} (first at index {first}): {pubkey:?}");
+        }
+    }
+
+    Ok(parsed)
+}
+
 /// Write some serializable data to an output json file
 pub fn write_to_file<T: Serialize>(out: &str, data: &T) -> Result<()> {
     let out_path = PathBuf::from(out);
@@ -69,3 +102,32 @@ pub fn request_confirmation() {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_bls_public_keys, parse_unique_bls_public_keys};
+
+    const PUBKEY_A: &str = "0x83eeddfac5e60f8fe607ee8713efb8877c295ad9f8ca075f4d8f6f2ae241a30dd57f78f6f3863a9fe0d5b5db9d550b93";
+    const PUBKEY_B: &str = "0x8d0edf4fe9c80cd640220ca7a68a48efcbc56a13536d6b274bf3719befaffa13688ebee9f37414b3dddc8c7e77233ce8";
+
+    #[test]
+    fn test_parse_bls_public_keys_duplicate() {
+        let pubkeys = [PUBKEY_A, PUBKEY_B, PUBKEY_A];
+        assert_eq!(parse_bls_public_keys(&pubkeys).unwrap().len(), 3);
+
+        let err = parse_unique_bls_public_keys(&pubkeys).unwrap_err();
+        assert!(err.to_string().contains("index 2 (first at index 0)"), "{err}");
+        assert_eq!(parse_unique_bls_public_keys(&pubkeys[..2]).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_bls_public_keys_malformed() {
+        let pubkeys = [PUBKEY_A, "0x1234", PUBKEY_B];
+        let err = parse_bls_public_keys(&pubkeys).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid BLS public key at index 1");
+
+        // The malformed entry is reported before any duplicate check
+        let err = parse_unique_bls_public_keys(&["not hex", PUBKEY_A, PUBKEY_A]).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid BLS public key at index 0");
+    }
+}
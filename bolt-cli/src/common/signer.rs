@@ -0,0 +1,566 @@
+use std::{collections::HashMap, fs, path::Path, str::FromStr};
+
+use alloy::primitives::B256;
+use async_trait::async_trait;
+use ethereum_consensus::crypto::bls::{
+    PublicKey as BlsPublicKey, SecretKey as BlsSecretKey, Signature as BlsSignature,
+};
+use eyre::{bail, eyre, Context, ContextCompat, Result};
+use lighthouse_eth2_keystore::Keystore;
+use reqwest::Url;
+use tracing::{debug, warn};
+
+use crate::{
+    cli::{Chain, CommitBoostOpts, DirkOpts, LocalKeystoreOpts, SecretsSource, TlsCredentials},
+    pb::eth2_signer_api::Account,
+};
+
+use super::{
+    commit_boost::CommitBoostSigner,
+    dirk::Dirk,
+    keystore::{keystore_paths, KeystoreError, KeystoreSecret},
+    parse_bls_public_key,
+    signing::{
+        compute_commit_boost_signing_root, compute_domain_from_mask, verify_commit_boost_root,
+    },
+};
+
+/// A signing backend holding BLS keys, able to sign roots with the Commit Boost domain.
+#[async_trait]
+pub trait Signer: Send {
+    /// Returns the name of the signing backend.
+    fn backend(&self) -> &'static str;
+
+    /// Returns the public keys available to sign with.
+    async fn pubkeys(&mut self) -> Result<Vec<BlsPublicKey>>;
+
+    /// Sign the given object root with the key of `pubkey`, using the Commit Boost domain
+    /// of `chain`.
+    async fn sign_commit_boost_root(
+        &mut self,
+        pubkey: &BlsPublicKey,
+        root: [u8; 32],
+        chain: Chain,
+    ) -> Result<BlsSignature>;
+}
+
+/// Errors that can occur while parsing a signer URI.
+#[derive(Debug, thiserror::Error)]
+pub enum SignerUriError {
+    #[error("Missing scheme in signer URI, expected keystore://, dirk:// or commit-boost://")]
+    MissingScheme,
+    #[error("Unsupported signer URI scheme: {0}")]
+    UnsupportedScheme(String),
+    #[error("Missing `{0}` parameter in signer URI")]
+    MissingParam(&'static str),
+    #[error("Secrets can't be given in the signer URI, use the `{0}` parameter instead")]
+    InlineSecret(&'static str),
+    #[error("Invalid percent-encoding in signer URI: {0}")]
+    InvalidEncoding(String),
+    #[error("Invalid signer address {0}: {1}")]
+    InvalidAddress(String, String),
+}
+
+/// The location of a signing backend, parsed from a URI whose scheme selects the backend:
+///
+/// - `keystore://<path>[?password_path=<path>]`: an EIP-2335 keystore directory. The password path
+///   is either a directory of per-key passwords, or a file with the password of all keys. It
+///   defaults to the `secrets` directory.
+/// - `dirk://<host>:<port>?wallet=<path>&client_cert=<path>&client_key=<path>[&ca_cert=<path>]
+///   [&passphrases_path=<path>]`: a remote Dirk keystore, reached over TLS. The passphrases file
+///   holds one passphrase per line.
+/// - `commit-boost://<host>:<port>?jwt_path=<path>`: a commit-boost signer module.
+///
+/// Secrets are never part of the URI, which may end up in the shell history or the process
+/// list: they are read from the referenced files when connecting. Components of the URI are
+/// percent-decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignerUri {
+    /// A local EIP-2335 keystore directory.
+    Keystore {
+        /// The path to the keystore directory.
+        path: String,
+        /// The path to the passwords directory or file.
+        password_path: String,
+    },
+    /// A remote Dirk keystore.
+    Dirk {
+        /// The URL of the Dirk keystore.
+        url: String,
+        /// The path of the wallet in the Dirk keystore.
+        wallet_path: String,
+        /// The path to the file with the passphrases of the wallet, if any.
+        passphrases_path: Option<String>,
+        /// The TLS credentials for connecting to the Dirk keystore.
+        tls_credentials: TlsCredentials,
+    },
+    /// A commit-boost signer module.
+    CommitBoost {
+        /// The URL of the commit-boost signer.
+        signer_url: Url,
+        /// The path to the file with the JWT of the module.
+        jwt_path: String,
+    },
+}
+
+impl FromStr for SignerUri {
+    type Err = SignerUriError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (scheme, rest) = s.split_once("://").ok_or(SignerUriError::MissingScheme)?;
+        let (location, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let location = percent_decode(location)?;
+
+        let mut params = HashMap::new();
+        for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+            params.insert(percent_decode(key)?, percent_decode(value)?);
+        }
+
+        // Reject the inline secrets rather than silently ignoring them
+        for (param, instead) in [
+            ("password", "password_path"),
+            ("passphrases", "passphrases_path"),
+            ("jwt", "jwt_path"),
+        ] {
+            if params.contains_key(param) {
+                return Err(SignerUriError::InlineSecret(instead));
+            }
+        }
+
+        match scheme {
+            "keystore" => Ok(Self::Keystore {
+                path: location,
+                password_path: params
+                    .remove("password_path")
+                    .unwrap_or_else(|| "secrets".to_string()),
+            }),
+            "dirk" => Ok(Self::Dirk {
+                url: format!("https://{location}"),
+                wallet_path: required(&mut params, "wallet")?,
+                passphrases_path: params.remove("passphrases_path"),
+                tls_credentials: TlsCredentials {
+                    client_cert_path: required(&mut params, "client_cert")?,
+                    client_key_path: required(&mut params, "client_key")?,
+                    ca_cert_path: params.remove("ca_cert"),
+                },
+            }),
+            "commit-boost" => {
+                let signer_url = Url::parse(&format!("http://{location}"))
+                    .map_err(|e| SignerUriError::InvalidAddress(location, e.to_string()))?;
+                Ok(Self::CommitBoost { signer_url, jwt_path: required(&mut params, "jwt_path")? })
+            }
+            other => Err(SignerUriError::UnsupportedScheme(other.to_string())),
+        }
+    }
+}
+
+/// Takes the value of a required parameter of a signer URI.
+fn required(
+    params: &mut HashMap<String, String>,
+    key: &'static str,
+) -> Result<String, SignerUriError> {
+    params.remove(key).ok_or(SignerUriError::MissingParam(key))
+}
+
+/// Decodes the percent-encoded bytes of a component of a signer URI.
+fn percent_decode(component: &str) -> Result<String, SignerUriError> {
+    let invalid = || SignerUriError::InvalidEncoding(component.to_string());
+
+    let mut bytes = Vec::with_capacity(component.len());
+    let mut rest = component.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = tail.get(..2).ok_or_else(invalid)?;
+            let hex = std::str::from_utf8(hex).map_err(|_| invalid())?;
+            bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+/// Reads a secret from the given file, without its surrounding whitespace.
+fn read_secret(path: &str) -> Result<String> {
+    let secret = fs::read_to_string(path).wrap_err(format!("Failed to read secret at {path}"))?;
+    Ok(secret.trim().to_string())
+}
+
+impl SignerUri {
+    /// Connect to the signing backend, or load its keys for local keystores. The secrets are
+    /// read from their files.
+    pub async fn connect(self) -> Result<Box<dyn Signer>> {
+        let source = match self {
+            Self::Keystore { path, password_path } => {
+                // A password file holds the password of all the keys
+                let (password, password_path) = if Path::new(&password_path).is_file() {
+                    (Some(read_secret(&password_path)?), None)
+                } else {
+                    (None, Some(password_path))
+                };
+                SecretsSource::LocalKeystore {
+                    opts: LocalKeystoreOpts { path, password, password_path },
+                }
+            }
+            Self::Dirk { url, wallet_path, passphrases_path, tls_credentials } => {
+                let passphrases = passphrases_path
+                    .map(|path| {
+                        let passphrases = read_secret(&path)?;
+                        eyre::Ok(passphrases.lines().map(str::to_string).collect())
+                    })
+                    .transpose()?;
+                SecretsSource::Dirk {
+                    opts: DirkOpts { url, wallet_path, passphrases, tls_credentials },
+                }
+            }
+            Self::CommitBoost { signer_url, jwt_path } => SecretsSource::CommitBoost {
+                opts: CommitBoostOpts { signer_url, jwt: read_secret(&jwt_path)? },
+            },
+        };
+
+        source.connect().await
+    }
+}
+
+impl SecretsSource {
+    /// Connect to the signing backend of the key source, or load its keys for local sources.
+    pub async fn connect(self) -> Result<Box<dyn Signer>> {
+        match self {
+            Self::SecretKeys { secret_keys } => {
+                Ok(Box::new(SecretKeysSigner::from_secret_keys(&secret_keys)?))
+            }
+            Self::LocalKeystore { opts } => Ok(Box::new(KeystoreSigner::load(&opts)?)),
+            Self::Dirk { opts } => Ok(Box::new(DirkSigner::connect(opts).await?)),
+            Self::CommitBoost { opts } => {
+                Ok(Box::new(CommitBoostSigner::new(opts.signer_url, opts.jwt)))
+            }
+        }
+    }
+}
+
+/// A signer with BLS secret keys given in hex format.
+pub struct SecretKeysSigner {
+    keys: Vec<BlsSecretKey>,
+}
+
+impl SecretKeysSigner {
+    /// Parse the given hex-encoded secret keys.
+    pub fn from_secret_keys(secret_keys: &[String]) -> Result<Self> {
+        let keys = secret_keys
+            .iter()
+            .enumerate()
+            .map(|(index, sk)| {
+                // Don't leak the secret key in the error
+                BlsSecretKey::try_from(sk.trim().to_string())
+                    .map_err(|_| eyre!("Invalid secret key #{index}"))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { keys })
+    }
+}
+
+#[async_trait]
+impl Signer for SecretKeysSigner {
+    fn backend(&self) -> &'static str {
+        "local keys"
+    }
+
+    async fn pubkeys(&mut self) -> Result<Vec<BlsPublicKey>> {
+        Ok(self.keys.iter().map(|sk| sk.public_key()).collect())
+    }
+
+    async fn sign_commit_boost_root(
+        &mut self,
+        pubkey: &BlsPublicKey,
+        root: [u8; 32],
+        chain: Chain,
+    ) -> Result<BlsSignature> {
+        let sk = self
+            .keys
+            .iter()
+            .find(|sk| sk.public_key() == *pubkey)
+            .wrap_err_with(|| format!("No secret key for {:?}", pubkey))?;
+
+        let signing_root = compute_commit_boost_signing_root(root, &chain)?;
+        Ok(sk.sign(signing_root.0.as_ref()))
+    }
+}
+
+/// A signer with the keys of a local EIP-2335 keystore directory.
+///
+/// Each key is only decrypted to sign, so that a key failing to decrypt, e.g. because of a
+/// missing password, doesn't prevent the others from signing.
+pub struct KeystoreSigner {
+    keystores: Vec<Keystore>,
+    secret: KeystoreSecret,
+}
+
+impl KeystoreSigner {
+    /// Read the keystores of the directory, with their passwords.
+    pub fn load(opts: &LocalKeystoreOpts) -> Result<Self> {
+        Self::from_directory(&opts.path, KeystoreSecret::from_keystore_options(opts)?)
+    }
+
+    /// Read the keystores of the given directory, decrypted with the given passwords.
+    pub fn from_directory(keys_path: &str, secret: KeystoreSecret) -> Result<Self> {
+        let keystores = keystore_paths(keys_path)?
+            .into_iter()
+            .map(|path| Keystore::from_json_file(path).map_err(KeystoreError::Eth2Keystore))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        debug!("Found {} keys in the keystore", keystores.len());
+        Ok(Self { keystores, secret })
+    }
+}
+
+#[async_trait]
+impl Signer for KeystoreSigner {
+    fn backend(&self) -> &'static str {
+        "keystore"
+    }
+
+    async fn pubkeys(&mut self) -> Result<Vec<BlsPublicKey>> {
+        self.keystores.iter().map(|ks| parse_bls_public_key(ks.pubkey())).collect()
+    }
+
+    async fn sign_commit_boost_root(
+        &mut self,
+        pubkey: &BlsPublicKey,
+        root: [u8; 32],
+        chain: Chain,
+    ) -> Result<BlsSignature> {
+        let pubkey_hex = alloy::hex::encode(pubkey.as_ref());
+        let ks = self
+            .keystores
+            .iter()
+            .find(|ks| ks.pubkey() == pubkey_hex)
+            .wrap_err_with(|| format!("No key in the keystore for {:?}", pubkey))?;
+
+        let password = self.secret.get(ks.pubkey()).ok_or(KeystoreError::MissingPassword)?;
+        let kp = ks.decrypt_keypair(password.as_bytes()).map_err(KeystoreError::Eth2Keystore)?;
+
+        let signing_root = compute_commit_boost_signing_root(root, &chain)?;
+        let signature = kp.sk.sign(signing_root.0.into());
+        Ok(BlsSignature::try_from(signature.serialize().as_ref())?)
+    }
+}
+
+/// A signer with the accounts of a wallet of a remote Dirk keystore.
+pub struct DirkSigner {
+    dirk: Dirk,
+    wallet_path: String,
+    passphrases: Vec<String>,
+    accounts: Vec<Account>,
+}
+
+impl DirkSigner {
+    /// Connect to the Dirk keystore.
+    pub async fn connect(opts: DirkOpts) -> Result<Self> {
+        let dirk = Dirk::connect(opts.url, opts.tls_credentials).await?;
+        Self::new(dirk, opts.wallet_path, opts.passphrases)
+    }
+
+    /// Create a new signer with the accounts of the given wallet, unlocked with the first
+    /// working passphrase.
+    pub fn new(dirk: Dirk, wallet_path: String, passphrases: Option<Vec<String>>) -> Result<Self> {
+        let Some(passphrases) = passphrases else {
+            bail!("A passphrase is required in order to sign messages remotely with Dirk");
+        };
+
+        Ok(Self { dirk, wallet_path, passphrases, accounts: vec![] })
+    }
+}
+
+#[async_trait]
+impl Signer for DirkSigner {
+    fn backend(&self) -> &'static str {
+        "dirk"
+    }
+
+    async fn pubkeys(&mut self) -> Result<Vec<BlsPublicKey>> {
+        self.accounts = self.dirk.list_accounts(self.wallet_path.clone()).await?;
+        debug!("Found {} remote accounts to sign with", self.accounts.len());
+        self.accounts
+            .iter()
+            .map(|account| Ok(BlsPublicKey::try_from(account.public_key.as_slice())?))
+            .collect()
+    }
+
+    async fn sign_commit_boost_root(
+        &mut self,
+        pubkey: &BlsPublicKey,
+        root: [u8; 32],
+        chain: Chain,
+    ) -> Result<BlsSignature> {
+        if self.accounts.is_empty() {
+            self.pubkeys().await?;
+        }
+        let account = self
+            .accounts
+            .iter()
+            .find(|account| account.public_key == pubkey.as_ref())
+            .cloned()
+            .wrap_err_with(|| format!("No Dirk account for {:?}", pubkey))?;
+
+        // Note: before signing, we must unlock the account
+        for passphrase in &self.passphrases {
+            if self.dirk.unlock_account(account.name.clone(), passphrase.clone()).await? {
+                break;
+            }
+        }
+
+        // Dirk does the hash tree root internally
+        let domain = B256::from(compute_domain_from_mask(chain.fork_version()));
+        let signature = self.dirk.request_signature(&account, root.into(), domain).await;
+
+        // Try to lock the account back after signing
+        if let Err(err) = self.dirk.lock_account(account.name.clone()).await {
+            warn!("Failed to lock account after signing {}: {:?}", account.name, err);
+        }
+
+        signature
+    }
+}
+
+#[async_trait]
+impl Signer for CommitBoostSigner {
+    fn backend(&self) -> &'static str {
+        "commit-boost"
+    }
+
+    async fn pubkeys(&mut self) -> Result<Vec<BlsPublicKey>> {
+        self.list_pubkeys().await
+    }
+
+    /// The signer computes the signing root with the Commit Boost domain of its own chain:
+    /// the signature is checked against `chain`, so that a signer configured for another
+    /// chain is reported instead of producing unusable messages.
+    async fn sign_commit_boost_root(
+        &mut self,
+        pubkey: &BlsPublicKey,
+        root: [u8; 32],
+        chain: Chain,
+    ) -> Result<BlsSignature> {
+        let signature = self.request_signature(pubkey, root.into()).await?;
+
+        let blst_sig = blst::min_pk::Signature::from_bytes(signature.as_ref())
+            .map_err(|e| eyre!("Failed to parse signature: {:?}", e))?;
+        verify_commit_boost_root(pubkey.clone(), root, &blst_sig, &chain).wrap_err_with(|| {
+            format!("The commit-boost signature of {:?} is not valid on {:?}", pubkey, chain)
+        })?;
+
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::Chain;
+
+    use super::{percent_decode, SignerUri, SignerUriError};
+
+    #[test]
+    fn test_parse_signer_uri() -> eyre::Result<()> {
+        let uri =
+            "keystore://test_data/lighthouse/validators?password_path=test_data/lighthouse/secrets";
+        let SignerUri::Keystore { path, password_path } = uri.parse()? else {
+            panic!("expected a keystore URI")
+        };
+        assert_eq!(path, "test_data/lighthouse/validators");
+        assert_eq!(password_path, "test_data/lighthouse/secrets");
+
+        // The components are percent-decoded
+        let SignerUri::Keystore { path, password_path } =
+            "keystore:///my%20keys?password_path=%2Fetc%2Fpasswords".parse()?
+        else {
+            panic!("expected a keystore URI")
+        };
+        assert_eq!(path, "/my keys");
+        assert_eq!(password_path, "/etc/passwords");
+
+        let uri = "dirk://localhost:9091?wallet=wallet1&passphrases_path=pass.txt&client_cert=c.crt&client_key=c.key";
+        let SignerUri::Dirk { url, wallet_path, passphrases_path, tls_credentials } =
+            uri.parse()?
+        else {
+            panic!("expected a Dirk URI")
+        };
+        assert_eq!(url, "https://localhost:9091");
+        assert_eq!(wallet_path, "wallet1");
+        assert_eq!(passphrases_path.as_deref(), Some("pass.txt"));
+        assert_eq!(tls_credentials.client_cert_path, "c.crt");
+        assert_eq!(tls_credentials.client_key_path, "c.key");
+        assert_eq!(tls_credentials.ca_cert_path, None);
+
+        let SignerUri::CommitBoost { signer_url, jwt_path } =
+            "commit-boost://localhost:20000?jwt_path=jwt.hex".parse()?
+        else {
+            panic!("expected a commit-boost URI")
+        };
+        assert_eq!(signer_url.as_str(), "http://localhost:20000/");
+        assert_eq!(jwt_path, "jwt.hex");
+
+        // Secrets can't be given inline
+        assert!(matches!(
+            "keystore:///keys?password=secret".parse::<SignerUri>(),
+            Err(SignerUriError::InlineSecret("password_path"))
+        ));
+        assert!(matches!(
+            "commit-boost://localhost:20000?jwt=token".parse::<SignerUri>(),
+            Err(SignerUriError::InlineSecret("jwt_path"))
+        ));
+
+        assert!(matches!(
+            "dirk://localhost:9091?wallet=wallet1".parse::<SignerUri>(),
+            Err(SignerUriError::MissingParam("client_cert"))
+        ));
+        assert!(matches!(
+            "web3signer://localhost:9000".parse::<SignerUri>(),
+            Err(SignerUriError::UnsupportedScheme(scheme)) if scheme == "web3signer"
+        ));
+        assert!(matches!("validators".parse::<SignerUri>(), Err(SignerUriError::MissingScheme)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("a%2Cb%3Dc").unwrap(), "a,b=c");
+        assert_eq!(percent_decode("%F0%9F%94%91").unwrap(), "🔑");
+        assert!(matches!(percent_decode("100%"), Err(SignerUriError::InvalidEncoding(_))));
+        assert!(matches!(percent_decode("%zz"), Err(SignerUriError::InvalidEncoding(_))));
+        assert!(matches!(percent_decode("%FF"), Err(SignerUriError::InvalidEncoding(_))));
+    }
+
+    #[tokio::test]
+    async fn test_connect_signer_uri() -> eyre::Result<()> {
+        let keys_path = env!("CARGO_MANIFEST_DIR").to_string() + "/test_data/lighthouse/validators";
+        let secrets_path = env!("CARGO_MANIFEST_DIR").to_string() + "/test_data/lighthouse/secrets";
+
+        let uri: SignerUri =
+            format!("keystore://{keys_path}?password_path={secrets_path}").parse()?;
+        let mut signer = uri.connect().await?;
+        assert_eq!(signer.backend(), "keystore");
+
+        let pubkeys = signer.pubkeys().await?;
+        assert!(!pubkeys.is_empty());
+        signer.sign_commit_boost_root(&pubkeys[0], [1; 32], Chain::Holesky).await?;
+
+        // The JWT is read from its file when connecting
+        let dir = tempfile::tempdir()?;
+        let jwt_path = dir.path().join("jwt.hex");
+        std::fs::write(&jwt_path, "token\n")?;
+
+        let uri: SignerUri =
+            format!("commit-boost://localhost:20000?jwt_path={}", jwt_path.display()).parse()?;
+        assert_eq!(uri.connect().await?.backend(), "commit-boost");
+
+        let uri: SignerUri = "commit-boost://localhost:20000?jwt_path=/missing/jwt.hex".parse()?;
+        assert!(uri.connect().await.is_err());
+
+        Ok(())
+    }
+}
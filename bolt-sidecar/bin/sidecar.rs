@@ -17,15 +17,18 @@ async fn main() -> eyre::Result<()> {
 
     let opts = Opts::try_parse()?;
 
-    init_telemetry_stack(opts.telemetry.metrics_port())?;
+    init_telemetry_stack(&opts.telemetry)?;
 
     info!(chain = opts.chain.name(), "Starting Bolt sidecar");
 
     let use_local_signer = opts.constraint_signing.constraint_private_key.is_some();
+    let use_aggregate_signer = !opts.constraint_signing.constraint_cosigner_urls.is_empty();
     let use_commit_boost_signer = opts.constraint_signing.commit_boost_signer_url.is_some();
     let use_keystore_signer = opts.constraint_signing.keystore_path.is_some();
 
-    if use_local_signer {
+    if use_aggregate_signer {
+        SidecarDriver::with_aggregate_signer(&opts).await?.run_forever().await
+    } else if use_local_signer {
         SidecarDriver::with_local_signer(&opts).await?.run_forever().await
     } else if use_commit_boost_signer {
         SidecarDriver::with_commit_boost_signer(&opts).await?.run_forever().await
@@ -0,0 +1,15 @@
+use std::process::Command;
+
+// Expose the commit the sidecar is built from, when built from a git checkout.
+fn main() {
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs/heads");
+
+    let Ok(output) = Command::new("git").args(["rev-parse", "--short=8", "HEAD"]).output() else {
+        return;
+    };
+    if output.status.success() {
+        let commit = String::from_utf8_lossy(&output.stdout);
+        println!("cargo:rustc-env=BOLT_SIDECAR_GIT_COMMIT={}", commit.trim());
+    }
+}
@@ -1,5 +1,6 @@
 use std::{sync::Arc, time::Duration};
 
+use alloy::primitives::U256;
 use axum::{
     body::{self, Body},
     extract::{Path, Request, State},
@@ -26,17 +27,18 @@ use super::spec::{
     STATUS_PATH,
 };
 use crate::{
-    builder::PayloadFetcher,
+    builder::{
+        signature::{verify_builder_bid, BidVerificationError},
+        PayloadFetcher,
+    },
     client::ConstraintsClient,
-    primitives::{GetPayloadResponse, SignedBuilderBid},
+    config::{ChainConfig, DEFAULT_RELAY_GET_HEADER_TIMEOUT_MS},
+    primitives::{GetPayloadResponse, PayloadAndBid, SignedBuilderBid},
     telemetry::ApiMetrics,
 };
 
 const MAX_BLINDED_BLOCK_LENGTH: usize = 1024 * 1024;
 
-/// TODO: determine value
-const GET_HEADER_WITH_PROOFS_TIMEOUT: Duration = Duration::from_millis(500);
-
 /// A proxy server for the builder API.
 /// Forwards all requests to the target after interception.
 #[derive(Debug)]
@@ -46,6 +48,15 @@ pub struct BuilderProxyServer<T, P> {
     local_payload: Mutex<Option<GetPayloadResponse>>,
     /// The payload fetcher to get locally built payloads.
     payload_fetcher: P,
+    /// The minimum value of the bids returned to the beacon client.
+    min_bid: U256,
+    /// The public keys of the relays whose bids can be returned. If empty, any relay is accepted
+    /// and the bid signatures are not verified.
+    relay_pubkeys: Vec<BlsPublicKey>,
+    /// How long to wait for the relay bid before falling back to the local one.
+    get_header_timeout: Duration,
+    /// The chain configuration, used to verify the bid signatures.
+    chain: ChainConfig,
 }
 
 /// Parameters for the get_header request.
@@ -60,12 +71,56 @@ pub struct GetHeaderParams {
 
 impl<T, P> BuilderProxyServer<T, P>
 where
-    T: ConstraintsApi,
-    P: PayloadFetcher + Send + Sync,
+    T: ConstraintsApi + Send + Sync + 'static,
+    P: PayloadFetcher + Send + Sync + 'static,
 {
     /// Create a new builder proxy server.
-    pub fn new(proxy_target: T, payload_fetcher: P) -> Self {
-        Self { proxy_target, local_payload: Mutex::new(None), payload_fetcher }
+    pub fn new(proxy_target: T, payload_fetcher: P, chain: ChainConfig) -> Self {
+        Self {
+            proxy_target,
+            local_payload: Mutex::new(None),
+            payload_fetcher,
+            min_bid: U256::ZERO,
+            relay_pubkeys: Vec::new(),
+            get_header_timeout: Duration::from_millis(DEFAULT_RELAY_GET_HEADER_TIMEOUT_MS),
+            chain,
+        }
+    }
+
+    /// Set how long to wait for the relay bid of a `getHeader` request before falling back to
+    /// the locally built bid.
+    pub fn with_get_header_timeout(mut self, timeout: Duration) -> Self {
+        self.get_header_timeout = timeout;
+        self
+    }
+
+    /// Set the minimum value of the bids returned to the beacon client.
+    pub fn with_min_bid(mut self, min_bid: U256) -> Self {
+        self.min_bid = min_bid;
+        self
+    }
+
+    /// Set the public keys of the relays whose bids can be returned to the beacon client.
+    pub fn with_relay_pubkeys(mut self, relay_pubkeys: Vec<BlsPublicKey>) -> Self {
+        self.relay_pubkeys = relay_pubkeys;
+        self
+    }
+
+    /// Verifies that the bid is signed by an accepted relay.
+    ///
+    /// Without configured relay public keys, there is no key to verify the bid against: the
+    /// public key of the bid itself would accept any self-signed bid, so the check is skipped.
+    fn verify_relay_bid(&self, bid: &SignedBuilderBid) -> Result<(), BidVerificationError> {
+        if self.relay_pubkeys.is_empty() {
+            return Ok(());
+        }
+
+        let relay_pubkey = &bid.message.public_key;
+        if !self.relay_pubkeys.contains(relay_pubkey) {
+            return Err(BidVerificationError::PubkeyMismatch(relay_pubkey.clone()));
+        }
+
+        verify_builder_bid(bid, relay_pubkey, &self.chain)
     }
 
     /// Gets the status. Just forwards the request to constraints client and returns the status.
@@ -103,8 +158,10 @@ where
     /// Gets the header. NOTE: converts this request to a get_header_with_proofs
     /// request to the modified constraints client.
     ///
-    /// In case of a builder or relay failure, we return the locally built block header
-    /// and store the actual payload so we can return it later.
+    /// The relay bid is compared against the locally built block (if any), and the most
+    /// valuable one above the minimum bid is returned. See [select_bid] for the details.
+    /// If the local block header is returned, the actual payload is stored so we can
+    /// return it later.
     pub async fn get_header(
         State(server): State<Arc<Self>>,
         Path(params): Path<GetHeaderParams>,
@@ -114,39 +171,72 @@ where
         debug!("Received get_header request");
         let slot = params.slot;
 
-        let err = match tokio::time::timeout(
-            GET_HEADER_WITH_PROOFS_TIMEOUT,
-            server.proxy_target.get_header_with_proofs(params),
-        )
-        .await
-        {
-            Ok(res) => match res {
-                Err(builder_err) => builder_err,
-                Ok(header) => {
-                    // Clear the local payload cache if we have a successful response
-                    // By definition of `server.local_payload`, this will be `Some` IFF we have
-                    // signed a local header
-                    let mut local_payload = server.local_payload.lock();
-                    *local_payload = None;
-
-                    debug!(elapsed = ?start.elapsed(), "Returning signed builder bid");
-                    return Ok(Json(header));
-                }
-            },
-            Err(err) => BuilderApiError::Timeout(err),
+        let (relay_res, local_payload) = tokio::join!(
+            tokio::time::timeout(
+                server.get_header_timeout,
+                server.proxy_target.get_header_with_proofs(params),
+            ),
+            server.payload_fetcher.fetch_payload(slot),
+        );
+
+        let relay_bid = match relay_res {
+            Ok(Ok(header)) => Some(header),
+            Ok(Err(err)) => {
+                warn!(slot, elapsed = ?start.elapsed(), ?err, "Proxy error, no relay bid available");
+                None
+            }
+            Err(err) => {
+                let err = BuilderApiError::Timeout(err);
+                warn!(slot, elapsed = ?start.elapsed(), ?err, "Proxy error, no relay bid available");
+                None
+            }
         };
 
-        // On ANY error, we fall back to locally built block
-        warn!(slot, elapsed = ?start.elapsed(), err = ?err, "Proxy error, fetching local payload instead");
+        // Never serve a relay bid with an invalid signature
+        let relay_bid = relay_bid.filter(|bid| match server.verify_relay_bid(&bid.data) {
+            Ok(()) => true,
+            Err(err) => {
+                warn!(slot, ?err, "Invalid relay bid signature, ignoring relay bid");
+                false
+            }
+        });
 
-        let Some(payload_and_bid) = server.payload_fetcher.fetch_payload(slot).await else {
-            // TODO: handle failure? In this case, we don't have a fallback block
-            // which means we haven't made any commitments. This means the EL should
-            // fallback to local block building.
-            debug!("No local payload with commitments produced for slot {slot}");
-            return Err(BuilderApiError::FailedToFetchLocalPayload(slot));
-        };
+        let relay_value = relay_bid.as_ref().map(|bid| bid.data.message.value);
+        let local_value = local_payload.as_ref().map(|payload| payload.value);
+
+        match (select_bid(relay_value, local_value, server.min_bid), relay_bid, local_payload) {
+            (Some(BidSource::Relay), Some(header), _) => {
+                // Clear the local payload cache since we're returning the relay bid.
+                // By definition of `server.local_payload`, this will be `Some` IFF we have
+                // signed a local header
+                let mut local_payload = server.local_payload.lock();
+                *local_payload = None;
+
+                debug!(elapsed = ?start.elapsed(), ?relay_value, ?local_value, "Returning signed builder bid");
+                Ok(Json(header))
+            }
+            (Some(BidSource::Local), _, Some(payload_and_bid)) => {
+                debug!(?relay_value, ?local_value, "Local payload selected for slot {slot}");
+                Ok(Json(server.use_local_payload(slot, payload_and_bid, start)))
+            }
+            _ => {
+                // TODO: handle failure? In this case, we don't have a fallback block
+                // which means we haven't made any commitments. This means the EL should
+                // fallback to local block building.
+                debug!(?relay_value, min_bid = %server.min_bid, "No valid bid nor local payload with commitments produced for slot {slot}");
+                Err(BuilderApiError::FailedToFetchLocalPayload(slot))
+            }
+        }
+    }
 
+    /// Stores the locally built payload for the following `get_payload` request,
+    /// and returns its signed bid.
+    fn use_local_payload(
+        &self,
+        slot: u64,
+        payload_and_bid: PayloadAndBid,
+        start: std::time::Instant,
+    ) -> VersionedValue<SignedBuilderBid> {
         let hash = payload_and_bid.bid.message.header.block_hash.clone();
         let number = payload_and_bid.bid.message.header.block_number;
         info!(elapsed = ?start.elapsed(), %hash, "Fetched local payload for slot {slot}");
@@ -154,7 +244,7 @@ where
         {
             // Since we've signed a local header, set the payload for
             // the following `get_payload` request.
-            let mut local_payload = server.local_payload.lock();
+            let mut local_payload = self.local_payload.lock();
             *local_payload = Some(payload_and_bid.payload);
         }
 
@@ -165,7 +255,7 @@ where
         };
 
         info!(elapsed = ?start.elapsed(), %hash, number, ?versioned_bid, "Returning locally built header");
-        Ok(Json(versioned_bid))
+        versioned_bid
     }
 
     /// Gets the payload. If we have a locally built payload, we return it.
@@ -198,6 +288,12 @@ where
             info!("Valid local block found, returning: {:?}", local_payload.block_hash());
             ApiMetrics::increment_local_blocks_proposed();
 
+            // The payload is served: remove it from the local builder, without delaying
+            // the response
+            let slot = signed_blinded_block.message.slot;
+            let fetcher_server = server.clone();
+            tokio::spawn(async move { fetcher_server.payload_fetcher.take_payload(slot).await });
+
             return Ok(Json(local_payload));
         }
 
@@ -221,6 +317,32 @@ where
     }
 }
 
+/// The source of the bid returned to the beacon client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BidSource {
+    /// The bid of the relay, fetched through the constraints client.
+    Relay,
+    /// The bid of the locally built payload.
+    Local,
+}
+
+/// Selects the bid to return to the beacon client, given the values of the relay bid and of the
+/// locally built payload, if available.
+///
+/// The most valuable bid at or above `min_bid` is selected, with ties going to the relay. If no
+/// bid meets the floor, the local one is still selected to guarantee the liveness of the
+/// commitments, while relay bids are dropped.
+fn select_bid(relay: Option<U256>, local: Option<U256>, min_bid: U256) -> Option<BidSource> {
+    let relay_valid = relay.filter(|value| *value >= min_bid);
+    let local_valid = local.filter(|value| *value >= min_bid);
+
+    match (relay_valid, local_valid) {
+        (Some(relay), Some(local)) if local > relay => Some(BidSource::Local),
+        (Some(_), _) => Some(BidSource::Relay),
+        (None, _) => local.map(|_| BidSource::Local),
+    }
+}
+
 /// Configuration for the builder proxy.
 #[derive(Debug, Clone)]
 pub struct BuilderProxyConfig {
@@ -228,6 +350,14 @@ pub struct BuilderProxyConfig {
     pub constraints_client: ConstraintsClient,
     /// The port on which the builder proxy should listen.
     pub server_port: u16,
+    /// The minimum value of the bids returned to the beacon client.
+    pub min_bid: U256,
+    /// The public keys of the relays whose bids can be returned to the beacon client.
+    pub relay_pubkeys: Vec<BlsPublicKey>,
+    /// How long to wait for the relay bid before falling back to the locally built one.
+    pub get_header_timeout: Duration,
+    /// The chain configuration.
+    pub chain: ChainConfig,
 }
 
 /// Start the builder proxy with the given payload fetcher and configuration.
@@ -244,15 +374,18 @@ where
         "Starting builder proxy..."
     );
 
-    let server = Arc::new(BuilderProxyServer::new(config.constraints_client, payload_fetcher));
+    if config.relay_pubkeys.is_empty() {
+        warn!("No relay public keys configured, the signatures of relay bids won't be verified");
+    }
 
-    let router = Router::new()
-        .route("/", get(index))
-        .route(STATUS_PATH, get(BuilderProxyServer::status))
-        .route(REGISTER_VALIDATORS_PATH, post(BuilderProxyServer::register_validators))
-        .route(GET_HEADER_PATH, get(BuilderProxyServer::get_header))
-        .route(GET_PAYLOAD_PATH, post(BuilderProxyServer::get_payload))
-        .with_state(server);
+    let server = Arc::new(
+        BuilderProxyServer::new(config.constraints_client, payload_fetcher, config.chain)
+            .with_min_bid(config.min_bid)
+            .with_relay_pubkeys(config.relay_pubkeys)
+            .with_get_header_timeout(config.get_header_timeout),
+    );
+
+    let router = make_router(server);
 
     let addr = format!("0.0.0.0:{}", config.server_port);
     let listener = TcpListener::bind(addr).await?;
@@ -261,6 +394,20 @@ where
     Ok(())
 }
 
+/// Creates the router of the builder proxy, serving the builder API endpoints.
+pub(crate) fn make_router<P>(server: Arc<BuilderProxyServer<ConstraintsClient, P>>) -> Router
+where
+    P: PayloadFetcher + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(index))
+        .route(STATUS_PATH, get(BuilderProxyServer::status))
+        .route(REGISTER_VALIDATORS_PATH, post(BuilderProxyServer::register_validators))
+        .route(GET_HEADER_PATH, get(BuilderProxyServer::get_header))
+        .route(GET_PAYLOAD_PATH, post(BuilderProxyServer::get_payload))
+        .with_state(server)
+}
+
 async fn index() -> Html<&'static str> {
     Html("Hello")
 }
@@ -404,3 +551,168 @@ fn check_locally_built_payload_integrity(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    use alloy::primitives::U256;
+    use axum::{
+        extract::{Path, State},
+        http::StatusCode,
+        routing::get,
+        Router,
+    };
+    use ethereum_consensus::primitives::BlsPublicKey;
+    use reqwest::Url;
+    use tokio::net::TcpListener;
+
+    use crate::{
+        builder::{
+            payload_fetcher::NoopPayloadFetcher,
+            signature::{sign_builder_message, BidVerificationError},
+            PayloadFetcher,
+        },
+        client::ConstraintsClient,
+        config::ChainConfig,
+        primitives::{
+            BuilderBid, GetPayloadResponse, PayloadAndBid, PayloadAndBlobs, SignedBuilderBid,
+        },
+    };
+
+    use super::{select_bid, BidSource, BuilderProxyServer, GetHeaderParams};
+
+    /// A payload fetcher that always has a locally built payload of the given value.
+    struct LocalPayload(U256);
+
+    #[async_trait::async_trait]
+    impl PayloadFetcher for LocalPayload {
+        async fn fetch_payload(&self, _slot: u64) -> Option<PayloadAndBid> {
+            Some(PayloadAndBid {
+                bid: SignedBuilderBid {
+                    message: BuilderBid { value: self.0, ..Default::default() },
+                    ..Default::default()
+                },
+                payload: GetPayloadResponse::from(PayloadAndBlobs::default()),
+                value: self.0,
+            })
+        }
+
+        async fn take_payload(&self, slot: u64) -> Option<PayloadAndBid> {
+            self.fetch_payload(slot).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slow_relay_falls_back_to_local_bid() -> eyre::Result<()> {
+        // A relay that takes longer than the timeout to respond
+        async fn slow_get_header(Path(_params): Path<(u64, String, String)>) -> StatusCode {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            StatusCode::NO_CONTENT
+        }
+
+        let router = Router::new().route(
+            "/eth/v1/builder/header_with_proofs/:slot/:parent_hash/:pubkey",
+            get(slow_get_header),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = Url::parse(&format!("http://{}", listener.local_addr()?))?;
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let local_value = U256::from(42);
+        let timeout = Duration::from_millis(100);
+        let server = BuilderProxyServer::new(
+            ConstraintsClient::new(url)?,
+            LocalPayload(local_value),
+            ChainConfig::mainnet(),
+        )
+        .with_get_header_timeout(timeout);
+
+        let params = GetHeaderParams {
+            slot: 10,
+            parent_hash: Default::default(),
+            public_key: Default::default(),
+        };
+
+        let start = Instant::now();
+        let bid = BuilderProxyServer::get_header(State(Arc::new(server)), Path(params)).await?;
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(bid.data.message.value, local_value);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_relay_bid() -> eyre::Result<()> {
+        let chain = ChainConfig::mainnet();
+        let url = Url::parse("http://127.0.0.1:0")?;
+
+        let random_sk = || {
+            let mut ikm = [0u8; 32];
+            rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut ikm);
+            blst::min_pk::SecretKey::key_gen(&ikm, &[]).unwrap()
+        };
+        let signed_bid = |sk: &blst::min_pk::SecretKey| -> eyre::Result<SignedBuilderBid> {
+            let public_key = BlsPublicKey::try_from(sk.sk_to_pk().to_bytes().as_ref())?;
+            let message = BuilderBid { value: U256::from(42), public_key, ..Default::default() };
+            let signature = sign_builder_message(&chain, sk, &message)?;
+            Ok(SignedBuilderBid { message, signature })
+        };
+
+        let relay_bid = signed_bid(&random_sk())?;
+        let forged_bid = signed_bid(&random_sk())?;
+
+        // Without relay public keys, the verification is skipped
+        let server = BuilderProxyServer::new(
+            ConstraintsClient::new(url.clone())?,
+            NoopPayloadFetcher,
+            chain,
+        );
+        assert!(server.verify_relay_bid(&forged_bid).is_ok());
+
+        // Otherwise, only the bids of the configured relays are accepted
+        let server =
+            BuilderProxyServer::new(ConstraintsClient::new(url)?, NoopPayloadFetcher, chain)
+                .with_relay_pubkeys(vec![relay_bid.message.public_key.clone()]);
+        assert!(server.verify_relay_bid(&relay_bid).is_ok());
+        assert!(matches!(
+            server.verify_relay_bid(&forged_bid),
+            Err(BidVerificationError::PubkeyMismatch(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_bid() {
+        let min_bid = U256::from(10);
+
+        // External wins: the relay bid is above the floor and more valuable
+        assert_eq!(
+            select_bid(Some(U256::from(20)), Some(U256::from(15)), min_bid),
+            Some(BidSource::Relay)
+        );
+        assert_eq!(select_bid(Some(U256::from(20)), None, min_bid), Some(BidSource::Relay));
+
+        // Self-built wins: it is above the floor and more valuable
+        assert_eq!(
+            select_bid(Some(U256::from(15)), Some(U256::from(20)), min_bid),
+            Some(BidSource::Local)
+        );
+        assert_eq!(
+            select_bid(Some(U256::from(5)), Some(U256::from(12)), min_bid),
+            Some(BidSource::Local)
+        );
+
+        // Floor not met: the self-built one is returned for liveness, relay bids are dropped
+        assert_eq!(
+            select_bid(Some(U256::from(5)), Some(U256::from(8)), min_bid),
+            Some(BidSource::Local)
+        );
+        assert_eq!(select_bid(Some(U256::from(5)), None, min_bid), None);
+        assert_eq!(select_bid(None, None, min_bid), None);
+    }
+}
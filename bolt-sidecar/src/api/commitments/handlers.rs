@@ -3,7 +3,7 @@ use std::sync::Arc;
 use axum::{
     body::Body,
     extract::State,
-    http::{HeaderMap, Request},
+    http::{HeaderMap, Request, StatusCode},
     response::Html,
     Json,
 };
@@ -12,20 +12,33 @@ use serde_json::Value;
 use tracing::{debug, error, info, instrument};
 
 use crate::{
-    api::commitments::headers::auth_from_headers,
+    api::commitments::headers::{
+        auth_from_headers, response_mode_from_headers, session_from_headers,
+    },
     common::CARGO_PKG_VERSION,
-    primitives::{commitment::SignatureError, InclusionRequest},
+    primitives::{
+        commitment::SignatureError, InclusionRequest, OpenSessionRequest, ReserveInclusionRequest,
+        SubmitBodyRequest,
+    },
+    signer::SignerSource,
+    telemetry::ApiMetrics,
 };
 
 use super::{
     jsonrpc::{JsonPayload, JsonResponse},
     server::CommitmentsApiInner,
     spec::{
-        CommitmentError, CommitmentsApi, RejectionError, GET_METADATA_METHOD, GET_VERSION_METHOD,
-        REQUEST_INCLUSION_METHOD,
+        CommitmentError, CommitmentsApi, RejectionError, ResponseMode, DRAIN_SLOT_METHOD,
+        GET_COMMITMENT_STATUS_METHOD, GET_METADATA_METHOD, GET_SLOT_INFO_METHOD,
+        GET_VERSION_METHOD, OPEN_SESSION_METHOD, REQUEST_INCLUSION_METHOD,
+        RESERVE_INCLUSION_METHOD, ROTATE_SIGNER_METHOD, SUBMIT_BODY_METHOD,
     },
 };
 
+/// The methods requesting a commitment, whose rejections are accounted for in the metrics.
+const COMMITMENT_METHODS: [&str; 3] =
+    [REQUEST_INCLUSION_METHOD, RESERVE_INCLUSION_METHOD, SUBMIT_BODY_METHOD];
+
 /// Handler function for the root JSON-RPC path.
 #[instrument(skip_all, name = "POST /rpc", fields(method = %payload.method))]
 pub async fn rpc_entrypoint(
@@ -35,6 +48,24 @@ pub async fn rpc_entrypoint(
 ) -> Result<Json<JsonResponse>, CommitmentError> {
     debug!("Received new request");
 
+    let method = payload.method.clone();
+    let response = handle_method(headers, api, payload).await;
+
+    if let Err(err) = &response {
+        if COMMITMENT_METHODS.contains(&method.as_str()) {
+            ApiMetrics::increment_commitments_rejected(err.rejection_reason());
+        }
+    }
+
+    response
+}
+
+/// Handles a JSON-RPC request according to its method.
+async fn handle_method(
+    headers: HeaderMap,
+    api: Arc<CommitmentsApiInner>,
+    payload: JsonPayload,
+) -> Result<Json<JsonResponse>, CommitmentError> {
     match payload.method.as_str() {
         GET_VERSION_METHOD => {
             let version_string = format!("bolt-sidecar-v{CARGO_PKG_VERSION}");
@@ -54,11 +85,41 @@ pub async fn rpc_entrypoint(
             Ok(Json(response))
         }
 
+        GET_SLOT_INFO_METHOD => {
+            let Some(slot) = payload.params.first().and_then(Value::as_u64) else {
+                return Err(RejectionError::ValidationFailed("Bad params".to_string()).into());
+            };
+
+            let slot_info = api.get_slot_info(slot).await?;
+            let response = JsonResponse {
+                id: payload.id,
+                result: serde_json::to_value(slot_info).expect("infallible"),
+                ..Default::default()
+            };
+            Ok(Json(response))
+        }
+
+        GET_COMMITMENT_STATUS_METHOD => {
+            let (Some(slot), Some(tx_hash)) = (
+                payload.params.first().and_then(Value::as_u64),
+                payload.params.get(1).and_then(|p| serde_json::from_value(p.clone()).ok()),
+            ) else {
+                return Err(RejectionError::ValidationFailed("Bad params".to_string()).into());
+            };
+
+            let status = api.get_commitment_status(slot, tx_hash).await?;
+            let response = JsonResponse {
+                id: payload.id,
+                result: serde_json::to_value(status).expect("infallible"),
+                ..Default::default()
+            };
+            Ok(Json(response))
+        }
+
         REQUEST_INCLUSION_METHOD => {
-            // Validate the authentication header and extract the signer and signature
-            let (signer, signature) = auth_from_headers(&headers).inspect_err(|e| {
-                error!("Failed to extract signature from headers: {:?}", e);
-            })?;
+            // Requests made within a session are authorized by it, others must be signed
+            let session = session_from_headers(&headers)?;
+            let response_mode = response_mode_from_headers(&headers)?;
 
             let Some(request_json) = payload.params.first().cloned() else {
                 return Err(RejectionError::ValidationFailed("Bad params".to_string()).into());
@@ -69,12 +130,80 @@ pub async fn rpc_entrypoint(
                 .map_err(|e| RejectionError::ValidationFailed(e.to_string()))
                 .inspect_err(|e| error!("Failed to parse inclusion request: {:?}", e))?;
 
-            debug!(?inclusion_request, "New inclusion request");
+            if !inclusion_request.validate_slot_range() {
+                return Err(
+                    RejectionError::ValidationFailed("Invalid slot range".to_string()).into()
+                );
+            }
 
-            // Set the signature here for later processing
-            inclusion_request.set_signature(signature);
+            debug!(?inclusion_request, "New inclusion request");
 
             let digest = inclusion_request.digest();
+            if let Some((signer, session_id)) = session {
+                api.authorize_session(signer, session_id).inspect_err(|e| {
+                    error!(%signer, "Unauthorized session request: {:?}", e);
+                })?;
+
+                inclusion_request.set_signer(signer);
+                // The session identifier is a bearer credential, so it is never logged
+                info!(?signer, %digest, "New inclusion request received in session");
+            } else {
+                // Validate the authentication header and extract the signer and signature
+                let (signer, signature) = auth_from_headers(&headers).inspect_err(|e| {
+                    error!("Failed to extract signature from headers: {:?}", e);
+                })?;
+
+                // Set the signature here for later processing
+                inclusion_request.set_signature(signature);
+
+                let recovered_signer = inclusion_request.recover(&signature)?;
+
+                if recovered_signer != signer {
+                    error!(
+                        %recovered_signer,
+                        %signer,
+                        "Recovered signer does not match the provided signer"
+                    );
+
+                    return Err(CommitmentError::InvalidSignature(SignatureError));
+                }
+
+                // Set the request signer
+                inclusion_request.set_signer(recovered_signer);
+
+                info!(signer = ?recovered_signer, %digest, "New valid inclusion request received");
+            }
+
+            let inclusion_commitment = api.request_inclusion(inclusion_request).await?;
+            let result = match response_mode {
+                ResponseMode::Full => serde_json::to_value(inclusion_commitment),
+                ResponseMode::Compact => serde_json::to_value(inclusion_commitment.receipt()),
+            };
+
+            // Create the JSON-RPC response
+            let response = JsonResponse {
+                id: payload.id,
+                result: result.expect("infallible"),
+                ..Default::default()
+            };
+
+            Ok(Json(response))
+        }
+        RESERVE_INCLUSION_METHOD => {
+            // Validate the authentication header and extract the signer and signature
+            let (signer, signature) = auth_from_headers(&headers).inspect_err(|e| {
+                error!("Failed to extract signature from headers: {:?}", e);
+            })?;
+
+            let Some(request_json) = payload.params.first().cloned() else {
+                return Err(RejectionError::ValidationFailed("Bad params".to_string()).into());
+            };
+
+            let reserve_request: ReserveInclusionRequest = serde_json::from_value(request_json)
+                .map_err(|e| RejectionError::ValidationFailed(e.to_string()))
+                .inspect_err(|e| error!("Failed to parse reservation request: {:?}", e))?;
+
+            let digest = reserve_request.digest();
             let recovered_signer = signature.recover_address_from_prehash(&digest)?;
 
             if recovered_signer != signer {
@@ -87,21 +216,121 @@ pub async fn rpc_entrypoint(
                 return Err(CommitmentError::InvalidSignature(SignatureError));
             }
 
-            // Set the request signer
-            inclusion_request.set_signer(recovered_signer);
+            info!(signer = ?recovered_signer, %digest, "New inclusion reservation received");
+            let reservation = api.reserve_inclusion(reserve_request, signer, signature).await?;
 
-            info!(signer = ?recovered_signer, %digest, "New valid inclusion request received");
-            let inclusion_commitment = api.request_inclusion(inclusion_request).await?;
+            let response = JsonResponse {
+                id: payload.id,
+                result: serde_json::to_value(reservation).expect("infallible"),
+                ..Default::default()
+            };
+            Ok(Json(response))
+        }
+
+        OPEN_SESSION_METHOD => {
+            // Validate the authentication header and extract the signer and signature
+            let (signer, signature) = auth_from_headers(&headers).inspect_err(|e| {
+                error!("Failed to extract signature from headers: {:?}", e);
+            })?;
+
+            let Some(request_json) = payload.params.first().cloned() else {
+                return Err(RejectionError::ValidationFailed("Bad params".to_string()).into());
+            };
+
+            let session_request: OpenSessionRequest = serde_json::from_value(request_json)
+                .map_err(|e| RejectionError::ValidationFailed(e.to_string()))
+                .inspect_err(|e| error!("Failed to parse session request: {:?}", e))?;
+
+            let digest = session_request.digest(&api.session_domain());
+            let recovered_signer = signature.recover_address_from_prehash(&digest)?;
+
+            if recovered_signer != signer {
+                error!(
+                    %recovered_signer,
+                    %signer,
+                    "Recovered signer does not match the provided signer"
+                );
+
+                return Err(CommitmentError::InvalidSignature(SignatureError));
+            }
+
+            info!(%signer, nonce = session_request.nonce, "New session request received");
+            let session = api.open_session(session_request, signer).await?;
+
+            let response = JsonResponse {
+                id: payload.id,
+                result: serde_json::to_value(session).expect("infallible"),
+                ..Default::default()
+            };
+            Ok(Json(response))
+        }
+
+        SUBMIT_BODY_METHOD => {
+            let Some(request_json) = payload.params.first().cloned() else {
+                return Err(RejectionError::ValidationFailed("Bad params".to_string()).into());
+            };
+
+            let submit_request: SubmitBodyRequest = serde_json::from_value(request_json)
+                .map_err(|e| RejectionError::ValidationFailed(e.to_string()))
+                .inspect_err(|e| error!("Failed to parse body submission: {:?}", e))?;
+
+            let reservation_id = submit_request.reservation_id;
+            let inclusion_commitment = api.submit_body(submit_request).await.inspect_err(|e| {
+                error!(%reservation_id, "Failed to submit reserved transaction body: {:?}", e);
+            })?;
 
-            // Create the JSON-RPC response
             let response = JsonResponse {
                 id: payload.id,
                 result: serde_json::to_value(inclusion_commitment).expect("infallible"),
                 ..Default::default()
             };
+            Ok(Json(response))
+        }
+
+        ROTATE_SIGNER_METHOD => {
+            api.authorize_admin(&headers).inspect_err(|e| {
+                error!("Unauthorized admin request: {:?}", e);
+            })?;
 
+            let Some(request_json) = payload.params.first().cloned() else {
+                return Err(RejectionError::ValidationFailed("Bad params".to_string()).into());
+            };
+
+            let source: SignerSource = serde_json::from_value(request_json)
+                .map_err(|e| RejectionError::ValidationFailed(e.to_string()))?;
+
+            info!(?source, "Rotating constraint signer");
+            let pubkeys = api.rotate_signer(source).await.inspect_err(|e| {
+                error!("Failed to rotate constraint signer: {:?}", e);
+            })?;
+
+            let response = JsonResponse {
+                id: payload.id,
+                result: serde_json::to_value(pubkeys).expect("infallible"),
+                ..Default::default()
+            };
+            Ok(Json(response))
+        }
+
+        DRAIN_SLOT_METHOD => {
+            api.authorize_admin(&headers).inspect_err(|e| {
+                error!("Unauthorized admin request: {:?}", e);
+            })?;
+
+            let Some(slot) = payload.params.first().and_then(Value::as_u64) else {
+                return Err(RejectionError::ValidationFailed("Bad params".to_string()).into());
+            };
+
+            info!(slot, "Draining slot commitments");
+            let drained = api.drain_slot(slot).await.inspect_err(|e| {
+                error!(slot, "Failed to drain slot commitments: {:?}", e);
+            })?;
+
+            let response =
+                JsonResponse { id: payload.id, result: Value::from(drained), ..Default::default() };
             Ok(Json(response))
         }
+
         other => {
             error!("Unknown method: {}", other);
             Err(CommitmentError::UnknownMethod)
@@ -123,3 +352,113 @@ pub async fn not_found(req: Request<Body>) -> Html<&'static str> {
 pub async fn status() -> Html<&'static str> {
     Html("OK")
 }
+
+/// Readiness handler. Returns `503 Service Unavailable` while the sidecar is not
+/// accepting new commitments, e.g. when the engine API circuit breaker is open.
+#[instrument(skip_all, name = "GET /ready")]
+pub async fn ready(
+    State(api): State<Arc<CommitmentsApiInner>>,
+) -> (StatusCode, Html<&'static str>) {
+    if api.is_ready() {
+        (StatusCode::OK, Html("OK"))
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, Html("Engine API unavailable"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{marker::PhantomData, num::NonZero, sync::Arc};
+
+    use alloy::{
+        primitives::{Signature, B256},
+        signers::{local::PrivateKeySigner, Signer},
+    };
+    use axum::{extract::State, http::HeaderMap, Json};
+    use axum_extra::extract::WithRejection;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+    use serde_json::json;
+    use tokio::sync::mpsc;
+
+    use crate::{
+        api::commitments::{
+            jsonrpc::JsonPayload,
+            server::CommitmentsApiInner,
+            spec::{CommitmentError, SIGNATURE_HEADER},
+        },
+        common::circuit_breaker::CircuitBreaker,
+        config::limits::LimitsOpts,
+        primitives::{commitment::ECDSASignatureExt, ReserveInclusionRequest},
+    };
+
+    use super::rpc_entrypoint;
+
+    #[test]
+    fn test_duplicate_rejection_metric() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        let (events_tx, _events) = mpsc::channel(1);
+        let api = Arc::new(CommitmentsApiInner::new(
+            events_tx,
+            LimitsOpts::default(),
+            CircuitBreaker::default(),
+            NonZero::new(1).unwrap(),
+        ));
+
+        // The recorder is thread-local, so the handler must run on the current thread
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        metrics::with_local_recorder(&recorder, || {
+            runtime.block_on(async {
+                let signer = PrivateKeySigner::random();
+                let request = ReserveInclusionRequest {
+                    slot: 12,
+                    tx_hash: B256::random(),
+                    gas_limit: 21_000,
+                };
+                let signature = signer.sign_hash(&request.digest()).await.unwrap();
+                let signature = Signature::try_from(signature.as_bytes().as_ref()).unwrap();
+
+                let mut headers = HeaderMap::new();
+                let auth = format!("{}:{}", signer.address(), signature.to_hex());
+                headers.insert(SIGNATURE_HEADER, auth.parse().unwrap());
+
+                let payload = JsonPayload {
+                    jsonrpc: "2.0".to_string(),
+                    method: "bolt_reserveInclusion".to_string(),
+                    id: Some(json!(1)),
+                    params: vec![serde_json::to_value(&request).unwrap()],
+                };
+
+                // The same reservation is only accepted once
+                for expected_duplicate in [false, true] {
+                    let response = rpc_entrypoint(
+                        headers.clone(),
+                        State(api.clone()),
+                        WithRejection(Json(payload.clone()), PhantomData),
+                    )
+                    .await;
+
+                    assert_eq!(
+                        matches!(response, Err(CommitmentError::Duplicate)),
+                        expected_duplicate
+                    );
+                }
+            })
+        });
+
+        let rejected =
+            snapshotter.snapshot().into_vec().into_iter().find_map(|(key, _, _, value)| {
+                let key = key.key();
+                let is_duplicate = key.name() == "bolt_sidecar_commitments_rejected" &&
+                    key.labels().any(|l| l.key() == "reason" && l.value() == "duplicate");
+
+                match value {
+                    DebugValue::Counter(count) if is_duplicate => Some(count),
+                    _ => None,
+                }
+            });
+
+        assert_eq!(rejected, Some(1));
+    }
+}
@@ -1,13 +1,23 @@
 use std::str::FromStr;
 
-use alloy::primitives::{Address, Signature};
-use axum::http::HeaderMap;
+use alloy::primitives::{uint, Address, Signature, B256, U256};
+use axum::http::{header::AUTHORIZATION, HeaderMap};
 
 use crate::primitives::commitment::SignatureError;
 
-use super::spec::{CommitmentError, SIGNATURE_HEADER};
+use super::spec::{
+    CommitmentError, ResponseMode, RESPONSE_MODE_HEADER, SESSION_HEADER, SIGNATURE_HEADER,
+};
+
+/// Half of the secp256k1 curve order. Signatures with a higher `s` value are malleable, as
+/// `(r, n - s)` with the opposite parity is also valid for the same message and signer.
+const SECP256K1N_HALF: U256 =
+    uint!(0x7FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF5D576E7357A4501DDFE92F46681B20A0_U256);
 
 /// Extracts the signature ([SIGNATURE_HEADER]) from the HTTP headers.
+///
+/// Only canonical (low-S) signatures are accepted, so that a single request can't be
+/// signed in two different ways.
 #[inline]
 pub fn auth_from_headers(headers: &HeaderMap) -> Result<(Address, Signature), CommitmentError> {
     let auth = headers.get(SIGNATURE_HEADER).ok_or(CommitmentError::NoSignature)?;
@@ -24,9 +34,55 @@ pub fn auth_from_headers(headers: &HeaderMap) -> Result<(Address, Signature), Co
     let sig =
         Signature::from_str(sig).map_err(|_| CommitmentError::InvalidSignature(SignatureError))?;
 
+    if sig.s() > SECP256K1N_HALF {
+        return Err(CommitmentError::InvalidSignature(SignatureError));
+    }
+
     Ok((address, sig))
 }
 
+/// Extracts the signer and the session identifier ([SESSION_HEADER]) from the HTTP headers,
+/// if the request is made within a session.
+#[inline]
+pub fn session_from_headers(
+    headers: &HeaderMap,
+) -> Result<Option<(Address, B256)>, CommitmentError> {
+    let Some(session) = headers.get(SESSION_HEADER) else {
+        return Ok(None);
+    };
+
+    let session = session.to_str().map_err(|_| CommitmentError::MalformedHeader)?;
+    let (address, session_id) = session.split_once(':').ok_or(CommitmentError::MalformedHeader)?;
+
+    let address = Address::from_str(address).map_err(|_| CommitmentError::MalformedHeader)?;
+    let session_id = B256::from_str(session_id).map_err(|_| CommitmentError::MalformedHeader)?;
+
+    Ok(Some((address, session_id)))
+}
+
+/// Extracts the response verbosity ([RESPONSE_MODE_HEADER]) from the HTTP headers, defaulting
+/// to the full commitment if not set.
+#[inline]
+pub fn response_mode_from_headers(headers: &HeaderMap) -> Result<ResponseMode, CommitmentError> {
+    let Some(mode) = headers.get(RESPONSE_MODE_HEADER) else {
+        return Ok(ResponseMode::Full);
+    };
+
+    match mode.to_str().map_err(|_| CommitmentError::MalformedHeader)? {
+        "full" => Ok(ResponseMode::Full),
+        "compact" => Ok(ResponseMode::Compact),
+        _ => Err(CommitmentError::MalformedHeader),
+    }
+}
+
+/// Extracts the bearer token of the `Authorization` header, used to authenticate admin methods.
+#[inline]
+pub fn bearer_token_from_headers(headers: &HeaderMap) -> Result<&str, CommitmentError> {
+    let auth = headers.get(AUTHORIZATION).ok_or(CommitmentError::Unauthorized)?;
+    let auth = auth.to_str().map_err(|_| CommitmentError::MalformedHeader)?;
+    auth.strip_prefix("Bearer ").ok_or(CommitmentError::MalformedHeader)
+}
+
 #[cfg(test)]
 mod test {
     use alloy::{
@@ -54,4 +110,31 @@ mod test {
         assert_eq!(signature, Signature::try_from(expected_sig.as_bytes().as_ref()).unwrap());
         assert_eq!(address, addr);
     }
+
+    #[tokio::test]
+    async fn test_high_s_signature_rejected() {
+        // The secp256k1 curve order
+        let n = uint!(0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141_U256);
+
+        let hash = TxHash::random();
+        let signer = PrivateKeySigner::random();
+        let addr = signer.address();
+
+        let sig = signer.sign_hash(&hash).await.unwrap();
+        assert!(sig.s() <= SECP256K1N_HALF);
+
+        // Malleate the signature: (r, n - s) with the opposite parity
+        let mut high_s = sig.as_bytes();
+        high_s[32..64].copy_from_slice(&(n - sig.s()).to_be_bytes::<32>());
+        high_s[64] = if high_s[64] == 27 { 28 } else { 27 };
+
+        let mut headers = HeaderMap::new();
+        headers
+            .insert(SIGNATURE_HEADER, format!("{addr}:{}", high_s.encode_hex()).parse().unwrap());
+
+        assert!(matches!(
+            auth_from_headers(&headers),
+            Err(CommitmentError::InvalidSignature(SignatureError))
+        ));
+    }
 }
@@ -36,13 +36,30 @@ impl JsonResponse {
             jsonrpc: "2.0".to_string(),
             id: None,
             result: Value::Null,
-            error: Some(JsonError { code, message }),
+            error: Some(JsonError { code, message, data: None }),
         }
     }
+
+    /// Sets whether the client may retry the request that caused the error.
+    pub fn with_retryable(mut self, retryable: bool) -> Self {
+        if let Some(error) = &mut self.error {
+            error.data = Some(JsonErrorData { retryable });
+        }
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonError {
     pub code: i32,
     pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub data: Option<JsonErrorData>,
+}
+
+/// Additional information about a JSON-RPC error.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonErrorData {
+    /// Whether the error is transient and the same request may succeed if submitted again.
+    pub retryable: bool,
 }
@@ -8,5 +8,7 @@ mod jsonrpc;
 mod middleware;
 /// The commitments-API JSON-RPC server implementation.
 pub mod server;
+/// Sessions authorizing inclusion requests without a signature each.
+mod sessions;
 /// The commitments-API specification and errors.
 pub mod spec;
@@ -1,36 +1,56 @@
 use std::{
+    collections::HashSet,
     fmt,
     future::Future,
+    io,
     net::{SocketAddr, ToSocketAddrs},
+    num::NonZero,
+    path::{Path, PathBuf},
     pin::Pin,
     sync::Arc,
+    time::Duration,
 };
 
+use alloy::primitives::{keccak256, Address, Signature, TxHash, B256};
 use axum::{
+    http::HeaderMap,
     middleware,
     routing::{get, post},
     Router,
 };
+use futures::{future::Shared, FutureExt};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::conn::auto,
+    service::TowerToHyperService,
+};
 use tokio::{
-    net::TcpListener,
-    sync::{mpsc, oneshot},
+    net::{TcpListener, UnixListener},
+    sync::{mpsc, oneshot, Semaphore},
 };
 use tower_http::timeout::TimeoutLayer;
-use tracing::{error, info};
+use tracing::{debug, error, info};
 
 use crate::{
     api::commitments::handlers,
-    config::limits::LimitsOpts,
+    common::circuit_breaker::CircuitBreaker,
+    config::{limits::LimitsOpts, DEFAULT_MAX_INFLIGHT_COMMITMENTS, DEFAULT_RESERVATION_TTL_MS},
     primitives::{
-        commitment::{InclusionCommitment, SignedCommitment},
-        CommitmentRequest, InclusionRequest,
+        commitment::{DigestVersion, InclusionCommitment, SignedCommitment},
+        BlsPublicKey, CommitmentKind, CommitmentRequest, FullTransaction, InclusionRequest,
+        InclusionReservation, OpenSessionRequest, ReserveInclusionRequest, SessionDomain,
+        SessionInfo, SlotInfo, SubmitBodyRequest,
     },
+    signer::{SignerRotationRequest, SignerSource},
+    state::{CommitmentStatus, CommitmentStatuses, ReservationBook, ValidationError},
 };
 
 use super::{
+    headers::bearer_token_from_headers,
     middleware::track_server_metrics,
+    sessions::SessionBook,
     spec,
-    spec::{CommitmentError, CommitmentsApi},
+    spec::{CommitmentError, CommitmentsApi, RejectionError},
 };
 
 /// Event type emitted by the commitments API.
@@ -42,26 +62,209 @@ pub struct CommitmentEvent {
     pub response: oneshot::Sender<Result<SignedCommitment, CommitmentError>>,
 }
 
+/// Request to fetch the [SlotInfo] of a given slot, emitted by the commitments API.
+#[derive(Debug)]
+pub struct SlotInfoRequest {
+    /// The target slot
+    pub slot: u64,
+    /// Channel to send the response to
+    pub response_tx: oneshot::Sender<Result<SlotInfo, ValidationError>>,
+}
+
+/// Request to drain the commitments of an upcoming slot, emitted by the commitments API.
+#[derive(Debug)]
+pub struct DrainSlotRequest {
+    /// The target slot
+    pub slot: u64,
+    /// Channel to send the number of drained constraints to
+    pub response_tx: oneshot::Sender<Result<usize, CommitmentError>>,
+}
+
 /// The inner commitments-API handler that implements the [CommitmentsApi] spec.
 /// Should be wrapped by a [CommitmentsApiServer] JSON-RPC server to handle requests.
 #[derive(Debug)]
 pub struct CommitmentsApiInner {
     /// Event notification channel
     events: mpsc::Sender<CommitmentEvent>,
+    /// Channel to request slot information, if supported
+    slot_info_requests: Option<mpsc::Sender<SlotInfoRequest>>,
+    /// The relay responses to the constraints submissions, if supported
+    commitment_statuses: Option<CommitmentStatuses>,
+    /// The token authenticating admin methods and the channel to request signer rotations,
+    /// if admin methods are enabled
+    admin: Option<(String, mpsc::Sender<SignerRotationRequest>)>,
+    /// Channel to request the draining of a slot, if supported. Requires admin methods.
+    drain_slot_requests: Option<mpsc::Sender<DrainSlotRequest>>,
     /// The sidecar's operating limits that should be exposed in a metadata endpoint
     limits: LimitsOpts,
+    /// Circuit breaker tracking the health of the engine API. When open, new
+    /// commitment requests are rejected.
+    engine_breaker: CircuitBreaker,
+    /// Bounds the number of commitment requests processed concurrently. When no
+    /// permits are left, new requests are rejected as busy.
+    inflight: Semaphore,
+    /// Pending reservations of transactions committed to by hash, waiting for their body.
+    reservations: ReservationBook,
+    /// Open sessions, authorizing inclusion requests on behalf of their signer.
+    sessions: SessionBook,
+    /// The domain of the sessions opened with the sidecar.
+    session_domain: SessionDomain,
+    /// The commitment kinds accepted by the sidecar.
+    allowed_kinds: Vec<CommitmentKind>,
 }
 
 impl CommitmentsApiInner {
-    /// Creates a new instance of the commitments API handler.
-    pub fn new(events: mpsc::Sender<CommitmentEvent>, limits: LimitsOpts) -> Self {
-        Self { events, limits }
+    /// Creates a new instance of the commitments API handler, processing at most
+    /// `max_inflight` commitment requests at a time.
+    pub fn new(
+        events: mpsc::Sender<CommitmentEvent>,
+        limits: LimitsOpts,
+        engine_breaker: CircuitBreaker,
+        max_inflight: NonZero<usize>,
+    ) -> Self {
+        Self {
+            events,
+            slot_info_requests: None,
+            commitment_statuses: None,
+            admin: None,
+            drain_slot_requests: None,
+            limits,
+            engine_breaker,
+            inflight: Semaphore::new(max_inflight.get()),
+            reservations: ReservationBook::new(
+                Duration::from_millis(DEFAULT_RESERVATION_TTL_MS),
+                limits,
+            ),
+            sessions: SessionBook::new(),
+            session_domain: SessionDomain::default(),
+            allowed_kinds: vec![CommitmentKind::Inclusion, CommitmentKind::TopOfBlock],
+        }
+    }
+
+    /// Sets the domain of the sessions, signed along with their authorization.
+    pub fn with_session_domain(mut self, domain: SessionDomain) -> Self {
+        self.session_domain = domain;
+        self
+    }
+
+    /// Sets the commitment kinds accepted by the sidecar. Requests for other kinds are
+    /// rejected before being processed.
+    pub fn with_allowed_kinds(mut self, allowed_kinds: Vec<CommitmentKind>) -> Self {
+        self.allowed_kinds = allowed_kinds;
+        self
+    }
+
+    /// Sets the book of the inclusion reservations, shared with the execution state so that
+    /// they are counted in the slot limits.
+    pub fn with_reservations(mut self, reservations: ReservationBook) -> Self {
+        self.reservations = reservations;
+        self
+    }
+
+    /// Sets the channel used to request slot information.
+    pub fn with_slot_info_requests(mut self, requests: mpsc::Sender<SlotInfoRequest>) -> Self {
+        self.slot_info_requests = Some(requests);
+        self
+    }
+
+    /// Sets the store of the relay responses to the constraints submissions.
+    pub fn with_commitment_statuses(mut self, statuses: CommitmentStatuses) -> Self {
+        self.commitment_statuses = Some(statuses);
+        self
+    }
+
+    /// Enables the admin methods, authenticated with the given token, sending signer
+    /// rotations to the given channel.
+    pub fn with_admin(
+        mut self,
+        token: String,
+        signer_rotation_requests: mpsc::Sender<SignerRotationRequest>,
+    ) -> Self {
+        self.admin = Some((token, signer_rotation_requests));
+        self
+    }
+
+    /// Sets the channel used to request the draining of a slot.
+    pub fn with_drain_slot_requests(mut self, requests: mpsc::Sender<DrainSlotRequest>) -> Self {
+        self.drain_slot_requests = Some(requests);
+        self
+    }
+
+    /// Checks that the request is authenticated with the admin token. If admin methods are
+    /// disabled, they are reported as unknown.
+    pub fn authorize_admin(&self, headers: &HeaderMap) -> Result<(), CommitmentError> {
+        let Some((token, _)) = &self.admin else {
+            return Err(CommitmentError::UnknownMethod);
+        };
+
+        // Compare the hashes so that the comparison time doesn't depend on the token
+        let provided = bearer_token_from_headers(headers)?;
+        if keccak256(provided) != keccak256(token) {
+            return Err(CommitmentError::Unauthorized);
+        }
+
+        Ok(())
+    }
+
+    /// Rotates the constraint signer to the keys of the given source, returning their public
+    /// keys. Callers must be authorized with [Self::authorize_admin] first.
+    pub async fn rotate_signer(
+        &self,
+        source: SignerSource,
+    ) -> Result<HashSet<BlsPublicKey>, CommitmentError> {
+        let Some((_, requests)) = &self.admin else {
+            return Err(CommitmentError::UnknownMethod);
+        };
+
+        let (response_tx, response_rx) = oneshot::channel();
+        requests
+            .send(SignerRotationRequest { source, response_tx })
+            .await
+            .map_err(|_| CommitmentError::Internal)?;
+
+        Ok(response_rx.await.map_err(|_| CommitmentError::Internal)??)
+    }
+
+    /// Drains the commitments of the given upcoming slot, and the ones of later slots depending
+    /// on them, returning the number of constraints removed. Callers must be authorized with
+    /// [Self::authorize_admin] first.
+    pub async fn drain_slot(&self, slot: u64) -> Result<usize, CommitmentError> {
+        let Some(requests) = &self.drain_slot_requests else {
+            return Err(CommitmentError::UnknownMethod);
+        };
+
+        let (response_tx, response_rx) = oneshot::channel();
+        requests
+            .send(DrainSlotRequest { slot, response_tx })
+            .await
+            .map_err(|_| CommitmentError::Internal)?;
+
+        response_rx.await.map_err(|_| CommitmentError::Internal)?
+    }
+
+    /// Checks that the session with the given identifier is open and was opened by the signer.
+    pub fn authorize_session(
+        &self,
+        signer: Address,
+        session_id: B256,
+    ) -> Result<(), CommitmentError> {
+        self.sessions.authorize(session_id, signer)
+    }
+
+    /// Returns the domain of the sessions, signed along with their authorization.
+    pub fn session_domain(&self) -> SessionDomain {
+        self.session_domain
     }
 
     /// Returns the operating limits for the sidecar.
     pub fn limits(&self) -> LimitsOpts {
         self.limits
     }
+
+    /// Returns `true` if the sidecar is ready to accept new commitments.
+    pub fn is_ready(&self) -> bool {
+        !self.engine_breaker.is_open()
+    }
 }
 
 #[async_trait::async_trait]
@@ -70,6 +273,20 @@ impl CommitmentsApi for CommitmentsApiInner {
         &self,
         inclusion_request: InclusionRequest,
     ) -> Result<InclusionCommitment, CommitmentError> {
+        let kind = inclusion_request.kind();
+        if !self.allowed_kinds.contains(&kind) {
+            return Err(CommitmentError::UnsupportedKind(kind));
+        }
+
+        if !self.is_ready() {
+            return Err(CommitmentError::EngineUnavailable);
+        }
+
+        // The permit is held until the commitment is returned
+        let Ok(_permit) = self.inflight.try_acquire() else {
+            return Err(CommitmentError::Busy);
+        };
+
         let (response_tx, response_rx) = oneshot::channel();
 
         let event = CommitmentEvent {
@@ -81,20 +298,156 @@ impl CommitmentsApi for CommitmentsApiInner {
 
         response_rx.await.map_err(|_| CommitmentError::Internal)?.map(|c| c.into())
     }
+
+    async fn get_slot_info(&self, slot: u64) -> Result<SlotInfo, CommitmentError> {
+        let Some(requests) = &self.slot_info_requests else {
+            return Err(CommitmentError::UnknownMethod);
+        };
+
+        let (response_tx, response_rx) = oneshot::channel();
+        requests
+            .send(SlotInfoRequest { slot, response_tx })
+            .await
+            .map_err(|_| CommitmentError::Internal)?;
+
+        match response_rx.await.map_err(|_| CommitmentError::Internal)? {
+            Ok(slot_info) => Ok(slot_info),
+            // The state of past slots is pruned
+            Err(ValidationError::SlotTooLow(_)) => {
+                Err(CommitmentError::NotFound(format!("Slot {slot}")))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn get_commitment_status(
+        &self,
+        slot: u64,
+        tx_hash: TxHash,
+    ) -> Result<CommitmentStatus, CommitmentError> {
+        let Some(statuses) = &self.commitment_statuses else {
+            return Err(CommitmentError::UnknownMethod);
+        };
+
+        // Commitments are only known here once their constraints have been submitted
+        statuses
+            .get(slot, tx_hash)
+            .ok_or_else(|| CommitmentError::NotFound(format!("Commitment to {tx_hash}")))
+    }
+
+    async fn reserve_inclusion(
+        &self,
+        request: ReserveInclusionRequest,
+        signer: Address,
+        signature: Signature,
+    ) -> Result<InclusionReservation, CommitmentError> {
+        if !self.is_ready() {
+            return Err(CommitmentError::EngineUnavailable);
+        }
+
+        // The reservation must also fit next to the commitments of the slot, if they are
+        // known. The validation of the body is still authoritative once it's submitted.
+        if self.slot_info_requests.is_some() {
+            let slot_info = self.get_slot_info(request.slot).await?;
+            if slot_info.commitments_count >= slot_info.max_commitments {
+                return Err(ValidationError::MaxCommitmentsReachedForSlot(
+                    request.slot,
+                    slot_info.max_commitments,
+                )
+                .into());
+            }
+            if request.gas_limit > slot_info.remaining_gas {
+                let max_gas = self.limits.max_committed_gas_per_slot.get();
+                return Err(
+                    ValidationError::MaxCommittedGasReachedForSlot(request.slot, max_gas).into()
+                );
+            }
+        }
+
+        let reservation_id = self.reservations.reserve(request, signer, signature)?;
+        let expires_in_ms = self.reservations.ttl().as_millis() as u64;
+
+        Ok(InclusionReservation { reservation_id, expires_in_ms })
+    }
+
+    async fn submit_body(
+        &self,
+        request: SubmitBodyRequest,
+    ) -> Result<InclusionCommitment, CommitmentError> {
+        let tx = FullTransaction::decode_enveloped(request.tx.as_ref())
+            .map_err(|e| RejectionError::ValidationFailed(e.to_string()))?;
+
+        let reservation = self.reservations.take(request.reservation_id, &tx)?;
+
+        // The reservation signature is over the same digest as the inclusion request
+        let inclusion_request = InclusionRequest {
+            slot: reservation.request.slot,
+            txs: vec![tx],
+            top: false,
+            reverting_tx_hashes: Vec::new(),
+            max_slot: None,
+            webhook: None,
+            digest_version: DigestVersion::V0,
+            signature: Some(reservation.signature),
+            signer: Some(reservation.signer),
+        };
+
+        self.request_inclusion(inclusion_request).await
+    }
+
+    async fn open_session(
+        &self,
+        request: OpenSessionRequest,
+        signer: Address,
+    ) -> Result<SessionInfo, CommitmentError> {
+        self.sessions.open(request, signer)
+    }
 }
 
+/// The shutdown signal of the server, shared between its transports.
+type ShutdownSignal = Shared<Pin<Box<dyn Future<Output = ()> + Send>>>;
+
 /// The outer commitments-API JSON-RPC server that wraps the [CommitmentsApiInner] handler.
+///
+/// The server is always reachable over HTTP, and optionally over an IPC socket: in that
+/// case the same JSON-RPC API is served over HTTP on a Unix domain socket.
 pub struct CommitmentsApiServer {
     /// The address to bind the server to. This will be updated
     /// with the actual address after the server is started.
     addr: SocketAddr,
+    /// The path of the IPC socket to bind the server to, if enabled.
+    ipc_path: Option<PathBuf>,
     /// The shutdown signal.
     signal: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+    /// Circuit breaker tracking the health of the engine API.
+    engine_breaker: CircuitBreaker,
+    /// The maximum number of commitment requests processed concurrently.
+    max_inflight: NonZero<usize>,
+    /// Channel to request slot information from the driver, if supported.
+    slot_info_requests: Option<mpsc::Sender<SlotInfoRequest>>,
+    /// The relay responses to the constraints submissions recorded by the driver, if supported.
+    commitment_statuses: Option<CommitmentStatuses>,
+    /// The admin token and the channel to request signer rotations from the driver, if
+    /// admin methods are enabled.
+    admin: Option<(String, mpsc::Sender<SignerRotationRequest>)>,
+    /// Channel to request the draining of a slot from the driver, if supported.
+    drain_slot_requests: Option<mpsc::Sender<DrainSlotRequest>>,
+    /// The book of the inclusion reservations, shared with the driver if set.
+    reservations: Option<ReservationBook>,
+    /// The commitment kinds accepted by the sidecar.
+    allowed_kinds: Vec<CommitmentKind>,
+    /// The domain of the sessions opened with the sidecar.
+    session_domain: SessionDomain,
 }
 
 impl fmt::Debug for CommitmentsApiServer {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("CommitmentsApiServer").field("addr", &self.addr).finish()
+        f.debug_struct("CommitmentsApiServer")
+            .field("addr", &self.addr)
+            .field("ipc_path", &self.ipc_path)
+            .field("max_inflight", &self.max_inflight)
+            .field("allowed_kinds", &self.allowed_kinds)
+            .finish()
     }
 }
 
@@ -103,9 +456,19 @@ impl CommitmentsApiServer {
     pub fn new<A: ToSocketAddrs>(addr: A) -> Self {
         Self {
             addr: addr.to_socket_addrs().unwrap().next().unwrap(),
+            ipc_path: None,
             signal: Some(Box::pin(async {
                 let _ = tokio::signal::ctrl_c().await;
             })),
+            engine_breaker: CircuitBreaker::default(),
+            max_inflight: NonZero::new(DEFAULT_MAX_INFLIGHT_COMMITMENTS).expect("Valid non-zero"),
+            slot_info_requests: None,
+            commitment_statuses: None,
+            admin: None,
+            drain_slot_requests: None,
+            reservations: None,
+            allowed_kinds: vec![CommitmentKind::Inclusion, CommitmentKind::TopOfBlock],
+            session_domain: SessionDomain::default(),
         }
     }
 
@@ -117,13 +480,115 @@ impl CommitmentsApiServer {
     {
         Self {
             addr: addr.to_socket_addrs().unwrap().next().unwrap(),
+            ipc_path: self.ipc_path,
             signal: Some(Box::pin(signal)),
+            engine_breaker: self.engine_breaker,
+            max_inflight: self.max_inflight,
+            slot_info_requests: self.slot_info_requests,
+            commitment_statuses: self.commitment_statuses,
+            admin: self.admin,
+            drain_slot_requests: self.drain_slot_requests,
+            reservations: self.reservations,
+            allowed_kinds: self.allowed_kinds,
+            session_domain: self.session_domain,
         }
     }
 
+    /// Sets the circuit breaker used to reject new commitments when the engine API is unhealthy.
+    pub fn with_engine_breaker(mut self, engine_breaker: CircuitBreaker) -> Self {
+        self.engine_breaker = engine_breaker;
+        self
+    }
+
+    /// Sets the maximum number of commitment requests processed concurrently.
+    pub fn with_max_inflight_commitments(mut self, max_inflight: NonZero<usize>) -> Self {
+        self.max_inflight = max_inflight;
+        self
+    }
+
+    /// Sets the channel used to request slot information for `bolt_getSlotInfo`.
+    pub fn with_slot_info_requests(mut self, requests: mpsc::Sender<SlotInfoRequest>) -> Self {
+        self.slot_info_requests = Some(requests);
+        self
+    }
+
+    /// Sets the store of the relay responses served by `bolt_getCommitmentStatus`.
+    pub fn with_commitment_statuses(mut self, statuses: CommitmentStatuses) -> Self {
+        self.commitment_statuses = Some(statuses);
+        self
+    }
+
+    /// Enables the admin methods such as `bolt_admin_rotateSigner`, authenticated with the
+    /// given bearer token. Signer rotations are sent to the driver over the given channel.
+    pub fn with_admin(
+        mut self,
+        token: String,
+        signer_rotation_requests: mpsc::Sender<SignerRotationRequest>,
+    ) -> Self {
+        self.admin = Some((token, signer_rotation_requests));
+        self
+    }
+
+    /// Sets the channel used to request the draining of a slot for `bolt_admin_drainSlot`.
+    /// The method is only available if the admin methods are enabled.
+    pub fn with_drain_slot_requests(mut self, requests: mpsc::Sender<DrainSlotRequest>) -> Self {
+        self.drain_slot_requests = Some(requests);
+        self
+    }
+
+    /// Sets the book of the inclusion reservations made with `bolt_reserveInclusion`, shared
+    /// with the execution state of the driver.
+    pub fn with_reservations(mut self, reservations: ReservationBook) -> Self {
+        self.reservations = Some(reservations);
+        self
+    }
+
+    /// Sets the commitment kinds accepted by the sidecar. By default, all kinds are accepted.
+    pub fn with_allowed_kinds(mut self, allowed_kinds: Vec<CommitmentKind>) -> Self {
+        self.allowed_kinds = allowed_kinds;
+        self
+    }
+
+    /// Sets the domain of the sessions opened with `bolt_openSession`, i.e. the chain ID and
+    /// the commitment signer of the sidecar, so that their authorization can't be replayed
+    /// against other sidecars.
+    pub fn with_session_domain(mut self, domain: SessionDomain) -> Self {
+        self.session_domain = domain;
+        self
+    }
+
+    /// Also serves the JSON-RPC API over an IPC socket at the given path.
+    pub fn with_ipc_path(mut self, ipc_path: impl Into<PathBuf>) -> Self {
+        self.ipc_path = Some(ipc_path.into());
+        self
+    }
+
     /// Runs the JSON-RPC server, sending events to the provided channel.
     pub async fn run(&mut self, events_tx: mpsc::Sender<CommitmentEvent>, limits: LimitsOpts) {
-        let api = Arc::new(CommitmentsApiInner::new(events_tx, limits));
+        let mut api = CommitmentsApiInner::new(
+            events_tx,
+            limits,
+            self.engine_breaker.clone(),
+            self.max_inflight,
+        )
+        .with_allowed_kinds(self.allowed_kinds.clone())
+        .with_session_domain(self.session_domain);
+        if let Some(reservations) = self.reservations.clone() {
+            api = api.with_reservations(reservations);
+        }
+        if let Some(requests) = self.slot_info_requests.clone() {
+            api = api.with_slot_info_requests(requests);
+        }
+        if let Some(statuses) = self.commitment_statuses.clone() {
+            api = api.with_commitment_statuses(statuses);
+        }
+        if let Some((token, requests)) = self.admin.clone() {
+            api = api.with_admin(token, requests);
+        }
+        if let Some(requests) = self.drain_slot_requests.clone() {
+            api = api.with_drain_slot_requests(requests);
+        }
+        let api = Arc::new(api);
 
         let router = make_router(api);
 
@@ -140,7 +605,21 @@ impl CommitmentsApiServer {
 
         info!("Commitments RPC server bound to {addr}");
 
-        let signal = self.signal.take().expect("Signal not set");
+        let signal = self.signal.take().expect("Signal not set").shared();
+
+        if let Some(ipc_path) = &self.ipc_path {
+            let listener = match bind_ipc(ipc_path) {
+                Ok(listener) => listener,
+                Err(err) => {
+                    error!(?err, "Failed to bind Commitments API IPC socket");
+                    panic!("Failed to bind Commitments API IPC socket");
+                }
+            };
+
+            info!("Commitments RPC server bound to IPC socket {}", ipc_path.display());
+
+            tokio::spawn(serve_ipc(listener, router.clone(), signal.clone()));
+        }
 
         tokio::spawn(async move {
             if let Err(err) = axum::serve(listener, router).with_graceful_shutdown(signal).await {
@@ -153,6 +632,50 @@ impl CommitmentsApiServer {
     pub fn local_addr(&self) -> SocketAddr {
         self.addr
     }
+
+    /// Returns the path of the IPC socket the server is listening on, if enabled.
+    pub fn ipc_path(&self) -> Option<&Path> {
+        self.ipc_path.as_deref()
+    }
+}
+
+/// Binds a Unix domain socket at the given path, removing any stale socket
+/// left behind by a previous run.
+fn bind_ipc(path: &Path) -> io::Result<UnixListener> {
+    match std::fs::remove_file(path) {
+        Ok(()) => debug!("Removed stale IPC socket at {}", path.display()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+
+    UnixListener::bind(path)
+}
+
+/// Serves the router over HTTP on the given Unix domain socket until the
+/// shutdown signal resolves.
+async fn serve_ipc(listener: UnixListener, router: Router, signal: ShutdownSignal) {
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    error!(?err, "Failed to accept IPC connection");
+                    continue;
+                }
+            },
+            _ = signal.clone() => break,
+        };
+
+        let service = TowerToHyperService::new(router.clone());
+        tokio::spawn(async move {
+            if let Err(err) = auto::Builder::new(TokioExecutor::new())
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                debug!(?err, "Commitments API IPC connection error");
+            }
+        });
+    }
 }
 
 /// Creates a new [Router]
@@ -164,6 +687,7 @@ fn make_router(state: Arc<CommitmentsApiInner>) -> Router {
     Router::new()
         .route("/", post(handlers::rpc_entrypoint))
         .route("/status", get(handlers::status))
+        .route("/ready", get(handlers::ready))
         .fallback(handlers::not_found)
         .layer(TimeoutLayer::new(spec::MAX_REQUEST_TIMEOUT))
         .route_layer(middleware::from_fn(track_server_metrics))
@@ -172,11 +696,21 @@ fn make_router(state: Arc<CommitmentsApiInner>) -> Router {
 
 #[cfg(test)]
 mod test {
-    use crate::api::commitments::{jsonrpc::JsonResponse, spec::SIGNATURE_HEADER};
-    use alloy::signers::{k256::SecretKey, local::PrivateKeySigner};
+    use crate::api::commitments::{
+        jsonrpc::JsonResponse,
+        spec::{SESSION_HEADER, SIGNATURE_HEADER},
+    };
+    use std::num::NonZero;
+
+    use alloy::{
+        primitives::B256,
+        signers::{k256::SecretKey, local::PrivateKeySigner},
+    };
+    use reqwest::StatusCode;
     use serde_json::json;
 
     use crate::{
+        config::ChainConfig,
         primitives::commitment::ECDSASignatureExt,
         test_util::{create_signed_inclusion_request, default_test_transaction},
     };
@@ -321,4 +855,612 @@ mod test {
 
         assert_eq!(limits, LimitsOpts::default());
     }
+
+    #[tokio::test]
+    async fn test_request_busy_when_saturated() {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut server = CommitmentsApiServer::new("0.0.0.0:0")
+            .with_max_inflight_commitments(NonZero::new(1).unwrap());
+
+        let (events_tx, mut events) = mpsc::channel(1);
+
+        server.run(events_tx, LimitsOpts::default()).await;
+        let url = format!("http://{}", server.local_addr());
+
+        let sk = SecretKey::random(&mut rand::thread_rng());
+        let signer = PrivateKeySigner::from(sk.clone());
+        let tx = default_test_transaction(signer.address(), None);
+        let req = create_signed_inclusion_request(&[tx], &sk, 12).await.unwrap();
+        let sig = req.signature.unwrap().to_hex();
+
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "bolt_requestInclusion",
+            "params": [req]
+        });
+
+        let send_request = {
+            let client = reqwest::Client::new();
+            let header = format!("{}:{}", signer.address(), sig);
+            move || {
+                client
+                    .post(url.clone())
+                    .header(SIGNATURE_HEADER, header.clone())
+                    .json(&payload)
+                    .send()
+            }
+        };
+
+        // The first request holds the only permit until it gets a response
+        let first = tokio::spawn(send_request());
+        let CommitmentEvent { request, response } = events.recv().await.unwrap();
+
+        let busy = send_request().await.unwrap().json::<JsonResponse>().await.unwrap();
+        assert_eq!(busy.error.unwrap().code, -32009);
+
+        let commitment = request.commit_and_sign(&PrivateKeySigner::random()).await.unwrap();
+        response.send(Ok(commitment)).unwrap();
+
+        let first = first.await.unwrap().unwrap().json::<JsonResponse>().await.unwrap();
+        assert!(first.error.is_none());
+
+        // The permit is released once the first request completes
+        let (tx, rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let json = send_request().await.unwrap().json::<JsonResponse>().await.unwrap();
+            let _ = tx.send(json);
+        });
+
+        let CommitmentEvent { request, response } = events.recv().await.unwrap();
+        let commitment = request.commit_and_sign(&PrivateKeySigner::random()).await.unwrap();
+        response.send(Ok(commitment)).unwrap();
+        assert!(rx.await.unwrap().error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_request_disallowed_kind_rejected() {
+        use alloy::signers::Signer;
+
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut server = CommitmentsApiServer::new("0.0.0.0:0")
+            .with_allowed_kinds(vec![CommitmentKind::Inclusion]);
+
+        let (events_tx, mut events) = mpsc::channel(1);
+
+        server.run(events_tx, LimitsOpts::default()).await;
+        let url = format!("http://{}", server.local_addr());
+
+        let sk = SecretKey::random(&mut rand::thread_rng());
+        let signer = PrivateKeySigner::from(sk.clone());
+        let tx = default_test_transaction(signer.address(), None);
+        let req = create_signed_inclusion_request(&[tx], &sk, 12).await.unwrap();
+
+        let address = signer.address();
+        let send_request = move |req: InclusionRequest| {
+            let client = reqwest::Client::new();
+            let url = url.clone();
+            let sig = req.signature.unwrap().to_hex();
+            let header = format!("{address}:{sig}");
+            let payload = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "bolt_requestInclusion",
+                "params": [req]
+            });
+            async move {
+                client
+                    .post(url)
+                    .header(SIGNATURE_HEADER, header)
+                    .json(&payload)
+                    .send()
+                    .await
+                    .unwrap()
+                    .json::<JsonResponse>()
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // The same request at the top of the block, re-signed as the digest changes
+        let mut top_req = req.clone();
+        top_req.top = true;
+        let sig = signer.sign_hash(&top_req.digest()).await.unwrap();
+        top_req.set_signature(Signature::try_from(sig.as_bytes().as_ref()).unwrap());
+
+        let response = send_request(top_req).await;
+        assert_eq!(response.error.unwrap().code, -32012);
+
+        // Plain inclusion is still accepted
+        let (tx, rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let _ = tx.send(send_request(req).await);
+        });
+
+        let CommitmentEvent { request, response } = events.recv().await.unwrap();
+        assert_eq!(request.kind(), CommitmentKind::Inclusion);
+        let commitment = request.commit_and_sign(&PrivateKeySigner::random()).await.unwrap();
+        response.send(Ok(commitment)).unwrap();
+        assert!(rx.await.unwrap().error.is_none());
+    }
+
+    /// Reserves the inclusion of the first transaction of `req` by its hash, and returns
+    /// the raw transaction along with the JSON-RPC response.
+    async fn reserve_inclusion(
+        url: &str,
+        sk: &SecretKey,
+        req: &InclusionRequest,
+    ) -> (Vec<u8>, JsonResponse) {
+        use alloy::{eips::eip2718::Encodable2718, signers::Signer};
+
+        use crate::primitives::TransactionExt;
+
+        let signer = PrivateKeySigner::from(sk.clone());
+        let tx = &req.txs[0];
+        let reserve_request = ReserveInclusionRequest {
+            slot: req.slot,
+            tx_hash: *tx.hash(),
+            gas_limit: tx.gas_limit(),
+        };
+        let sig = signer.sign_hash(&reserve_request.digest()).await.unwrap();
+        let sig = Signature::try_from(sig.as_bytes().as_ref()).unwrap().to_hex();
+
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "bolt_reserveInclusion",
+            "params": [reserve_request]
+        });
+
+        let response = reqwest::Client::new()
+            .post(url)
+            .header(SIGNATURE_HEADER, format!("{}:{}", signer.address(), sig))
+            .json(&payload)
+            .send()
+            .await
+            .unwrap()
+            .json::<JsonResponse>()
+            .await
+            .unwrap();
+
+        (tx.tx.encoded_2718(), response)
+    }
+
+    /// Submits the given raw transaction body for a reservation.
+    async fn submit_body(url: &str, reservation_id: B256, raw_tx: &[u8]) -> JsonResponse {
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "bolt_submitBody",
+            "params": [{ "reservationId": reservation_id, "tx": alloy::hex::encode_prefixed(raw_tx) }]
+        });
+
+        reqwest::Client::new()
+            .post(url)
+            .json(&payload)
+            .send()
+            .await
+            .unwrap()
+            .json::<JsonResponse>()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reserve_and_submit_matching_body() {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut server = CommitmentsApiServer::new("0.0.0.0:0");
+
+        let (events_tx, mut events) = mpsc::channel(1);
+
+        server.run(events_tx, LimitsOpts::default()).await;
+        let url = format!("http://{}", server.local_addr());
+
+        let sk = SecretKey::random(&mut rand::thread_rng());
+        let signer = PrivateKeySigner::from(sk.clone());
+        let tx = default_test_transaction(signer.address(), None);
+        let req = create_signed_inclusion_request(&[tx], &sk, 12).await.unwrap();
+
+        let (raw_tx, response) = reserve_inclusion(&url, &sk, &req).await;
+        assert!(response.error.is_none());
+        let reservation: InclusionReservation = serde_json::from_value(response.result).unwrap();
+
+        let (tx, rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let _ = tx.send(submit_body(&url, reservation.reservation_id, &raw_tx).await);
+        });
+
+        // The submitted body is forwarded as a regular inclusion request, signed by the user
+        let CommitmentEvent { request, response } = events.recv().await.unwrap();
+        let CommitmentRequest::Inclusion(inclusion_request) = &request;
+        assert_eq!(inclusion_request.txs, req.txs);
+        assert_eq!(inclusion_request.signer, Some(signer.address()));
+        let recovered = inclusion_request.recover(&inclusion_request.signature.unwrap()).unwrap();
+        assert_eq!(recovered, signer.address());
+
+        let commitment = request.commit_and_sign(&PrivateKeySigner::random()).await.unwrap();
+        response.send(Ok(commitment)).unwrap();
+        assert!(rx.await.unwrap().error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reserve_and_submit_mismatching_body() {
+        use alloy::eips::eip2718::Encodable2718;
+
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut server = CommitmentsApiServer::new("0.0.0.0:0");
+
+        let (events_tx, _events) = mpsc::channel(1);
+
+        server.run(events_tx, LimitsOpts::default()).await;
+        let url = format!("http://{}", server.local_addr());
+
+        let sk = SecretKey::random(&mut rand::thread_rng());
+        let signer = PrivateKeySigner::from(sk.clone());
+        let tx = default_test_transaction(signer.address(), None);
+        let req = create_signed_inclusion_request(&[tx], &sk, 12).await.unwrap();
+
+        let (_, response) = reserve_inclusion(&url, &sk, &req).await;
+        let reservation: InclusionReservation = serde_json::from_value(response.result).unwrap();
+
+        // A different transaction from the same sender doesn't match the reserved hash
+        let other_tx = default_test_transaction(signer.address(), Some(1));
+        let other = create_signed_inclusion_request(&[other_tx], &sk, 12).await.unwrap();
+        let other_raw_tx = other.txs[0].tx.encoded_2718();
+
+        let response = submit_body(&url, reservation.reservation_id, &other_raw_tx).await;
+        assert_eq!(response.error.unwrap().code, -32011);
+
+        // Unknown reservations are rejected
+        let response = submit_body(&url, B256::random(), &other_raw_tx).await;
+        assert_eq!(response.error.unwrap().code, -32010);
+    }
+
+    #[tokio::test]
+    async fn test_session_authorizes_inclusion_requests() {
+        use alloy::signers::Signer;
+
+        use crate::{
+            primitives::SessionInfo,
+            state::slot_clock::{SystemTimeSource, TimeSource},
+        };
+
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let domain = SessionDomain { chain_id: 1, sidecar: Address::random() };
+        let mut server = CommitmentsApiServer::new("0.0.0.0:0").with_session_domain(domain);
+
+        let (events_tx, mut events) = mpsc::channel(1);
+
+        server.run(events_tx, LimitsOpts::default()).await;
+        let url = format!("http://{}", server.local_addr());
+
+        let sk = SecretKey::random(&mut rand::thread_rng());
+        let signer = PrivateKeySigner::from(sk.clone());
+        let address = signer.address();
+
+        // An authorization for another sidecar doesn't open a session
+        let now = SystemTimeSource.now().as_secs();
+        let session_request = OpenSessionRequest { nonce: 1, issued_at: now, expires_at: now + 60 };
+        let other = SessionDomain { sidecar: Address::random(), ..domain };
+        let sig = signer.sign_hash(&session_request.digest(&other)).await.unwrap();
+        let sig = Signature::try_from(sig.as_bytes().as_ref()).unwrap().to_hex();
+        let response = reqwest::Client::new()
+            .post(&url)
+            .header(SIGNATURE_HEADER, format!("{address}:{sig}"))
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "bolt_openSession",
+                "params": [session_request]
+            }))
+            .send()
+            .await
+            .unwrap()
+            .json::<JsonResponse>()
+            .await
+            .unwrap();
+        assert!(response.error.is_some());
+
+        // Open a session with a single signature
+        let sig = signer.sign_hash(&session_request.digest(&domain)).await.unwrap();
+        let sig = Signature::try_from(sig.as_bytes().as_ref()).unwrap().to_hex();
+
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "bolt_openSession",
+            "params": [session_request]
+        });
+        let response = reqwest::Client::new()
+            .post(&url)
+            .header(SIGNATURE_HEADER, format!("{address}:{sig}"))
+            .json(&payload)
+            .send()
+            .await
+            .unwrap()
+            .json::<JsonResponse>()
+            .await
+            .unwrap();
+        assert!(response.error.is_none());
+        let session: SessionInfo = serde_json::from_value(response.result).unwrap();
+
+        let send_request = |nonce, session_id: B256| {
+            let url = url.clone();
+            let sk = sk.clone();
+            async move {
+                let tx = default_test_transaction(address, Some(nonce));
+                let req = create_signed_inclusion_request(&[tx], &sk, 12).await.unwrap();
+                let payload = json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "bolt_requestInclusion",
+                    "params": [req]
+                });
+
+                reqwest::Client::new()
+                    .post(url)
+                    .header(SESSION_HEADER, format!("{address}:{session_id}"))
+                    .json(&payload)
+                    .send()
+                    .await
+                    .unwrap()
+                    .json::<JsonResponse>()
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // Requests within the session are forwarded on behalf of its signer, unsigned
+        for nonce in 0..2 {
+            let (tx, rx) = oneshot::channel();
+            let request = send_request(nonce, session.session_id);
+            tokio::spawn(async move {
+                let _ = tx.send(request.await);
+            });
+
+            let CommitmentEvent { request, response } = events.recv().await.unwrap();
+            let CommitmentRequest::Inclusion(inclusion_request) = &request;
+            assert_eq!(inclusion_request.signer, Some(address));
+            assert!(inclusion_request.signature.is_none());
+
+            let commitment = request.commit_and_sign(&PrivateKeySigner::random()).await.unwrap();
+            response.send(Ok(commitment)).unwrap();
+            assert!(rx.await.unwrap().error.is_none());
+        }
+
+        // Requests referencing an unknown session are rejected
+        let response = send_request(2, B256::random()).await;
+        assert_eq!(response.error.unwrap().code, -32017);
+    }
+
+    /// Sends a JSON-RPC request over HTTP on the given IPC socket and returns the response.
+    async fn ipc_request(path: &Path, payload: &serde_json::Value) -> JsonResponse {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let body = serde_json::to_vec(payload).unwrap();
+        let head = format!(
+            "POST / HTTP/1.1\r\nhost: localhost\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+            body.len()
+        );
+
+        let mut stream = tokio::net::UnixStream::connect(path).await.unwrap();
+        stream.write_all(head.as_bytes()).await.unwrap();
+        stream.write_all(&body).await.unwrap();
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+
+        let response = String::from_utf8(response).unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").expect("HTTP response");
+        serde_json::from_str(body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_request_metadata_over_ipc() {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let ipc_path =
+            std::env::temp_dir().join(format!("bolt-sidecar-{}.ipc", rand::random::<u64>()));
+        let mut server = CommitmentsApiServer::new("0.0.0.0:0").with_ipc_path(&ipc_path);
+
+        let (events_tx, _) = mpsc::channel(1);
+
+        server.run(events_tx, LimitsOpts::default()).await;
+        let addr = server.local_addr();
+
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "bolt_metadata",
+            "params": []
+        });
+
+        let http_response = reqwest::Client::new()
+            .post(format!("http://{addr}"))
+            .json(&payload)
+            .send()
+            .await
+            .unwrap()
+            .json::<JsonResponse>()
+            .await
+            .unwrap();
+
+        let ipc_response = ipc_request(server.ipc_path().unwrap(), &payload).await;
+
+        // Both transports are served by the same handler
+        assert!(ipc_response.error.is_none());
+        assert_eq!(ipc_response.result, http_response.result);
+
+        let limits: LimitsOpts = serde_json::from_value(ipc_response.result).unwrap();
+        assert_eq!(limits, LimitsOpts::default());
+
+        let _ = std::fs::remove_file(ipc_path);
+    }
+
+    #[tokio::test]
+    async fn test_ready_reflects_engine_breaker() {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let breaker = CircuitBreaker::new(NonZero::new(2).unwrap());
+        let mut server =
+            CommitmentsApiServer::new("0.0.0.0:0").with_engine_breaker(breaker.clone());
+
+        let (events_tx, _) = mpsc::channel(1);
+
+        server.run(events_tx, LimitsOpts::default()).await;
+        let addr = server.local_addr();
+        let ready_url = format!("http://{addr}/ready");
+
+        assert_eq!(reqwest::get(&ready_url).await.unwrap().status(), StatusCode::OK);
+
+        // Repeated engine failures open the breaker
+        breaker.record_failure();
+        breaker.record_failure();
+        assert_eq!(
+            reqwest::get(&ready_url).await.unwrap().status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        // New commitment requests are rejected while the breaker is open
+        let sk = SecretKey::random(&mut rand::thread_rng());
+        let signer = PrivateKeySigner::from(sk.clone());
+        let tx = default_test_transaction(signer.address(), None);
+        let req = create_signed_inclusion_request(&[tx], &sk, 12).await.unwrap();
+        let sig = req.signature.unwrap().to_hex();
+
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "bolt_requestInclusion",
+            "params": [req]
+        });
+
+        let response = reqwest::Client::new()
+            .post(format!("http://{addr}"))
+            .header(SIGNATURE_HEADER, format!("{}:{}", signer.address(), sig))
+            .json(&payload)
+            .send()
+            .await
+            .unwrap()
+            .json::<JsonResponse>()
+            .await
+            .unwrap();
+
+        assert_eq!(response.error.unwrap().code, -32008);
+
+        // A successful engine call closes the breaker again
+        breaker.record_success();
+        assert_eq!(reqwest::get(&ready_url).await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_get_slot_info_unknown_slot() {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let (slot_info_tx, mut slot_info_rx) = mpsc::channel::<SlotInfoRequest>(1);
+        let mut server =
+            CommitmentsApiServer::new("0.0.0.0:0").with_slot_info_requests(slot_info_tx);
+
+        let (events_tx, _) = mpsc::channel(1);
+
+        server.run(events_tx, LimitsOpts::default()).await;
+        let addr = server.local_addr();
+
+        // The state is at slot 10, so earlier slots are gone
+        tokio::spawn(async move {
+            while let Some(request) = slot_info_rx.recv().await {
+                let _ = request.response_tx.send(Err(ValidationError::SlotTooLow(10)));
+            }
+        });
+
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "bolt_getSlotInfo",
+            "params": [5]
+        });
+
+        let response = reqwest::Client::new()
+            .post(format!("http://{addr}"))
+            .json(&payload)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let error = response.json::<JsonResponse>().await.unwrap().error.unwrap();
+        assert_eq!(error.code, -32014);
+        assert_eq!(error.message, "Slot 5 not found");
+        assert!(!error.data.unwrap().retryable);
+    }
+
+    #[tokio::test]
+    async fn test_rotate_signer_requires_admin_token() {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let (rotation_tx, mut rotation_rx) = mpsc::channel::<SignerRotationRequest>(1);
+        let mut server =
+            CommitmentsApiServer::new("0.0.0.0:0").with_admin("secret".to_string(), rotation_tx);
+
+        let (events_tx, _) = mpsc::channel(1);
+
+        server.run(events_tx, LimitsOpts::default()).await;
+        let addr = server.local_addr();
+
+        // The driver loads the new signer and responds with its public keys
+        tokio::spawn(async move {
+            while let Some(request) = rotation_rx.recv().await {
+                let result = request
+                    .source
+                    .load(ChainConfig::mainnet())
+                    .map(|s| s.available_pubkeys())
+                    .map_err(Into::into);
+                let _ = request.response_tx.send(result);
+            }
+        });
+
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+        let source = SignerSource {
+            keystore_path: root.join("test_data/keys"),
+            keystore_secrets_path: root.join("test_data/secrets"),
+        };
+        let new_pubkeys = source.load(ChainConfig::mainnet()).unwrap().available_pubkeys();
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "bolt_admin_rotateSigner",
+            "params": [{
+                "keystorePath": source.keystore_path,
+                "keystoreSecretsPath": source.keystore_secrets_path,
+            }]
+        });
+
+        let client = reqwest::Client::new();
+        let rotate = |token: Option<&str>| {
+            let mut request = client.post(format!("http://{addr}")).json(&payload);
+            if let Some(token) = token {
+                request = request.bearer_auth(token);
+            }
+            request.send()
+        };
+
+        let response = rotate(None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = rotate(Some("wrong")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let error = response.json::<JsonResponse>().await.unwrap().error.unwrap();
+        assert_eq!(error.code, -32015);
+
+        let response = rotate(Some("secret")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let pubkeys: Vec<BlsPublicKey> =
+            serde_json::from_value(response.json::<JsonResponse>().await.unwrap().result).unwrap();
+        assert_eq!(pubkeys, Vec::from_iter(new_pubkeys));
+    }
 }
@@ -0,0 +1,229 @@
+use std::{collections::HashMap, time::Duration};
+
+use alloy::primitives::{Address, B256};
+use parking_lot::Mutex;
+
+use crate::{
+    primitives::{OpenSessionRequest, SessionInfo},
+    state::slot_clock::{SystemTimeSource, TimeSource},
+};
+
+use super::spec::{CommitmentError, RejectionError};
+
+/// The maximum duration of a session, from the time it is authorized.
+pub const MAX_SESSION_DURATION: Duration = Duration::from_secs(60 * 60);
+
+/// The maximum number of sessions open at once for a signer.
+pub const MAX_SESSIONS_PER_SIGNER: usize = 4;
+
+/// The maximum time by which the authorization time of a session may be ahead of the sidecar
+/// clock, to tolerate the clock drift of the clients.
+const MAX_CLOCK_DRIFT: Duration = Duration::from_secs(12);
+
+/// An open session, authorizing requests on behalf of its signer.
+#[derive(Debug, Clone, Copy)]
+struct Session {
+    /// The signer of the session authorization.
+    signer: Address,
+    /// The UNIX timestamp in seconds at which the session expires.
+    expires_at: u64,
+}
+
+/// The book of open sessions.
+///
+/// A session is opened with a single signed [OpenSessionRequest], after which the requests
+/// referencing it are authorized on behalf of its signer until it expires. Each signer's
+/// session nonces must be strictly increasing, so that a session authorization can't be
+/// replayed to reopen a session once it has expired.
+///
+/// The nonces are kept in memory only, so the sessions authorized before the book was
+/// created are rejected: their authorization may have been used already.
+#[derive(Debug)]
+pub struct SessionBook<T = SystemTimeSource> {
+    /// The open sessions, indexed by their identifier.
+    sessions: Mutex<HashMap<B256, Session>>,
+    /// The nonce of the latest session opened by each signer, along with the latest expiry of
+    /// its sessions. Once past, none of its authorizations can be replayed, so it is pruned.
+    nonces: Mutex<HashMap<Address, (u64, u64)>>,
+    /// The UNIX timestamp in seconds at which the book was created.
+    started_at: u64,
+    /// The source of the current time.
+    time: T,
+}
+
+impl SessionBook {
+    /// Creates a new empty session book following the system clock.
+    pub fn new() -> Self {
+        Self::with_time_source(SystemTimeSource)
+    }
+}
+
+impl Default for SessionBook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: TimeSource> SessionBook<T> {
+    /// Creates a new empty session book following the given time source.
+    pub fn with_time_source(time: T) -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+            nonces: Mutex::new(HashMap::new()),
+            started_at: time.now().as_secs(),
+            time,
+        }
+    }
+
+    /// Opens a session for the signer of the request, returning its identifier and expiry.
+    pub fn open(
+        &self,
+        request: OpenSessionRequest,
+        signer: Address,
+    ) -> Result<SessionInfo, CommitmentError> {
+        let now = self.time.now().as_secs();
+        if request.issued_at < self.started_at {
+            let msg = "Session authorized before the sidecar started".to_string();
+            return Err(RejectionError::ValidationFailed(msg).into());
+        }
+        if request.issued_at > now + MAX_CLOCK_DRIFT.as_secs() {
+            let msg = "Session authorized in the future".to_string();
+            return Err(RejectionError::ValidationFailed(msg).into());
+        }
+        if request.expires_at <= now {
+            return Err(RejectionError::ValidationFailed("Session already expired".into()).into());
+        }
+        if request.expires_at > request.issued_at + MAX_SESSION_DURATION.as_secs() {
+            let msg = format!("Session lasts longer than {}s", MAX_SESSION_DURATION.as_secs());
+            return Err(RejectionError::ValidationFailed(msg).into());
+        }
+
+        let mut nonces = self.nonces.lock();
+        nonces.retain(|_, (_, expires_at)| *expires_at > now);
+        if let Some((last, _)) = nonces.get(&signer).filter(|(last, _)| request.nonce <= *last) {
+            return Err(CommitmentError::StaleSessionNonce(*last));
+        }
+
+        let mut sessions = self.sessions.lock();
+        sessions.retain(|_, session| session.expires_at > now);
+        if sessions.values().filter(|session| session.signer == signer).count() >=
+            MAX_SESSIONS_PER_SIGNER
+        {
+            let msg = format!("More than {MAX_SESSIONS_PER_SIGNER} open sessions");
+            return Err(RejectionError::ValidationFailed(msg).into());
+        }
+
+        let latest_expiry = nonces.get(&signer).map_or(0, |(_, expires_at)| *expires_at);
+        nonces.insert(signer, (request.nonce, latest_expiry.max(request.expires_at)));
+
+        let session_id = B256::random();
+        sessions.insert(session_id, Session { signer, expires_at: request.expires_at });
+
+        Ok(SessionInfo { session_id, expires_at: request.expires_at })
+    }
+
+    /// Checks that the session with the given identifier is open and belongs to the signer.
+    pub fn authorize(&self, session_id: B256, signer: Address) -> Result<(), CommitmentError> {
+        let now = self.time.now().as_secs();
+        let mut sessions = self.sessions.lock();
+
+        match sessions.get(&session_id) {
+            Some(session) if session.expires_at <= now => {
+                sessions.remove(&session_id);
+                Err(CommitmentError::SessionNotFound)
+            }
+            Some(session) if session.signer == signer => Ok(()),
+            _ => Err(CommitmentError::SessionNotFound),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use alloy::primitives::{Address, B256};
+
+    use crate::{
+        api::commitments::spec::CommitmentError, primitives::OpenSessionRequest,
+        test_util::MockClock,
+    };
+
+    use super::{SessionBook, MAX_SESSIONS_PER_SIGNER, MAX_SESSION_DURATION};
+
+    #[test]
+    fn test_session_authorizes_requests_until_expiry() {
+        let clock = MockClock::default();
+        clock.set(Duration::from_secs(1_000));
+        let book = SessionBook::with_time_source(clock.clone());
+
+        let signer = Address::random();
+        let request = OpenSessionRequest { nonce: 1, issued_at: 1_000, expires_at: 1_060 };
+        let session = book.open(request, signer).unwrap();
+        assert_eq!(session.expires_at, 1_060);
+
+        // The session authorizes any number of requests of its signer
+        for _ in 0..3 {
+            book.authorize(session.session_id, signer).unwrap();
+        }
+        assert!(matches!(
+            book.authorize(session.session_id, Address::random()),
+            Err(CommitmentError::SessionNotFound)
+        ));
+        assert!(matches!(
+            book.authorize(B256::random(), signer),
+            Err(CommitmentError::SessionNotFound)
+        ));
+
+        // The authorization can't be replayed, even while the session is open
+        assert!(matches!(book.open(request, signer), Err(CommitmentError::StaleSessionNonce(1))));
+
+        // Expired sessions don't authorize requests anymore
+        clock.set(Duration::from_secs(1_060));
+        assert!(matches!(
+            book.authorize(session.session_id, signer),
+            Err(CommitmentError::SessionNotFound)
+        ));
+
+        // Nor can they be opened
+        let expired = OpenSessionRequest { nonce: 2, issued_at: 1_000, expires_at: 1_060 };
+        assert!(matches!(book.open(expired, signer), Err(CommitmentError::Rejected(_))));
+
+        // Sessions are bounded in duration
+        let expires_at = 1_060 + MAX_SESSION_DURATION.as_secs() + 1;
+        let too_long = OpenSessionRequest { nonce: 2, issued_at: 1_060, expires_at };
+        assert!(matches!(book.open(too_long, signer), Err(CommitmentError::Rejected(_))));
+
+        // A new session can be opened with a higher nonce
+        let renewed = OpenSessionRequest { nonce: 2, issued_at: 1_060, expires_at: 1_120 };
+        let session = book.open(renewed, signer).unwrap();
+        book.authorize(session.session_id, signer).unwrap();
+    }
+
+    #[test]
+    fn test_sessions_are_bounded() {
+        let clock = MockClock::default();
+        clock.set(Duration::from_secs(1_000));
+        let book = SessionBook::with_time_source(clock.clone());
+        let signer = Address::random();
+
+        // Authorizations from before the start of the sidecar may have been used already
+        let replayed = OpenSessionRequest { nonce: 1, issued_at: 999, expires_at: 1_060 };
+        assert!(matches!(book.open(replayed, signer), Err(CommitmentError::Rejected(_))));
+
+        // A signer can only open a few sessions at once
+        for nonce in 1..=MAX_SESSIONS_PER_SIGNER as u64 {
+            let request = OpenSessionRequest { nonce, issued_at: 1_000, expires_at: 1_060 };
+            book.open(request, signer).unwrap();
+        }
+        let nonce = MAX_SESSIONS_PER_SIGNER as u64 + 1;
+        let request = OpenSessionRequest { nonce, issued_at: 1_000, expires_at: 1_060 };
+        assert!(matches!(book.open(request, signer), Err(CommitmentError::Rejected(_))));
+
+        // The nonces are pruned once all the sessions of their signer have expired
+        clock.set(Duration::from_secs(1_060));
+        let other = OpenSessionRequest { nonce: 1, issued_at: 1_060, expires_at: 1_120 };
+        book.open(other, Address::random()).unwrap();
+        assert!(!book.nonces.lock().contains_key(&signer));
+    }
+}
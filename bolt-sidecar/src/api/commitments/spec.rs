@@ -1,15 +1,36 @@
-use alloy::primitives::SignatureError;
+use alloy::primitives::{Address, Signature, SignatureError, TxHash};
 use axum::{extract::rejection::JsonRejection, http::StatusCode, response::IntoResponse, Json};
 use thiserror::Error;
 
 use crate::{
-    primitives::{commitment::InclusionCommitment, InclusionRequest},
-    state::{consensus::ConsensusError, ValidationError},
+    primitives::{
+        commitment::InclusionCommitment, CommitmentKind, InclusionRequest, InclusionReservation,
+        OpenSessionRequest, ReserveInclusionRequest, SessionInfo, SlotInfo, SubmitBodyRequest,
+    },
+    signer::SignerRotationError,
+    state::{consensus::ConsensusError, CommitmentStatus, ReservationError, ValidationError},
 };
 
 use super::jsonrpc::JsonResponse;
 
-pub(super) const SIGNATURE_HEADER: &str = "x-bolt-signature";
+/// The verbosity of the response to an inclusion request, selected with the
+/// [RESPONSE_MODE_HEADER] header.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResponseMode {
+    /// The full signed commitment, including the raw transactions.
+    #[default]
+    Full,
+    /// A compact receipt of the commitment. See [CommitmentReceipt].
+    ///
+    /// [CommitmentReceipt]: crate::primitives::commitment::CommitmentReceipt
+    Compact,
+}
+
+pub(crate) const SIGNATURE_HEADER: &str = "x-bolt-signature";
+
+pub(super) const SESSION_HEADER: &str = "x-bolt-session";
+
+pub(crate) const RESPONSE_MODE_HEADER: &str = "x-bolt-response";
 
 pub(super) const GET_VERSION_METHOD: &str = "bolt_getVersion";
 
@@ -17,6 +38,20 @@ pub(super) const REQUEST_INCLUSION_METHOD: &str = "bolt_requestInclusion";
 
 pub(super) const GET_METADATA_METHOD: &str = "bolt_metadata";
 
+pub(super) const GET_SLOT_INFO_METHOD: &str = "bolt_getSlotInfo";
+
+pub(super) const RESERVE_INCLUSION_METHOD: &str = "bolt_reserveInclusion";
+
+pub(super) const SUBMIT_BODY_METHOD: &str = "bolt_submitBody";
+
+pub(super) const ROTATE_SIGNER_METHOD: &str = "bolt_admin_rotateSigner";
+
+pub(super) const DRAIN_SLOT_METHOD: &str = "bolt_admin_drainSlot";
+
+pub(super) const GET_COMMITMENT_STATUS_METHOD: &str = "bolt_getCommitmentStatus";
+
+pub(super) const OPEN_SESSION_METHOD: &str = "bolt_openSession";
+
 pub(super) const MAX_REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(6);
 
 /// Error type for the commitments API.
@@ -55,58 +90,166 @@ pub enum CommitmentError {
     /// Invalid JSON.
     #[error(transparent)]
     InvalidJson(#[from] JsonRejection),
+    /// The engine API is unhealthy and new commitments can't be honored.
+    #[error("Engine API is unavailable, not accepting new commitments")]
+    EngineUnavailable,
+    /// Too many commitment requests are being processed, try again later.
+    #[error("Too many in-flight commitment requests, try again later")]
+    Busy,
+    /// The requested commitment kind is not offered by this sidecar.
+    #[error("Unsupported commitment kind: {0}")]
+    UnsupportedKind(CommitmentKind),
+    /// The inclusion reservation doesn't exist or has expired.
+    #[error("Reservation not found or expired")]
+    ReservationNotFound,
+    /// The queried data doesn't exist, e.g. because its slot has been pruned already.
+    #[error("{0} not found")]
+    NotFound(String),
+    /// The submitted transaction body doesn't match the reserved transaction hash.
+    #[error("Transaction body mismatch: reserved {expected}, got {got}")]
+    BodyMismatch {
+        /// The reserved transaction hash.
+        expected: TxHash,
+        /// The hash of the submitted transaction.
+        got: TxHash,
+    },
+    /// Missing or invalid admin token for an admin method.
+    #[error("Unauthorized")]
+    Unauthorized,
+    /// The new signer of a signer rotation failed to load or to verify.
+    #[error("Failed to rotate signer: {0}")]
+    InvalidSigner(#[from] SignerRotationError),
+    /// The session doesn't exist, has expired or belongs to another signer.
+    #[error("Session not found or expired")]
+    SessionNotFound,
+    /// The nonce of the session isn't higher than the one of the signer's latest session.
+    #[error("Session nonce too low, latest is {0}")]
+    StaleSessionNonce(u64),
+    /// The constraints of the slot were already posted to the relays, which can't cancel them.
+    #[error("Constraints of slot {0} were already posted to the relays")]
+    ConstraintsPosted(u64),
+}
+
+impl From<ReservationError> for CommitmentError {
+    fn from(err: ReservationError) -> Self {
+        match err {
+            ReservationError::Duplicate => Self::Duplicate,
+            ReservationError::NotFound => Self::ReservationNotFound,
+            ReservationError::BodyMismatch { expected, got } => {
+                Self::BodyMismatch { expected, got }
+            }
+            ReservationError::Validation(err) => Self::Validation(err),
+        }
+    }
+}
+
+impl CommitmentError {
+    /// Returns `true` if the error is transient, i.e. the same request may be accepted if
+    /// submitted again later, as opposed to errors caused by the request itself.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Internal | Self::EngineUnavailable | Self::Busy => true,
+            Self::Consensus(err) => matches!(err, ConsensusError::BeaconApiError(_)),
+            Self::Validation(err) => err.is_internal(),
+            Self::Rejected(_) |
+            Self::Duplicate |
+            Self::NoSignature |
+            Self::InvalidSignature(_) |
+            Self::MalformedHeader |
+            Self::Signature(_) |
+            Self::UnknownMethod |
+            Self::InvalidJson(_) |
+            Self::UnsupportedKind(_) |
+            Self::ReservationNotFound |
+            Self::NotFound(_) |
+            Self::BodyMismatch { .. } |
+            Self::Unauthorized |
+            Self::InvalidSigner(_) |
+            Self::SessionNotFound |
+            Self::StaleSessionNonce(_) |
+            Self::ConstraintsPosted(_) => false,
+        }
+    }
+
+    /// Returns the reason a commitment request was rejected with this error, for metrics
+    /// purposes. Reasons are coarser than the errors to keep the metric cardinality low.
+    pub fn rejection_reason(&self) -> &'static str {
+        match self {
+            Self::Duplicate => "duplicate",
+            Self::Validation(
+                ValidationError::MaxCommitmentsReachedForSlot(..) |
+                ValidationError::MaxCommittedGasReachedForSlot(..) |
+                ValidationError::MaxCommitmentValueReachedForSlot(..) |
+                ValidationError::TopOfBlockTaken(_) |
+                ValidationError::Eip4844Limit,
+            ) => "slot_full",
+            Self::Validation(
+                ValidationError::BaseFeeTooLow(_) |
+                ValidationError::BlobBaseFeeTooLow(_) |
+                ValidationError::MaxPriorityFeePerGasTooLow(_),
+            ) => "underpriced",
+            Self::Validation(ValidationError::SlotTooLow(_)) |
+            Self::Consensus(ConsensusError::DeadlineExceeded) |
+            Self::ConstraintsPosted(_) => "too_late",
+            Self::Consensus(ConsensusError::BeaconApiError(_)) => "internal",
+            Self::Consensus(_) => "slot_unavailable",
+            Self::Validation(err) if err.is_internal() => "internal",
+            Self::NoSignature |
+            Self::InvalidSignature(_) |
+            Self::MalformedHeader |
+            Self::Signature(_) |
+            Self::Unauthorized |
+            Self::SessionNotFound => "unauthorized",
+            Self::EngineUnavailable | Self::Busy => "unavailable",
+            Self::Internal | Self::InvalidSigner(_) => "internal",
+            Self::Validation(_) |
+            Self::Rejected(_) |
+            Self::UnknownMethod |
+            Self::InvalidJson(_) |
+            Self::UnsupportedKind(_) |
+            Self::ReservationNotFound |
+            Self::NotFound(_) |
+            Self::BodyMismatch { .. } |
+            Self::StaleSessionNonce(_) => "invalid_request",
+        }
+    }
 }
 
 impl IntoResponse for CommitmentError {
     fn into_response(self) -> axum::http::Response<axum::body::Body> {
-        match self {
-            Self::Rejected(err) => {
-                (StatusCode::BAD_REQUEST, Json(JsonResponse::from_error(-32000, err.to_string())))
-                    .into_response()
-            }
-            Self::Duplicate => {
-                (StatusCode::BAD_REQUEST, Json(JsonResponse::from_error(-32001, self.to_string())))
-                    .into_response()
+        let retryable = self.is_retryable();
+        let (status, code, message) = match self {
+            Self::Rejected(err) => (StatusCode::BAD_REQUEST, -32000, err.to_string()),
+            Self::Duplicate => (StatusCode::BAD_REQUEST, -32001, self.to_string()),
+            Self::Internal => (StatusCode::INTERNAL_SERVER_ERROR, -32002, self.to_string()),
+            Self::NoSignature => (StatusCode::BAD_REQUEST, -32003, self.to_string()),
+            Self::InvalidSignature(err) => (StatusCode::BAD_REQUEST, -32004, err.to_string()),
+            Self::Signature(err) => (StatusCode::BAD_REQUEST, -32005, err.to_string()),
+            Self::Consensus(err) => (StatusCode::BAD_REQUEST, -32006, err.to_string()),
+            Self::Validation(err) => (StatusCode::BAD_REQUEST, -32006, err.to_string()),
+            Self::MalformedHeader => (StatusCode::BAD_REQUEST, -32007, self.to_string()),
+            Self::UnknownMethod => (StatusCode::BAD_REQUEST, -32601, self.to_string()),
+            Self::InvalidJson(err) => {
+                (StatusCode::BAD_REQUEST, -32600, format!("Invalid request: {err}"))
             }
-            Self::Internal => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(JsonResponse::from_error(-32002, self.to_string())),
-            )
-                .into_response(),
-            Self::NoSignature => {
-                (StatusCode::BAD_REQUEST, Json(JsonResponse::from_error(-32003, self.to_string())))
-                    .into_response()
+            Self::EngineUnavailable => (StatusCode::SERVICE_UNAVAILABLE, -32008, self.to_string()),
+            Self::Busy => (StatusCode::SERVICE_UNAVAILABLE, -32009, self.to_string()),
+            Self::ReservationNotFound => (StatusCode::BAD_REQUEST, -32010, self.to_string()),
+            Self::BodyMismatch { .. } => (StatusCode::BAD_REQUEST, -32011, self.to_string()),
+            Self::UnsupportedKind(_) => (StatusCode::BAD_REQUEST, -32012, self.to_string()),
+            Self::NotFound(_) => (StatusCode::NOT_FOUND, -32014, self.to_string()),
+            Self::Unauthorized => (StatusCode::UNAUTHORIZED, -32015, self.to_string()),
+            Self::InvalidSigner(SignerRotationError::Task(_)) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, -32002, self.to_string())
             }
-            Self::InvalidSignature(err) => {
-                (StatusCode::BAD_REQUEST, Json(JsonResponse::from_error(-32004, err.to_string())))
-                    .into_response()
-            }
-            Self::Signature(err) => {
-                (StatusCode::BAD_REQUEST, Json(JsonResponse::from_error(-32005, err.to_string())))
-                    .into_response()
-            }
-            Self::Consensus(err) => {
-                (StatusCode::BAD_REQUEST, Json(JsonResponse::from_error(-32006, err.to_string())))
-                    .into_response()
-            }
-            Self::Validation(err) => {
-                (StatusCode::BAD_REQUEST, Json(JsonResponse::from_error(-32006, err.to_string())))
-                    .into_response()
-            }
-            Self::MalformedHeader => {
-                (StatusCode::BAD_REQUEST, Json(JsonResponse::from_error(-32007, self.to_string())))
-                    .into_response()
-            }
-            Self::UnknownMethod => {
-                (StatusCode::BAD_REQUEST, Json(JsonResponse::from_error(-32601, self.to_string())))
-                    .into_response()
-            }
-            Self::InvalidJson(err) => (
-                StatusCode::BAD_REQUEST,
-                Json(JsonResponse::from_error(-32600, format!("Invalid request: {err}"))),
-            )
-                .into_response(),
-        }
+            Self::InvalidSigner(_) => (StatusCode::BAD_REQUEST, -32016, self.to_string()),
+            Self::SessionNotFound => (StatusCode::UNAUTHORIZED, -32017, self.to_string()),
+            Self::StaleSessionNonce(_) => (StatusCode::BAD_REQUEST, -32018, self.to_string()),
+            Self::ConstraintsPosted(_) => (StatusCode::BAD_REQUEST, -32019, self.to_string()),
+        };
+
+        let response = JsonResponse::from_error(code, message).with_retryable(retryable);
+        (status, Json(response)).into_response()
     }
 }
 
@@ -127,4 +270,68 @@ pub trait CommitmentsApi {
         &self,
         inclusion_request: InclusionRequest,
     ) -> Result<InclusionCommitment, CommitmentError>;
+
+    /// Returns the fees and the remaining commitment capacity of the given slot.
+    async fn get_slot_info(&self, slot: u64) -> Result<SlotInfo, CommitmentError>;
+
+    /// Returns the relay responses to the submission of the constraints of the commitment
+    /// to the given transaction in the given slot.
+    async fn get_commitment_status(
+        &self,
+        slot: u64,
+        tx_hash: TxHash,
+    ) -> Result<CommitmentStatus, CommitmentError>;
+
+    /// Reserves the inclusion of a transaction by its hash, before its body is known.
+    /// The signer and signature must be over the [ReserveInclusionRequest] digest.
+    async fn reserve_inclusion(
+        &self,
+        request: ReserveInclusionRequest,
+        signer: Address,
+        signature: Signature,
+    ) -> Result<InclusionReservation, CommitmentError>;
+
+    /// Submits the body of a reserved transaction, finalizing the inclusion commitment.
+    async fn submit_body(
+        &self,
+        request: SubmitBodyRequest,
+    ) -> Result<InclusionCommitment, CommitmentError>;
+
+    /// Opens a session authorizing inclusion requests on behalf of the signer until it
+    /// expires. The signer and signature must be over the [OpenSessionRequest] digest.
+    async fn open_session(
+        &self,
+        request: OpenSessionRequest,
+        signer: Address,
+    ) -> Result<SessionInfo, CommitmentError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::response::IntoResponse;
+
+    use crate::{api::commitments::jsonrpc::JsonResponse, state::ValidationError};
+
+    use super::CommitmentError;
+
+    async fn error_response(err: CommitmentError) -> JsonResponse {
+        let body = axum::body::to_bytes(err.into_response().into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_retryable_error_hint() {
+        // An unavailable engine API may accept the request later
+        let response = error_response(CommitmentError::EngineUnavailable).await;
+        let error = response.error.unwrap();
+        assert_eq!(error.code, -32008);
+        assert!(error.data.unwrap().retryable);
+
+        // An invalid request won't be accepted if resubmitted
+        let response =
+            error_response(CommitmentError::Validation(ValidationError::NonceTooLow(1, 0))).await;
+        let error = response.error.unwrap();
+        assert_eq!(error.code, -32006);
+        assert!(!error.data.unwrap().retryable);
+    }
 }
@@ -8,6 +8,8 @@ use ethereum_consensus::{
     builder::SignedValidatorRegistration, deneb::mainnet::SignedBlindedBeaconBlock,
 };
 use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+use tracing::warn;
 
 use crate::primitives::{
     BatchedSignedConstraints, GetPayloadResponse, SignedBuilderBid, SignedDelegation,
@@ -30,6 +32,10 @@ pub const SUBMIT_CONSTRAINTS_PATH: &str = "/constraints/v1/builder/constraints";
 pub const DELEGATE_PATH: &str = "/constraints/v1/builder/delegate";
 /// The path to the constraints API revoke endpoint.
 pub const REVOKE_PATH: &str = "/constraints/v1/builder/revoke";
+/// The path to the relay API submit block endpoint.
+pub const SUBMIT_BLOCK_PATH: &str = "/relay/v1/builder/blocks";
+/// The path to the relay API get constraints endpoint.
+pub const GET_CONSTRAINTS_PATH: &str = "/relay/v1/builder/constraints";
 
 /// A response object for errors.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +45,13 @@ pub struct ErrorResponse {
     message: String,
 }
 
+impl ErrorResponse {
+    /// Create a new error response with the given HTTP status code and message.
+    pub fn new(code: u16, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
 /// Helper to serialize a status code as a string using the provided serializer.
 pub fn serialize_status_code<S>(value: &u16, serializer: S) -> Result<S::Ok, S::Error>
 where
@@ -63,6 +76,10 @@ pub enum BuilderApiError {
     FailedDelegating(ErrorResponse),
     #[error("Failed to revoke constraint submission rights: {0:?}")]
     FailedRevoking(ErrorResponse),
+    #[error("Failed submitting block: {0:?}")]
+    FailedSubmittingBlock(ErrorResponse),
+    #[error("Failed getting constraints: {0:?}")]
+    FailedGettingConstraints(ErrorResponse),
     #[error("Failed to fetch local payload for slot {0}")]
     FailedToFetchLocalPayload(u64),
     #[error("Axum error: {0:?}")]
@@ -77,10 +94,50 @@ pub enum BuilderApiError {
     InvalidFork(String),
     #[error("Locally-built payload does not match expected signed header")]
     LocalPayloadIntegrity(#[from] super::builder::LocalPayloadIntegrityError),
+    #[error("Failed to SSZ-encode constraints: {0}")]
+    ConstraintsSsz(#[from] crate::primitives::constraint::ConstraintsSszError),
+    #[error("Failed to SSZ-encode signed messages: {0}")]
+    MessagesSsz(#[from] ethereum_consensus::ssz::prelude::ssz_rs::SerializeError),
     #[error("Generic error: {0}")]
     Generic(String),
 }
 
+impl BuilderApiError {
+    /// Returns `true` if the error is transient, i.e. the same request may succeed if
+    /// retried later. Network errors, timeouts and server errors are transient, while
+    /// client errors and invalid payloads are not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::FailedRegisteringValidators(error) |
+            Self::FailedGettingHeader(error) |
+            Self::FailedGettingPayload(error) |
+            Self::FailedSubmittingConstraints(error) |
+            Self::FailedDelegating(error) |
+            Self::FailedRevoking(error) |
+            Self::FailedSubmittingBlock(error) |
+            Self::FailedGettingConstraints(error) => error.code >= 500,
+            Self::ReqwestError(err) => !err.is_decode() && !err.is_builder(),
+            Self::Timeout(_) | Self::FailedToFetchLocalPayload(_) => true,
+            Self::AxumError(_) |
+            Self::JsonError(_) |
+            Self::InvalidFork(_) |
+            Self::LocalPayloadIntegrity(_) |
+            Self::ConstraintsSsz(_) |
+            Self::MessagesSsz(_) |
+            Self::Generic(_) => false,
+        }
+    }
+
+    /// Returns `true` if the constraints were rejected by the relay with a client error, i.e.
+    /// some of the submitted constraints are invalid.
+    pub const fn is_client_rejection(&self) -> bool {
+        match self {
+            Self::FailedSubmittingConstraints(error) => error.code >= 400 && error.code < 500,
+            _ => false,
+        }
+    }
+}
+
 impl IntoResponse for BuilderApiError {
     fn into_response(self) -> Response {
         match self {
@@ -89,7 +146,9 @@ impl IntoResponse for BuilderApiError {
             Self::FailedGettingPayload(error) |
             Self::FailedSubmittingConstraints(error) |
             Self::FailedDelegating(error) |
-            Self::FailedRevoking(error) => {
+            Self::FailedRevoking(error) |
+            Self::FailedSubmittingBlock(error) |
+            Self::FailedGettingConstraints(error) => {
                 (StatusCode::from_u16(error.code).unwrap(), Json(error)).into_response()
             }
             Self::AxumError(err) => (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
@@ -111,6 +170,12 @@ impl IntoResponse for BuilderApiError {
             Self::LocalPayloadIntegrity(err) => {
                 (StatusCode::BAD_REQUEST, err.to_string()).into_response()
             }
+            Self::ConstraintsSsz(err) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+            }
+            Self::MessagesSsz(err) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+            }
             Self::Generic(err) => (StatusCode::INTERNAL_SERVER_ERROR, Json(err)).into_response(),
         }
     }
@@ -151,10 +216,38 @@ pub trait BuilderApi {
 #[async_trait::async_trait]
 pub trait ConstraintsApi: BuilderApi {
     /// Implements: <https://docs.boltprotocol.xyz/technical-docs/api/builder#constraints>
+    ///
+    /// Returns the response body of the relay, `null` if it was empty.
     async fn submit_constraints(
         &self,
         constraints: &BatchedSignedConstraints,
-    ) -> Result<(), BuilderApiError>;
+    ) -> Result<Value, BuilderApiError>;
+
+    /// Submit a batch of constraints, returning the outcome of each signed constraints of the
+    /// batch, in order.
+    ///
+    /// The batch is submitted at once first. If the relay rejects it with a client error, each
+    /// signed constraints is then submitted on its own, so that a single invalid commitment
+    /// doesn't fail the others. Any other error fails the whole batch, as submitting the
+    /// constraints one by one wouldn't fare better.
+    async fn submit_constraints_batch(
+        &self,
+        constraints: &BatchedSignedConstraints,
+    ) -> Result<Vec<Result<Value, BuilderApiError>>, BuilderApiError> {
+        match self.submit_constraints(constraints).await {
+            Ok(response) => Ok(constraints.iter().map(|_| Ok(response.clone())).collect()),
+            Err(err) if constraints.len() > 1 && err.is_client_rejection() => {
+                warn!(?err, "Constraints batch rejected, submitting them one by one");
+                let mut outcomes = Vec::with_capacity(constraints.len());
+                for signed_constraints in constraints {
+                    let single = vec![signed_constraints.clone()];
+                    outcomes.push(self.submit_constraints(&single).await);
+                }
+                Ok(outcomes)
+            }
+            Err(err) => Err(err),
+        }
+    }
 
     /// Implements: <https://docs.boltprotocol.xyz/technical-docs/api/builder#get_header_with_proofs>
     async fn get_header_with_proofs(
@@ -18,18 +18,23 @@ use ethereum_consensus::{
             Withdrawal as ConsensusWithdrawal, MAX_TRANSACTIONS_PER_PAYLOAD,
             MAX_WITHDRAWALS_PER_PAYLOAD,
         },
+        minimal::MAX_WITHDRAWALS_PER_PAYLOAD as MINIMAL_MAX_WITHDRAWALS_PER_PAYLOAD,
         ExecutionAddress, ExecutionPayload as DenebExecutionPayload,
     },
-    ssz::prelude::{ssz_rs, ByteList, ByteVector, HashTreeRoot, List},
+    ssz::prelude::{ssz_rs, ByteList, ByteVector, HashTreeRoot, List, Node},
     types::mainnet::ExecutionPayload as ConsensusExecutionPayload,
 };
 use reth_primitives::{SealedBlock, TransactionSigned};
 
+use crate::config::chain::Preset;
+
 /// Compatibility: convert a sealed header into an ethereum-consensus execution payload header.
-/// This requires recalculating the withdrals and transactions roots as SSZ instead of MPT roots.
+/// This requires recalculating the withdrals and transactions roots as SSZ instead of MPT roots,
+/// with the list limits of the given preset.
 pub(crate) fn to_execution_payload_header(
     sealed_block: &SealedBlock,
     transactions: Vec<TransactionSigned>,
+    preset: Preset,
 ) -> ConsensusExecutionPayloadHeader {
     // Transactions and withdrawals are treated as opaque byte arrays in consensus types
     let transactions_bytes = transactions.iter().map(|t| t.encoded_2718()).collect::<Vec<_>>();
@@ -42,16 +47,17 @@ pub(crate) fn to_execution_payload_header(
 
     let transactions_root = transactions_ssz.hash_tree_root().expect("valid transactions root");
 
-    let mut withdrawals_ssz: List<ConsensusWithdrawal, MAX_WITHDRAWALS_PER_PAYLOAD> =
-        List::default();
-
-    if let Some(withdrawals) = sealed_block.body.withdrawals.as_ref() {
-        for w in withdrawals {
-            withdrawals_ssz.push(to_consensus_withdrawal(w));
-        }
-    }
+    let withdrawals = sealed_block
+        .body
+        .withdrawals
+        .as_ref()
+        .map(|withdrawals| withdrawals.iter().map(to_consensus_withdrawal).collect())
+        .unwrap_or_default();
 
-    let withdrawals_root = withdrawals_ssz.hash_tree_root().expect("valid withdrawals root");
+    let withdrawals_root = match preset {
+        Preset::Mainnet => withdrawals_root::<MAX_WITHDRAWALS_PER_PAYLOAD>(withdrawals),
+        Preset::Minimal => withdrawals_root::<MINIMAL_MAX_WITHDRAWALS_PER_PAYLOAD>(withdrawals),
+    };
 
     let header = &sealed_block.header;
 
@@ -76,6 +82,13 @@ pub(crate) fn to_execution_payload_header(
     }
 }
 
+/// Computes the SSZ root of a list of withdrawals with the given list limit.
+fn withdrawals_root<const MAX_WITHDRAWALS: usize>(withdrawals: Vec<ConsensusWithdrawal>) -> Node {
+    let withdrawals_ssz: List<ConsensusWithdrawal, MAX_WITHDRAWALS> =
+        List::try_from(withdrawals).expect("withdrawals fit in a payload");
+    withdrawals_ssz.hash_tree_root().expect("valid withdrawals root")
+}
+
 /// Compatibility: convert a sealed block into an Alloy execution payload
 pub(crate) fn to_alloy_execution_payload(
     block: &SealedBlock,
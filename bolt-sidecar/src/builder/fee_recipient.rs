@@ -0,0 +1,127 @@
+use std::{fmt, sync::Arc};
+
+use alloy::primitives::Address;
+use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
+use tracing::warn;
+
+use super::BuilderError;
+use crate::primitives::Slot;
+
+/// Resolves the fee recipient of a locally built payload from the public key of
+/// the proposer of the target slot.
+///
+/// This is useful for validator setups where the fee recipient isn't static, e.g.
+/// when it is looked up in a registry contract through an `eth_call`.
+#[async_trait::async_trait]
+pub trait FeeRecipientResolver: Send + Sync {
+    /// Returns the fee recipient for the given slot and proposer.
+    async fn resolve(&self, slot: Slot, proposer: &BlsPublicKey) -> Result<Address, BuilderError>;
+}
+
+#[async_trait::async_trait]
+impl<F> FeeRecipientResolver for F
+where
+    F: Fn(Slot, &BlsPublicKey) -> Result<Address, BuilderError> + Send + Sync,
+{
+    async fn resolve(&self, slot: Slot, proposer: &BlsPublicKey) -> Result<Address, BuilderError> {
+        self(slot, proposer)
+    }
+}
+
+/// The schedule of fee recipients used by the local builder.
+///
+/// If a [FeeRecipientResolver] is set, it is queried for every slot. The static
+/// `default_recipient` is used when no resolver is configured, when the proposer
+/// of the slot is unknown, or when the resolver returns an error.
+#[derive(Clone)]
+pub struct FeeRecipientSchedule {
+    default_recipient: Address,
+    resolver: Option<Arc<dyn FeeRecipientResolver>>,
+}
+
+impl FeeRecipientSchedule {
+    /// Create a new schedule which always returns the given fee recipient.
+    pub fn new(default_recipient: Address) -> Self {
+        Self { default_recipient, resolver: None }
+    }
+
+    /// Set the resolver used to compute the fee recipient of each slot.
+    pub fn with_resolver(mut self, resolver: Arc<dyn FeeRecipientResolver>) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
+    /// Returns the static fee recipient used as fallback.
+    pub fn default_recipient(&self) -> Address {
+        self.default_recipient
+    }
+
+    /// Returns the fee recipient for the given slot and (optional) proposer public key.
+    /// Resolver errors are logged and never fail the payload build.
+    pub async fn fee_recipient_for(&self, slot: Slot, proposer: Option<&BlsPublicKey>) -> Address {
+        let (Some(resolver), Some(proposer)) = (&self.resolver, proposer) else {
+            return self.default_recipient;
+        };
+
+        match resolver.resolve(slot, proposer).await {
+            Ok(recipient) => recipient,
+            Err(err) => {
+                warn!(slot, ?err, "Failed to resolve fee recipient, using the default one");
+                self.default_recipient
+            }
+        }
+    }
+}
+
+impl fmt::Debug for FeeRecipientSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FeeRecipientSchedule")
+            .field("default_recipient", &self.default_recipient)
+            .field("has_resolver", &self.resolver.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use alloy::primitives::Address;
+    use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
+
+    use super::FeeRecipientSchedule;
+    use crate::{builder::BuilderError, primitives::Slot};
+
+    #[tokio::test]
+    async fn test_fee_recipient_schedule_with_resolver() {
+        let default_recipient = Address::repeat_byte(0xff);
+        let proposer = BlsPublicKey::default();
+
+        // Resolve a different recipient for each slot, failing on odd ones.
+        let resolver = |slot: Slot, _: &BlsPublicKey| {
+            if slot % 2 == 1 {
+                return Err(BuilderError::Custom("registry lookup failed".to_string()));
+            }
+            Ok(Address::repeat_byte(slot as u8))
+        };
+
+        let schedule =
+            FeeRecipientSchedule::new(default_recipient).with_resolver(Arc::new(resolver));
+
+        assert_eq!(schedule.fee_recipient_for(2, Some(&proposer)).await, Address::repeat_byte(2));
+        assert_eq!(schedule.fee_recipient_for(4, Some(&proposer)).await, Address::repeat_byte(4));
+
+        // Resolver errors and unknown proposers fall back to the default recipient
+        assert_eq!(schedule.fee_recipient_for(3, Some(&proposer)).await, default_recipient);
+        assert_eq!(schedule.fee_recipient_for(2, None).await, default_recipient);
+    }
+
+    #[tokio::test]
+    async fn test_fee_recipient_schedule_without_resolver() {
+        let default_recipient = Address::repeat_byte(0xff);
+        let schedule = FeeRecipientSchedule::new(default_recipient);
+
+        let proposer = BlsPublicKey::default();
+        assert_eq!(schedule.fee_recipient_for(1, Some(&proposer)).await, default_recipient);
+    }
+}
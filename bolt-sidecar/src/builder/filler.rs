@@ -0,0 +1,298 @@
+use std::collections::{HashMap, HashSet};
+
+use alloy::primitives::{Address, U256};
+use reqwest::Url;
+use reth_primitives::TxType;
+use tracing::warn;
+
+use super::ordering::{InclusionKind, OrderingPolicy, PayloadTransaction};
+use crate::{
+    client::RpcClient,
+    common::transactions::max_transaction_cost,
+    primitives::{AccountState, FullTransaction, Slot, TransactionExt},
+};
+
+/// The maximum number of pending transactions of the mempool considered as fillers.
+const MAX_MEMPOOL_CANDIDATES: usize = 256;
+
+/// A local pool of non-committed transactions, used to fill the spare gas of locally
+/// built payloads after the committed transactions.
+#[async_trait::async_trait]
+pub trait FillerPool: Send + Sync {
+    /// Returns the candidate filler transactions for the given slot.
+    async fn pending_transactions(&self, slot: Slot) -> Vec<FullTransaction>;
+}
+
+#[async_trait::async_trait]
+impl<F> FillerPool for F
+where
+    F: Fn(Slot) -> Vec<FullTransaction> + Send + Sync,
+{
+    async fn pending_transactions(&self, slot: Slot) -> Vec<FullTransaction> {
+        self(slot)
+    }
+}
+
+/// A [FillerPool] serving the pending transactions of the mempool of the execution client.
+///
+/// Blob transactions are never served, as the node doesn't return their sidecars.
+#[derive(Debug, Clone)]
+pub struct MempoolFillerPool {
+    client: RpcClient,
+}
+
+impl MempoolFillerPool {
+    /// Create a new pool fetching the pending transactions from the execution client at `url`.
+    pub fn new<U: Into<Url>>(url: U) -> Self {
+        Self { client: RpcClient::new(url) }
+    }
+}
+
+#[async_trait::async_trait]
+impl FillerPool for MempoolFillerPool {
+    async fn pending_transactions(&self, _slot: Slot) -> Vec<FullTransaction> {
+        let mut hashes = match self.client.get_pending_tx_hashes().await {
+            Ok(hashes) => hashes,
+            Err(err) => {
+                warn!(?err, "Failed to fetch the pending transactions of the mempool");
+                return Vec::new();
+            }
+        };
+        hashes.truncate(MAX_MEMPOOL_CANDIDATES);
+
+        match self.client.get_raw_transactions(&hashes).await {
+            Ok(raw_txs) => raw_txs
+                .iter()
+                .filter_map(|raw| FullTransaction::decode_enveloped(raw).ok())
+                .collect(),
+            Err(err) => {
+                warn!(?err, "Failed to fetch the raw pending transactions of the mempool");
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// Selects the filler transactions to include in a payload on top of the `committed` ones.
+///
+/// Candidates are considered by effective tip, and are skipped unless:
+/// - they aren't committed already, aren't blob transactions and match `chain_id`;
+/// - they pay at least `base_fee` and their sender is in `accounts`;
+/// - their nonce follows the account nonce, or the last committed nonce of the sender;
+/// - the sender can afford them after its committed transactions;
+/// - they fit in the gas left by the committed transactions under `gas_limit`.
+///
+/// The committed transactions are never touched: fillers only use the spare gas.
+pub fn select_filler_transactions(
+    candidates: Vec<FullTransaction>,
+    committed: &[PayloadTransaction],
+    accounts: &HashMap<Address, AccountState>,
+    gas_limit: u64,
+    base_fee: u128,
+    chain_id: u64,
+) -> Vec<PayloadTransaction> {
+    let committed_hashes = committed.iter().map(|tx| *tx.tx.hash()).collect::<HashSet<_>>();
+    let mut gas_used = committed.iter().map(|tx| tx.tx.gas_limit()).sum::<u64>();
+
+    // The next nonce and the remaining balance of each sender, after the committed transactions
+    let mut senders = accounts
+        .iter()
+        .map(|(address, account)| (*address, (account.transaction_count, account.balance)))
+        .collect::<HashMap<Address, (u64, U256)>>();
+    for tx in committed {
+        let sender = tx.tx.sender().copied().or_else(|| tx.tx.recover_signer());
+        if let Some((nonce, balance)) = sender.and_then(|sender| senders.get_mut(&sender)) {
+            *nonce = (*nonce).max(tx.tx.nonce() + 1);
+            *balance = balance.saturating_sub(max_transaction_cost(&tx.tx));
+        }
+    }
+
+    let candidates = candidates
+        .into_iter()
+        .filter(|tx| {
+            !committed_hashes.contains(tx.hash()) &&
+                tx.tx_type() != TxType::Eip4844 &&
+                tx.chain_id().map_or(true, |id| id == chain_id) &&
+                tx.effective_tip_per_gas(base_fee).is_some()
+        })
+        .filter_map(|mut tx| {
+            let sender = tx.sender().copied().or_else(|| tx.recover_signer())?;
+            tx.sender = Some(sender);
+            senders
+                .contains_key(&sender)
+                .then(|| PayloadTransaction::new(tx, InclusionKind::Filler))
+        })
+        .collect::<Vec<_>>();
+
+    let mut fillers = Vec::new();
+    for tx in OrderingPolicy::PriorityFee.apply(candidates, base_fee) {
+        let Some(gas) = gas_used.checked_add(tx.gas_limit()).filter(|gas| *gas <= gas_limit) else {
+            continue;
+        };
+
+        let sender = tx.sender().expect("sender is recovered");
+        let (nonce, balance) = senders.get_mut(sender).expect("sender account is known");
+        let cost = max_transaction_cost(&tx);
+        if tx.nonce() != *nonce || cost > *balance {
+            continue;
+        }
+
+        *nonce += 1;
+        *balance -= cost;
+        gas_used = gas;
+        fillers.push(PayloadTransaction::new(tx, InclusionKind::Filler));
+    }
+
+    fillers
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use alloy::{
+        eips::eip2718::Encodable2718,
+        network::{EthereumWallet, TransactionBuilder},
+        primitives::{Address, Bytes, TxHash, U256},
+        signers::local::PrivateKeySigner,
+    };
+    use axum::{extract::State, routing::post, Json, Router};
+    use reqwest::Url;
+    use serde_json::{json, Map, Value};
+    use tokio::net::TcpListener;
+
+    use crate::{
+        builder::ordering::{InclusionKind, OrderingPolicy, PayloadTransaction},
+        primitives::{AccountState, FullTransaction, TransactionExt},
+        test_util::default_test_transaction,
+    };
+
+    use super::{select_filler_transactions, FillerPool, MempoolFillerPool};
+
+    async fn signed_tx(
+        signer: &PrivateKeySigner,
+        nonce: u64,
+        priority_fee: u128,
+    ) -> eyre::Result<FullTransaction> {
+        let wallet = EthereumWallet::from(signer.clone());
+        let tx = default_test_transaction(signer.address(), Some(nonce))
+            .with_max_fee_per_gas(100_000_000_000)
+            .with_max_priority_fee_per_gas(priority_fee);
+
+        Ok(FullTransaction::decode_enveloped(tx.build(&wallet).await?.encoded_2718())?)
+    }
+
+    #[tokio::test]
+    async fn test_fill_spare_gas_after_committed_txs() -> eyre::Result<()> {
+        let alice = PrivateKeySigner::random();
+        let bob = PrivateKeySigner::random();
+        let carol = PrivateKeySigner::random();
+        let base_fee = 1_000_000_000;
+
+        let account = |nonce| AccountState {
+            transaction_count: nonce,
+            balance: U256::from(10u128.pow(18)),
+            has_code: false,
+        };
+        let accounts = HashMap::from([
+            (alice.address(), account(0)),
+            (bob.address(), account(3)),
+            (carol.address(), account(0)),
+        ]);
+
+        // Alice has a committed transaction with a low tip
+        let committed = signed_tx(&alice, 0, 1).await?;
+        let committed = vec![PayloadTransaction::new(committed, InclusionKind::Committed)];
+        let gas_per_tx = committed[0].tx.gas_limit();
+
+        let alice_next = signed_tx(&alice, 1, 2_000_000_000).await?;
+        let bob_next = signed_tx(&bob, 3, 3_000_000_000).await?;
+        let alice_stale = signed_tx(&alice, 0, 9_000_000_000).await?;
+        let bob_gap = signed_tx(&bob, 5, 9_000_000_000).await?;
+        let carol_overflow = signed_tx(&carol, 0, 1_000_000_000).await?;
+        let candidates = vec![
+            committed[0].tx.clone(),
+            alice_stale,
+            bob_gap,
+            carol_overflow,
+            alice_next.clone(),
+            bob_next.clone(),
+        ];
+
+        // Room for the committed transaction and two fillers only
+        let gas_limit = gas_per_tx * 3;
+        let fillers = select_filler_transactions(
+            candidates, &committed, &accounts, gas_limit, base_fee, 1337,
+        );
+
+        let hashes = fillers.iter().map(|tx| *tx.tx.hash()).collect::<Vec<_>>();
+        assert_eq!(hashes, vec![*bob_next.hash(), *alice_next.hash()]);
+        assert!(fillers.iter().all(|tx| tx.kind == InclusionKind::Filler));
+
+        // The committed transaction is still included, ahead of the fillers
+        let block = OrderingPolicy::PriorityFee
+            .apply(committed.iter().cloned().chain(fillers).collect(), base_fee);
+        assert_eq!(block[0].hash(), committed[0].tx.hash());
+        assert_eq!(block.len(), 3);
+        assert!(block.iter().map(|tx| tx.gas_limit()).sum::<u64>() <= gas_limit);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mempool_filler_pool() -> eyre::Result<()> {
+        type RawTxs = Arc<HashMap<TxHash, Bytes>>;
+
+        // A mock EL serving the pending transactions, plus one unknown to `eth_getRawTransaction`
+        async fn rpc(State(raw_txs): State<RawTxs>, Json(body): Json<Value>) -> Json<Value> {
+            let respond = |call: &Value| match call["method"].as_str() {
+                Some("txpool_content") => {
+                    let unknown = TxHash::repeat_byte(0xaa);
+                    let by_nonce = raw_txs
+                        .keys()
+                        .chain([&unknown])
+                        .enumerate()
+                        .map(|(nonce, hash)| (nonce.to_string(), json!({ "hash": hash })))
+                        .collect::<Map<_, _>>();
+                    let pending =
+                        Map::from_iter([(Address::ZERO.to_string(), Value::Object(by_nonce))]);
+                    let content = json!({ "pending": pending, "queued": {} });
+                    json!({ "jsonrpc": "2.0", "id": call["id"], "result": content })
+                }
+                Some("eth_getRawTransactionByHash") => {
+                    let hash = serde_json::from_value::<TxHash>(call["params"][0].clone()).unwrap();
+                    json!({ "jsonrpc": "2.0", "id": call["id"], "result": raw_txs.get(&hash) })
+                }
+                method => panic!("unexpected method {method:?}"),
+            };
+
+            match body.as_array() {
+                Some(batch) => Json(Value::Array(batch.iter().map(respond).collect())),
+                None => Json(respond(&body)),
+            }
+        }
+
+        let signer = PrivateKeySigner::random();
+        let txs = [signed_tx(&signer, 0, 1).await?, signed_tx(&signer, 1, 1).await?];
+        let raw_txs = txs
+            .iter()
+            .map(|tx| (*tx.hash(), Bytes::from(tx.encoded_2718())))
+            .collect::<HashMap<_, _>>();
+
+        let router = Router::new().route("/", post(rpc)).with_state(Arc::new(raw_txs));
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = Url::parse(&format!("http://{}", listener.local_addr()?))?;
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let pool = MempoolFillerPool::new(url);
+        let mut hashes =
+            pool.pending_transactions(10).await.iter().map(|tx| *tx.hash()).collect::<Vec<_>>();
+        hashes.sort();
+
+        let mut expected = txs.iter().map(|tx| *tx.hash()).collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(hashes, expected);
+
+        Ok(())
+    }
+}
@@ -1,16 +1,21 @@
-use alloy::primitives::U256;
+use std::{future::Future, sync::Arc};
+
+use alloy::{eips::eip4844::calc_blob_gasprice, primitives::U256};
 use beacon_api_client::mainnet::Client as BeaconClient;
 use ethereum_consensus::{
     crypto::{KzgCommitment, PublicKey},
-    deneb::mainnet::ExecutionPayloadHeader,
-    ssz::prelude::{List, MerkleizationError},
+    deneb::{mainnet::ExecutionPayloadHeader, Hash32},
+    ssz::prelude::MerkleizationError,
+    types::mainnet::ExecutionPayload,
 };
+use tracing::{debug, info, warn};
 
 use crate::{
     common::secrets::BlsSecretKeyWrapper,
     config::{ChainConfig, Opts},
     primitives::{
-        BuilderBid, GetPayloadResponse, PayloadAndBid, PayloadAndBlobs, SignedBuilderBid,
+        BidTrace, BuilderBid, GetPayloadResponse, PayloadAndBid, PayloadAndBlobs,
+        SignedBidSubmission, SignedBuilderBid,
     },
 };
 
@@ -20,21 +25,37 @@ use crate::{
 /// The built template can be used as a fallback block in case of no valid
 /// response from all relays.
 pub mod template;
-pub use template::BlockTemplate;
+pub use template::{BlockTemplate, BlockTemplateError, BlockValue, ConflictPolicy, IncludeError};
 
 /// Builder payload signing utilities
 pub mod signature;
-use signature::sign_builder_message;
+use signature::{sign_builder_bid, sign_builder_message};
 
 /// Fallback Payload builder agent that leverages the engine API's
 /// `engine_newPayloadV3` response error to produce a valid payload.
 pub mod payload_builder;
 use payload_builder::FallbackPayloadBuilder;
 
+/// Ordering policies for the transactions of locally built payloads.
+pub mod ordering;
+pub use ordering::{InclusionKind, OrderingPolicy, PayloadTransaction};
+
 /// Interface for fetching payloads from the beacon node.
 pub mod payload_fetcher;
 pub use payload_fetcher::{LocalPayloadFetcher, PayloadFetcher};
 
+/// Fee recipient resolution for locally built payloads.
+pub mod fee_recipient;
+pub use fee_recipient::{FeeRecipientResolver, FeeRecipientSchedule};
+
+/// Filler transactions to use the spare gas of locally built payloads.
+pub mod filler;
+pub use filler::{FillerPool, MempoolFillerPool};
+
+/// Storage backends for the payloads built by the local builder.
+pub mod payload_store;
+pub use payload_store::{DiskPayloadStore, InMemoryPayloadStore, PayloadStore};
+
 /// Compatibility types and utilities between Alloy, Reth,
 /// Ethereum-consensus and other crates.
 #[doc(hidden)]
@@ -54,18 +75,39 @@ pub enum BuilderError {
     Jwt(#[from] alloy_rpc_types_engine::JwtError),
     #[error("Failed HTTP request: {0}")]
     Reqwest(#[from] reqwest::Error),
+    #[error("Failed engine API request: {0}")]
+    Http(#[from] crate::common::http::PostJsonError),
     #[error("Failed while fetching from RPC: {0}")]
     Transport(#[from] alloy::transports::TransportError),
     #[error("Failed in SSZ merkleization: {0}")]
     Merkleization(#[from] MerkleizationError),
     #[error("Failed while interacting with beacon client: {0}")]
     BeaconApi(#[from] beacon_api_client::Error),
-    #[error("Failed to parse hint from engine response: {0}")]
-    InvalidEngineHint(String),
+    #[error("No hint found in engine response: {0}")]
+    MissingHint(String),
+    #[error("Failed to parse {kind} hint from engine response: {response}")]
+    UnparseableHint { kind: &'static str, response: String },
+    #[error("Unexpected engine error: {0}")]
+    UnexpectedEngineError(String),
+    #[error("Engine API doesn't support the required methods: {}", .0.join(", "))]
+    MissingEngineCapabilities(Vec<String>),
     #[error("Failed to build payload: {0}")]
     Custom(String),
 }
 
+impl BuilderError {
+    /// Returns `true` if the error is specific to the engine API endpoint that was queried.
+    /// Only these failures are counted by the engine circuit breaker of the driver, which
+    /// rejects new commitments while the engine API is unhealthy.
+    ///
+    /// Missing or unparseable hints are not engine failures: they are caused by the payload
+    /// itself or by the hint format of the execution client. Neither are the failures of the
+    /// beacon node, e.g. when fetching the RANDAO value.
+    pub fn is_engine_failure(&self) -> bool {
+        matches!(self, Self::UnexpectedEngineError(_) | Self::Http(_) | Self::Jwt(_))
+    }
+}
+
 /// Local builder instance that can ingest a sealed header and
 /// create the corresponding builder bid ready for the Builder API.
 #[derive(Debug)]
@@ -79,86 +121,441 @@ pub struct LocalBuilder {
     /// Async fallback payload builder to generate valid payloads with
     /// the engine API's `engine_newPayloadV3` response error.
     fallback_builder: FallbackPayloadBuilder,
-    /// The last payload and bid that was built by the local builder.
-    payload_and_bid: Option<PayloadAndBid>,
+    /// Store of the last payload and bid that was built by the local builder.
+    payload_store: Box<dyn PayloadStore>,
+    /// Whether to rebuild the cached payloads on top of the new head when it changes.
+    reorg_rebuild: bool,
 }
 
 impl LocalBuilder {
     /// Create a new local builder with the given secret key.
     pub fn new(opts: &Opts, beacon_api_client: BeaconClient, genesis_time: u64) -> Self {
+        let payload_store: Box<dyn PayloadStore> = match &opts.payload_store_path {
+            Some(path) => Box::new(DiskPayloadStore::new(path)),
+            None => Box::new(InMemoryPayloadStore::default()),
+        };
+
         Self {
-            payload_and_bid: None,
+            payload_store,
             fallback_builder: FallbackPayloadBuilder::new(opts, beacon_api_client, genesis_time),
             secret_key: opts.builder_private_key.clone(),
             chain: opts.chain,
+            reorg_rebuild: opts.builder_reorg_rebuild,
         }
     }
 
+    /// Set a resolver to compute the fee recipient of each slot from its proposer
+    /// public key. The static fee recipient in the config is used as fallback.
+    pub fn with_fee_recipient_resolver(mut self, resolver: Arc<dyn FeeRecipientResolver>) -> Self {
+        self.fallback_builder.set_fee_recipient_resolver(resolver);
+        self
+    }
+
+    /// Set the local pool of filler transactions used to top up the spare gas of the
+    /// payloads after the committed transactions. It is only used if filler transactions
+    /// are enabled in the config.
+    pub fn with_filler_pool(mut self, pool: Arc<dyn FillerPool>) -> Self {
+        self.fallback_builder.set_filler_pool(pool);
+        self
+    }
+
     /// Build a new payload with the given transactions. This method will
     /// cache the payload in the local builder instance, and make it available
     pub async fn build_new_local_payload(
         &mut self,
         slot: u64,
+        proposer: Option<&PublicKey>,
         template: &BlockTemplate,
     ) -> Result<(), BuilderError> {
-        let transactions = template.as_signed_transactions();
-        let blobs_bundle = template.as_blobs_bundle();
-        let kzg_commitments = blobs_bundle.commitments.clone();
+        let payload_and_bid = self.build_payload(slot, proposer.cloned(), template.clone()).await?;
+        self.store_payload(slot, payload_and_bid);
+        Ok(())
+    }
 
-        // 1. build a fallback payload with the given transactions, on top of
-        // the current head of the chain
-        let block = self.fallback_builder.build_fallback_payload(slot, &transactions).await?;
+    /// Build a payload and its signed bid for the given slot with the given template, on top
+    /// of the current head of the chain. The build doesn't borrow the builder, so that it can
+    /// run in the background.
+    pub fn build_payload(
+        &self,
+        slot: u64,
+        proposer: Option<PublicKey>,
+        template: BlockTemplate,
+    ) -> impl Future<Output = Result<PayloadAndBid, BuilderError>> + Send + 'static {
+        let fallback_builder = self.fallback_builder.clone();
+        let (chain, secret_key) = (self.chain, self.secret_key.clone());
 
-        // NOTE: we use a big value for the bid to ensure it gets chosen by constraints client.
-        // the client has no way to actually verify this, and we don't need to trust
-        // an external relay as this block is self-built, so the fake bid value is fine.
-        //
-        // NOTE: we don't strictly need this. The validator & beacon nodes have options
-        // to ALWAYS prefer PBS blocks. This is a safety measure that doesn't hurt to keep.
-        let value = U256::from(100_000_000_000_000_000_000u128);
+        async move {
+            let transactions = template.as_payload_transactions();
 
-        let eth_payload = compat::to_consensus_execution_payload(&block);
-        let payload_and_blobs = PayloadAndBlobs { execution_payload: eth_payload, blobs_bundle };
+            // 1. build a fallback payload with the given transactions, on top of
+            // the current head of the chain
+            let block = fallback_builder
+                .build_fallback_payload(slot, proposer.as_ref(), transactions)
+                .await?;
 
-        // 2. create a signed builder bid with the sealed block header we just created
-        let eth_header = compat::to_execution_payload_header(&block, transactions);
+            // The builder may have reordered the transactions, the blobs must follow the block
+            // order
+            let transactions = block.body.transactions.clone();
+            let tx_hashes = transactions.iter().map(|tx| tx.hash()).collect::<Vec<_>>();
+            let blobs_bundle = template.as_ordered_blobs_bundle(&tx_hashes);
+            let kzg_commitments = blobs_bundle.commitments.clone();
 
-        // 3. sign the bid with the local builder's BLS key
-        let signed_bid = self.create_signed_builder_bid(value, eth_header, kzg_commitments)?;
+            // NOTE: we use a big value for the bid to ensure it gets chosen by constraints
+            // client. the client has no way to actually verify this, and we don't need to trust
+            // an external relay as this block is self-built, so the fake bid value is fine.
+            //
+            // NOTE: we don't strictly need this. The validator & beacon nodes have options
+            // to ALWAYS prefer PBS blocks. This is a safety measure that doesn't hurt to keep.
+            let value = U256::from(100_000_000_000_000_000_000u128);
 
-        // 4. prepare a get_payload response for when the beacon node will ask for it
-        let get_payload_response = GetPayloadResponse::from(payload_and_blobs);
+            // The actual value of the payload, used to compare it against relay bids
+            let base_fee = block.base_fee_per_gas.unwrap_or_default() as u128;
+            let blob_base_fee = calc_blob_gasprice(block.excess_blob_gas.unwrap_or_default());
+            let block_value = template.value(base_fee, blob_base_fee);
+            debug!(
+                slot,
+                priority_fees = %block_value.priority_fees,
+                burned_blob_fees = %block_value.burned_blob_fees,
+                "Estimated the value of the local payload"
+            );
+            let estimated_value = block_value.priority_fees;
 
-        self.payload_and_bid =
-            Some(PayloadAndBid { bid: signed_bid, payload: get_payload_response });
+            let eth_payload = compat::to_consensus_execution_payload(&block);
+            let payload_and_blobs =
+                PayloadAndBlobs { execution_payload: eth_payload, blobs_bundle };
 
-        Ok(())
+            // 2. create a signed builder bid with the sealed block header we just created
+            let eth_header =
+                compat::to_execution_payload_header(&block, transactions, chain.preset);
+
+            // 3. sign the bid with the local builder's BLS key
+            let signed_bid =
+                create_signed_builder_bid(&chain, &secret_key, value, eth_header, kzg_commitments)?;
+
+            // 4. prepare a get_payload response for when the beacon node will ask for it
+            let get_payload_response = GetPayloadResponse::from(payload_and_blobs);
+
+            Ok(PayloadAndBid {
+                bid: signed_bid,
+                payload: get_payload_response,
+                value: estimated_value,
+            })
+        }
     }
 
-    /// Get the cached payload and bid from the local builder, consuming the value.
+    /// Cache the given payload and bid as the local payload of the given slot, replacing the
+    /// previous one if any.
+    pub fn store_payload(&mut self, slot: u64, payload_and_bid: PayloadAndBid) {
+        if let Err(err) = self.payload_store.store(slot, payload_and_bid) {
+            warn!(?err, slot, "Failed to persist the local payload");
+        }
+    }
+
+    /// Check that the engine API used for fallback block building is reachable. The check
+    /// doesn't borrow the builder, so that it can run in the background.
+    pub fn check_engine_health(
+        &self,
+    ) -> impl Future<Output = Result<(), BuilderError>> + Send + 'static {
+        self.fallback_builder.check_engine_health()
+    }
+
+    /// Open the connection to the engine API used for fallback block building, and check
+    /// that it supports the required methods.
+    pub async fn warm_up_engine(&self) -> Result<(), BuilderError> {
+        self.fallback_builder.warm_up_engine().await
+    }
+
+    /// Get the cached payload and bid for the given slot from the local builder, consuming
+    /// the value.
+    #[inline]
+    pub fn get_cached_payload(&mut self, slot: u64) -> Option<PayloadAndBid> {
+        self.payload_store.take(slot).unwrap_or_else(|err| {
+            warn!(?err, slot, "Failed to load the local payload");
+            None
+        })
+    }
+
+    /// Get a copy of the cached payload and bid for the given slot from the local builder,
+    /// leaving it in the cache until the beacon node fetches the payload.
     #[inline]
-    pub fn get_cached_payload(&mut self) -> Option<PayloadAndBid> {
-        self.payload_and_bid.take()
+    pub fn peek_cached_payload(&self, slot: u64) -> Option<PayloadAndBid> {
+        self.payload_store.get(slot).unwrap_or_else(|err| {
+            warn!(?err, slot, "Failed to load the local payload");
+            None
+        })
     }
 
-    /// transform a sealed header into a signed builder bid using
-    /// the local builder's BLS key.
-    fn create_signed_builder_bid(
+    /// Returns the rebuild of the cached payload of the given slot on top of the head of the
+    /// chain, if rebuilding on head changes is enabled and a payload was built for the slot.
+    ///
+    /// If the head changed since the payload was built, e.g. because of a small reorg, the
+    /// payload is rebuilt with the given template and returned, to replace the cached one. Its
+    /// parent would be stale otherwise, and the block rejected. Nothing is returned if the
+    /// payload is still on top of the head, or if the rebuild fails. The rebuild doesn't
+    /// borrow the builder, so that the cached payload can still be served while it runs.
+    pub fn rebuild_on_head(
         &self,
-        value: U256,
-        header: ExecutionPayloadHeader,
-        blob_kzg_commitments: Vec<KzgCommitment>,
-    ) -> Result<SignedBuilderBid, BuilderError> {
-        // compat: convert from blst to ethereum consensus types
-        let pubkey = self.secret_key.sk_to_pk().to_bytes();
-        let consensus_pubkey = PublicKey::try_from(pubkey.as_slice()).expect("valid pubkey bytes");
-        let blob_kzg_commitments = List::try_from(blob_kzg_commitments).expect("valid list");
-
-        let message =
-            BuilderBid { header, blob_kzg_commitments, public_key: consensus_pubkey, value };
+        slot: u64,
+        proposer: Option<PublicKey>,
+        template: BlockTemplate,
+    ) -> Option<impl Future<Output = Option<PayloadAndBid>> + Send + 'static> {
+        if !self.reorg_rebuild {
+            return None;
+        }
+
+        let cached = self.peek_cached_payload(slot)?;
+        let fallback_builder = self.fallback_builder.clone();
+        let rebuild = self.build_payload(slot, proposer, template);
+
+        Some(async move {
+            let head = match fallback_builder.head_block_hash().await {
+                Ok(head) => head,
+                Err(err) => {
+                    warn!(?err, slot, "Failed to fetch the head block, keeping the local payload");
+                    return None;
+                }
+            };
+
+            let parent = &cached.bid.message.header.parent_hash;
+            if parent.as_ref() == head.as_slice() {
+                return None;
+            }
+
+            info!(slot, ?parent, %head, "Head changed since the local payload was built");
+            match rebuild.await {
+                Ok(payload_and_bid) => Some(payload_and_bid),
+                Err(err) => {
+                    warn!(?err, slot, "Failed to rebuild the local payload, keeping the stale one");
+                    None
+                }
+            }
+        })
+    }
+
+    /// Get the signed bid and the execution payload of the cached block with the given hash,
+    /// without consuming them. This allows an external tool to verify the signature of the
+    /// bid, and that the payload matches the header it commits to.
+    pub fn get_signed_block(
+        &self,
+        block_hash: &Hash32,
+    ) -> Option<(SignedBuilderBid, ExecutionPayload)> {
+        let payload_and_bid = self.payload_store.find(block_hash).unwrap_or_else(|err| {
+            warn!(?err, ?block_hash, "Failed to load the local payload");
+            None
+        })?;
+
+        let payload = payload_and_bid.payload.execution_payload().clone();
+        Some((payload_and_bid.bid, payload))
+    }
 
+    /// Create the submission of the cached payload of the given slot to the block-submission
+    /// endpoint of a relay, signed with the local builder's BLS key. The trace carries the
+    /// estimated value of the payload rather than the inflated value of the bid.
+    ///
+    /// Returns `None` if no payload was built for the slot.
+    pub fn create_bid_submission(
+        &self,
+        slot: u64,
+        proposer: &PublicKey,
+    ) -> Result<Option<SignedBidSubmission>, BuilderError> {
+        let Some(PayloadAndBid { bid, payload, value }) = self.peek_cached_payload(slot) else {
+            return Ok(None);
+        };
+
+        let GetPayloadResponse::Deneb(PayloadAndBlobs {
+            execution_payload: ExecutionPayload::Deneb(execution_payload),
+            blobs_bundle,
+        }) = payload
+        else {
+            return Err(BuilderError::Custom("only Deneb payloads can be submitted".to_string()));
+        };
+
+        let header = &bid.message.header;
+        let message = BidTrace {
+            slot,
+            parent_hash: header.parent_hash.clone(),
+            block_hash: header.block_hash.clone(),
+            builder_pubkey: bid.message.public_key.clone(),
+            proposer_pubkey: proposer.clone(),
+            proposer_fee_recipient: header.fee_recipient.clone(),
+            gas_limit: header.gas_limit,
+            gas_used: header.gas_used,
+            value,
+        };
         let signature = sign_builder_message(&self.chain, &self.secret_key, &message)?;
 
-        Ok(SignedBuilderBid { message, signature })
+        Ok(Some(SignedBidSubmission { message, execution_payload, blobs_bundle, signature }))
+    }
+}
+
+/// Transform a sealed header into a builder bid signed with the given BLS key.
+fn create_signed_builder_bid(
+    chain: &ChainConfig,
+    secret_key: &BlsSecretKeyWrapper,
+    value: U256,
+    header: ExecutionPayloadHeader,
+    blob_kzg_commitments: Vec<KzgCommitment>,
+) -> Result<SignedBuilderBid, BuilderError> {
+    // compat: convert from blst to ethereum consensus types
+    let pubkey = secret_key.sk_to_pk().to_bytes();
+    let consensus_pubkey = PublicKey::try_from(pubkey.as_slice()).expect("valid pubkey bytes");
+
+    let message = BuilderBid { header, public_key: consensus_pubkey, value, ..Default::default() }
+        .with_blob_commitments(blob_kzg_commitments);
+
+    let signature = sign_builder_bid(chain, secret_key, &message)?;
+
+    Ok(SignedBuilderBid { message, signature })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::eips::eip2718::Encodable2718;
+    use ethereum_consensus::{ssz::prelude::HashTreeRoot, types::mainnet::ExecutionPayload};
+
+    use crate::{
+        builder::{
+            signature::{verify_builder_bid, verify_signed_builder_message},
+            BlockTemplate,
+        },
+        client::RpcClient,
+        crypto::SignableBLS,
+        primitives::{BlsPublicKey, ConstraintsMessage, SignedConstraints},
+        test_util::{
+            create_signed_inclusion_request, default_test_transaction, harness::SelfBuildHarness,
+        },
+    };
+
+    #[tokio::test]
+    async fn test_get_signed_local_block() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut harness = SelfBuildHarness::new().await?;
+        let target_slot = 10;
+
+        let sender = harness.anvil.addresses()[0];
+        let sender_sk = harness.anvil.keys()[0].clone();
+        let tx = default_test_transaction(sender, None);
+        let request = create_signed_inclusion_request(&[tx], &sender_sk, target_slot).await?;
+        let constraint_signer = harness.constraint_signer();
+        let message = ConstraintsMessage::build(constraint_signer.pubkey(), request);
+        let signature = constraint_signer.sign_commit_boost_root(message.digest())?;
+        let mut template = BlockTemplate::default();
+        template.add_constraints(SignedConstraints { message, signature })?;
+        let local_builder = harness.driver.local_builder_mut();
+        local_builder.build_new_local_payload(target_slot, None, &template).await?;
+
+        // Look up the hash of the built block, leaving it in the cache
+        let cached = local_builder.peek_cached_payload(target_slot).expect("payload");
+        let block_hash = cached.payload.block_hash().clone();
+
+        assert!(local_builder.get_signed_block(&Default::default()).is_none());
+        let (bid, payload) = local_builder.get_signed_block(&block_hash).expect("block is cached");
+
+        // The bid is signed by the builder key
+        let builder_pubkey = harness.opts.builder_private_key.sk_to_pk().to_bytes();
+        let builder_pubkey = BlsPublicKey::try_from(builder_pubkey.as_slice())?;
+        verify_builder_bid(&bid, &builder_pubkey, &harness.opts.chain)?;
+
+        // The payload hashes to the header of the bid
+        let ExecutionPayload::Deneb(payload) = payload else {
+            eyre::bail!("unexpected payload fork");
+        };
+        let header = &bid.message.header;
+        assert_eq!(payload.block_hash, header.block_hash);
+        assert_eq!(payload.parent_hash, header.parent_hash);
+        assert_eq!(payload.state_root, header.state_root);
+        assert_eq!(payload.receipts_root, header.receipts_root);
+        assert_eq!(payload.gas_used, header.gas_used);
+        assert_eq!(payload.transactions.hash_tree_root()?, header.transactions_root);
+        assert_eq!(payload.withdrawals.hash_tree_root()?, header.withdrawals_root);
+
+        // The block is still served to the beacon node
+        assert!(harness.driver.local_builder_mut().get_cached_payload(target_slot).is_some());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_bid_submission() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut harness = SelfBuildHarness::new().await?;
+        let target_slot = 10;
+        let proposer = BlsPublicKey::try_from([0x11; 48].as_slice())?;
+
+        let local_builder = harness.driver.local_builder_mut();
+        assert!(local_builder.create_bid_submission(target_slot, &proposer)?.is_none());
+
+        local_builder.build_new_local_payload(target_slot, None, &BlockTemplate::default()).await?;
+        let cached = local_builder.peek_cached_payload(target_slot).expect("payload");
+        let submission =
+            local_builder.create_bid_submission(target_slot, &proposer)?.expect("submission");
+
+        // The trace describes the cached payload, with its estimated value
+        let trace = &submission.message;
+        assert_eq!(trace.slot, target_slot);
+        assert_eq!(&trace.block_hash, cached.payload.block_hash());
+        assert_eq!(trace.block_hash, submission.execution_payload.block_hash);
+        assert_eq!(trace.proposer_pubkey, proposer);
+        assert_eq!(trace.builder_pubkey, cached.bid.message.public_key);
+        assert_eq!(trace.value, cached.value);
+
+        // The trace is signed by the builder key
+        verify_signed_builder_message(
+            &harness.opts.chain,
+            &trace.builder_pubkey,
+            trace,
+            &submission.signature,
+        )?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_local_payload_on_head_change() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut harness = SelfBuildHarness::new().await?;
+        let rpc_client = RpcClient::new(harness.anvil.endpoint_url());
+        let target_slot = 10;
+        let keys = harness.anvil.keys().to_vec();
+        let sender = harness.anvil.addresses()[1];
+
+        let template = BlockTemplate::default();
+        let builder = harness.driver.local_builder_mut();
+        builder.reorg_rebuild = true;
+        builder.build_new_local_payload(target_slot, None, &template).await?;
+        let block_hash =
+            builder.peek_cached_payload(target_slot).unwrap().payload.block_hash().clone();
+
+        // The head didn't change, there is nothing to rebuild
+        let rebuild = builder.rebuild_on_head(target_slot, None, template.clone()).unwrap();
+        assert!(rebuild.await.is_none());
+
+        // Nor is there without a payload for the slot
+        assert!(builder.rebuild_on_head(target_slot + 1, None, template.clone()).is_none());
+
+        // A new block is mined on top of the parent of the payload
+        let tx = default_test_transaction(sender, None);
+        let request = create_signed_inclusion_request(&[tx], &keys[1], target_slot).await?;
+        rpc_client.send_raw_transaction(request.txs[0].encoded_2718().into()).await?;
+        let head = rpc_client.get_block(None, false).await?.header.hash;
+
+        // The payload is rebuilt on top of the new head in the background, while the stale one
+        // is still served
+        let rebuild = builder.rebuild_on_head(target_slot, None, template).unwrap();
+        let rebuild = tokio::spawn(rebuild);
+        let stale = builder.peek_cached_payload(target_slot).unwrap();
+        assert_eq!(stale.payload.block_hash(), &block_hash);
+
+        let payload = rebuild.await?.unwrap();
+        builder.store_payload(target_slot, payload);
+        let payload = builder.peek_cached_payload(target_slot).unwrap();
+        assert_ne!(payload.payload.block_hash(), &block_hash);
+        assert_eq!(payload.bid.message.header.parent_hash.as_ref(), head.as_slice());
+        assert_eq!(payload.payload.block_hash(), &payload.bid.message.header.block_hash);
+
+        Ok(())
     }
 }
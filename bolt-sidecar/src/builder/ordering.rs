@@ -0,0 +1,192 @@
+use std::collections::{HashMap, VecDeque};
+
+use alloy::primitives::Address;
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::primitives::FullTransaction;
+
+/// The position constraints of a transaction in a locally built payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum InclusionKind {
+    /// Committed transaction that must be included at the top of the block.
+    TopOfBlock,
+    /// Committed transaction that can be included anywhere in the block.
+    Committed,
+    /// Non-committed transaction used to fill the rest of the block.
+    Filler,
+}
+
+/// A transaction to be included in a locally built payload.
+#[derive(Debug, Clone)]
+pub struct PayloadTransaction {
+    /// The transaction itself.
+    pub tx: FullTransaction,
+    /// Where the transaction can be placed in the block.
+    pub kind: InclusionKind,
+}
+
+impl PayloadTransaction {
+    /// Create a new payload transaction of the given kind.
+    pub fn new(tx: FullTransaction, kind: InclusionKind) -> Self {
+        Self { tx, kind }
+    }
+}
+
+/// The policy used to order the transactions of a locally built payload.
+///
+/// Regardless of the policy, top-of-block transactions always come first in the
+/// order they were committed, followed by the other committed transactions and
+/// finally by the filler ones.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[clap(rename_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum OrderingPolicy {
+    /// Keep the transactions in the order they were committed.
+    Commitment,
+    /// Order the transactions by effective tip, in descending order. Transactions of
+    /// the same sender are kept in nonce order.
+    #[default]
+    PriorityFee,
+}
+
+impl OrderingPolicy {
+    /// Orders the given transactions according to the policy, using `base_fee` to compute
+    /// their effective tip. The result is deterministic for the same set of transactions.
+    pub fn apply(&self, txs: Vec<PayloadTransaction>, base_fee: u128) -> Vec<FullTransaction> {
+        let mut top = Vec::new();
+        let mut committed = Vec::new();
+        let mut filler = Vec::new();
+
+        for tx in txs {
+            match tx.kind {
+                InclusionKind::TopOfBlock => top.push(tx.tx),
+                InclusionKind::Committed => committed.push(tx.tx),
+                InclusionKind::Filler => filler.push(tx.tx),
+            }
+        }
+
+        if *self == Self::PriorityFee {
+            committed = order_by_tip(committed, base_fee);
+            filler = order_by_tip(filler, base_fee);
+        }
+
+        top.into_iter().chain(committed).chain(filler).collect()
+    }
+}
+
+/// Orders the transactions by effective tip in descending order, keeping the transactions
+/// of each sender in nonce order. Ties are broken by transaction hash.
+fn order_by_tip(txs: Vec<FullTransaction>, base_fee: u128) -> Vec<FullTransaction> {
+    let total = txs.len();
+
+    // Group the transactions by sender, sorted by nonce. Transactions with an unknown
+    // sender are placed in their own group.
+    let mut groups: Vec<VecDeque<FullTransaction>> = Vec::new();
+    let mut group_of_sender: HashMap<Address, usize> = HashMap::new();
+    for tx in txs {
+        let sender = tx.sender().copied().or_else(|| tx.recover_signer());
+        match sender.and_then(|sender| group_of_sender.get(&sender).copied()) {
+            Some(index) => groups[index].push_back(tx),
+            None => {
+                if let Some(sender) = sender {
+                    group_of_sender.insert(sender, groups.len());
+                }
+                groups.push(VecDeque::from([tx]));
+            }
+        }
+    }
+    for group in &mut groups {
+        group.make_contiguous().sort_by_key(|tx| tx.nonce());
+    }
+
+    // Repeatedly pick the best transaction among the next ones of each sender
+    let mut ordered = Vec::with_capacity(total);
+    while ordered.len() < total {
+        let best = groups
+            .iter()
+            .enumerate()
+            .filter_map(|(index, group)| group.front().map(|tx| (index, tx)))
+            .max_by(|(_, a), (_, b)| {
+                let tip_a = a.effective_tip_per_gas(base_fee).unwrap_or_default();
+                let tip_b = b.effective_tip_per_gas(base_fee).unwrap_or_default();
+                tip_a.cmp(&tip_b).then_with(|| b.hash().cmp(a.hash()))
+            })
+            .map(|(index, _)| index)
+            .expect("at least one transaction left");
+
+        ordered.extend(groups[best].pop_front());
+    }
+
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        eips::eip2718::Encodable2718,
+        network::{EthereumWallet, TransactionBuilder},
+        signers::local::PrivateKeySigner,
+    };
+
+    use crate::{primitives::FullTransaction, test_util::default_test_transaction};
+
+    use super::{InclusionKind, OrderingPolicy, PayloadTransaction};
+
+    async fn signed_tx(
+        signer: &PrivateKeySigner,
+        nonce: u64,
+        priority_fee: u128,
+    ) -> eyre::Result<FullTransaction> {
+        let sender = signer.address();
+        let wallet = EthereumWallet::from(signer.clone());
+        let tx = default_test_transaction(sender, Some(nonce))
+            .with_max_fee_per_gas(100_000_000_000)
+            .with_max_priority_fee_per_gas(priority_fee);
+
+        let mut tx = FullTransaction::decode_enveloped(tx.build(&wallet).await?.encoded_2718())?;
+        tx.sender = Some(sender);
+        Ok(tx)
+    }
+
+    #[tokio::test]
+    async fn test_priority_fee_ordering() -> eyre::Result<()> {
+        let alice = PrivateKeySigner::random();
+        let bob = PrivateKeySigner::random();
+        let base_fee = 1_000_000_000;
+
+        let top = signed_tx(&alice, 0, 1).await?;
+        let low = signed_tx(&bob, 0, 2_000_000_000).await?;
+        let high = signed_tx(&alice, 1, 5_000_000_000).await?;
+        // Highest tip of all, but must come after bob's nonce 0
+        let highest_next_nonce = signed_tx(&bob, 1, 9_000_000_000).await?;
+        let filler = signed_tx(&PrivateKeySigner::random(), 0, 50_000_000_000).await?;
+
+        let txs = vec![
+            PayloadTransaction::new(filler.clone(), InclusionKind::Filler),
+            PayloadTransaction::new(low.clone(), InclusionKind::Committed),
+            PayloadTransaction::new(highest_next_nonce.clone(), InclusionKind::Committed),
+            PayloadTransaction::new(high.clone(), InclusionKind::Committed),
+            PayloadTransaction::new(top.clone(), InclusionKind::TopOfBlock),
+        ];
+
+        let ordered = OrderingPolicy::PriorityFee.apply(txs.clone(), base_fee);
+        let hashes = ordered.iter().map(|tx| *tx.hash()).collect::<Vec<_>>();
+        let expected = [&top, &high, &low, &highest_next_nonce, &filler]
+            .iter()
+            .map(|tx| *tx.hash())
+            .collect::<Vec<_>>();
+        assert_eq!(hashes, expected);
+
+        // The commitment policy only moves top-of-block and filler transactions
+        let ordered = OrderingPolicy::Commitment.apply(txs, base_fee);
+        let hashes = ordered.iter().map(|tx| *tx.hash()).collect::<Vec<_>>();
+        let expected = [&top, &low, &highest_next_nonce, &high, &filler]
+            .iter()
+            .map(|tx| *tx.hash())
+            .collect::<Vec<_>>();
+        assert_eq!(hashes, expected);
+
+        Ok(())
+    }
+}
@@ -1,34 +1,68 @@
 use std::{
+    collections::{HashMap, HashSet},
     fmt,
+    future::Future,
+    sync::Arc,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use alloy::{
     consensus::{Header, EMPTY_OMMER_ROOT_HASH},
-    eips::{calc_excess_blob_gas, calc_next_block_base_fee, eip1559::BaseFeeParams},
+    eips::calc_excess_blob_gas,
     primitives::{Address, Bloom, Bytes, B256, B64, U256},
     rpc::types::{Block, Withdrawal, Withdrawals},
 };
 use alloy_rpc_types_engine::{Claims, ExecutionPayload, JwtSecret};
 use axum::http::HeaderValue;
 use beacon_api_client::{BlockId, StateId};
+use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
 use hex::FromHex;
 use regex::Regex;
-use reqwest::Url;
+use reqwest::{
+    header::{HeaderMap, HeaderName, AUTHORIZATION, USER_AGENT},
+    Url,
+};
 use reth_primitives::{proofs, BlockBody, SealedBlock, SealedHeader, TransactionSigned};
-use serde_json::Value;
-use tracing::trace;
+use serde_json::{json, Value};
+use tokio::time::Instant;
+use tracing::{debug, info, trace, warn};
 
 use super::{
     compat::{to_alloy_execution_payload, to_alloy_withdrawal},
+    fee_recipient::{FeeRecipientResolver, FeeRecipientSchedule},
+    filler::{select_filler_transactions, FillerPool},
+    ordering::{OrderingPolicy, PayloadTransaction},
     BuilderError,
 };
 
 use crate::{
     client::{BeaconClient, RpcClient},
-    config::Opts,
+    common::{
+        backoff::BackoffPolicy, http::post_json_with_backoff, secrets::JwtSecretConfig,
+        transactions::predict_next_basefee,
+    },
+    config::{ChainConfig, Opts},
+    state::SlotClock,
 };
 
+/// The timeout of each attempt of a request to the engine API.
+const ENGINE_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The highest maximum number of `engine_newPayloadV3` calls made to resolve the hints of a
+/// payload that can be configured, so that building a payload is always bounded.
+pub const MAX_HINT_ITERATIONS_CAP: usize = 100;
+
+/// The default maximum number of `engine_newPayloadV3` calls made to resolve the hints of a
+/// payload.
+pub const DEFAULT_MAX_HINT_ITERATIONS: usize = 20;
+
+/// The `User-Agent` of the requests to the engine API.
+const ENGINE_USER_AGENT: &str = concat!("bolt-sidecar/", env!("CARGO_PKG_VERSION"));
+
+/// The engine API methods used by the fallback payload builder, which the execution client
+/// must support.
+pub(crate) const REQUIRED_ENGINE_CAPABILITIES: [&str; 1] = ["engine_newPayloadV3"];
+
 /// Extra-data payload field used for locally built blocks, decoded in UTF-8.
 ///
 /// Corresponds to the string "Self-built with Bolt". It can be max 32 bytes
@@ -48,35 +82,109 @@ const DEFAULT_EXTRA_DATA: [u8; 20] = [
 ///
 /// Find more information about this process & its reasoning here:
 /// <https://github.com/chainbound/bolt/discussions/59>
+#[derive(Clone)]
 pub struct FallbackPayloadBuilder {
     extra_data: Bytes,
-    fee_recipient: Address,
+    fee_recipient: FeeRecipientSchedule,
+    ordering: OrderingPolicy,
+    include_filler_txs: bool,
+    filler_pool: Option<Arc<dyn FillerPool>>,
+    chain_id: u64,
     beacon_api_client: BeaconClient,
     execution_rpc_client: RpcClient,
     engine_hinter: EngineHinter,
-    slot_time: u64,
-    genesis_time: u64,
+    slot_clock: SlotClock,
+    max_hint_iterations: usize,
 }
 
 impl FallbackPayloadBuilder {
     /// Create a new fallback payload builder
     pub fn new(config: &Opts, beacon_api_client: BeaconClient, genesis_time: u64) -> Self {
-        let engine_hinter = EngineHinter {
-            client: reqwest::Client::new(),
-            jwt_hex: config.engine_jwt_hex.to_string(),
-            engine_rpc_url: config.engine_api_url.clone(),
-        };
+        let engine_hinter = EngineHinter::new(
+            config.engine_api_url.clone(),
+            config.engine_jwt_hex.to_string(),
+            config.engine_headers.iter().cloned(),
+        );
 
+        Self {
+            ordering: config.builder_ordering,
+            include_filler_txs: config.builder_filler_txs,
+            max_hint_iterations: config.builder_max_hint_iterations,
+            ..Self::with_endpoints(
+                &config.chain,
+                RpcClient::new(config.execution_api_url.clone()),
+                beacon_api_client,
+                engine_hinter,
+                config.fee_recipient,
+                genesis_time,
+            )
+        }
+    }
+
+    /// Create a new fallback payload builder on the given endpoints outside of a sidecar, e.g.
+    /// to check that an engine setup can build payloads. The transactions are included in the
+    /// default order, and the spare gas of the payloads isn't filled.
+    pub async fn connect(
+        chain: ChainConfig,
+        execution_api_url: Url,
+        beacon_api_url: Url,
+        engine_api_url: Url,
+        engine_jwt_hex: &JwtSecretConfig,
+        fee_recipient: Address,
+    ) -> Result<Self, BuilderError> {
+        let beacon_api_client = BeaconClient::new(beacon_api_url);
+        let genesis_time = beacon_api_client.get_genesis_details().await?.genesis_time;
+
+        Ok(Self::with_endpoints(
+            &chain,
+            RpcClient::new(execution_api_url),
+            beacon_api_client,
+            EngineHinter::new(engine_api_url, engine_jwt_hex.to_string(), []),
+            fee_recipient,
+            genesis_time,
+        ))
+    }
+
+    /// Create a new fallback payload builder on the given clients, with the default settings.
+    fn with_endpoints(
+        chain: &ChainConfig,
+        execution_rpc_client: RpcClient,
+        beacon_api_client: BeaconClient,
+        engine_hinter: EngineHinter,
+        fee_recipient: Address,
+        genesis_time: u64,
+    ) -> Self {
         Self {
             engine_hinter,
             extra_data: DEFAULT_EXTRA_DATA.into(),
-            fee_recipient: config.fee_recipient,
-            execution_rpc_client: RpcClient::new(config.execution_api_url.clone()),
-            slot_time: config.chain.slot_time(),
-            genesis_time,
+            fee_recipient: FeeRecipientSchedule::new(fee_recipient),
+            ordering: OrderingPolicy::default(),
+            include_filler_txs: false,
+            filler_pool: None,
+            chain_id: chain.chain_id(),
+            execution_rpc_client,
+            slot_clock: chain.slot_clock(genesis_time),
             beacon_api_client,
+            max_hint_iterations: DEFAULT_MAX_HINT_ITERATIONS,
         }
     }
+
+    /// Returns the slot clock of the chain the payloads are built for.
+    pub fn slot_clock(&self) -> &SlotClock {
+        &self.slot_clock
+    }
+
+    /// Set the resolver used to determine the fee recipient of each payload.
+    /// The configured fee recipient is kept as fallback.
+    pub fn set_fee_recipient_resolver(&mut self, resolver: Arc<dyn FeeRecipientResolver>) {
+        let default_recipient = self.fee_recipient.default_recipient();
+        self.fee_recipient = FeeRecipientSchedule::new(default_recipient).with_resolver(resolver);
+    }
+
+    /// Set the local pool used to fill the spare gas of the payloads, if enabled.
+    pub fn set_filler_pool(&mut self, pool: Arc<dyn FillerPool>) {
+        self.filler_pool = Some(pool);
+    }
 }
 
 /// Lightweight context struct to hold the necessary values for
@@ -109,10 +217,18 @@ struct Hints {
 impl FallbackPayloadBuilder {
     /// Build a minimal payload to be used as a fallback in case PBS relays fail
     /// to provide a valid payload that fulfills the commitments made by Bolt.
+    ///
+    /// If the `proposer` of the target slot is known, it is used to resolve
+    /// the fee recipient of the payload. The transactions are included in the
+    /// order given by the configured [OrderingPolicy].
+    ///
+    /// If filler transactions are enabled, the spare gas of the block is topped
+    /// up with transactions from the [FillerPool], after the given ones.
     pub async fn build_fallback_payload(
         &self,
         target_slot: u64,
-        transactions: &[TransactionSigned],
+        proposer: Option<&BlsPublicKey>,
+        mut transactions: Vec<PayloadTransaction>,
     ) -> Result<SealedBlock, BuilderError> {
         // We fetch the latest block to get the necessary parent values for the new block.
         // For the timestamp, we must use the one expected by the beacon chain instead, to
@@ -129,19 +245,38 @@ impl FallbackPayloadBuilder {
             self.beacon_api_client.get_beacon_block_root(BlockId::Head).await?.as_slice(),
         );
 
+        let base_fee = predict_next_basefee(
+            latest_block.header.gas_used,
+            latest_block.header.gas_limit,
+            latest_block.header.base_fee_per_gas.unwrap_or_default(),
+        );
+
+        if let Some(pool) = self.filler_pool.as_ref().filter(|_| self.include_filler_txs) {
+            let fillers = self
+                .get_filler_transactions(
+                    pool.as_ref(),
+                    target_slot,
+                    &transactions,
+                    latest_block.header.gas_limit,
+                    base_fee as u128,
+                )
+                .await;
+            transactions.extend(fillers);
+        }
+
+        let transactions = self
+            .ordering
+            .apply(transactions, base_fee as u128)
+            .into_iter()
+            .map(|tx| tx.into_inner().into_transaction())
+            .collect::<Vec<TransactionSigned>>();
+
         let versioned_hashes = transactions
             .iter()
             .flat_map(|tx| tx.blob_versioned_hashes())
             .flatten()
             .collect::<Vec<_>>();
 
-        let base_fee = calc_next_block_base_fee(
-            latest_block.header.gas_used,
-            latest_block.header.gas_limit,
-            latest_block.header.base_fee_per_gas.unwrap_or_default(),
-            BaseFeeParams::ethereum(),
-        );
-
         let excess_blob_gas = calc_excess_blob_gas(
             latest_block.header.excess_blob_gas.unwrap_or_default(),
             latest_block.header.blob_gas_used.unwrap_or_default(),
@@ -153,7 +288,9 @@ impl FallbackPayloadBuilder {
         // We must calculate the next block timestamp manually rather than rely on the
         // previous execution block, to cover the edge case where any previous slots have
         // been missed by the proposers immediately before us.
-        let block_timestamp = self.genesis_time + (target_slot * self.slot_time);
+        let block_timestamp = self.slot_clock.slot_start(target_slot).as_secs();
+
+        let fee_recipient = self.fee_recipient.fee_recipient_for(target_slot, proposer).await;
 
         let ctx = Context {
             base_fee,
@@ -162,22 +299,24 @@ impl FallbackPayloadBuilder {
             parent_beacon_block_root,
             prev_randao,
             extra_data: self.extra_data.clone(),
-            fee_recipient: self.fee_recipient,
-            transactions_root: proofs::calculate_transaction_root(transactions),
+            fee_recipient,
+            transactions_root: proofs::calculate_transaction_root(&transactions),
             withdrawals_root: proofs::calculate_withdrawals_root(&withdrawals),
             block_timestamp,
         };
 
         let body = BlockBody {
             ommers: Vec::new(),
-            transactions: transactions.to_vec(),
+            transactions,
             withdrawals: Some(Withdrawals::new(withdrawals)),
         };
 
+        // The payload is useless once its slot has passed, so the engine API calls aren't
+        // retried past it
+        let deadline = self.slot_clock.instant_at(self.slot_clock.slot_start(target_slot + 1));
+
         let mut hints = Hints::default();
-        let max_iterations = 20;
-        let mut i = 0;
-        loop {
+        for _ in 0..self.max_hint_iterations {
             let header = build_header_with_hints_and_context(&latest_block, &hints, &ctx);
 
             let sealed_hash = header.hash_slow();
@@ -190,7 +329,12 @@ impl FallbackPayloadBuilder {
 
             let engine_hint = self
                 .engine_hinter
-                .fetch_next_payload_hint(&exec_payload, &versioned_hashes, parent_beacon_block_root)
+                .fetch_next_payload_hint(
+                    &exec_payload,
+                    &versioned_hashes,
+                    parent_beacon_block_root,
+                    deadline,
+                )
                 .await?;
 
             match engine_hint {
@@ -218,16 +362,79 @@ impl FallbackPayloadBuilder {
 
                 EngineApiHint::ValidPayload => return Ok(sealed_block),
             }
+        }
 
-            if i > max_iterations {
-                return Err(BuilderError::Custom(
-                    "Too many iterations: Failed to fetch all missing header values from geth error messages"
-                        .to_string(),
-                ));
-            }
+        warn!(
+            max_iterations = self.max_hint_iterations,
+            "Failed to fetch all the missing header values from the engine hints"
+        );
+        Err(BuilderError::Custom("hint loop did not converge".to_string()))
+    }
+
+    /// Get the hash of the latest block, on top of which new payloads are built.
+    pub async fn head_block_hash(&self) -> Result<B256, BuilderError> {
+        Ok(self.execution_rpc_client.get_block(None, false).await?.header.hash)
+    }
+
+    /// Check that the engine API is reachable and accepts our JWT. The check doesn't borrow
+    /// the builder, so that it can run in the background.
+    pub fn check_engine_health(
+        &self,
+    ) -> impl Future<Output = Result<(), BuilderError>> + Send + 'static {
+        let engine_hinter = self.engine_hinter.clone();
+        async move { engine_hinter.exchange_capabilities().await.map(|_| ()) }
+    }
 
-            i += 1;
+    /// Open the connection to the engine API and check that it supports the methods used
+    /// to build payloads.
+    pub async fn warm_up_engine(&self) -> Result<(), BuilderError> {
+        self.engine_hinter.warm_up().await
+    }
+
+    /// Select the filler transactions from the pool that fit in the spare gas left by
+    /// the `committed` ones. Errors are logged and result in no filler transactions,
+    /// so that they never prevent the committed transactions from being included.
+    async fn get_filler_transactions(
+        &self,
+        pool: &dyn FillerPool,
+        target_slot: u64,
+        committed: &[PayloadTransaction],
+        gas_limit: u64,
+        base_fee: u128,
+    ) -> Vec<PayloadTransaction> {
+        let candidates = pool.pending_transactions(target_slot).await;
+        if candidates.is_empty() {
+            return Vec::new();
         }
+
+        let senders = candidates
+            .iter()
+            .filter_map(|tx| tx.sender().copied().or_else(|| tx.recover_signer()))
+            .collect::<HashSet<Address>>();
+
+        let mut accounts = HashMap::with_capacity(senders.len());
+        for sender in senders {
+            match self.execution_rpc_client.get_account_state(&sender, None).await {
+                Ok(account) => {
+                    accounts.insert(sender, account);
+                }
+                Err(err) => {
+                    warn!(?err, %sender, "Failed to fetch filler sender state, skipping fillers");
+                    return Vec::new();
+                }
+            }
+        }
+
+        let fillers = select_filler_transactions(
+            candidates,
+            committed,
+            &accounts,
+            gas_limit,
+            base_fee,
+            self.chain_id,
+        );
+        trace!(target_slot, count = fillers.len(), "Selected filler transactions");
+        fillers
     }
 
     /// Fetch the previous RANDAO value from the beacon chain.
@@ -282,71 +489,201 @@ pub(crate) enum EngineApiHint {
 /// Engine hinter struct that is responsible for fetching hints from the
 /// engine API to complete the sealed block. This struct is used by the
 /// fallback payload builder to fetch missing header values.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct EngineHinter {
     client: reqwest::Client,
     jwt_hex: String,
     engine_rpc_url: Url,
+    headers: HeaderMap,
 }
 
 impl EngineHinter {
+    /// Create a new engine hinter. The given headers are sent with every request to the
+    /// engine API, and override the default `User-Agent` if set.
+    pub fn new(
+        engine_rpc_url: Url,
+        jwt_hex: String,
+        extra_headers: impl IntoIterator<Item = (HeaderName, HeaderValue)>,
+    ) -> Self {
+        let mut headers =
+            HeaderMap::from_iter([(USER_AGENT, HeaderValue::from_static(ENGINE_USER_AGENT))]);
+        for (name, value) in extra_headers {
+            headers.insert(name, value);
+        }
+
+        Self { client: reqwest::Client::new(), jwt_hex, engine_rpc_url, headers }
+    }
+
     /// Fetch the next payload hint from the engine API to complete the sealed block.
+    /// The request isn't retried past the given deadline.
     pub async fn fetch_next_payload_hint(
         &self,
         exec_payload: &ExecutionPayload,
         versioned_hashes: &[B256],
         parent_beacon_root: B256,
+        deadline: Instant,
     ) -> Result<EngineApiHint, BuilderError> {
-        let auth_jwt = secret_to_bearer_header(&JwtSecret::from_hex(&self.jwt_hex)?);
+        let body = json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "method": "engine_newPayloadV3",
+            "params": [exec_payload, versioned_hashes, parent_beacon_root],
+        });
 
-        let body = format!(
-            r#"{{"id":1,"jsonrpc":"2.0","method":"engine_newPayloadV3","params":[{}, {}, "{:?}"]}}"#,
-            serde_json::to_string(&exec_payload)?,
-            serde_json::to_string(&versioned_hashes)?,
-            parent_beacon_root
-        );
+        let policy = BackoffPolicy::default().with_deadline(deadline);
+        let raw_hint = self.post_engine(&body, policy).await?.to_string();
 
-        let raw_hint = self
-            .client
-            .post(self.engine_rpc_url.as_str())
-            .header("Content-Type", "application/json")
-            .header("Authorization", auth_jwt.clone())
-            .body(body)
-            .send()
-            .await?
-            .text()
-            .await?;
-
-        let Some(hint_value) = parse_geth_response(&raw_hint) else {
-            // If the hint is not found, it means that we likely got a VALID
-            // payload response or an error message that we can't parse.
-            if raw_hint.contains("\"status\":\"VALID\"") {
-                return Ok(EngineApiHint::ValidPayload);
-            }
-            return Err(BuilderError::InvalidEngineHint(raw_hint));
+        trace!("engine hint: {:?}", raw_hint);
+
+        parse_engine_hint(&raw_hint)
+    }
+
+    /// Call `engine_exchangeCapabilities` with the methods we need, returning the methods
+    /// supported by the engine. This also checks that the engine API is reachable and
+    /// authenticated.
+    pub async fn exchange_capabilities(&self) -> Result<Vec<String>, BuilderError> {
+        let body = json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "method": "engine_exchangeCapabilities",
+            "params": [REQUIRED_ENGINE_CAPABILITIES],
+        });
+
+        let response = self.post_engine(&body, BackoffPolicy::default()).await?;
+
+        let Some(result) = response.get("result") else {
+            return Err(BuilderError::Custom(format!("Unexpected engine response: {response}")));
         };
 
-        trace!("engine hint: {:?}", raw_hint);
+        Ok(serde_json::from_value(result.clone())?)
+    }
 
-        // Match the hint value to the corresponding header field and return it
-        if raw_hint.contains("blockhash mismatch") {
-            return Ok(EngineApiHint::BlockHash(B256::from_hex(hint_value)?));
-        } else if raw_hint.contains("invalid gas used") {
-            return Ok(EngineApiHint::GasUsed(hint_value.parse()?));
-        } else if raw_hint.contains("invalid merkle root") {
-            return Ok(EngineApiHint::StateRoot(B256::from_hex(hint_value)?));
-        } else if raw_hint.contains("invalid receipt root hash") {
-            return Ok(EngineApiHint::ReceiptsRoot(B256::from_hex(hint_value)?));
-        } else if raw_hint.contains("invalid bloom") {
-            return Ok(EngineApiHint::LogsBloom(Bloom::from_hex(&hint_value)?));
+    /// Call `engine_getClientVersionV1` to identify the execution client behind the engine.
+    ///
+    /// The method requires the commit the sidecar is built from, so it fails if the sidecar
+    /// wasn't built from a git checkout.
+    pub async fn get_client_version(&self) -> Result<Value, BuilderError> {
+        let Some(commit) = option_env!("BOLT_SIDECAR_GIT_COMMIT") else {
+            return Err(BuilderError::Custom("Unknown commit of the sidecar build".to_string()));
         };
 
-        Err(BuilderError::Custom(
-            "Unexpected: failed to parse any hint from engine response".to_string(),
-        ))
+        let body = json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "method": "engine_getClientVersionV1",
+            "params": [{
+                "code": "BO",
+                "name": "bolt-sidecar",
+                "version": env!("CARGO_PKG_VERSION"),
+                "commit": format!("0x{commit}"),
+            }],
+        });
+
+        let response = self.post_engine(&body, BackoffPolicy::default()).await?;
+
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| BuilderError::Custom(format!("Unexpected engine response: {response}")))
+    }
+
+    /// Open the connection to the engine API and check that it supports all the
+    /// [REQUIRED_ENGINE_CAPABILITIES]. The connection is then kept alive by the HTTP client,
+    /// so that the first payload built doesn't pay for its setup.
+    pub async fn warm_up(&self) -> Result<(), BuilderError> {
+        let capabilities = self.exchange_capabilities().await?;
+
+        let missing = REQUIRED_ENGINE_CAPABILITIES
+            .iter()
+            .filter(|method| !capabilities.iter().any(|c| c == *method))
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            return Err(BuilderError::MissingEngineCapabilities(missing));
+        }
+
+        // Not all execution clients implement this method, so it's only informative
+        match self.get_client_version().await {
+            Ok(version) => info!(%version, "Connected to the engine API"),
+            Err(err) => debug!(?err, "Failed to get the client version of the engine API"),
+        }
+
+        Ok(())
+    }
+
+    /// Post the given JSON-RPC request to the engine API, authenticated with a fresh JWT,
+    /// retrying transient failures according to the given backoff `policy`.
+    async fn post_engine(
+        &self,
+        body: &Value,
+        policy: BackoffPolicy,
+    ) -> Result<Value, BuilderError> {
+        let auth_jwt = secret_to_bearer_header(&JwtSecret::from_hex(&self.jwt_hex)?);
+        let mut headers = self.headers.clone();
+        headers.insert(AUTHORIZATION, auth_jwt);
+
+        Ok(post_json_with_backoff(
+            &self.client,
+            self.engine_rpc_url.clone(),
+            headers,
+            body,
+            ENGINE_REQUEST_TIMEOUT,
+            policy,
+        )
+        .await?)
     }
 }
 
+/// A parser of the hint value carried by an engine API error message.
+type HintParser = fn(&str) -> Option<EngineApiHint>;
+
+/// The error messages of the engine API that carry a hint, with the header field they hint
+/// and the parser of their value.
+const ENGINE_HINT_MESSAGES: [(&str, &str, HintParser); 5] = [
+    ("blockhash mismatch", "block hash", |v| B256::from_hex(v).ok().map(EngineApiHint::BlockHash)),
+    ("invalid gas used", "gas used", |v| v.parse().ok().map(EngineApiHint::GasUsed)),
+    ("invalid merkle root", "state root", |v| B256::from_hex(v).ok().map(EngineApiHint::StateRoot)),
+    ("invalid receipt root hash", "receipts root", |v| {
+        B256::from_hex(v).ok().map(EngineApiHint::ReceiptsRoot)
+    }),
+    ("invalid bloom", "logs bloom", |v| Bloom::from_hex(v).ok().map(EngineApiHint::LogsBloom)),
+];
+
+/// Parse the hint from a raw `engine_newPayloadV3` response.
+///
+/// Fails with [BuilderError::UnparseableHint] if the response carries a known hint message
+/// whose value can't be parsed, with [BuilderError::UnexpectedEngineError] if the engine
+/// returned any other error, and with [BuilderError::MissingHint] if the payload was
+/// neither valid nor rejected with a hint.
+pub(crate) fn parse_engine_hint(raw_hint: &str) -> Result<EngineApiHint, BuilderError> {
+    let Some((_, kind, parse_hint)) =
+        ENGINE_HINT_MESSAGES.into_iter().find(|(message, ..)| raw_hint.contains(message))
+    else {
+        if raw_hint.contains("\"status\":\"VALID\"") {
+            return Ok(EngineApiHint::ValidPayload);
+        }
+
+        return match serde_json::from_str::<Value>(raw_hint) {
+            Ok(response) if response.get("error").is_none() => {
+                Err(BuilderError::MissingHint(raw_hint.to_string()))
+            }
+            Ok(response) => {
+                let error = &response["error"];
+                let message = error.get("message").and_then(Value::as_str).map(str::to_string);
+                Err(BuilderError::UnexpectedEngineError(
+                    message.unwrap_or_else(|| error.to_string()),
+                ))
+            }
+            Err(_) => Err(BuilderError::UnexpectedEngineError(raw_hint.to_string())),
+        };
+    };
+
+    let unparseable = || BuilderError::UnparseableHint { kind, response: raw_hint.to_string() };
+    let hint_value = parse_geth_response(raw_hint).ok_or_else(unparseable)?;
+
+    parse_hint(&hint_value).ok_or_else(unparseable)
+}
+
 /// Parse the hint value from the engine response.
 /// An example error message from the engine API looks like this:
 /// ```text
@@ -425,6 +762,9 @@ impl fmt::Debug for FallbackPayloadBuilder {
         f.debug_struct("FallbackPayloadBuilder")
             .field("extra_data", &self.extra_data)
             .field("fee_recipient", &self.fee_recipient)
+            .field("ordering", &self.ordering)
+            .field("include_filler_txs", &self.include_filler_txs)
+            .field("max_hint_iterations", &self.max_hint_iterations)
             .field("engine_hinter", &self.engine_hinter)
             .finish()
     }
@@ -432,21 +772,43 @@ impl fmt::Debug for FallbackPayloadBuilder {
 
 #[cfg(test)]
 mod tests {
-    use std::time::{SystemTime, UNIX_EPOCH};
+    use std::{
+        sync::{Arc, Mutex},
+        time::{SystemTime, UNIX_EPOCH},
+    };
 
     use alloy::{
-        eips::eip2718::{Decodable2718, Encodable2718},
+        eips::eip2718::Encodable2718,
         network::{EthereumWallet, TransactionBuilder},
         primitives::{hex, Address},
         signers::{k256::ecdsa::SigningKey, local::PrivateKeySigner},
     };
+    use axum::{extract::State, http::HeaderValue, routing::post, Json, Router};
     use beacon_api_client::mainnet::Client as BeaconClient;
-    use reth_primitives::TransactionSigned;
+    use clap::Parser;
+    use reqwest::{
+        header::{HeaderMap, HeaderName, AUTHORIZATION, CONTENT_TYPE, USER_AGENT},
+        Url,
+    };
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
     use tracing::warn;
 
     use crate::{
-        builder::payload_builder::FallbackPayloadBuilder,
-        test_util::{default_test_transaction, get_test_config},
+        builder::{
+            ordering::{InclusionKind, PayloadTransaction},
+            payload_builder::{
+                parse_engine_hint, EngineApiHint, EngineHinter, FallbackPayloadBuilder,
+                ENGINE_USER_AGENT, MAX_HINT_ITERATIONS_CAP, REQUIRED_ENGINE_CAPABILITIES,
+            },
+            BuilderError,
+        },
+        common::secrets::{BlsSecretKeyWrapper, EcdsaSecretKeyWrapper, JwtSecretConfig},
+        config::Opts,
+        primitives::FullTransaction,
+        test_util::{
+            default_test_transaction, get_test_config, harness::spawn_mock_beacon, launch_anvil,
+        },
     };
 
     #[tokio::test]
@@ -472,18 +834,186 @@ mod tests {
         let tx = default_test_transaction(addy, Some(3)).with_chain_id(1);
         let tx_signed = tx.build(&wallet).await?;
         let raw_encoded = tx_signed.encoded_2718();
-        let tx_signed_reth = TransactionSigned::decode_2718(&mut raw_encoded.as_slice())?;
+        let tx = FullTransaction::decode_enveloped(raw_encoded)?;
 
         let slot = genesis_time
             + (SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() / cfg.chain.slot_time())
             + 1;
 
-        let block = builder.build_fallback_payload(slot, &[tx_signed_reth]).await?;
+        let txs = vec![PayloadTransaction::new(tx, InclusionKind::Committed)];
+        let block = builder.build_fallback_payload(slot, None, txs).await?;
         assert_eq!(block.body.transactions.len(), 1);
 
         Ok(())
     }
 
+    #[test]
+    fn test_parse_engine_hint_errors() {
+        let response = |result: &str| format!(r#"{{"jsonrpc":"2.0","id":1,"result":{result}}}"#);
+
+        let valid = response(r#"{"status":"VALID","validationError":null}"#);
+        assert!(matches!(parse_engine_hint(&valid), Ok(EngineApiHint::ValidPayload)));
+
+        let gas_used = response(
+            r#"{"status":"INVALID","validationError":"invalid gas used (remote: 0 local: 21000)"}"#,
+        );
+        assert!(matches!(parse_engine_hint(&gas_used), Ok(EngineApiHint::GasUsed(21000))));
+
+        // A known hint message with a value that can't be parsed
+        let bad_gas_used = response(
+            r#"{"status":"INVALID","validationError":"invalid gas used (remote: 0 local: 0x5208)"}"#,
+        );
+        let err = parse_engine_hint(&bad_gas_used).unwrap_err();
+        assert!(matches!(err, BuilderError::UnparseableHint { kind: "gas used", .. }));
+        assert!(!err.is_engine_failure());
+
+        let no_block_hash =
+            r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"blockhash mismatch"}}"#;
+        let err = parse_engine_hint(no_block_hash).unwrap_err();
+        assert!(matches!(err, BuilderError::UnparseableHint { kind: "block hash", .. }));
+
+        // The payload is neither valid nor rejected with a hint
+        let syncing = response(r#"{"status":"SYNCING","validationError":null}"#);
+        let err = parse_engine_hint(&syncing).unwrap_err();
+        assert!(matches!(err, BuilderError::MissingHint(_)));
+        assert!(!err.is_engine_failure());
+
+        // Errors unrelated to the payload, which another engine may not return
+        let unauthorized =
+            r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"invalid token"}}"#;
+        let err = parse_engine_hint(unauthorized).unwrap_err();
+        assert!(matches!(&err, BuilderError::UnexpectedEngineError(msg) if msg == "invalid token"));
+        assert!(err.is_engine_failure());
+
+        let err = parse_engine_hint("502 Bad Gateway").unwrap_err();
+        assert!(matches!(err, BuilderError::UnexpectedEngineError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_engine_request_headers() -> eyre::Result<()> {
+        type Captured = Arc<Mutex<Option<HeaderMap>>>;
+
+        async fn capture(State(captured): State<Captured>, headers: HeaderMap) -> Json<Value> {
+            *captured.lock().unwrap() = Some(headers);
+            Json(json!({ "jsonrpc": "2.0", "id": 1, "result": [] }))
+        }
+
+        let captured = Captured::default();
+        let router = Router::new().route("/", post(capture)).with_state(captured.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = Url::parse(&format!("http://{}", listener.local_addr()?))?;
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let extra_headers =
+            [(HeaderName::from_static("x-engine-route"), HeaderValue::from_static("geth-primary"))];
+        let hinter = EngineHinter::new(url, hex::encode([1u8; 32]), extra_headers);
+        hinter.exchange_capabilities().await?;
+
+        let headers = captured.lock().unwrap().take().expect("request received");
+        assert_eq!(headers[CONTENT_TYPE], "application/json");
+        assert_eq!(headers[USER_AGENT], ENGINE_USER_AGENT);
+        assert_eq!(headers["x-engine-route"], "geth-primary");
+        assert!(headers[AUTHORIZATION].to_str()?.starts_with("Bearer "));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_engine_warm_up() -> eyre::Result<()> {
+        /// Spawns a mock engine advertising the given capabilities.
+        async fn spawn_engine(capabilities: Vec<&'static str>) -> eyre::Result<Url> {
+            async fn handle(
+                State(capabilities): State<Vec<&'static str>>,
+                Json(body): Json<Value>,
+            ) -> Json<Value> {
+                let result = match body["method"].as_str() {
+                    Some("engine_exchangeCapabilities") => json!(capabilities),
+                    _ => json!([{ "code": "GE", "name": "Geth", "version": "1.14.11" }]),
+                };
+                Json(json!({ "jsonrpc": "2.0", "id": body["id"], "result": result }))
+            }
+
+            let router = Router::new().route("/", post(handle)).with_state(capabilities);
+            let listener = TcpListener::bind("127.0.0.1:0").await?;
+            let url = Url::parse(&format!("http://{}", listener.local_addr()?))?;
+            tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+            Ok(url)
+        }
+
+        let jwt_hex = hex::encode([1u8; 32]);
+        let no_headers = Vec::<(HeaderName, HeaderValue)>::new;
+
+        let url = spawn_engine(vec!["engine_forkchoiceUpdatedV3", "engine_newPayloadV3"]).await?;
+        let hinter = EngineHinter::new(url, jwt_hex.clone(), no_headers());
+        hinter.warm_up().await?;
+
+        // An engine without the required methods fails the warm-up, naming them
+        let url = spawn_engine(vec!["engine_newPayloadV2"]).await?;
+        let hinter = EngineHinter::new(url, jwt_hex, no_headers());
+        let err = hinter.warm_up().await.unwrap_err();
+        assert!(matches!(
+            &err,
+            BuilderError::MissingEngineCapabilities(missing)
+                if missing == &REQUIRED_ENGINE_CAPABILITIES
+        ));
+        assert!(err.to_string().contains("engine_newPayloadV3"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hint_loop_does_not_converge() -> eyre::Result<()> {
+        type Calls = Arc<Mutex<usize>>;
+
+        // A misbehaving engine, always hinting another gas used than the one of the payload
+        async fn new_payload(State(calls): State<Calls>, Json(body): Json<Value>) -> Json<Value> {
+            *calls.lock().unwrap() += 1;
+            let gas_used = body["params"][0]["gasUsed"].as_str().unwrap().trim_start_matches("0x");
+            let gas_used = u64::from_str_radix(gas_used, 16).unwrap();
+
+            let error = format!("invalid gas used (remote: {gas_used} local: {})", gas_used + 1);
+            let result = json!({ "status": "INVALID", "validationError": error });
+            Json(json!({ "jsonrpc": "2.0", "id": body["id"], "result": result }))
+        }
+
+        let calls = Calls::default();
+        let router = Router::new().route("/", post(new_payload)).with_state(calls.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let engine_url = Url::parse(&format!("http://{}", listener.local_addr()?))?;
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let anvil = launch_anvil();
+        let beacon_url = spawn_mock_beacon(Default::default()).await;
+        let parse_opts = |max_hint_iterations: usize| {
+            Opts::try_parse_from([
+                "bolt-sidecar".to_string(),
+                format!("--execution-api-url={}", anvil.endpoint_url()),
+                format!("--beacon-api-url={beacon_url}"),
+                format!("--engine-api-url={engine_url}"),
+                format!("--engine-jwt-hex={}", JwtSecretConfig::default()),
+                format!("--fee-recipient={}", Address::ZERO),
+                format!("--builder-private-key={}", BlsSecretKeyWrapper::random()),
+                format!("--commitment-private-key={}", EcdsaSecretKeyWrapper::random()),
+                format!("--constraint-private-key={}", BlsSecretKeyWrapper::random()),
+                format!("--builder-max-hint-iterations={max_hint_iterations}"),
+            ])
+        };
+        let opts = parse_opts(3)?;
+
+        let builder = FallbackPayloadBuilder::new(&opts, BeaconClient::new(beacon_url), 0);
+        let slot = builder.slot_clock().current_slot() + 1;
+        let err = builder.build_fallback_payload(slot, None, Vec::new()).await.unwrap_err();
+        assert!(matches!(&err, BuilderError::Custom(msg) if msg == "hint loop did not converge"));
+        assert_eq!(*calls.lock().unwrap(), 3);
+
+        // A configured maximum out of range is rejected
+        assert!(parse_opts(0).is_err());
+        assert!(parse_opts(MAX_HINT_ITERATIONS_CAP + 1).is_err());
+        assert_eq!(parse_opts(MAX_HINT_ITERATIONS_CAP)?.builder_max_hint_iterations, 100);
+
+        Ok(())
+    }
+
     #[test]
     fn test_empty_el_withdrawals_root() {
         // Withdrawal root in the execution layer header is MPT.
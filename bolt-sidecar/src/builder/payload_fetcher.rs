@@ -17,12 +17,12 @@ impl LocalPayloadFetcher {
     }
 }
 
-#[async_trait::async_trait]
-impl PayloadFetcher for LocalPayloadFetcher {
-    async fn fetch_payload(&self, slot: u64) -> Option<PayloadAndBid> {
+impl LocalPayloadFetcher {
+    /// Send a fetch request for the payload of the given slot, and wait for its response.
+    async fn request_payload(&self, slot: u64, take: bool) -> Option<PayloadAndBid> {
         let (response_tx, response_rx) = oneshot::channel();
 
-        let fetch_params = FetchPayloadRequest { response_tx, slot };
+        let fetch_params = FetchPayloadRequest { response_tx, slot, take };
         self.tx.send(fetch_params).await.ok()?;
 
         match response_rx.await {
@@ -35,11 +35,25 @@ impl PayloadFetcher for LocalPayloadFetcher {
     }
 }
 
+#[async_trait::async_trait]
+impl PayloadFetcher for LocalPayloadFetcher {
+    async fn fetch_payload(&self, slot: u64) -> Option<PayloadAndBid> {
+        self.request_payload(slot, false).await
+    }
+
+    async fn take_payload(&self, slot: u64) -> Option<PayloadAndBid> {
+        self.request_payload(slot, true).await
+    }
+}
+
 /// Interface for fetching payloads for the builder.
 #[async_trait::async_trait]
 pub trait PayloadFetcher {
-    /// Fetch a payload for the given slot.
+    /// Fetch a payload for the given slot, leaving it available for later requests.
     async fn fetch_payload(&self, slot: u64) -> Option<PayloadAndBid>;
+
+    /// Take the payload of the given slot, once it's served to the beacon node.
+    async fn take_payload(&self, slot: u64) -> Option<PayloadAndBid>;
 }
 
 /// A payload fetcher that does nothing, used for testing.
@@ -54,4 +68,9 @@ impl PayloadFetcher for NoopPayloadFetcher {
         tracing::info!(slot, "Fetch payload called");
         None
     }
+
+    async fn take_payload(&self, slot: u64) -> Option<PayloadAndBid> {
+        tracing::info!(slot, "Take payload called");
+        None
+    }
 }
@@ -0,0 +1,254 @@
+use std::{
+    fmt::Debug,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use ethereum_consensus::deneb::Hash32;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tracing::{error, warn};
+
+use crate::primitives::PayloadAndBid;
+
+/// Errors that can occur while storing or loading a cached payload.
+#[derive(Debug, thiserror::Error)]
+pub enum PayloadStoreError {
+    /// Failed to read or write the store file.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// Failed to de/serialize the payload.
+    #[error("Failed to de/serialize payload: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Storage for the last payload built by the local builder, until the beacon node fetches it.
+pub trait PayloadStore: Debug + Send + Sync {
+    /// Store the payload built for the given slot, replacing any previous one.
+    fn store(&mut self, slot: u64, payload: PayloadAndBid) -> Result<(), PayloadStoreError>;
+
+    /// Get a copy of the payload built for the given slot, if any, leaving it in the store.
+    fn get(&self, slot: u64) -> Result<Option<PayloadAndBid>, PayloadStoreError>;
+
+    /// Take the payload built for the given slot, if any, removing it from the store.
+    fn take(&mut self, slot: u64) -> Result<Option<PayloadAndBid>, PayloadStoreError>;
+
+    /// Get a copy of the stored payload with the given block hash, if any, leaving it in
+    /// the store.
+    fn find(&self, block_hash: &Hash32) -> Result<Option<PayloadAndBid>, PayloadStoreError>;
+}
+
+/// A payload store keeping the payload in memory only. This is the default.
+#[derive(Debug, Default)]
+pub struct InMemoryPayloadStore {
+    payload: Option<(u64, PayloadAndBid)>,
+}
+
+impl PayloadStore for InMemoryPayloadStore {
+    fn store(&mut self, slot: u64, payload: PayloadAndBid) -> Result<(), PayloadStoreError> {
+        self.payload = Some((slot, payload));
+        Ok(())
+    }
+
+    fn get(&self, slot: u64) -> Result<Option<PayloadAndBid>, PayloadStoreError> {
+        Ok(self
+            .payload
+            .as_ref()
+            .filter(|(stored_slot, _)| *stored_slot == slot)
+            .map(|(_, payload)| payload.clone()))
+    }
+
+    fn take(&mut self, slot: u64) -> Result<Option<PayloadAndBid>, PayloadStoreError> {
+        match self.payload.take() {
+            Some((stored_slot, payload)) if stored_slot == slot => Ok(Some(payload)),
+            other => {
+                self.payload = other;
+                Ok(None)
+            }
+        }
+    }
+
+    fn find(&self, block_hash: &Hash32) -> Result<Option<PayloadAndBid>, PayloadStoreError> {
+        Ok(self
+            .payload
+            .as_ref()
+            .filter(|(_, payload)| payload.payload.block_hash() == block_hash)
+            .map(|(_, payload)| payload.clone()))
+    }
+}
+
+/// The payload of a slot, as stored on disk.
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredPayload {
+    slot: u64,
+    payload: PayloadAndBid,
+}
+
+/// A write to the file of a [DiskPayloadStore], applied by its writer task.
+#[derive(Debug)]
+enum StoreCommand {
+    Write(StoredPayload),
+    Clear,
+}
+
+/// A payload store backed by a file, so that the payload survives a restart of the sidecar
+/// within its slot.
+///
+/// The payload is served from memory: the file is only read when the store is created, i.e.
+/// after a restart. Writes to the file are queued to a task which applies them in order and
+/// off the caller's task, so that the file I/O never stalls the driver loop.
+#[derive(Debug)]
+pub struct DiskPayloadStore {
+    path: PathBuf,
+    memory: InMemoryPayloadStore,
+    tx: mpsc::UnboundedSender<StoreCommand>,
+}
+
+impl DiskPayloadStore {
+    /// Create a new store backed by the file at the given path, loading the payload it holds
+    /// if any, and spawn its writer task. The file is created on the first write if it
+    /// doesn't exist.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+
+        let mut memory = InMemoryPayloadStore::default();
+        match load(&path) {
+            Ok(Some(stored)) => memory.payload = Some((stored.slot, stored.payload)),
+            Ok(None) => {}
+            Err(err) => warn!(?err, ?path, "Failed to load the payload store file"),
+        }
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let file_path = path.clone();
+        tokio::spawn(async move {
+            while let Some(command) = rx.recv().await {
+                let path = file_path.clone();
+                match tokio::task::spawn_blocking(move || apply(&path, command)).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(err)) => error!(?err, "Failed to update the payload store file"),
+                    Err(err) => error!(?err, "Payload store update task failed"),
+                }
+            }
+        });
+
+        Self { path, memory, tx }
+    }
+
+    /// Returns the path of the store file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn send(&self, command: StoreCommand) {
+        if self.tx.send(command).is_err() {
+            error!("The payload store task is not running");
+        }
+    }
+}
+
+/// Load the payload in the store file at the given path, if any.
+fn load(path: &Path) -> Result<Option<StoredPayload>, PayloadStoreError> {
+    match fs::read(path) {
+        Ok(contents) => Ok(Some(serde_json::from_slice(&contents)?)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Apply a write command to the store file at the given path.
+fn apply(path: &Path, command: StoreCommand) -> Result<(), PayloadStoreError> {
+    match command {
+        StoreCommand::Write(stored) => {
+            let contents = serde_json::to_vec(&stored)?;
+
+            // Write to a temporary file first so that the store is never left half-written
+            let tmp_path = path.with_extension("tmp");
+            fs::write(&tmp_path, contents)?;
+            fs::rename(tmp_path, path)?;
+        }
+        StoreCommand::Clear => match fs::remove_file(path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        },
+    }
+
+    Ok(())
+}
+
+impl PayloadStore for DiskPayloadStore {
+    fn store(&mut self, slot: u64, payload: PayloadAndBid) -> Result<(), PayloadStoreError> {
+        self.memory.store(slot, payload.clone())?;
+        self.send(StoreCommand::Write(StoredPayload { slot, payload }));
+        Ok(())
+    }
+
+    fn get(&self, slot: u64) -> Result<Option<PayloadAndBid>, PayloadStoreError> {
+        self.memory.get(slot)
+    }
+
+    fn take(&mut self, slot: u64) -> Result<Option<PayloadAndBid>, PayloadStoreError> {
+        let payload = self.memory.take(slot)?;
+        if payload.is_some() {
+            self.send(StoreCommand::Clear);
+        }
+        Ok(payload)
+    }
+
+    fn find(&self, block_hash: &Hash32) -> Result<Option<PayloadAndBid>, PayloadStoreError> {
+        self.memory.find(block_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::U256;
+
+    use crate::{
+        primitives::{
+            BuilderBid, GetPayloadResponse, PayloadAndBid, PayloadAndBlobs, SignedBuilderBid,
+        },
+        test_util::harness::wait_until,
+    };
+
+    use super::{DiskPayloadStore, PayloadStore};
+
+    fn payload_and_bid(value: u64) -> PayloadAndBid {
+        PayloadAndBid {
+            bid: SignedBuilderBid {
+                message: BuilderBid { value: U256::from(value), ..Default::default() },
+                ..Default::default()
+            },
+            payload: GetPayloadResponse::from(PayloadAndBlobs::default()),
+            value: U256::from(value),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_disk_payload_store_survives_restart() -> eyre::Result<()> {
+        let path =
+            std::env::temp_dir().join(format!("bolt-payload-store-{}.json", rand::random::<u64>()));
+
+        // The payload is served from memory right away, and written in the background
+        let mut store = DiskPayloadStore::new(&path);
+        store.store(10, payload_and_bid(42))?;
+        assert!(store.get(10)?.is_some());
+        wait_until(|| async { path.exists() }).await?;
+        drop(store);
+
+        // A new store backed by the same file serves the payload of its slot only once
+        let mut store = DiskPayloadStore::new(&path);
+        assert!(store.take(9)?.is_none());
+
+        // Looking the payload up leaves it in the store
+        assert!(store.get(9)?.is_none());
+        assert_eq!(store.get(10)?.expect("payload is stored").value, U256::from(42));
+
+        let payload = store.take(10)?.expect("payload is stored");
+        assert_eq!(payload.value, U256::from(42));
+        assert_eq!(payload.bid.message.value, U256::from(42));
+        assert!(store.take(10)?.is_none());
+        wait_until(|| async { !path.exists() }).await?;
+
+        Ok(())
+    }
+}
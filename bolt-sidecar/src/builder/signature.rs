@@ -4,14 +4,32 @@ use blst::{
     BLST_ERROR,
 };
 use ethereum_consensus::{
-    crypto::Signature,
+    crypto::{PublicKey as BlsPublicKey, Signature},
     deneb::{compute_fork_data_root, Domain, DomainType, Root},
     ssz::prelude::{HashTreeRoot, MerkleizationError},
 };
+use thiserror::Error;
 use tree_hash::TreeHash;
 use tree_hash_derive::TreeHash;
 
-use crate::config::ChainConfig;
+use crate::{
+    config::ChainConfig,
+    primitives::{BuilderBid, SignedBuilderBid},
+};
+
+/// Errors that can occur when verifying a builder bid.
+#[derive(Debug, Error)]
+pub enum BidVerificationError {
+    /// The bid was not signed by the expected relay.
+    #[error("Bid public key {0:?} doesn't match the relay public key")]
+    PubkeyMismatch(BlsPublicKey),
+    /// The relay public key is not a valid BLS public key.
+    #[error("Invalid relay public key")]
+    InvalidPubkey,
+    /// The bid signature is not valid.
+    #[error("Invalid bid signature")]
+    InvalidSignature,
+}
 
 /// Sign a SSZ object with a BLS secret key, using the Application Builder domain
 /// for signing arbitrary builder-api messages in the out-of-protocol specifications.
@@ -23,15 +41,27 @@ pub fn sign_builder_message<T: HashTreeRoot>(
     sk: &SecretKey,
     msg: &T,
 ) -> Result<Signature, MerkleizationError> {
+    Ok(sign_builder_root(chain, sk, msg.hash_tree_root()?.0))
+}
+
+/// Sign a builder bid with a BLS secret key, using the Application Builder domain. The bid
+/// is merkleized with the list limits of the preset of the chain.
+pub fn sign_builder_bid(
+    chain: &ChainConfig,
+    sk: &SecretKey,
+    bid: &BuilderBid,
+) -> Result<Signature, MerkleizationError> {
+    Ok(sign_builder_root(chain, sk, bid.hash_tree_root_with_preset(chain.preset)?.0))
+}
+
+/// Sign the hash tree root of a SSZ object with a BLS secret key, using the Application
+/// Builder domain.
+fn sign_builder_root(chain: &ChainConfig, sk: &SecretKey, object_root: [u8; 32]) -> Signature {
     let domain = chain.application_builder_domain();
-    let object_root = msg.hash_tree_root()?.0;
     let signing_root = compute_signing_root(object_root, domain);
 
     let alloy_signature = sign_message(sk, &signing_root);
-    let consensus_signature =
-        Signature::try_from(alloy_signature.as_slice()).expect("valid signature bytes");
-
-    Ok(consensus_signature)
+    Signature::try_from(alloy_signature.as_slice()).expect("valid signature bytes")
 }
 
 /// Verify a SSZ object signed with a BLS public key, using the Application Builder domain
@@ -42,13 +72,49 @@ pub fn verify_signed_builder_message<T: HashTreeRoot>(
     msg: &T,
     signature: &BlsSignature,
 ) -> Result<(), ethereum_consensus::Error> {
+    verify_builder_root(chain, pubkey, msg.hash_tree_root()?.0, signature).map_err(|_| {
+        ethereum_consensus::Error::Bls(ethereum_consensus::crypto::BlsError::InvalidSignature)
+    })
+}
+
+/// Verify a BLS signature over the hash tree root of a SSZ object, using the Application
+/// Builder domain.
+fn verify_builder_root(
+    chain: &ChainConfig,
+    pubkey: &PublicKey,
+    object_root: [u8; 32],
+    signature: &BlsSignature,
+) -> Result<(), BLST_ERROR> {
     let domain = chain.application_builder_domain();
-    let object_root = msg.hash_tree_root()?.0;
     let signing_root = compute_signing_root(object_root, domain);
 
-    verify_signature(pubkey, &signing_root, signature).map_err(|_| {
-        ethereum_consensus::Error::Bls(ethereum_consensus::crypto::BlsError::InvalidSignature)
-    })
+    verify_signature(pubkey, &signing_root, signature)
+}
+
+/// Verify the signature of a builder bid served by a relay against the relay public key,
+/// using the Application Builder domain.
+pub fn verify_builder_bid(
+    bid: &SignedBuilderBid,
+    relay_pubkey: &BlsPublicKey,
+    chain: &ChainConfig,
+) -> Result<(), BidVerificationError> {
+    if bid.message.public_key != *relay_pubkey {
+        return Err(BidVerificationError::PubkeyMismatch(bid.message.public_key.clone()));
+    }
+
+    // compat: convert from ethereum consensus to blst and alloy types
+    let pubkey = PublicKey::from_bytes(relay_pubkey.as_ref())
+        .map_err(|_| BidVerificationError::InvalidPubkey)?;
+    let signature = BlsSignature::from_slice(bid.signature.as_ref());
+
+    // The bid is merkleized with the list limits of the preset of the chain
+    let object_root = bid
+        .message
+        .hash_tree_root_with_preset(chain.preset)
+        .map_err(|_| BidVerificationError::InvalidSignature)?;
+
+    verify_builder_root(chain, &pubkey, object_root.0, &signature)
+        .map_err(|_| BidVerificationError::InvalidSignature)
 }
 
 /// Verify a BLS signature for a given message and public key.
@@ -57,7 +123,7 @@ pub fn verify_signature(
     msg: &[u8],
     signature: &BlsSignature,
 ) -> Result<(), blst::BLST_ERROR> {
-    let sig = blst::min_pk::Signature::from_bytes(&signature.0).expect("valid signature bytes");
+    let sig = blst::min_pk::Signature::from_bytes(&signature.0)?;
 
     let res = sig.verify(true, msg, BLS_DST_SIG, &[], pubkey, true);
     if res == BLST_ERROR::BLST_SUCCESS {
@@ -116,7 +182,120 @@ pub fn compute_builder_domain(
 
 #[cfg(test)]
 mod tests {
-    use crate::{builder::signature::compute_builder_domain, config::ChainConfig};
+    use alloy::primitives::U256;
+    use blst::min_pk::SecretKey;
+    use ethereum_consensus::{
+        crypto::{KzgCommitment, PublicKey as BlsPublicKey},
+        ssz::prelude::HashTreeRoot,
+    };
+    use rand::RngCore;
+
+    use crate::{
+        builder::signature::{
+            compute_builder_domain, sign_builder_bid, sign_builder_message, verify_builder_bid,
+            BidVerificationError,
+        },
+        config::{chain::Preset, ChainConfig},
+        primitives::{BuilderBid, SignedBuilderBid},
+    };
+
+    fn random_secret_key() -> SecretKey {
+        let mut ikm = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut ikm);
+        SecretKey::key_gen(&ikm, &[]).unwrap()
+    }
+
+    #[test]
+    fn test_verify_builder_bid() {
+        let chain = ChainConfig::holesky();
+        let relay_sk = random_secret_key();
+        let relay_pubkey = BlsPublicKey::try_from(relay_sk.sk_to_pk().to_bytes().as_ref()).unwrap();
+
+        let message = BuilderBid {
+            value: U256::from(1_000_000),
+            public_key: relay_pubkey.clone(),
+            ..Default::default()
+        };
+        let signature = sign_builder_message(&chain, &relay_sk, &message).unwrap();
+        let mut bid = SignedBuilderBid { message, signature };
+
+        assert!(verify_builder_bid(&bid, &relay_pubkey, &chain).is_ok());
+
+        // A bid from another relay is rejected
+        let other_pubkey =
+            BlsPublicKey::try_from(random_secret_key().sk_to_pk().to_bytes().as_ref()).unwrap();
+        assert!(matches!(
+            verify_builder_bid(&bid, &other_pubkey, &chain),
+            Err(BidVerificationError::PubkeyMismatch(_))
+        ));
+
+        // Tampering with the bid invalidates the signature
+        bid.message.value = U256::from(2_000_000);
+        assert!(matches!(
+            verify_builder_bid(&bid, &relay_pubkey, &chain),
+            Err(BidVerificationError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_builder_bid_with_blob_commitments() {
+        let chain = ChainConfig::holesky();
+        let sk = random_secret_key();
+        let pubkey = BlsPublicKey::try_from(sk.sk_to_pk().to_bytes().as_ref()).unwrap();
+
+        let bid =
+            BuilderBid { value: U256::from(1), public_key: pubkey.clone(), ..Default::default() };
+        let commitments = vec![KzgCommitment::try_from([1u8; 48].as_ref()).unwrap()];
+        let blob_bid = bid.clone().with_blob_commitments(commitments.clone());
+
+        assert_eq!(blob_bid.blob_kzg_commitments.to_vec(), commitments);
+        assert_ne!(bid.hash_tree_root().unwrap(), blob_bid.hash_tree_root().unwrap());
+
+        // A signature over the bid without commitments doesn't cover the blob bid
+        let signature = sign_builder_message(&chain, &sk, &bid).unwrap();
+        let signed = SignedBuilderBid { message: blob_bid.clone(), signature };
+        assert!(matches!(
+            verify_builder_bid(&signed, &pubkey, &chain),
+            Err(BidVerificationError::InvalidSignature)
+        ));
+
+        let signature = sign_builder_message(&chain, &sk, &blob_bid).unwrap();
+        let signed = SignedBuilderBid { message: blob_bid, signature };
+        assert!(verify_builder_bid(&signed, &pubkey, &chain).is_ok());
+    }
+
+    #[test]
+    fn test_builder_bid_root_with_minimal_preset() {
+        let mainnet = ChainConfig::kurtosis(12, 8_000);
+        let minimal = ChainConfig { preset: Preset::Minimal, ..mainnet };
+        let sk = random_secret_key();
+        let pubkey = BlsPublicKey::try_from(sk.sk_to_pk().to_bytes().as_ref()).unwrap();
+
+        let commitment = KzgCommitment::try_from([1u8; 48].as_ref()).unwrap();
+        let bid =
+            BuilderBid { value: U256::from(1), public_key: pubkey.clone(), ..Default::default() }
+                .with_blob_commitments(vec![commitment.clone()]);
+
+        // The limit of the commitments list is part of the root
+        let mainnet_root = bid.hash_tree_root_with_preset(Preset::Mainnet).unwrap();
+        let minimal_root = bid.hash_tree_root_with_preset(Preset::Minimal).unwrap();
+        assert_eq!(mainnet_root, bid.hash_tree_root().unwrap());
+        assert_ne!(mainnet_root, minimal_root);
+
+        // A bid is only valid on chains with the same preset
+        let signature = sign_builder_bid(&minimal, &sk, &bid).unwrap();
+        let signed = SignedBuilderBid { message: bid, signature };
+        assert!(verify_builder_bid(&signed, &pubkey, &minimal).is_ok());
+        assert!(matches!(
+            verify_builder_bid(&signed, &pubkey, &mainnet),
+            Err(BidVerificationError::InvalidSignature)
+        ));
+
+        // More commitments than the minimal preset allows can't be merkleized with it
+        let bid = BuilderBid::default().with_blob_commitments(vec![commitment; 17]);
+        assert!(bid.hash_tree_root_with_preset(Preset::Mainnet).is_ok());
+        assert!(bid.hash_tree_root_with_preset(Preset::Minimal).is_err());
+    }
 
     #[test]
     fn test_compute_builder_domain() {
@@ -1,11 +1,19 @@
-use std::collections::HashMap;
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
 
-use alloy::primitives::{Address, TxHash, U256};
+use alloy::{
+    eips::eip4844::{DATA_GAS_PER_BLOB, MAX_DATA_GAS_PER_BLOCK},
+    primitives::{Address, TxHash, U256},
+};
+use clap::ValueEnum;
 use ethereum_consensus::{
     crypto::{KzgCommitment, KzgProof},
     deneb::mainnet::{Blob, BlobsBundle},
 };
 use reth_primitives::TransactionSigned;
+use serde::Deserialize;
 use tracing::warn;
 
 use crate::{
@@ -13,6 +21,112 @@ use crate::{
     primitives::{AccountState, FullTransaction, SignedConstraints, TransactionExt},
 };
 
+use super::ordering::{InclusionKind, PayloadTransaction};
+
+/// The value of a [BlockTemplate], as returned by [BlockTemplate::value].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlockValue {
+    /// An upper bound of the priority fees paid to the proposer, assuming the transactions
+    /// use their whole gas limit.
+    pub priority_fees: U256,
+    /// The blob fees paid by the blob transactions at the blob base fee, which are burned.
+    pub burned_blob_fees: U256,
+}
+
+/// Errors that can occur while adding constraints to a [BlockTemplate].
+#[derive(Debug, thiserror::Error)]
+pub enum BlockTemplateError {
+    /// The blob gas of the new constraints would exceed the per-block blob gas limit.
+    #[error("Blob gas limit exceeded: {committed} committed + {requested} requested > {limit}")]
+    BlobGasLimitExceeded {
+        /// The blob gas already committed in the template.
+        committed: u64,
+        /// The blob gas of the new constraints.
+        requested: u64,
+        /// The maximum blob gas allowed in a block.
+        limit: u64,
+    },
+    /// A transaction of the new constraints has the same sender and nonce as one already
+    /// committed in the template, and the conflict policy didn't allow to replace it.
+    #[error("Nonce conflict: sender {sender} already has a commitment with nonce {nonce}")]
+    NonceConflict {
+        /// The sender of the conflicting transactions.
+        sender: Address,
+        /// The nonce of the conflicting transactions.
+        nonce: u64,
+    },
+    /// The sender of a transaction of the new constraints hasn't been recovered.
+    #[error("Unknown sender of transaction {0}")]
+    UnknownSender(TxHash),
+}
+
+/// How nonce conflicts are resolved when adding constraints to a [BlockTemplate], i.e. when
+/// a new transaction has the same sender and nonce as one already committed in the template.
+///
+/// The policy is applied when validating requests, before their nonces are checked against
+/// the template diffs, and again when their constraints are appended to the template.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[clap(rename_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictPolicy {
+    /// Reject the later constraints, so that commitments are honored in the order they
+    /// were made.
+    #[default]
+    RejectLater,
+    /// Replace the conflicting constraints if the new ones pay a strictly higher priority
+    /// fee and no later commitment of their senders depends on them, and reject the new
+    /// ones otherwise.
+    PreferHigherFee,
+}
+
+/// The reason why a transaction can't be included in a [BlockTemplate].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum IncludeError {
+    /// The transaction nonce is lower than the next nonce of the sender.
+    #[error("Transaction nonce too low. Expected {expected}, got {got}")]
+    NonceTooLow {
+        /// The next nonce of the sender, including the template diffs.
+        expected: u64,
+        /// The nonce of the transaction.
+        got: u64,
+    },
+    /// The transaction nonce is higher than the next nonce of the sender.
+    #[error("Transaction nonce too high. Expected {expected}, got {got}")]
+    NonceTooHigh {
+        /// The next nonce of the sender, including the template diffs.
+        expected: u64,
+        /// The nonce of the transaction.
+        got: u64,
+    },
+    /// The sender can't pay for the maximum cost of the transaction.
+    #[error("Insufficient balance: max cost {max_cost}, balance {balance}")]
+    InsufficientBalance {
+        /// The maximum cost of the transaction (value + fees).
+        max_cost: U256,
+        /// The balance of the sender, including the template diffs.
+        balance: U256,
+    },
+    /// The sender account is a smart contract.
+    #[error("Account has code")]
+    AccountHasCode,
+    /// The transaction gas limit doesn't fit in the remaining gas of the block.
+    #[error("Gas limit too high: requested {requested}, remaining {remaining}")]
+    GasLimitTooHigh {
+        /// The gas limit of the transaction.
+        requested: u64,
+        /// The gas left in the block template.
+        remaining: u64,
+    },
+    /// The transaction max fee doesn't cover the base fee of the target block.
+    #[error("Max fee per gas {max_fee} is lower than the base fee {base_fee}")]
+    BaseFeeTooLow {
+        /// The max fee per gas of the transaction.
+        max_fee: u128,
+        /// The base fee of the target block.
+        base_fee: u128,
+    },
+}
+
 /// A block template that serves as a fallback block, but is also used
 /// to keep intermediary state for new commitment requests.
 ///
@@ -22,15 +136,34 @@ use crate::{
 /// - Simulate new commitment requests.
 /// - Update state every block, to invalidate old commitments.
 /// - Make sure we DO NOT accept invalid commitments in any circumstances.
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct BlockTemplate {
     /// The state diffs per address given the list of commitments.
     pub(crate) state_diff: StateDiff,
     /// The signed constraints associated to the block
     pub signed_constraints_list: Vec<SignedConstraints>,
+    /// The sequence number of the next commitment accepted for the block.
+    next_sequence: u64,
+    /// How nonce conflicts between constraints are resolved.
+    conflict_policy: ConflictPolicy,
 }
 
 impl BlockTemplate {
+    /// Set the policy resolving nonce conflicts between constraints. Defaults to
+    /// [ConflictPolicy::RejectLater].
+    pub fn with_conflict_policy(mut self, policy: ConflictPolicy) -> Self {
+        self.conflict_policy = policy;
+        self
+    }
+
+    /// Returns the sequence number of the next commitment accepted for the block, starting
+    /// from 0, and increments it. Numbers are never reused, even if constraints are evicted.
+    pub fn next_commitment_sequence(&mut self) -> u64 {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        sequence
+    }
+
     /// Return the state diff of the block template.
     pub fn get_diff(&self, address: &Address) -> Option<(u64, U256)> {
         self.state_diff.get_diff(address)
@@ -63,34 +196,45 @@ impl BlockTemplate {
             .collect()
     }
 
+    /// Converts the list of signed constraints into a list of transactions to be included
+    /// in a local execution payload, tagged with their position constraints.
+    pub fn as_payload_transactions(&self) -> Vec<PayloadTransaction> {
+        self.signed_constraints_list
+            .iter()
+            .flat_map(|sc| {
+                let kind = if sc.message.top {
+                    InclusionKind::TopOfBlock
+                } else {
+                    InclusionKind::Committed
+                };
+                sc.message
+                    .transactions
+                    .iter()
+                    .map(move |tx| PayloadTransaction::new(tx.clone(), kind))
+            })
+            .collect()
+    }
+
     /// Converts the list of signed constraints into a list of all blobs in all transactions
     /// in the constraints. Use this when building a local execution payload.
     #[inline]
     pub fn as_blobs_bundle(&self) -> BlobsBundle {
-        let (commitments, proofs, blobs) =
-            self.signed_constraints_list
-                .iter()
-                .flat_map(|sc| sc.message.transactions.iter())
-                .filter_map(|c| c.blob_sidecar())
-                .fold(
-                    (Vec::new(), Vec::new(), Vec::new()),
-                    |(mut commitments, mut proofs, mut blobs), bs| {
-                        commitments.extend(bs.commitments.iter().map(|c| {
-                            KzgCommitment::try_from(c.as_slice()).expect("both are 48 bytes")
-                        }));
-                        proofs.extend(
-                            bs.proofs.iter().map(|p| {
-                                KzgProof::try_from(p.as_slice()).expect("both are 48 bytes")
-                            }),
-                        );
-                        blobs.extend(bs.blobs.iter().map(|b| {
-                            Blob::try_from(b.as_slice()).expect("both are 131_072 bytes")
-                        }));
-                        (commitments, proofs, blobs)
-                    },
-                );
-
-        BlobsBundle { commitments, proofs, blobs }
+        blobs_bundle(
+            self.signed_constraints_list.iter().flat_map(|sc| sc.message.transactions.iter()),
+        )
+    }
+
+    /// Same as [BlockTemplate::as_blobs_bundle], but with the blobs following the order of the
+    /// given transaction hashes, e.g. the one of the transactions in a built payload.
+    pub fn as_ordered_blobs_bundle(&self, tx_hashes: &[TxHash]) -> BlobsBundle {
+        let transactions = self
+            .signed_constraints_list
+            .iter()
+            .flat_map(|sc| sc.message.transactions.iter())
+            .map(|tx| (*tx.hash(), tx))
+            .collect::<HashMap<_, _>>();
+
+        blobs_bundle(tx_hashes.iter().filter_map(|hash| transactions.get(hash).copied()))
     }
 
     /// Returns the length of the transactions in the block template.
@@ -99,6 +243,12 @@ impl BlockTemplate {
         self.signed_constraints_list.iter().fold(0, |acc, sc| acc + sc.message.transactions.len())
     }
 
+    /// Returns `true` if the block template already contains top-of-block constraints.
+    #[inline]
+    pub fn has_top_of_block(&self) -> bool {
+        self.signed_constraints_list.iter().any(|sc| sc.message.top)
+    }
+
     /// Returns the committed gas in the block template.
     #[inline]
     pub fn committed_gas(&self) -> u64 {
@@ -107,6 +257,35 @@ impl BlockTemplate {
         })
     }
 
+    /// Returns an upper bound of the priority fees paid to the proposer by the transactions in
+    /// the block template at the given base fee, assuming they use their whole gas limit.
+    pub fn max_priority_fees(&self, base_fee: u128) -> U256 {
+        self.transactions().iter().fold(U256::ZERO, |acc, tx| {
+            let tip = tx.effective_tip_per_gas(base_fee).unwrap_or_default();
+            acc + U256::from(tip) * U256::from(tx.gas_limit())
+        })
+    }
+
+    /// Returns the value of the block template at the given base fee and blob base fee.
+    ///
+    /// The blob fees are burned in full, so only the priority fees on the execution gas are
+    /// paid to the proposer. Blob transactions whose max fee per blob gas doesn't cover the
+    /// blob base fee can't be included, and are left out of the value.
+    pub fn value(&self, base_fee: u128, blob_base_fee: u128) -> BlockValue {
+        self.transactions().iter().fold(BlockValue::default(), |mut value, tx| {
+            let blob_gas = match tx.as_eip4844() {
+                Some(blob_tx) if blob_tx.max_fee_per_blob_gas < blob_base_fee => return value,
+                Some(blob_tx) => blob_tx.blob_versioned_hashes.len() as u64 * DATA_GAS_PER_BLOB,
+                None => 0,
+            };
+
+            let tip = tx.effective_tip_per_gas(base_fee).unwrap_or_default();
+            value.priority_fees += U256::from(tip) * U256::from(tx.gas_limit());
+            value.burned_blob_fees += U256::from(blob_gas) * U256::from(blob_base_fee);
+            value
+        })
+    }
+
     /// Returns the blob count of the block template.
     #[inline]
     pub fn blob_count(&self) -> usize {
@@ -119,25 +298,261 @@ impl BlockTemplate {
         })
     }
 
+    /// Returns the blob gas committed in the block template.
+    #[inline]
+    pub fn committed_blob_gas(&self) -> u64 {
+        self.blob_count() as u64 * DATA_GAS_PER_BLOB
+    }
+
+    /// Checks whether the transaction can be included in this block template, returning the
+    /// reason why it can't otherwise.
+    ///
+    /// `account_snapshot` is the state of the sender the transaction is validated against,
+    /// with any pending diff (e.g. from other templates) already applied. The transaction
+    /// must:
+    /// - have the next nonce of the sender;
+    /// - have a max cost covered by the balance of the sender, which must be an EOA;
+    /// - fit in the `gas_limit` left after the already committed gas;
+    /// - have a max fee per gas of at least `base_fee`.
+    pub fn is_includable(
+        &self,
+        tx: &FullTransaction,
+        account_snapshot: &AccountState,
+        gas_limit: u64,
+        base_fee: u128,
+    ) -> Result<(), IncludeError> {
+        let expected = account_snapshot.transaction_count;
+        let got = tx.nonce();
+        if got < expected {
+            return Err(IncludeError::NonceTooLow { expected, got });
+        }
+        if got > expected {
+            return Err(IncludeError::NonceTooHigh { expected, got });
+        }
+
+        let max_cost = max_transaction_cost(tx);
+        if max_cost > account_snapshot.balance {
+            return Err(IncludeError::InsufficientBalance {
+                max_cost,
+                balance: account_snapshot.balance,
+            });
+        }
+
+        if account_snapshot.has_code {
+            return Err(IncludeError::AccountHasCode);
+        }
+
+        let remaining = gas_limit.saturating_sub(self.committed_gas());
+        if tx.gas_limit() > remaining {
+            return Err(IncludeError::GasLimitTooHigh { requested: tx.gas_limit(), remaining });
+        }
+
+        if tx.max_fee_per_gas() < base_fee {
+            return Err(IncludeError::BaseFeeTooLow { max_fee: tx.max_fee_per_gas(), base_fee });
+        }
+
+        Ok(())
+    }
+
     /// Adds a list of constraints to the block template and updates the state diff.
-    pub fn add_constraints(&mut self, constraints: SignedConstraints) {
+    /// Returns the constraints replaced because of a nonce conflict, if any.
+    ///
+    /// Returns an error if the constraints would exceed the per-block blob gas limit, or if
+    /// they conflict with committed ones and the [ConflictPolicy] doesn't allow to replace
+    /// them. In both cases the template is left untouched.
+    pub fn add_constraints(
+        &mut self,
+        constraints: SignedConstraints,
+    ) -> Result<Vec<SignedConstraints>, BlockTemplateError> {
+        let requested = constraints.message.transactions.iter().fold(0, |acc, c| {
+            acc + c.as_eip4844().map(|tx| tx.blob_versioned_hashes.len() as u64).unwrap_or(0)
+        }) * DATA_GAS_PER_BLOB;
+
+        let committed = self.committed_blob_gas();
+        if committed + requested > MAX_DATA_GAS_PER_BLOCK {
+            return Err(BlockTemplateError::BlobGasLimitExceeded {
+                committed,
+                requested,
+                limit: MAX_DATA_GAS_PER_BLOCK,
+            });
+        }
+
+        let replaced = self.resolve_conflicts(&constraints.message.transactions)?;
+        let replaced = self.evict(replaced);
+
         for constraint in &constraints.message.transactions {
             let max_cost = max_transaction_cost(constraint);
+            // The senders have been checked when resolving the conflicts
+            let Some(sender) = constraint.sender() else { continue };
             self.state_diff
                 .diffs
-                .entry(*constraint.sender().expect("recovered sender"))
+                .entry(*sender)
                 .and_modify(|(nonce, balance)| {
                     *nonce += 1;
                     *balance += max_cost;
                 })
                 .or_insert((1, max_cost));
+
+            // The EIP-7702 authorizations bump the nonces of their authorities
+            for (authority, _) in constraint.authority_nonce_bumps() {
+                self.state_diff
+                    .diffs
+                    .entry(authority)
+                    .and_modify(|(nonce, _)| *nonce += 1)
+                    .or_insert((1, U256::ZERO));
+            }
         }
 
         self.signed_constraints_list.push(constraints);
+
+        Ok(replaced)
     }
 
-    /// Remove all signed constraints at the specified index and updates the state diff
-    fn remove_constraints_at_index(&mut self, index: usize) {
+    /// Resolves the nonce conflicts of the given transactions with the committed constraints,
+    /// according to the [ConflictPolicy] of the template. Returns the indexes of the
+    /// constraints to replace, if any.
+    ///
+    /// Returns an error if a conflict can't be resolved: the constraints are only replaced if
+    /// the new transactions pay a strictly higher priority fee, and if they hold the last
+    /// committed transactions of their senders in the template, to avoid nonce gaps.
+    pub fn resolve_conflicts(
+        &self,
+        transactions: &[FullTransaction],
+    ) -> Result<Vec<usize>, BlockTemplateError> {
+        let mut nonces = HashSet::with_capacity(transactions.len());
+        for tx in transactions {
+            let sender = tx.sender().ok_or(BlockTemplateError::UnknownSender(*tx.hash()))?;
+            nonces.insert((*sender, tx.nonce()));
+        }
+
+        let conflicts = self
+            .signed_constraints_list
+            .iter()
+            .enumerate()
+            .filter_map(|(index, sc)| {
+                sc.message
+                    .transactions
+                    .iter()
+                    .find_map(|tx| nonces.get(&(*tx.sender()?, tx.nonce())))
+                    .map(|conflict| (*conflict, index))
+            })
+            .collect::<Vec<_>>();
+
+        let Some(&((sender, nonce), _)) = conflicts.first() else {
+            return Ok(Vec::new());
+        };
+        let conflict = BlockTemplateError::NonceConflict { sender, nonce };
+
+        if self.conflict_policy != ConflictPolicy::PreferHigherFee {
+            return Err(conflict);
+        }
+
+        let priority = transactions_priority(transactions, 0);
+        let indexes = conflicts.into_iter().map(|(_, index)| index).collect::<Vec<_>>();
+        if indexes
+            .iter()
+            .any(|index| constraints_priority(&self.signed_constraints_list[*index], 0) >= priority)
+        {
+            return Err(conflict);
+        }
+
+        // The lowest replaced nonce of each sender: the remaining constraints must not have
+        // later transactions of the same sender, which would be left with a nonce gap
+        let mut lowest_nonces = HashMap::new();
+        for index in &indexes {
+            for tx in &self.signed_constraints_list[*index].message.transactions {
+                let Some(sender) = tx.sender() else { continue };
+                let nonce = lowest_nonces.entry(*sender).or_insert(u64::MAX);
+                *nonce = tx.nonce().min(*nonce);
+            }
+        }
+
+        let has_dependents = self
+            .signed_constraints_list
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !indexes.contains(index))
+            .flat_map(|(_, sc)| &sc.message.transactions)
+            .any(|tx| {
+                tx.sender()
+                    .and_then(|sender| lowest_nonces.get(sender))
+                    .is_some_and(|lowest| tx.nonce() > *lowest)
+            });
+        if has_dependents {
+            return Err(conflict);
+        }
+
+        Ok(indexes)
+    }
+
+    /// Selects the constraints to evict to make room for a commitment of the given `priority`,
+    /// so that `fits` holds for the transactions count and the committed gas left in the
+    /// template. Returns the indexes of the constraints to evict, or `None` if not enough
+    /// room can be made.
+    ///
+    /// Only the `evictable` constraints with a priority lower than `priority` are considered,
+    /// the lowest priority and then the most recent ones first. To avoid nonce gaps, constraints
+    /// are also kept if one of their senders has a later transaction in the template.
+    pub fn select_evictions(
+        &self,
+        priority: u128,
+        base_fee: u128,
+        evictable: impl Fn(&SignedConstraints) -> bool,
+        fits: impl Fn(usize, u64) -> bool,
+    ) -> Option<Vec<usize>> {
+        let mut count = self.transactions_len();
+        let mut gas = self.committed_gas();
+        if fits(count, gas) {
+            return Some(Vec::new());
+        }
+
+        let mut last_nonces = HashMap::new();
+        for tx in self.signed_constraints_list.iter().flat_map(|sc| &sc.message.transactions) {
+            let nonce = last_nonces.entry(*tx.sender().expect("recovered sender")).or_insert(0);
+            *nonce = tx.nonce().max(*nonce);
+        }
+
+        let mut candidates = self
+            .signed_constraints_list
+            .iter()
+            .enumerate()
+            .filter(|(_, sc)| {
+                sc.message.transactions.iter().all(|tx| {
+                    last_nonces.get(tx.sender().expect("recovered sender")) == Some(&tx.nonce())
+                })
+            })
+            .filter(|(_, sc)| evictable(sc))
+            .map(|(index, sc)| (index, constraints_priority(sc, base_fee)))
+            .filter(|(_, constraints_priority)| *constraints_priority < priority)
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(index, priority)| (*priority, Reverse(*index)));
+
+        let mut evictions = Vec::new();
+        for (index, _) in candidates {
+            let transactions = &self.signed_constraints_list[index].message.transactions;
+            count -= transactions.len();
+            gas -= transactions.iter().fold(0, |acc, tx| acc + tx.gas_limit());
+            evictions.push(index);
+
+            if fits(count, gas) {
+                return Some(evictions);
+            }
+        }
+
+        None
+    }
+
+    /// Evicts the signed constraints at the given indexes and updates the state diff.
+    /// Returns the evicted constraints.
+    pub fn evict(&mut self, mut indexes: Vec<usize>) -> Vec<SignedConstraints> {
+        indexes.sort_unstable_by_key(|index| Reverse(*index));
+        indexes.dedup();
+        indexes.into_iter().map(|index| self.remove_constraints_at_index(index)).collect()
+    }
+
+    /// Remove all signed constraints at the specified index and updates the state diff.
+    /// Returns the removed constraints.
+    fn remove_constraints_at_index(&mut self, index: usize) -> SignedConstraints {
         let constraints = self.signed_constraints_list.remove(index);
 
         for constraint in &constraints.message.transactions {
@@ -148,7 +563,27 @@ impl BlockTemplate {
                     *nonce = nonce.saturating_sub(1);
                     *balance -= max_transaction_cost(constraint);
                 });
+
+            for (authority, _) in constraint.authority_nonce_bumps() {
+                self.state_diff
+                    .diffs
+                    .entry(authority)
+                    .and_modify(|(nonce, _)| *nonce = nonce.saturating_sub(1));
+            }
         }
+
+        constraints
+    }
+
+    /// Returns true if the nonce of the given address is bumped by an EIP-7702 authorization of
+    /// a transaction of another sender in the template. Its nonce then depends on the order in
+    /// which the transactions are included in the block.
+    pub fn is_authorized_by_others(&self, address: &Address) -> bool {
+        self.signed_constraints_list
+            .iter()
+            .flat_map(|sc| &sc.message.transactions)
+            .filter(|tx| tx.sender() != Some(address))
+            .any(|tx| tx.authority_nonce_bumps().iter().any(|(authority, _)| authority == address))
     }
 
     /// Retain removes any transactions that conflict with the given account state.
@@ -197,8 +632,51 @@ impl BlockTemplate {
     }
 }
 
+/// Returns the priority of the signed constraints at the given base fee, i.e. the lowest
+/// effective tip per gas of their transactions.
+fn constraints_priority(constraints: &SignedConstraints, base_fee: u128) -> u128 {
+    transactions_priority(&constraints.message.transactions, base_fee)
+}
+
+/// Returns the priority of the given transactions at the given base fee, i.e. their lowest
+/// effective tip per gas.
+fn transactions_priority(transactions: &[FullTransaction], base_fee: u128) -> u128 {
+    transactions
+        .iter()
+        .map(|tx| tx.effective_tip_per_gas(base_fee).unwrap_or_default())
+        .min()
+        .unwrap_or_default()
+}
+
+/// Collects the blobs of the given transactions into a bundle, in the same order.
+fn blobs_bundle<'a>(transactions: impl Iterator<Item = &'a FullTransaction>) -> BlobsBundle {
+    let (commitments, proofs, blobs) = transactions.filter_map(|c| c.blob_sidecar()).fold(
+        (Vec::new(), Vec::new(), Vec::new()),
+        |(mut commitments, mut proofs, mut blobs), bs| {
+            commitments.extend(
+                bs.commitments
+                    .iter()
+                    .map(|c| KzgCommitment::try_from(c.as_slice()).expect("both are 48 bytes")),
+            );
+            proofs.extend(
+                bs.proofs
+                    .iter()
+                    .map(|p| KzgProof::try_from(p.as_slice()).expect("both are 48 bytes")),
+            );
+            blobs.extend(
+                bs.blobs
+                    .iter()
+                    .map(|b| Blob::try_from(b.as_slice()).expect("both are 131_072 bytes")),
+            );
+            (commitments, proofs, blobs)
+        },
+    );
+
+    BlobsBundle { commitments, proofs, blobs }
+}
+
 /// StateDiff tracks the intermediate changes to the state according to the block template.
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct StateDiff {
     /// Map of diffs per address. Each diff is a tuple of the nonce and balance diff
     /// that should be applied to the current state.
@@ -213,3 +691,240 @@ impl StateDiff {
         self.diffs.get(address).copied()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        consensus::{BlobTransactionSidecar, SidecarBuilder, SimpleCoder},
+        eips::{
+            eip2718::Encodable2718,
+            eip4844::{DATA_GAS_PER_BLOB, MAX_BLOBS_PER_BLOCK, MAX_DATA_GAS_PER_BLOCK},
+        },
+        network::{EthereumWallet, TransactionBuilder, TransactionBuilder4844},
+        primitives::{Address, U256},
+        signers::local::PrivateKeySigner,
+    };
+
+    use crate::{
+        common::transactions::max_transaction_cost,
+        primitives::{AccountState, ConstraintsMessage, FullTransaction, SignedConstraints},
+        test_util::default_test_transaction,
+    };
+
+    use super::{BlockTemplate, BlockTemplateError, BlockValue, ConflictPolicy, IncludeError};
+
+    /// Create signed constraints with a single blob transaction carrying one blob.
+    async fn blob_constraints(
+        wallet: &EthereumWallet,
+        sender: Address,
+        nonce: u64,
+    ) -> eyre::Result<SignedConstraints> {
+        let sidecar: BlobTransactionSidecar =
+            SidecarBuilder::<SimpleCoder>::from_slice(b"Blobs are fun!").build()?;
+        let tx = default_test_transaction(sender, Some(nonce))
+            .with_blob_sidecar(sidecar)
+            .with_max_fee_per_blob_gas(3_000_000);
+
+        let raw_encoded = tx.build(wallet).await?.encoded_2718();
+        let mut tx = FullTransaction::decode_enveloped(raw_encoded)?;
+        tx.sender = Some(sender);
+
+        let message = ConstraintsMessage::from_tx(Default::default(), 10, tx);
+        Ok(SignedConstraints { message, signature: Default::default() })
+    }
+
+    #[tokio::test]
+    async fn test_is_includable() -> eyre::Result<()> {
+        let signer = PrivateKeySigner::random();
+        let sender = signer.address();
+        let wallet = EthereumWallet::from(signer);
+
+        // A transaction with nonce 1, 21_000 gas and a max fee of 20 gwei
+        let raw_encoded =
+            default_test_transaction(sender, Some(1)).build(&wallet).await?.encoded_2718();
+        let mut tx = FullTransaction::decode_enveloped(raw_encoded)?;
+        tx.sender = Some(sender);
+
+        let max_cost = max_transaction_cost(&tx);
+        let account = AccountState { transaction_count: 1, balance: max_cost, has_code: false };
+        let gas_limit = 30_000;
+        let base_fee = 10_000_000_000;
+
+        let template = BlockTemplate::default();
+        template.is_includable(&tx, &account, gas_limit, base_fee)?;
+
+        let stale = AccountState { transaction_count: 2, ..account };
+        assert_eq!(
+            template.is_includable(&tx, &stale, gas_limit, base_fee),
+            Err(IncludeError::NonceTooLow { expected: 2, got: 1 })
+        );
+
+        let behind = AccountState { transaction_count: 0, ..account };
+        assert_eq!(
+            template.is_includable(&tx, &behind, gas_limit, base_fee),
+            Err(IncludeError::NonceTooHigh { expected: 0, got: 1 })
+        );
+
+        let poor = AccountState { balance: max_cost - U256::from(1), ..account };
+        assert_eq!(
+            template.is_includable(&tx, &poor, gas_limit, base_fee),
+            Err(IncludeError::InsufficientBalance { max_cost, balance: max_cost - U256::from(1) })
+        );
+
+        let contract = AccountState { has_code: true, ..account };
+        assert_eq!(
+            template.is_includable(&tx, &contract, gas_limit, base_fee),
+            Err(IncludeError::AccountHasCode)
+        );
+
+        // The gas already committed in the template reduces the remaining gas
+        let mut template = BlockTemplate::default();
+        template.add_constraints(SignedConstraints {
+            message: ConstraintsMessage::from_tx(Default::default(), 10, tx.clone()),
+            signature: Default::default(),
+        })?;
+        assert_eq!(
+            template.is_includable(&tx, &account, gas_limit, base_fee),
+            Err(IncludeError::GasLimitTooHigh { requested: 21_000, remaining: 9_000 })
+        );
+
+        let template = BlockTemplate::default();
+        assert_eq!(
+            template.is_includable(&tx, &account, gas_limit, 30_000_000_000),
+            Err(IncludeError::BaseFeeTooLow { max_fee: 20_000_000_000, base_fee: 30_000_000_000 })
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_add_constraints_blob_gas_limit() -> eyre::Result<()> {
+        let signer = PrivateKeySigner::random();
+        let sender = signer.address();
+        let wallet = EthereumWallet::from(signer);
+
+        let mut template = BlockTemplate::default();
+
+        // Fill the template up to the blob gas limit
+        for nonce in 0..MAX_BLOBS_PER_BLOCK as u64 {
+            template.add_constraints(blob_constraints(&wallet, sender, nonce).await?)?;
+        }
+        assert_eq!(template.committed_blob_gas(), MAX_DATA_GAS_PER_BLOCK);
+
+        // One more blob doesn't fit in the block
+        let constraints = blob_constraints(&wallet, sender, MAX_BLOBS_PER_BLOCK as u64).await?;
+        assert!(matches!(
+            template.add_constraints(constraints),
+            Err(BlockTemplateError::BlobGasLimitExceeded {
+                committed: MAX_DATA_GAS_PER_BLOCK,
+                requested: DATA_GAS_PER_BLOB,
+                ..
+            })
+        ));
+
+        // The rejected constraints must not be part of the template
+        assert_eq!(template.transactions_len(), MAX_BLOBS_PER_BLOCK);
+        assert_eq!(template.committed_blob_gas(), MAX_DATA_GAS_PER_BLOCK);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_value_of_blob_transactions() -> eyre::Result<()> {
+        let signer = PrivateKeySigner::random();
+        let sender = signer.address();
+        let wallet = EthereumWallet::from(signer);
+
+        let mut template = BlockTemplate::default();
+        for nonce in 0..2 {
+            template.add_constraints(blob_constraints(&wallet, sender, nonce).await?)?;
+        }
+
+        // Only the priority fees on the execution gas are paid to the proposer, while the
+        // blob fees at the blob base fee are burned
+        let base_fee = 10_000_000_000;
+        let blob_base_fee = 1_000_000;
+        let value = template.value(base_fee, blob_base_fee);
+        assert_eq!(value.priority_fees, U256::from(2 * 21_000 * 1_000_000_000u64));
+        assert_eq!(value.priority_fees, template.max_priority_fees(base_fee));
+        assert_eq!(value.burned_blob_fees, U256::from(2 * DATA_GAS_PER_BLOB * 1_000_000));
+
+        // Blob transactions that can't pay the blob base fee add no value
+        let value = template.value(base_fee, 3_000_001);
+        assert_eq!(value, BlockValue::default());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_add_constraints_nonce_conflict() -> eyre::Result<()> {
+        let signer = PrivateKeySigner::random();
+        let sender = signer.address();
+        let wallet = EthereumWallet::from(signer);
+
+        // Constraints with a single transaction of nonce 0 and the given priority fee
+        let constraints = |priority_fee: u128| {
+            let tx = default_test_transaction(sender, Some(0))
+                .with_max_priority_fee_per_gas(priority_fee);
+            let wallet = wallet.clone();
+            async move {
+                let raw_encoded = tx.build(&wallet).await?.encoded_2718();
+                let mut tx = FullTransaction::decode_enveloped(raw_encoded)?;
+                tx.sender = Some(sender);
+
+                let message = ConstraintsMessage::from_tx(Default::default(), 10, tx);
+                eyre::Ok(SignedConstraints { message, signature: Default::default() })
+            }
+        };
+
+        let first = constraints(1_000_000_000).await?;
+        let higher_fee = constraints(2_000_000_000).await?;
+
+        // By default, the later constraints are rejected
+        let mut template = BlockTemplate::default();
+        template.add_constraints(first.clone())?;
+        assert!(matches!(
+            template.add_constraints(higher_fee.clone()),
+            Err(BlockTemplateError::NonceConflict { sender: s, nonce: 0 }) if s == sender
+        ));
+        assert_eq!(template.transaction_hashes(), vec![*first.message.transactions[0].hash()]);
+        assert_eq!(
+            template.get_diff(&sender),
+            Some((1, max_transaction_cost(&first.message.transactions[0])))
+        );
+
+        // Preferring the higher fee replaces the committed constraints
+        let mut template =
+            BlockTemplate::default().with_conflict_policy(ConflictPolicy::PreferHigherFee);
+        template.add_constraints(first.clone())?;
+        let replaced = template.add_constraints(higher_fee.clone())?;
+        assert_eq!(replaced.len(), 1);
+        assert_eq!(
+            replaced[0].message.transactions[0].hash(),
+            first.message.transactions[0].hash()
+        );
+        assert_eq!(template.transaction_hashes(), vec![*higher_fee.message.transactions[0].hash()]);
+        assert_eq!(
+            template.get_diff(&sender),
+            Some((1, max_transaction_cost(&higher_fee.message.transactions[0])))
+        );
+
+        // ...but not by constraints paying less
+        assert!(matches!(
+            template.add_constraints(first.clone()),
+            Err(BlockTemplateError::NonceConflict { nonce: 0, .. })
+        ));
+        assert_eq!(template.transactions_len(), 1);
+
+        // Constraints of unknown senders are rejected rather than compared
+        let mut unknown_sender = first;
+        unknown_sender.message.transactions[0].sender = None;
+        let hash = *unknown_sender.message.transactions[0].hash();
+        assert!(matches!(
+            template.add_constraints(unknown_sender),
+            Err(BlockTemplateError::UnknownSender(h)) if h == hash
+        ));
+
+        Ok(())
+    }
+}
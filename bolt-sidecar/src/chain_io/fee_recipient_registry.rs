@@ -0,0 +1,131 @@
+use alloy::{
+    primitives::Address,
+    providers::{ProviderBuilder, RootProvider},
+    sol,
+    transports::http::Http,
+};
+use ethereum_consensus::primitives::BlsPublicKey;
+use eyre::{bail, Context};
+use reqwest::{Client, Url};
+use tracing::debug;
+use FeeRecipientRegistryContract::FeeRecipientRegistryContractInstance;
+
+use super::utils::pubkey_hash;
+use crate::{
+    builder::{BuilderError, FeeRecipientResolver},
+    primitives::Slot,
+};
+
+/// A wrapper over a registry contract mapping validators to the fee recipient their operator
+/// registered for them.
+#[derive(Debug, Clone)]
+pub struct FeeRecipientRegistry(
+    FeeRecipientRegistryContractInstance<Http<Client>, RootProvider<Http<Client>>>,
+);
+
+impl FeeRecipientRegistry {
+    /// Creates a new FeeRecipientRegistry instance.
+    pub fn from_address<U: Into<Url>>(execution_client_url: U, registry_address: Address) -> Self {
+        let provider = ProviderBuilder::new().on_http(execution_client_url.into());
+        Self(FeeRecipientRegistryContract::new(registry_address, provider))
+    }
+
+    /// Fetch the fee recipient registered for the validator with the given public key.
+    pub async fn fee_recipient(&self, pubkey: &BlsPublicKey) -> eyre::Result<Address> {
+        let pubkey_hash = pubkey_hash(pubkey);
+        debug!(%pubkey_hash, "fetching the registered fee recipient of the validator");
+
+        Ok(self
+            .0
+            .getFeeRecipient(pubkey_hash)
+            .call()
+            .await
+            .wrap_err("Failed to fetch the registered fee recipient from EL client")?
+            .feeRecipient)
+    }
+
+    /// Verify that the fee recipient registered for each of the given validators is the
+    /// configured `fee_recipient`, so that block rewards can't be sent to another address.
+    pub async fn verify_fee_recipient(
+        &self,
+        keys: &[BlsPublicKey],
+        fee_recipient: Address,
+    ) -> eyre::Result<()> {
+        for pubkey in keys {
+            let registered = self.fee_recipient(pubkey).await?;
+            if registered != fee_recipient {
+                bail!(
+                    "Mismatch between the configured fee recipient {fee_recipient} and the fee \
+                     recipient {registered} registered for the validator with public key {pubkey}"
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl FeeRecipientResolver for FeeRecipientRegistry {
+    async fn resolve(&self, _slot: Slot, proposer: &BlsPublicKey) -> Result<Address, BuilderError> {
+        self.fee_recipient(proposer).await.map_err(|err| BuilderError::Custom(err.to_string()))
+    }
+}
+
+sol! {
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface FeeRecipientRegistryContract {
+        function getFeeRecipient(bytes20 pubkeyHash) external view returns (address feeRecipient);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        hex,
+        primitives::{address, Address},
+        sol_types::SolValue,
+    };
+    use axum::{extract::State, routing::post, Json, Router};
+    use ethereum_consensus::primitives::BlsPublicKey;
+    use reqwest::Url;
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
+
+    use super::FeeRecipientRegistry;
+    use crate::builder::FeeRecipientResolver;
+
+    #[tokio::test]
+    async fn test_verify_fee_recipient() -> eyre::Result<()> {
+        // A mock EL serving the calls to a registry with the same recipient for every validator
+        async fn eth_call(
+            State(recipient): State<Address>,
+            Json(body): Json<Value>,
+        ) -> Json<Value> {
+            assert_eq!(body["method"], "eth_call");
+            let result = hex::encode_prefixed(recipient.abi_encode());
+            Json(json!({ "jsonrpc": "2.0", "id": body["id"], "result": result }))
+        }
+
+        let registered = address!("725028b0b7c3db8b8242d35cd3a5779838b217b1");
+        let router = Router::new().route("/", post(eth_call)).with_state(registered);
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = Url::parse(&format!("http://{}", listener.local_addr()?))?;
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let registry = FeeRecipientRegistry::from_address(url, Address::random());
+        let keys = vec![BlsPublicKey::try_from([0; 48].as_ref()).expect("valid bls public key")];
+
+        registry.verify_fee_recipient(&keys, registered).await?;
+
+        // The registry also resolves the fee recipient of the fallback blocks of the validators
+        assert_eq!(registry.resolve(10, &keys[0]).await?, registered);
+
+        let err = registry.verify_fee_recipient(&keys, Address::ZERO).await.unwrap_err();
+        assert!(err.to_string().starts_with("Mismatch between the configured fee recipient"));
+        assert!(err.to_string().contains(&registered.to_string()));
+
+        Ok(())
+    }
+}
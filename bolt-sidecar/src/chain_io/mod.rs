@@ -2,5 +2,9 @@
 pub mod manager;
 pub use manager::BoltManager;
 
+/// Wrapper over a registry contract of the fee recipients of validators
+pub mod fee_recipient_registry;
+pub use fee_recipient_registry::FeeRecipientRegistry;
+
 /// Utilities and functions used in the Bolt contracts
 pub mod utils;
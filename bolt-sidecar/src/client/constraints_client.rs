@@ -1,4 +1,11 @@
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use alloy::hex;
 use axum::http::StatusCode;
@@ -7,40 +14,173 @@ use ethereum_consensus::{
     builder::SignedValidatorRegistration, crypto::PublicKey as BlsPublicKey,
     deneb::mainnet::SignedBlindedBeaconBlock, Fork,
 };
-use reqwest::Url;
-use tracing::error;
+use reqwest::{
+    header::{HeaderMap, HeaderValue, CONTENT_TYPE},
+    Url,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use tokio::time::Instant;
+use tracing::{error, warn};
 
+use super::PinnedCertificate;
 use crate::{
     api::{
         builder::GetHeaderParams,
         spec::{
             BuilderApi, BuilderApiError, ConstraintsApi, ErrorResponse, DELEGATE_PATH,
-            GET_PAYLOAD_PATH, REGISTER_VALIDATORS_PATH, REVOKE_PATH, STATUS_PATH,
-            SUBMIT_CONSTRAINTS_PATH,
+            GET_CONSTRAINTS_PATH, GET_PAYLOAD_PATH, REGISTER_VALIDATORS_PATH, REVOKE_PATH,
+            STATUS_PATH, SUBMIT_BLOCK_PATH, SUBMIT_CONSTRAINTS_PATH,
+        },
+    },
+    common::{
+        backoff::BackoffPolicy,
+        http::{
+            get_json_with_backoff, post_json_with_backoff, post_json_with_backoff_raw,
+            PostJsonError,
         },
     },
+    crypto::SignableBLS,
     primitives::{
-        BatchedSignedConstraints, GetPayloadResponse, SignedBuilderBid, SignedDelegation,
-        SignedRevocation,
+        constraint::batch_to_ssz_bytes, signed_messages_to_ssz_bytes, BatchedSignedConstraints,
+        GetPayloadResponse, MessageEncoding, SignedBidSubmission, SignedBuilderBid,
+        SignedConstraints, SignedDelegation, SignedRevocation,
     },
 };
 
+/// The timeout of each attempt of a JSON request to the relay.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Errors in the URL of the relay a [ConstraintsClient] is created with.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RelayUrlError {
+    /// The scheme of the URL is neither HTTP nor HTTPS.
+    #[error("Unsupported relay URL scheme '{0}', expected http or https")]
+    UnsupportedScheme(String),
+    /// The URL has no host to connect to.
+    #[error("Relay URL '{0}' has no host")]
+    MissingHost(String),
+    /// The URL is plain HTTP, while TLS is required.
+    #[error("Relay URL '{0}' is not HTTPS, while TLS is required")]
+    TlsRequired(String),
+    /// The HTTP client couldn't be built with the TLS settings.
+    #[error("Failed to build the relay HTTP client: {0}")]
+    Client(String),
+}
+
+/// The TLS settings of the connection to the relay.
+#[derive(Debug, Clone, Default)]
+pub struct RelayTls {
+    /// Reject plain HTTP relay URLs.
+    pub require_tls: bool,
+    /// The only certificate trusted to authenticate the relay, replacing the system roots.
+    /// The relay must present exactly this certificate, otherwise the connection fails.
+    /// Pinning a certificate implies requiring TLS.
+    pub pinned_cert: Option<PinnedCertificate>,
+}
+
+/// The differences between the constraints of a slot known locally and the ones the relay has.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConstraintsDrift {
+    /// The local constraints the relay doesn't have.
+    pub missing: Vec<SignedConstraints>,
+    /// The constraints the relay has that aren't known locally.
+    pub unexpected: Vec<SignedConstraints>,
+}
+
+impl ConstraintsDrift {
+    /// Compares the local constraints to the relay ones. Constraints are matched by the digest
+    /// of their message, regardless of their order.
+    pub fn between(local: &[SignedConstraints], relay: &[SignedConstraints]) -> Self {
+        let local_digests = local.iter().map(|c| c.message.digest()).collect::<HashSet<_>>();
+        let relay_digests = relay.iter().map(|c| c.message.digest()).collect::<HashSet<_>>();
+
+        let missing =
+            local.iter().filter(|c| !relay_digests.contains(&c.message.digest())).cloned();
+        let unexpected =
+            relay.iter().filter(|c| !local_digests.contains(&c.message.digest())).cloned();
+
+        Self { missing: missing.collect(), unexpected: unexpected.collect() }
+    }
+
+    /// Returns `true` if the relay has exactly the local constraints.
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
 /// A client for interacting with the Constraints client API.
 #[derive(Debug, Clone)]
 pub struct ConstraintsClient {
     url: Url,
     client: reqwest::Client,
     delegations: Vec<SignedDelegation>,
+    /// Whether to submit constraints SSZ-encoded. Shared between clones, as it is
+    /// turned off if the relay turns out not to support it.
+    ssz_constraints: Arc<AtomicBool>,
+    /// The encoding of the delegations and revocations expected by the relay.
+    delegations_encoding: MessageEncoding,
 }
 
 impl ConstraintsClient {
     /// Creates a new constraint client with the given URL.
-    pub fn new<U: Into<Url>>(url: U) -> Self {
-        Self {
-            url: url.into(),
-            client: reqwest::ClientBuilder::new().user_agent("bolt-sidecar").build().unwrap(),
-            delegations: Vec::new(),
+    ///
+    /// Returns an error if the URL is not an HTTP(S) URL with a host, so that a misconfigured
+    /// relay is caught at startup rather than on the first request.
+    pub fn new<U: Into<Url>>(url: U) -> Result<Self, RelayUrlError> {
+        Self::with_tls(url, RelayTls::default())
+    }
+
+    /// Creates a new constraint client with the given URL, connecting to the relay with the
+    /// given TLS settings.
+    pub fn with_tls<U: Into<Url>>(url: U, tls: RelayTls) -> Result<Self, RelayUrlError> {
+        let url = url.into();
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(RelayUrlError::UnsupportedScheme(url.scheme().to_string()));
+        }
+        if url.host_str().is_none_or(str::is_empty) {
+            return Err(RelayUrlError::MissingHost(url.to_string()));
+        }
+
+        let https_only = tls.require_tls || tls.pinned_cert.is_some();
+        if https_only && url.scheme() != "https" {
+            return Err(RelayUrlError::TlsRequired(url.to_string()));
+        }
+
+        let mut builder = reqwest::ClientBuilder::new().user_agent("bolt-sidecar");
+        if https_only {
+            builder = builder.https_only(true);
+        }
+        if let Some(cert) = tls.pinned_cert {
+            let config = cert.client_config().map_err(|e| RelayUrlError::Client(e.to_string()))?;
+            builder = builder.use_preconfigured_tls(config);
         }
+        let client = builder.build().map_err(|e| RelayUrlError::Client(e.to_string()))?;
+
+        Ok(Self {
+            url,
+            client,
+            delegations: Vec::new(),
+            ssz_constraints: Arc::new(AtomicBool::new(false)),
+            delegations_encoding: MessageEncoding::default(),
+        })
+    }
+
+    /// Sets whether constraints should be submitted SSZ-encoded. If the relay rejects
+    /// the SSZ body as an unsupported media type, the client falls back to JSON.
+    pub fn set_ssz_constraints(&mut self, enabled: bool) {
+        self.ssz_constraints.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if constraints are currently submitted SSZ-encoded.
+    pub fn ssz_constraints(&self) -> bool {
+        self.ssz_constraints.load(Ordering::Relaxed)
+    }
+
+    /// Sets the encoding of the delegations and revocations posted to the relay. Unlike
+    /// constraints, there is no fallback: the relay must accept the given encoding.
+    pub fn set_delegations_encoding(&mut self, encoding: MessageEncoding) {
+        self.delegations_encoding = encoding;
     }
 
     /// Adds a list of delegations to the client.
@@ -70,6 +210,31 @@ impl ConstraintsClient {
         delegatees.into_iter().find(|delegatee| available_pubkeys.contains(delegatee))
     }
 
+    /// Returns the validators this sidecar can sign constraints for with the given public keys,
+    /// i.e. those for which [Self::find_signing_key] would return a key.
+    pub fn owned_validators(
+        &self,
+        available_pubkeys: &HashSet<BlsPublicKey>,
+    ) -> HashSet<BlsPublicKey> {
+        let delegated = self
+            .delegations
+            .iter()
+            .filter(|d| available_pubkeys.contains(&d.message.delegatee_pubkey))
+            .map(|d| d.message.validator_pubkey.clone());
+
+        // Validators with delegations only sign through their delegatees
+        let direct = available_pubkeys.iter().filter(|pubkey| {
+            !self.delegations.iter().any(|d| d.message.validator_pubkey == **pubkey)
+        });
+
+        delegated.chain(direct.cloned()).collect()
+    }
+
+    /// Returns the public keys delegated to by any of the delegations.
+    pub fn delegatees(&self) -> HashSet<BlsPublicKey> {
+        self.delegations.iter().map(|d| d.message.delegatee_pubkey.clone()).collect()
+    }
+
     /// Finds all delegations for the given validator public key.
     pub fn find_delegatees(&self, validator_pubkey: &BlsPublicKey) -> HashSet<BlsPublicKey> {
         self.delegations
@@ -84,6 +249,145 @@ impl ConstraintsClient {
         self.url.as_str()
     }
 
+    /// Returns the URL of the target client.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Submits a self-built block to the relay's block-submission endpoint. This is used as a
+    /// fallback for relays that don't support the constraints API.
+    pub async fn submit_block(
+        &self,
+        submission: &SignedBidSubmission,
+    ) -> Result<(), BuilderApiError> {
+        self.post_json(SUBMIT_BLOCK_PATH, submission, BuilderApiError::FailedSubmittingBlock).await
+    }
+
+    /// Fetches the constraints the relay currently has for the given slot, signed by one of the
+    /// given keys.
+    ///
+    /// Other delegates may have submitted constraints for the slot too. Those are skipped before
+    /// being decoded, so that the constraints we can't vouch for don't fail the whole response.
+    pub async fn get_constraints(
+        &self,
+        slot: u64,
+        pubkeys: &HashSet<BlsPublicKey>,
+    ) -> Result<BatchedSignedConstraints, BuilderApiError> {
+        let mut url = self.endpoint(GET_CONSTRAINTS_PATH);
+        url.query_pairs_mut().append_pair("slot", &slot.to_string());
+        let constraints: Vec<Value> = self
+            .get_json(url, BackoffPolicy::default(), BuilderApiError::FailedGettingConstraints)
+            .await?;
+
+        let signed_by_us = |constraints: &Value| {
+            serde_json::from_value::<BlsPublicKey>(constraints["message"]["pubkey"].clone())
+                .is_ok_and(|pubkey| pubkeys.contains(&pubkey))
+        };
+
+        let ours = constraints.into_iter().filter(signed_by_us).map(serde_json::from_value);
+        Ok(ours.collect::<Result<_, _>>()?)
+    }
+
+    /// Compares the constraints the relay has for the given slot to the `local` ones, e.g. the
+    /// ones of the block template, and reports the differences. Only the constraints signed by
+    /// one of the given keys are compared.
+    pub async fn reconcile_constraints(
+        &self,
+        slot: u64,
+        local: &[SignedConstraints],
+        pubkeys: &HashSet<BlsPublicKey>,
+    ) -> Result<ConstraintsDrift, BuilderApiError> {
+        let relay = self.get_constraints(slot, pubkeys).await?;
+        let drift = ConstraintsDrift::between(local, &relay);
+
+        if !drift.is_empty() {
+            warn!(
+                slot,
+                missing = drift.missing.len(),
+                unexpected = drift.unexpected.len(),
+                "Constraints of the relay drifted from the local ones"
+            );
+        }
+
+        Ok(drift)
+    }
+
+    /// Posts the JSON-encoded `body` to the given path of the relay, retrying transient
+    /// failures. Error responses from the relay are wrapped with `on_error`.
+    async fn post_json<B, T>(
+        &self,
+        path: &str,
+        body: &B,
+        on_error: fn(ErrorResponse) -> BuilderApiError,
+    ) -> Result<T, BuilderApiError>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        self.post_json_with_policy(path, body, BackoffPolicy::default(), on_error).await
+    }
+
+    /// Posts the JSON-encoded `body` to the given path of the relay, retrying transient
+    /// failures according to the given backoff `policy`. Error responses from the relay are
+    /// wrapped with `on_error`.
+    async fn post_json_with_policy<B, T>(
+        &self,
+        path: &str,
+        body: &B,
+        policy: BackoffPolicy,
+        on_error: fn(ErrorResponse) -> BuilderApiError,
+    ) -> Result<T, BuilderApiError>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        let url = self.endpoint(path);
+        post_json_with_backoff(&self.client, url, HeaderMap::new(), body, REQUEST_TIMEOUT, policy)
+            .await
+            .map_err(|err| request_error(err, on_error))
+    }
+
+    /// Fetches and decodes the JSON response of the relay at the given URL, retrying transient
+    /// failures according to the given backoff `policy`, including the rate-limited ones.
+    /// Error responses from the relay are wrapped with `on_error`.
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        url: Url,
+        policy: BackoffPolicy,
+        on_error: fn(ErrorResponse) -> BuilderApiError,
+    ) -> Result<T, BuilderApiError> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        get_json_with_backoff(&self.client, url, headers, REQUEST_TIMEOUT, policy)
+            .await
+            .map_err(|err| request_error(err, on_error))
+    }
+
+    /// Posts the SSZ-encoded `body` to the given path of the relay. Error responses from the
+    /// relay are wrapped with `on_error`.
+    async fn post_ssz(
+        &self,
+        path: &str,
+        body: Vec<u8>,
+        on_error: fn(ErrorResponse) -> BuilderApiError,
+    ) -> Result<(), BuilderApiError> {
+        let response = self
+            .client
+            .post(self.endpoint(path))
+            .header("content-type", "application/octet-stream")
+            .body(body)
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status != StatusCode::OK {
+            return Err(on_error(relay_error(status, response.text().await?)));
+        }
+
+        Ok(())
+    }
+
     /// Joins the given path with the client's URL.
     /// If the path is invalid, an error is logged and the client's URL is returned.
     fn endpoint(&self, path: &str) -> Url {
@@ -112,18 +416,12 @@ impl BuilderApi for ConstraintsClient {
         &self,
         registrations: Vec<SignedValidatorRegistration>,
     ) -> Result<(), BuilderApiError> {
-        let response = self
-            .client
-            .post(self.endpoint(REGISTER_VALIDATORS_PATH))
-            .header("content-type", "application/json")
-            .body(serde_json::to_vec(&registrations)?)
-            .send()
-            .await?;
-
-        if response.status() != StatusCode::OK {
-            let error = response.json::<ErrorResponse>().await?;
-            return Err(BuilderApiError::FailedRegisteringValidators(error));
-        }
+        self.post_json::<_, ()>(
+            REGISTER_VALIDATORS_PATH,
+            &registrations,
+            BuilderApiError::FailedRegisteringValidators,
+        )
+        .await?;
 
         // If there are any delegations, propagate the one associated to the incoming
         // registrations to the relay
@@ -155,24 +453,14 @@ impl BuilderApi for ConstraintsClient {
         let parent_hash = hex::encode_prefixed(params.parent_hash.as_ref());
         let public_key = hex::encode_prefixed(params.public_key.as_ref());
 
-        let response = self
-            .client
-            .get(self.endpoint(&format!(
-                "/eth/v1/builder/header/{}/{}/{}",
-                params.slot, parent_hash, public_key
-            )))
-            .header("content-type", "application/json")
-            .send()
-            .await?;
-
-        if response.status() != StatusCode::OK {
-            let error = response.json::<ErrorResponse>().await?;
-            return Err(BuilderApiError::FailedGettingHeader(error));
-        }
-
-        let header = response.json::<SignedBuilderBid>().await?;
+        let url = self.endpoint(&format!(
+            "/eth/v1/builder/header/{}/{}/{}",
+            params.slot, parent_hash, public_key
+        ));
 
-        Ok(header)
+        // The header is needed right away, so the retries don't outlast a single attempt
+        let policy = BackoffPolicy::default().with_deadline(Instant::now() + REQUEST_TIMEOUT);
+        self.get_json(url, policy, BuilderApiError::FailedGettingHeader).await
     }
 
     /// Implements: <https://ethereum.github.io/builder-specs/#/Builder/submitBlindedBlock>
@@ -180,22 +468,7 @@ impl BuilderApi for ConstraintsClient {
         &self,
         signed_block: SignedBlindedBeaconBlock,
     ) -> Result<GetPayloadResponse, BuilderApiError> {
-        let response = self
-            .client
-            .post(self.endpoint(GET_PAYLOAD_PATH))
-            .header("content-type", "application/json")
-            .body(serde_json::to_vec(&signed_block)?)
-            .send()
-            .await?;
-
-        if response.status() != StatusCode::OK {
-            let error = response.json::<ErrorResponse>().await?;
-            return Err(BuilderApiError::FailedGettingPayload(error));
-        }
-
-        let payload = response.json().await?;
-
-        Ok(payload)
+        self.post_json(GET_PAYLOAD_PATH, &signed_block, BuilderApiError::FailedGettingPayload).await
     }
 }
 
@@ -204,21 +477,47 @@ impl ConstraintsApi for ConstraintsClient {
     async fn submit_constraints(
         &self,
         constraints: &BatchedSignedConstraints,
-    ) -> Result<(), BuilderApiError> {
-        let response = self
-            .client
-            .post(self.endpoint(SUBMIT_CONSTRAINTS_PATH))
-            .header("content-type", "application/json")
-            .body(serde_json::to_vec(&constraints)?)
-            .send()
-            .await?;
+    ) -> Result<Value, BuilderApiError> {
+        if self.ssz_constraints() {
+            let response = self
+                .client
+                .post(self.endpoint(SUBMIT_CONSTRAINTS_PATH))
+                .header("content-type", "application/octet-stream")
+                .body(batch_to_ssz_bytes(constraints)?)
+                .timeout(REQUEST_TIMEOUT)
+                .send()
+                .await?;
+
+            let status = response.status();
+            if status != StatusCode::UNSUPPORTED_MEDIA_TYPE {
+                if status != StatusCode::OK {
+                    let error = relay_error(status, response.text().await?);
+                    return Err(BuilderApiError::FailedSubmittingConstraints(error));
+                }
+
+                return Ok(relay_acknowledgement(&response.bytes().await?));
+            }
 
-        if response.status() != StatusCode::OK {
-            let error = response.json::<ErrorResponse>().await?;
-            return Err(BuilderApiError::FailedSubmittingConstraints(error));
+            warn!("Relay doesn't support SSZ-encoded constraints, falling back to JSON");
+            self.ssz_constraints.store(false, Ordering::Relaxed);
         }
 
-        Ok(())
+        // Submissions are retried by the caller until the cutoff of their slot, with the
+        // failed constraints only, so they are attempted once here
+        let url = self.endpoint(SUBMIT_CONSTRAINTS_PATH);
+        let policy = BackoffPolicy::with_max_retries(0);
+        let body = post_json_with_backoff_raw(
+            &self.client,
+            url,
+            HeaderMap::new(),
+            constraints,
+            REQUEST_TIMEOUT,
+            policy,
+        )
+        .await
+        .map_err(|err| request_error(err, BuilderApiError::FailedSubmittingConstraints))?;
+
+        Ok(relay_acknowledgement(&body))
     }
 
     async fn get_header_with_proofs(
@@ -228,22 +527,14 @@ impl ConstraintsApi for ConstraintsClient {
         let parent_hash = hex::encode_prefixed(params.parent_hash.as_ref());
         let public_key = hex::encode_prefixed(params.public_key.as_ref());
 
-        let response = self
-            .client
-            .get(self.endpoint(&format!(
-                "/eth/v1/builder/header_with_proofs/{}/{}/{}",
-                params.slot, parent_hash, public_key,
-            )))
-            .header("content-type", "application/json")
-            .send()
-            .await?;
+        let url = self.endpoint(&format!(
+            "/eth/v1/builder/header_with_proofs/{}/{}/{}",
+            params.slot, parent_hash, public_key,
+        ));
 
-        if response.status() != StatusCode::OK {
-            let error = response.json::<ErrorResponse>().await?;
-            return Err(BuilderApiError::FailedGettingHeader(error));
-        }
-
-        let header = response.json::<VersionedValue<SignedBuilderBid>>().await?;
+        let policy = BackoffPolicy::default().with_deadline(Instant::now() + REQUEST_TIMEOUT);
+        let header: VersionedValue<SignedBuilderBid> =
+            self.get_json(url, policy, BuilderApiError::FailedGettingHeader).await?;
 
         if !matches!(header.version, Fork::Deneb) {
             return Err(BuilderApiError::InvalidFork(header.version.to_string()));
@@ -253,49 +544,437 @@ impl ConstraintsApi for ConstraintsClient {
     }
 
     async fn delegate(&self, signed_data: &[SignedDelegation]) -> Result<(), BuilderApiError> {
-        let response = self
-            .client
-            .post(self.endpoint(DELEGATE_PATH))
-            .header("content-type", "application/json")
-            .body(serde_json::to_string(signed_data)?)
-            .send()
-            .await?;
+        let on_error = BuilderApiError::FailedDelegating;
+        match self.delegations_encoding {
+            MessageEncoding::Json => self.post_json(DELEGATE_PATH, signed_data, on_error).await,
+            MessageEncoding::Ssz => {
+                let body = signed_messages_to_ssz_bytes(signed_data)?;
+                self.post_ssz(DELEGATE_PATH, body, on_error).await
+            }
+        }
+    }
 
-        if response.status() != StatusCode::OK {
-            let error = response.json::<ErrorResponse>().await?;
-            return Err(BuilderApiError::FailedDelegating(error));
+    async fn revoke(&self, signed_data: &[SignedRevocation]) -> Result<(), BuilderApiError> {
+        let on_error = BuilderApiError::FailedRevoking;
+        match self.delegations_encoding {
+            MessageEncoding::Json => self.post_json(REVOKE_PATH, signed_data, on_error).await,
+            MessageEncoding::Ssz => {
+                let body = signed_messages_to_ssz_bytes(signed_data)?;
+                self.post_ssz(REVOKE_PATH, body, on_error).await
+            }
         }
+    }
+}
+
+/// Converts the error of a JSON request to the relay, wrapping its error responses with
+/// `on_error`.
+fn request_error(
+    err: PostJsonError,
+    on_error: fn(ErrorResponse) -> BuilderApiError,
+) -> BuilderApiError {
+    let (status, body) = match err {
+        PostJsonError::Status { status, body } => (status, body),
+        PostJsonError::RateLimited { body, .. } => (StatusCode::TOO_MANY_REQUESTS, body),
+        PostJsonError::Json(err) => return err.into(),
+        PostJsonError::Reqwest(err) => return err.into(),
+        PostJsonError::Timeout(err) => return err.into(),
+    };
+    on_error(relay_error(status, body))
+}
+
+/// Parses the error response of the relay. Relays may respond with a non-JSON body, which is
+/// then kept as the message of the error, along with the status code of the response.
+fn relay_error(status: StatusCode, body: String) -> ErrorResponse {
+    serde_json::from_str(&body).unwrap_or_else(|_| ErrorResponse::new(status.as_u16(), body))
+}
+
+/// Parses the acknowledgement of a constraints submission by the relay. Relays may respond
+/// with a non-JSON body, which is then kept as a string, or with an empty one, kept as `null`.
+fn relay_acknowledgement(body: &[u8]) -> Value {
+    if body.is_empty() {
+        return Value::Null;
+    }
+    serde_json::from_slice(body)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(body).into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    use alloy::primitives::U256;
+    use axum::{
+        extract::{Query, State},
+        http::HeaderMap,
+        routing::{get, post},
+        Json, Router,
+    };
+    use hyper_util::{
+        rt::{TokioExecutor, TokioIo},
+        server::conn::auto,
+        service::TowerToHyperService,
+    };
+    use reqwest::{StatusCode, Url};
+    use serde_json::json;
+    use tokio::net::TcpListener;
+    use tokio_native_tls::{
+        native_tls::{self, Identity},
+        TlsAcceptor,
+    };
+
+    use super::{ConstraintsClient, ConstraintsDrift, RelayTls, RelayUrlError};
+    use crate::{
+        api::spec::{
+            BuilderApi, BuilderApiError, ConstraintsApi, ErrorResponse, DELEGATE_PATH,
+            GET_CONSTRAINTS_PATH, STATUS_PATH, SUBMIT_BLOCK_PATH, SUBMIT_CONSTRAINTS_PATH,
+        },
+        client::PinnedCertificate,
+        crypto::SignableBLS,
+        primitives::{
+            constraint::batch_to_ssz_bytes, read_signed_delegations_from_file, BidTrace,
+            ConstraintsMessage, MessageEncoding, SignedBidSubmission, SignedConstraints,
+        },
+        signer::local::LocalSigner,
+    };
+
+    type Received = Arc<Mutex<Vec<(String, Vec<u8>)>>>;
+
+    /// Spawns a relay that records the submitted constraints and only accepts JSON ones.
+    async fn spawn_json_only_relay() -> (Url, Received) {
+        async fn submit(
+            State(received): State<Received>,
+            headers: HeaderMap,
+            body: axum::body::Bytes,
+        ) -> StatusCode {
+            let content_type = headers["content-type"].to_str().unwrap().to_string();
+            let is_json = content_type == "application/json";
+            received.lock().unwrap().push((content_type, body.to_vec()));
+
+            if is_json {
+                StatusCode::OK
+            } else {
+                StatusCode::UNSUPPORTED_MEDIA_TYPE
+            }
+        }
+
+        let received = Received::default();
+        let router =
+            Router::new().route(SUBMIT_CONSTRAINTS_PATH, post(submit)).with_state(received.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        (url, received)
+    }
+
+    #[tokio::test]
+    async fn test_submit_constraints_ssz_fallback() {
+        let (url, received) = spawn_json_only_relay().await;
+        let constraints = vec![SignedConstraints::default()];
+
+        let mut client = ConstraintsClient::new(url).unwrap();
+        client.set_ssz_constraints(true);
+        client.submit_constraints(&constraints).await.unwrap();
+
+        // The SSZ body is rejected, then the JSON one is accepted
+        {
+            let received = received.lock().unwrap();
+            assert_eq!(received.len(), 2);
+            assert_eq!(received[0].0, "application/octet-stream");
+            assert_eq!(received[0].1, batch_to_ssz_bytes(&constraints).unwrap());
+            assert_eq!(received[1].0, "application/json");
+            assert_eq!(received[1].1, serde_json::to_vec(&constraints).unwrap());
+        }
+
+        // Following submissions go straight to JSON
+        assert!(!client.ssz_constraints());
+        client.submit_constraints(&constraints).await.unwrap();
+        assert_eq!(received.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_submit_constraints_non_json_responses() {
+        /// Acknowledges JSON constraints with plain text, and rejects SSZ ones with plain text.
+        async fn submit(headers: HeaderMap) -> (StatusCode, &'static str) {
+            match headers["content-type"].to_str().unwrap() {
+                "application/json" => (StatusCode::OK, "OK"),
+                _ => (StatusCode::BAD_REQUEST, "invalid constraints"),
+            }
+        }
+
+        let router = Router::new().route(SUBMIT_CONSTRAINTS_PATH, post(submit));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let constraints = vec![SignedConstraints::default()];
+        let mut client = ConstraintsClient::new(url).unwrap();
+
+        // Both encodings keep the plain text bodies, as the acknowledgement or the error
+        let acknowledgement = client.submit_constraints(&constraints).await.unwrap();
+        assert_eq!(acknowledgement, serde_json::Value::String("OK".to_string()));
+
+        client.set_ssz_constraints(true);
+        let Err(BuilderApiError::FailedSubmittingConstraints(error)) =
+            client.submit_constraints(&constraints).await
+        else {
+            panic!("expected the SSZ submission to be rejected");
+        };
+        let expected = ErrorResponse::new(400, "invalid constraints");
+        assert_eq!(serde_json::to_value(error).unwrap(), serde_json::to_value(expected).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_submit_constraints_batch_partial_success() {
+        /// Rejects any batch with constraints for slot 13.
+        async fn submit(
+            State(requests): State<Arc<AtomicUsize>>,
+            Json(constraints): Json<Vec<SignedConstraints>>,
+        ) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+            requests.fetch_add(1, Ordering::Relaxed);
+            if constraints.iter().any(|sc| sc.message.slot == 13) {
+                let error = ErrorResponse::new(400, "invalid constraints for slot 13");
+                return Err((StatusCode::BAD_REQUEST, Json(error)));
+            }
+            Ok(StatusCode::OK)
+        }
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let router =
+            Router::new().route(SUBMIT_CONSTRAINTS_PATH, post(submit)).with_state(requests.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let constraints = [12, 13, 14]
+            .into_iter()
+            .map(|slot| SignedConstraints {
+                message: ConstraintsMessage { slot, ..Default::default() },
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        let client = ConstraintsClient::new(url).unwrap();
+        let outcomes = client.submit_constraints_batch(&constraints).await.unwrap();
+
+        // The rejected batch is split, and only the invalid constraints fail
+        assert_eq!(requests.load(Ordering::Relaxed), 1 + constraints.len());
+        assert_eq!(outcomes.len(), 3);
+        assert!(outcomes[0].is_ok());
+        assert!(matches!(outcomes[1], Err(BuilderApiError::FailedSubmittingConstraints(_))));
+        assert!(outcomes[2].is_ok());
+
+        // Valid batches are submitted at once
+        let outcomes = client.submit_constraints_batch(&vec![constraints[0].clone()]).await;
+        assert!(outcomes.unwrap().iter().all(Result::is_ok));
+        assert_eq!(requests.load(Ordering::Relaxed), 5);
+    }
+
+    #[tokio::test]
+    async fn test_submit_constraints_batch_server_error() {
+        async fn submit(
+            State(requests): State<Arc<AtomicUsize>>,
+        ) -> (StatusCode, Json<ErrorResponse>) {
+            requests.fetch_add(1, Ordering::Relaxed);
+            let error = ErrorResponse::new(503, "relay unavailable");
+            (StatusCode::SERVICE_UNAVAILABLE, Json(error))
+        }
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let router =
+            Router::new().route(SUBMIT_CONSTRAINTS_PATH, post(submit)).with_state(requests.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let constraints = vec![SignedConstraints::default(); 3];
+        let client = ConstraintsClient::new(url).unwrap();
+
+        // Server errors fail the whole batch without splitting it
+        let err = client.submit_constraints_batch(&constraints).await.unwrap_err();
+        assert!(err.is_retryable());
+        assert_eq!(requests.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_submit_block() -> eyre::Result<()> {
+        async fn submit(
+            State(received): State<Received>,
+            headers: HeaderMap,
+            body: axum::body::Bytes,
+        ) -> StatusCode {
+            let content_type = headers["content-type"].to_str().unwrap().to_string();
+            received.lock().unwrap().push((content_type, body.to_vec()));
+            StatusCode::OK
+        }
+
+        let received = Received::default();
+        let router =
+            Router::new().route(SUBMIT_BLOCK_PATH, post(submit)).with_state(received.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = Url::parse(&format!("http://{}", listener.local_addr()?))?;
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let submission = SignedBidSubmission {
+            message: BidTrace { slot: 10, value: U256::from(42), ..Default::default() },
+            ..Default::default()
+        };
+
+        let client = ConstraintsClient::new(url)?;
+        client.submit_block(&submission).await?;
+
+        // The relay receives the JSON-encoded block, with the numbers of the trace as strings
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].0, "application/json");
+        let json = serde_json::from_slice::<serde_json::Value>(&received[0].1)?;
+        assert_eq!(json["message"]["slot"], "10");
+        assert_eq!(json["message"]["value"], "42");
+        assert_eq!(serde_json::from_value::<SignedBidSubmission>(json)?, submission);
 
         Ok(())
     }
 
-    async fn revoke(&self, signed_data: &[SignedRevocation]) -> Result<(), BuilderApiError> {
-        let response = self
-            .client
-            .post(self.endpoint(REVOKE_PATH))
-            .header("content-type", "application/json")
-            .body(serde_json::to_string(signed_data)?)
-            .send()
-            .await?;
+    #[tokio::test]
+    async fn test_delegate_ssz() -> eyre::Result<()> {
+        async fn delegate(
+            State(received): State<Received>,
+            headers: HeaderMap,
+            body: axum::body::Bytes,
+        ) -> StatusCode {
+            let content_type = headers["content-type"].to_str().unwrap().to_string();
+            received.lock().unwrap().push((content_type, body.to_vec()));
+            StatusCode::OK
+        }
+
+        let received = Received::default();
+        let router =
+            Router::new().route(DELEGATE_PATH, post(delegate)).with_state(received.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = Url::parse(&format!("http://{}", listener.local_addr()?))?;
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/delegations.json");
+        let delegations = read_signed_delegations_from_file(&path)?;
+
+        let mut client = ConstraintsClient::new(url)?;
+        client.set_delegations_encoding(MessageEncoding::Ssz);
+        client.delegate(&delegations).await?;
+
+        // The relay receives the same payload as the captured one
+        path.set_file_name("delegations.ssz.hex");
+        let expected = alloy::hex::decode(std::fs::read_to_string(path)?.trim())?;
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].0, "application/octet-stream");
+        assert_eq!(received[0].1, expected);
+
+        Ok(())
+    }
 
-        if response.status() != StatusCode::OK {
-            let error = response.json::<ErrorResponse>().await?;
-            return Err(BuilderApiError::FailedRevoking(error));
+    #[tokio::test]
+    async fn test_reconcile_constraints_drift() -> eyre::Result<()> {
+        #[derive(serde::Deserialize)]
+        struct SlotQuery {
+            slot: u64,
         }
 
+        let constraints = (0..3)
+            .map(|_| SignedConstraints {
+                message: ConstraintsMessage {
+                    pubkey: LocalSigner::random().pubkey(),
+                    slot: 10,
+                    ..Default::default()
+                },
+                signature: Default::default(),
+            })
+            .collect::<Vec<_>>();
+        let pubkeys = constraints.iter().map(|c| c.message.pubkey.clone()).collect::<HashSet<_>>();
+
+        // Other delegates of the slot submitted constraints too, one of them undecodable
+        let foreign = SignedConstraints {
+            message: ConstraintsMessage {
+                pubkey: LocalSigner::random().pubkey(),
+                slot: 10,
+                ..Default::default()
+            },
+            signature: Default::default(),
+        };
+        let mut malformed = serde_json::to_value(&foreign)?;
+        malformed["message"]["transactions"] = json!(["0xdeadbeef"]);
+
+        // The relay lost the second constraints of slot 10, and has none for the other slots
+        let relay_constraints = vec![
+            serde_json::to_value(&constraints[0])?,
+            serde_json::to_value(&foreign)?,
+            malformed,
+            serde_json::to_value(&constraints[2])?,
+        ];
+        let router = Router::new().route(
+            GET_CONSTRAINTS_PATH,
+            get(move |Query(query): Query<SlotQuery>| async move {
+                Json(if query.slot == 10 { relay_constraints } else { Vec::new() })
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = Url::parse(&format!("http://{}", listener.local_addr()?))?;
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        // Only the constraints signed by our keys are returned
+        let client = ConstraintsClient::new(url)?;
+        let relay = client.get_constraints(10, &pubkeys).await?;
+        assert_eq!(relay, vec![constraints[0].clone(), constraints[2].clone()]);
+        assert!(client.get_constraints(11, &pubkeys).await?.is_empty());
+
+        // The drift flags the missing constraints, ignoring the ones of the other delegates
+        let drift = client.reconcile_constraints(10, &constraints, &pubkeys).await?;
+        assert_eq!(drift.missing, vec![constraints[1].clone()]);
+        assert!(drift.unexpected.is_empty());
+
+        // And the ones the relay has but that aren't known locally
+        let drift = client.reconcile_constraints(10, &constraints[..1], &pubkeys).await?;
+        assert!(drift.missing.is_empty());
+        assert_eq!(drift.unexpected, vec![constraints[2].clone()]);
+
+        // The order of the constraints doesn't matter
+        let reordered = [constraints[2].clone(), constraints[0].clone()];
+        let drift = client.reconcile_constraints(10, &reordered, &pubkeys).await?;
+        assert_eq!(drift, ConstraintsDrift::default());
+        assert!(drift.is_empty());
+
         Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use reqwest::Url;
+    #[test]
+    fn test_relay_url_validation() {
+        let url = Url::parse("https://relay.example.com:18551/").unwrap();
+        assert!(ConstraintsClient::new(url).is_ok());
 
-    use super::ConstraintsClient;
+        let url = Url::parse("ftp://relay.example.com/").unwrap();
+        assert_eq!(
+            ConstraintsClient::new(url).unwrap_err(),
+            RelayUrlError::UnsupportedScheme("ftp".to_string())
+        );
+
+        // A URL without a scheme is parsed with its host as the scheme
+        let url = Url::parse("localhost:18551").unwrap();
+        assert_eq!(
+            ConstraintsClient::new(url).unwrap_err(),
+            RelayUrlError::UnsupportedScheme("localhost".to_string())
+        );
+    }
 
     #[test]
     fn test_join_endpoints() {
-        let client = ConstraintsClient::new(Url::parse("http://localhost:8080/").unwrap());
+        let client = ConstraintsClient::new(Url::parse("http://localhost:8080/").unwrap()).unwrap();
         assert_eq!(
             client.endpoint("/eth/v1/builder/header/1/0x123/0x456"),
             Url::parse("http://localhost:8080/eth/v1/builder/header/1/0x123/0x456").unwrap()
@@ -306,4 +985,80 @@ mod tests {
             Url::parse("http://localhost:8080/eth/v1/builder/validators").unwrap()
         );
     }
+
+    /// Reads a file of the TLS test fixtures.
+    fn read_tls_fixture(name: &str) -> Vec<u8> {
+        let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/tls");
+        path.push(name);
+        std::fs::read(path).unwrap()
+    }
+
+    /// Spawns a relay serving its status over TLS with the given fixture certificate and key.
+    async fn spawn_tls_relay(cert: &str, key: &str) -> Url {
+        let identity =
+            Identity::from_pkcs8(&read_tls_fixture(cert), &read_tls_fixture(key)).unwrap();
+        let acceptor = TlsAcceptor::from(native_tls::TlsAcceptor::new(identity).unwrap());
+        let router = Router::new().route(STATUS_PATH, get(|| async { StatusCode::OK }));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("https://{}", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let (acceptor, router) = (acceptor.clone(), router.clone());
+                tokio::spawn(async move {
+                    // Handshakes rejected by the client are expected
+                    let Ok(stream) = acceptor.accept(stream).await else { return };
+                    let _ = auto::Builder::new(TokioExecutor::new())
+                        .serve_connection(TokioIo::new(stream), TowerToHyperService::new(router))
+                        .await;
+                });
+            }
+        });
+
+        url
+    }
+
+    #[tokio::test]
+    async fn test_pinned_relay_certificate() {
+        let url = spawn_tls_relay("relay.pem", "relay.key").await;
+        let pinned = |name| RelayTls {
+            require_tls: true,
+            pinned_cert: Some(PinnedCertificate::from_pem(&read_tls_fixture(name)).unwrap()),
+        };
+
+        // The relay presents the pinned certificate
+        let client = ConstraintsClient::with_tls(url.clone(), pinned("relay.pem")).unwrap();
+        assert_eq!(client.status().await.unwrap(), StatusCode::OK);
+
+        // The relay presents another certificate than the pinned one
+        let client = ConstraintsClient::with_tls(url.clone(), pinned("other.pem")).unwrap();
+        assert!(client.status().await.is_err());
+
+        // Without pinning, the self-signed certificate isn't trusted either
+        let client = ConstraintsClient::with_tls(url, RelayTls::default()).unwrap();
+        assert!(client.status().await.is_err());
+
+        // A relay presenting a certificate issued by a CA, like any public relay
+        let url = spawn_tls_relay("issued.pem", "issued.key").await;
+        let client = ConstraintsClient::with_tls(url.clone(), pinned("issued.pem")).unwrap();
+        assert_eq!(client.status().await.unwrap(), StatusCode::OK);
+
+        // Pinning the CA, or another certificate it issued, doesn't trust the relay
+        for name in ["ca.pem", "issued-other.pem"] {
+            let client = ConstraintsClient::with_tls(url.clone(), pinned(name)).unwrap();
+            assert!(client.status().await.is_err());
+        }
+
+        // Plain HTTP is rejected when TLS is required or a certificate is pinned
+        let http = Url::parse("http://localhost:18551/").unwrap();
+        let require_tls = RelayTls { require_tls: true, pinned_cert: None };
+        for tls in [require_tls, pinned("relay.pem")] {
+            assert_eq!(
+                ConstraintsClient::with_tls(http.clone(), tls).unwrap_err(),
+                RelayUrlError::TlsRequired(http.to_string())
+            );
+        }
+    }
 }
@@ -2,12 +2,20 @@
 /// The Bolt sidecar's main purpose is to sit between the beacon node and Constraints client,
 /// so most requests are simply proxied to its API.
 pub mod constraints_client;
-pub use constraints_client::ConstraintsClient;
+pub use constraints_client::{ConstraintsClient, RelayTls};
 
 /// Module defining an RpcClient wrapper around the [`alloy::rpc::client::RpcClient`].
 /// It provides a simple interface to interact with the Execution layer JSON-RPC API.
 pub mod rpc;
 pub use rpc::RpcClient;
 
+/// Module pinning the certificate of the servers the sidecar connects to over TLS.
+pub mod tls;
+pub use tls::{PinnedCertificate, PinningError};
+
+/// Module notifying the webhooks of the commitments of their outcome.
+pub mod webhook;
+pub use webhook::WebhookNotifier;
+
 // Re-export the beacon_api_client
 pub use beacon_api_client::mainnet::Client as BeaconClient;
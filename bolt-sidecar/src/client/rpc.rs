@@ -1,4 +1,7 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+};
 
 use alloy::{
     eips::BlockNumberOrTag,
@@ -12,8 +15,9 @@ use alloy::{
 
 use futures::{stream::FuturesUnordered, StreamExt};
 use reqwest::{Client, Url};
+use serde::Deserialize;
 
-use crate::primitives::AccountState;
+use crate::primitives::{is_contract_code, AccountState};
 
 /// An HTTP-based JSON-RPC client that supports batching.
 /// Implements all methods that are relevant to Bolt state.
@@ -57,6 +61,20 @@ impl RpcClient {
         Ok(base_fee)
     }
 
+    /// Get the basefee of the block following the latest block or the block at the specified
+    /// number, as predicted by the execution client according to EIP-1559.
+    pub async fn get_next_basefee(&self, block_number: Option<u64>) -> TransportResult<u128> {
+        let tag = block_number.map_or(BlockNumberOrTag::Latest, BlockNumberOrTag::Number);
+        let fee_history: FeeHistory =
+            self.0.request("eth_feeHistory", (U64::from(1), tag, &[] as &[f64])).await?;
+
+        let Some(base_fee) = fee_history.next_block_base_fee() else {
+            return Err(TransportErrorKind::Custom("Next base fee not found".into()).into());
+        };
+
+        Ok(base_fee)
+    }
+
     /// Get the blob basefee of the latest block.
     ///
     /// Reference: https://github.com/ethereum/execution-apis/blob/main/src/eth/fee_market.yaml
@@ -103,7 +121,11 @@ impl RpcClient {
         let balance: U256 = balance.await?;
         let code: Bytes = code.await?;
 
-        Ok(AccountState { balance, transaction_count: tx_count.to(), has_code: !code.is_empty() })
+        Ok(AccountState {
+            balance,
+            transaction_count: tx_count.to(),
+            has_code: is_contract_code(&code),
+        })
     }
 
     /// Get the block with the given number. If `None`, the latest block is returned.
@@ -113,12 +135,71 @@ impl RpcClient {
         self.0.request("eth_getBlockByNumber", (tag, full)).await
     }
 
+    /// Get the code of the given address at the given block. If `None`, at the latest block.
+    pub async fn get_code(
+        &self,
+        address: &Address,
+        block_number: Option<u64>,
+    ) -> TransportResult<Bytes> {
+        let tag = block_number.map_or(BlockNumberOrTag::Latest, BlockNumberOrTag::Number);
+
+        self.0.request("eth_getCode", (address, tag)).await
+    }
+
+    /// Get the value of the given storage slot of an address at the given block. If `None`, at
+    /// the latest block.
+    pub async fn get_storage_at(
+        &self,
+        address: &Address,
+        index: U256,
+        block_number: Option<u64>,
+    ) -> TransportResult<U256> {
+        let tag = block_number.map_or(BlockNumberOrTag::Latest, BlockNumberOrTag::Number);
+
+        self.0.request("eth_getStorageAt", (address, index, tag)).await
+    }
+
     /// Send a raw transaction to the network.
-    #[allow(unused)]
     pub async fn send_raw_transaction(&self, raw: Bytes) -> TransportResult<B256> {
         self.0.request("eth_sendRawTransaction", [raw]).await
     }
 
+    /// Get the hashes of the pending transactions in the mempool of the node.
+    pub async fn get_pending_tx_hashes(&self) -> TransportResult<Vec<TxHash>> {
+        let content: TxpoolContent = self.0.request("txpool_content", ()).await?;
+
+        Ok(content
+            .pending
+            .into_values()
+            .flat_map(|txs| txs.into_values().map(|tx| tx.hash))
+            .collect())
+    }
+
+    /// Get the raw transactions for a list of transaction hashes. The transactions unknown
+    /// to the node are skipped.
+    pub async fn get_raw_transactions(&self, hashes: &[TxHash]) -> TransportResult<Vec<Bytes>> {
+        let mut batch = self.0.new_batch();
+
+        let futs = FuturesUnordered::new();
+
+        for hash in hashes {
+            futs.push(
+                batch
+                    .add_call("eth_getRawTransactionByHash", &(&[hash]))
+                    .expect("Correct parameters"),
+            );
+        }
+
+        batch.send().await?;
+
+        Ok(futs
+            .collect::<Vec<TransportResult<Option<Bytes>>>>()
+            .await
+            .into_iter()
+            .filter_map(|r| r.ok().flatten())
+            .collect())
+    }
+
     /// Get the receipts for a list of transaction hashes.
     pub async fn get_receipts(
         &self,
@@ -147,6 +228,18 @@ impl RpcClient {
     }
 }
 
+/// The pending transactions of the `txpool_content` response, by sender and nonce. Only
+/// their hashes are decoded.
+#[derive(Debug, Deserialize)]
+struct TxpoolContent {
+    pending: HashMap<Address, HashMap<String, TxpoolTransaction>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TxpoolTransaction {
+    hash: TxHash,
+}
+
 impl Deref for RpcClient {
     type Target = alloyClient::RpcClient<Http<Client>>;
 
@@ -171,7 +264,7 @@ mod tests {
     };
     use dotenvy::dotenv;
 
-    use crate::test_util::launch_anvil;
+    use crate::{common::transactions::predict_next_basefee, test_util::launch_anvil};
 
     use super::*;
 
@@ -191,6 +284,18 @@ mod tests {
         assert_eq!(account_state.transaction_count, 0);
     }
 
+    #[tokio::test]
+    async fn test_get_next_basefee() {
+        let anvil = launch_anvil();
+        let client = RpcClient::new(Url::from_str(&anvil.endpoint()).unwrap());
+
+        let header = client.get_block(None, false).await.unwrap().header;
+        let basefee = header.base_fee_per_gas.unwrap_or_default();
+        let expected = predict_next_basefee(header.gas_used, header.gas_limit, basefee);
+
+        assert_eq!(client.get_next_basefee(None).await.unwrap(), expected as u128);
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_get_receipts() {
@@ -0,0 +1,119 @@
+use std::{fmt, sync::Arc};
+
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider},
+    pki_types::{pem::PemObject, CertificateDer, ServerName, UnixTime},
+    CertificateError, ClientConfig, DigitallySignedStruct, SignatureScheme,
+};
+use sha2::{Digest, Sha256};
+
+/// Errors in the certificate pinned for a TLS connection.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PinningError {
+    /// The certificate isn't a valid PEM certificate.
+    #[error("Invalid PEM certificate: {0}")]
+    InvalidCertificate(String),
+    /// The TLS client configuration couldn't be built.
+    #[error("Failed to configure TLS: {0}")]
+    Config(String),
+}
+
+/// A certificate pinned by the SHA-256 hash of its DER encoding.
+///
+/// The server must present exactly this certificate as its leaf, whichever CA issued it.
+/// Other certificates are rejected, including the ones issued by the same CA.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct PinnedCertificate([u8; 32]);
+
+impl PinnedCertificate {
+    /// Pin the first certificate of the given PEM file contents.
+    pub fn from_pem(pem: &[u8]) -> Result<Self, PinningError> {
+        let cert = CertificateDer::from_pem_slice(pem)
+            .map_err(|e| PinningError::InvalidCertificate(e.to_string()))?;
+        Ok(Self::from_der(&cert))
+    }
+
+    /// Pin the given DER-encoded certificate.
+    pub fn from_der(der: &[u8]) -> Self {
+        Self(Sha256::digest(der).into())
+    }
+
+    /// Returns true if the given DER-encoded certificate is the pinned one.
+    pub fn matches(&self, der: &[u8]) -> bool {
+        Sha256::digest(der).as_slice() == self.0
+    }
+
+    /// Returns the TLS client configuration only trusting servers presenting the pinned
+    /// certificate.
+    pub fn client_config(self) -> Result<ClientConfig, PinningError> {
+        let provider = Arc::new(ring::default_provider());
+        let verifier = PinnedCertVerifier { pin: self, provider: provider.clone() };
+
+        let config = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|e| PinningError::Config(e.to_string()))?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth();
+
+        Ok(config)
+    }
+}
+
+impl fmt::Debug for PinnedCertificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PinnedCertificate(sha256:{})", alloy::hex::encode(self.0))
+    }
+}
+
+/// A verifier of the server certificates accepting the pinned certificate only.
+///
+/// The chain and the server name aren't checked: the pin already identifies the server,
+/// so that relays with a certificate from a private CA or a self-signed one can be pinned.
+/// The handshake signatures are still verified, proving the server holds the key of the
+/// pinned certificate.
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    pin: PinnedCertificate,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if self.pin.matches(end_entity) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::InvalidCertificate(CertificateError::ApplicationVerificationFailure))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider.signature_verification_algorithms.supported_schemes()
+    }
+}
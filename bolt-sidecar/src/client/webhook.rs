@@ -0,0 +1,449 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use alloy::primitives::TxHash;
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    header::HeaderMap,
+    redirect, Url,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::common::{
+    backoff::BackoffPolicy,
+    http::{post_json_with_backoff, PostJsonError},
+};
+
+/// The timeout of each attempt to notify a webhook.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Errors in the webhook URL of a commitment request.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum WebhookUrlError {
+    /// The scheme of the URL is neither HTTP nor HTTPS.
+    #[error("Unsupported webhook URL scheme '{0}', expected http or https")]
+    UnsupportedScheme(String),
+    /// The URL has no host to connect to.
+    #[error("Webhook URL has no host")]
+    MissingHost,
+    /// The host of the URL is, or resolves to, a private address.
+    #[error("Webhook URL points to the private address {0}")]
+    PrivateAddress(IpAddr),
+    /// The host of the URL couldn't be resolved.
+    #[error("Failed to resolve the webhook host: {0}")]
+    Resolution(String),
+}
+
+/// The outcome of a commitment, notified to its webhook once its slot has passed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookEventKind {
+    /// All the committed transactions were included on chain.
+    Included,
+    /// Some of the committed transactions weren't included on chain.
+    Violated,
+    /// The commitment was evicted from its slot by a higher-priority one before the deadline,
+    /// so its transactions won't be part of the constraints. This is sent right away.
+    Evicted,
+}
+
+/// The notification POSTed as JSON to the webhook of a commitment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookEvent {
+    /// The outcome of the commitment.
+    pub event: WebhookEventKind,
+    /// The slot the commitment was made for.
+    pub slot: u64,
+    /// The hashes of the committed transactions.
+    pub tx_hashes: Vec<TxHash>,
+}
+
+/// A commitment whose outcome must be notified to a webhook.
+#[derive(Debug, Clone)]
+struct Subscription {
+    slot: u64,
+    tx_hashes: Vec<TxHash>,
+    url: Url,
+}
+
+/// Client notifying the webhooks of the commitments of their outcome, once their slot has
+/// passed. Clones share the same subscriptions.
+///
+/// Webhook URLs are user-provided, so unless private addresses are allowed, URLs pointing to
+/// loopback, private or link-local addresses are rejected, both when the commitment is
+/// requested and when its host is resolved to connect to it. Redirects are not followed for
+/// the same reason.
+#[derive(Debug, Clone)]
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    allow_private: bool,
+    policy: BackoffPolicy,
+    subscriptions: Arc<Mutex<Vec<Subscription>>>,
+}
+
+impl Default for WebhookNotifier {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+impl WebhookNotifier {
+    /// Creates a new notifier, allowing webhooks on private addresses or not.
+    pub fn new(allow_private: bool) -> Self {
+        let mut builder = reqwest::ClientBuilder::new()
+            .user_agent("bolt-sidecar")
+            .redirect(redirect::Policy::none());
+        if !allow_private {
+            // The addresses are checked by the resolver the client connects with, so that a
+            // host can't resolve to a public address when checked and to a private one after
+            builder = builder.dns_resolver(Arc::new(PublicResolver));
+        }
+        let client = builder.build().expect("valid webhook client");
+
+        Self {
+            client,
+            allow_private,
+            policy: BackoffPolicy::default(),
+            subscriptions: Default::default(),
+        }
+    }
+
+    /// Sets the backoff policy of the notifications to the webhooks.
+    pub fn with_backoff_policy(mut self, policy: BackoffPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Checks that the webhook URL is an HTTP(S) URL, and that its host isn't a private
+    /// address if they're not allowed. Host names are only checked on notification, when
+    /// they are resolved to connect to them.
+    pub fn check_url(&self, url: &Url) -> Result<(), WebhookUrlError> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(WebhookUrlError::UnsupportedScheme(url.scheme().to_string()));
+        }
+
+        let host = url.host_str().filter(|host| !host.is_empty());
+        let host = host.ok_or(WebhookUrlError::MissingHost)?;
+
+        // IPv6 hosts are bracketed in URLs
+        match host.trim_matches(&['[', ']'][..]).parse::<IpAddr>() {
+            Ok(ip) => self.check_ip(ip),
+            Err(_) if is_local_domain(host) => self.check_ip(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Notifies the webhook at `url` of the outcome of the commitment to the given
+    /// transactions, once the given slot has passed.
+    pub fn subscribe(&self, slot: u64, tx_hashes: Vec<TxHash>, url: Url) {
+        let mut subscriptions = self.subscriptions.lock().expect("lock not poisoned");
+        subscriptions.push(Subscription { slot, tx_hashes, url });
+    }
+
+    /// Notifies the webhooks of the commitments made for slots up to `slot`, which have
+    /// passed: a commitment is honored if all its transactions are in `included`.
+    ///
+    /// The notifications are sent in the background, retrying transient failures.
+    pub fn settle_until(&self, slot: u64, included: &[TxHash]) {
+        let settled = {
+            let mut subscriptions = self.subscriptions.lock().expect("lock not poisoned");
+            let (settled, pending) = subscriptions.drain(..).partition(|s| s.slot <= slot);
+            *subscriptions = pending;
+            settled
+        };
+
+        for Subscription { slot, tx_hashes, url } in settled {
+            let event = if tx_hashes.iter().all(|hash| included.contains(hash)) {
+                WebhookEventKind::Included
+            } else {
+                WebhookEventKind::Violated
+            };
+
+            let notifier = self.clone();
+            let event = WebhookEvent { event, slot, tx_hashes };
+            tokio::spawn(async move { notifier.notify(url, event).await });
+        }
+    }
+
+    /// Notifies right away the webhooks of the commitments of `slot` that were evicted, i.e.
+    /// those with any of the `evicted` transactions. They won't be notified again once the
+    /// slot has passed.
+    pub fn notify_evicted(&self, slot: u64, evicted: &[TxHash]) {
+        let notified = {
+            let mut subscriptions = self.subscriptions.lock().expect("lock not poisoned");
+            let (notified, pending) = subscriptions.drain(..).partition(|s: &Subscription| {
+                s.slot == slot && s.tx_hashes.iter().any(|hash| evicted.contains(hash))
+            });
+            *subscriptions = pending;
+            notified
+        };
+
+        for Subscription { slot, tx_hashes, url } in notified {
+            let notifier = self.clone();
+            let event = WebhookEvent { event: WebhookEventKind::Evicted, slot, tx_hashes };
+            tokio::spawn(async move { notifier.notify(url, event).await });
+        }
+    }
+
+    /// POSTs the event to the webhook, after checking its URL. Host names resolving to a
+    /// private address are rejected when connecting, if they're not allowed. Failures are only
+    /// logged.
+    async fn notify(&self, url: Url, event: WebhookEvent) {
+        if let Err(err) = self.check_url(&url) {
+            warn!(?err, %url, "Refusing to notify webhook");
+            return;
+        }
+
+        let result = post_json_with_backoff::<_, serde_json::Value>(
+            &self.client,
+            url.clone(),
+            HeaderMap::new(),
+            &event,
+            WEBHOOK_TIMEOUT,
+            self.policy,
+        )
+        .await;
+
+        match result {
+            // The response body of the webhook is irrelevant
+            Ok(_) | Err(PostJsonError::Json(_)) => {
+                debug!(%url, slot = event.slot, kind = ?event.event, "Notified webhook");
+            }
+            Err(err) => warn!(?err, %url, slot = event.slot, "Failed to notify webhook"),
+        }
+    }
+
+    fn check_ip(&self, ip: IpAddr) -> Result<(), WebhookUrlError> {
+        if !self.allow_private && is_private_ip(ip) {
+            return Err(WebhookUrlError::PrivateAddress(ip));
+        }
+        Ok(())
+    }
+}
+
+/// A DNS resolver failing the resolution of the hosts with any private address, so that the
+/// client only ever connects to the public addresses it checked.
+#[derive(Debug, Clone, Copy)]
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            // The port is set by the client from the URL
+            let addrs = tokio::net::lookup_host((name.as_str(), 0))
+                .await
+                .map_err(|e| WebhookUrlError::Resolution(e.to_string()))?
+                .collect::<Vec<_>>();
+
+            if let Some(addr) = addrs.iter().find(|addr| is_private_ip(addr.ip())) {
+                return Err(WebhookUrlError::PrivateAddress(addr.ip()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Returns `true` for the host names that always resolve to the local machine.
+fn is_local_domain(domain: &str) -> bool {
+    let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+    domain == "localhost" || domain.ends_with(".localhost")
+}
+
+/// Returns `true` if the address isn't publicly routable: loopback, private, link-local,
+/// shared, multicast or unspecified addresses, and the IPv6 addresses embedding them, i.e.
+/// the IPv4-mapped, NAT64 and 6to4 ones.
+fn is_private_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_private_ipv4(ip),
+        IpAddr::V6(ip) => match embedded_ipv4(ip) {
+            Some(ip) => is_private_ipv4(ip),
+            None => is_private_ipv6(ip),
+        },
+    }
+}
+
+/// Returns the IPv4 address embedded in an IPv4-mapped, NAT64 (64:ff9b::/96) or 6to4
+/// (2002::/16) IPv6 address.
+fn embedded_ipv4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    let octets = ip.octets();
+    match ip.segments() {
+        [0x64, 0xff9b, 0, 0, 0, 0, ..] => {
+            Some(Ipv4Addr::new(octets[12], octets[13], octets[14], octets[15]))
+        }
+        [0x2002, ..] => Some(Ipv4Addr::new(octets[2], octets[3], octets[4], octets[5])),
+        _ => ip.to_ipv4_mapped(),
+    }
+}
+
+fn is_private_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_loopback() ||
+        ip.is_private() ||
+        ip.is_link_local() ||
+        ip.is_multicast() ||
+        ip.is_broadcast() ||
+        // "This network", 0.0.0.0/8, including the unspecified address
+        a == 0 ||
+        // Shared address space, 100.64.0.0/10
+        (a == 100 && (b & 0b1100_0000) == 64)
+}
+
+fn is_private_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    ip.is_loopback() ||
+        ip.is_unspecified() ||
+        ip.is_multicast() ||
+        // Unique local addresses, fc00::/7
+        (first & 0xfe00) == 0xfc00 ||
+        // Link-local unicast addresses, fe80::/10
+        (first & 0xffc0) == 0xfe80
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use alloy::primitives::TxHash;
+    use axum::{extract::State, routing::post, Json, Router};
+    use reqwest::{
+        dns::{Name, Resolve},
+        Url,
+    };
+    use tokio::{net::TcpListener, sync::mpsc};
+
+    use super::{PublicResolver, WebhookEvent, WebhookEventKind, WebhookNotifier, WebhookUrlError};
+
+    #[test]
+    fn test_webhook_url_rejects_private_addresses() {
+        let notifier = WebhookNotifier::new(false);
+        assert!(notifier.check_url(&Url::parse("https://example.com/hook").unwrap()).is_ok());
+        assert!(notifier.check_url(&Url::parse("http://93.184.215.14/hook").unwrap()).is_ok());
+        assert!(notifier
+            .check_url(&Url::parse("http://[64:ff9b::5db8:d70e]/hook").unwrap())
+            .is_ok());
+
+        for url in [
+            "http://127.0.0.1:8080/hook",
+            "http://10.0.0.1/hook",
+            "http://192.168.1.10/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://100.64.0.1/hook",
+            "http://[::1]/hook",
+            "http://[fd00::1]/hook",
+            "http://[::ffff:10.0.0.1]/hook",
+            "http://0.1.2.3/hook",
+            "http://224.0.0.1/hook",
+            "http://[ff02::1]/hook",
+            "http://[64:ff9b::10.0.0.1]/hook",
+            "http://[2002:a00:1::1]/hook",
+        ] {
+            let err = notifier.check_url(&Url::parse(url).unwrap()).unwrap_err();
+            assert!(matches!(err, WebhookUrlError::PrivateAddress(_)), "{url}: {err:?}");
+        }
+
+        let localhost = Url::parse("http://localhost:8080/hook").unwrap();
+        assert!(notifier.check_url(&localhost).is_err());
+        assert_eq!(
+            notifier.check_url(&Url::parse("ftp://example.com/hook").unwrap()),
+            Err(WebhookUrlError::UnsupportedScheme("ftp".to_string()))
+        );
+
+        // Private addresses can be allowed explicitly
+        let notifier = WebhookNotifier::new(true);
+        assert!(notifier.check_url(&Url::parse("http://127.0.0.1:8080/hook").unwrap()).is_ok());
+        assert!(notifier.check_url(&localhost).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_resolver_rejects_private_addresses() {
+        // The client connects to the addresses checked by its resolver, so a host resolving
+        // to a private address is never reached, whatever it resolved to before
+        let name = Name::from_str("localhost").unwrap();
+        let err = PublicResolver.resolve(name).await.err().expect("loopback is rejected");
+        let err = err.downcast::<WebhookUrlError>().unwrap();
+        assert!(matches!(*err, WebhookUrlError::PrivateAddress(ip) if ip.is_loopback()));
+    }
+
+    #[tokio::test]
+    async fn test_webhook_notified_of_commitment_outcomes() {
+        async fn hook(
+            State(events): State<mpsc::UnboundedSender<WebhookEvent>>,
+            Json(event): Json<WebhookEvent>,
+        ) {
+            events.send(event).unwrap();
+        }
+
+        let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+        let router = Router::new().route("/hook", post(hook)).with_state(events_tx);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/hook", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        // The test server is local, so private addresses must be allowed
+        let notifier = WebhookNotifier::new(true);
+        let (included, missed) = (TxHash::random(), TxHash::random());
+        notifier.subscribe(10, vec![included], url.clone());
+        notifier.subscribe(10, vec![included, missed], url.clone());
+        notifier.subscribe(11, vec![TxHash::random()], url);
+
+        notifier.settle_until(10, &[included]);
+
+        let mut events = Vec::new();
+        for _ in 0..2 {
+            let event = tokio::time::timeout(Duration::from_secs(5), events_rx.recv()).await;
+            events.push(event.unwrap().unwrap());
+        }
+        events.sort_by_key(|event| event.tx_hashes.len());
+
+        assert_eq!(events[0].event, WebhookEventKind::Included);
+        assert_eq!(events[0].slot, 10);
+        assert_eq!(events[0].tx_hashes, vec![included]);
+        assert_eq!(events[1].event, WebhookEventKind::Violated);
+
+        // The commitment for the next slot is still pending
+        let next = tokio::time::timeout(Duration::from_millis(200), events_rx.recv()).await;
+        assert!(next.is_err());
+        assert_eq!(notifier.subscriptions.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_notified_of_eviction() {
+        async fn hook(
+            State(events): State<mpsc::UnboundedSender<WebhookEvent>>,
+            Json(event): Json<WebhookEvent>,
+        ) {
+            events.send(event).unwrap();
+        }
+
+        let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+        let router = Router::new().route("/hook", post(hook)).with_state(events_tx);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/hook", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let notifier = WebhookNotifier::new(true);
+        let (evicted, kept) = (TxHash::random(), TxHash::random());
+        notifier.subscribe(10, vec![evicted], url.clone());
+        notifier.subscribe(10, vec![kept], url);
+
+        // The holder of the evicted commitment is notified before the slot has passed
+        notifier.notify_evicted(10, &[evicted]);
+        let event = tokio::time::timeout(Duration::from_secs(5), events_rx.recv()).await;
+        let event = event.unwrap().unwrap();
+        assert_eq!(event.event, WebhookEventKind::Evicted);
+        assert_eq!(event.slot, 10);
+        assert_eq!(event.tx_hashes, vec![evicted]);
+
+        // Only the kept commitment is settled with the slot
+        notifier.settle_until(10, &[kept]);
+        let event = tokio::time::timeout(Duration::from_secs(5), events_rx.recv()).await;
+        let event = event.unwrap().unwrap();
+        assert_eq!(event.event, WebhookEventKind::Included);
+        assert_eq!(event.tx_hashes, vec![kept]);
+    }
+}
@@ -1,22 +1,107 @@
 use std::{future::Future, time::Duration};
 
+use tokio::time::Instant;
 use tokio_retry::{
     strategy::{jitter, ExponentialBackoff},
     Retry,
 };
 
+/// An exponential backoff policy with jitter.
+///
+/// The n-th retry waits up to `factor * base_millis^n` milliseconds, capped to `max_delay`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackoffPolicy {
+    /// The maximum number of retries after the first attempt.
+    pub max_retries: usize,
+    /// The base of the exponential delay, in milliseconds.
+    pub base_millis: u64,
+    /// The factor the exponential delay is multiplied by.
+    pub factor: u64,
+    /// The maximum delay between two attempts.
+    pub max_delay: Duration,
+    /// The maximum delay honored from the `Retry-After` header of a rate-limited response,
+    /// which replaces the exponential one for its retry. Defaults to a sixth of a 12 seconds
+    /// slot, so that the retries of a request stay within the slot it is for, even when
+    /// nested in an outer retry loop.
+    pub max_retry_after: Duration,
+    /// The instant after which no more attempts are made, e.g. the deadline of the slot the
+    /// request is for. Without a deadline, only the number of retries is bounded.
+    pub deadline: Option<Instant>,
+}
+
+impl Default for BackoffPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_millis: 100,
+            factor: 2,
+            max_delay: Duration::from_secs(1),
+            max_retry_after: Duration::from_secs(2),
+            deadline: None,
+        }
+    }
+}
+
+impl BackoffPolicy {
+    /// Returns a policy with the default delays and the given maximum number of retries.
+    pub fn with_max_retries(max_retries: usize) -> Self {
+        Self { max_retries, ..Default::default() }
+    }
+
+    /// Stop retrying at the given deadline.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Returns `true` if a retry after the given delay would still start before the deadline.
+    pub fn allows_retry_after(&self, delay: Duration) -> bool {
+        self.deadline.map_or(true, |deadline| Instant::now() + delay < deadline)
+    }
+
+    /// Returns the delay to wait before retrying a rate-limited request: the one requested by
+    /// the server, capped to [Self::max_retry_after] and to half the time left until the
+    /// deadline, so that a last attempt still fits before it.
+    pub fn retry_after_delay(&self, requested: Duration) -> Duration {
+        let delay = requested.min(self.max_retry_after);
+        self.deadline.map_or(delay, |deadline| {
+            delay.min(deadline.saturating_duration_since(Instant::now()) / 2)
+        })
+    }
+
+    /// Returns the timeout of an attempt starting now: the given one, cut to the deadline.
+    pub fn attempt_timeout(&self, timeout: Duration) -> Duration {
+        self.deadline.map_or(timeout, |deadline| {
+            timeout.min(deadline.saturating_duration_since(Instant::now()))
+        })
+    }
+
+    /// Returns the delays to wait before each retry, stopping at the deadline.
+    pub fn delays(&self) -> impl Iterator<Item = Duration> {
+        let policy = *self;
+        ExponentialBackoff::from_millis(self.base_millis)
+            .factor(self.factor)
+            .max_delay(self.max_delay)
+            .take(self.max_retries)
+            .map(jitter)
+            .take_while(move |delay| policy.allows_retry_after(*delay))
+    }
+}
+
 /// Retry a future with exponential backoff and jitter.
 pub async fn retry_with_backoff<F, T, E>(max_retries: usize, fut: impl Fn() -> F) -> Result<T, E>
 where
     F: Future<Output = Result<T, E>>,
 {
-    let backoff = ExponentialBackoff::from_millis(100)
-        .factor(2)
-        .max_delay(Duration::from_secs(1))
-        .take(max_retries)
-        .map(jitter);
+    retry_with_policy(BackoffPolicy::with_max_retries(max_retries), fut).await
+}
 
-    Retry::spawn(backoff, fut).await
+/// Retry a future with the delays of the given backoff policy, until its deadline if any.
+pub async fn retry_with_policy<F, T, E>(policy: BackoffPolicy, fut: impl Fn() -> F) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    Retry::spawn(policy.delays(), fut).await
 }
 
 #[cfg(test)]
@@ -125,4 +210,37 @@ mod tests {
             "Total backoff duration should be at least 700ms"
         );
     }
+
+    #[test]
+    fn test_retry_after_delay_is_capped() {
+        let policy = BackoffPolicy::default();
+        assert_eq!(policy.retry_after_delay(Duration::from_secs(1)), Duration::from_secs(1));
+        assert_eq!(policy.retry_after_delay(Duration::from_secs(3600)), Duration::from_secs(2));
+
+        // With a deadline, at most half the time left is waited
+        let policy = policy.with_deadline(Instant::now() + Duration::from_secs(1));
+        assert!(policy.retry_after_delay(Duration::from_secs(2)) <= Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn test_retries_stop_at_deadline() {
+        let counter = Arc::new(Mutex::new(Counter::new(usize::MAX)));
+        let start_time = Instant::now();
+
+        // Many retries are allowed, but not past the deadline
+        let deadline = start_time + Duration::from_millis(500);
+        let policy = BackoffPolicy::with_max_retries(100).with_deadline(deadline);
+        let result = retry_with_policy(policy, || {
+            let counter = Arc::clone(&counter);
+            async move {
+                let mut counter = counter.lock().await;
+                counter.retryable_fn().await
+            }
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert!(start_time.elapsed() < Duration::from_millis(500));
+        assert!(counter.lock().await.count < 100);
+    }
 }
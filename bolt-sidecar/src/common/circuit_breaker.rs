@@ -0,0 +1,92 @@
+use std::{
+    num::NonZero,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use tracing::{info, warn};
+
+/// Default number of consecutive failures after which a [CircuitBreaker] opens.
+pub const DEFAULT_FAILURE_THRESHOLD: usize = 3;
+
+/// A circuit breaker that trips after a number of consecutive failures of a
+/// dependency, and resets as soon as a successful call is recorded.
+///
+/// The breaker is cheap to clone and all clones share the same state, so that
+/// the component recording failures and the ones checking the state can live
+/// in different tasks.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    /// The number of consecutive failures after which the breaker opens.
+    threshold: NonZero<usize>,
+    /// The current number of consecutive failures.
+    consecutive_failures: Arc<AtomicUsize>,
+}
+
+impl CircuitBreaker {
+    /// Create a new closed circuit breaker with the given failure threshold.
+    pub fn new(threshold: NonZero<usize>) -> Self {
+        Self { threshold, consecutive_failures: Arc::new(AtomicUsize::new(0)) }
+    }
+
+    /// Returns `true` if the breaker is open, i.e. the failure threshold has been reached.
+    pub fn is_open(&self) -> bool {
+        self.consecutive_failures.load(Ordering::Relaxed) >= self.threshold.get()
+    }
+
+    /// Record a failure, opening the breaker if the threshold is reached.
+    pub fn record_failure(&self) {
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures == self.threshold.get() {
+            warn!(failures, "Circuit breaker opened after consecutive failures");
+        }
+    }
+
+    /// Record a success, closing the breaker.
+    pub fn record_success(&self) {
+        let failures = self.consecutive_failures.swap(0, Ordering::Relaxed);
+        if failures >= self.threshold.get() {
+            info!("Circuit breaker closed after a successful call");
+        }
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(NonZero::new(DEFAULT_FAILURE_THRESHOLD).expect("Valid non-zero"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use super::CircuitBreaker;
+
+    #[test]
+    fn test_circuit_breaker_opens_and_closes() {
+        let breaker = CircuitBreaker::new(NonZero::new(3).unwrap());
+        let observer = breaker.clone();
+
+        breaker.record_failure();
+        breaker.record_failure();
+        assert!(!observer.is_open());
+
+        // The third consecutive failure opens the breaker
+        breaker.record_failure();
+        assert!(observer.is_open());
+
+        // Further failures keep it open
+        breaker.record_failure();
+        assert!(observer.is_open());
+
+        // A single success closes it again and resets the counter
+        breaker.record_success();
+        assert!(!observer.is_open());
+
+        breaker.record_failure();
+        assert!(!observer.is_open());
+    }
+}
@@ -0,0 +1,383 @@
+use std::time::Duration;
+
+use reqwest::{
+    header::{HeaderMap, CONTENT_TYPE, RETRY_AFTER},
+    StatusCode, Url,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::backoff::BackoffPolicy;
+
+/// Errors that can occur while sending a JSON request.
+#[derive(Debug, thiserror::Error)]
+pub enum PostJsonError {
+    /// Failed to serialize the request body or to deserialize the response body.
+    #[error("Failed to de/serialize JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    /// Failed to send the request or to read the response.
+    #[error("Failed HTTP request: {0}")]
+    Reqwest(#[from] reqwest::Error),
+    /// An attempt didn't complete before its timeout.
+    #[error("Request timed out: {0}")]
+    Timeout(#[from] tokio::time::error::Elapsed),
+    /// The server responded with a non-success status code.
+    #[error("Unexpected status code {status}: {body}")]
+    Status {
+        /// The status code of the response.
+        status: StatusCode,
+        /// The body of the response.
+        body: String,
+    },
+    /// The server rate-limited the request with a `429 Too Many Requests` response.
+    #[error("Rate limited, retry after {retry_after:?}: {body}")]
+    RateLimited {
+        /// The delay requested by the `Retry-After` header of the response, if any.
+        retry_after: Option<Duration>,
+        /// The body of the response.
+        body: String,
+    },
+}
+
+impl PostJsonError {
+    /// Returns `true` if the request may succeed if retried: network errors, timeouts,
+    /// server errors and rate limiting are transient, while client errors are not.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Reqwest(err) => !err.is_decode() && !err.is_builder(),
+            Self::Timeout(_) => true,
+            Self::Status { status, .. } => status.is_server_error(),
+            Self::RateLimited { .. } => true,
+            Self::Json(_) => false,
+        }
+    }
+
+    /// Returns the delay to wait before retrying a rate-limited request, if the server
+    /// requested one.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+/// Parses the `Retry-After` header of a response as a number of seconds. HTTP dates aren't
+/// supported, in which case the request is retried with the regular backoff.
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?;
+    value.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// POSTs the JSON-encoded `body` to `url` and decodes the JSON response, retrying transient
+/// failures according to the backoff `policy`. Each attempt times out after `timeout`, or
+/// at the deadline of the policy if it comes first, and no retry starts past it.
+///
+/// Rate-limited attempts are retried after the delay of their `Retry-After` header instead,
+/// capped by the policy, so that the server isn't hammered. See
+/// [BackoffPolicy::retry_after_delay].
+///
+/// An empty response body is decoded as `null`, so that `()` can be used for endpoints that
+/// don't return anything.
+pub async fn post_json_with_backoff<B, T>(
+    client: &reqwest::Client,
+    url: Url,
+    headers: HeaderMap,
+    body: &B,
+    timeout: Duration,
+    policy: BackoffPolicy,
+) -> Result<T, PostJsonError>
+where
+    B: Serialize + ?Sized,
+    T: DeserializeOwned,
+{
+    let response = post_json_with_backoff_raw(client, url, headers, body, timeout, policy).await?;
+    let response = if response.is_empty() { b"null".as_slice() } else { &response[..] };
+    Ok(serde_json::from_slice(response)?)
+}
+
+/// POSTs the JSON-encoded `body` to `url` like [post_json_with_backoff], but returns the raw
+/// body of the successful response, for endpoints that may not respond with JSON.
+pub async fn post_json_with_backoff_raw<B>(
+    client: &reqwest::Client,
+    url: Url,
+    headers: HeaderMap,
+    body: &B,
+    timeout: Duration,
+    policy: BackoffPolicy,
+) -> Result<Vec<u8>, PostJsonError>
+where
+    B: Serialize + ?Sized,
+{
+    let body = serde_json::to_vec(body)?;
+    let request = || {
+        client
+            .post(url.clone())
+            .headers(headers.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.clone())
+    };
+
+    send_with_backoff(request, timeout, policy).await
+}
+
+/// GETs `url` and decodes the JSON response, retrying transient failures according to the
+/// backoff `policy` like [post_json_with_backoff], including the rate-limited ones.
+pub async fn get_json_with_backoff<T>(
+    client: &reqwest::Client,
+    url: Url,
+    headers: HeaderMap,
+    timeout: Duration,
+    policy: BackoffPolicy,
+) -> Result<T, PostJsonError>
+where
+    T: DeserializeOwned,
+{
+    let request = || client.get(url.clone()).headers(headers.clone());
+    let response = send_with_backoff(request, timeout, policy).await?;
+    Ok(serde_json::from_slice(&response)?)
+}
+
+/// Sends the requests built by `request` until one succeeds, retrying transient failures
+/// according to the backoff `policy`, and returns the raw body of the successful response.
+async fn send_with_backoff(
+    request: impl Fn() -> reqwest::RequestBuilder,
+    timeout: Duration,
+    policy: BackoffPolicy,
+) -> Result<Vec<u8>, PostJsonError> {
+    let attempt = |timeout: Duration| {
+        let request = request();
+        async move {
+            let (status, retry_after, response) = tokio::time::timeout(timeout, async {
+                let response = request.send().await?;
+                let retry_after = parse_retry_after(response.headers());
+                Ok::<_, reqwest::Error>((response.status(), retry_after, response.bytes().await?))
+            })
+            .await??;
+
+            if status == StatusCode::TOO_MANY_REQUESTS {
+                let body = String::from_utf8_lossy(&response).into_owned();
+                return Err(PostJsonError::RateLimited { retry_after, body });
+            }
+
+            if !status.is_success() {
+                let body = String::from_utf8_lossy(&response).into_owned();
+                return Err(PostJsonError::Status { status, body });
+            }
+
+            Ok(response.to_vec())
+        }
+    };
+
+    let mut delays = policy.delays();
+    loop {
+        let err = match attempt(policy.attempt_timeout(timeout)).await {
+            Ok(response) => return Ok(response),
+            Err(err) => err,
+        };
+
+        let Some(delay) = delays.next().filter(|_| err.is_transient()) else {
+            return Err(err);
+        };
+        let delay = err.retry_after().map_or(delay, |after| policy.retry_after_delay(after));
+        if !policy.allows_retry_after(delay) {
+            return Err(err);
+        }
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    };
+
+    use axum::{
+        extract::State,
+        routing::{get, post},
+        Json, Router,
+    };
+    use reqwest::{
+        header::{HeaderMap, HeaderName, RETRY_AFTER},
+        StatusCode, Url,
+    };
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
+
+    use super::{get_json_with_backoff, post_json_with_backoff, PostJsonError};
+    use crate::common::backoff::BackoffPolicy;
+
+    /// Spawns a server that fails the first `failures` requests with the given status code,
+    /// then echoes the request body. Returns its URL and the number of requests received.
+    async fn spawn_flaky_server(failures: usize, status: StatusCode) -> (Url, Arc<AtomicUsize>) {
+        type Ctx = (Arc<AtomicUsize>, usize, StatusCode);
+
+        async fn echo(
+            State((requests, failures, status)): State<Ctx>,
+            Json(body): Json<Value>,
+        ) -> Result<Json<Value>, StatusCode> {
+            if requests.fetch_add(1, Ordering::SeqCst) < failures {
+                return Err(status);
+            }
+            Ok(Json(body))
+        }
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let router =
+            Router::new().route("/", post(echo)).with_state((requests.clone(), failures, status));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        (url, requests)
+    }
+
+    #[tokio::test]
+    async fn test_post_json_retries_until_success() -> eyre::Result<()> {
+        let (url, requests) = spawn_flaky_server(2, StatusCode::SERVICE_UNAVAILABLE).await;
+        let client = reqwest::Client::new();
+        let body = json!({ "slot": 42 });
+        let timeout = Duration::from_secs(1);
+
+        let response: Value = post_json_with_backoff(
+            &client,
+            url,
+            HeaderMap::new(),
+            &body,
+            timeout,
+            BackoffPolicy::default(),
+        )
+        .await?;
+
+        assert_eq!(response, body);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        // Client errors aren't retried
+        let (url, requests) = spawn_flaky_server(1, StatusCode::BAD_REQUEST).await;
+        let res = post_json_with_backoff::<_, Value>(
+            &client,
+            url,
+            HeaderMap::new(),
+            &body,
+            timeout,
+            BackoffPolicy::default(),
+        )
+        .await;
+
+        assert!(matches!(res, Err(PostJsonError::Status { status: StatusCode::BAD_REQUEST, .. })));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_post_json_honors_retry_after() -> eyre::Result<()> {
+        /// Rate-limits the first request with the given `Retry-After` value, then echoes.
+        async fn rate_limited(
+            State((requests, retry_after)): State<(Arc<AtomicUsize>, &'static str)>,
+            Json(body): Json<Value>,
+        ) -> Result<Json<Value>, (StatusCode, [(HeaderName, &'static str); 1])> {
+            if requests.fetch_add(1, Ordering::SeqCst) == 0 {
+                return Err((StatusCode::TOO_MANY_REQUESTS, [(RETRY_AFTER, retry_after)]));
+            }
+            Ok(Json(body))
+        }
+
+        async fn spawn_rate_limited_server(retry_after: &'static str) -> (Url, Arc<AtomicUsize>) {
+            let requests = Arc::new(AtomicUsize::new(0));
+            let router = Router::new()
+                .route("/", post(rate_limited))
+                .with_state((requests.clone(), retry_after));
+
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+            tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+            (url, requests)
+        }
+
+        let client = reqwest::Client::new();
+        let body = json!({ "slot": 42 });
+        let timeout = Duration::from_secs(1);
+
+        // The retry waits for the delay requested by the server, past the max backoff delay
+        let (url, requests) = spawn_rate_limited_server("2").await;
+        let policy = BackoffPolicy::default();
+        let start = Instant::now();
+        let response: Value =
+            post_json_with_backoff(&client, url, HeaderMap::new(), &body, timeout, policy).await?;
+
+        assert_eq!(response, body);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert!(start.elapsed() >= Duration::from_secs(2));
+
+        // The requested delay is capped
+        let (url, requests) = spawn_rate_limited_server("3600").await;
+        let policy =
+            BackoffPolicy { max_retry_after: Duration::from_millis(500), ..Default::default() };
+        let start = Instant::now();
+        let response: Value =
+            post_json_with_backoff(&client, url, HeaderMap::new(), &body, timeout, policy).await?;
+
+        assert_eq!(response, body);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(500) && elapsed < Duration::from_secs(2));
+
+        // With a deadline, the requested delay is capped to half the time left, so that the
+        // retry still happens before it
+        let (url, requests) = spawn_rate_limited_server("2").await;
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(1);
+        let policy = BackoffPolicy::default().with_deadline(deadline);
+        let response: Value =
+            post_json_with_backoff(&client, url, HeaderMap::new(), &body, timeout, policy).await?;
+
+        assert_eq!(response, body);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert!(tokio::time::Instant::now() < deadline);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_json_honors_retry_after() -> eyre::Result<()> {
+        /// Rate-limits the first request for a second, then responds.
+        async fn rate_limited(
+            State(requests): State<Arc<AtomicUsize>>,
+        ) -> Result<Json<Value>, (StatusCode, [(HeaderName, &'static str); 1])> {
+            if requests.fetch_add(1, Ordering::SeqCst) == 0 {
+                return Err((StatusCode::TOO_MANY_REQUESTS, [(RETRY_AFTER, "1")]));
+            }
+            Ok(Json(json!({ "slot": 42 })))
+        }
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let router = Router::new().route("/", get(rate_limited)).with_state(requests.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = Url::parse(&format!("http://{}", listener.local_addr()?))?;
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let client = reqwest::Client::new();
+        let start = Instant::now();
+        let response: Value = get_json_with_backoff(
+            &client,
+            url,
+            HeaderMap::new(),
+            Duration::from_secs(1),
+            BackoffPolicy::default(),
+        )
+        .await?;
+
+        assert_eq!(response, json!({ "slot": 42 }));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert!(start.elapsed() >= Duration::from_secs(1));
+
+        Ok(())
+    }
+}
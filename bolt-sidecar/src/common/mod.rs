@@ -1,5 +1,9 @@
 /// Utilities for retrying a future with backoff.
 pub mod backoff;
+/// Circuit breaker to stop relying on a dependency after repeated failures.
+pub mod circuit_breaker;
+/// Helpers for the HTTP requests of the sidecar clients.
+pub mod http;
 /// A hash map-like bounded data structure with an additional scoring mechanism.
 pub mod score_cache;
 /// Secret key types wrappers for BLS, ECDSA and JWT.
@@ -1,10 +1,10 @@
-use alloy::primitives::U256;
+use alloy::{
+    eips::{calc_next_block_base_fee, eip1559::BaseFeeParams, eip2930::AccessList},
+    primitives::U256,
+};
 use reth_primitives::PooledTransactionsElement;
 
-use crate::{
-    primitives::{AccountState, TransactionExt},
-    state::ValidationError,
-};
+use crate::primitives::TransactionExt;
 
 /// Calculates the max_basefee `slot_diff` blocks in the future given a current basefee (in wei).
 /// Returns None if an overflow would occur.
@@ -31,12 +31,94 @@ pub fn calculate_max_basefee(current: u128, block_diff: u64) -> Option<u128> {
     Some(max_basefee)
 }
 
+/// Predicts the basefee of the block following a parent block with the given gas usage, gas
+/// limit and basefee (in wei), according to EIP-1559.
+///
+/// The basefee stays the same if the parent block used exactly its gas target, i.e. half of
+/// its gas limit. It increases by up to 12.5% after a full block, and decreases by 12.5% after
+/// an empty one.
+pub fn predict_next_basefee(
+    parent_gas_used: u64,
+    parent_gas_limit: u64,
+    parent_basefee: u64,
+) -> u64 {
+    calc_next_block_base_fee(
+        parent_gas_used,
+        parent_gas_limit,
+        parent_basefee,
+        BaseFeeParams::ethereum(),
+    )
+}
+
+/// The intrinsic gas of a transaction, before any of its data or authorizations.
+const TX_BASE_GAS: u64 = 21_000;
+
+/// The intrinsic gas of a contract-creation transaction, before any of its data.
+const TX_CREATE_GAS: u64 = 53_000;
+
+/// The intrinsic gas of each zero byte of the transaction input.
+const TX_DATA_ZERO_GAS: u64 = 4;
+
+/// The intrinsic gas of each non-zero byte of the transaction input.
+const TX_DATA_NON_ZERO_GAS: u64 = 16;
+
+/// The intrinsic gas of each 32-byte word of the init code of a contract creation (EIP-3860).
+const INIT_CODE_WORD_GAS: u64 = 2;
+
+/// The intrinsic gas of each address of the access list (EIP-2930).
+const ACCESS_LIST_ADDRESS_GAS: u64 = 2_400;
+
+/// The intrinsic gas of each storage key of the access list (EIP-2930).
+const ACCESS_LIST_STORAGE_KEY_GAS: u64 = 1_900;
+
+/// The intrinsic gas of each EIP-7702 authorization, charged as if the authority was empty.
+const PER_AUTHORIZATION_GAS: u64 = 25_000;
+
+/// Calculates the intrinsic gas of a transaction, i.e. the gas charged before its execution.
+/// Transactions with a lower gas limit are invalid.
+///
+/// It includes the base cost of the transaction, the cost of its input and access list, the
+/// init code words of contract creations, and 25000 gas per EIP-7702 authorization.
+pub fn intrinsic_gas(transaction: &PooledTransactionsElement) -> u64 {
+    let input = transaction.input();
+    let zero_bytes = input.iter().filter(|byte| **byte == 0).count() as u64;
+    let non_zero_bytes = input.len() as u64 - zero_bytes;
+
+    let mut gas = zero_bytes * TX_DATA_ZERO_GAS + non_zero_bytes * TX_DATA_NON_ZERO_GAS;
+
+    if transaction.tx_kind().is_create() {
+        gas += TX_CREATE_GAS + (input.len() as u64).div_ceil(32) * INIT_CODE_WORD_GAS;
+    } else {
+        gas += TX_BASE_GAS;
+    }
+
+    let access_list = match transaction {
+        PooledTransactionsElement::Legacy { .. } => None,
+        PooledTransactionsElement::Eip2930 { transaction, .. } => Some(&transaction.access_list),
+        PooledTransactionsElement::Eip1559 { transaction, .. } => Some(&transaction.access_list),
+        PooledTransactionsElement::Eip7702 { transaction, .. } => Some(&transaction.access_list),
+        PooledTransactionsElement::BlobTransaction(blob_tx) => {
+            Some(&blob_tx.transaction.tx.access_list)
+        }
+    };
+    if let Some(AccessList(items)) = access_list {
+        for item in items {
+            gas += ACCESS_LIST_ADDRESS_GAS;
+            gas += item.storage_keys.len() as u64 * ACCESS_LIST_STORAGE_KEY_GAS;
+        }
+    }
+
+    let authorizations = transaction.authorization_list().map_or(0, <[_]>::len) as u64;
+    gas + authorizations * PER_AUTHORIZATION_GAS
+}
+
 /// Calculates the max transaction cost (gas + value) in wei.
 ///
 /// - For EIP-1559 transactions: `max_fee_per_gas * gas_limit + tx_value`.
 /// - For legacy transactions: `gas_price * gas_limit + tx_value`.
 /// - For EIP-4844 blob transactions: `max_fee_per_gas * gas_limit + tx_value + max_blob_fee_per_gas
 ///   * blob_gas_used`.
+/// - For EIP-7702 transactions: as for EIP-1559 ones, the authorizations are paid by the gas.
 pub fn max_transaction_cost(transaction: &PooledTransactionsElement) -> U256 {
     let gas_limit = transaction.gas_limit() as u128;
 
@@ -50,45 +132,21 @@ pub fn max_transaction_cost(transaction: &PooledTransactionsElement) -> U256 {
     U256::from(gas_limit * fee_cap) + transaction.value()
 }
 
-/// This function validates a transaction against an account state. It checks 2 things:
-/// 1. The nonce of the transaction must be higher than the account's nonce, but not higher than
-///    current + 1.
-/// 2. The balance of the account must be higher than the transaction's max cost.
-pub fn validate_transaction(
-    account_state: &AccountState,
-    transaction: &PooledTransactionsElement,
-) -> Result<(), ValidationError> {
-    // Check if the nonce is correct (should be the same as the transaction count)
-    if transaction.nonce() < account_state.transaction_count {
-        return Err(ValidationError::NonceTooLow(
-            account_state.transaction_count,
-            transaction.nonce(),
-        ));
-    }
-
-    if transaction.nonce() > account_state.transaction_count {
-        return Err(ValidationError::NonceTooHigh(
-            account_state.transaction_count,
-            transaction.nonce(),
-        ));
-    }
-
-    // Check if the balance is enough
-    if max_transaction_cost(transaction) > account_state.balance {
-        return Err(ValidationError::InsufficientBalance);
-    }
-
-    // Check if the account has code (i.e. is a smart contract)
-    if account_state.has_code {
-        return Err(ValidationError::AccountHasCode);
-    }
-
-    Ok(())
-}
-
 #[cfg(test)]
 mod tests {
+    use alloy::{
+        eips::{eip2718::Encodable2718, eip2930::AccessListItem},
+        network::{EthereumWallet, TransactionBuilder},
+        primitives::{Address, Bytes, TxKind, B256},
+        signers::local::PrivateKeySigner,
+    };
+    use reth_primitives::TxType;
+
     use super::*;
+    use crate::{
+        primitives::FullTransaction,
+        test_util::{default_test_7702_transaction, default_test_transaction},
+    };
 
     #[test]
     fn test_calculate_max_basefee() {
@@ -98,4 +156,84 @@ mod tests {
         let result = calculate_max_basefee(current, slot_diff);
         assert_eq!(result, Some(28865075793))
     }
+
+    #[test]
+    fn test_predict_next_basefee() {
+        let basefee = 1_000_000_000; // 1 gwei
+        let gas_limit = 30_000_000;
+
+        // At the gas target, i.e. half of the gas limit
+        assert_eq!(predict_next_basefee(15_000_000, gas_limit, basefee), basefee);
+
+        // 5M gas above the target of 15M: +1/3 of the max 12.5% increase
+        assert_eq!(predict_next_basefee(20_000_000, gas_limit, basefee), 1_041_666_666);
+
+        // Full and empty parent blocks
+        assert_eq!(predict_next_basefee(gas_limit, gas_limit, basefee), 1_125_000_000);
+        assert_eq!(predict_next_basefee(0, gas_limit, basefee), 875_000_000);
+
+        // The basefee always increases after a block above its target, even if tiny
+        assert_eq!(predict_next_basefee(gas_limit, gas_limit, 7), 8);
+    }
+
+    #[tokio::test]
+    async fn test_intrinsic_gas() -> eyre::Result<()> {
+        let sender = PrivateKeySigner::random();
+        let wallet = EthereumWallet::from(sender.clone());
+
+        let access_list = AccessList(vec![AccessListItem {
+            address: Address::random(),
+            storage_keys: vec![B256::random(), B256::random()],
+        }]);
+        let tx = default_test_transaction(sender.address(), None)
+            .with_input(Bytes::from_static(&[0, 0, 1]))
+            .with_access_list(access_list);
+        let tx = FullTransaction::decode_enveloped(tx.build(&wallet).await?.encoded_2718())?;
+
+        // Two zero bytes, a non-zero one, an address and two storage keys
+        assert_eq!(intrinsic_gas(&tx), 21_000 + 2 * 4 + 16 + 2_400 + 2 * 1_900);
+
+        // A contract creation with 33 bytes of non-zero init code, i.e. 2 words
+        let mut tx = default_test_transaction(sender.address(), None)
+            .with_input(Bytes::from(vec![1; 33]))
+            .with_gas_limit(100_000);
+        tx.to = Some(TxKind::Create);
+        let tx = FullTransaction::decode_enveloped(tx.build(&wallet).await?.encoded_2718())?;
+
+        assert_eq!(intrinsic_gas(&tx), 53_000 + 33 * 16 + 2 * 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_eip7702_transaction() -> eyre::Result<()> {
+        let sender = PrivateKeySigner::random();
+        let delegate = Address::random();
+        let tx = default_test_7702_transaction(&sender, None, delegate)?;
+
+        let wallet = EthereumWallet::from(sender.clone());
+        let raw = tx.build(&wallet).await?.encoded_2718();
+        let tx = FullTransaction::decode_enveloped(raw)?;
+
+        assert_eq!(tx.tx_type(), TxType::Eip7702);
+        assert_eq!(tx.tx_kind(), TxKind::Call(Address::ZERO));
+        assert_eq!(tx.chain_id(), Some(1337));
+        assert_eq!(tx.value(), U256::from(100));
+        assert!(tx.size() > 0);
+        assert_eq!(tx.recover_signer(), Some(sender.address()));
+
+        let authorizations = tx.authorization_list().expect("eip-7702 authorization list");
+        assert_eq!(authorizations.len(), 1);
+        assert_eq!(authorizations[0].address, delegate);
+        assert_eq!(authorizations[0].recover_authority()?, sender.address());
+
+        // 46000 gas at a max fee of 20 gwei plus a tip of 1 gwei, and the value
+        let cost = 46_000 * 21_000_000_000 + 100;
+        assert_eq!(max_transaction_cost(&tx), U256::from(cost));
+
+        // The base cost of the transaction, and 25000 gas for its authorization
+        assert_eq!(intrinsic_gas(&tx), 46_000);
+
+        Ok(())
+    }
 }
@@ -5,11 +5,16 @@ use std::{
     time::Duration,
 };
 
-use alloy::primitives::{address, Address};
+use alloy::primitives::{address, Address, B256};
 use clap::{Args, ValueEnum};
-use ethereum_consensus::deneb::{compute_fork_data_root, Root};
+use ethereum_consensus::{
+    deneb::{compute_fork_data_root, Root},
+    phase0::{mainnet::SLOTS_PER_EPOCH, minimal::SLOTS_PER_EPOCH as MINIMAL_SLOTS_PER_EPOCH},
+};
 use serde::Deserialize;
 
+use crate::{crypto::bls::BlsDst, state::SlotClock};
+
 /// Default commitment deadline duration.
 ///
 /// The sidecar will stop accepting new commitments for the next block
@@ -17,6 +22,16 @@ use serde::Deserialize;
 /// relays have enough time to build valid payloads.
 pub const DEFAULT_COMMITMENT_DEADLINE_IN_MILLIS: u64 = 8_000;
 
+/// The deadline in a slot by which attesters vote for its block, in milliseconds. Blocks
+/// must be propagated before it to get the proposer boost.
+pub const ATTESTATION_DEADLINE_IN_MILLIS: u64 = 4_000;
+
+/// Default constraints submission cutoff.
+///
+/// Constraints should reach the relays by this time in their target slot, well before the
+/// attestation deadline, so that the proposer can still get a header honoring them.
+pub const DEFAULT_CONSTRAINTS_CUTOFF_IN_MILLIS: u64 = 1_000;
+
 /// Default slot time duration in seconds.
 pub const DEFAULT_SLOT_TIME_IN_SECONDS: u64 = 12;
 
@@ -30,10 +45,51 @@ pub const COMMIT_BOOST_DOMAIN_MASK: [u8; 4] = [109, 109, 111, 67];
 pub const DEFAULT_CHAIN_CONFIG: ChainConfig = ChainConfig {
     chain: Chain::Mainnet,
     commitment_deadline: DEFAULT_COMMITMENT_DEADLINE_IN_MILLIS,
+    constraints_cutoff: DEFAULT_CONSTRAINTS_CUTOFF_IN_MILLIS,
     slot_time: DEFAULT_SLOT_TIME_IN_SECONDS,
     enable_unsafe_lookahead: false,
+    max_lookahead_slots: None,
+    genesis_validators_root: None,
+    preset: Preset::Mainnet,
+    bls_dst: BlsDst::POP,
 };
 
+/// The fork schedule of Mainnet, as `(activation_epoch, fork_version)` pairs.
+pub const MAINNET_FORK_SCHEDULE: &[(u64, [u8; 4])] = &[
+    (0, [0, 0, 0, 0]),
+    (74_240, [1, 0, 0, 0]),
+    (144_896, [2, 0, 0, 0]),
+    (194_048, [3, 0, 0, 0]),
+    (269_568, [4, 0, 0, 0]),
+    (364_032, [5, 0, 0, 0]),
+    (411_392, [6, 0, 0, 0]),
+];
+
+/// The fork schedule of Holesky, as `(activation_epoch, fork_version)` pairs.
+pub const HOLESKY_FORK_SCHEDULE: &[(u64, [u8; 4])] = &[
+    (0, [1, 1, 112, 0]),
+    (0, [2, 1, 112, 0]),
+    (0, [3, 1, 112, 0]),
+    (256, [4, 1, 112, 0]),
+    (29_696, [5, 1, 112, 0]),
+    (115_968, [6, 1, 112, 0]),
+    (165_120, [7, 1, 112, 0]),
+];
+
+/// The fork schedule of Helder. Only its genesis fork version is tracked.
+pub const HELDER_FORK_SCHEDULE: &[(u64, [u8; 4])] = &[(0, [16, 0, 0, 0])];
+
+/// The fork schedule of Kurtosis devnets, where all the forks up to Electra are active
+/// from genesis by default.
+pub const KURTOSIS_FORK_SCHEDULE: &[(u64, [u8; 4])] = &[
+    (0, [16, 0, 0, 56]),
+    (0, [32, 0, 0, 56]),
+    (0, [48, 0, 0, 56]),
+    (0, [64, 0, 0, 56]),
+    (0, [80, 0, 0, 56]),
+    (0, [96, 0, 0, 56]),
+];
+
 /// The address of the canonical BoltManager contract for the Holesky chain.
 ///
 /// https://holesky.etherscan.io/address/0x440202829b493F9FF43E730EB5e8379EEa3678CF
@@ -53,6 +109,14 @@ pub struct ChainConfig {
         default_value_t = DEFAULT_CHAIN_CONFIG.commitment_deadline
     )]
     pub(crate) commitment_deadline: u64,
+    /// The time in the target slot by which constraints should be posted to the relays to be
+    /// honored (parsed as milliseconds). It should be well before the 4s attestation deadline.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_CONSTRAINTS_CUTOFF",
+        default_value_t = DEFAULT_CHAIN_CONFIG.constraints_cutoff
+    )]
+    pub(crate) constraints_cutoff: u64,
     /// The slot time duration in seconds. If provided,
     /// it overrides the default for the selected [Chain].
     #[clap(
@@ -69,6 +133,26 @@ pub struct ChainConfig {
         default_value_t = DEFAULT_CHAIN_CONFIG.enable_unsafe_lookahead
     )]
     pub(crate) enable_unsafe_lookahead: bool,
+    /// The maximum number of slots ahead of the current one for which commitments are
+    /// accepted. It can only narrow the window of the proposer duties, which spans the current
+    /// epoch, or the next one too with the unsafe lookahead. If unset, the whole window is used.
+    #[clap(long, env = "BOLT_SIDECAR_MAX_LOOKAHEAD_SLOTS")]
+    pub(crate) max_lookahead_slots: Option<u64>,
+    /// The genesis validators root to use in the signing domains. The application-builder
+    /// and commit-boost specs require it to be zero, which is the default. Only set this
+    /// for protocols or test networks that expect a real root in the domain.
+    #[clap(long, env = "BOLT_SIDECAR_GENESIS_VALIDATORS_ROOT")]
+    pub(crate) genesis_validators_root: Option<B256>,
+    /// The SSZ preset of the chain. Only minimal-preset devnets need to change it.
+    #[clap(long, env = "BOLT_SIDECAR_PRESET", default_value_t = DEFAULT_CHAIN_CONFIG.preset)]
+    #[serde(default)]
+    pub(crate) preset: Preset,
+    /// The domain separation tag of the BLS signatures of constraints and delegations. The
+    /// default is the proof-of-possession tag used by commit-boost: only change it to
+    /// interoperate with signers or relays using another one.
+    #[clap(long, env = "BOLT_SIDECAR_BLS_DST", default_value_t = DEFAULT_CHAIN_CONFIG.bls_dst)]
+    #[serde(default)]
+    pub(crate) bls_dst: BlsDst,
 }
 
 impl Default for ChainConfig {
@@ -107,7 +191,7 @@ impl Chain {
         }
     }
 
-    /// Get the fork version for the given chain.
+    /// Get the genesis fork version for the given chain.
     pub fn fork_version(&self) -> [u8; 4] {
         match self {
             Self::Mainnet => [0, 0, 0, 0],
@@ -117,6 +201,17 @@ impl Chain {
         }
     }
 
+    /// Returns the fork schedule of the chain, as `(activation_epoch, fork_version)` pairs
+    /// ordered by activation epoch, starting with the genesis fork version.
+    pub fn fork_schedule(&self) -> &'static [(u64, [u8; 4])] {
+        match self {
+            Self::Mainnet => MAINNET_FORK_SCHEDULE,
+            Self::Holesky => HOLESKY_FORK_SCHEDULE,
+            Self::Helder => HELDER_FORK_SCHEDULE,
+            Self::Kurtosis => KURTOSIS_FORK_SCHEDULE,
+        }
+    }
+
     /// Returns the address of the canonical BoltManager contract for a given chain, if present
     pub const fn manager_address(&self) -> Option<Address> {
         match self {
@@ -132,6 +227,38 @@ impl Display for Chain {
     }
 }
 
+/// The SSZ presets of the consensus specs, defining the epoch length and the list limits
+/// used in the merkleization of consensus and builder-api types.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[clap(rename_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    /// The preset of Mainnet and of the public testnets.
+    #[default]
+    Mainnet,
+    /// The preset of some local devnets, with shorter epochs and smaller lists.
+    Minimal,
+}
+
+impl Preset {
+    /// Returns the number of slots in an epoch.
+    pub const fn slots_per_epoch(&self) -> u64 {
+        match self {
+            Self::Mainnet => SLOTS_PER_EPOCH,
+            Self::Minimal => MINIMAL_SLOTS_PER_EPOCH,
+        }
+    }
+}
+
+impl Display for Preset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mainnet => write!(f, "mainnet"),
+            Self::Minimal => write!(f, "minimal"),
+        }
+    }
+}
+
 impl ChainConfig {
     /// Get the chain ID for the given chain.
     pub fn chain_id(&self) -> u64 {
@@ -148,6 +275,21 @@ impl ChainConfig {
         self.slot_time
     }
 
+    /// Get the number of slots in an epoch, according to the preset of the chain.
+    pub fn slots_per_epoch(&self) -> u64 {
+        self.preset.slots_per_epoch()
+    }
+
+    /// Get a clock of the slots of the chain, given its genesis time in seconds.
+    pub fn slot_clock(&self, genesis_time: u64) -> SlotClock {
+        SlotClock::new(genesis_time, self.slot_time(), self.slots_per_epoch())
+    }
+
+    /// Get the domain separation tag of the BLS signatures.
+    pub fn bls_dst(&self) -> &[u8] {
+        self.bls_dst.as_bytes()
+    }
+
     /// Get the domain for signing application-builder messages on the given chain.
     pub fn application_builder_domain(&self) -> [u8; 32] {
         self.compute_domain_from_mask(APPLICATION_BUILDER_DOMAIN_MASK)
@@ -163,16 +305,27 @@ impl ChainConfig {
         Duration::from_millis(self.commitment_deadline)
     }
 
+    /// Get the constraints submission cutoff duration, from the start of the target slot.
+    pub fn constraints_cutoff(&self) -> Duration {
+        Duration::from_millis(self.constraints_cutoff)
+    }
+
     /// Compute the domain for signing messages on the given chain.
     fn compute_domain_from_mask(&self, mask: [u8; 4]) -> [u8; 32] {
-        let mut domain = [0; 32];
+        self.compute_domain(mask, self.chain.fork_version())
+    }
 
-        let fork_version = self.chain.fork_version();
+    /// Compute the domain of the given type with the given fork version.
+    fn compute_domain(&self, mask: [u8; 4], fork_version: [u8; 4]) -> [u8; 32] {
+        let mut domain = [0; 32];
 
         // Note: the application builder domain specs require the genesis_validators_root
         // to be 0x00 for any out-of-protocol message. The commit-boost domain follows the
-        // same rule.
-        let root = Root::default();
+        // same rule, so a custom root is only used if explicitly configured.
+        let root = self
+            .genesis_validators_root
+            .map(|root| Root::from_slice(root.as_slice()))
+            .unwrap_or_default();
         let fork_data_root = compute_fork_data_root(fork_version, root).expect("valid fork data");
 
         domain[..4].copy_from_slice(&mask);
@@ -237,4 +390,37 @@ mod tests {
         let kurtosis = ChainConfig::kurtosis(0, 0);
         assert_eq!(kurtosis.application_builder_domain(), BUILDER_DOMAIN_KURTOSIS);
     }
+
+    #[test]
+    fn test_custom_genesis_validators_root() {
+        use super::ChainConfig;
+
+        let mainnet = ChainConfig::mainnet();
+        let custom = ChainConfig {
+            genesis_validators_root: Some(b256!(
+                "4b363db94e286120d76eb905340fdd4e54bfe9f06bf33ff6cf5ad27f511bfe95"
+            )),
+            ..mainnet
+        };
+
+        assert_ne!(custom.application_builder_domain(), mainnet.application_builder_domain());
+        assert_ne!(custom.commit_boost_domain(), mainnet.commit_boost_domain());
+
+        // The domain type is kept
+        assert_eq!(custom.application_builder_domain()[..4], BUILDER_DOMAIN_MAINNET[..4]);
+
+        // An explicit zero root is the same as the default
+        let zero = ChainConfig { genesis_validators_root: Some(Default::default()), ..mainnet };
+        assert_eq!(zero.commit_boost_domain(), mainnet.commit_boost_domain());
+    }
+
+    #[test]
+    fn test_fork_schedule() {
+        use super::Chain;
+
+        for chain in [Chain::Mainnet, Chain::Holesky, Chain::Helder, Chain::Kurtosis] {
+            assert_eq!(chain.fork_schedule()[0].1, chain.fork_version());
+            assert!(chain.fork_schedule().windows(2).all(|forks| forks[0].0 <= forks[1].0));
+        }
+    }
 }
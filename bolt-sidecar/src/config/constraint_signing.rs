@@ -17,12 +17,54 @@ pub struct ConstraintSigningOpts {
     /// Private key to use for signing constraint messages
     #[clap(long, env = "BOLT_SIDECAR_CONSTRAINT_PRIVATE_KEY")]
     pub constraint_private_key: Option<BlsSecretKeyWrapper>,
+    /// Comma-separated list of the URLs of remote commit-boost signers, each on its own host,
+    /// jointly signing constraint messages with the constraint private key. The signatures
+    /// are aggregated, and verify against the aggregate public key of the keys, which is the
+    /// key to delegate to.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_CONSTRAINT_COSIGNER_URLS",
+        value_delimiter = ',',
+        requires_all(["constraint_private_key", "constraint_cosigner_jwts"])
+    )]
+    #[serde(default)]
+    pub constraint_cosigner_urls: Vec<Url>,
+    /// Comma-separated list of the JWTs authenticating with the co-signers, in hexadecimal
+    /// format or as paths to files holding them, in the same order as their URLs.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_CONSTRAINT_COSIGNER_JWTS",
+        value_delimiter = ',',
+        requires("constraint_cosigner_urls")
+    )]
+    #[serde(default)]
+    pub constraint_cosigner_jwts: Vec<JwtSecretConfig>,
     /// URL for the commit-boost sidecar
     #[clap(long, env = "BOLT_SIDECAR_CB_SIGNER_URL", requires("commit_boost_jwt_hex"))]
     pub commit_boost_signer_url: Option<Url>,
     /// JWT in hexadecimal format for authenticating with the commit-boost service
     #[clap(long, env = "BOLT_SIDECAR_CB_JWT_HEX", requires("commit_boost_signer_url"))]
     pub commit_boost_jwt_hex: Option<JwtSecretConfig>,
+    /// Sign constraints with a BLS proxy key delegated from the commit-boost consensus key,
+    /// instead of the consensus key itself. The proxy key should be the delegatee of the
+    /// validators in the delegations file.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_CB_PROXY_KEY",
+        default_value_t = false,
+        requires("commit_boost_signer_url")
+    )]
+    pub commit_boost_proxy_key: bool,
+    /// The file where the delegation of the commit-boost proxy key is persisted. If the file
+    /// holds a valid delegation to a proxy key still known to the signer, that key is reused
+    /// across restarts, so that the delegations file stays valid. Otherwise a new proxy key
+    /// is requested and its delegation written to the file.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_CB_PROXY_DELEGATION_PATH",
+        requires("commit_boost_proxy_key")
+    )]
+    pub commit_boost_proxy_delegation_path: Option<PathBuf>,
     /// The password for the ERC-2335 keystore.
     /// Reference: https://eips.ethereum.org/EIPS/eip-2335
     #[clap(long, env = "BOLT_SIDECAR_KEYSTORE_PASSWORD")]
@@ -44,8 +86,12 @@ impl fmt::Debug for ConstraintSigningOpts {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("SigningOpts")
             .field("constraint_private_key", &"********") // Hides the actual private key
+            .field("constraint_cosigner_urls", &self.constraint_cosigner_urls)
+            .field("constraint_cosigner_jwts", &"********")
             .field("commit_boost_signer_url", &self.commit_boost_signer_url)
             .field("commit_boost_jwt_hex", &self.commit_boost_jwt_hex)
+            .field("commit_boost_proxy_key", &self.commit_boost_proxy_key)
+            .field("commit_boost_proxy_delegation_path", &self.commit_boost_proxy_delegation_path)
             .field("keystore_password", &"********") // Hides the actual password
             .field("keystore_path", &self.keystore_path)
             .field("keystore_secrets_path", &self.keystore_secrets_path)
@@ -1,5 +1,6 @@
 use std::num::NonZero;
 
+use alloy::primitives::U256;
 use clap::Parser;
 
 /// Default max commitments to accept per block.
@@ -14,6 +15,10 @@ pub const DEFAULT_MIN_PRIORITY_FEE: u128 = 1_000_000_000; // 1 Gwei
 /// Default max account states size.
 pub const DEFAULT_MAX_ACCOUNT_STATES_SIZE: u64 = 1_024;
 
+/// Default max number of constraints accepted per slot by the Constraints API server, which
+/// is the limit of bolt-boost.
+pub const DEFAULT_RELAY_MAX_CONSTRAINTS_PER_SLOT: usize = 128;
+
 /// Limits for the sidecar.
 #[cfg_attr(test, derive(PartialEq))]
 #[derive(Debug, Parser, Clone, Copy, serde::Serialize, serde::Deserialize)]
@@ -39,6 +44,33 @@ pub struct LimitsOpts {
         default_value_t = LimitsOpts::default().min_priority_fee
     )]
     pub min_priority_fee: u128,
+    /// Max total value in wei of the commitments to accept per slot, i.e. the priority fees
+    /// the committed transactions pay at the current basefee. Unlimited if not set.
+    #[clap(long, env = "BOLT_SIDECAR_MAX_COMMITMENT_VALUE")]
+    pub max_commitment_value: Option<U256>,
+    /// Max number of pending commitments per account across all slots, i.e. accepted
+    /// requests signed by the same account that haven't landed yet. A bundle counts as a
+    /// single commitment. Unlimited if not set.
+    #[clap(long, env = "BOLT_SIDECAR_MAX_PENDING_COMMITMENTS_PER_ACCOUNT")]
+    pub max_pending_commitments_per_account: Option<NonZero<usize>>,
+    /// Evict the lowest-priority commitments of a slot when it is full to make room for a
+    /// request with a higher priority, i.e. a higher effective tip per gas. The constraints
+    /// of the evicted commitments are dropped before being submitted.
+    #[clap(long, env = "BOLT_SIDECAR_EVICT_LOWER_PRIORITY", default_value_t = false)]
+    pub evict_lower_priority: bool,
+    /// Accept commitment requests whose sender has code, i.e. is a smart contract. By default
+    /// only EOAs, including the ones delegated with EIP-7702, can request commitments.
+    #[clap(long, env = "BOLT_SIDECAR_ALLOW_CONTRACT_SENDERS", default_value_t = false)]
+    pub allow_contract_senders: bool,
+    /// Reject commitment requests with contract-creation transactions, i.e. transactions
+    /// without a recipient.
+    #[clap(long, env = "BOLT_SIDECAR_REJECT_CONTRACT_CREATION", default_value_t = false)]
+    pub reject_contract_creation: bool,
+    /// Simulate bundles of multiple transactions in order on top of the pending state of their
+    /// slot with revm, rejecting them if any transaction reverts. The state they touch is read
+    /// from the execution client.
+    #[clap(long, env = "BOLT_SIDECAR_SIMULATE_BUNDLES", default_value_t = false)]
+    pub simulate_bundles: bool,
     /// The maximum size in MiB of the [crate::state::ExecutionState] ScoreCache that holds account
     /// states. Each [crate::primitives::AccountState] is 48 bytes, its score is [usize] bytes, and
     /// its key is 20 bytes, so the default value of 1024 KiB = 1 MiB can hold around 15k account
@@ -51,6 +83,47 @@ pub struct LimitsOpts {
     pub max_account_states_size: NonZero<usize>,
 }
 
+impl LimitsOpts {
+    /// Returns these limits further restricted by the ones of the Constraints API server, so
+    /// that no commitment is issued for constraints that the server would drop.
+    pub fn with_relay_limits(mut self, relay: RelayLimits) -> Self {
+        self.max_commitments_per_slot =
+            self.max_commitments_per_slot.min(relay.relay_max_constraints_per_slot);
+        if let Some(max) = relay.relay_max_committed_gas_per_slot {
+            self.max_committed_gas_per_slot = self.max_committed_gas_per_slot.min(max);
+        }
+        self
+    }
+}
+
+/// Limits of the Constraints API server. Constraints posted beyond them are dropped by the
+/// server, so commitments exceeding them must not be issued. They aren't advertised by the
+/// servers, so they must match the ones of the server in use.
+#[cfg_attr(test, derive(PartialEq))]
+#[derive(Debug, Parser, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct RelayLimits {
+    /// Max number of constraints accepted per slot by the Constraints API server
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_RELAY_MAX_CONSTRAINTS_PER_SLOT",
+        default_value_t = RelayLimits::default().relay_max_constraints_per_slot
+    )]
+    pub relay_max_constraints_per_slot: NonZero<usize>,
+    /// Max committed gas accepted per slot by the Constraints API server. Unlimited if not set.
+    #[clap(long, env = "BOLT_SIDECAR_RELAY_MAX_COMMITTED_GAS_PER_SLOT")]
+    pub relay_max_committed_gas_per_slot: Option<NonZero<u64>>,
+}
+
+impl Default for RelayLimits {
+    fn default() -> Self {
+        Self {
+            relay_max_constraints_per_slot: NonZero::new(DEFAULT_RELAY_MAX_CONSTRAINTS_PER_SLOT)
+                .expect("Valid non-zero"),
+            relay_max_committed_gas_per_slot: None,
+        }
+    }
+}
+
 impl Default for LimitsOpts {
     fn default() -> Self {
         Self {
@@ -59,6 +132,12 @@ impl Default for LimitsOpts {
             max_committed_gas_per_slot: NonZero::new(DEFAULT_MAX_COMMITTED_GAS)
                 .expect("Valid non-zero"),
             min_priority_fee: DEFAULT_MIN_PRIORITY_FEE,
+            max_commitment_value: None,
+            max_pending_commitments_per_account: None,
+            evict_lower_priority: false,
+            allow_contract_senders: false,
+            reject_contract_creation: false,
+            simulate_bundles: false,
             max_account_states_size: NonZero::new(1_024).expect("Valid non-zero"),
         }
     }
@@ -1,9 +1,13 @@
-use std::env;
+use std::{env, num::NonZero, path::PathBuf};
 
-use alloy::primitives::Address;
+use alloy::{hex, primitives::Address};
 use clap::Parser;
+use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
 use eyre::bail;
-use reqwest::Url;
+use reqwest::{
+    header::{HeaderName, HeaderValue},
+    Url,
+};
 use serde::Deserialize;
 
 /// Chain configuration options.
@@ -20,16 +24,36 @@ use telemetry::TelemetryOpts;
 
 /// Operating limits for commitments and constraints.
 pub mod limits;
-use limits::LimitsOpts;
+use limits::{LimitsOpts, RelayLimits};
 use tracing::debug;
 
-use crate::common::secrets::{BlsSecretKeyWrapper, EcdsaSecretKeyWrapper, JwtSecretConfig};
+use crate::{
+    builder::{
+        payload_builder::{DEFAULT_MAX_HINT_ITERATIONS, MAX_HINT_ITERATIONS_CAP},
+        ConflictPolicy, OrderingPolicy,
+    },
+    common::{
+        circuit_breaker::DEFAULT_FAILURE_THRESHOLD,
+        secrets::{BlsSecretKeyWrapper, EcdsaSecretKeyWrapper, JwtSecretConfig},
+    },
+    crypto::bls::BlsDst,
+    primitives::{CommitmentKind, MessageEncoding},
+};
 
 /// Default port for the JSON-RPC server exposed by the sidecar supporting the Commitments API.
 ///
 /// 8017 -> BOLT :)
 pub const DEFAULT_RPC_PORT: u16 = 8017;
 
+/// Default maximum number of commitment requests processed concurrently.
+pub const DEFAULT_MAX_INFLIGHT_COMMITMENTS: usize = 64;
+
+/// Default time in milliseconds an inclusion reservation is held for before its body is submitted.
+pub const DEFAULT_RESERVATION_TTL_MS: u64 = 2_000;
+
+/// Default time in milliseconds to wait for the relay bid of a `getHeader` request.
+pub const DEFAULT_RELAY_GET_HEADER_TIMEOUT_MS: u64 = 500;
+
 /// Default port for the Constraints proxy server, binded to the default port used by MEV-Boost.
 pub const DEFAULT_CONSTRAINTS_PROXY_PORT: u16 = 18550;
 
@@ -40,12 +64,50 @@ pub struct Opts {
     /// This port should be open on your firewall in order to receive external requests!
     #[clap(long, env = "BOLT_SIDECAR_PORT", default_value_t = DEFAULT_RPC_PORT)]
     pub port: u16,
+    /// Path of an IPC socket on which the Commitments API is also served, for co-located
+    /// clients. If not set, the Commitments API is only served over HTTP.
+    #[clap(long, env = "BOLT_SIDECAR_IPC_PATH")]
+    pub ipc_path: Option<PathBuf>,
+    /// Bearer token authenticating the admin methods of the Commitments API, such as
+    /// `bolt_admin_rotateSigner`. If not set, the admin methods are disabled.
+    #[clap(long, env = "BOLT_SIDECAR_ADMIN_TOKEN")]
+    pub admin_token: Option<String>,
+    /// Maximum number of commitment requests processed concurrently. Requests received
+    /// while this many are in-flight are rejected as busy.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_MAX_INFLIGHT_COMMITMENTS",
+        default_value_t = NonZero::new(DEFAULT_MAX_INFLIGHT_COMMITMENTS).expect("Valid non-zero")
+    )]
+    pub max_inflight_commitments: NonZero<usize>,
+    /// Time in milliseconds an inclusion reservation made by transaction hash is held for.
+    /// If the transaction body isn't submitted in time, the reservation is dropped.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_RESERVATION_TTL_MS",
+        default_value_t = DEFAULT_RESERVATION_TTL_MS
+    )]
+    pub reservation_ttl_ms: u64,
+    /// Comma-separated list of the commitment kinds accepted by the sidecar. Requests for
+    /// other kinds are rejected as unsupported.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_ALLOWED_COMMITMENT_KINDS",
+        value_delimiter = ',',
+        default_value = "inclusion,top-of-block"
+    )]
+    pub allowed_commitment_kinds: Vec<CommitmentKind>,
     /// Execution client API URL
     #[clap(long, env = "BOLT_SIDECAR_EXECUTION_API_URL", default_value = "http://localhost:8545")]
     pub execution_api_url: Url,
     /// URL for the beacon client
     #[clap(long, env = "BOLT_SIDECAR_BEACON_API_URL", default_value = "http://localhost:5052")]
     pub beacon_api_url: Url,
+    /// Refresh the state on the head events of the beacon client instead of the wall clock,
+    /// e.g. probe the health of the engine API once it received a new block. The slot and the
+    /// commitment deadlines always follow the wall clock, so slots without a block still fire.
+    #[clap(long, env = "BOLT_SIDECAR_HEAD_FOLLOW", default_value_t = false)]
+    pub head_follow: bool,
     /// Execution client Engine API URL. This is needed for fallback block building and must be a
     /// synced Geth node.
     #[clap(long, env = "BOLT_SIDECAR_ENGINE_API_URL", default_value = "http://localhost:8551")]
@@ -58,6 +120,22 @@ pub struct Opts {
         default_value = "http://localhost:18551"
     )]
     pub constraints_api_url: Url,
+    /// Submit constraints to the Constraints API SSZ-encoded instead of JSON-encoded. If the
+    /// server doesn't support it, the sidecar falls back to JSON.
+    #[clap(long, env = "BOLT_SIDECAR_CONSTRAINTS_API_SSZ", default_value_t = false)]
+    pub constraints_api_ssz: bool,
+    /// The encoding of the delegations and revocations posted to the Constraints API, as
+    /// expected by the server: `json` or `ssz`.
+    #[clap(long, env = "BOLT_SIDECAR_DELEGATIONS_ENCODING", default_value = "json")]
+    pub delegations_encoding: MessageEncoding,
+    /// Reject a plain HTTP Constraints API URL, so that the sidecar only talks to the server
+    /// over TLS.
+    #[clap(long, env = "BOLT_SIDECAR_CONSTRAINTS_API_REQUIRE_TLS", default_value_t = false)]
+    pub constraints_api_require_tls: bool,
+    /// Path to the PEM certificate of the Constraints API server. If set, the server must
+    /// present exactly this certificate, whichever CA issued it, and TLS is required.
+    #[clap(long, env = "BOLT_SIDECAR_CONSTRAINTS_API_PINNED_CERT_PATH")]
+    pub constraints_api_pinned_cert_path: Option<PathBuf>,
     /// The port from which the Bolt sidecar will receive Builder-API requests from the
     /// Beacon client
     #[clap(
@@ -66,15 +144,146 @@ pub struct Opts {
         default_value_t = DEFAULT_CONSTRAINTS_PROXY_PORT
     )]
     pub constraints_proxy_port: u16,
+    /// The minimum value in wei of the bids returned to the beacon client. Relay bids below it
+    /// are ignored, and a locally built payload is returned instead if there is one.
+    #[clap(long, env = "BOLT_SIDECAR_MIN_BID_WEI", default_value_t = 0)]
+    pub min_bid_wei: u128,
+    /// Comma-separated list of the public keys of the relays whose bids can be returned to the
+    /// beacon client. If this list is set, bids from relays not in it or with an invalid
+    /// signature are ignored. Otherwise, the bid signatures are not verified.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_RELAY_PUBKEYS",
+        value_delimiter = ',',
+        value_parser = parse_bls_public_key
+    )]
+    pub relay_pubkeys: Vec<BlsPublicKey>,
+    /// Time in milliseconds to wait for the relay bid of a `getHeader` request. Past it, the
+    /// locally built bid is returned if there is one, so that the proposer gets a header in time.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_RELAY_GET_HEADER_TIMEOUT_MS",
+        default_value_t = DEFAULT_RELAY_GET_HEADER_TIMEOUT_MS
+    )]
+    pub relay_get_header_timeout_ms: u64,
+    /// Path to the file where the signed constraints are persisted, so that they can be
+    /// re-posted to the relays after a restart. If not set, constraints are not persisted.
+    #[clap(long, env = "BOLT_SIDECAR_CONSTRAINTS_STORE_PATH")]
+    pub constraints_store_path: Option<PathBuf>,
+    /// Path to the file where the locally built payload is persisted until it is fetched, so
+    /// that it can still be served after a restart within its slot. If not set, the payload is
+    /// only kept in memory.
+    #[clap(long, env = "BOLT_SIDECAR_PAYLOAD_STORE_PATH")]
+    pub payload_store_path: Option<PathBuf>,
+    /// Path to the file where the relay responses to the constraints submissions are
+    /// persisted, so that `bolt_getCommitmentStatus` can still serve them after a restart. If
+    /// not set, they are only kept in memory.
+    #[clap(long, env = "BOLT_SIDECAR_COMMITMENT_STATUS_PATH")]
+    pub commitment_status_path: Option<PathBuf>,
+    /// Re-post the persisted constraints for slots that haven't passed yet on startup, and
+    /// restore them in the block templates of their slots.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_REPLAY",
+        default_value_t = false,
+        requires = "constraints_store_path"
+    )]
+    pub replay: bool,
+    /// Broadcast the committed transactions that weren't included in their target slot to the
+    /// public mempool through the execution client, once the slot has passed.
+    #[clap(long, env = "BOLT_SIDECAR_POST_SLOT_BROADCAST", default_value_t = false)]
+    pub post_slot_broadcast: bool,
+    /// Broadcast the committed transactions to the public mempool through the execution client
+    /// if the relay never accepted their constraints, as a last resort. The commitments are
+    /// then reported as degraded in their status.
+    #[clap(long, env = "BOLT_SIDECAR_RELAY_FAILURE_MEMPOOL_FALLBACK", default_value_t = false)]
+    pub relay_failure_mempool_fallback: bool,
+    /// Fetch the constraints the relay has for a slot once they were submitted, and warn about
+    /// any drift from the local ones. The relay must support the `get_constraints` endpoint.
+    #[clap(long, env = "BOLT_SIDECAR_RECONCILE_CONSTRAINTS", default_value_t = false)]
+    pub reconcile_constraints: bool,
+    /// Also submit the locally built blocks of our proposer slots to the block-submission
+    /// endpoint of the relay, for relays that don't implement the constraints API.
+    #[clap(long, env = "BOLT_SIDECAR_RELAY_SUBMIT_LOCAL_BLOCKS", default_value_t = false)]
+    pub relay_submit_local_blocks: bool,
+    /// Record the fee accrued by the sender of each honored commitment, priced as
+    /// `commitment_base_fee + gas_limit * commitment_fee_per_gas` for each transaction.
+    /// The fees are logged, for later billing or settlement.
+    #[clap(long, env = "BOLT_SIDECAR_COMMITMENT_FEE_ACCOUNTING", default_value_t = false)]
+    pub commitment_fee_accounting: bool,
+    /// The flat fee in wei accrued for each committed transaction.
+    #[clap(long, env = "BOLT_SIDECAR_COMMITMENT_BASE_FEE", default_value_t = 0)]
+    pub commitment_base_fee: u128,
+    /// The fee in wei accrued per unit of gas limit of each committed transaction.
+    #[clap(long, env = "BOLT_SIDECAR_COMMITMENT_FEE_PER_GAS", default_value_t = 0)]
+    pub commitment_fee_per_gas: u128,
+    /// Allow the webhooks of commitment requests to point to private addresses, e.g. loopback
+    /// or LAN ones. They are rejected by default, as the webhook URLs are user-provided.
+    #[clap(long, env = "BOLT_SIDECAR_WEBHOOKS_ALLOW_PRIVATE_IPS", default_value_t = false)]
+    pub webhooks_allow_private_ips: bool,
+    /// How to resolve constraints with the same sender and nonce as already committed ones:
+    /// `reject-later` keeps the first commitment, `prefer-higher-fee` replaces it if the new
+    /// one pays a higher priority fee.
+    #[clap(long, env = "BOLT_SIDECAR_CONSTRAINTS_CONFLICT_POLICY", default_value = "reject-later")]
+    pub constraints_conflict_policy: ConflictPolicy,
     /// The JWT secret token to authenticate calls to the engine API.
     ///
     /// It can either be a hex-encoded string or a file path to a file
     /// containing the hex-encoded secret.
     #[clap(long, env = "BOLT_SIDECAR_ENGINE_JWT_HEX")]
     pub engine_jwt_hex: JwtSecretConfig,
+    /// Comma-separated list of extra HTTP headers sent with every engine API request,
+    /// formatted as `Name: value`. They take precedence over the default `User-Agent`.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_ENGINE_HEADERS",
+        value_delimiter = ',',
+        value_parser = parse_header
+    )]
+    #[serde(skip)]
+    pub engine_headers: Vec<(HeaderName, HeaderValue)>,
     /// The fee recipient address for fallback blocks
     #[clap(long, env = "BOLT_SIDECAR_FEE_RECIPIENT")]
     pub fee_recipient: Address,
+    /// The address of a registry contract mapping validators to their registered fee recipient.
+    /// If set, the sidecar checks on startup that the fee recipient registered for each of its
+    /// validators is the configured one, and fails to start otherwise.
+    #[clap(long, env = "BOLT_SIDECAR_FEE_RECIPIENT_REGISTRY")]
+    pub fee_recipient_registry: Option<Address>,
+    /// The address of a registry contract to resolve the fee recipient of the fallback block of
+    /// each slot from, given the public key of its proposer. The static `fee_recipient` is used
+    /// if the proposer is unknown or the lookup fails.
+    #[clap(long, env = "BOLT_SIDECAR_FEE_RECIPIENT_RESOLVER")]
+    pub fee_recipient_resolver: Option<Address>,
+    /// The ordering policy of the transactions in fallback blocks. Top-of-block constraints are
+    /// always included first, regardless of the policy.
+    #[clap(long, env = "BOLT_SIDECAR_BUILDER_ORDERING", default_value = "priority-fee")]
+    pub builder_ordering: OrderingPolicy,
+    /// Top up fallback blocks with filler transactions from the mempool of the execution client,
+    /// after the committed transactions. Filler transactions only use the gas left by the
+    /// committed ones.
+    #[clap(long, env = "BOLT_SIDECAR_BUILDER_FILLER_TXS", default_value_t = false)]
+    pub builder_filler_txs: bool,
+    /// The maximum number of engine API calls made to resolve the header values of a fallback
+    /// block from the engine hints, before giving up on the block. It must be between 1 and 100,
+    /// so that building a fallback block always takes a bounded time.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_BUILDER_MAX_HINT_ITERATIONS",
+        default_value_t = DEFAULT_MAX_HINT_ITERATIONS,
+        value_parser = parse_hint_iterations
+    )]
+    pub builder_max_hint_iterations: usize,
+    /// Build a fallback block for the slots of our validators even if no commitments were
+    /// issued, so that a local bid is always available. The block is empty unless filler
+    /// transactions are enabled.
+    #[clap(long, env = "BOLT_SIDECAR_BUILDER_EMPTY_BLOCKS", default_value_t = false)]
+    pub builder_empty_blocks: bool,
+    /// Rebuild the fallback block on top of the new head if the head changed since it was
+    /// built, e.g. after a small reorg. The rebuild runs in the background on the head event,
+    /// and the stale block is served until it completes.
+    #[clap(long, env = "BOLT_SIDECAR_BUILDER_REORG_REBUILD", default_value_t = false)]
+    pub builder_reorg_rebuild: bool,
     /// Secret BLS key to sign fallback payloads with
     #[clap(long, env = "BOLT_SIDECAR_BUILDER_PRIVATE_KEY")]
     pub builder_private_key: BlsSecretKeyWrapper,
@@ -88,12 +297,31 @@ pub struct Opts {
     /// available without checking if connected validators are scheduled to propose a block.
     #[clap(long, env = "BOLT_SIDECAR_UNSAFE_DISABLE_CONSENSUS_CHECKS", default_value_t = false)]
     pub unsafe_disable_consensus_checks: bool,
+    /// Only accept commitments for the slots proposed by the validators controlled by this
+    /// sidecar, directly or through a delegation. Other slots are rejected upfront.
+    #[clap(long, env = "BOLT_SIDECAR_STRICT_PROPOSER", default_value_t = false)]
+    pub strict_proposer: bool,
     /// Unsafely disables on-chain checks of validators and operator when starting the sidecar
     #[clap(long, env = "BOLT_SIDECAR_UNSAFE_DISABLE_ONCHAIN_CHECKS", default_value_t = false)]
     pub unsafe_disable_onchain_checks: bool,
+    /// Number of consecutive engine API failures after which the sidecar stops accepting new
+    /// commitments, until the engine is reachable again.
+    #[clap(
+        long,
+        env = "BOLT_SIDECAR_ENGINE_FAILURE_THRESHOLD",
+        default_value_t = NonZero::new(DEFAULT_FAILURE_THRESHOLD).expect("Valid non-zero")
+    )]
+    pub engine_failure_threshold: NonZero<usize>,
+    /// Fail startup if the engine API is unreachable or doesn't support the methods used to
+    /// build fallback blocks. Otherwise, a failed check is only logged.
+    #[clap(long, env = "BOLT_SIDECAR_REQUIRE_ENGINE_CAPABILITIES", default_value_t = false)]
+    pub require_engine_capabilities: bool,
     /// Operating limits for the sidecar
     #[clap(flatten)]
     pub limits: LimitsOpts,
+    /// Limits of the Constraints API server, further restricting the operating limits
+    #[clap(flatten)]
+    pub relay_limits: RelayLimits,
     /// Chain config for the chain on which the sidecar is running
     #[clap(flatten)]
     pub chain: ChainConfig,
@@ -118,8 +346,47 @@ impl Opts {
     pub fn try_parse() -> eyre::Result<Self> {
         read_env_file()?;
 
-        Ok(Self::parse())
+        let opts = Self::parse();
+        opts.validate()?;
+        Ok(opts)
+    }
+
+    /// Check the combinations of options that can't be expressed as argument constraints.
+    pub fn validate(&self) -> eyre::Result<()> {
+        // Commit-boost signers always sign with the proof-of-possession tag, so their
+        // signatures wouldn't verify against a custom one
+        let signing = &self.constraint_signing;
+        let remote_signer = signing.commit_boost_signer_url.is_some() ||
+            !signing.constraint_cosigner_urls.is_empty();
+        if remote_signer && self.chain.bls_dst != BlsDst::POP {
+            bail!("A custom BLS DST can't be used with commit-boost signers, set the default one");
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a hex-encoded BLS public key.
+fn parse_bls_public_key(value: &str) -> eyre::Result<BlsPublicKey> {
+    let bytes = hex::decode(value.trim())?;
+    Ok(BlsPublicKey::try_from(bytes.as_slice())?)
+}
+
+/// Parses an HTTP header formatted as `Name: value`.
+fn parse_header(value: &str) -> eyre::Result<(HeaderName, HeaderValue)> {
+    let Some((name, value)) = value.split_once(':') else {
+        bail!("Expected a header formatted as 'Name: value', got '{value}'");
+    };
+    Ok((HeaderName::try_from(name.trim())?, HeaderValue::try_from(value.trim())?))
+}
+
+/// Parses a maximum number of engine hint iterations, rejecting the ones out of range.
+fn parse_hint_iterations(value: &str) -> eyre::Result<usize> {
+    let iterations = value.trim().parse::<usize>()?;
+    if !(1..=MAX_HINT_ITERATIONS_CAP).contains(&iterations) {
+        bail!("Expected between 1 and {MAX_HINT_ITERATIONS_CAP} iterations, got {iterations}");
     }
+    Ok(iterations)
 }
 
 /// Reads the `.env` file and loads the environment variables into the process.
@@ -171,6 +438,34 @@ mod tests {
         Opts::command().debug_assert();
     }
 
+    #[test]
+    fn test_custom_bls_dst_rejected_with_commit_boost_signer() {
+        let args = |extra: &[&str]| {
+            let defaults = [
+                "bolt-sidecar".to_string(),
+                format!("--engine-jwt-hex={}", JwtSecretConfig::default()),
+                format!("--fee-recipient={}", Address::ZERO),
+                format!("--builder-private-key={}", BlsSecretKeyWrapper::random()),
+                format!("--commitment-private-key={}", EcdsaSecretKeyWrapper::random()),
+            ];
+            defaults.into_iter().chain(extra.iter().map(|arg| arg.to_string())).collect::<Vec<_>>()
+        };
+        let commit_boost =
+            ["--commit-boost-signer-url=http://127.0.0.1:18550", "--commit-boost-jwt-hex=00"];
+
+        let opts = Opts::try_parse_from(args(&commit_boost)).unwrap();
+        assert!(opts.validate().is_ok());
+
+        let custom_dst = [&commit_boost[..], &["--bls-dst=BOLT_TEST_DST_"]].concat();
+        let opts = Opts::try_parse_from(args(&custom_dst)).unwrap();
+        assert!(opts.validate().is_err());
+
+        // Local signers sign with any tag
+        let local = format!("--constraint-private-key={}", BlsSecretKeyWrapper::random());
+        let opts = Opts::try_parse_from(args(&[&local, "--bls-dst=BOLT_TEST_DST_"])).unwrap();
+        assert!(opts.validate().is_ok());
+    }
+
     #[test]
     fn test_parse_url() {
         let url = "http://0.0.0.0:3030";
@@ -1,5 +1,6 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use serde::Deserialize;
+use tracing::Level;
 
 /// Telemetry and metrics related options.
 #[derive(Parser, Debug, Clone, Deserialize)]
@@ -9,6 +10,56 @@ pub struct TelemetryOpts {
     metrics_port: u16,
     #[clap(long, env = "BOLT_SIDECAR_DISABLE_METRICS", default_value_t = false)]
     disable_metrics: bool,
+    /// Only log the slot, signer and transaction hashes of incoming commitment requests at
+    /// info level. The full requests, including the signed transactions, are logged at debug.
+    #[clap(long, env = "BOLT_SIDECAR_REDACT_LOGS", default_value_t = false)]
+    redact_logs: bool,
+    /// The format of the logs
+    #[clap(long, env = "BOLT_SIDECAR_LOG_FORMAT", default_value = "pretty")]
+    #[serde(default)]
+    log_format: LogFormat,
+    /// The level of the sidecar logs. The `RUST_LOG` directives take precedence over it.
+    #[clap(long, env = "BOLT_SIDECAR_LOG_LEVEL", default_value = "info")]
+    #[serde(default)]
+    log_level: LogLevel,
+}
+
+/// The format of the logs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[clap(rename_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum LogFormat {
+    /// Human-readable lines, for local debugging.
+    #[default]
+    Pretty,
+    /// One JSON object per line, for log aggregators.
+    Json,
+}
+
+/// The level of the logs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[clap(rename_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+#[allow(missing_docs)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
+impl From<LogLevel> for Level {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Trace => Self::TRACE,
+            LogLevel::Debug => Self::DEBUG,
+            LogLevel::Info => Self::INFO,
+            LogLevel::Warn => Self::WARN,
+            LogLevel::Error => Self::ERROR,
+        }
+    }
 }
 
 impl TelemetryOpts {
@@ -20,4 +71,19 @@ impl TelemetryOpts {
             Some(self.metrics_port)
         }
     }
+
+    /// Whether sensitive fields of incoming requests should be redacted from info logs.
+    pub fn redact_logs(&self) -> bool {
+        self.redact_logs
+    }
+
+    /// Get the format of the logs.
+    pub fn log_format(&self) -> LogFormat {
+        self.log_format
+    }
+
+    /// Get the level of the sidecar logs.
+    pub fn log_level(&self) -> Level {
+        self.log_level.into()
+    }
 }
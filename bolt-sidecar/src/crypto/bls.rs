@@ -1,5 +1,9 @@
+use std::{fmt, str::FromStr};
+
 use alloy::{primitives::FixedBytes, rpc::types::beacon::constants::BLS_PUBLIC_KEY_BYTES_LEN};
-use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
+use blst::BLST_ERROR;
+use ethereum_consensus::{crypto::PublicKey as BlsPublicKey, deneb::compute_signing_root};
+use serde::{de, Deserialize, Deserializer};
 
 pub use blst::min_pk::{PublicKey, SecretKey as BlsSecretKey};
 pub use ethereum_consensus::deneb::BlsSignature;
@@ -7,6 +11,78 @@ pub use ethereum_consensus::deneb::BlsSignature;
 /// The BLS Domain Separator used in Ethereum 2.0.
 pub const BLS_DST_PREFIX: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
 
+/// The maximum length of a BLS domain separation tag, as per the hash-to-curve spec.
+pub const MAX_BLS_DST_LEN: usize = 255;
+
+/// A BLS domain separation tag (DST), binding signatures to a signature scheme. Defaults to
+/// [BLS_DST_PREFIX], the proof-of-possession scheme used by Ethereum and commit-boost.
+///
+/// The tag is a static reference, so that the `Copy` chain configuration holding it stays
+/// small. Custom tags are parsed once from the configuration and leaked for the lifetime of
+/// the process.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct BlsDst(&'static [u8]);
+
+impl BlsDst {
+    /// The DST of the proof-of-possession scheme.
+    pub const POP: Self = Self(BLS_DST_PREFIX);
+
+    /// The DST of the basic scheme, without proof of possession.
+    pub const NUL: Self = Self(b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_");
+
+    /// Returns the bytes of the tag.
+    pub const fn as_bytes(&self) -> &'static [u8] {
+        self.0
+    }
+}
+
+impl Default for BlsDst {
+    fn default() -> Self {
+        Self::POP
+    }
+}
+
+impl fmt::Display for BlsDst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&String::from_utf8_lossy(self.as_bytes()))
+    }
+}
+
+impl fmt::Debug for BlsDst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BlsDst({self})")
+    }
+}
+
+/// Error returned when parsing a [BlsDst] of an invalid length.
+#[derive(Debug, thiserror::Error)]
+#[error("BLS DST must be between 1 and {MAX_BLS_DST_LEN} bytes long, got {0}")]
+pub struct InvalidBlsDst(usize);
+
+impl FromStr for BlsDst {
+    type Err = InvalidBlsDst;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || s.len() > MAX_BLS_DST_LEN {
+            return Err(InvalidBlsDst(s.len()));
+        }
+
+        // The well-known tags are reused rather than leaked again
+        match s.as_bytes() {
+            dst if dst == Self::POP.0 => Ok(Self::POP),
+            dst if dst == Self::NUL.0 => Ok(Self::NUL),
+            dst => Ok(Self(Box::leak(dst.to_vec().into_boxed_slice()))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BlsDst {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
 /// A fixed-size byte array for BLS signatures.
 pub type BLSSig = FixedBytes<96>;
 
@@ -17,6 +93,50 @@ pub trait SignableBLS {
     fn digest(&self) -> [u8; 32];
 }
 
+/// Error verifying a BLS signature.
+#[derive(Debug, thiserror::Error)]
+pub enum BlsVerificationError {
+    /// The public key is not a valid BLS public key.
+    #[error("invalid BLS public key: {0:?}")]
+    InvalidPubkey(BLST_ERROR),
+    /// The signature is malformed, or not a signature of the public key over the message.
+    #[error("invalid BLS signature: {0:?}")]
+    InvalidSignature(BLST_ERROR),
+}
+
+/// Verify the BLS signature of `pubkey` over the signing root of the object `root` in the
+/// given `domain`, with the domain separation tag `dst`.
+pub fn verify_root(
+    root: [u8; 32],
+    domain: [u8; 32],
+    signature: &[u8],
+    pubkey: &BlsPublicKey,
+    dst: &[u8],
+) -> Result<(), BlsVerificationError> {
+    let signing_root =
+        compute_signing_root(&root, domain).expect("signing data of fixed size can be merkleized");
+    verify_signing_root(signing_root.as_ref(), signature, pubkey, dst)
+}
+
+/// Verify the BLS signature of `pubkey` over the given signing root, with the domain
+/// separation tag `dst`.
+pub fn verify_signing_root(
+    signing_root: &[u8],
+    signature: &[u8],
+    pubkey: &BlsPublicKey,
+    dst: &[u8],
+) -> Result<(), BlsVerificationError> {
+    let pubkey =
+        PublicKey::from_bytes(pubkey.as_ref()).map_err(BlsVerificationError::InvalidPubkey)?;
+    let signature = blst::min_pk::Signature::from_bytes(signature)
+        .map_err(BlsVerificationError::InvalidSignature)?;
+
+    match signature.verify(true, signing_root, dst, &[], &pubkey, true) {
+        BLST_ERROR::BLST_SUCCESS => Ok(()),
+        err => Err(BlsVerificationError::InvalidSignature(err)),
+    }
+}
+
 /// Convert a BLS public key from Consensus Types to a byte array.
 pub fn cl_public_key_to_arr(pubkey: impl AsRef<BlsPublicKey>) -> [u8; BLS_PUBLIC_KEY_BYTES_LEN] {
     pubkey.as_ref().as_ref().try_into().expect("BLS keys are 48 bytes")
@@ -1,38 +1,59 @@
-use std::{fmt, sync::Arc, time::Instant};
+use std::{
+    collections::HashSet,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
-use alloy::{rpc::types::beacon::events::HeadEvent, signers::local::PrivateKeySigner};
-use beacon_api_client::mainnet::Client as BeaconClient;
-use ethereum_consensus::{
-    clock::{self, SlotStream, SystemTimeProvider},
-    phase0::mainnet::SLOTS_PER_EPOCH,
+use alloy::{
+    eips::eip2718::Encodable2718,
+    primitives::{Address, U256},
+    rpc::types::beacon::events::HeadEvent,
+    signers::local::PrivateKeySigner,
 };
-use eyre::Context;
+use beacon_api_client::mainnet::Client as BeaconClient;
+use ethereum_consensus::clock::{self, SlotStream, SystemTimeProvider};
+use eyre::{bail, Context};
 use futures::StreamExt;
-use tokio::sync::mpsc;
+use tokio::{
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+};
 use tracing::{debug, error, info, warn};
 
 use crate::{
     api::{
         builder::{start_builder_proxy_server, BuilderProxyConfig},
         commitments::{
-            server::{CommitmentEvent, CommitmentsApiServer},
-            spec::CommitmentError,
+            server::{CommitmentEvent, CommitmentsApiServer, DrainSlotRequest, SlotInfoRequest},
+            spec::{CommitmentError, RejectionError},
         },
         spec::ConstraintsApi,
     },
-    builder::payload_fetcher::LocalPayloadFetcher,
-    chain_io::BoltManager,
-    client::ConstraintsClient,
-    common::backoff::retry_with_backoff,
-    config::Opts,
+    builder::{payload_fetcher::LocalPayloadFetcher, BlockTemplate, MempoolFillerPool},
+    chain_io::{BoltManager, FeeRecipientRegistry},
+    client::{ConstraintsClient, PinnedCertificate, RelayTls, RpcClient, WebhookNotifier},
+    common::{
+        backoff::{retry_with_policy, BackoffPolicy},
+        circuit_breaker::CircuitBreaker,
+    },
+    config::{chain::ATTESTATION_DEADLINE_IN_MILLIS, ChainConfig, Opts},
     crypto::{SignableBLS, SignerECDSA},
     primitives::{
-        commitment::SignedCommitment, read_signed_delegations_from_file, CommitmentRequest,
-        ConstraintsMessage, FetchPayloadRequest, SignedConstraints, TransactionExt,
+        commitment::SignedCommitment, read_signed_delegations_from_file, BlsPublicKey,
+        CommitmentRequest, ConstraintsMessage, FetchPayloadRequest, PayloadAndBid, SessionDomain,
+        SignedConstraints, TransactionExt,
+    },
+    signer::{
+        aggregate::CoSigner, keystore::KeystoreSigner, local::LocalSigner, AggregateSigner,
+        CommitBoostSigner, SignerBLS, SignerRotationError, SignerRotationRequest,
     },
-    signer::{keystore::KeystoreSigner, local::LocalSigner, CommitBoostSigner, SignerBLS},
-    state::{fetcher::StateFetcher, ConsensusState, ExecutionState, HeadTracker, StateClient},
-    telemetry::ApiMetrics,
+    state::{
+        consensus::ConsensusError, fetcher::StateFetcher, replay_constraints, CommitmentStatuses,
+        ConsensusState, ConstraintsStore, ConstraintsStoreHandle, ExecutionState, FeeAccounting,
+        GasPricing, HeadTracker, RelaySubmission, ReservationBook, StateClient, SubmissionTiming,
+    },
+    telemetry::{log_commitment_request, ApiMetrics, CommitmentStage},
     LocalBuilder,
 };
 
@@ -64,10 +85,55 @@ pub struct SidecarDriver<C, ECDSA> {
     api_events_rx: mpsc::Receiver<CommitmentEvent>,
     /// Channel for receiving requests to fetch a local payload
     payload_requests_rx: mpsc::Receiver<FetchPayloadRequest>,
+    /// Channel for receiving requests of slot information
+    slot_info_requests_rx: mpsc::Receiver<SlotInfoRequest>,
+    /// Channel for receiving requests to rotate the constraint signer
+    signer_rotation_requests_rx: mpsc::Receiver<SignerRotationRequest>,
+    /// Channel for sending the rotated signers once loaded and verified off the event loop
+    rotated_signers_tx: mpsc::Sender<RotatedSigner>,
+    /// Channel for receiving the rotated signers to swap the constraint signer for
+    rotated_signers_rx: mpsc::Receiver<RotatedSigner>,
+    /// Channel for sending the local payloads rebuilt on a new head off the event loop
+    rebuilt_payloads_tx: mpsc::Sender<(u64, PayloadAndBid)>,
+    /// Channel for receiving the rebuilt local payloads to replace the stale ones with
+    rebuilt_payloads_rx: mpsc::Receiver<(u64, PayloadAndBid)>,
+    /// The Bolt Manager verifying the keys of rotated signers, if the onchain checks are enabled
+    bolt_manager: Option<BoltManager>,
+    /// Channel for draining the commitments of a slot
+    drain_slot_requests_rx: mpsc::Receiver<DrainSlotRequest>,
+    /// The slots of the constraints re-posted to the relays on startup, which can't be drained
+    replayed_slots: HashSet<u64>,
+    /// The chain configuration, used to load rotated signers
+    chain: ChainConfig,
     /// Stream of slots made from the consensus clock
     slot_stream: SlotStream<SystemTimeProvider>,
+    /// Whether to probe the engine health on new heads instead of the consensus clock
+    head_follow: bool,
     /// Whether to skip consensus checks (should only be used for testing)
     unsafe_skip_consensus_checks: bool,
+    /// Circuit breaker tracking consecutive failures of the engine API
+    engine_breaker: CircuitBreaker,
+    /// The health check of the engine API in progress while the breaker is open, if any
+    engine_probe: Option<JoinHandle<()>>,
+    /// Store persisting the signed constraints across restarts, if enabled
+    constraints_store: Option<ConstraintsStoreHandle>,
+    /// Timing of the constraints submissions, to warn about late ones
+    submission_timing: SubmissionTiming,
+    /// The relay responses to the constraints submissions, served by the commitments API
+    commitment_statuses: CommitmentStatuses,
+    /// Whether to redact sensitive fields of incoming requests from info logs
+    redact_logs: bool,
+    /// Whether to build a local payload for our proposer slots without commitments
+    build_empty_blocks: bool,
+    /// Client broadcasting the committed transactions to the public mempool when the relay
+    /// never accepted their constraints, if enabled
+    mempool_fallback: Option<RpcClient>,
+    /// Whether to compare the constraints of the relay to the local ones once submitted
+    reconcile_constraints: bool,
+    /// Whether to submit the local blocks to the block-submission endpoint of the relay
+    submit_local_blocks: bool,
+    /// Notifier of the webhooks of the accepted commitments
+    webhooks: WebhookNotifier,
 }
 
 impl SidecarDriver<StateClient, PrivateKeySigner> {
@@ -96,6 +162,40 @@ impl SidecarDriver<StateClient, PrivateKeySigner> {
     }
 }
 
+impl SidecarDriver<StateClient, PrivateKeySigner> {
+    /// Create a new sidecar driver with the given [Opts] and a signer aggregating the
+    /// signatures of the local constraint key and of the remote co-signers.
+    pub async fn with_aggregate_signer(opts: &Opts) -> eyre::Result<Self> {
+        // The default state client simply uses the execution API URL to fetch state updates.
+        let state_client = StateClient::new(opts.execution_api_url.clone());
+
+        let signing = &opts.constraint_signing;
+        if signing.constraint_cosigner_urls.len() != signing.constraint_cosigner_jwts.len() {
+            bail!("Each constraint co-signer URL needs exactly one JWT");
+        }
+
+        let local_key = signing.constraint_private_key.clone().expect("local constraint key").0;
+        let mut cosigners = vec![CoSigner::Local(LocalSigner::new(local_key, opts.chain))];
+        for (url, jwt) in
+            signing.constraint_cosigner_urls.iter().zip(&signing.constraint_cosigner_jwts)
+        {
+            cosigners.push(CoSigner::Remote(CommitBoostSigner::new(url.clone(), &jwt.0)?));
+        }
+
+        let aggregate_signer = AggregateSigner::new(cosigners, opts.chain).await?;
+        info!(pubkey = %aggregate_signer.pubkey(), "Signing constraints with an aggregate key");
+        let constraint_signer = SignerBLS::Aggregate(aggregate_signer);
+
+        // Commitment responses are signed with a regular Ethereum wallet private key.
+        let commitment_key = opts.commitment_private_key.0.clone();
+        let commitment_signer = PrivateKeySigner::from_signing_key(commitment_key);
+
+        Self::from_components(opts, constraint_signer, commitment_signer, state_client)
+            .await
+            .wrap_err("Failed to initialize sidecar with aggregate signer")
+    }
+}
+
 impl SidecarDriver<StateClient, PrivateKeySigner> {
     /// Create a new sidecar driver with the given [Opts] and keystore signer.
     pub async fn with_keystore_signer(opts: &Opts) -> eyre::Result<Self> {
@@ -139,6 +239,18 @@ impl SidecarDriver<StateClient, CommitBoostSigner> {
             &opts.constraint_signing.commit_boost_jwt_hex.clone().expect("CommitBoost JWT"),
         )?;
 
+        if opts.constraint_signing.commit_boost_proxy_key {
+            match &opts.constraint_signing.commit_boost_proxy_delegation_path {
+                Some(path) => {
+                    commit_boost_signer.load_or_request_proxy_key(&opts.chain, path).await?;
+                }
+                None => {
+                    warn!("No proxy delegation path set, a new commit_boost proxy key is used");
+                    commit_boost_signer.request_proxy_key(&opts.chain).await?;
+                }
+            }
+        }
+
         let cb_bls_signer = SignerBLS::CommitBoost(commit_boost_signer.clone());
 
         Self::from_components(opts, cb_bls_signer, commit_boost_signer, state_client)
@@ -155,7 +267,19 @@ impl<C: StateFetcher, ECDSA: SignerECDSA> SidecarDriver<C, ECDSA> {
         commitment_signer: ECDSA,
         fetcher: C,
     ) -> eyre::Result<Self> {
-        let mut constraints_client = ConstraintsClient::new(opts.constraints_api_url.clone());
+        let pinned_cert = match &opts.constraints_api_pinned_cert_path {
+            Some(path) => {
+                let pem = std::fs::read(path).wrap_err("Failed to read the pinned certificate")?;
+                Some(PinnedCertificate::from_pem(&pem).wrap_err("Invalid pinned certificate")?)
+            }
+            None => None,
+        };
+        let tls = RelayTls { require_tls: opts.constraints_api_require_tls, pinned_cert };
+        let mut constraints_client =
+            ConstraintsClient::with_tls(opts.constraints_api_url.clone(), tls)
+                .wrap_err("Invalid constraints API URL")?;
+        constraints_client.set_ssz_constraints(opts.constraints_api_ssz);
+        constraints_client.set_delegations_encoding(opts.delegations_encoding);
 
         // read the delegations from disk if they exist and add them to the constraints client.
         let validator_pubkeys = if let Some(delegations_path) =
@@ -170,8 +294,9 @@ impl<C: StateFetcher, ECDSA: SignerECDSA> SidecarDriver<C, ECDSA> {
             Vec::from_iter(constraint_signer.available_pubkeys())
         };
 
-        if opts.unsafe_disable_onchain_checks {
+        let bolt_manager = if opts.unsafe_disable_onchain_checks {
             warn!("Skipping validators and operator public keys verification, --unsafe-disable-onchain-checks is 'true'");
+            None
         } else if let Some(manager) =
             BoltManager::from_chain(opts.execution_api_url.clone(), *opts.chain)
         {
@@ -182,38 +307,149 @@ impl<C: StateFetcher, ECDSA: SignerECDSA> SidecarDriver<C, ECDSA> {
             );
 
             manager
-                .verify_validator_pubkeys(validator_pubkeys, commitment_signer.public_key())
+                .verify_validator_pubkeys(validator_pubkeys.clone(), commitment_signer.public_key())
                 .await?;
 
             info!("Successfully verified validators and operator keys with Bolt Manager.");
+            Some(manager)
         } else {
             warn!(
                 "Bolt Manager is not deployed on {}, skipping validators and operator public keys verification",
                 opts.chain.name()
             );
+            None
+        };
+
+        if let Some(address) = opts.fee_recipient_registry {
+            let registry =
+                FeeRecipientRegistry::from_address(opts.execution_api_url.clone(), address);
+            registry.verify_fee_recipient(&validator_pubkeys, opts.fee_recipient).await?;
+            info!(fee_recipient = %opts.fee_recipient, "Verified the registered fee recipient");
         }
 
         let beacon_client = BeaconClient::new(opts.beacon_api_url.clone());
-        let execution = ExecutionState::new(fetcher, opts.limits).await?;
+        // Commitments must fit in the limits of the Constraints API server
+        let limits = opts.limits.with_relay_limits(opts.relay_limits);
+
+        let webhooks = WebhookNotifier::new(opts.webhooks_allow_private_ips);
+        let reservations =
+            ReservationBook::new(Duration::from_millis(opts.reservation_ttl_ms), limits);
+        let mut execution = ExecutionState::new(fetcher, limits)
+            .await?
+            .with_conflict_policy(opts.constraints_conflict_policy)
+            .with_webhooks(webhooks.clone())
+            .with_reservations(reservations.clone());
+        if opts.commitment_fee_accounting {
+            execution = execution.with_fee_accounting(FeeAccounting::new(Arc::new(GasPricing {
+                base_fee: opts.commitment_base_fee,
+                fee_per_gas: opts.commitment_fee_per_gas,
+            })));
+        }
+
+        // A single client broadcasts the committed transactions to the public mempool, be it
+        // after their slot or when the relays fail to accept their constraints
+        let broadcast_client = (opts.post_slot_broadcast || opts.relay_failure_mempool_fallback)
+            .then(|| RpcClient::new(opts.execution_api_url.clone()));
+        if let Some(client) = broadcast_client.as_ref().filter(|_| opts.post_slot_broadcast) {
+            execution = execution.with_post_slot_broadcast(client.clone());
+        }
 
         let genesis_time = beacon_client.get_genesis_details().await?.genesis_time;
+        let slots_per_epoch = opts.chain.slots_per_epoch();
         let slot_stream =
-            clock::from_system_time(genesis_time, opts.chain.slot_time(), SLOTS_PER_EPOCH)
+            clock::from_system_time(genesis_time, opts.chain.slot_time(), slots_per_epoch)
                 .into_stream();
 
-        let local_builder = LocalBuilder::new(opts, beacon_client.clone(), genesis_time);
+        let submission_timing = SubmissionTiming::new(
+            opts.chain.slot_clock(genesis_time),
+            opts.chain.constraints_cutoff(),
+        );
+        if opts.chain.constraints_cutoff >= ATTESTATION_DEADLINE_IN_MILLIS {
+            warn!(
+                cutoff = opts.chain.constraints_cutoff,
+                "The constraints cutoff is past the attestation deadline"
+            );
+        }
+
+        let mut constraints_store = None;
+        let mut replayed_slots = HashSet::new();
+        if let Some(path) = opts.constraints_store_path.clone() {
+            let store = ConstraintsStore::new(path);
+
+            if opts.replay {
+                // Load the constraints that are still relevant before any other write to the store
+                let current_slot = opts.chain.slot_clock(genesis_time).current_slot();
+                let pending = {
+                    let store = store.clone();
+                    tokio::task::spawn_blocking(move || store.load_pending(current_slot)).await??
+                };
+
+                // Rebuild the block templates, so that the replayed commitments are accounted
+                // for in the validation of new requests and included in the local payloads
+                for signed_constraints in &pending {
+                    let slot = signed_constraints.message.slot;
+                    replayed_slots.insert(slot);
+                    match execution.add_constraint(slot, signed_constraints.clone()) {
+                        Ok(replaced) if !replaced.is_empty() => {
+                            let count = replaced.len();
+                            warn!(slot, count, "Replayed constraints replaced conflicting ones");
+                        }
+                        Ok(_) => {}
+                        Err(err) => {
+                            warn!(slot, ?err, "Failed to add replayed constraints to the template")
+                        }
+                    }
+                }
+
+                // Re-post them to the relays in the background
+                let constraints_client = constraints_client.clone();
+                tokio::spawn(async move { replay_constraints(&constraints_client, pending).await });
+            }
+
+            constraints_store = Some(store.spawn());
+        }
+
+        let mut local_builder = LocalBuilder::new(opts, beacon_client.clone(), genesis_time);
+        if let Some(address) = opts.fee_recipient_resolver {
+            let registry =
+                FeeRecipientRegistry::from_address(opts.execution_api_url.clone(), address);
+            local_builder = local_builder.with_fee_recipient_resolver(Arc::new(registry));
+        }
+        if opts.builder_filler_txs {
+            let pool = MempoolFillerPool::new(opts.execution_api_url.clone());
+            local_builder = local_builder.with_filler_pool(Arc::new(pool));
+        }
+        match local_builder.warm_up_engine().await {
+            Ok(()) => {}
+            Err(err) if opts.require_engine_capabilities => {
+                return Err(err).wrap_err("Failed to verify the engine API");
+            }
+            Err(err) => warn!(?err, "Failed to verify the engine API, fallback blocks may fail"),
+        }
         let head_tracker = HeadTracker::start(beacon_client.clone());
 
-        let consensus = ConsensusState::new(
+        let mut consensus = ConsensusState::new(
             beacon_client,
             opts.chain.commitment_deadline(),
             opts.chain.enable_unsafe_lookahead,
-        );
+        )
+        .with_slots_per_epoch(slots_per_epoch)
+        .with_max_lookahead_slots(opts.chain.max_lookahead_slots);
+        if opts.strict_proposer {
+            let available_pubkeys = constraint_signer.available_pubkeys();
+            let owned = constraints_client.owned_validators(&available_pubkeys);
+            info!(owned = owned.len(), "Strict proposer mode enabled");
+            consensus = consensus.with_owned_validators(owned);
+        }
 
         let (payload_requests_tx, payload_requests_rx) = mpsc::channel(16);
         let builder_proxy_cfg = BuilderProxyConfig {
             constraints_client: constraints_client.clone(),
             server_port: opts.constraints_proxy_port,
+            min_bid: U256::from(opts.min_bid_wei),
+            relay_pubkeys: opts.relay_pubkeys.clone(),
+            get_header_timeout: Duration::from_millis(opts.relay_get_header_timeout_ms),
+            chain: opts.chain,
         };
 
         // start the builder api proxy server
@@ -227,7 +463,36 @@ impl<C: StateFetcher, ECDSA: SignerECDSA> SidecarDriver<C, ECDSA> {
         // start the commitments api server
         let api_addr = format!("0.0.0.0:{}", opts.port);
         let (api_events_tx, api_events_rx) = mpsc::channel(1024);
-        CommitmentsApiServer::new(api_addr).run(api_events_tx, opts.limits).await;
+        let (slot_info_requests_tx, slot_info_requests_rx) = mpsc::channel(64);
+        let engine_breaker = CircuitBreaker::new(opts.engine_failure_threshold);
+        let commitment_statuses = match &opts.commitment_status_path {
+            Some(path) => CommitmentStatuses::persistent(path),
+            None => CommitmentStatuses::default(),
+        };
+        let mut api_server = CommitmentsApiServer::new(api_addr)
+            .with_engine_breaker(engine_breaker.clone())
+            .with_commitment_statuses(commitment_statuses.clone())
+            .with_max_inflight_commitments(opts.max_inflight_commitments)
+            .with_reservations(reservations)
+            .with_allowed_kinds(opts.allowed_commitment_kinds.clone())
+            .with_session_domain(SessionDomain {
+                chain_id: opts.chain.chain_id(),
+                sidecar: commitment_signer.public_key(),
+            })
+            .with_slot_info_requests(slot_info_requests_tx);
+        if let Some(ipc_path) = &opts.ipc_path {
+            api_server = api_server.with_ipc_path(ipc_path);
+        }
+        let (signer_rotation_requests_tx, signer_rotation_requests_rx) = mpsc::channel(1);
+        let (rotated_signers_tx, rotated_signers_rx) = mpsc::channel(1);
+        let (rebuilt_payloads_tx, rebuilt_payloads_rx) = mpsc::channel(1);
+        let (drain_slot_requests_tx, drain_slot_requests_rx) = mpsc::channel(1);
+        if let Some(token) = &opts.admin_token {
+            api_server = api_server
+                .with_admin(token.clone(), signer_rotation_requests_tx)
+                .with_drain_slot_requests(drain_slot_requests_tx);
+        }
+        api_server.run(api_events_tx, limits).await;
 
         let unsafe_skip_consensus_checks = opts.unsafe_disable_consensus_checks;
 
@@ -242,10 +507,39 @@ impl<C: StateFetcher, ECDSA: SignerECDSA> SidecarDriver<C, ECDSA> {
             constraints_client,
             api_events_rx,
             payload_requests_rx,
+            slot_info_requests_rx,
+            signer_rotation_requests_rx,
+            rotated_signers_tx,
+            rotated_signers_rx,
+            rebuilt_payloads_tx,
+            rebuilt_payloads_rx,
+            bolt_manager,
+            drain_slot_requests_rx,
+            replayed_slots,
+            chain: opts.chain,
             slot_stream,
+            head_follow: opts.head_follow,
+            engine_breaker,
+            engine_probe: None,
+            constraints_store,
+            submission_timing,
+            commitment_statuses,
+            redact_logs: opts.telemetry.redact_logs(),
+            build_empty_blocks: opts.builder_empty_blocks,
+            mempool_fallback: broadcast_client.filter(|_| opts.relay_failure_mempool_fallback),
+            reconcile_constraints: opts.reconcile_constraints,
+            submit_local_blocks: opts.relay_submit_local_blocks,
+            webhooks,
         })
     }
 
+    /// Set the accounting of the fees accrued by the honored commitments, e.g. to record them
+    /// to a database with a custom pricing and [FeeSink](crate::state::FeeSink).
+    pub fn with_fee_accounting(mut self, fee_accounting: FeeAccounting) -> Self {
+        self.execution = self.execution.with_fee_accounting(fee_accounting);
+        self
+    }
+
     /// Run the main event loop endlessly for the sidecar driver.
     ///
     /// Any errors encountered are contained to the specific `handler` in which
@@ -265,10 +559,23 @@ impl<C: StateFetcher, ECDSA: SignerECDSA> SidecarDriver<C, ECDSA> {
                 Some(payload_request) = self.payload_requests_rx.recv() => {
                     self.handle_fetch_payload_request(payload_request);
                 }
+                Some(slot_info_request) = self.slot_info_requests_rx.recv() => {
+                    self.handle_slot_info_request(slot_info_request);
+                }
+                Some(rotation_request) = self.signer_rotation_requests_rx.recv() => {
+                    self.handle_signer_rotation_request(rotation_request);
+                }
+                Some(rotated_signer) = self.rotated_signers_rx.recv() => {
+                    self.handle_rotated_signer(rotated_signer);
+                }
+                Some((slot, payload_and_bid)) = self.rebuilt_payloads_rx.recv() => {
+                    self.handle_rebuilt_payload(slot, payload_and_bid);
+                }
+                Some(drain_request) = self.drain_slot_requests_rx.recv() => {
+                    self.handle_drain_slot_request(drain_request);
+                }
                 Some(slot) = self.slot_stream.next() => {
-                    if let Err(e) = self.consensus.update_slot(slot).await {
-                        error!(err = ?e, "Failed to update consensus state slot");
-                    }
+                    self.handle_new_slot(slot).await;
                 }
             }
         }
@@ -278,7 +585,7 @@ impl<C: StateFetcher, ECDSA: SignerECDSA> SidecarDriver<C, ECDSA> {
     async fn handle_incoming_api_event(&mut self, event: CommitmentEvent) {
         let CommitmentEvent { request, response } = event;
 
-        info!("Received new commitment request: {:?}", request);
+        log_commitment_request(&request, self.redact_logs);
         ApiMetrics::increment_inclusion_commitments_received();
 
         let start = Instant::now();
@@ -286,152 +593,429 @@ impl<C: StateFetcher, ECDSA: SignerECDSA> SidecarDriver<C, ECDSA> {
         // When we'll add more commitment types, we'll need to match on the request type here.
         // For now, we only support inclusion requests so the flow is straightforward.
         let CommitmentRequest::Inclusion(mut inclusion_request) = request;
-        let target_slot = inclusion_request.slot;
+        let requested_slot = inclusion_request.slot;
 
-        let available_pubkeys = self.constraint_signer.available_pubkeys();
+        // Reject webhooks pointing to internal services before doing any work for the request
+        let webhook_check = inclusion_request.webhook.as_ref().map(|u| self.webhooks.check_url(u));
+        if let Some(Err(err)) = webhook_check {
+            warn!(?err, "Rejected the webhook of the request");
+            let msg = format!("Invalid webhook: {err}");
+            let _ = response.send(Err(RejectionError::ValidationFailed(msg).into()));
+            return;
+        }
 
-        // Determine the constraint signing public key for this request. Rationale:
-        // - If we're skipping consensus checks, we can use any available pubkey in the keystore.
-        // - On regular operation, we need to validate the request against the consensus state to
-        //   determine if the sidecar is the proposer for the given slot. If so, we use the
-        //   validator pubkey or any of its active delegatees to sign constraints.
-        let signing_pubkey = if self.unsafe_skip_consensus_checks {
-            available_pubkeys.iter().min().cloned().expect("at least one available pubkey")
+        // Determine the candidate slots of the request, i.e. the slots of its range for which
+        // the sidecar can commit. Unless we're skipping consensus checks, we need to validate
+        // the request against the consensus state to determine if the sidecar is the proposer
+        // for each slot, and keep track of the validator pubkey.
+        let candidates = if self.unsafe_skip_consensus_checks {
+            inclusion_request.slot_range().map(|slot| (slot, None)).collect::<Vec<_>>()
         } else {
-            let validator_pubkey = match self.consensus.validate_request(&inclusion_request) {
-                Ok(pubkey) => pubkey,
-                Err(err) => {
-                    warn!(?err, "Consensus: failed to validate request");
-                    let _ = response.send(Err(CommitmentError::Consensus(err)));
-                    return;
+            let stage_start = Instant::now();
+            let mut candidates = Vec::new();
+            let mut last_err = None;
+            for slot in inclusion_request.slot_range() {
+                match self.consensus.validate_slot(slot) {
+                    Ok(pubkey) => candidates.push((slot, Some(pubkey))),
+                    Err(err) => last_err = Some(err),
                 }
-            };
+            }
 
-            // Find a public key to sign new constraints with for this slot.
-            // This can either be the validator pubkey or a delegatee (if one is available).
-            let Some(signing_key) =
-                self.constraints_client.find_signing_key(validator_pubkey, available_pubkeys)
-            else {
-                error!(%target_slot, "No available public key to sign constraints with");
-                let _ = response.send(Err(CommitmentError::Internal));
+            if candidates.is_empty() {
+                let err = last_err.expect("at least one slot in the range");
+                warn!(?err, "Consensus: failed to validate request");
+                let _ = response.send(Err(CommitmentError::Consensus(err)));
                 return;
-            };
+            }
+            ApiMetrics::observe_commitment_stage(
+                CommitmentStage::ConsensusValidation,
+                stage_start.elapsed(),
+            );
 
-            signing_key
+            candidates
         };
 
-        if let Err(err) = self.execution.validate_request(&mut inclusion_request).await {
-            warn!(?err, "Execution: failed to validate request");
-            ApiMetrics::increment_validation_errors(err.to_tag_str().to_owned());
-            let _ = response.send(Err(CommitmentError::Validation(err)));
-            return;
+        let stage_start = Instant::now();
+        let slots = candidates.iter().map(|(slot, _)| *slot);
+        let target_slot =
+            match self.execution.validate_request_in_slots(&mut inclusion_request, slots).await {
+                Ok(slot) => slot,
+                Err(err) => {
+                    warn!(?err, "Execution: failed to validate request");
+                    ApiMetrics::increment_validation_errors(err.to_tag_str().to_owned());
+                    let _ = response.send(Err(CommitmentError::Validation(err)));
+                    return;
+                }
+            };
+        ApiMetrics::observe_commitment_stage(
+            CommitmentStage::ExecutionValidation,
+            stage_start.elapsed(),
+        );
+
+        if target_slot != requested_slot {
+            debug!(requested_slot, target_slot, "Request placed in a later slot of its range");
         }
 
+        let available_pubkeys = self.constraint_signer.available_pubkeys();
+
+        // Determine the constraint signing public key for the chosen slot. Rationale:
+        // - If we're skipping consensus checks, we can use any available pubkey in the keystore.
+        // - On regular operation, we use the validator pubkey of the slot or any of its active
+        //   delegatees to sign constraints.
+        let validator_pubkey = candidates
+            .into_iter()
+            .find_map(|(slot, pubkey)| (slot == target_slot).then_some(pubkey))
+            .flatten();
+        let signing_pubkey = match validator_pubkey {
+            None => available_pubkeys.iter().min().cloned().expect("at least one available pubkey"),
+            Some(validator_pubkey) => {
+                // Find a public key to sign new constraints with for this slot.
+                // This can either be the validator pubkey or a delegatee (if one is available).
+                let Some(signing_key) =
+                    self.constraints_client.find_signing_key(validator_pubkey, available_pubkeys)
+                else {
+                    error!(%target_slot, "No available public key to sign constraints with");
+                    let _ = response.send(Err(CommitmentError::Internal));
+                    return;
+                };
+
+                signing_key
+            }
+        };
+
         info!(
             target_slot,
             elapsed = ?start.elapsed(),
             "Validation against execution state passed"
         );
 
+        // The commitments to evict are only selected here: the block template is left untouched
+        // until all the constraints and the commitment are signed, so that a signing failure
+        // doesn't break the commitments of others for a rejected request
+        let evictions = self.execution.evictions_for_request(&inclusion_request, target_slot);
+
         // NOTE: we iterate over the transactions in the request and generate a signed constraint
         // for each one. This is because the transactions in the commitment request are not supposed
         // to be treated as a relative-ordering bundle, but a batch with no ordering guarantees.
         //
         // For more information, check out the constraints API docs:
         // https://docs.boltprotocol.xyz/technical-docs/api/builder#constraints
+        let mut constraints = Vec::with_capacity(inclusion_request.txs.len());
         for tx in &inclusion_request.txs {
-            let tx_type = tx.tx_type();
             let message =
                 ConstraintsMessage::from_tx(signing_pubkey.clone(), target_slot, tx.clone());
             let digest = message.digest();
 
+            let stage_start = Instant::now();
             let signature_result = match &self.constraint_signer {
                 SignerBLS::Local(signer) => signer.sign_commit_boost_root(digest),
                 SignerBLS::CommitBoost(signer) => signer.sign_commit_boost_root(digest).await,
                 SignerBLS::Keystore(signer) => {
                     signer.sign_commit_boost_root(digest, &signing_pubkey)
                 }
+                SignerBLS::Aggregate(signer) => signer.sign_commit_boost_root(digest).await,
+                #[cfg(feature = "mock-signer")]
+                SignerBLS::Mock(signer) => signer.sign_commit_boost_root(digest),
             };
 
-            let signed_constraints = match signature_result {
-                Ok(signature) => SignedConstraints { message, signature },
+            match signature_result {
+                Ok(signature) => {
+                    ApiMetrics::observe_commitment_stage(
+                        CommitmentStage::ConstraintsSigning,
+                        stage_start.elapsed(),
+                    );
+                    constraints.push(SignedConstraints { message, signature });
+                }
                 Err(e) => {
                     error!(?e, "Failed to sign constraints");
                     let _ = response.send(Err(CommitmentError::Internal));
                     return;
                 }
-            };
-
-            ApiMetrics::increment_transactions_preconfirmed(tx_type);
-            self.execution.add_constraint(target_slot, signed_constraints);
+            }
         }
 
         // Create a commitment by signing the request
-        match inclusion_request.commit_and_sign(&self.commitment_signer).await {
-            Ok(commitment) => {
-                debug!(target_slot, elapsed = ?start.elapsed(), "Commitment signed and sent");
-                response.send(Ok(SignedCommitment::Inclusion(commitment))).ok()
-            }
+        let stage_start = Instant::now();
+        let commitment = match inclusion_request.commit_and_sign(&self.commitment_signer).await {
+            Ok(commitment) => commitment,
             Err(err) => {
                 error!(?err, "Failed to sign commitment");
-                response.send(Err(CommitmentError::Internal)).ok()
+                let _ = response.send(Err(CommitmentError::Internal));
+                return;
             }
         };
+        ApiMetrics::observe_commitment_stage(
+            CommitmentStage::CommitmentSigning,
+            stage_start.elapsed(),
+        );
+
+        // Evict the selected commitments and add all the constraints of the request at once.
+        // On error, none of them is added and nothing is evicted.
+        let persisted = self.constraints_store.as_ref().map(|_| constraints.clone());
+        match self.execution.commit_constraints(target_slot, evictions, constraints) {
+            Ok(removed) if !removed.is_empty() => {
+                self.handle_evicted_constraints(target_slot, removed)
+            }
+            Ok(_) => {}
+            Err(err) => {
+                warn!(?err, "Failed to add constraints to the block template");
+                let _ = response.send(Err(CommitmentError::Validation(err.into())));
+                return;
+            }
+        }
+
+        if let (Some(store), Some(constraints)) = (&self.constraints_store, persisted) {
+            for signed_constraints in constraints {
+                store.append(signed_constraints);
+            }
+        }
+
+        let request = commitment.request();
+        for tx in &request.txs {
+            ApiMetrics::increment_transactions_preconfirmed(tx.tx_type());
+        }
+        self.execution.record_pending_commitment(target_slot, request);
+
+        if let Some(url) = request.webhook.clone() {
+            let tx_hashes = request.txs.iter().map(|tx| *tx.hash()).collect();
+            self.webhooks.subscribe(target_slot, tx_hashes, url);
+        }
+
+        let sequence = self.execution.next_commitment_sequence(target_slot);
+        let degraded = self.mempool_fallback.is_some() && self.commitment_statuses.relays_failing();
+        let commitment = commitment
+            .with_sequence(sequence)
+            .with_target_slot(target_slot)
+            .with_degraded(degraded);
+
+        debug!(target_slot, sequence, elapsed = ?start.elapsed(), "Commitment signed and sent");
+        let _ = response.send(Ok(SignedCommitment::Inclusion(commitment)));
 
         ApiMetrics::increment_inclusion_commitments_accepted();
     }
 
+    /// Handle a new slot of the consensus clock.
+    ///
+    /// The slot always follows the clock, even when following the head: a slot without a block
+    /// has no head event, but its commitment deadline must still fire.
+    async fn handle_new_slot(&mut self, slot: u64) {
+        if let Err(e) = self.consensus.update_slot(slot).await {
+            error!(err = ?e, "Failed to update consensus state slot");
+        }
+
+        if !self.head_follow && self.engine_breaker.is_open() {
+            self.probe_engine_health();
+        }
+    }
+
     /// Handle a new head event, updating the execution state.
     async fn handle_new_head_event(&mut self, head_event: HeadEvent) {
         let slot = head_event.slot;
         info!(slot, "Received new head event");
+        self.submission_timing.on_head(slot);
 
         // We use None to signal that we want to fetch the latest EL head
         if let Err(e) = self.execution.update_head(None, slot).await {
             error!(err = ?e, "Failed to update execution state head");
         }
+
+        self.rebuild_local_payload(slot + 1);
+
+        if self.head_follow && self.engine_breaker.is_open() {
+            self.probe_engine_health();
+        }
+    }
+
+    /// Rebuild the local payload of the given slot on top of the new head in a task, if it was
+    /// built on top of another one and rebuilding on head changes is enabled. The stale payload
+    /// is served until the rebuilt one replaces it in [Self::handle_rebuilt_payload].
+    fn rebuild_local_payload(&self, slot: u64) {
+        let proposer = self.consensus.find_validator_pubkey_for_slot(slot).ok();
+        let template = self.execution.get_block_template(slot).cloned().unwrap_or_default();
+        let Some(rebuild) = self.local_builder.rebuild_on_head(slot, proposer, template) else {
+            return;
+        };
+
+        let rebuilt_payloads_tx = self.rebuilt_payloads_tx.clone();
+        tokio::spawn(async move {
+            if let Some(payload_and_bid) = rebuild.await {
+                let _ = rebuilt_payloads_tx.send((slot, payload_and_bid)).await;
+            }
+        });
+    }
+
+    /// Handle a local payload rebuilt on top of a new head, replacing the stale one unless it
+    /// was already served to the beacon node.
+    fn handle_rebuilt_payload(&mut self, slot: u64, payload_and_bid: PayloadAndBid) {
+        if self.local_builder.peek_cached_payload(slot).is_none() {
+            debug!(slot, "Local payload already served, dropping the rebuilt one");
+            return;
+        }
+
+        info!(slot, block_hash = ?payload_and_bid.payload.block_hash(), "Rebuilt local payload");
+        self.local_builder.store_payload(slot, payload_and_bid);
+    }
+
+    /// Handle the constraints evicted from the block template of `slot`, by a higher-priority
+    /// commitment to make room or to resolve a nonce conflict, or by the draining of a slot:
+    /// they are dropped from the constraints store so that they are never submitted.
+    ///
+    /// The evicted commitments were signed, so their holders are notified of the violation:
+    /// their status is marked as evicted and their webhooks, if any, are notified right away.
+    fn handle_evicted_constraints(&self, slot: u64, evicted: Vec<SignedConstraints>) {
+        let tx_hashes = evicted
+            .iter()
+            .flat_map(|sc| sc.message.transactions.iter().map(|tx| *tx.hash()))
+            .collect::<Vec<_>>();
+
+        warn!(slot, ?tx_hashes, "Evicted commitments from the slot");
+        ApiMetrics::increment_transactions_evicted(tx_hashes.len());
+
+        for tx_hash in &tx_hashes {
+            self.commitment_statuses.mark_evicted(slot, *tx_hash);
+        }
+        self.webhooks.notify_evicted(slot, &tx_hashes);
+
+        if let Some(store) = &self.constraints_store {
+            store.remove(evicted);
+        }
     }
 
     /// Handle a commitment deadline event, submitting constraints to the Constraints client service
     /// and starting to build a local payload for the given target slot.
+    ///
+    /// If no commitments were issued for the slot, an empty local payload is built only if
+    /// enabled and one of our validators is the proposer, with nothing to submit.
     async fn handle_commitment_deadline(&mut self, slot: u64) {
-        let Some(template) = self.execution.get_block_template(slot) else {
+        // The proposer pubkey is used to resolve the fee recipient, if a resolver is set.
+        let proposer = self.consensus.find_validator_pubkey_for_slot(slot).ok();
+
+        let empty_template = BlockTemplate::default();
+        let template = match self.execution.get_block_template(slot) {
+            Some(template) => template,
+            None if self.build_empty_blocks && proposer.is_some() => &empty_template,
             // Nothing to do then. Block templates are created only when constraints are added,
             // which means we haven't issued any commitment for this slot because we are
             // (probably) not the proposer for this block.
-            return;
+            None => return,
         };
 
         info!(slot, "Commitment deadline reached, building local block");
 
-        if let Err(e) = self.local_builder.build_new_local_payload(slot, template).await {
-            error!(err = ?e, "Error while building local payload at deadline for slot {slot}");
+        match self.local_builder.build_new_local_payload(slot, proposer.as_ref(), template).await {
+            Ok(()) => {
+                self.engine_breaker.record_success();
+                if self.submit_local_blocks {
+                    self.submit_local_block(slot, proposer.as_ref());
+                }
+            }
+            Err(e) => {
+                error!(err = ?e, "Error while building local payload at deadline for slot {slot}");
+                // Only the failures of the engine API count, not the ones of the beacon node
+                if e.is_engine_failure() {
+                    self.engine_breaker.record_failure();
+                }
+            }
         };
 
-        let constraints = Arc::new(template.signed_constraints_list.clone());
+        // Constraints of past slots are not relevant anymore after a restart
+        if let Some(store) = &self.constraints_store {
+            store.prune_before(slot);
+        }
+
+        if template.signed_constraints_list.is_empty() {
+            return;
+        }
+
+        // Submit the constraints in the background, retrying those that failed
+        let submission = submit_constraints_with_retries(
+            slot,
+            template.signed_constraints_list.clone(),
+            CONSTRAINTS_SUBMISSION_RETRIES,
+            self.constraints_client.clone(),
+            self.submission_timing.clone(),
+            self.commitment_statuses.clone(),
+            self.mempool_fallback.clone(),
+        );
+
+        if !self.reconcile_constraints {
+            tokio::spawn(submission);
+            return;
+        }
+
+        // Then check that the relay ended up with the same constraints
         let constraints_client = self.constraints_client.clone();
+        let constraints = template.signed_constraints_list.clone();
+        let pubkeys = self.constraint_signer.available_pubkeys();
+        tokio::spawn(async move {
+            submission.await;
+            let drift = constraints_client.reconcile_constraints(slot, &constraints, &pubkeys);
+            if let Err(err) = drift.await {
+                warn!(?err, slot, "Failed to reconcile the constraints with the relay");
+            }
+        });
+    }
 
-        // Submit constraints to the constraints service with an exponential retry mechanism.
-        tokio::spawn(retry_with_backoff(10, move || {
-            let constraints_client = constraints_client.clone();
-            let constraints = Arc::clone(&constraints);
-            async move {
-                match constraints_client.submit_constraints(constraints.as_ref()).await {
-                    Ok(_) => Ok(()),
-                    Err(e) => {
-                        error!(err = ?e, "Failed to submit constraints, retrying...");
-                        Err(e)
-                    }
+    /// Submit the local payload of the slot to the block-submission endpoint of the relay in
+    /// the background, for relays that don't implement the constraints API.
+    fn submit_local_block(&self, slot: u64, proposer: Option<&BlsPublicKey>) {
+        let Some(proposer) = proposer else {
+            warn!(slot, "Unknown proposer, not submitting the local block to the relay");
+            return;
+        };
+
+        let submission = match self.local_builder.create_bid_submission(slot, proposer) {
+            Ok(Some(submission)) => submission,
+            Ok(None) => return,
+            Err(err) => {
+                warn!(?err, slot, "Failed to create the submission of the local block");
+                return;
+            }
+        };
+
+        let constraints_client = self.constraints_client.clone();
+        tokio::spawn(async move {
+            if let Err(err) = constraints_client.submit_block(&submission).await {
+                warn!(?err, slot, "Failed to submit the local block to the relay");
+            }
+        });
+    }
+
+    /// Check in the background whether the engine API is reachable again after the circuit
+    /// breaker opened, closing it on success so that new commitments can be accepted. Nothing
+    /// is done if the previous check is still in progress.
+    fn probe_engine_health(&mut self) {
+        if self.engine_probe.as_ref().is_some_and(|probe| !probe.is_finished()) {
+            return;
+        }
+
+        let health_check = self.local_builder.check_engine_health();
+        let engine_breaker = self.engine_breaker.clone();
+        self.engine_probe = Some(tokio::spawn(async move {
+            match health_check.await {
+                Ok(()) => engine_breaker.record_success(),
+                Err(err) => {
+                    warn!(?err, "Engine API is still unhealthy, rejecting new commitments");
+                    engine_breaker.record_failure();
                 }
             }
         }));
     }
 
     /// Handle a fetch payload request, responding with the local payload if available.
+    ///
+    /// The payload is never built here, so that the request doesn't stall the event loop:
+    /// whichever payload is ready is served, be it a stale one still being rebuilt.
     fn handle_fetch_payload_request(&mut self, request: FetchPayloadRequest) {
         info!(slot = request.slot, "Received local payload request");
 
-        let Some(payload_and_bid) = self.local_builder.get_cached_payload() else {
+        // The payload is only taken once served, and peeked at for the bids before that
+        let payload_and_bid = if request.take {
+            self.local_builder.get_cached_payload(request.slot)
+        } else {
+            self.local_builder.peek_cached_payload(request.slot)
+        };
+        let Some(payload_and_bid) = payload_and_bid else {
             warn!(slot = request.slot, "No local payload found");
             let _ = request.response_tx.send(None);
             return;
@@ -441,6 +1025,156 @@ impl<C: StateFetcher, ECDSA: SignerECDSA> SidecarDriver<C, ECDSA> {
             error!(err = ?e, "Failed to send payload and bid in response channel");
         }
     }
+
+    /// Handle a slot info request, responding with the fees and remaining capacity of the slot.
+    fn handle_slot_info_request(&self, request: SlotInfoRequest) {
+        debug!(slot = request.slot, "Received slot info request");
+        let _ = request.response_tx.send(self.execution.slot_info(request.slot));
+    }
+
+    /// Handle a signer rotation request, loading and verifying the new signer in a task so
+    /// that decrypting the keystores and querying the Bolt Manager don't stall the event loop.
+    /// The new signer is then swapped in by [Self::handle_rotated_signer].
+    fn handle_signer_rotation_request(&self, request: SignerRotationRequest) {
+        let SignerRotationRequest { source, response_tx } = request;
+        let chain = self.chain;
+        let delegatees = self.constraints_client.delegatees();
+        let bolt_manager = self.bolt_manager.clone();
+        let operator = self.commitment_signer.public_key();
+        let rotated_signers_tx = self.rotated_signers_tx.clone();
+
+        tokio::spawn(async move {
+            let result = async {
+                let signer = tokio::task::spawn_blocking(move || source.load(chain)).await??;
+                let pubkeys = signer.available_pubkeys();
+                verify_rotated_pubkeys(&pubkeys, &delegatees, bolt_manager.as_ref(), operator)
+                    .await?;
+                Ok::<_, SignerRotationError>(signer)
+            }
+            .await;
+
+            match result {
+                Ok(signer) => {
+                    let rotated = RotatedSigner { signer, response_tx };
+                    if rotated_signers_tx.send(rotated).await.is_err() {
+                        error!("Failed to send the rotated signer to the driver");
+                    }
+                }
+                Err(err) => {
+                    error!(?err, "Failed to load the new constraint signer");
+                    let _ = response_tx.send(Err(err));
+                }
+            }
+        });
+    }
+
+    /// Swap the constraint signer for a rotated one, loaded and verified off the event loop.
+    ///
+    /// Signers are swapped by the event loop, so signings in progress complete with the old
+    /// signer, and all later ones use the new signer.
+    fn handle_rotated_signer(&mut self, rotated: RotatedSigner) {
+        let RotatedSigner { signer, response_tx } = rotated;
+        let old = std::mem::replace(&mut self.constraint_signer, signer);
+        let pubkeys = self.constraint_signer.available_pubkeys();
+        info!(old = ?old.available_pubkeys(), new = ?pubkeys, "Rotated constraint signer");
+
+        let owned = self.constraints_client.owned_validators(&pubkeys);
+        self.consensus.update_owned_validators(owned);
+        let _ = response_tx.send(Ok(pubkeys));
+    }
+
+    /// Handle a request to drain the commitments of an upcoming slot: its constraints, and the
+    /// ones of later slots depending on them, are removed from the block templates and the
+    /// store so that they are never submitted. Their holders are notified as for evictions.
+    ///
+    /// The constraints API can't cancel constraints already posted to the relays, so slots
+    /// past their commitment deadline can't be drained, nor can the slots after the ones
+    /// re-posted on startup, whose constraints may depend on the drained ones.
+    fn handle_drain_slot_request(&mut self, request: DrainSlotRequest) {
+        let DrainSlotRequest { slot, response_tx } = request;
+
+        if self.consensus.deadline_reached(slot) {
+            warn!(slot, "Cannot drain a slot past its commitment deadline");
+            let _ = response_tx.send(Err(ConsensusError::DeadlineExceeded.into()));
+            return;
+        }
+
+        if let Some(replayed) = self.replayed_slots.iter().copied().filter(|s| *s >= slot).min() {
+            warn!(slot, replayed, "Cannot drain a slot before re-posted constraints");
+            let _ = response_tx.send(Err(CommitmentError::ConstraintsPosted(replayed)));
+            return;
+        }
+
+        let mut drained_count = 0;
+        for (drained_slot, drained) in self.execution.drain_slot(slot) {
+            drained_count += drained.len();
+            self.handle_evicted_constraints(drained_slot, drained);
+        }
+
+        info!(slot, drained = drained_count, "Drained the commitments of the slot");
+        let _ = response_tx.send(Ok(drained_count));
+    }
+}
+
+/// Hooks stepping through the event loop of the driver one event at a time, like
+/// [SidecarDriver::run_forever] does, for the end-to-end tests.
+#[cfg(test)]
+impl<C: StateFetcher, ECDSA: SignerECDSA> SidecarDriver<C, ECDSA> {
+    /// Handle the next commitment request received by the API.
+    pub(crate) async fn handle_next_api_event(&mut self) -> eyre::Result<()> {
+        let event = self.api_events_rx.recv().await.ok_or_else(|| eyre::eyre!("API closed"))?;
+        self.handle_incoming_api_event(event).await;
+        Ok(())
+    }
+
+    /// Handle the next local payload request of the builder proxy.
+    pub(crate) async fn handle_next_payload_request(&mut self) -> eyre::Result<()> {
+        let request =
+            self.payload_requests_rx.recv().await.ok_or_else(|| eyre::eyre!("proxy closed"))?;
+        self.handle_fetch_payload_request(request);
+        Ok(())
+    }
+
+    /// Handle the next signer rotation request received by the API, until the new signer,
+    /// loaded off the event loop, is swapped in.
+    pub(crate) async fn handle_next_signer_rotation(&mut self) -> eyre::Result<()> {
+        let request = self
+            .signer_rotation_requests_rx
+            .recv()
+            .await
+            .ok_or_else(|| eyre::eyre!("API closed"))?;
+        self.handle_signer_rotation_request(request);
+
+        let rotated =
+            self.rotated_signers_rx.recv().await.ok_or_else(|| eyre::eyre!("driver closed"))?;
+        self.handle_rotated_signer(rotated);
+        Ok(())
+    }
+
+    /// Handle the commitment deadline of the given slot.
+    pub(crate) async fn reach_commitment_deadline(&mut self, slot: u64) {
+        self.handle_commitment_deadline(slot).await
+    }
+
+    /// Handle a new slot of the consensus clock.
+    pub(crate) async fn reach_slot(&mut self, slot: u64) {
+        self.handle_new_slot(slot).await
+    }
+
+    /// The execution state of the driver.
+    pub(crate) fn execution_mut(&mut self) -> &mut ExecutionState<C> {
+        &mut self.execution
+    }
+
+    /// The local builder of the driver.
+    pub(crate) fn local_builder_mut(&mut self) -> &mut LocalBuilder {
+        &mut self.local_builder
+    }
+
+    /// The relay responses to the constraints submissions.
+    pub(crate) fn commitment_statuses(&self) -> &CommitmentStatuses {
+        &self.commitment_statuses
+    }
 }
 
 impl fmt::Debug for SidecarDriver<StateClient, PrivateKeySigner> {
@@ -458,3 +1192,490 @@ impl fmt::Debug for SidecarDriver<StateClient, PrivateKeySigner> {
             .finish()
     }
 }
+
+/// A rotated constraint signer, loaded and verified, to swap the current one for.
+#[derive(Debug)]
+struct RotatedSigner {
+    signer: SignerBLS,
+    response_tx: oneshot::Sender<Result<HashSet<BlsPublicKey>, SignerRotationError>>,
+}
+
+/// Verify the public keys of a rotated constraint signer like the ones of the initial signer
+/// are verified at startup: if there are delegations, the keys must be delegatees of them, as
+/// the delegating validators have been verified already. Otherwise, the keys are the validator
+/// keys themselves, and are verified with the Bolt Manager if the onchain checks are enabled.
+async fn verify_rotated_pubkeys(
+    pubkeys: &HashSet<BlsPublicKey>,
+    delegatees: &HashSet<BlsPublicKey>,
+    bolt_manager: Option<&BoltManager>,
+    operator: Address,
+) -> Result<(), SignerRotationError> {
+    if !delegatees.is_empty() {
+        let undelegated = pubkeys.difference(delegatees).cloned().collect::<Vec<_>>();
+        if !undelegated.is_empty() {
+            return Err(SignerRotationError::NotDelegated(undelegated));
+        }
+        return Ok(());
+    }
+
+    let Some(manager) = bolt_manager else {
+        warn!("No Bolt Manager to verify the rotated signer keys with, skipping verification");
+        return Ok(());
+    };
+
+    manager
+        .verify_validator_pubkeys(Vec::from_iter(pubkeys.iter().cloned()), operator)
+        .await
+        .map_err(|err| SignerRotationError::Unverified(err.to_string()))?;
+
+    Ok(())
+}
+
+/// The number of retries of the submission of the constraints of a slot to the relay.
+const CONSTRAINTS_SUBMISSION_RETRIES: usize = 10;
+
+/// Submit the constraints of a slot to the constraints service with an exponential retry
+/// mechanism, recording the relay response to each submission. Only the constraints that failed
+/// are retried, and not past the cutoff of the slot. This is the only retry layer of the
+/// submissions, so that they are bounded by the slot. Constraints already past the cutoff
+/// are not posted at all, as they are unlikely to be honored.
+///
+/// If the relay never accepted some constraints and a mempool fallback client is given, their
+/// transactions are broadcast to the public mempool as a last resort, and the commitments are
+/// marked as degraded.
+async fn submit_constraints_with_retries(
+    slot: u64,
+    constraints: Vec<SignedConstraints>,
+    max_retries: usize,
+    constraints_client: ConstraintsClient,
+    submission_timing: SubmissionTiming,
+    commitment_statuses: CommitmentStatuses,
+    mempool_fallback: Option<RpcClient>,
+) {
+    let until_cutoff = match submission_timing.check(slot) {
+        Ok(until_cutoff) => until_cutoff,
+        Err(late) => {
+            error!(slot, late_by = ?late.late_by, "Not posting constraints past the cutoff");
+            if let Some(client) = mempool_fallback {
+                broadcast_to_mempool(slot, &constraints, &client, &commitment_statuses).await;
+            }
+            return;
+        }
+    };
+
+    let pending = Arc::new(Mutex::new(constraints));
+    let policy = BackoffPolicy::with_max_retries(max_retries)
+        .with_deadline(tokio::time::Instant::now() + until_cutoff);
+
+    // The retries stop at the cutoff, even while waiting on a relay, so that the mempool
+    // fallback still broadcasts the transactions in time for the target slot
+    let submission = retry_with_policy(policy, || {
+        let constraints_client = constraints_client.clone();
+        let pending = Arc::clone(&pending);
+        let commitment_statuses = commitment_statuses.clone();
+        async move {
+            let stage_start = Instant::now();
+            let constraints = pending.lock().expect("lock not poisoned").clone();
+            let outcomes = match constraints_client.submit_constraints_batch(&constraints).await {
+                Ok(outcomes) => outcomes,
+                Err(e) => {
+                    // The whole batch failed, e.g. the relay is unreachable: keep it as is
+                    error!(err = ?e, "Failed to submit constraints batch, retrying...");
+                    let result = Err(e);
+                    let submission = RelaySubmission::new(constraints_client.url(), &result);
+                    commitment_statuses.record(slot, &constraints, submission);
+                    return result.map(drop);
+                }
+            };
+
+            let mut failed = Vec::new();
+            let mut last_error = None;
+            for (signed_constraints, result) in constraints.into_iter().zip(outcomes) {
+                // Keep the relay response of each attempt for auditing
+                let submission = RelaySubmission::new(constraints_client.url(), &result);
+                let signed_constraints = std::slice::from_ref(&signed_constraints);
+                commitment_statuses.record(slot, signed_constraints, submission);
+
+                if let Err(e) = result {
+                    failed.extend_from_slice(signed_constraints);
+                    last_error = Some(e);
+                }
+            }
+
+            let failures = failed.len();
+            *pending.lock().expect("lock not poisoned") = failed;
+
+            match last_error {
+                None => {
+                    ApiMetrics::observe_commitment_stage(
+                        CommitmentStage::ConstraintsSubmission,
+                        stage_start.elapsed(),
+                    );
+                    Ok(())
+                }
+                Some(e) => {
+                    error!(err = ?e, failures, "Failed to submit constraints, retrying...");
+                    Err(e)
+                }
+            }
+        }
+    });
+    let submitted = tokio::time::timeout(until_cutoff, submission).await;
+
+    let Some(client) = mempool_fallback else { return };
+    match submitted {
+        Ok(Ok(())) => return,
+        Ok(Err(err)) => {
+            warn!(?err, slot, "Relay never accepted the constraints, broadcasting to the mempool")
+        }
+        Err(_) => warn!(slot, "Relay didn't accept the constraints by the cutoff, broadcasting"),
+    }
+
+    let undelivered = pending.lock().expect("lock not poisoned").clone();
+    broadcast_to_mempool(slot, &undelivered, &client, &commitment_statuses).await;
+}
+
+/// Broadcast the transactions of the given constraints to the public mempool as a last resort,
+/// marking their commitments as degraded.
+async fn broadcast_to_mempool(
+    slot: u64,
+    constraints: &[SignedConstraints],
+    client: &RpcClient,
+    commitment_statuses: &CommitmentStatuses,
+) {
+    for tx in constraints.iter().flat_map(|sc| &sc.message.transactions) {
+        match client.send_raw_transaction(tx.encoded_2718().into()).await {
+            Ok(hash) => {
+                info!(%hash, slot, "Broadcast committed transaction, commitment is degraded");
+                commitment_statuses.mark_degraded(slot, hash);
+            }
+            Err(err) => {
+                error!(?err, hash = %tx.hash(), "Failed to broadcast committed transaction")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::Ordering,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    };
+
+    use axum::{http::StatusCode, routing::post, Router};
+    use reqwest::Url;
+    use tokio::net::TcpListener;
+
+    use crate::{
+        api::spec::SUBMIT_CONSTRAINTS_PATH,
+        client::{ConstraintsClient, RpcClient},
+        crypto::SignableBLS,
+        primitives::{ConstraintsMessage, SignedConstraints},
+        signer::{local::LocalSigner, SignerSource},
+        state::{slot_clock::SlotClock, CommitmentStatuses, ConstraintsStore, SubmissionTiming},
+        test_util::{
+            create_signed_inclusion_request, default_test_transaction,
+            harness::{wait_until, SelfBuildHarness},
+            launch_anvil,
+        },
+    };
+
+    use super::submit_constraints_with_retries;
+
+    /// Returns `true` if the commitments API at the given URL reports itself as ready.
+    async fn is_ready(commitments_url: Url) -> bool {
+        let ready_url = commitments_url.join("/ready").expect("valid URL");
+        reqwest::get(ready_url).await.is_ok_and(|response| response.status() == StatusCode::OK)
+    }
+
+    #[tokio::test]
+    async fn test_engine_breaker_counts_engine_failures_only() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut harness = SelfBuildHarness::with_args(["--engine-failure-threshold=1"]).await?;
+        let url = harness.commitments_url();
+
+        // A local payload that can't be built because of the beacon node doesn't open it
+        let response = harness.request_inclusion(0, None, 10).await?;
+        assert!(response.get("error").is_none(), "unexpected error: {response}");
+        harness.beacon_outage.store(true, Ordering::Relaxed);
+        harness.handle_commitment_deadline(10).await?;
+        assert!(is_ready(url.clone()).await);
+
+        // A failure of the engine API does
+        harness.beacon_outage.store(false, Ordering::Relaxed);
+        harness.engine_outage.store(true, Ordering::Relaxed);
+        let response = harness.request_inclusion(1, None, 11).await?;
+        assert!(response.get("error").is_none(), "unexpected error: {response}");
+        harness.handle_commitment_deadline(11).await?;
+        assert!(!is_ready(url.clone()).await);
+
+        // Once the engine is back, the probe of the next slot closes the breaker
+        harness.engine_outage.store(false, Ordering::Relaxed);
+        harness.driver.reach_slot(12).await;
+        wait_until(|| is_ready(url.clone())).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rotated_signer_signs_new_constraints() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut harness = SelfBuildHarness::with_args(["--admin-token=secret"]).await?;
+        let chain = harness.opts.chain;
+        let old_pubkey = harness.constraint_signer().pubkey();
+
+        // Rotate the constraint signer to the test keystores through the admin method
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+        let source = SignerSource {
+            keystore_path: root.join("test_data/keys"),
+            keystore_secrets_path: root.join("test_data/secrets"),
+        };
+        let new_pubkey = source.load(chain)?.available_pubkeys().into_iter().next().unwrap();
+        let payload = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "bolt_admin_rotateSigner",
+            "params": [{
+                "keystorePath": source.keystore_path,
+                "keystoreSecretsPath": source.keystore_secrets_path,
+            }]
+        });
+        let response = tokio::spawn(
+            reqwest::Client::new()
+                .post(harness.commitments_url())
+                .bearer_auth("secret")
+                .json(&payload)
+                .send(),
+        );
+
+        harness.driver.handle_next_signer_rotation().await?;
+        let response = response.await??.json::<serde_json::Value>().await?;
+        assert!(response.get("error").is_none(), "unexpected error: {response}");
+
+        // The constraints signed after the rotation only verify under the new public key
+        let response = harness.request_inclusion(0, None, 10).await?;
+        assert!(response.get("error").is_none(), "unexpected error: {response}");
+
+        let template = harness.driver.execution_mut().get_block_template(10).expect("template");
+        let constraints = &template.signed_constraints_list[0];
+        assert_eq!(constraints.message.pubkey, new_pubkey);
+        assert!(constraints.verify(&new_pubkey, &chain).is_ok());
+        assert!(constraints.verify(&old_pubkey, &chain).is_err());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "mock-signer")]
+    #[tokio::test]
+    async fn test_mock_signer_signs_constraints() -> eyre::Result<()> {
+        use crate::signer::{MockSigner, SignerBLS};
+
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut harness = SelfBuildHarness::new().await?;
+        let pubkey = harness.constraint_signer().pubkey();
+        let signer = MockSigner::new(pubkey.clone());
+        harness.driver.constraint_signer = SignerBLS::Mock(signer.clone());
+
+        let response = harness.request_inclusion(0, None, 10).await?;
+        assert!(response.get("error").is_none(), "unexpected error: {response}");
+
+        // The constraints carry the deterministic signature of the mock signer
+        let template = harness.driver.execution_mut().get_block_template(10).expect("template");
+        let constraints = &template.signed_constraints_list[0];
+        assert_eq!(constraints.message.pubkey, pubkey);
+        assert_eq!(
+            constraints.signature,
+            signer.sign_commit_boost_root(constraints.message.digest())?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_commitment_errors_carry_retryable_hint() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut harness = SelfBuildHarness::with_args(["--engine-failure-threshold=1"]).await?;
+        let retryable =
+            |response: &serde_json::Value| response["error"]["data"]["retryable"].clone();
+
+        // An invalid request won't be accepted if resubmitted: its nonce is already committed
+        let response = harness.request_inclusion(0, None, 10).await?;
+        assert!(response.get("error").is_none(), "unexpected error: {response}");
+        let response = harness.request_inclusion(0, Some(0), 10).await?;
+        assert_eq!(retryable(&response), false, "unexpected response: {response}");
+
+        // While a request rejected because of an engine failure may be accepted later
+        harness.engine_outage.store(true, Ordering::Relaxed);
+        harness.handle_commitment_deadline(10).await?;
+        let (_, response) = harness.post_inclusion_request(1, None, 11, &[]).await?;
+        let response = response.await?.json::<serde_json::Value>().await?;
+        assert_eq!(retryable(&response), true, "unexpected response: {response}");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_replay_rebuilds_block_templates() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        // The mock beacon node of the harness reports a genesis time of 0
+        let current_slot = SlotClock::new(0, 12, 32).current_slot();
+        let target_slot = current_slot + 5;
+
+        // Constraints persisted before a restart, for a slot that hasn't passed yet
+        let anvil = launch_anvil();
+        let tx = default_test_transaction(anvil.addresses()[0], None);
+        let request = create_signed_inclusion_request(&[tx], &anvil.keys()[0], target_slot).await?;
+        let tx_hash = *request.txs[0].hash();
+
+        let signer = LocalSigner::random();
+        let message = ConstraintsMessage::build(signer.pubkey(), request);
+        let signature = signer.sign_commit_boost_root(message.digest())?;
+        let path = std::env::temp_dir()
+            .join(format!("bolt-constraints-store-{}.jsonl", rand::random::<u64>()));
+        ConstraintsStore::new(&path).append(&SignedConstraints { message, signature })?;
+
+        let mut harness = SelfBuildHarness::with_args([
+            format!("--constraints-store-path={}", path.display()),
+            "--replay".to_string(),
+        ])
+        .await?;
+
+        // The template of the slot is rebuilt with the replayed commitment
+        let template = harness.driver.execution_mut().get_block_template(target_slot);
+        assert_eq!(template.expect("rebuilt template").transaction_hashes(), vec![tx_hash]);
+
+        // It is accounted for in the validation of new requests: the nonce is already used
+        let response = harness.request_inclusion(0, None, target_slot).await?;
+        assert!(response.get("error").is_some(), "expected a nonce error: {response}");
+
+        // And it is re-posted to the relay
+        let relay_constraints = harness.relay_constraints.clone();
+        wait_until(|| {
+            let relay_constraints = relay_constraints.clone();
+            async move { relay_constraints.lock().unwrap().len() == 1 }
+        })
+        .await?;
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_relay_failure_falls_back_to_mempool() -> eyre::Result<()> {
+        // A relay rejecting every submission
+        let router = Router::new()
+            .route(SUBMIT_CONSTRAINTS_PATH, post(|| async { StatusCode::INTERNAL_SERVER_ERROR }));
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = Url::parse(&format!("http://{}", listener.local_addr()?))?;
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        let constraints_client = ConstraintsClient::new(url)?;
+
+        let anvil = launch_anvil();
+        let rpc_client = RpcClient::new(anvil.endpoint_url());
+        let slot = 10;
+
+        let tx = default_test_transaction(anvil.addresses()[0], None);
+        let request = create_signed_inclusion_request(&[tx], &anvil.keys()[0], slot).await?;
+        let tx_hash = *request.txs[0].hash();
+
+        let signer = LocalSigner::random();
+        let message = ConstraintsMessage::build(signer.pubkey(), request);
+        let signature = signer.sign_commit_boost_root(message.digest())?;
+        let constraints = vec![SignedConstraints { message, signature }];
+
+        // The slot hasn't started yet, so the constraints are posted
+        let genesis_time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let slot_clock = SlotClock::new(genesis_time, 12, 32);
+        let timing = SubmissionTiming::new(slot_clock, Duration::from_secs(1));
+        let statuses = CommitmentStatuses::default();
+
+        // Without the fallback, the transaction is left to the relay
+        submit_constraints_with_retries(
+            slot,
+            constraints.clone(),
+            0,
+            constraints_client.clone(),
+            timing.clone(),
+            statuses.clone(),
+            None,
+        )
+        .await;
+        assert!(!statuses.get(slot, tx_hash).unwrap().degraded);
+        assert!(rpc_client.get_receipts(&[tx_hash]).await?[0].is_none());
+
+        // With the fallback, it is broadcast to the mempool and the commitment is degraded
+        submit_constraints_with_retries(
+            slot,
+            constraints,
+            0,
+            constraints_client,
+            timing,
+            statuses.clone(),
+            Some(rpc_client.clone()),
+        )
+        .await;
+        assert!(statuses.get(slot, tx_hash).unwrap().degraded);
+        assert!(statuses.relays_failing());
+        assert!(rpc_client.get_receipts(&[tx_hash]).await?[0].is_some());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hanging_relay_falls_back_to_mempool_at_cutoff() -> eyre::Result<()> {
+        // A relay never responding to the submissions
+        let router = Router::new().route(
+            SUBMIT_CONSTRAINTS_PATH,
+            post(|| async {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                StatusCode::OK
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = Url::parse(&format!("http://{}", listener.local_addr()?))?;
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        let constraints_client = ConstraintsClient::new(url)?;
+
+        let anvil = launch_anvil();
+        let rpc_client = RpcClient::new(anvil.endpoint_url());
+
+        // With one-second slots, the cutoff of slot 2 is at most 2 seconds away
+        let genesis_time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let timing = SubmissionTiming::new(SlotClock::new(genesis_time, 1, 32), Duration::ZERO);
+        let statuses = CommitmentStatuses::default();
+        let slot = 2;
+
+        let tx = default_test_transaction(anvil.addresses()[0], None);
+        let request = create_signed_inclusion_request(&[tx], &anvil.keys()[0], slot).await?;
+        let tx_hash = *request.txs[0].hash();
+
+        let signer = LocalSigner::random();
+        let message = ConstraintsMessage::build(signer.pubkey(), request);
+        let signature = signer.sign_commit_boost_root(message.digest())?;
+        let constraints = vec![SignedConstraints { message, signature }];
+
+        // The transaction is broadcast at the cutoff, not once the retries are exhausted
+        let start = std::time::Instant::now();
+        submit_constraints_with_retries(
+            slot,
+            constraints,
+            10,
+            constraints_client,
+            timing,
+            statuses.clone(),
+            Some(rpc_client.clone()),
+        )
+        .await;
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(statuses.get(slot, tx_hash).unwrap().degraded);
+        assert!(rpc_client.get_receipts(&[tx_hash]).await?[0].is_some());
+
+        Ok(())
+    }
+}
@@ -1,9 +1,11 @@
-use std::str::FromStr;
+use std::{fmt, ops::RangeInclusive, str::FromStr};
 
 use alloy::{
     hex,
-    primitives::{keccak256, Address, Signature, B256},
+    primitives::{keccak256, Address, Bytes, Signature, TxHash, B256, U256},
 };
+use clap::ValueEnum;
+use reqwest::Url;
 use serde::{de, Deserialize, Deserializer, Serialize};
 
 use crate::crypto::SignerECDSA;
@@ -23,6 +25,26 @@ pub enum CommitmentRequest {
     Inclusion(InclusionRequest),
 }
 
+/// The kinds of commitment a sidecar can offer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum, Serialize, Deserialize)]
+#[clap(rename_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum CommitmentKind {
+    /// Inclusion of transactions anywhere in the block.
+    Inclusion,
+    /// Inclusion of transactions at the top of the block.
+    TopOfBlock,
+}
+
+impl fmt::Display for CommitmentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inclusion => write!(f, "inclusion"),
+            Self::TopOfBlock => write!(f, "top-of-block"),
+        }
+    }
+}
+
 /// A signed commitment with a generic signature.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(untagged)]
@@ -38,6 +60,21 @@ pub struct InclusionCommitment {
     request: InclusionRequest,
     #[serde(deserialize_with = "deserialize_sig", serialize_with = "serialize_sig")]
     signature: Signature,
+    /// The position of the commitment among the ones accepted for its slot, starting from 0.
+    /// It is not covered by the signature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sequence: Option<u64>,
+    /// The slot of the requested range the transactions are committed to, if it isn't the
+    /// first one. It is not covered by the signature, which is over the digest signed by the
+    /// user: the slot is bound by the constraints signed for it instead.
+    #[serde(default, rename = "targetSlot", skip_serializing_if = "Option::is_none")]
+    target_slot: Option<u64>,
+    /// Whether the commitment is degraded: the relays are failing to accept constraints, so
+    /// its transactions are expected to be broadcast to the public mempool as a last resort,
+    /// and their inclusion in the target slot won't be enforced. It is not covered by the
+    /// signature.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    degraded: bool,
 }
 
 impl From<SignedCommitment> for InclusionCommitment {
@@ -48,6 +85,93 @@ impl From<SignedCommitment> for InclusionCommitment {
     }
 }
 
+impl InclusionCommitment {
+    /// Returns the slot at which the transactions are committed to be included.
+    pub fn slot(&self) -> u64 {
+        self.target_slot.unwrap_or(self.request.slot)
+    }
+
+    /// Returns the hashes of the committed transactions, in request order.
+    pub fn tx_hashes(&self) -> Vec<TxHash> {
+        self.request.txs.iter().map(|tx| *tx.hash()).collect()
+    }
+
+    /// Returns the digest signed by the sidecar. See [InclusionRequest::digest].
+    pub fn digest(&self) -> B256 {
+        self.request.digest()
+    }
+
+    /// Returns the signature of the sidecar over the commitment digest.
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// Recovers the address of the sidecar commitment signer from the signature.
+    pub fn signer(&self) -> Result<Address, SignatureError> {
+        self.signature.recover_address_from_prehash(&self.digest()).map_err(|_| SignatureError)
+    }
+
+    /// Returns the underlying inclusion request.
+    pub fn request(&self) -> &InclusionRequest {
+        &self.request
+    }
+
+    /// Returns the sequence number of the commitment in its slot, if assigned.
+    pub fn sequence(&self) -> Option<u64> {
+        self.sequence
+    }
+
+    /// Sets the sequence number of the commitment in its slot.
+    pub fn with_sequence(mut self, sequence: u64) -> Self {
+        self.sequence = Some(sequence);
+        self
+    }
+
+    /// Sets the slot of the requested range the transactions are committed to.
+    pub fn with_target_slot(mut self, slot: u64) -> Self {
+        self.target_slot = (slot != self.request.slot).then_some(slot);
+        self
+    }
+
+    /// Returns true if the commitment is degraded, i.e. its inclusion won't be enforced by
+    /// the relays.
+    pub fn is_degraded(&self) -> bool {
+        self.degraded
+    }
+
+    /// Sets whether the commitment is degraded.
+    pub fn with_degraded(mut self, degraded: bool) -> Self {
+        self.degraded = degraded;
+        self
+    }
+
+    /// Returns the compact receipt of the commitment, without the raw transactions.
+    pub fn receipt(&self) -> CommitmentReceipt {
+        CommitmentReceipt {
+            slot: self.slot(),
+            tx_hashes: self.tx_hashes(),
+            signature: self.signature,
+            digest: self.digest(),
+        }
+    }
+}
+
+/// A compact receipt of an [InclusionCommitment], returned instead of the full commitment to
+/// the clients that don't need the raw transactions back.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitmentReceipt {
+    /// The slot at which the transactions are committed to be included.
+    pub slot: u64,
+    /// The hashes of the committed transactions, in request order.
+    pub tx_hashes: Vec<TxHash>,
+    /// The signature of the sidecar over the commitment digest.
+    #[serde(deserialize_with = "deserialize_sig", serialize_with = "serialize_sig")]
+    pub signature: Signature,
+    /// The digest signed by the sidecar. See [InclusionRequest::digest].
+    pub digest: B256,
+}
+
 impl CommitmentRequest {
     /// Returns a reference to the inner request if this is an inclusion request, otherwise `None`.
     pub fn as_inclusion_request(&self) -> Option<&InclusionRequest> {
@@ -74,6 +198,71 @@ impl CommitmentRequest {
             Self::Inclusion(req) => req.signature.as_ref(),
         }
     }
+
+    /// Returns the kind of commitment requested.
+    pub fn kind(&self) -> CommitmentKind {
+        match self {
+            Self::Inclusion(req) => req.kind(),
+        }
+    }
+}
+
+/// The maximum number of slots an inclusion request can span, i.e. one epoch of lookahead.
+pub const MAX_INCLUSION_SLOT_RANGE: u64 = 32;
+
+/// The tags of the optional fields in the digest of [DigestVersion::V2].
+const DIGEST_TAG_TOP: u8 = 0x01;
+const DIGEST_TAG_REVERTING_TX_HASHES: u8 = 0x02;
+const DIGEST_TAG_MAX_SLOT: u8 = 0x03;
+const DIGEST_TAG_WEBHOOK: u8 = 0x04;
+
+/// The versions of the digest scheme of inclusion requests. See [InclusionRequest::digest].
+///
+/// The digest defines what users sign, so a version is frozen once released: changing its
+/// scheme would invalidate the signatures of existing clients. Requests declare the version
+/// they were signed with, and default to [DigestVersion::V0] if they don't.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "u8", into = "u8")]
+pub enum DigestVersion {
+    /// The original scheme, without a version byte.
+    #[default]
+    V0,
+    /// The fields of [DigestVersion::V0], prefixed with the version byte `0x01`.
+    V1,
+    /// The fields of [DigestVersion::V0] prefixed with the version byte `0x02`, with the
+    /// transaction hashes prefixed by their count and each optional field by its own tag and
+    /// length, so that the preimage is unambiguous whichever optional fields are set.
+    V2,
+}
+
+impl DigestVersion {
+    /// Returns `true` if this is the original scheme, which isn't serialized in requests.
+    pub fn is_v0(&self) -> bool {
+        *self == Self::V0
+    }
+}
+
+impl TryFrom<u8> for DigestVersion {
+    type Error = String;
+
+    fn try_from(version: u8) -> Result<Self, Self::Error> {
+        match version {
+            0 => Ok(Self::V0),
+            1 => Ok(Self::V1),
+            2 => Ok(Self::V2),
+            _ => Err(format!("unsupported digest version {version}")),
+        }
+    }
+}
+
+impl From<DigestVersion> for u8 {
+    fn from(version: DigestVersion) -> Self {
+        match version {
+            DigestVersion::V0 => 0,
+            DigestVersion::V1 => 1,
+            DigestVersion::V2 => 2,
+        }
+    }
 }
 
 /// Request to include a transaction at a specific slot.
@@ -84,7 +273,27 @@ pub struct InclusionRequest {
     /// The transaction to be included.
     #[serde(deserialize_with = "deserialize_txs", serialize_with = "serialize_txs")]
     pub txs: Vec<FullTransaction>,
-    /// The signature over the "slot" and "tx" fields by the user.
+    /// Whether the transactions must be included at the top of the block.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub top: bool,
+    /// The hashes of the transactions of the bundle that are allowed to revert when the bundle
+    /// is simulated.
+    #[serde(default, rename = "revertingTxHashes", skip_serializing_if = "Vec::is_empty")]
+    pub reverting_tx_hashes: Vec<TxHash>,
+    /// The last slot at which the transactions may be included, if the request is for a range
+    /// of slots starting at `slot`. The sidecar commits to the first slot of the range that
+    /// can fit the transactions, returned as the "targetSlot" of the commitment.
+    #[serde(default, rename = "maxSlot", skip_serializing_if = "Option::is_none")]
+    pub max_slot: Option<u64>,
+    /// The URL the sidecar POSTs to once the slot has passed, to notify whether the
+    /// transactions were included or the commitment was violated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<Url>,
+    /// The version of the digest scheme the request is signed with.
+    #[serde(default, rename = "digestVersion", skip_serializing_if = "DigestVersion::is_v0")]
+    pub digest_version: DigestVersion,
+    /// The signature over the "slot", "tx", "top", "revertingTxHashes", "maxSlot" and "webhook"
+    /// fields by the user, as defined by the digest scheme of its "digestVersion".
     /// A valid signature is the only proof that the user actually requested
     /// this specific commitment to be included at the given slot.
     #[serde(skip)]
@@ -102,7 +311,27 @@ impl InclusionRequest {
     ) -> eyre::Result<InclusionCommitment> {
         let digest = self.digest();
         let signature = signer.sign_hash(&digest).await?;
-        Ok(InclusionCommitment { request: self, signature })
+        Ok(InclusionCommitment {
+            request: self,
+            signature,
+            sequence: None,
+            target_slot: None,
+            degraded: false,
+        })
+    }
+
+    /// Returns the slots at which the request can be included, i.e. the range from `slot` to
+    /// `max_slot`, or only `slot` if no range was requested.
+    pub fn slot_range(&self) -> RangeInclusive<u64> {
+        self.slot..=self.max_slot.unwrap_or(self.slot)
+    }
+
+    /// Returns `true` if the requested slot range is not empty and spans at most
+    /// [MAX_INCLUSION_SLOT_RANGE] slots.
+    pub fn validate_slot_range(&self) -> bool {
+        self.max_slot.map_or(true, |max_slot| {
+            max_slot >= self.slot && max_slot - self.slot < MAX_INCLUSION_SLOT_RANGE
+        })
     }
 
     /// Validates the transaction fees against a minimum basefee.
@@ -144,6 +373,11 @@ impl InclusionRequest {
         true
     }
 
+    /// Returns true if any of the transactions is a contract creation.
+    pub fn has_contract_creation(&self) -> bool {
+        self.txs.iter().any(|tx| tx.tx_kind().is_create())
+    }
+
     /// Validates the init code limit.
     pub fn validate_init_code_limit(&self, limit: usize) -> bool {
         for tx in &self.txs {
@@ -177,11 +411,61 @@ impl InclusionRequest {
         })
     }
 
+    /// Returns an upper bound of the priority fees paid to the proposer by the transactions in
+    /// the request at the given base fee, assuming they use their whole gas limit.
+    pub fn max_priority_fees(&self, base_fee: u128) -> U256 {
+        self.txs.iter().fold(U256::ZERO, |acc, tx| {
+            let tip = tx.effective_tip_per_gas(base_fee).unwrap_or_default();
+            acc + U256::from(tip) * U256::from(tx.gas_limit())
+        })
+    }
+
+    /// Returns the priority of the request at the given base fee, i.e. the lowest effective
+    /// tip per gas of its transactions. Used to decide which commitments to keep when a
+    /// slot is oversubscribed.
+    pub fn priority(&self, base_fee: u128) -> u128 {
+        self.txs
+            .iter()
+            .map(|tx| tx.effective_tip_per_gas(base_fee).unwrap_or_default())
+            .min()
+            .unwrap_or_default()
+    }
+
+    /// Returns the kind of commitment requested.
+    pub fn kind(&self) -> CommitmentKind {
+        if self.top {
+            CommitmentKind::TopOfBlock
+        } else {
+            CommitmentKind::Inclusion
+        }
+    }
+
+    /// Returns `true` if the transaction with the given hash is allowed to revert when the
+    /// bundle is simulated.
+    pub fn allows_revert(&self, tx_hash: &TxHash) -> bool {
+        self.reverting_tx_hashes.contains(tx_hash)
+    }
+
+    /// Returns the first allowed revert that isn't the hash of a transaction of the bundle,
+    /// if any.
+    pub fn unknown_reverting_tx_hash(&self) -> Option<&TxHash> {
+        self.reverting_tx_hashes.iter().find(|hash| !self.txs.iter().any(|tx| tx.hash() == *hash))
+    }
+
     /// Returns the total gas limit of all transactions in this request.
     pub fn gas_limit(&self) -> u64 {
         self.txs.iter().map(|tx| tx.gas_limit()).sum()
     }
 
+    /// Returns the total number of blobs carried by the transactions in this request.
+    pub fn blob_count(&self) -> usize {
+        self.txs
+            .iter()
+            .filter_map(|tx| tx.as_eip4844())
+            .map(|tx| tx.blob_versioned_hashes.len())
+            .sum()
+    }
+
     /// Returns the transaction signer.
     pub fn signer(&self) -> Option<Address> {
         self.signer
@@ -227,22 +511,233 @@ fn serialize_sig<S: serde::Serializer>(sig: &Signature, serializer: S) -> Result
 }
 
 impl InclusionRequest {
-    /// Returns the digest of the request.
-    /// digest = keccak256(bytes(tx_hash1) | bytes(tx_hash2) | ... | le_bytes(target_slot))
+    /// Returns the digest of the request, signed by the user.
+    ///
+    /// With [DigestVersion::V0]:
+    /// digest = keccak256(bytes(tx_hash1) | bytes(tx_hash2) | ... | le_bytes(target_slot)
+    ///     [| 0x01 if top] [| bytes(reverting_tx_hash1) | ...] [| le_bytes(max_slot)]
+    ///     [| utf8(webhook)])
+    ///
+    /// With [DigestVersion::V1], the same fields are prefixed with the version byte `0x01`.
+    ///
+    /// With [DigestVersion::V2]:
+    /// digest = keccak256(0x02 | le_u32(tx_count) | bytes(tx_hash1) | ... | le_bytes(target_slot)
+    ///     [| 0x01 if top] [| 0x02 | le_u32(reverting_count) | bytes(reverting_tx_hash1) | ...]
+    ///     [| 0x03 | le_bytes(max_slot)] [| 0x04 | le_u32(webhook_len) | utf8(webhook)])
     pub fn digest(&self) -> B256 {
+        let tx_hashes = self.txs.iter().map(|tx| *tx.hash()).collect::<Vec<_>>();
+        self.digest_with_tx_hashes(&tx_hashes)
+    }
+
+    /// Returns the digest of the request with the given transaction hashes in place of the
+    /// ones of its transactions. Reservations made by hash use it to share the digest of the
+    /// request completed with the transaction body.
+    fn digest_with_tx_hashes(&self, tx_hashes: &[TxHash]) -> B256 {
+        let data = match self.digest_version {
+            DigestVersion::V0 | DigestVersion::V1 => self.concatenated_preimage(tx_hashes),
+            DigestVersion::V2 => self.tagged_preimage(tx_hashes),
+        };
+
+        keccak256(&data)
+    }
+
+    /// Returns the preimage of the digest of [DigestVersion::V0] and [DigestVersion::V1], the
+    /// plain concatenation of the fields.
+    fn concatenated_preimage(&self, tx_hashes: &[TxHash]) -> Vec<u8> {
         let mut data = Vec::new();
+
+        // Versioned schemes start with the version byte, the original one has none
+        if !self.digest_version.is_v0() {
+            data.push(self.digest_version.into());
+        }
+
         // First field is the concatenation of all the transaction hashes
-        data.extend_from_slice(
-            &self.txs.iter().map(|tx| tx.hash().as_slice()).collect::<Vec<_>>().concat(),
-        );
+        for hash in tx_hashes {
+            data.extend_from_slice(hash.as_slice());
+        }
 
         // Second field is the little endian encoding of the target slot
         data.extend_from_slice(&self.slot.to_le_bytes());
 
+        // Top-of-block requests are marked with a trailing byte, so that the digest of plain
+        // inclusion requests doesn't change
+        if self.top {
+            data.push(1);
+        }
+
+        // Followed by the hashes of the transactions allowed to revert, if any
+        for hash in &self.reverting_tx_hashes {
+            data.extend_from_slice(hash.as_slice());
+        }
+
+        // Then the little endian encoding of the last slot of the range, if any
+        if let Some(max_slot) = self.max_slot {
+            data.extend_from_slice(&max_slot.to_le_bytes());
+        }
+
+        // And finally the webhook URL, if any, so that it can't be swapped by a third party
+        if let Some(webhook) = &self.webhook {
+            data.extend_from_slice(webhook.as_str().as_bytes());
+        }
+
+        data
+    }
+
+    /// Returns the preimage of the digest of [DigestVersion::V2], where the variable-length
+    /// fields are prefixed by their length and the optional fields by a tag.
+    fn tagged_preimage(&self, tx_hashes: &[TxHash]) -> Vec<u8> {
+        let mut data = vec![self.digest_version.into()];
+
+        data.extend_from_slice(&(tx_hashes.len() as u32).to_le_bytes());
+        for hash in tx_hashes {
+            data.extend_from_slice(hash.as_slice());
+        }
+        data.extend_from_slice(&self.slot.to_le_bytes());
+
+        if self.top {
+            data.push(DIGEST_TAG_TOP);
+        }
+
+        if !self.reverting_tx_hashes.is_empty() {
+            data.push(DIGEST_TAG_REVERTING_TX_HASHES);
+            data.extend_from_slice(&(self.reverting_tx_hashes.len() as u32).to_le_bytes());
+            for hash in &self.reverting_tx_hashes {
+                data.extend_from_slice(hash.as_slice());
+            }
+        }
+
+        if let Some(max_slot) = self.max_slot {
+            data.push(DIGEST_TAG_MAX_SLOT);
+            data.extend_from_slice(&max_slot.to_le_bytes());
+        }
+
+        if let Some(webhook) = &self.webhook {
+            let webhook = webhook.as_str().as_bytes();
+            data.push(DIGEST_TAG_WEBHOOK);
+            data.extend_from_slice(&(webhook.len() as u32).to_le_bytes());
+            data.extend_from_slice(webhook);
+        }
+
+        data
+    }
+
+    /// Recovers the address of the user that signed the request with the given signature,
+    /// according to the digest scheme of the request version.
+    pub fn recover(&self, signature: &Signature) -> Result<Address, SignatureError> {
+        signature.recover_address_from_prehash(&self.digest()).map_err(|_| SignatureError)
+    }
+}
+
+/// Request to reserve the inclusion of a transaction at a specific slot, committing only
+/// to its hash. The body of the transaction is submitted later with a [SubmitBodyRequest].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReserveInclusionRequest {
+    /// The consensus slot number at which the transaction should be included.
+    pub slot: u64,
+    /// The hash of the transaction to be included.
+    pub tx_hash: TxHash,
+    /// The gas limit of the transaction, reserved until the body is submitted.
+    pub gas_limit: u64,
+}
+
+impl ReserveInclusionRequest {
+    /// Returns the digest of the request, which is the same as the digest of an
+    /// [InclusionRequest] for the same slot and transaction. This way the signature
+    /// over the reservation is also valid for the final inclusion request.
+    pub fn digest(&self) -> B256 {
+        let request = InclusionRequest {
+            slot: self.slot,
+            txs: Vec::new(),
+            top: false,
+            reverting_tx_hashes: Vec::new(),
+            max_slot: None,
+            webhook: None,
+            digest_version: DigestVersion::V0,
+            signature: None,
+            signer: None,
+        };
+
+        request.digest_with_tx_hashes(&[self.tx_hash])
+    }
+}
+
+/// The response to a [ReserveInclusionRequest].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct InclusionReservation {
+    /// The identifier of the reservation, to be used when submitting the body.
+    pub reservation_id: B256,
+    /// The number of milliseconds the reservation is held for.
+    pub expires_in_ms: u64,
+}
+
+/// Request to submit the body of a transaction previously reserved with a
+/// [ReserveInclusionRequest].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmitBodyRequest {
+    /// The identifier of the reservation, returned when reserving the inclusion.
+    pub reservation_id: B256,
+    /// The EIP-2718 encoded signed transaction.
+    pub tx: Bytes,
+}
+
+/// Request to open a session. Once opened, inclusion requests referencing the session are
+/// authorized on behalf of its signer until it expires, without being signed one by one.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenSessionRequest {
+    /// The nonce of the session, which must be higher than the one of any previous session
+    /// of the signer so that the authorization can't be replayed.
+    pub nonce: u64,
+    /// The UNIX timestamp in seconds at which the session was authorized. The nonces are kept
+    /// in memory, so sessions authorized before the sidecar started are rejected.
+    pub issued_at: u64,
+    /// The UNIX timestamp in seconds at which the session expires.
+    pub expires_at: u64,
+}
+
+impl OpenSessionRequest {
+    /// Returns the digest of the request, for a session with the sidecar of the given domain.
+    /// digest = keccak256(bytes("bolt_openSession") | le_bytes(chain_id) | bytes(sidecar) |
+    ///                    le_bytes(nonce) | le_bytes(issued_at) | le_bytes(expires_at))
+    ///
+    /// The method name prefix keeps a session authorization from being mistaken for the
+    /// signature of any other request, and the domain from being replayed against other
+    /// sidecars.
+    pub fn digest(&self, domain: &SessionDomain) -> B256 {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"bolt_openSession");
+        data.extend_from_slice(&domain.chain_id.to_le_bytes());
+        data.extend_from_slice(domain.sidecar.as_slice());
+        data.extend_from_slice(&self.nonce.to_le_bytes());
+        data.extend_from_slice(&self.issued_at.to_le_bytes());
+        data.extend_from_slice(&self.expires_at.to_le_bytes());
+
         keccak256(&data)
     }
 }
 
+/// The sidecar a session is opened with, signed as part of the [OpenSessionRequest] digest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionDomain {
+    /// The chain ID of the sidecar.
+    pub chain_id: u64,
+    /// The address of the commitment signer of the sidecar.
+    pub sidecar: Address,
+}
+
+/// The response to an [OpenSessionRequest].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionInfo {
+    /// The identifier of the session, to be referenced by the requests made within it.
+    pub session_id: B256,
+    /// The UNIX timestamp in seconds at which the session expires.
+    pub expires_at: u64,
+}
+
 impl From<InclusionRequest> for CommitmentRequest {
     fn from(req: InclusionRequest) -> Self {
         Self::Inclusion(req)
@@ -279,10 +774,14 @@ mod tests {
 
     use alloy::{
         hex,
-        primitives::{Address, Signature},
+        primitives::{Address, Signature, B256},
+        signers::{local::PrivateKeySigner, Signer},
     };
+    use reqwest::Url;
 
-    use super::{CommitmentRequest, InclusionRequest};
+    use super::{
+        CommitmentRequest, DigestVersion, ECDSASignatureExt, InclusionCommitment, InclusionRequest,
+    };
 
     #[test]
     fn test_create_digest() {
@@ -307,6 +806,92 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_digest_versions_are_frozen() {
+        let tx = "0x02f86c870c72dd9d5e883e4d0183408f2382520894d2e2adf7177b7a8afddbc12d1634cf23ea1a71020180c001a08556dcfea479b34675db3fe08e29486fe719c2b22f6b0c1741ecbbdce4575cc6a01cd48009ccafd6b9f1290bbe2ceea268f94101d1d322c787018423ebcbc87ab4";
+        let json_req = format!(
+            r#"{{"slot": 10, "txs": ["{tx}"], "top": true, "maxSlot": 12,
+                "webhook": "https://example.com/notify"}}"#
+        );
+
+        // The digests of a known request must never change, or existing signatures would
+        // break: a new scheme needs a new version
+        let v0 = serde_json::from_str::<InclusionRequest>(&json_req).unwrap();
+        assert_eq!(v0.digest_version, DigestVersion::V0);
+        assert_eq!(
+            hex::encode(v0.digest()),
+            "3a9072dcfdae74c4f9018d396255a98995454ac28493c71a73d2d61079b42d2f"
+        );
+
+        let mut v1 = v0.clone();
+        v1.digest_version = DigestVersion::V1;
+        assert_eq!(
+            hex::encode(v1.digest()),
+            "a4ed62daa0903052ef8b5ae13bb169f4a568495b20bb3ad63c206f6ad143f0ae"
+        );
+
+        let mut v2 = v0.clone();
+        v2.digest_version = DigestVersion::V2;
+        assert_eq!(
+            hex::encode(v2.digest()),
+            "95bde9156812bceaebb842a8964e7e5a194c835699c50d1af0c5a126437a4800"
+        );
+
+        // The concatenated schemes are ambiguous across the optional fields, the tagged one
+        // isn't: the same bytes read as a range and a webhook, or as a reverting hash
+        let mut ranged = v0.clone();
+        ranged.top = false;
+        ranged.max_slot = Some(12);
+        ranged.webhook = Some(Url::parse("https://example.com/abcd").unwrap());
+
+        let mut hash = [0; 32];
+        hash[..8].copy_from_slice(&12u64.to_le_bytes());
+        hash[8..].copy_from_slice(b"https://example.com/abcd");
+        let mut reverting = ranged.clone();
+        reverting.max_slot = None;
+        reverting.webhook = None;
+        reverting.reverting_tx_hashes = vec![B256::from(hash)];
+        assert_eq!(ranged.digest(), reverting.digest());
+
+        ranged.digest_version = DigestVersion::V2;
+        reverting.digest_version = DigestVersion::V2;
+        assert_ne!(ranged.digest(), reverting.digest());
+
+        // The version is part of the request, and unsupported versions are rejected
+        let ser = serde_json::to_value(&v1).unwrap();
+        assert_eq!(ser["digestVersion"], 1);
+        assert_eq!(serde_json::from_value::<InclusionRequest>(ser.clone()).unwrap(), v1);
+        assert!(serde_json::to_value(&v0).unwrap().get("digestVersion").is_none());
+
+        let mut unsupported = ser;
+        unsupported["digestVersion"] = 3.into();
+        assert!(serde_json::from_value::<InclusionRequest>(unsupported).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_recover_with_digest_version() {
+        let json_req = r#"{
+            "slot": 633067,
+            "txs": ["0xf86b82016e84042343e0830f424094deaddeaddeaddeaddeaddeaddeaddeaddeaddead0780850344281a21a0e525fc31b5574722ff064bdd127c4441b0fc66de7dc44928e163cb68e9d807e5a00b3ec02fc1e34b0209f252369ad10b745cd5a51c88384a340f7a150d0e45e471"]
+        }"#;
+        let v0: InclusionRequest = serde_json::from_str(json_req).unwrap();
+
+        // Signatures of old clients are still recovered with the original scheme
+        let sig = Signature::from_str("0xcdd20b2abbd8cdfb77ec2608e1227f8ce0f66133b9d0ec0ea68102c2152b82193e3be0d6967b7c20b83e1a2530daa3a07713556541dc2aa16a46d922e6145a2b01").unwrap();
+        let signer = Address::from_str("0x27083ED52464625660f3e30Aa5B9C20A30D7E110").unwrap();
+        assert_eq!(v0.recover(&sig).unwrap(), signer);
+
+        // The same signature doesn't recover the signer of a versioned request
+        let mut v1 = v0.clone();
+        v1.digest_version = DigestVersion::V1;
+        assert_ne!(v1.recover(&sig).ok(), Some(signer));
+
+        let user = PrivateKeySigner::random();
+        let sig = user.sign_hash(&v1.digest()).await.unwrap();
+        let sig = Signature::try_from(sig.as_bytes().as_ref()).unwrap();
+        assert_eq!(v1.recover(&sig).unwrap(), user.address());
+    }
+
     #[test]
     fn test_deserialize_inclusion_request() {
         let json_req = r#"{
@@ -325,6 +910,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_deserialize_inclusion_request_with_reverting_txs() {
+        let tx = "0x02f86c870c72dd9d5e883e4d0183408f2382520894d2e2adf7177b7a8afddbc12d1634cf23ea1a71020180c001a08556dcfea479b34675db3fe08e29486fe719c2b22f6b0c1741ecbbdce4575cc6a01cd48009ccafd6b9f1290bbe2ceea268f94101d1d322c787018423ebcbc87ab4";
+        let plain = format!(r#"{{"slot": 10, "txs": ["{tx}"]}}"#);
+        let plain = serde_json::from_str::<InclusionRequest>(&plain).unwrap();
+        let tx_hash = *plain.txs[0].hash();
+        assert!(!plain.allows_revert(&tx_hash));
+
+        let json_req =
+            format!(r#"{{"slot": 10, "txs": ["{tx}"], "revertingTxHashes": ["{tx_hash}"]}}"#);
+        let req = serde_json::from_str::<InclusionRequest>(&json_req).unwrap();
+        assert!(req.allows_revert(&tx_hash));
+        assert!(req.unknown_reverting_tx_hash().is_none());
+
+        // The allowed reverts are signed over by the user
+        assert_ne!(req.digest(), plain.digest());
+
+        let ser = serde_json::to_value(&req).unwrap();
+        assert_eq!(ser, json_req.parse::<serde_json::Value>().unwrap());
+    }
+
+    #[test]
+    fn test_deserialize_large_bundle_bails_early() {
+        let tx = "0x02f86c870c72dd9d5e883e4d0183408f2382520894d2e2adf7177b7a8afddbc12d1634cf23ea1a71020180c001a08556dcfea479b34675db3fe08e29486fe719c2b22f6b0c1741ecbbdce4575cc6a01cd48009ccafd6b9f1290bbe2ceea268f94101d1d322c787018423ebcbc87ab4";
+
+        // The tail of the bundle is huge and not even valid JSON: it must never be reached
+        let tail = format!("\"{}", "ff".repeat(1_000_000));
+
+        // Undecodable transaction
+        let json_req = format!(r#"{{"slot": 10, "txs": ["{tx}", "0xdeadbeef", {tail}"#);
+        let err = serde_json::from_str::<InclusionRequest>(&json_req).unwrap_err();
+        assert!(!err.is_eof(), "unexpected error: {err}");
+
+        // Deserialization is structural: the senders are only recovered by the validation
+        let json_req = format!(r#"{{"slot": 10, "txs": ["{tx}", "{tx}"]}}"#);
+        let req = serde_json::from_str::<InclusionRequest>(&json_req).unwrap();
+        assert!(req.txs.iter().all(|tx| tx.sender().is_none()));
+    }
+
     #[test]
     fn test_deserialize_commitment_request() {
         let json_req = r#"{
@@ -341,4 +965,53 @@ mod tests {
             panic!("Expected Inclusion request");
         }
     }
+
+    #[tokio::test]
+    async fn test_inclusion_commitment_roundtrip() {
+        let json_req = r#"{
+            "slot": 10,
+            "txs": ["0x02f86c870c72dd9d5e883e4d0183408f2382520894d2e2adf7177b7a8afddbc12d1634cf23ea1a71020180c001a08556dcfea479b34675db3fe08e29486fe719c2b22f6b0c1741ecbbdce4575cc6a01cd48009ccafd6b9f1290bbe2ceea268f94101d1d322c787018423ebcbc87ab4"]
+        }"#;
+
+        let req: InclusionRequest = serde_json::from_str(json_req).unwrap();
+        let signer = PrivateKeySigner::random();
+        let commitment = req.clone().commit_and_sign(&signer).await.unwrap();
+
+        assert_eq!(commitment.slot(), 10);
+        assert_eq!(commitment.tx_hashes(), vec![*req.txs[0].hash()]);
+        assert_eq!(commitment.digest(), req.digest());
+        assert_eq!(commitment.signer().unwrap(), signer.address());
+
+        // The JSON shape is unchanged: the request fields and the sidecar signature
+        let json = serde_json::to_value(&commitment).unwrap();
+        let mut expected = json_req.parse::<serde_json::Value>().unwrap();
+        expected["signature"] = commitment.signature().to_hex().into();
+        assert_eq!(json, expected);
+
+        let roundtrip: InclusionCommitment = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&roundtrip).unwrap(), json);
+        assert_eq!(roundtrip.signer().unwrap(), signer.address());
+
+        // A ranged request committed to a later slot keeps the digest signed by the user
+        let mut req = req;
+        req.max_slot = Some(12);
+        let commitment = req.clone().commit_and_sign(&signer).await.unwrap().with_target_slot(11);
+
+        assert_eq!(commitment.slot(), 11);
+        assert_eq!(commitment.request().slot, 10);
+        assert_eq!(commitment.digest(), req.digest());
+        assert_eq!(commitment.signer().unwrap(), signer.address());
+
+        let json = serde_json::to_value(&commitment).unwrap();
+        assert_eq!(json["targetSlot"], 11);
+        let roundtrip: InclusionCommitment = serde_json::from_value(json).unwrap();
+        assert_eq!(roundtrip.slot(), 11);
+
+        // A degraded commitment is flagged in the response, and still verifies
+        let json = serde_json::to_value(commitment.with_degraded(true)).unwrap();
+        assert_eq!(json["degraded"], true);
+        let roundtrip: InclusionCommitment = serde_json::from_value(json).unwrap();
+        assert!(roundtrip.is_degraded());
+        assert_eq!(roundtrip.signer().unwrap(), signer.address());
+    }
 }
@@ -1,8 +1,23 @@
-use alloy::signers::k256::sha2::{Digest, Sha256};
-use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
+use alloy::{
+    eips::eip2718::Encodable2718,
+    primitives::B256,
+    signers::k256::sha2::{Digest, Sha256},
+};
+use ethereum_consensus::{
+    bellatrix::mainnet::Transaction,
+    crypto::{PublicKey as BlsPublicKey, Signature as BlsSignature},
+    deneb::compute_signing_root,
+    ssz::prelude::{ssz_rs, List, SimpleSerialize},
+};
 use serde::{Deserialize, Serialize};
 
-use crate::crypto::{bls::BLSSig, SignableBLS};
+use crate::{
+    config::ChainConfig,
+    crypto::{
+        bls::{verify_signing_root, BLSSig, BlsVerificationError},
+        SignableBLS,
+    },
+};
 
 use super::{deserialize_txs, serialize_txs, FullTransaction, InclusionRequest};
 
@@ -10,10 +25,110 @@ use super::{deserialize_txs, serialize_txs, FullTransaction, InclusionRequest};
 /// that need to be forwarded to the PBS pipeline to inform block production.
 pub type BatchedSignedConstraints = Vec<SignedConstraints>;
 
+/// The maximum number of transactions in a single [ConstraintsMessage], as per the
+/// Constraints API SSZ schema.
+pub const MAX_CONSTRAINTS_PER_SLOT: usize = 128;
+
+/// Errors that can occur when SSZ-encoding constraints.
+#[derive(Debug, thiserror::Error)]
+pub enum ConstraintsSszError {
+    /// The message has more transactions than the schema allows.
+    #[error("Too many transactions in constraints message: {0} > {MAX_CONSTRAINTS_PER_SLOT}")]
+    TooManyTransactions(usize),
+    /// The batch has more signed constraints than the schema allows.
+    #[error("Too many signed constraints in batch: {0} > {MAX_CONSTRAINTS_PER_SLOT}")]
+    TooManyConstraints(usize),
+    /// A field couldn't be converted to its SSZ representation.
+    #[error("SSZ encoding error: {0}")]
+    Encoding(String),
+}
+
+/// Encodes a batch of signed constraints as an SSZ `List[SignedConstraints]`, following the
+/// schema of the Constraints API.
+///
+/// Reference: https://chainbound.github.io/bolt-docs/api/builder#constraints
+pub fn batch_to_ssz_bytes(batch: &[SignedConstraints]) -> Result<Vec<u8>, ConstraintsSszError> {
+    if batch.len() > MAX_CONSTRAINTS_PER_SLOT {
+        return Err(ConstraintsSszError::TooManyConstraints(batch.len()));
+    }
+
+    let mut list = List::<SignedConstraintsSsz, MAX_CONSTRAINTS_PER_SLOT>::default();
+    for signed_constraints in batch {
+        list.push(SignedConstraintsSsz::try_from(signed_constraints)?);
+    }
+
+    ssz_rs::serialize(&list).map_err(|e| ConstraintsSszError::Encoding(e.to_string()))
+}
+
+/// SSZ representation of [SignedConstraints].
+#[derive(Debug, Default, Clone, PartialEq, Eq, SimpleSerialize)]
+struct SignedConstraintsSsz {
+    message: ConstraintsMessageSsz,
+    signature: BlsSignature,
+}
+
+/// SSZ representation of [ConstraintsMessage]. Transactions are opaque byte lists holding
+/// their EIP-2718 encoding, as in the JSON representation.
+#[derive(Debug, Default, Clone, PartialEq, Eq, SimpleSerialize)]
+struct ConstraintsMessageSsz {
+    pubkey: BlsPublicKey,
+    slot: u64,
+    top: bool,
+    transactions: List<Transaction, MAX_CONSTRAINTS_PER_SLOT>,
+}
+
+impl TryFrom<&SignedConstraints> for SignedConstraintsSsz {
+    type Error = ConstraintsSszError;
+
+    fn try_from(value: &SignedConstraints) -> Result<Self, Self::Error> {
+        let message = &value.message;
+        if message.transactions.len() > MAX_CONSTRAINTS_PER_SLOT {
+            return Err(ConstraintsSszError::TooManyTransactions(message.transactions.len()));
+        }
+
+        let mut transactions = List::default();
+        for tx in &message.transactions {
+            let tx = Transaction::try_from(tx.tx.encoded_2718().as_ref())
+                .map_err(|e| ConstraintsSszError::Encoding(e.to_string()))?;
+            transactions.push(tx);
+        }
+
+        let signature = BlsSignature::try_from(value.signature.as_ref())
+            .map_err(|e| ConstraintsSszError::Encoding(e.to_string()))?;
+
+        Ok(Self {
+            message: ConstraintsMessageSsz {
+                pubkey: message.pubkey.clone(),
+                slot: message.slot,
+                top: message.top,
+                transactions,
+            },
+            signature,
+        })
+    }
+}
+
+/// Errors that can occur when verifying the signature of [SignedConstraints].
+#[derive(Debug, thiserror::Error)]
+pub enum ConstraintsVerificationError {
+    /// The constraints were not signed by the expected sidecar.
+    #[error("Constraints public key {0:?} doesn't match the sidecar public key")]
+    PubkeyMismatch(BlsPublicKey),
+    /// The sidecar public key is not a valid BLS public key.
+    #[error("Invalid sidecar public key")]
+    InvalidPubkey,
+    /// The constraints signature is not valid.
+    #[error("Invalid constraints signature")]
+    InvalidSignature,
+}
+
 /// A container for a list of constraints and the signature of the proposer sidecar.
 ///
+/// The JSON representation must match the one expected by the relays: snake_case field
+/// names, numeric slot and `0x`-prefixed lowercase hex bytes. Relays reject anything else.
+///
 /// Reference: https://chainbound.github.io/bolt-docs/api/builder#constraints
-#[derive(Serialize, Default, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
 pub struct SignedConstraints {
     /// The constraints that need to be signed.
     pub message: ConstraintsMessage,
@@ -21,6 +136,34 @@ pub struct SignedConstraints {
     pub signature: BLSSig,
 }
 
+impl SignedConstraints {
+    /// Verify that the constraints are signed by the given sidecar public key, over the
+    /// [ConstraintsMessage::signing_root] on the given chain.
+    pub fn verify(
+        &self,
+        pubkey: &BlsPublicKey,
+        chain: &ChainConfig,
+    ) -> Result<(), ConstraintsVerificationError> {
+        if self.message.pubkey != *pubkey {
+            return Err(ConstraintsVerificationError::PubkeyMismatch(self.message.pubkey.clone()));
+        }
+
+        let signing_root = self.message.signing_root(chain);
+        verify_signing_root(
+            signing_root.as_slice(),
+            self.signature.as_ref(),
+            pubkey,
+            chain.bls_dst(),
+        )
+        .map_err(|err| match err {
+            BlsVerificationError::InvalidPubkey(_) => ConstraintsVerificationError::InvalidPubkey,
+            BlsVerificationError::InvalidSignature(_) => {
+                ConstraintsVerificationError::InvalidSignature
+            }
+        })
+    }
+}
+
 /// A message that contains the constraints that need to be signed by the proposer sidecar.
 ///
 /// Reference: https://chainbound.github.io/bolt-docs/api/builder#constraints
@@ -43,13 +186,22 @@ impl ConstraintsMessage {
     pub fn build(pubkey: BlsPublicKey, request: InclusionRequest) -> Self {
         let transactions = request.txs;
 
-        Self { pubkey, slot: request.slot, top: false, transactions }
+        Self { pubkey, slot: request.slot, top: request.top, transactions }
     }
 
     /// Builds a constraints message from a single transaction.
     pub fn from_tx(pubkey: BlsPublicKey, slot: u64, tx: FullTransaction) -> Self {
         Self { pubkey, slot, top: false, transactions: vec![tx] }
     }
+
+    /// Returns the signing root of the message on the given chain, i.e. the exact bytes
+    /// the sidecar signs with the Commit Boost domain. Third-party verifiers can use it
+    /// to check the signature of [SignedConstraints].
+    pub fn signing_root(&self, chain: &ChainConfig) -> B256 {
+        let root = compute_signing_root(&self.digest(), chain.commit_boost_domain())
+            .expect("signing data of fixed size can be merkleized");
+        B256::from_slice(root.as_ref())
+    }
 }
 
 impl SignableBLS for ConstraintsMessage {
@@ -69,10 +221,10 @@ impl SignableBLS for ConstraintsMessage {
 
 #[cfg(test)]
 mod tests {
-    use crate::signer::local::LocalSigner;
+    use crate::signer::local::{LocalSigner, BLS_DST_PREFIX};
 
     use super::*;
-    use alloy::primitives::bytes;
+    use alloy::primitives::{b256, bytes};
     use blst::min_pk::Signature as BlsSignature;
     use rand::{rngs::ThreadRng, Rng};
 
@@ -150,4 +302,131 @@ mod tests {
         let blst_sig = BlsSignature::from_bytes(signed_constraints.signature.as_ref()).unwrap();
         assert!(signer.verify_commit_boost_root(digest, &blst_sig).is_ok());
     }
+
+    #[test]
+    fn test_constraints_signing_root() {
+        let signer = LocalSigner::random();
+        let chain = ChainConfig::mainnet();
+
+        let message =
+            ConstraintsMessage::from_tx(signer.pubkey(), 42, random_constraints(1)[0].clone());
+        let signing_root = message.signing_root(&chain);
+
+        // The signature produced by the sidecar signer is over the signing root
+        let signature = signer.sign_commit_boost_root(message.digest()).unwrap();
+        let blst_sig = BlsSignature::from_bytes(signature.as_ref()).unwrap();
+        let pubkey = blst::min_pk::PublicKey::from_bytes(signer.pubkey().as_ref()).unwrap();
+        let res =
+            blst_sig.verify(true, signing_root.as_slice(), BLS_DST_PREFIX, &[], &pubkey, true);
+        assert_eq!(res, blst::BLST_ERROR::BLST_SUCCESS);
+
+        // Fixed vector, shared with the `bolt signing-root` command of the CLI
+        let pubkey = bytes!("83eeddfac5e60f8fe607ee8713efb8877c295ad9f8ca075f4d8f6f2ae241a30dd57f78f6f3863a9fe0d5b5db9d550b93");
+        let pubkey = BlsPublicKey::try_from(pubkey.as_ref()).unwrap();
+        let message = ConstraintsMessage::from_tx(pubkey, 42, random_constraints(1)[0].clone());
+        assert_eq!(
+            message.signing_root(&chain),
+            b256!("6eff58a4b1052a84feccdbe6efa11a48df1e57b5e7f9e754c0d32b67b9e39c2f")
+        );
+    }
+
+    #[test]
+    fn test_verify_signed_constraints() {
+        let signer = LocalSigner::random();
+        let chain = ChainConfig::mainnet();
+
+        let message =
+            ConstraintsMessage::from_tx(signer.pubkey(), 42, random_constraints(1)[0].clone());
+        let signature = signer.sign_commit_boost_root(message.digest()).unwrap();
+        let signed_constraints = SignedConstraints { message, signature };
+
+        assert!(signed_constraints.verify(&signer.pubkey(), &chain).is_ok());
+
+        // Tampering with the message invalidates the signature
+        let mut tampered = signed_constraints.clone();
+        tampered.message.slot = 43;
+        assert!(matches!(
+            tampered.verify(&signer.pubkey(), &chain),
+            Err(ConstraintsVerificationError::InvalidSignature)
+        ));
+
+        // Constraints signed by another sidecar are rejected
+        let other = LocalSigner::random();
+        assert!(matches!(
+            signed_constraints.verify(&other.pubkey(), &chain),
+            Err(ConstraintsVerificationError::PubkeyMismatch(_))
+        ));
+    }
+
+    /// The batch of signed constraints of the test fixtures.
+    fn fixture_batch() -> Vec<SignedConstraints> {
+        let tx_1 = bytes!("02f86c870c72dd9d5e883e4d0183408f2382520894d2e2adf7177b7a8afddbc12d1634cf23ea1a71020180c001a08556dcfea479b34675db3fe08e29486fe719c2b22f6b0c1741ecbbdce4575cc6a01cd48009ccafd6b9f1290bbe2ceea268f94101d1d322c787018423ebcbc87ab4");
+        let tx_2 = bytes!("f8678085019dc6838082520894deaddeaddeaddeaddeaddeaddeaddeaddeaddead38808360306ca06664c078fa60bd3ece050903dd295949908dd9686ec8871fa558f868e031cd39a00ed4f0b122b32b73f19230fabe6a726e2d07f84eda5beaa42a1ae1271bdee39f");
+
+        let message = ConstraintsMessage {
+            pubkey: BlsPublicKey::default(),
+            slot: 10,
+            top: true,
+            transactions: vec![
+                FullTransaction::decode_enveloped(tx_1.as_ref()).unwrap(),
+                FullTransaction::decode_enveloped(tx_2.as_ref()).unwrap(),
+            ],
+        };
+        vec![SignedConstraints { message, signature: BLSSig::repeat_byte(0xab) }]
+    }
+
+    #[test]
+    fn test_constraints_ssz_layout() {
+        let batch = fixture_batch();
+        let signed_constraints = &batch[0];
+        let message = &signed_constraints.message;
+        let txs = message.transactions.iter().map(|tx| tx.tx.encoded_2718()).collect::<Vec<_>>();
+
+        // Lay out the encoding by hand from the SSZ schema of the Constraints API, where
+        // variable-size fields are replaced by 4-byte offsets in the fixed part of containers
+        // and lists, independently of the containers used by the encoder.
+        let offset = |n: usize| (n as u32).to_le_bytes();
+
+        // ConstraintsMessage: pubkey (48) | slot (8) | top (1) | transactions offset (4)
+        let mut expected_message = message.pubkey.as_ref().to_vec();
+        expected_message.extend_from_slice(&message.slot.to_le_bytes());
+        expected_message.push(message.top as u8);
+        expected_message.extend_from_slice(&offset(48 + 8 + 1 + 4));
+
+        // List[Transaction]: an offset per transaction, then the transactions
+        let mut tx_offset = 4 * txs.len();
+        for tx in &txs {
+            expected_message.extend_from_slice(&offset(tx_offset));
+            tx_offset += tx.len();
+        }
+        for tx in &txs {
+            expected_message.extend_from_slice(tx);
+        }
+
+        // List[SignedConstraints] with a single element: its offset, then the element with
+        // the message offset (4) | signature (96) | message
+        let mut expected = offset(4).to_vec();
+        expected.extend_from_slice(&offset(4 + 96));
+        expected.extend_from_slice(signed_constraints.signature.as_ref());
+        expected.extend_from_slice(&expected_message);
+
+        assert_eq!(batch_to_ssz_bytes(&batch).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_constraints_json_fixture() {
+        // Signed constraints with a blob transaction, as received by the bolt builder on a
+        // devnet (shared with the bolt-boost proofs tests)
+        let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/signed_constraints_with_blob.json");
+        let fixture = std::fs::read_to_string(path).unwrap();
+
+        let batch = serde_json::from_str::<Vec<SignedConstraints>>(&fixture).unwrap();
+        assert_eq!(batch[0].message.slot, 496);
+        assert_eq!(batch[0].message.transactions.len(), 1);
+
+        // Serializing the batch again gives back the same field names, numbers and hex strings
+        let fixture = serde_json::from_str::<serde_json::Value>(&fixture).unwrap();
+        assert_eq!(serde_json::to_value(&batch).unwrap(), fixture);
+    }
 }
@@ -1,10 +1,21 @@
 use std::{fs, ops::Deref, path::PathBuf};
 
-use alloy::signers::k256::sha2::{Digest, Sha256};
-use ethereum_consensus::crypto::{PublicKey as BlsPublicKey, Signature as BlsSignature};
+use alloy::{
+    primitives::B256,
+    signers::k256::sha2::{Digest, Sha256},
+};
+use clap::ValueEnum;
+use ethereum_consensus::{
+    crypto::{PublicKey as BlsPublicKey, Signature as BlsSignature},
+    ssz::prelude::{ssz_rs, HashTreeRoot, SimpleSerialize},
+};
 use eyre::bail;
+use serde::{Deserialize, Serialize};
 
-use crate::crypto::SignableBLS;
+use crate::{
+    config::ChainConfig,
+    crypto::{bls::verify_root, SignableBLS},
+};
 
 /// Event types that can be emitted by the validator pubkey to
 /// signal some action on the Bolt protocol.
@@ -17,11 +28,40 @@ pub enum SignedMessageAction {
     Revocation,
 }
 
+/// The encoding of the delegations and revocations posted to the Constraints API.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[clap(rename_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum MessageEncoding {
+    /// A JSON array of signed messages.
+    #[default]
+    Json,
+    /// An SSZ list of signed messages.
+    Ssz,
+}
+
+/// Encodes signed delegations or revocations as an SSZ list, as expected by the relays
+/// accepting SSZ-encoded messages.
+///
+/// The signed messages are fixed-size containers, so the list is the concatenation of
+/// their encodings, without offsets.
+pub fn signed_messages_to_ssz_bytes<T: SimpleSerialize>(
+    messages: &[T],
+) -> Result<Vec<u8>, ssz_rs::SerializeError> {
+    let mut bytes = Vec::new();
+    for message in messages {
+        bytes.extend(ssz_rs::serialize(message)?);
+    }
+    Ok(bytes)
+}
+
 /// A signed delegation message.
 ///
 /// This is a message that is signed by a validator to delegate its
 /// constraint signing power to another key (delegatee).
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+#[derive(
+    Debug, Default, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, SimpleSerialize,
+)]
 pub struct SignedDelegation {
     /// The delegation message.
     pub message: DelegationMessage,
@@ -37,8 +77,145 @@ impl Deref for SignedDelegation {
     }
 }
 
+impl SignedDelegation {
+    /// Verify the signature of the validator over the delegation message, with the Commit
+    /// Boost domain of the given chain.
+    pub fn verify(&self, chain: &ChainConfig) -> bool {
+        let digest = self.message.digest();
+        let (domain, dst) = (chain.commit_boost_domain(), chain.bls_dst());
+        verify_root(digest, domain, self.signature.as_ref(), &self.message.validator_pubkey, dst)
+            .is_ok()
+    }
+}
+
+/// A message delegating the signing power of a consensus key of the commit-boost signer
+/// to a module-specific BLS proxy key.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, SimpleSerialize)]
+pub struct ProxyDelegation {
+    /// The consensus public key delegating its signing power.
+    pub delegator: BlsPublicKey,
+    /// The proxy public key receiving the signing power.
+    pub proxy: BlsPublicKey,
+}
+
+impl SignableBLS for ProxyDelegation {
+    fn digest(&self) -> [u8; 32] {
+        let root = self.hash_tree_root().expect("proxy delegation can be merkleized");
+        B256::from_slice(root.as_ref()).0
+    }
+}
+
+/// A [ProxyDelegation] signed by the delegating consensus key with the Commit Boost domain.
+/// It proves that the signatures of the proxy key are made on behalf of the consensus key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedProxyDelegation {
+    /// The delegation message.
+    pub message: ProxyDelegation,
+    /// The signature of the delegator over the delegation message.
+    pub signature: BlsSignature,
+}
+
+impl SignedProxyDelegation {
+    /// Verify the signature of the delegator over the delegation message.
+    pub fn verify(&self, chain: &ChainConfig) -> bool {
+        let digest = self.message.digest();
+        let (domain, dst) = (chain.commit_boost_domain(), chain.bls_dst());
+        verify_root(digest, domain, self.signature.as_ref(), &self.message.delegator, dst).is_ok()
+    }
+}
+
+/// A link of a delegation chain, from a key to the key receiving its signing power.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DelegationLink {
+    /// A Bolt delegation, e.g. from a validator key to a sidecar key.
+    Delegation(SignedDelegation),
+    /// A commit-boost proxy delegation, e.g. from a sidecar key to its proxy key.
+    Proxy(SignedProxyDelegation),
+}
+
+impl DelegationLink {
+    /// Returns the key delegating its signing power.
+    pub fn delegator(&self) -> &BlsPublicKey {
+        match self {
+            Self::Delegation(delegation) => &delegation.message.validator_pubkey,
+            Self::Proxy(delegation) => &delegation.message.delegator,
+        }
+    }
+
+    /// Returns the key receiving the signing power.
+    pub fn delegatee(&self) -> &BlsPublicKey {
+        match self {
+            Self::Delegation(delegation) => &delegation.message.delegatee_pubkey,
+            Self::Proxy(delegation) => &delegation.message.proxy,
+        }
+    }
+
+    /// Verify the signature of the delegator over the link.
+    pub fn verify(&self, chain: &ChainConfig) -> bool {
+        match self {
+            Self::Delegation(delegation) => delegation.verify(chain),
+            Self::Proxy(delegation) => delegation.verify(chain),
+        }
+    }
+}
+
+/// Errors that can occur when verifying a delegation chain.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DelegationChainError {
+    /// The chain has no links.
+    #[error("Empty delegation chain")]
+    Empty,
+    /// A link is not delegated by the delegatee of the previous link.
+    #[error("Link {index} is delegated by {delegator:?} instead of {expected:?}")]
+    Disconnected {
+        /// The index of the link in the chain.
+        index: usize,
+        /// The delegatee of the previous link.
+        expected: BlsPublicKey,
+        /// The delegator of the link.
+        delegator: BlsPublicKey,
+    },
+    /// The signature of a link is not valid.
+    #[error("Invalid signature of link {0}")]
+    InvalidSignature(usize),
+}
+
+/// Verify a delegation chain, e.g. validator → sidecar → proxy, in which each link is
+/// delegated by the delegatee of the previous one. The chain is walked from the first link,
+/// failing on the first broken one.
+///
+/// Returns the delegatee of the last link, which signs on behalf of the first delegator.
+pub fn verify_delegation_chain<'a>(
+    links: &'a [DelegationLink],
+    chain: &ChainConfig,
+) -> Result<&'a BlsPublicKey, DelegationChainError> {
+    let Some(first) = links.first() else {
+        return Err(DelegationChainError::Empty);
+    };
+
+    let mut expected = first.delegator();
+    for (index, link) in links.iter().enumerate() {
+        if link.delegator() != expected {
+            return Err(DelegationChainError::Disconnected {
+                index,
+                expected: expected.clone(),
+                delegator: link.delegator().clone(),
+            });
+        }
+        if !link.verify(chain) {
+            return Err(DelegationChainError::InvalidSignature(index));
+        }
+
+        expected = link.delegatee();
+    }
+
+    Ok(expected)
+}
+
 /// A delegation message.
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+#[derive(
+    Debug, Default, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, SimpleSerialize,
+)]
 pub struct DelegationMessage {
     action: u8,
     /// The validator pubkey that is delegating its power.
@@ -82,7 +259,7 @@ pub fn read_signed_delegations_from_file(
 ///
 /// This is a message that is signed by a validator to revoke its
 /// constraint signing power from another key (delegatee).
-#[derive(Debug, Clone, serde::Serialize, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, serde::Serialize, PartialEq, Eq, SimpleSerialize)]
 pub struct SignedRevocation {
     /// The revocation message.
     pub message: RevocationMessage,
@@ -99,7 +276,7 @@ impl Deref for SignedRevocation {
 }
 
 /// A revocation message.
-#[derive(Debug, Clone, serde::Serialize, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, serde::Serialize, PartialEq, Eq, SimpleSerialize)]
 pub struct RevocationMessage {
     action: u8,
     /// The validator pubkey that is revoking a delegatee's power.
@@ -130,6 +307,89 @@ impl SignableBLS for RevocationMessage {
 mod tests {
     use std::path::PathBuf;
 
+    use ethereum_consensus::crypto::{PublicKey as BlsPublicKey, Signature as BlsSignature};
+
+    use super::{
+        signed_messages_to_ssz_bytes, verify_delegation_chain, DelegationChainError,
+        DelegationLink, DelegationMessage, ProxyDelegation, SignedDelegation,
+        SignedProxyDelegation,
+    };
+    use crate::{
+        common::secrets::BlsSecretKeyWrapper, config::ChainConfig, crypto::SignableBLS,
+        signer::local::LocalSigner,
+    };
+
+    fn random_signer() -> LocalSigner {
+        LocalSigner::new(BlsSecretKeyWrapper::random().0, ChainConfig::mainnet())
+    }
+
+    fn sign(signer: &LocalSigner, message: &impl SignableBLS) -> BlsSignature {
+        let signature = signer.sign_commit_boost_root(message.digest()).unwrap();
+        BlsSignature::try_from(signature.as_ref()).unwrap()
+    }
+
+    /// Returns the links validator → sidecar → proxy, with the given key signing the proxy
+    /// delegation on behalf of the sidecar.
+    fn delegation_chain(
+        validator: &LocalSigner,
+        sidecar: &BlsPublicKey,
+        proxy_delegator: &LocalSigner,
+        proxy: &BlsPublicKey,
+    ) -> Vec<DelegationLink> {
+        let message = DelegationMessage::new(validator.pubkey(), sidecar.clone());
+        let signature = sign(validator, &message);
+        let delegation = SignedDelegation { message, signature };
+
+        let message = ProxyDelegation { delegator: sidecar.clone(), proxy: proxy.clone() };
+        let signature = sign(proxy_delegator, &message);
+        let proxy_delegation = SignedProxyDelegation { message, signature };
+
+        vec![DelegationLink::Delegation(delegation), DelegationLink::Proxy(proxy_delegation)]
+    }
+
+    #[test]
+    fn test_verify_delegation_chain() {
+        let chain = ChainConfig::mainnet();
+        let (validator, sidecar, proxy) = (random_signer(), random_signer(), random_signer());
+
+        let links = delegation_chain(&validator, &sidecar.pubkey(), &sidecar, &proxy.pubkey());
+        assert_eq!(verify_delegation_chain(&links, &chain), Ok(&proxy.pubkey()));
+        assert_eq!(verify_delegation_chain(&[], &chain), Err(DelegationChainError::Empty));
+
+        // The signatures are bound to the chain
+        assert_eq!(
+            verify_delegation_chain(&links, &ChainConfig::holesky()),
+            Err(DelegationChainError::InvalidSignature(0))
+        );
+    }
+
+    #[test]
+    fn test_verify_delegation_chain_broken_link() {
+        let chain = ChainConfig::mainnet();
+        let (validator, sidecar, proxy) = (random_signer(), random_signer(), random_signer());
+
+        // The proxy delegation claims to come from the sidecar, but another key signed it
+        let attacker = random_signer();
+        let links = delegation_chain(&validator, &sidecar.pubkey(), &attacker, &proxy.pubkey());
+        assert_eq!(
+            verify_delegation_chain(&links, &chain),
+            Err(DelegationChainError::InvalidSignature(1))
+        );
+
+        // The proxy delegation comes from a key the validator never delegated to
+        let valid = delegation_chain(&validator, &sidecar.pubkey(), &sidecar, &proxy.pubkey());
+        let forged = delegation_chain(&attacker, &attacker.pubkey(), &attacker, &proxy.pubkey());
+        let links = vec![valid[0].clone(), forged[1].clone()];
+        assert_eq!(
+            verify_delegation_chain(&links, &chain),
+            Err(DelegationChainError::Disconnected {
+                index: 1,
+                expected: sidecar.pubkey(),
+                delegator: attacker.pubkey(),
+            })
+        );
+    }
+
     #[test]
     fn test_read_signed_delegations_from_file() {
         let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -144,4 +404,33 @@ mod tests {
             "0x83b85769a8f2a1a6bd3a609e51b460f6fb897daff1157991479421493926faeffa6670152524403929a8a7e551d345f3"
         );
     }
+
+    #[test]
+    fn test_delegations_encodings() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/delegations.json");
+        let delegations = super::read_signed_delegations_from_file(&path).unwrap();
+
+        // The JSON form round-trips
+        let json = serde_json::to_string(&delegations).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<SignedDelegation>>(&json).unwrap(), delegations);
+
+        // The SSZ form is the concatenation of the fixed-size containers, laid out as in the
+        // Constraints API spec: action (1) || validator pubkey (48) || delegatee pubkey (48)
+        // || signature (96)
+        let expected = delegations
+            .iter()
+            .flat_map(|signed| {
+                [
+                    &[signed.message.action][..],
+                    signed.message.validator_pubkey.as_ref(),
+                    signed.message.delegatee_pubkey.as_ref(),
+                    signed.signature.as_ref(),
+                ]
+                .concat()
+            })
+            .collect::<Vec<u8>>();
+        assert_eq!(expected.len(), delegations.len() * 193);
+        assert_eq!(signed_messages_to_ssz_bytes(&delegations).unwrap(), expected);
+    }
 }
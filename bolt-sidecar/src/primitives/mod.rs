@@ -4,9 +4,11 @@ use ethereum_consensus::{
     deneb::{
         self,
         mainnet::{BlobsBundle, MAX_BLOB_COMMITMENTS_PER_BLOCK},
+        minimal::MAX_BLOB_COMMITMENTS_PER_BLOCK as MINIMAL_MAX_BLOB_COMMITMENTS_PER_BLOCK,
         presets::mainnet::ExecutionPayloadHeader,
         Hash32,
     },
+    primitives::ExecutionAddress,
     serde::as_str,
     ssz::prelude::*,
     types::mainnet::ExecutionPayload,
@@ -14,29 +16,40 @@ use ethereum_consensus::{
 };
 use tokio::sync::oneshot;
 
+use crate::config::chain::Preset;
+
 pub use ethereum_consensus::crypto::{PublicKey as BlsPublicKey, Signature as BlsSignature};
 
 /// Commitment types, received by users wishing to receive preconfirmations.
 pub mod commitment;
-pub use commitment::{CommitmentRequest, InclusionRequest};
+pub use commitment::{
+    CommitmentKind, CommitmentRequest, InclusionRequest, InclusionReservation, OpenSessionRequest,
+    ReserveInclusionRequest, SessionDomain, SessionInfo, SubmitBodyRequest,
+};
 
 /// Constraint types, signed by proposers and sent along the PBS pipeline
 /// for validation.
 pub mod constraint;
-pub use constraint::{BatchedSignedConstraints, ConstraintsMessage, SignedConstraints};
+pub use constraint::{
+    BatchedSignedConstraints, ConstraintsMessage, ConstraintsVerificationError, SignedConstraints,
+};
 
 /// Delegation and revocation signed message types and utilities.
 pub mod delegation;
 pub use delegation::{
-    read_signed_delegations_from_file, DelegationMessage, RevocationMessage, SignedDelegation,
-    SignedRevocation,
+    read_signed_delegations_from_file, signed_messages_to_ssz_bytes, verify_delegation_chain,
+    DelegationChainError, DelegationLink, DelegationMessage, MessageEncoding, ProxyDelegation,
+    RevocationMessage, SignedDelegation, SignedProxyDelegation, SignedRevocation,
 };
 
 /// Transaction types and extension utilities.
 pub mod transaction;
 pub use transaction::{deserialize_txs, serialize_txs, FullTransaction, TransactionExt};
 
-/// An alias for a Beacon Chain slot number
+/// An alias for a Beacon Chain slot number.
+///
+/// Being a plain `u64`, it has the standard conversions, `Display` and `FromStr`
+/// implementations, so it can be parsed from CLI arguments and logged as is.
 pub type Slot = u64;
 
 /// Minimal account state needed for commitment validation.
@@ -48,10 +61,23 @@ pub struct AccountState {
     pub transaction_count: u64,
     /// The balance of the account in wei
     pub balance: U256,
-    /// Flag to indicate if the account is a smart contract or an EOA
+    /// Flag to indicate if the account is a smart contract or an EOA. EOAs delegated with
+    /// EIP-7702 are not considered smart contracts, see [is_contract_code].
     pub has_code: bool,
 }
 
+/// The prefix of an EIP-7702 delegation designator, i.e. the code of a delegated EOA.
+pub const EIP7702_DELEGATION_PREFIX: [u8; 3] = [0xef, 0x01, 0x00];
+
+/// Returns `true` if the given account code belongs to a smart contract.
+///
+/// The code of an EOA is either empty or, if it was delegated with EIP-7702, a delegation
+/// designator: `0xef0100 || address`.
+pub fn is_contract_code(code: &[u8]) -> bool {
+    let is_delegation = code.len() == 23 && code.starts_with(&EIP7702_DELEGATION_PREFIX);
+    !code.is_empty() && !is_delegation
+}
+
 /// Builder bid, object that is signed by the proposer
 #[derive(Debug, Default, Clone, SimpleSerialize, serde::Serialize, serde::Deserialize)]
 #[allow(missing_docs)]
@@ -64,6 +90,56 @@ pub struct BuilderBid {
     pub public_key: BlsPublicKey,
 }
 
+impl BuilderBid {
+    /// Sets the KZG commitments of the blobs carried by the bid payload.
+    ///
+    /// The commitments are part of the hash tree root of the bid, so they must be set
+    /// before signing it: a bid for a block with blobs signed with empty commitments is
+    /// rejected by the relays and the beacon node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than `MAX_BLOB_COMMITMENTS_PER_BLOCK` commitments.
+    pub fn with_blob_commitments(mut self, commitments: Vec<KzgCommitment>) -> Self {
+        self.blob_kzg_commitments =
+            List::try_from(commitments).expect("commitments fit in a block");
+        self
+    }
+
+    /// Returns the hash tree root of the bid with the list limits of the given preset. The
+    /// bid is signed over this root, so it must match the preset of the chain.
+    pub fn hash_tree_root_with_preset(&self, preset: Preset) -> Result<Node, MerkleizationError> {
+        match preset {
+            Preset::Mainnet => self.hash_tree_root(),
+            Preset::Minimal => {
+                let blob_kzg_commitments = List::try_from(self.blob_kzg_commitments.to_vec())
+                    .map_err(|_| {
+                        MerkleizationError::InputExceedsLimit(
+                            MINIMAL_MAX_BLOB_COMMITMENTS_PER_BLOCK,
+                        )
+                    })?;
+
+                let bid = MinimalBuilderBid {
+                    header: self.header.clone(),
+                    blob_kzg_commitments,
+                    value: self.value,
+                    public_key: self.public_key.clone(),
+                };
+                bid.hash_tree_root()
+            }
+        }
+    }
+}
+
+/// A [BuilderBid] with the list limits of the minimal preset, only used to merkleize it.
+#[derive(Debug, Default, SimpleSerialize)]
+struct MinimalBuilderBid {
+    header: ExecutionPayloadHeader,
+    blob_kzg_commitments: List<KzgCommitment, MINIMAL_MAX_BLOB_COMMITMENTS_PER_BLOCK>,
+    value: U256,
+    public_key: BlsPublicKey,
+}
+
 /// Signed builder bid with the proposer signature
 #[derive(Debug, Default, Clone, SimpleSerialize, serde::Serialize, serde::Deserialize)]
 #[allow(missing_docs)]
@@ -112,16 +188,79 @@ pub struct MerkleMultiProof {
 pub struct FetchPayloadRequest {
     /// Slot number for the payload to fetch
     pub slot: u64,
+    /// Whether to remove the payload from the cache, once it's served to the beacon node.
+    /// Otherwise it's only looked up, e.g. to compare its bid to the one of the relay.
+    pub take: bool,
     /// Channel to send the response to
     pub response_tx: oneshot::Sender<Option<PayloadAndBid>>,
 }
 
 /// Response to a fetch payload request
-#[derive(Debug)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[allow(missing_docs)]
 pub struct PayloadAndBid {
     pub bid: SignedBuilderBid,
     pub payload: GetPayloadResponse,
+    /// The estimated value of the payload for the proposer. This differs from the value in the
+    /// bid, which is inflated to make sure the local block is picked by the beacon node.
+    pub value: U256,
+}
+
+/// The trace of a block submitted to a relay, signed by the builder.
+///
+/// Reference: https://flashbots.github.io/relay-specs/#/Builder/submitBlock
+#[derive(
+    Debug, Default, Clone, PartialEq, SimpleSerialize, serde::Serialize, serde::Deserialize,
+)]
+#[allow(missing_docs)]
+pub struct BidTrace {
+    #[serde(with = "as_str")]
+    pub slot: u64,
+    pub parent_hash: Hash32,
+    pub block_hash: Hash32,
+    pub builder_pubkey: BlsPublicKey,
+    pub proposer_pubkey: BlsPublicKey,
+    pub proposer_fee_recipient: ExecutionAddress,
+    #[serde(with = "as_str")]
+    pub gas_limit: u64,
+    #[serde(with = "as_str")]
+    pub gas_used: u64,
+    #[serde(with = "as_str")]
+    pub value: U256,
+}
+
+/// A self-built block submitted to a relay through its block-submission endpoint, as a
+/// fallback for relays that don't implement the constraints API. This is the Deneb
+/// version of the `SignedBidSubmission` of the relay specs.
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[allow(missing_docs)]
+pub struct SignedBidSubmission {
+    pub message: BidTrace,
+    pub execution_payload: deneb::mainnet::ExecutionPayload,
+    pub blobs_bundle: BlobsBundle,
+    pub signature: BlsSignature,
+}
+
+/// Fee and capacity information about a target slot, to help users craft valid
+/// commitment requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlotInfo {
+    /// The target slot.
+    pub slot: u64,
+    /// The basefee at the current head block.
+    pub base_fee: u128,
+    /// The maximum basefee of the block at the target slot. Requested transactions must
+    /// have a max fee per gas of at least this value.
+    pub max_base_fee: u128,
+    /// The minimum priority fee accepted for a commitment.
+    pub min_priority_fee: u128,
+    /// The gas that can still be committed in the slot, net of the pending reservations.
+    pub remaining_gas: u64,
+    /// The number of transactions already committed or reserved in the slot.
+    pub commitments_count: usize,
+    /// The maximum number of transactions that can be committed in the slot.
+    pub max_commitments: usize,
 }
 
 /// GetPayload response content, with blobs bundle included.
@@ -142,7 +281,7 @@ impl Default for PayloadAndBlobs {
 }
 
 /// Response to a get payload request
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "version", content = "data")]
 #[allow(missing_docs)]
 pub enum GetPayloadResponse {
@@ -186,3 +325,34 @@ impl From<PayloadAndBlobs> for GetPayloadResponse {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::num::IntErrorKind;
+
+    use super::Slot;
+
+    #[test]
+    fn test_slot_parse_round_trip() {
+        for slot in [0, 1, 9_876_543, Slot::MAX] {
+            assert_eq!(slot.to_string().parse::<Slot>(), Ok(slot));
+        }
+
+        // Leading zeros and a plus sign are accepted, as for any u64
+        assert_eq!("007".parse::<Slot>(), Ok(7));
+        assert_eq!("+7".parse::<Slot>(), Ok(7));
+    }
+
+    #[test]
+    fn test_slot_parse_rejects_invalid_strings() {
+        for invalid in ["abc", "-1", "1.5", "0x10", " 10", "10 "] {
+            let err = invalid.parse::<Slot>().unwrap_err();
+            assert_eq!(err.kind(), &IntErrorKind::InvalidDigit, "{invalid}");
+            assert_eq!(err.to_string(), "invalid digit found in string");
+        }
+
+        assert_eq!("".parse::<Slot>().unwrap_err().kind(), &IntErrorKind::Empty);
+        let overflow = (u128::from(Slot::MAX) + 1).to_string();
+        assert_eq!(overflow.parse::<Slot>().unwrap_err().kind(), &IntErrorKind::PosOverflow);
+    }
+}
@@ -2,7 +2,10 @@ use std::{borrow::Cow, fmt};
 
 use alloy::{
     consensus::BlobTransactionSidecar,
-    eips::eip2718::{Decodable2718, Encodable2718},
+    eips::{
+        eip2718::{Decodable2718, Encodable2718},
+        eip7702::SignedAuthorization,
+    },
     hex,
     primitives::{Address, Bytes, TxKind, U256},
 };
@@ -35,6 +38,9 @@ pub trait TransactionExt {
 
     /// Returns the size of the transaction in bytes.
     fn size(&self) -> usize;
+
+    /// Returns the EIP-7702 authorization list of the transaction, if it has one.
+    fn authorization_list(&self) -> Option<&[SignedAuthorization]>;
 }
 
 impl TransactionExt for PooledTransactionsElement {
@@ -43,8 +49,8 @@ impl TransactionExt for PooledTransactionsElement {
             Self::Legacy { transaction, .. } => transaction.gas_limit,
             Self::Eip2930 { transaction, .. } => transaction.gas_limit,
             Self::Eip1559 { transaction, .. } => transaction.gas_limit,
+            Self::Eip7702 { transaction, .. } => transaction.gas_limit,
             Self::BlobTransaction(blob_tx) => blob_tx.transaction.tx.gas_limit,
-            _ => unimplemented!(),
         }
     }
 
@@ -53,8 +59,8 @@ impl TransactionExt for PooledTransactionsElement {
             Self::Legacy { transaction, .. } => transaction.value,
             Self::Eip2930 { transaction, .. } => transaction.value,
             Self::Eip1559 { transaction, .. } => transaction.value,
+            Self::Eip7702 { transaction, .. } => transaction.value,
             Self::BlobTransaction(blob_tx) => blob_tx.transaction.tx.value,
-            _ => unimplemented!(),
         }
     }
 
@@ -63,8 +69,8 @@ impl TransactionExt for PooledTransactionsElement {
             Self::Legacy { .. } => TxType::Legacy,
             Self::Eip2930 { .. } => TxType::Eip2930,
             Self::Eip1559 { .. } => TxType::Eip1559,
+            Self::Eip7702 { .. } => TxType::Eip7702,
             Self::BlobTransaction(_) => TxType::Eip4844,
-            _ => unimplemented!(),
         }
     }
 
@@ -73,10 +79,8 @@ impl TransactionExt for PooledTransactionsElement {
             Self::Legacy { transaction, .. } => transaction.to,
             Self::Eip2930 { transaction, .. } => transaction.to,
             Self::Eip1559 { transaction, .. } => transaction.to,
-            Self::BlobTransaction(blob_tx) => {
-                TxKind::Call(blob_tx.transaction.tx.to)
-            }
-            _ => unimplemented!(),
+            Self::Eip7702 { transaction, .. } => TxKind::Call(transaction.to),
+            Self::BlobTransaction(blob_tx) => TxKind::Call(blob_tx.transaction.tx.to),
         }
     }
 
@@ -85,8 +89,8 @@ impl TransactionExt for PooledTransactionsElement {
             Self::Legacy { transaction, .. } => &transaction.input,
             Self::Eip2930 { transaction, .. } => &transaction.input,
             Self::Eip1559 { transaction, .. } => &transaction.input,
+            Self::Eip7702 { transaction, .. } => &transaction.input,
             Self::BlobTransaction(blob_tx) => &blob_tx.transaction.tx.input,
-            _ => unimplemented!(),
         }
     }
 
@@ -95,18 +99,21 @@ impl TransactionExt for PooledTransactionsElement {
             Self::Legacy { transaction, .. } => transaction.chain_id,
             Self::Eip2930 { transaction, .. } => Some(transaction.chain_id),
             Self::Eip1559 { transaction, .. } => Some(transaction.chain_id),
-            Self::BlobTransaction(blob_tx) => {
-                Some(blob_tx.transaction.tx.chain_id)
-            }
-            _ => unimplemented!(),
+            Self::Eip7702 { transaction, .. } => Some(transaction.chain_id),
+            Self::BlobTransaction(blob_tx) => Some(blob_tx.transaction.tx.chain_id),
         }
     }
 
     fn blob_sidecar(&self) -> Option<&BlobTransactionSidecar> {
         match self {
-            Self::BlobTransaction(blob_tx) => {
-                Some(&blob_tx.transaction.sidecar)
-            }
+            Self::BlobTransaction(blob_tx) => Some(&blob_tx.transaction.sidecar),
+            _ => None,
+        }
+    }
+
+    fn authorization_list(&self) -> Option<&[SignedAuthorization]> {
+        match self {
+            Self::Eip7702 { transaction, .. } => Some(&transaction.authorization_list),
             _ => None,
         }
     }
@@ -116,8 +123,8 @@ impl TransactionExt for PooledTransactionsElement {
             Self::Legacy { transaction, .. } => transaction.size(),
             Self::Eip2930 { transaction, .. } => transaction.size(),
             Self::Eip1559 { transaction, .. } => transaction.size(),
+            Self::Eip7702 { transaction, .. } => transaction.size(),
             Self::BlobTransaction(blob_tx) => blob_tx.transaction.tx.size(),
-            _ => unimplemented!(),
         }
     }
 }
@@ -208,6 +215,40 @@ impl FullTransaction {
         self.sender.as_ref()
     }
 
+    /// Returns the nonce bumps of the authorities of the EIP-7702 authorizations of the
+    /// transaction, as the authority and the nonce its authorization is valid at, in order.
+    ///
+    /// Authorizations for another chain or without a recoverable authority are never applied,
+    /// and are left out. The authorizations of the sender itself are applied after the nonce of
+    /// the transaction is incremented, so they are only kept if their nonce follows it. The
+    /// nonces of the other authorities depend on the state, and must be checked against it.
+    pub fn authority_nonce_bumps(&self) -> Vec<(Address, u64)> {
+        let Some(authorizations) = self.authorization_list() else {
+            return Vec::new();
+        };
+
+        let chain_id = U256::from(self.chain_id().unwrap_or_default());
+        let mut sender_nonce = self.nonce() + 1;
+        let mut bumps = Vec::with_capacity(authorizations.len());
+        for authorization in authorizations {
+            if !authorization.chain_id.is_zero() && authorization.chain_id != chain_id {
+                continue;
+            }
+
+            let Ok(authority) = authorization.recover_authority() else { continue };
+            if self.sender() == Some(&authority) {
+                if authorization.nonce != sender_nonce {
+                    continue;
+                }
+                sender_nonce += 1;
+            }
+
+            bumps.push((authority, authorization.nonce));
+        }
+
+        bumps
+    }
+
     /// Returns the effective miner gas tip cap (`gasTipCap`) for the given base fee:
     /// `min(maxFeePerGas - baseFee, maxPriorityFeePerGas)`
     ///
@@ -246,20 +287,39 @@ pub fn serialize_txs<S: serde::Serializer>(
 }
 
 /// Deserialize a list of transactions from a sequence of hex-encoded strings.
+///
+/// The transactions are decoded one at a time, and the list is rejected as soon as one of
+/// them can't be decoded, so the rest of a huge invalid input is never materialized.
+/// Deserialization is only structural: the senders, gas limits and nonces of the
+/// transactions are checked by the validation of the request.
 pub fn deserialize_txs<'de, D>(deserializer: D) -> Result<Vec<FullTransaction>, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    let hex_strings = <Vec<Cow<'_, str>> as de::Deserialize>::deserialize(deserializer)?;
-    let mut txs = Vec::with_capacity(hex_strings.len());
-
-    for s in hex_strings {
-        let data = hex::decode(s.trim_start_matches("0x")).map_err(de::Error::custom)?;
-        let tx = PooledTransactionsElement::decode_2718(&mut data.as_slice())
-            .map_err(de::Error::custom)
-            .map(|tx| FullTransaction { tx, sender: None })?;
-        txs.push(tx);
+    deserializer.deserialize_seq(TxsVisitor)
+}
+
+/// Visitor that decodes a sequence of hex-encoded transactions incrementally.
+struct TxsVisitor;
+
+impl<'de> de::Visitor<'de> for TxsVisitor {
+    type Value = Vec<FullTransaction>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a sequence of hex-encoded transactions")
     }
 
-    Ok(txs)
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut txs = Vec::new();
+
+        while let Some(s) = seq.next_element::<Cow<'_, str>>()? {
+            let data = hex::decode(s.trim_start_matches("0x")).map_err(de::Error::custom)?;
+            let tx = PooledTransactionsElement::decode_2718(&mut data.as_slice())
+                .map_err(de::Error::custom)
+                .map(|tx| FullTransaction { tx, sender: None })?;
+            txs.push(tx);
+        }
+
+        Ok(txs)
+    }
 }
@@ -0,0 +1,272 @@
+use std::fmt::Debug;
+
+use alloy::signers::k256::sha2::{Digest, Sha256};
+use blst::{
+    min_pk::{AggregatePublicKey, AggregateSignature, PublicKey, Signature},
+    BLST_ERROR,
+};
+use ethereum_consensus::{crypto::PublicKey as ClPublicKey, deneb::compute_signing_root};
+use futures::future::try_join_all;
+
+use crate::{
+    config::ChainConfig,
+    crypto::bls::{verify_root, BLSSig},
+};
+
+use super::{commit_boost::CommitBoostSigner, local::LocalSigner, SignerResult};
+
+/// The tag of the roots signed as proofs of possession of the co-signer keys.
+const PROOF_OF_POSSESSION_TAG: &[u8] = b"BOLT_AGGREGATE_PROOF_OF_POSSESSION";
+
+/// Error in the aggregate signer.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum AggregateSignerError {
+    #[error("an aggregate signer needs at least one key")]
+    NoKeys,
+    #[error("the key {0} is used by several co-signers")]
+    DuplicateKey(ClPublicKey),
+    #[error("invalid proof of possession of the key {0}")]
+    InvalidProofOfPossession(ClPublicKey),
+    #[error("failed to aggregate BLS {0}: {1:?}")]
+    Aggregation(&'static str, BLST_ERROR),
+    #[error("failed to compute signing root: {0}")]
+    SigningRootComputation(#[from] ethereum_consensus::error::Error),
+    #[error("invalid aggregate signature: {0:?}")]
+    InvalidSignature(BLST_ERROR),
+}
+
+/// A signer holding one of the keys of an [AggregateSigner].
+#[derive(Debug, Clone)]
+pub enum CoSigner {
+    /// A BLS secret key held by the sidecar.
+    Local(LocalSigner),
+    /// A remote Commit-Boost signer, signing with its consensus key on its own host.
+    Remote(CommitBoostSigner),
+}
+
+impl CoSigner {
+    /// Get the public key of the co-signer.
+    async fn pubkey(&self) -> SignerResult<ClPublicKey> {
+        match self {
+            Self::Local(signer) => Ok(signer.pubkey()),
+            Self::Remote(signer) => signer.fetch_consensus_pubkey().await,
+        }
+    }
+
+    /// Sign an SSZ object root with the Commit Boost domain.
+    async fn sign_commit_boost_root(&self, root: [u8; 32]) -> SignerResult<BLSSig> {
+        match self {
+            Self::Local(signer) => signer.sign_commit_boost_root(root),
+            Self::Remote(signer) => signer.sign_commit_boost_root(root).await,
+        }
+    }
+}
+
+/// A BLS signer jointly authorizing messages with the keys of several co-signers,
+/// multisig-style.
+///
+/// Each co-signer signs the same root, and the signatures are aggregated into a single one
+/// that verifies against the aggregate public key of the co-signers. The aggregate public key
+/// is the one to delegate to and to put in the signed messages, e.g. the constraints.
+///
+/// The aggregate only adds security if the keys are held by independent parties, so all but
+/// one of the co-signers should be remote signers on other hosts.
+#[derive(Clone)]
+pub struct AggregateSigner {
+    cosigners: Vec<CoSigner>,
+    /// The public keys of the co-signers, in the same order.
+    pubkeys: Vec<PublicKey>,
+    /// The aggregate public key of the co-signers.
+    aggregate_pubkey: ClPublicKey,
+    chain: ChainConfig,
+}
+
+impl Debug for AggregateSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AggregateSigner")
+            .field("pubkey", &self.aggregate_pubkey)
+            .field("keys", &self.cosigners.len())
+            .field("chain", &self.chain.name())
+            .finish()
+    }
+}
+
+impl AggregateSigner {
+    /// Create a new signer aggregating the signatures of the given co-signers.
+    ///
+    /// Each co-signer first proves the possession of its key by signing a root derived from
+    /// its public key. Otherwise a co-signer could announce a rogue key cancelling out the
+    /// keys of the others in the aggregate public key, and sign alone on their behalf.
+    pub async fn new(cosigners: Vec<CoSigner>, chain: ChainConfig) -> SignerResult<Self> {
+        if cosigners.is_empty() {
+            return Err(AggregateSignerError::NoKeys.into());
+        }
+
+        let mut pubkeys = Vec::with_capacity(cosigners.len());
+        for cosigner in &cosigners {
+            let pubkey = cosigner.pubkey().await?;
+            if pubkeys.contains(&pubkey) {
+                return Err(AggregateSignerError::DuplicateKey(pubkey).into());
+            }
+
+            let proof = cosigner.sign_commit_boost_root(proof_of_possession_root(&pubkey)).await?;
+            verify_proof_of_possession(&pubkey, &proof, &chain)?;
+            pubkeys.push(pubkey);
+        }
+
+        let pubkeys = pubkeys
+            .iter()
+            .map(|pk| PublicKey::from_bytes(pk.as_ref()).expect("valid public key"))
+            .collect::<Vec<_>>();
+        let aggregate = AggregatePublicKey::aggregate(&pubkeys.iter().collect::<Vec<_>>(), false)
+            .map_err(|e| AggregateSignerError::Aggregation("public keys", e))?
+            .to_public_key();
+        let aggregate_pubkey =
+            ClPublicKey::try_from(aggregate.to_bytes().as_ref()).expect("valid public key");
+
+        Ok(Self { cosigners, pubkeys, aggregate_pubkey, chain })
+    }
+
+    /// Get the public keys of the individual co-signers.
+    pub fn pubkeys(&self) -> Vec<ClPublicKey> {
+        self.pubkeys
+            .iter()
+            .map(|pk| ClPublicKey::try_from(pk.to_bytes().as_ref()).expect("valid public key"))
+            .collect()
+    }
+
+    /// Get the aggregate public key of the co-signers.
+    pub fn pubkey(&self) -> ClPublicKey {
+        self.aggregate_pubkey.clone()
+    }
+
+    /// Sign an SSZ object root with every co-signer and the Commit Boost domain, and aggregate
+    /// the signatures.
+    pub async fn sign_commit_boost_root(&self, root: [u8; 32]) -> SignerResult<BLSSig> {
+        let signatures = try_join_all(
+            self.cosigners.iter().map(|cosigner| cosigner.sign_commit_boost_root(root)),
+        )
+        .await?;
+
+        let signatures = signatures
+            .iter()
+            .map(|signature| Signature::from_bytes(signature.as_ref()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AggregateSignerError::Aggregation("signatures", e))?;
+        let aggregate =
+            AggregateSignature::aggregate(&signatures.iter().collect::<Vec<_>>(), false)
+                .map_err(|e| AggregateSignerError::Aggregation("signatures", e))?;
+        Ok(BLSSig::from_slice(&aggregate.to_signature().to_bytes()))
+    }
+
+    /// Verify an aggregate signature over the root with the Commit Boost domain, against the
+    /// public keys of all the co-signers.
+    pub fn verify_commit_boost_root(
+        &self,
+        root: [u8; 32],
+        signature: &Signature,
+    ) -> SignerResult<()> {
+        let signing_root = compute_signing_root(&root, self.chain.commit_boost_domain())
+            .map_err(AggregateSignerError::SigningRootComputation)?;
+        let pubkeys = self.pubkeys.iter().collect::<Vec<_>>();
+
+        let dst = self.chain.bls_dst();
+        let res = signature.fast_aggregate_verify(true, signing_root.as_ref(), dst, &pubkeys);
+        if res == BLST_ERROR::BLST_SUCCESS {
+            Ok(())
+        } else {
+            Err(AggregateSignerError::InvalidSignature(res))?
+        }
+    }
+}
+
+/// The root signed by a co-signer to prove the possession of its key. It is tagged so that it
+/// can't be mistaken for the digest of a signed message.
+fn proof_of_possession_root(pubkey: &ClPublicKey) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(PROOF_OF_POSSESSION_TAG);
+    hasher.update(pubkey.as_ref());
+    hasher.finalize().into()
+}
+
+/// Verify the proof of possession of a co-signer key, signed with the Commit Boost domain.
+fn verify_proof_of_possession(
+    pubkey: &ClPublicKey,
+    proof: &BLSSig,
+    chain: &ChainConfig,
+) -> Result<(), AggregateSignerError> {
+    let root = proof_of_possession_root(pubkey);
+    verify_root(root, chain.commit_boost_domain(), proof.as_ref(), pubkey, chain.bls_dst())
+        .map_err(|_| AggregateSignerError::InvalidProofOfPossession(pubkey.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use blst::{min_pk::Signature, BLST_ERROR};
+    use ethereum_consensus::deneb::compute_signing_root;
+
+    use crate::{
+        config::ChainConfig,
+        signer::{local::LocalSigner, SignerBLS},
+    };
+
+    use super::{proof_of_possession_root, verify_proof_of_possession, AggregateSigner, CoSigner};
+
+    #[tokio::test]
+    async fn test_aggregate_signature_verifies_against_aggregate_pubkey() {
+        let chain = ChainConfig::mainnet();
+        let keys = [LocalSigner::random(), LocalSigner::random()];
+        let cosigners = keys.iter().cloned().map(CoSigner::Local).collect();
+        let signer = AggregateSigner::new(cosigners, chain).await.unwrap();
+        assert_eq!(signer.pubkeys(), keys.iter().map(LocalSigner::pubkey).collect::<Vec<_>>());
+
+        let root = [42; 32];
+        let signature = signer.sign_commit_boost_root(root).await.unwrap();
+        let signature = Signature::from_bytes(signature.as_ref()).unwrap();
+        assert!(signer.verify_commit_boost_root(root, &signature).is_ok());
+
+        // A relay verifies the signature against the aggregate public key alone
+        let signing_root = compute_signing_root(&root, chain.commit_boost_domain()).unwrap();
+        let aggregate_pubkey = blst::min_pk::PublicKey::from_bytes(signer.pubkey().as_ref());
+        let res = signature.verify(
+            true,
+            signing_root.as_ref(),
+            chain.bls_dst(),
+            &[],
+            &aggregate_pubkey.unwrap(),
+            true,
+        );
+        assert_eq!(res, BLST_ERROR::BLST_SUCCESS);
+        assert_eq!(
+            SignerBLS::Aggregate(signer.clone()).available_pubkeys(),
+            [signer.pubkey()].into()
+        );
+
+        // The signature of a single key doesn't authorize the root
+        let single = keys[0].sign_commit_boost_root(root).unwrap();
+        let single = Signature::from_bytes(single.as_ref()).unwrap();
+        assert!(signer.verify_commit_boost_root(root, &single).is_err());
+
+        assert!(AggregateSigner::new(Vec::new(), chain).await.is_err());
+
+        // The same key can't be counted twice
+        let duplicates = vec![CoSigner::Local(keys[0].clone()), CoSigner::Local(keys[0].clone())];
+        assert!(AggregateSigner::new(duplicates, chain).await.is_err());
+    }
+
+    #[test]
+    fn test_proof_of_possession() {
+        let chain = ChainConfig::mainnet();
+        let (signer, other) = (LocalSigner::random(), LocalSigner::random());
+
+        let proof = signer.sign_commit_boost_root(proof_of_possession_root(&signer.pubkey()));
+        let proof = proof.unwrap();
+        assert!(verify_proof_of_possession(&signer.pubkey(), &proof, &chain).is_ok());
+
+        // A key announced without its secret can't be proven with the signature of another key
+        assert!(verify_proof_of_possession(&other.pubkey(), &proof, &chain).is_err());
+        let forged = signer.sign_commit_boost_root(proof_of_possession_root(&other.pubkey()));
+        assert!(verify_proof_of_possession(&other.pubkey(), &forged.unwrap(), &chain).is_err());
+    }
+}
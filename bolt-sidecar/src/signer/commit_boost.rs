@@ -1,4 +1,4 @@
-use std::{str::FromStr, sync::Arc};
+use std::{path::Path, str::FromStr, sync::Arc};
 
 use alloy::{primitives::Address, rpc::types::beacon::BlsSignature, signers::Signature};
 use cb_common::{
@@ -6,16 +6,17 @@ use cb_common::{
     signer::EcdsaPublicKey,
 };
 use commit_boost::prelude::SignProxyRequest;
-use ethereum_consensus::crypto::bls::PublicKey as BlsPublicKey;
+use ethereum_consensus::crypto::bls::{PublicKey as BlsPublicKey, Signature as ConsensusSignature};
 use parking_lot::RwLock;
 use reqwest::Url;
 use ssz::Decode;
 use thiserror::Error;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use crate::{
+    config::ChainConfig,
     crypto::{bls::BLS_DST_PREFIX, ecdsa::SignerECDSA},
-    primitives::commitment::ECDSASignatureExt,
+    primitives::{commitment::ECDSASignatureExt, ProxyDelegation, SignedProxyDelegation},
 };
 
 use super::SignerResult;
@@ -27,6 +28,8 @@ pub struct CommitBoostSigner {
     signer_client: SignerClient,
     pubkeys: Arc<RwLock<Vec<BlsPublicKey>>>,
     proxy_ecdsa: Arc<RwLock<Vec<EcdsaPublicKey>>>,
+    /// The delegation of the BLS proxy key used to sign constraints, if any.
+    proxy_bls: Arc<RwLock<Option<SignedProxyDelegation>>>,
 }
 
 /// Error in the Commit-Boost signer.
@@ -37,6 +40,10 @@ pub enum CommitBoostError {
     NoSignature(String),
     #[error("failed to create signer client: {0}")]
     SignerClientError(#[from] SignerClientError),
+    #[error("no consensus key available in the commit boost signer")]
+    NoConsensusKey,
+    #[error("invalid proxy key delegation for {0}")]
+    InvalidProxyDelegation(BlsPublicKey),
     #[error("error in commit boost signer: {0}")]
     Other(eyre::Report),
 }
@@ -52,6 +59,7 @@ impl CommitBoostSigner {
             signer_client,
             pubkeys: Arc::new(RwLock::new(Vec::new())),
             proxy_ecdsa: Arc::new(RwLock::new(Vec::new())),
+            proxy_bls: Arc::new(RwLock::new(None)),
         };
 
         let mut this = client.clone();
@@ -93,7 +101,136 @@ impl CommitBoostSigner {
         *self.proxy_ecdsa.read().first().expect("proxy ecdsa key loaded")
     }
 
-    /// Verify the BLS signature of the object with the given public key.
+    /// Get the delegation of the BLS proxy key used to sign constraints, if any.
+    pub fn proxy_delegation(&self) -> Option<SignedProxyDelegation> {
+        self.proxy_bls.read().clone()
+    }
+
+    /// Request a new BLS proxy key from the Commit-Boost signer, delegated from its consensus
+    /// key, and use it instead of the consensus key to sign constraints from now on.
+    ///
+    /// The returned delegation is checked against the consensus key with the Commit Boost
+    /// domain of the given chain, and can be shared as proof of the delegation.
+    pub async fn request_proxy_key(
+        &self,
+        chain: &ChainConfig,
+    ) -> SignerResult<SignedProxyDelegation> {
+        let mut client = self.signer_client.clone();
+        let consensus_pubkey = self.fetch_consensus_pubkey().await?;
+
+        // TODO: compat: convert the keys between ethereum_consensus and commit-boost formats
+        let pubkey = cb_common::signer::BlsPublicKey::from_ssz_bytes(consensus_pubkey.as_ref())
+            .expect("pubkey bytes conversion");
+
+        debug!(%consensus_pubkey, "Requesting BLS proxy key from commit_boost");
+        let signed = client
+            .generate_proxy_key_bls(pubkey)
+            .await
+            .map_err(CommitBoostError::SignerClientError)?;
+
+        let delegation = SignedProxyDelegation {
+            message: ProxyDelegation {
+                delegator: BlsPublicKey::try_from(signed.message.delegator.as_ref())
+                    .map_err(|e| CommitBoostError::Other(e.into()))?,
+                proxy: BlsPublicKey::try_from(signed.message.proxy.as_ref())
+                    .map_err(|e| CommitBoostError::Other(e.into()))?,
+            },
+            signature: ConsensusSignature::try_from(signed.signature.as_ref())
+                .map_err(|e| CommitBoostError::Other(e.into()))?,
+        };
+
+        if delegation.message.delegator != consensus_pubkey || !delegation.verify(chain) {
+            return Err(CommitBoostError::InvalidProxyDelegation(delegation.message.proxy).into());
+        }
+
+        info!(proxy = %delegation.message.proxy, "Signing constraints with commit_boost proxy key");
+        *self.proxy_bls.write() = Some(delegation.clone());
+        Ok(delegation)
+    }
+
+    /// Like [Self::request_proxy_key], but reuse the proxy key delegated in the file at `path`
+    /// if the delegation is valid for the consensus key and the signer still holds the proxy
+    /// key. Otherwise, a new proxy key is requested and its delegation written to the file.
+    ///
+    /// This keeps the same proxy key across restarts, so that the delegations to it stay valid.
+    pub async fn load_or_request_proxy_key(
+        &self,
+        chain: &ChainConfig,
+        path: &Path,
+    ) -> SignerResult<SignedProxyDelegation> {
+        if let Some(delegation) = self.load_proxy_delegation(chain, path).await? {
+            info!(proxy = %delegation.message.proxy, "Reusing the persisted proxy key");
+            *self.proxy_bls.write() = Some(delegation.clone());
+            return Ok(delegation);
+        }
+
+        let delegation = self.request_proxy_key(chain).await?;
+        let json = serde_json::to_vec_pretty(&delegation)
+            .map_err(|e| CommitBoostError::Other(e.into()))?;
+        std::fs::write(path, json).map_err(|e| CommitBoostError::Other(e.into()))?;
+
+        Ok(delegation)
+    }
+
+    /// Load the proxy key delegation persisted at `path`, if it exists and can be reused.
+    async fn load_proxy_delegation(
+        &self,
+        chain: &ChainConfig,
+        path: &Path,
+    ) -> SignerResult<Option<SignedProxyDelegation>> {
+        let json = match std::fs::read(path) {
+            Ok(json) => json,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(CommitBoostError::Other(err.into()).into()),
+        };
+        let delegation = serde_json::from_slice::<SignedProxyDelegation>(&json)
+            .map_err(|e| CommitBoostError::Other(e.into()))?;
+
+        let consensus_pubkey = self.fetch_consensus_pubkey().await?;
+        if delegation.message.delegator != consensus_pubkey || !delegation.verify(chain) {
+            warn!(?path, "Persisted proxy delegation is invalid, requesting a new proxy key");
+            return Ok(None);
+        }
+
+        let mut client = self.signer_client.clone();
+        let pubkeys = client.get_pubkeys().await.map_err(CommitBoostError::SignerClientError)?;
+        let proxy = Some(&delegation.message.proxy);
+        if !pubkeys
+            .proxy_bls
+            .iter()
+            .any(|key| BlsPublicKey::try_from(key.as_ref()).ok().as_ref() == proxy)
+        {
+            warn!(?path, "Persisted proxy key is unknown to the signer, requesting a new one");
+            return Ok(None);
+        }
+
+        Ok(Some(delegation))
+    }
+
+    /// Get the consensus public key, fetching it from the signer if the pubkeys loaded in
+    /// the background on creation aren't there yet.
+    pub async fn fetch_consensus_pubkey(&self) -> SignerResult<BlsPublicKey> {
+        if let Some(pubkey) = self.pubkeys.read().first().cloned() {
+            return Ok(pubkey);
+        }
+
+        let mut client = self.signer_client.clone();
+        let pubkeys = client.get_pubkeys().await.map_err(CommitBoostError::SignerClientError)?;
+        let consensus = pubkeys
+            .consensus
+            .iter()
+            .map(|pubkey| BlsPublicKey::try_from(pubkey.as_ref()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| CommitBoostError::Other(e.into()))?;
+        let pubkey = consensus.first().cloned().ok_or(CommitBoostError::NoConsensusKey)?;
+
+        // Later signing requests read the consensus key from the loaded pubkeys
+        *self.pubkeys.write() = consensus;
+        Ok(pubkey)
+    }
+
+    /// Verify the BLS signature of the object with the given public key. Commit-boost always
+    /// signs with the proof-of-possession tag, whatever the DST of the chain configuration.
     pub fn verify_bls(
         &self,
         data: &[u8; 32],
@@ -115,13 +252,20 @@ impl CommitBoostSigner {
 }
 
 impl CommitBoostSigner {
-    /// Get the public key of the signer.
+    /// Get the public key of the signer: the proxy key if one is in use, or the consensus key.
     pub fn pubkey(&self) -> BlsPublicKey {
-        self.get_consensus_pubkey()
+        match self.proxy_bls.read().as_ref() {
+            Some(delegation) => delegation.message.proxy.clone(),
+            None => self.get_consensus_pubkey(),
+        }
     }
 
-    /// Sign an object root with the Commit Boost domain.
+    /// Sign an object root with the Commit Boost domain, with the proxy key if one is in use.
     pub async fn sign_commit_boost_root(&self, data: [u8; 32]) -> SignerResult<BlsSignature> {
+        if let Some(delegation) = self.proxy_delegation() {
+            return self.sign_with_proxy_key(&delegation.message.proxy, data).await;
+        }
+
         // convert the pubkey from ethereum_consensus to commit-boost format
         // TODO: compat: this is the only way to obtain a BlsPubkey for now unfortunately
         let pubkey = cb_common::signer::BlsPublicKey::from_ssz_bytes(self.pubkey().as_ref())
@@ -139,6 +283,27 @@ impl CommitBoostSigner {
             .map(|sig| BlsSignature::from_slice(sig.as_ref()))
             .map_err(CommitBoostError::SignerClientError)?)
     }
+
+    /// Sign an object root with the Commit Boost domain using the given BLS proxy key.
+    async fn sign_with_proxy_key(
+        &self,
+        proxy: &BlsPublicKey,
+        data: [u8; 32],
+    ) -> SignerResult<BlsSignature> {
+        let pubkey = cb_common::signer::BlsPublicKey::from_ssz_bytes(proxy.as_ref())
+            .expect("pubkey bytes conversion");
+
+        let request = SignProxyRequest::builder(pubkey).with_msg(&data);
+
+        debug!(?request, "Requesting proxy signature from commit_boost");
+
+        Ok(self
+            .signer_client
+            .request_proxy_signature_bls(request)
+            .await
+            .map(|sig| BlsSignature::from_slice(sig.as_ref()))
+            .map_err(CommitBoostError::SignerClientError)?)
+    }
 }
 
 #[async_trait::async_trait]
@@ -180,9 +345,141 @@ fn parse_address_from_url(url: Url) -> eyre::Result<String> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use axum::{
+        extract::State,
+        routing::{get, post},
+        Json, Router,
+    };
+    use cb_common::commit::constants::{
+        GENERATE_PROXY_KEY_PATH, GET_PUBKEYS_PATH, REQUEST_SIGNATURE_PATH,
+    };
     use rand::Rng;
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::net::TcpListener;
     use tracing::warn;
 
+    use crate::{crypto::SignableBLS, signer::local::LocalSigner};
+
+    /// Spawns a mock commit-boost signer with a single consensus key, which delegates to
+    /// `proxy` on every proxy key request. Returns its URL and the number of proxy key
+    /// requests it served.
+    async fn spawn_mock_commit_boost(
+        consensus: LocalSigner,
+        proxy: LocalSigner,
+    ) -> (Url, Arc<AtomicUsize>) {
+        type Keys = State<(LocalSigner, LocalSigner, Arc<AtomicUsize>)>;
+
+        async fn get_pubkeys(State((consensus, proxy, _)): Keys) -> Json<Value> {
+            let (consensus, proxy) = (consensus.pubkey(), proxy.pubkey());
+            Json(json!({ "consensus": [consensus], "proxy_bls": [proxy], "proxy_ecdsa": [] }))
+        }
+
+        async fn generate_proxy_key(
+            State((consensus, proxy, requests)): Keys,
+        ) -> Json<SignedProxyDelegation> {
+            requests.fetch_add(1, Ordering::Relaxed);
+            let message = ProxyDelegation { delegator: consensus.pubkey(), proxy: proxy.pubkey() };
+            let signature = consensus.sign_commit_boost_root(message.digest()).unwrap();
+            let signature = ConsensusSignature::try_from(signature.as_ref()).unwrap();
+            Json(SignedProxyDelegation { message, signature })
+        }
+
+        async fn request_signature(
+            State((consensus, proxy, _)): Keys,
+            Json(request): Json<Value>,
+        ) -> Json<Value> {
+            // The object root may be encoded either as hex or as a list of bytes
+            let root: [u8; 32] = match &request["object_root"] {
+                Value::String(hex) => alloy::hex::decode(hex).unwrap().try_into().unwrap(),
+                bytes => serde_json::from_value(bytes.clone()).unwrap(),
+            };
+            let signer = if request["type"] == "proxy_bls" { proxy } else { consensus };
+            Json(json!(signer.sign_commit_boost_root(root).unwrap()))
+        }
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let router = Router::new()
+            .route(GET_PUBKEYS_PATH, get(get_pubkeys))
+            .route(GENERATE_PROXY_KEY_PATH, post(generate_proxy_key))
+            .route(REQUEST_SIGNATURE_PATH, post(request_signature))
+            .with_state((consensus, proxy, requests.clone()));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        (url, requests)
+    }
+
+    #[tokio::test]
+    async fn test_commit_boost_proxy_key() -> eyre::Result<()> {
+        let consensus = LocalSigner::random();
+        let proxy = LocalSigner::random();
+        let (url, _) = spawn_mock_commit_boost(consensus.clone(), proxy.clone()).await;
+
+        let jwt = alloy::hex::encode(rand::random::<[u8; 32]>());
+        let signer = CommitBoostSigner::new(url, &jwt)?;
+        assert!(signer.proxy_delegation().is_none());
+
+        // The delegation proof is checked against the consensus key before using the proxy key
+        let chain = ChainConfig::mainnet();
+        let delegation = signer.request_proxy_key(&chain).await?;
+        assert_eq!(delegation.message.delegator, consensus.pubkey());
+        assert_eq!(delegation.message.proxy, proxy.pubkey());
+        assert!(delegation.verify(&chain));
+        assert_eq!(signer.proxy_delegation(), Some(delegation.clone()));
+        assert_eq!(signer.pubkey(), proxy.pubkey());
+
+        let mut forged = delegation;
+        forged.message.proxy = consensus.pubkey();
+        assert!(!forged.verify(&chain));
+
+        // Constraints are now signed with the proxy key
+        let data = rand::random::<[u8; 32]>();
+        let signature = signer.sign_commit_boost_root(data).await?;
+        let sig = blst::min_pk::Signature::from_bytes(signature.as_ref()).unwrap();
+        assert!(proxy.verify_commit_boost_root(data, &sig).is_ok());
+        assert!(consensus.verify_commit_boost_root(data, &sig).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_commit_boost_proxy_key_is_persisted() -> eyre::Result<()> {
+        let consensus = LocalSigner::random();
+        let proxy = LocalSigner::random();
+        let (url, requests) = spawn_mock_commit_boost(consensus.clone(), proxy.clone()).await;
+
+        let chain = ChainConfig::mainnet();
+        let jwt = alloy::hex::encode(rand::random::<[u8; 32]>());
+        let path = std::env::temp_dir()
+            .join(format!("bolt-cb-proxy-delegation-{}.json", rand::random::<u64>()));
+
+        // A new proxy key is requested on the first start, and its delegation persisted
+        let signer = CommitBoostSigner::new(url.clone(), &jwt)?;
+        let delegation = signer.load_or_request_proxy_key(&chain, &path).await?;
+        assert_eq!(requests.load(Ordering::Relaxed), 1);
+        assert!(path.exists());
+
+        // The persisted proxy key is reused after a restart
+        let signer = CommitBoostSigner::new(url.clone(), &jwt)?;
+        assert_eq!(signer.load_or_request_proxy_key(&chain, &path).await?, delegation);
+        assert_eq!(signer.pubkey(), proxy.pubkey());
+        assert_eq!(requests.load(Ordering::Relaxed), 1);
+
+        // A delegation that doesn't verify is replaced
+        let mut forged = delegation.clone();
+        forged.message.proxy = consensus.pubkey();
+        std::fs::write(&path, serde_json::to_vec(&forged)?)?;
+        let signer = CommitBoostSigner::new(url, &jwt)?;
+        assert_eq!(signer.load_or_request_proxy_key(&chain, &path).await?, delegation);
+        assert_eq!(requests.load(Ordering::Relaxed), 2);
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
     #[test]
     fn test_convert_pubkey_bytes() {
         // Generate random data for the test
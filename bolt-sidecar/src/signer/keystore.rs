@@ -8,13 +8,11 @@ use std::{
 };
 
 use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
-use lighthouse_bls::Keypair;
 use lighthouse_eth2_keystore::Keystore;
-use ssz::Encode;
 
-use crate::{builder::signature::compute_signing_root, config::ChainConfig, crypto::bls::BLSSig};
+use crate::{config::ChainConfig, crypto::bls::BLSSig};
 
-use super::SignerResult;
+use super::{local::LocalSigner, SignerResult};
 
 /// Error in the keystore signer.
 #[derive(Debug, thiserror::Error)]
@@ -30,16 +28,17 @@ pub enum KeystoreError {
     KeypairDecryption(PathBuf, String),
     #[error("could not find private key associated to public key {0}")]
     UnknownPublicKey(String),
+    #[error("invalid secret key: {0}")]
+    InvalidSecretKey(String),
     #[error("invalid signature key length -- signature: {0} -- message: {1}")]
     SignatureLength(String, String),
 }
 
 /// A signer that can sign messages with multiple keypairs loaded from
-/// ERC-2335 keystores files.
+/// ERC-2335 keystores files. Each keystore is loaded with [LocalSigner::from_keystore].
 #[derive(Clone)]
 pub struct KeystoreSigner {
-    keypairs: Vec<Keypair>,
-    chain: ChainConfig,
+    signers: Vec<LocalSigner>,
 }
 
 impl KeystoreSigner {
@@ -51,19 +50,12 @@ impl KeystoreSigner {
         chain: ChainConfig,
     ) -> SignerResult<Self> {
         // Create the path to the keystore directory, starting from the root of the project
-        let keystores_paths = find_json_keystores(keys_path)?;
-        let mut keypairs = Vec::with_capacity(keystores_paths.len());
-
-        for path in keystores_paths {
-            let keystore = Keystore::from_json_file(path.clone())
-                .map_err(|e| KeystoreError::ReadFromJSON(path.clone(), format!("{e:?}")))?;
-            let keypair = keystore
-                .decrypt_keypair(password)
-                .map_err(|e| KeystoreError::KeypairDecryption(path.clone(), format!("{e:?}")))?;
-            keypairs.push(keypair);
-        }
+        let signers = find_json_keystores(keys_path)?
+            .iter()
+            .map(|path| LocalSigner::from_keystore(path, password, chain))
+            .collect::<SignerResult<_>>()?;
 
-        Ok(Self { keypairs, chain })
+        Ok(Self { signers })
     }
 
     /// Creates a new `KeystoreSigner` from the keystore files in the `keys_path` directory.
@@ -75,13 +67,10 @@ impl KeystoreSigner {
     ) -> SignerResult<Self> {
         let keystores_paths = find_json_keystores(keys_path)?;
 
-        let mut keypairs = Vec::with_capacity(keystores_paths.len());
+        let mut signers = Vec::with_capacity(keystores_paths.len());
 
         for path in keystores_paths {
-            let keystore = Keystore::from_json_file(path.clone())
-                .map_err(|e| KeystoreError::ReadFromJSON(path.clone(), format!("{e:?}")))?;
-
-            let pubkey = format!("0x{}", keystore.pubkey());
+            let pubkey = format!("0x{}", read_keystore(&path)?.pubkey());
 
             let mut secret_path = secrets_path.to_path_buf();
             secret_path.push(pubkey);
@@ -89,23 +78,15 @@ impl KeystoreSigner {
             let password = fs::read_to_string(secret_path)
                 .map_err(|e| KeystoreError::ReadFromSecretFile(format!("{e:?}")))?;
 
-            let keypair = keystore
-                .decrypt_keypair(password.as_bytes())
-                .map_err(|e| KeystoreError::KeypairDecryption(path.clone(), format!("{e:?}")))?;
-            keypairs.push(keypair);
+            signers.push(LocalSigner::from_keystore(&path, password.as_bytes(), chain)?);
         }
 
-        Ok(Self { keypairs, chain })
+        Ok(Self { signers })
     }
 
     /// Returns the public keys of the keypairs in the keystore.
     pub fn pubkeys(&self) -> HashSet<BlsPublicKey> {
-        self.keypairs
-            .iter()
-            .map(|kp| {
-                BlsPublicKey::try_from(kp.pk.serialize().to_vec().as_ref()).expect("valid pubkey")
-            })
-            .collect::<HashSet<_>>()
+        self.signers.iter().map(LocalSigner::pubkey).collect::<HashSet<_>>()
     }
 
     /// Signs a message with the keystore signer and the Commit Boost domain
@@ -114,44 +95,30 @@ impl KeystoreSigner {
         root: [u8; 32],
         public_key: &BlsPublicKey,
     ) -> SignerResult<BLSSig> {
-        self.sign_root(root, public_key, self.chain.commit_boost_domain())
-    }
-
-    /// Signs a message with the keystore signer.
-    fn sign_root(
-        &self,
-        root: [u8; 32],
-        public_key: &BlsPublicKey,
-        domain: [u8; 32],
-    ) -> SignerResult<BLSSig> {
-        let sk = self
-            .keypairs
+        let signer = self
+            .signers
             .iter()
-            // `as_ssz_bytes` returns the raw bytes we need
-            .find(|kp| kp.pk.as_ssz_bytes() == public_key.as_ref())
+            .find(|signer| signer.pubkey() == *public_key)
             .ok_or(KeystoreError::UnknownPublicKey(public_key.to_string()))?;
 
-        let signing_root = compute_signing_root(root, domain);
-
-        let sig = sk.sk.sign(signing_root.into()).as_ssz_bytes();
-        let sig = BLSSig::try_from(sig.as_slice())
-            .map_err(|e| KeystoreError::SignatureLength(hex::encode(sig), format!("{e:?}")))?;
-
-        Ok(sig)
+        signer.sign_commit_boost_root(root)
     }
 }
 
 impl Debug for KeystoreSigner {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("KeystoreSigner")
-            .field(
-                "pubkeys",
-                &self.keypairs.iter().map(|kp| kp.pk.as_hex_string()).collect::<Vec<_>>(),
-            )
+            .field("pubkeys", &self.signers.iter().map(LocalSigner::pubkey).collect::<Vec<_>>())
             .finish()
     }
 }
 
+/// Reads the EIP-2335 keystore file at the given path, without decrypting it.
+pub(super) fn read_keystore(path: &Path) -> SignerResult<Keystore> {
+    Ok(Keystore::from_json_file(path)
+        .map_err(|e| KeystoreError::ReadFromJSON(path.to_path_buf(), format!("{e:?}")))?)
+}
+
 /// Returns the paths of all the keystore files provided an optional `keys_path`, which defaults to
 /// `keys`. `keys_path` is a relative path from the root of this cargo project
 /// We're expecting a directory structure like:
@@ -329,15 +296,11 @@ mod tests {
                 KeystoreSigner::from_password(&keys_path, password.as_bytes(), chain_config)
                     .expect("to create keystore signer from password");
 
-            assert_eq!(keystore_signer_from_password.keypairs.len(), 3);
+            let expected_pubkey = BlsPublicKey::try_from(public_key_bytes.as_ref()).unwrap();
+            assert_eq!(keystore_signer_from_password.signers.len(), 3);
             assert_eq!(
-                keystore_signer_from_password
-                    .keypairs
-                    .first()
-                    .expect("to get keypair")
-                    .pk
-                    .to_string(),
-                public_key
+                keystore_signer_from_password.signers.first().expect("to get signer").pubkey(),
+                expected_pubkey
             );
 
             let keystore_signer_from_directory = KeystoreSigner::from_secrets_directory(
@@ -347,15 +310,10 @@ mod tests {
             )
             .expect("to create keystore signer from secrets dir");
 
-            assert_eq!(keystore_signer_from_directory.keypairs.len(), 3);
+            assert_eq!(keystore_signer_from_directory.signers.len(), 3);
             assert_eq!(
-                keystore_signer_from_directory
-                    .keypairs
-                    .first()
-                    .expect("to get keypair")
-                    .pk
-                    .to_string(),
-                public_key
+                keystore_signer_from_directory.signers.first().expect("to get signer").pubkey(),
+                expected_pubkey
             );
 
             // 2. Sign a message with the signer and check the signature
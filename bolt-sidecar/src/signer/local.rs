@@ -1,14 +1,17 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, path::Path};
 
-use blst::{
-    min_pk::{SecretKey, Signature},
-    BLST_ERROR,
-};
+use blst::min_pk::{SecretKey, Signature};
 use ethereum_consensus::{crypto::PublicKey as ClPublicKey, deneb::compute_signing_root};
 
-use crate::{config::ChainConfig, crypto::bls::BLSSig};
+use crate::{
+    config::ChainConfig,
+    crypto::bls::{verify_signing_root, BLSSig},
+};
 
-use super::SignerResult;
+use super::{
+    keystore::{read_keystore, KeystoreError},
+    SignerResult,
+};
 
 /// The BLS Domain Separator used in Ethereum 2.0.
 pub const BLS_DST_PREFIX: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
@@ -21,6 +24,8 @@ pub enum LocalSignerError {
     SigningRootComputation(#[from] ethereum_consensus::error::Error),
     #[error("invalid signature: {0}")]
     InvalidSignature(String),
+    #[error("invalid secret key: {0}")]
+    InvalidSecretKey(String),
 }
 
 /// A BLS signer that can sign any type that implements the [`SignableBLS`] trait.
@@ -45,6 +50,28 @@ impl LocalSigner {
         Self { key, chain }
     }
 
+    /// Create a new signer from a hex-encoded BLS secret key, with or without `0x` prefix.
+    pub fn from_hex(key: &str, chain: ChainConfig) -> SignerResult<Self> {
+        let bytes = alloy::hex::decode(key)
+            .map_err(|e| LocalSignerError::InvalidSecretKey(e.to_string()))?;
+        let key = SecretKey::from_bytes(&bytes)
+            .map_err(|e| LocalSignerError::InvalidSecretKey(format!("{e:?}")))?;
+        Ok(Self::new(key, chain))
+    }
+
+    /// Create a new signer from the secret key of an EIP-2335 keystore file, decrypted with
+    /// the given password. This is the only place keystores are decrypted: the
+    /// [KeystoreSigner](super::KeystoreSigner) loads each of its keystores with it.
+    pub fn from_keystore(path: &Path, password: &[u8], chain: ChainConfig) -> SignerResult<Self> {
+        let keypair = read_keystore(path)?
+            .decrypt_keypair(password)
+            .map_err(|e| KeystoreError::KeypairDecryption(path.to_path_buf(), format!("{e:?}")))?;
+
+        let key = SecretKey::from_bytes(keypair.sk.serialize().as_bytes())
+            .map_err(|e| LocalSignerError::InvalidSecretKey(format!("{e:?}")))?;
+        Ok(Self::new(key, chain))
+    }
+
     /// Get the public key of the signer.
     pub fn pubkey(&self) -> ClPublicKey {
         let pk = self.key.sk_to_pk();
@@ -63,9 +90,8 @@ impl LocalSigner {
 
     /// Sign an SSZ object root with the given domain.
     pub fn sign_root(&self, root: [u8; 32], domain: [u8; 32]) -> SignerResult<BLSSig> {
-        let signing_root = compute_signing_root(&root, domain)
-            .map_err(LocalSignerError::SigningRootComputation)?;
-        let sig = self.key.sign(signing_root.as_slice(), BLS_DST_PREFIX, &[]);
+        let signing_root = self.signing_root(root, domain)?;
+        let sig = self.key.sign(&signing_root, self.chain.bls_dst(), &[]);
         Ok(BLSSig::from_slice(&sig.to_bytes()))
     }
 
@@ -94,16 +120,18 @@ impl LocalSigner {
         signature: &Signature,
         domain: [u8; 32],
     ) -> SignerResult<()> {
+        let signing_root = self.signing_root(root, domain)?;
+        let signature = signature.to_bytes();
+
+        verify_signing_root(&signing_root, &signature, &self.pubkey(), self.chain.bls_dst())
+            .map_err(|err| LocalSignerError::InvalidSignature(err.to_string()))?;
+        Ok(())
+    }
+
+    fn signing_root(&self, root: [u8; 32], domain: [u8; 32]) -> SignerResult<[u8; 32]> {
         let signing_root = compute_signing_root(&root, domain)
             .map_err(LocalSignerError::SigningRootComputation)?;
-        let pk = blst::min_pk::PublicKey::from_bytes(self.pubkey().as_ref()).unwrap();
-
-        let res = signature.verify(true, signing_root.as_ref(), BLS_DST_PREFIX, &[], &pk, true);
-        if res == BLST_ERROR::BLST_SUCCESS {
-            Ok(())
-        } else {
-            Err(LocalSignerError::InvalidSignature(format!("{res:?}")))?
-        }
+        Ok(signing_root.as_ref().try_into().expect("signing roots are 32 bytes"))
     }
 }
 
@@ -113,14 +141,21 @@ impl LocalSigner {
     pub fn random() -> Self {
         use crate::common::secrets::BlsSecretKeyWrapper;
 
-        Self { key: BlsSecretKeyWrapper::random().0, chain: ChainConfig::mainnet() }
+        Self::new(BlsSecretKeyWrapper::random().0, ChainConfig::mainnet())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{fs, path::Path};
+
+    use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
+
     use crate::{
-        crypto::bls::SignableBLS, signer::local::LocalSigner, test_util::TestSignableData,
+        config::ChainConfig,
+        crypto::bls::{BlsDst, SignableBLS},
+        signer::{keystore::KeystoreError, local::LocalSigner, SignerError},
+        test_util::TestSignableData,
     };
 
     use rand::Rng;
@@ -139,4 +174,53 @@ mod tests {
         let sig = blst::min_pk::Signature::from_bytes(signature.as_ref()).unwrap();
         assert!(signer.verify_commit_boost_root(msg.digest(), &sig).is_ok());
     }
+
+    #[test]
+    fn test_bls_signer_custom_dst() {
+        let key = LocalSigner::random().key;
+        let default_chain = ChainConfig::mainnet();
+        let nul_chain = ChainConfig { bls_dst: BlsDst::NUL, ..default_chain };
+        let custom_chain =
+            ChainConfig { bls_dst: "BOLT_TEST_DST_".parse().unwrap(), ..default_chain };
+
+        let root = [42; 32];
+        for chain in [nul_chain, custom_chain] {
+            let signer = LocalSigner::new(key.clone(), chain);
+            let signature = signer.sign_commit_boost_root(root).unwrap();
+            let sig = blst::min_pk::Signature::from_bytes(signature.as_ref()).unwrap();
+            assert!(signer.verify_commit_boost_root(root, &sig).is_ok());
+
+            // The signature is bound to its DST
+            let default_signer = LocalSigner::new(key.clone(), default_chain);
+            assert!(default_signer.verify_commit_boost_root(root, &sig).is_err());
+        }
+
+        // The default DST is the commit-boost one
+        assert_eq!(default_chain.bls_dst(), crate::crypto::bls::BLS_DST_PREFIX);
+        assert!("".parse::<BlsDst>().is_err());
+        assert!("a".repeat(256).parse::<BlsDst>().is_err());
+    }
+
+    #[test]
+    fn test_bls_signer_from_keystore() {
+        let pubkey = "0x9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07";
+        let test_data = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_data");
+        let keystore = test_data.join("keys").join(pubkey).join("voting-keystore-pbkdf2.json");
+        let password = fs::read_to_string(test_data.join("secrets").join(pubkey)).unwrap();
+
+        let signer =
+            LocalSigner::from_keystore(&keystore, password.as_bytes(), ChainConfig::mainnet())
+                .unwrap();
+        let expected = alloy::hex::decode(pubkey).unwrap();
+        assert_eq!(signer.pubkey(), BlsPublicKey::try_from(expected.as_slice()).unwrap());
+
+        let root = [42; 32];
+        let signature = signer.sign_commit_boost_root(root).unwrap();
+        let sig = blst::min_pk::Signature::from_bytes(signature.as_ref()).unwrap();
+        assert!(signer.verify_commit_boost_root(root, &sig).is_ok());
+
+        // The keystore can't be decrypted with another password
+        let err = LocalSigner::from_keystore(&keystore, b"wrong", ChainConfig::mainnet());
+        assert!(matches!(err, Err(SignerError::Keystore(KeystoreError::KeypairDecryption(..)))));
+    }
 }
@@ -0,0 +1,64 @@
+use alloy::primitives::keccak256;
+use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
+
+use crate::crypto::bls::BLSSig;
+
+use super::SignerResult;
+
+/// A deterministic stand-in for a BLS signer, for tests that don't need real cryptography.
+///
+/// Signatures are derived from the hash of the public key and the signed root: the same
+/// inputs always give the same signature, but it doesn't verify as a BLS signature.
+#[derive(Debug, Clone, Default)]
+pub struct MockSigner {
+    pubkey: BlsPublicKey,
+}
+
+impl MockSigner {
+    /// Create a new mock signer signing on behalf of the given public key.
+    pub fn new(pubkey: BlsPublicKey) -> Self {
+        Self { pubkey }
+    }
+
+    /// Get the public key of the signer.
+    pub fn pubkey(&self) -> BlsPublicKey {
+        self.pubkey.clone()
+    }
+
+    /// Sign an SSZ object root, as the other signers do with the Commit Boost domain.
+    pub fn sign_commit_boost_root(&self, root: [u8; 32]) -> SignerResult<BLSSig> {
+        let mut signature = [0; 96];
+        for (i, chunk) in signature.chunks_mut(32).enumerate() {
+            let hash = keccak256([self.pubkey.as_ref(), root.as_slice(), &[i as u8]].concat());
+            chunk.copy_from_slice(hash.as_slice());
+        }
+
+        Ok(BLSSig::from(signature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::hex;
+    use ethereum_consensus::crypto::PublicKey as BlsPublicKey;
+
+    use crate::signer::SignerBLS;
+
+    use super::MockSigner;
+
+    #[test]
+    fn test_mock_signer_is_deterministic() {
+        let pubkey = hex!("83eeddfac5e60f8fe607ee8713efb8877c295ad9f8ca075f4d8f6f2ae241a30dd57f78f6f3863a9fe0d5b5db9d550b93");
+        let pubkey = BlsPublicKey::try_from(pubkey.as_ref()).unwrap();
+        let signer = MockSigner::new(pubkey.clone());
+
+        let signature = signer.sign_commit_boost_root([42; 32]).unwrap();
+        assert_eq!(signature, MockSigner::new(pubkey).sign_commit_boost_root([42; 32]).unwrap());
+
+        // Different roots and keys give different signatures
+        assert_ne!(signature, signer.sign_commit_boost_root([43; 32]).unwrap());
+        assert_ne!(signature, MockSigner::default().sign_commit_boost_root([42; 32]).unwrap());
+
+        assert_eq!(SignerBLS::Mock(signer.clone()).available_pubkeys(), [signer.pubkey()].into());
+    }
+}
@@ -1,6 +1,14 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, path::PathBuf};
 
 use ethereum_consensus::crypto::bls::PublicKey as BlsPublicKey;
+use serde::Deserialize;
+use tokio::sync::oneshot;
+
+use crate::config::ChainConfig;
+
+/// Signer aggregating the signatures of several BLS keys.
+pub mod aggregate;
+pub use aggregate::AggregateSigner;
 
 /// Commit-Boost remote signer client wrapper.
 pub mod commit_boost;
@@ -14,6 +22,12 @@ pub use keystore::KeystoreSigner;
 pub mod local;
 pub use local::LocalSigner;
 
+/// Deterministic signer stub for tests.
+#[cfg(feature = "mock-signer")]
+pub mod mock;
+#[cfg(feature = "mock-signer")]
+pub use mock::MockSigner;
+
 /// Error in the signer.
 #[derive(Debug, thiserror::Error)]
 #[allow(missing_docs)]
@@ -24,6 +38,8 @@ pub enum SignerError {
     CommitBoost(#[from] commit_boost::CommitBoostError),
     #[error("keystore signer error: {0}")]
     Keystore(#[from] keystore::KeystoreError),
+    #[error("aggregate signer error: {0}")]
+    Aggregate(#[from] aggregate::AggregateSignerError),
 }
 
 /// Result type for the signer.
@@ -38,6 +54,12 @@ pub enum SignerBLS {
     CommitBoost(CommitBoostSigner),
     /// Signer consisting of multiple keypairs loaded from ERC-2335 keystores files.
     Keystore(KeystoreSigner),
+    /// Signer aggregating the signature of a local BLS secret key with the ones of remote
+    /// co-signers that proved the possession of their keys.
+    Aggregate(AggregateSigner),
+    /// Deterministic signer stub, whose signatures don't verify. Only for tests.
+    #[cfg(feature = "mock-signer")]
+    Mock(MockSigner),
 }
 
 impl SignerBLS {
@@ -47,6 +69,102 @@ impl SignerBLS {
             Self::Local(signer) => [signer.pubkey()].into(),
             Self::CommitBoost(signer) => [signer.pubkey()].into(),
             Self::Keystore(signer) => signer.pubkeys(),
+            Self::Aggregate(signer) => [signer.pubkey()].into(),
+            #[cfg(feature = "mock-signer")]
+            Self::Mock(signer) => [signer.pubkey()].into(),
         }
     }
 }
+
+/// The source of the BLS keys to rotate the constraint signer to.
+///
+/// Only references to keys on the sidecar host are accepted, so that no secret is ever sent
+/// to the commitments API.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SignerSource {
+    /// The path of the directory of ERC-2335 keystores.
+    pub keystore_path: PathBuf,
+    /// The path of the directory of the keystore passwords, each in a file named after the
+    /// public key of its keystore.
+    pub keystore_secrets_path: PathBuf,
+}
+
+impl SignerSource {
+    /// Load the signer from this source. Decrypting keystores is CPU-intensive, so this
+    /// should not be called on the async runtime.
+    pub fn load(&self, chain: ChainConfig) -> SignerResult<SignerBLS> {
+        Ok(SignerBLS::Keystore(KeystoreSigner::from_secrets_directory(
+            &self.keystore_path,
+            &self.keystore_secrets_path,
+            chain,
+        )?))
+    }
+}
+
+/// Error rotating the constraint signer.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum SignerRotationError {
+    #[error(transparent)]
+    Signer(#[from] SignerError),
+    #[error("signer loading task failed: {0}")]
+    Task(#[from] tokio::task::JoinError),
+    #[error("keys are not delegatees of any delegation: {0:?}")]
+    NotDelegated(Vec<BlsPublicKey>),
+    #[error("keys are not verified by the bolt manager: {0}")]
+    Unverified(String),
+}
+
+/// Request to rotate the constraint signer to the keys of the given source, without
+/// restarting the sidecar.
+#[derive(Debug)]
+pub struct SignerRotationRequest {
+    /// The source of the new keys
+    pub source: SignerSource,
+    /// Channel to send the public keys of the new signer to
+    pub response_tx: oneshot::Sender<Result<HashSet<BlsPublicKey>, SignerRotationError>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use serde_json::json;
+
+    use crate::{config::ChainConfig, signer::local::LocalSigner};
+
+    use super::SignerSource;
+
+    /// The secret key of the test keystores, from the EIP-2335 test cases.
+    const TEST_KEYSTORE_SECRET_KEY: &str =
+        "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+
+    #[test]
+    fn test_signer_source_loads_keystores() {
+        let chain = ChainConfig::mainnet();
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let keystore_path = root.join("test_data/keys");
+        let keystore_secrets_path = root.join("test_data/secrets");
+
+        let source: SignerSource = serde_json::from_value(json!({
+            "keystorePath": keystore_path,
+            "keystoreSecretsPath": keystore_secrets_path,
+        }))
+        .unwrap();
+
+        let signer = source.load(chain).unwrap();
+        let pubkey = LocalSigner::from_hex(TEST_KEYSTORE_SECRET_KEY, chain).unwrap().pubkey();
+        assert_eq!(signer.available_pubkeys(), [pubkey].into());
+
+        // Secrets are never accepted inline
+        let with_password = json!({
+            "keystorePath": keystore_path,
+            "keystoreSecretsPath": keystore_secrets_path,
+            "keystorePassword": "password",
+        });
+        assert!(serde_json::from_value::<SignerSource>(with_password).is_err());
+        let secret_key = json!({ "secretKey": TEST_KEYSTORE_SECRET_KEY });
+        assert!(serde_json::from_value::<SignerSource>(secret_key).is_err());
+    }
+}
@@ -0,0 +1,289 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use alloy::primitives::TxHash;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tracing::{error, warn};
+
+use crate::{api::spec::BuilderApiError, primitives::SignedConstraints};
+
+/// The number of slots for which the relay responses to the constraints submissions are
+/// kept, so that they can be audited after the block has been proposed.
+pub const COMMITMENT_STATUS_RETENTION_SLOTS: u64 = 64;
+
+/// The outcome of the submission of a commitment's constraints to a relay.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelaySubmission {
+    /// The origin of the relay URL, i.e. its scheme, host and port. Any credentials, path or
+    /// query of the URL are left out, as the status is served to unauthenticated callers.
+    pub relay: String,
+    /// Whether the relay accepted the constraints.
+    pub accepted: bool,
+    /// The response body of the relay, `null` if it was empty. For rejected submissions,
+    /// this is the error returned by the relay, or the error of the request.
+    pub response: Value,
+}
+
+impl RelaySubmission {
+    /// Create the submission outcome from the result of posting constraints to a relay.
+    pub fn new(relay: &Url, result: &Result<Value, BuilderApiError>) -> Self {
+        let (accepted, response) = match result {
+            Ok(response) => (true, response.clone()),
+            Err(BuilderApiError::FailedSubmittingConstraints(error)) => {
+                (false, serde_json::to_value(error).unwrap_or_default())
+            }
+            Err(err) => (false, Value::String(err.to_string())),
+        };
+
+        Self { relay: relay.origin().ascii_serialization(), accepted, response }
+    }
+}
+
+/// The status of an inclusion commitment, as returned by `bolt_getCommitmentStatus`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitmentStatus {
+    /// The target slot of the commitment.
+    pub slot: u64,
+    /// The hash of the committed transaction.
+    pub tx_hash: TxHash,
+    /// The outcome of the latest submission of the commitment's constraints to each relay.
+    pub relays: Vec<RelaySubmission>,
+    /// Whether the commitment is degraded: no relay accepted its constraints, and the
+    /// transaction was broadcast to the public mempool instead as a last resort. Its inclusion
+    /// in the target slot is then not enforced.
+    #[serde(default)]
+    pub degraded: bool,
+    /// Whether the commitment was evicted from its slot by a higher-priority commitment
+    /// before the deadline. Its constraints were then never submitted, and its inclusion
+    /// won't be enforced.
+    #[serde(default)]
+    pub evicted: bool,
+}
+
+/// The submissions of the constraints of a committed transaction.
+#[derive(Debug, Clone, Default)]
+struct Submissions {
+    relays: Vec<RelaySubmission>,
+    degraded: bool,
+    evicted: bool,
+}
+
+/// The submissions of the retained slots, indexed by slot and transaction hash.
+type SlotSubmissions = BTreeMap<u64, HashMap<TxHash, Submissions>>;
+
+/// The relay responses to the constraints submissions, indexed by slot and transaction hash.
+///
+/// The store is cheap to clone and all clones share the same state, so that the driver can
+/// record submissions while the commitments API serves them.
+///
+/// The statuses are kept in memory, and optionally persisted to a file so that they can still
+/// be audited after a restart. As for the payload store, the file is only read when the store
+/// is created, and written by a background task so that the file I/O never stalls the driver.
+#[derive(Debug, Clone, Default)]
+pub struct CommitmentStatuses {
+    submissions: Arc<RwLock<SlotSubmissions>>,
+    /// Channel to the task writing the statuses to the store file, if they are persisted.
+    persist_tx: Option<mpsc::UnboundedSender<Vec<CommitmentStatus>>>,
+}
+
+impl CommitmentStatuses {
+    /// Create a new store persisted to the file at the given path, loading the statuses it
+    /// holds if any, and spawn its writer task. The file is created on the first write if it
+    /// doesn't exist.
+    pub fn persistent(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+
+        let mut submissions = SlotSubmissions::new();
+        match load(&path) {
+            Ok(statuses) => {
+                for CommitmentStatus { slot, tx_hash, relays, degraded, evicted } in statuses {
+                    let slot_submissions = submissions.entry(slot).or_default();
+                    slot_submissions.insert(tx_hash, Submissions { relays, degraded, evicted });
+                }
+            }
+            Err(err) => warn!(?err, ?path, "Failed to load the commitment status file"),
+        }
+
+        let (persist_tx, mut persist_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(statuses) = persist_rx.recv().await {
+                let path = path.clone();
+                match tokio::task::spawn_blocking(move || write(&path, &statuses)).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(err)) => error!(?err, "Failed to write the commitment status file"),
+                    Err(err) => error!(?err, "Commitment status write task failed"),
+                }
+            }
+        });
+
+        Self { submissions: Arc::new(RwLock::new(submissions)), persist_tx: Some(persist_tx) }
+    }
+
+    /// Queue a write of the given submissions to the store file, if the statuses are
+    /// persisted. Called with the write lock held, so that the writes are queued in order.
+    fn persist(&self, submissions: &SlotSubmissions) {
+        let Some(persist_tx) = &self.persist_tx else { return };
+
+        let statuses = submissions
+            .iter()
+            .flat_map(|(slot, txs)| {
+                txs.iter().map(|(tx_hash, s)| CommitmentStatus {
+                    slot: *slot,
+                    tx_hash: *tx_hash,
+                    relays: s.relays.clone(),
+                    degraded: s.degraded,
+                    evicted: s.evicted,
+                })
+            })
+            .collect();
+
+        if persist_tx.send(statuses).is_err() {
+            error!("The commitment status writer task is not running");
+        }
+    }
+
+    /// Record the outcome of the submission of the given constraints to a relay, replacing
+    /// the outcome of any previous attempt to the same relay. Slots older than the retention
+    /// period are pruned.
+    pub fn record(
+        &self,
+        slot: u64,
+        constraints: &[SignedConstraints],
+        submission: RelaySubmission,
+    ) {
+        let mut submissions = self.submissions.write().expect("lock not poisoned");
+
+        let slot_submissions = submissions.entry(slot).or_default();
+        let txs = constraints.iter().flat_map(|sc| sc.message.transactions.iter());
+        for tx in txs {
+            let relays = &mut slot_submissions.entry(*tx.hash()).or_default().relays;
+            match relays.iter_mut().find(|s| s.relay == submission.relay) {
+                Some(previous) => *previous = submission.clone(),
+                None => relays.push(submission.clone()),
+            }
+        }
+
+        let oldest = slot.saturating_sub(COMMITMENT_STATUS_RETENTION_SLOTS);
+        submissions.retain(|s, _| *s >= oldest);
+        self.persist(&submissions);
+    }
+
+    /// Mark the commitment to the given transaction in the given slot as degraded, after its
+    /// transaction was broadcast to the public mempool because no relay accepted it.
+    pub fn mark_degraded(&self, slot: u64, tx_hash: TxHash) {
+        let mut submissions = self.submissions.write().expect("lock not poisoned");
+        submissions.entry(slot).or_default().entry(tx_hash).or_default().degraded = true;
+        self.persist(&submissions);
+    }
+
+    /// Mark the commitment to the given transaction in the given slot as evicted by a
+    /// higher-priority commitment, so that its holder can learn that it won't be honored.
+    pub fn mark_evicted(&self, slot: u64, tx_hash: TxHash) {
+        let mut submissions = self.submissions.write().expect("lock not poisoned");
+        submissions.entry(slot).or_default().entry(tx_hash).or_default().evicted = true;
+        self.persist(&submissions);
+    }
+
+    /// Returns true if the relays failed the latest slot with submitted constraints, i.e. the
+    /// transactions of that slot were all broadcast to the public mempool instead. New
+    /// commitments are then expected to be degraded too.
+    pub fn relays_failing(&self) -> bool {
+        let submissions = self.submissions.read().expect("lock not poisoned");
+        submissions
+            .values()
+            .rev()
+            .map(|txs| txs.values().filter(|s| !s.evicted).collect::<Vec<_>>())
+            .find(|txs| !txs.is_empty())
+            .is_some_and(|txs| txs.iter().all(|s| s.degraded))
+    }
+
+    /// Returns the status of the commitment to the given transaction in the given slot, if
+    /// its constraints have been submitted or it was evicted.
+    pub fn get(&self, slot: u64, tx_hash: TxHash) -> Option<CommitmentStatus> {
+        let submissions = self.submissions.read().expect("lock not poisoned");
+        let Submissions { relays, degraded, evicted } =
+            submissions.get(&slot)?.get(&tx_hash)?.clone();
+        Some(CommitmentStatus { slot, tx_hash, relays, degraded, evicted })
+    }
+}
+
+/// Load the statuses in the store file at the given path, if it exists.
+fn load(path: &Path) -> io::Result<Vec<CommitmentStatus>> {
+    match fs::read(path) {
+        Ok(contents) => Ok(serde_json::from_slice(&contents)?),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Write the statuses to the store file at the given path, replacing its contents.
+fn write(path: &Path, statuses: &[CommitmentStatus]) -> io::Result<()> {
+    let contents = serde_json::to_vec(statuses)?;
+
+    // Write to a temporary file first so that the store is never left half-written
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, contents)?;
+    fs::rename(tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::TxHash;
+    use reqwest::Url;
+    use serde_json::json;
+
+    use super::{CommitmentStatuses, RelaySubmission};
+    use crate::{
+        api::spec::{BuilderApiError, ErrorResponse},
+        test_util::harness::wait_until,
+    };
+
+    #[test]
+    fn test_relay_submission_leaves_out_credentials_and_path() {
+        let relay = Url::parse("https://0xabcd@relay.example.org:8443/api/key?token=secret")
+            .expect("valid URL");
+
+        let submission = RelaySubmission::new(&relay, &Ok(json!({ "accepted": true })));
+        assert_eq!(submission.relay, "https://relay.example.org:8443");
+        assert!(submission.accepted);
+
+        let error = ErrorResponse::new(400, "invalid constraints");
+        let submission =
+            RelaySubmission::new(&relay, &Err(BuilderApiError::FailedSubmittingConstraints(error)));
+        assert_eq!(submission.relay, "https://relay.example.org:8443");
+        assert!(!submission.accepted);
+    }
+
+    #[tokio::test]
+    async fn test_persistent_statuses_survive_restart() -> eyre::Result<()> {
+        let path = std::env::temp_dir()
+            .join(format!("bolt-commitment-status-{}.json", rand::random::<u64>()));
+        let tx_hash = TxHash::random();
+
+        let statuses = CommitmentStatuses::persistent(&path);
+        statuses.mark_evicted(10, tx_hash);
+        assert!(statuses.get(10, tx_hash).expect("status is recorded").evicted);
+        wait_until(|| async { path.exists() }).await?;
+        drop(statuses);
+
+        // A new store backed by the same file serves the recorded statuses
+        let statuses = CommitmentStatuses::persistent(&path);
+        let status = statuses.get(10, tx_hash).expect("status is loaded");
+        assert!(status.evicted);
+        assert!(!status.degraded);
+        assert!(statuses.get(11, tx_hash).is_none());
+
+        std::fs::remove_file(path)?;
+
+        Ok(())
+    }
+}
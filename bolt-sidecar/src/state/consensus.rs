@@ -1,4 +1,5 @@
 use std::{
+    collections::HashSet,
     fmt,
     time::{Duration, Instant},
 };
@@ -28,6 +29,10 @@ pub enum ConsensusError {
     DeadlineExceeded,
     #[error("Validator not found in the slot")]
     ValidatorNotFound,
+    #[error("Slot {0} is beyond the lookahead of {1} slots")]
+    BeyondLookahead(Slot, u64),
+    #[error("Slot {0} is not proposed by a validator of this sidecar")]
+    NotOwnedProposer(Slot),
 }
 
 /// Represents an epoch in the beacon chain.
@@ -72,6 +77,14 @@ pub struct ConsensusState {
     /// It is considered unsafe because it is possible for the next epoch's duties to
     /// change if there are beacon chain deposits or withdrawals in the current epoch.
     unsafe_lookahead_enabled: bool,
+    /// The number of slots in an epoch, which depends on the preset of the chain.
+    slots_per_epoch: u64,
+    /// The maximum number of slots ahead of the latest one for which commitments are accepted,
+    /// within the proposer duties window. If `None`, the whole window is accepted.
+    max_lookahead_slots: Option<u64>,
+    /// The validators controlled by this sidecar. If set, commitments are only accepted for the
+    /// slots they propose (strict proposer mode).
+    owned_validators: Option<HashSet<BlsPublicKey>>,
 }
 
 impl fmt::Debug for ConsensusState {
@@ -83,6 +96,9 @@ impl fmt::Debug for ConsensusState {
             .field("commitment_deadline", &self.commitment_deadline)
             .field("commitment_deadline_duration", &self.commitment_deadline_duration)
             .field("unsafe_lookahead_enabled", &self.unsafe_lookahead_enabled)
+            .field("slots_per_epoch", &self.slots_per_epoch)
+            .field("max_lookahead_slots", &self.max_lookahead_slots)
+            .field("owned_validators", &self.owned_validators)
             .finish()
     }
 }
@@ -102,6 +118,37 @@ impl ConsensusState {
             commitment_deadline: CommitmentDeadline::new(0, commitment_deadline_duration),
             commitment_deadline_duration,
             unsafe_lookahead_enabled,
+            slots_per_epoch: SLOTS_PER_EPOCH,
+            max_lookahead_slots: None,
+            owned_validators: None,
+        }
+    }
+
+    /// Sets the number of slots in an epoch, for chains that don't use the mainnet preset.
+    pub fn with_slots_per_epoch(mut self, slots_per_epoch: u64) -> Self {
+        self.slots_per_epoch = slots_per_epoch;
+        self
+    }
+
+    /// Sets the maximum number of slots ahead of the latest one for which commitments are
+    /// accepted. If `None`, any slot in the proposer duties window is accepted.
+    pub fn with_max_lookahead_slots(mut self, max_lookahead_slots: Option<u64>) -> Self {
+        self.max_lookahead_slots = max_lookahead_slots;
+        self
+    }
+
+    /// Enables the strict proposer mode: commitments are only accepted for the slots proposed
+    /// by the given validators, which are those controlled by this sidecar.
+    pub fn with_owned_validators(mut self, owned_validators: HashSet<BlsPublicKey>) -> Self {
+        self.owned_validators = Some(owned_validators);
+        self
+    }
+
+    /// Updates the validators controlled by this sidecar, e.g. after a signer rotation.
+    /// This is a no-op unless the strict proposer mode is enabled.
+    pub fn update_owned_validators(&mut self, owned_validators: HashSet<BlsPublicKey>) {
+        if let Some(owned) = &mut self.owned_validators {
+            *owned = owned_validators;
         }
     }
 
@@ -109,24 +156,53 @@ impl ConsensusState {
     /// The request is valid if:
     ///
     /// 1. The target slot is scheduled to be proposed by one of our validators.
-    /// 2. The request hasn't passed the slot deadline.
+    /// 2. The target slot is within the maximum lookahead, if set.
+    /// 3. The request hasn't passed the slot deadline.
+    /// 4. In strict proposer mode, the proposer of the target slot is controlled by this sidecar.
     ///
     /// If the request is valid, return the validator public key for the target slot.
     pub fn validate_request(&self, req: &InclusionRequest) -> Result<BlsPublicKey, ConsensusError> {
+        self.validate_slot(req.slot)
+    }
+
+    /// Validate a target slot of a commitment request, like [Self::validate_request]. This
+    /// allows checking each slot of a ranged request without changing the request itself.
+    pub fn validate_slot(&self, slot: u64) -> Result<BlsPublicKey, ConsensusError> {
         // Check if the slot is in the current epoch or next epoch (if unsafe lookahead is enabled)
-        if req.slot < self.epoch.start_slot || req.slot >= self.furthest_slot() {
-            return Err(ConsensusError::InvalidSlot(req.slot));
+        if slot < self.epoch.start_slot || slot >= self.furthest_slot() {
+            return Err(ConsensusError::InvalidSlot(slot));
+        }
+
+        if let Some(max_lookahead) = self.max_lookahead_slots {
+            if slot > self.latest_slot + max_lookahead {
+                return Err(ConsensusError::BeyondLookahead(slot, max_lookahead));
+            }
         }
 
         // If the request is for the next slot, check if it's within the commitment deadline
-        if req.slot == self.latest_slot + 1 &&
+        if slot == self.latest_slot + 1 &&
             self.latest_slot_timestamp + self.commitment_deadline_duration < Instant::now()
         {
             return Err(ConsensusError::DeadlineExceeded);
         }
 
         // Find the validator pubkey for the given slot from the proposer duties
-        self.find_validator_pubkey_for_slot(req.slot)
+        let pubkey = self.find_validator_pubkey_for_slot(slot)?;
+
+        if self.owned_validators.as_ref().is_some_and(|owned| !owned.contains(&pubkey)) {
+            return Err(ConsensusError::NotOwnedProposer(slot));
+        }
+
+        Ok(pubkey)
+    }
+
+    /// Returns `true` if the commitment deadline of the given slot has been reached, i.e. its
+    /// constraints have been submitted to the relays already.
+    pub fn deadline_reached(&self, slot: u64) -> bool {
+        slot <= self.latest_slot ||
+            (slot == self.latest_slot + 1 &&
+                self.latest_slot_timestamp + self.commitment_deadline_duration <
+                    Instant::now())
     }
 
     /// Wait for the commitment deadline to expire.
@@ -148,13 +224,13 @@ impl ConsensusState {
         self.latest_slot = slot;
 
         // Calculate the current value of epoch
-        let epoch = slot / SLOTS_PER_EPOCH;
+        let epoch = slot / self.slots_per_epoch;
 
         // If the epoch has changed, update the proposer duties
         if epoch != self.epoch.value {
             debug!("Updating epoch to {epoch}");
             self.epoch.value = epoch;
-            self.epoch.start_slot = epoch * SLOTS_PER_EPOCH;
+            self.epoch.start_slot = epoch * self.slots_per_epoch;
 
             self.fetch_proposer_duties(epoch).await?;
         } else if self.epoch.proposer_duties.is_empty() {
@@ -192,7 +268,10 @@ impl ConsensusState {
     }
 
     /// Finds the validator public key for the given slot from the proposer duties.
-    fn find_validator_pubkey_for_slot(&self, slot: u64) -> Result<BlsPublicKey, ConsensusError> {
+    pub fn find_validator_pubkey_for_slot(
+        &self,
+        slot: u64,
+    ) -> Result<BlsPublicKey, ConsensusError> {
         self.epoch
             .proposer_duties
             .iter()
@@ -205,8 +284,8 @@ impl ConsensusState {
     /// the current epoch or next epoch (if unsafe lookahead is enabled)
     fn furthest_slot(&self) -> u64 {
         self.epoch.start_slot +
-            SLOTS_PER_EPOCH +
-            if self.unsafe_lookahead_enabled { SLOTS_PER_EPOCH } else { 0 }
+            self.slots_per_epoch +
+            if self.unsafe_lookahead_enabled { self.slots_per_epoch } else { 0 }
     }
 }
 
@@ -217,7 +296,7 @@ mod tests {
     use tracing::warn;
 
     use super::*;
-    use crate::test_util::try_get_beacon_api_url;
+    use crate::{common::secrets::BlsSecretKeyWrapper, test_util::try_get_beacon_api_url};
 
     #[tokio::test]
     async fn test_update_slot() -> eyre::Result<()> {
@@ -241,6 +320,9 @@ mod tests {
             commitment_deadline: CommitmentDeadline::new(0, commitment_deadline_duration),
             commitment_deadline_duration,
             unsafe_lookahead_enabled: false,
+            slots_per_epoch: SLOTS_PER_EPOCH,
+            max_lookahead_slots: None,
+            owned_validators: None,
         };
 
         // Update the slot to 32
@@ -287,6 +369,9 @@ mod tests {
             commitment_deadline_duration,
             // We test for both epochs
             unsafe_lookahead_enabled: true,
+            slots_per_epoch: SLOTS_PER_EPOCH,
+            max_lookahead_slots: None,
+            owned_validators: None,
         };
 
         let epoch =
@@ -298,4 +383,95 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_reject_slot_beyond_lookahead() {
+        let beacon_client = BeaconClient::new(Url::parse("http://localhost:5052").unwrap());
+        let mut state = ConsensusState::new(beacon_client, Duration::from_secs(1), false)
+            .with_max_lookahead_slots(Some(4));
+
+        state.latest_slot = 33;
+        state.epoch = Epoch {
+            value: 1,
+            start_slot: 32,
+            proposer_duties: (32..64)
+                .map(|slot| ProposerDuty {
+                    public_key: BlsPublicKey::default(),
+                    validator_index: slot as usize,
+                    slot,
+                })
+                .collect(),
+        };
+
+        let request = |slot| InclusionRequest {
+            slot,
+            txs: Vec::new(),
+            top: false,
+            reverting_tx_hashes: Vec::new(),
+            max_slot: None,
+            webhook: None,
+            digest_version: Default::default(),
+            signature: None,
+            signer: None,
+        };
+
+        assert!(state.validate_request(&request(37)).is_ok());
+        assert!(matches!(
+            state.validate_request(&request(38)),
+            Err(ConsensusError::BeyondLookahead(38, 4))
+        ));
+
+        // Without a maximum, the whole epoch of proposer duties is accepted, but not beyond
+        state.max_lookahead_slots = None;
+        assert!(state.validate_request(&request(63)).is_ok());
+        assert!(matches!(
+            state.validate_request(&request(64)),
+            Err(ConsensusError::InvalidSlot(64))
+        ));
+    }
+
+    #[test]
+    fn test_strict_proposer_rejects_non_owned_slots() {
+        let random_pubkey = || {
+            let pk = BlsSecretKeyWrapper::random().0.sk_to_pk();
+            BlsPublicKey::try_from(pk.to_bytes().as_slice()).unwrap()
+        };
+        let (owned, other) = (random_pubkey(), random_pubkey());
+
+        let beacon_client = BeaconClient::new(Url::parse("http://localhost:5052").unwrap());
+        let mut state = ConsensusState::new(beacon_client, Duration::from_secs(1), false)
+            .with_owned_validators(HashSet::from([owned.clone()]));
+
+        state.latest_slot = 33;
+        state.epoch = Epoch {
+            value: 1,
+            start_slot: 32,
+            proposer_duties: vec![
+                ProposerDuty { public_key: owned.clone(), validator_index: 1, slot: 40 },
+                ProposerDuty { public_key: other.clone(), validator_index: 2, slot: 41 },
+            ],
+        };
+
+        let request = |slot| InclusionRequest {
+            slot,
+            txs: Vec::new(),
+            top: false,
+            reverting_tx_hashes: Vec::new(),
+            max_slot: None,
+            webhook: None,
+            digest_version: Default::default(),
+            signature: None,
+            signer: None,
+        };
+
+        assert_eq!(state.validate_request(&request(40)).unwrap(), owned);
+        assert!(matches!(
+            state.validate_request(&request(41)),
+            Err(ConsensusError::NotOwnedProposer(41))
+        ));
+
+        // Once the other validator is controlled too, its slot is accepted
+        state.update_owned_validators(HashSet::from([owned, other.clone()]));
+        assert_eq!(state.validate_request(&request(41)).unwrap(), other);
+    }
 }
@@ -0,0 +1,338 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+use crate::{
+    api::spec::ConstraintsApi,
+    common::backoff::{retry_with_policy, BackoffPolicy},
+    primitives::SignedConstraints,
+};
+
+/// Errors that can occur while reading or writing the constraints store.
+#[derive(Debug, thiserror::Error)]
+pub enum ConstraintsStoreError {
+    /// Failed to read or write the store file.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// Failed to de/serialize signed constraints.
+    #[error("Failed to de/serialize signed constraints: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// A file-backed store of the signed constraints issued by the sidecar, used to
+/// re-post them to the relays after a restart.
+///
+/// Constraints are stored as JSON lines, appended as soon as they are signed.
+#[derive(Debug, Clone)]
+pub struct ConstraintsStore {
+    path: PathBuf,
+}
+
+impl ConstraintsStore {
+    /// Create a new store backed by the file at the given path. The file is created
+    /// on the first write if it doesn't exist.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Returns the path of the store file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append the given signed constraints to the store.
+    pub fn append(&self, constraints: &SignedConstraints) -> Result<(), ConstraintsStoreError> {
+        let mut line = serde_json::to_vec(constraints)?;
+        line.push(b'\n');
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        file.write_all(&line)?;
+        Ok(())
+    }
+
+    /// Load all the signed constraints in the store. Lines that can't be decoded are skipped.
+    pub fn load(&self) -> Result<Vec<SignedConstraints>, ConstraintsStoreError> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut constraints = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str(&line) {
+                Ok(signed_constraints) => constraints.push(signed_constraints),
+                Err(err) => warn!(?err, "Skipping malformed entry in the constraints store"),
+            }
+        }
+
+        Ok(constraints)
+    }
+
+    /// Remove the constraints for slots before `slot` from the store.
+    pub fn prune_before(&self, slot: u64) -> Result<(), ConstraintsStoreError> {
+        self.retain(|sc| sc.message.slot >= slot)
+    }
+
+    /// Remove the given signed constraints from the store, e.g. after they were evicted.
+    pub fn remove(&self, constraints: &[SignedConstraints]) -> Result<(), ConstraintsStoreError> {
+        self.retain(|sc| !constraints.contains(sc))
+    }
+
+    /// Load the stored constraints for slots that haven't passed yet, i.e. from
+    /// `current_slot` onwards, and prune the past ones from the store. The senders of their
+    /// transactions are recovered, and constraints with an invalid signature are skipped.
+    pub fn load_pending(
+        &self,
+        current_slot: u64,
+    ) -> Result<Vec<SignedConstraints>, ConstraintsStoreError> {
+        let mut pending = Vec::new();
+        'constraints: for mut signed_constraints in self.load()? {
+            if signed_constraints.message.slot < current_slot {
+                continue;
+            }
+
+            for tx in &mut signed_constraints.message.transactions {
+                match tx.recover_signer() {
+                    Some(sender) => tx.sender = Some(sender),
+                    None => {
+                        warn!(hash = %tx.hash(), "Skipping invalid stored constraints");
+                        continue 'constraints;
+                    }
+                }
+            }
+            pending.push(signed_constraints);
+        }
+
+        self.prune_before(current_slot)?;
+        Ok(pending)
+    }
+
+    /// Spawn the task owning the store, and return a handle to it. All the writes go through
+    /// the task, which applies them in order and off the caller's task.
+    pub fn spawn(self) -> ConstraintsStoreHandle {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(command) = rx.recv().await {
+                let store = self.clone();
+                match tokio::task::spawn_blocking(move || store.apply(command)).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(err)) => error!(?err, "Failed to update the constraints store"),
+                    Err(err) => error!(?err, "Constraints store update task failed"),
+                }
+            }
+        });
+
+        ConstraintsStoreHandle { tx }
+    }
+
+    /// Apply a write command to the store.
+    fn apply(&self, command: StoreCommand) -> Result<(), ConstraintsStoreError> {
+        match command {
+            StoreCommand::Append(signed_constraints) => self.append(&signed_constraints),
+            StoreCommand::Remove(constraints) => self.remove(&constraints),
+            StoreCommand::PruneBefore(slot) => self.prune_before(slot),
+        }
+    }
+
+    /// Keep only the signed constraints for which `keep` returns `true` in the store.
+    fn retain(
+        &self,
+        keep: impl Fn(&SignedConstraints) -> bool,
+    ) -> Result<(), ConstraintsStoreError> {
+        let constraints = self.load()?;
+        let mut contents = Vec::new();
+        for signed_constraints in constraints.iter().filter(|sc| keep(sc)) {
+            serde_json::to_writer(&mut contents, signed_constraints)?;
+            contents.push(b'\n');
+        }
+
+        // Write to a temporary file first so that the store is never left half-written
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, contents)?;
+        fs::rename(tmp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+/// A write command to the task owning the constraints store.
+#[derive(Debug)]
+enum StoreCommand {
+    Append(SignedConstraints),
+    Remove(Vec<SignedConstraints>),
+    PruneBefore(u64),
+}
+
+/// A handle to the task owning a [ConstraintsStore], see [ConstraintsStore::spawn].
+///
+/// Writes are queued without blocking, so that the file I/O never stalls the driver loop.
+#[derive(Debug, Clone)]
+pub struct ConstraintsStoreHandle {
+    tx: mpsc::UnboundedSender<StoreCommand>,
+}
+
+impl ConstraintsStoreHandle {
+    /// Append the given signed constraints to the store.
+    pub fn append(&self, constraints: SignedConstraints) {
+        self.send(StoreCommand::Append(constraints));
+    }
+
+    /// Remove the given signed constraints from the store, e.g. after they were evicted.
+    pub fn remove(&self, constraints: Vec<SignedConstraints>) {
+        self.send(StoreCommand::Remove(constraints));
+    }
+
+    /// Remove the constraints for slots before `slot` from the store.
+    pub fn prune_before(&self, slot: u64) {
+        self.send(StoreCommand::PruneBefore(slot));
+    }
+
+    fn send(&self, command: StoreCommand) {
+        if self.tx.send(command).is_err() {
+            error!("The constraints store task is not running");
+        }
+    }
+}
+
+/// Re-post the given constraints to the relays, one submission per slot, retrying transient
+/// failures.
+///
+/// Returns the number of signed constraints successfully re-posted.
+pub async fn replay_constraints<C: ConstraintsApi>(
+    constraints_api: &C,
+    constraints: Vec<SignedConstraints>,
+) -> usize {
+    let mut batches = BTreeMap::<u64, Vec<SignedConstraints>>::new();
+    for signed_constraints in constraints {
+        batches.entry(signed_constraints.message.slot).or_default().push(signed_constraints);
+    }
+
+    let mut replayed = 0;
+    for (slot, batch) in batches {
+        let policy = BackoffPolicy::default();
+        match retry_with_policy(policy, || constraints_api.submit_constraints(&batch)).await {
+            Ok(_) => replayed += batch.len(),
+            Err(err) => error!(slot, ?err, "Failed to re-post persisted constraints"),
+        }
+    }
+
+    info!(replayed, "Replayed persisted constraints");
+    replayed
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{extract::State, routing::post, Json, Router};
+    use reqwest::{StatusCode, Url};
+    use tokio::net::TcpListener;
+
+    use crate::{
+        api::spec::SUBMIT_CONSTRAINTS_PATH,
+        client::ConstraintsClient,
+        common::secrets::BlsSecretKeyWrapper,
+        config::ChainConfig,
+        crypto::SignableBLS,
+        primitives::{ConstraintsMessage, SignedConstraints},
+        signer::local::LocalSigner,
+        test_util::harness::wait_until,
+    };
+
+    use super::{replay_constraints, ConstraintsStore};
+
+    type Received = Arc<Mutex<Vec<Vec<SignedConstraints>>>>;
+
+    /// Spawns a relay that records the submitted constraints.
+    async fn spawn_relay() -> (Url, Received) {
+        async fn submit(
+            State(received): State<Received>,
+            Json(constraints): Json<Vec<SignedConstraints>>,
+        ) -> StatusCode {
+            received.lock().unwrap().push(constraints);
+            StatusCode::OK
+        }
+
+        let received = Received::default();
+        let router =
+            Router::new().route(SUBMIT_CONSTRAINTS_PATH, post(submit)).with_state(received.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        (url, received)
+    }
+
+    fn signed_constraints(signer: &LocalSigner, slot: u64) -> SignedConstraints {
+        let message =
+            ConstraintsMessage { pubkey: signer.pubkey(), slot, top: false, transactions: vec![] };
+        let signature = signer.sign_commit_boost_root(message.digest()).unwrap();
+        SignedConstraints { message, signature }
+    }
+
+    #[tokio::test]
+    async fn test_replay_persisted_constraints() -> eyre::Result<()> {
+        let path = std::env::temp_dir()
+            .join(format!("bolt-constraints-store-{}.jsonl", rand::random::<u64>()));
+        let store = ConstraintsStore::new(&path);
+
+        let signer = LocalSigner::new(BlsSecretKeyWrapper::random().0, ChainConfig::default());
+        let past = signed_constraints(&signer, 9);
+        let future = signed_constraints(&signer, 11);
+        store.append(&past)?;
+        store.append(&future)?;
+        assert_eq!(store.load()?, vec![past, future.clone()]);
+
+        // Only the future-slot constraints are pending, and the past ones are pruned
+        let pending = store.load_pending(10)?;
+        assert_eq!(pending, vec![future.clone()]);
+        assert_eq!(store.load()?, vec![future.clone()]);
+
+        let (url, received) = spawn_relay().await;
+        let replayed = replay_constraints(&ConstraintsClient::new(url)?, pending).await;
+        assert_eq!(replayed, 1);
+        assert_eq!(*received.lock().unwrap(), vec![vec![future]]);
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_store_handle_applies_writes_in_order() -> eyre::Result<()> {
+        let path = std::env::temp_dir()
+            .join(format!("bolt-constraints-store-{}.jsonl", rand::random::<u64>()));
+        let store = ConstraintsStore::new(&path);
+        let handle = store.clone().spawn();
+
+        let signer = LocalSigner::new(BlsSecretKeyWrapper::random().0, ChainConfig::default());
+        let constraints = (8..12).map(|slot| signed_constraints(&signer, slot)).collect::<Vec<_>>();
+        for signed_constraints in &constraints {
+            handle.append(signed_constraints.clone());
+        }
+        handle.remove(vec![constraints[3].clone()]);
+        handle.prune_before(10);
+
+        let expected = vec![constraints[2].clone()];
+        wait_until(|| {
+            let store = store.clone();
+            let expected = expected.clone();
+            async move { store.load().is_ok_and(|loaded| loaded == expected) }
+        })
+        .await?;
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
@@ -1,26 +1,38 @@
 use alloy::{
     consensus::BlobTransactionValidationError,
-    eips::eip4844::MAX_BLOBS_PER_BLOCK,
-    primitives::{Address, U256},
+    eips::{eip2718::Encodable2718, eip4844::MAX_BLOBS_PER_BLOCK},
+    primitives::{Address, TxHash, U256},
+    rpc::types::TransactionReceipt,
     transports::TransportError,
 };
 use reth_primitives::{revm_primitives::EnvKzgSettings, PooledTransactionsElement};
-use std::{collections::HashMap, ops::Deref};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Deref,
+};
 use thiserror::Error;
-use tracing::{debug, error, trace, warn};
+use tracing::{debug, error, info, trace, warn};
 
 use crate::{
-    builder::BlockTemplate,
+    builder::{BlockTemplate, BlockTemplateError, ConflictPolicy, IncludeError},
+    client::{RpcClient, WebhookNotifier},
     common::{
         score_cache::ScoreCache,
-        transactions::{calculate_max_basefee, max_transaction_cost, validate_transaction},
+        transactions::{calculate_max_basefee, intrinsic_gas, max_transaction_cost},
     },
     config::limits::LimitsOpts,
-    primitives::{AccountState, InclusionRequest, SignedConstraints, Slot},
+    primitives::{AccountState, InclusionRequest, SignedConstraints, Slot, SlotInfo},
     telemetry::ApiMetrics,
 };
 
-use super::{account_state::AccountStateCache, fetcher::StateFetcher};
+use super::{
+    account_state::AccountStateCache, fetcher::StateFetcher, FeeAccounting, ReservationBook,
+};
+
+/// The maximum number of candidate slots of a ranged request that are validated against the
+/// execution state. Each validation may simulate the request, so this bounds the time the
+/// driver spends on a single request.
+pub const MAX_VALIDATED_CANDIDATE_SLOTS: usize = 4;
 
 /// Possible commitment validation errors.
 ///
@@ -45,27 +57,36 @@ pub enum ValidationError {
     /// The transaction nonce is too high.
     #[error("Transaction nonce too high. Expected {0}, got {1}")]
     NonceTooHigh(u64, u64),
+    /// The transaction creates a contract, and contract creations are not accepted.
+    #[error("Contract-creation transactions are not accepted")]
+    ContractCreation,
     /// The sender account is a smart contract and has code.
-    #[error("Account has code")]
+    #[error("Transaction sender is a smart contract, only EOAs can request commitments")]
     AccountHasCode,
     /// The gas limit is too high.
     #[error("Gas limit too high")]
     GasLimitTooHigh,
+    /// The gas limit of a transaction doesn't cover its intrinsic gas.
+    #[error("Gas limit too low. Expected at least {0}, got {1}")]
+    GasLimitTooLow(u64, u64),
     /// The transaction input size is too high.
     #[error("Transaction input size too high")]
     TransactionSizeTooHigh,
     /// Max priority fee per gas is greater than max fee per gas.
     #[error("Max priority fee per gas is greater than max fee per gas")]
     MaxPriorityFeePerGasTooHigh,
-    /// Max priority fee per gas is less than min priority fee.
-    #[error("Max priority fee per gas is less than min priority fee")]
-    MaxPriorityFeePerGasTooLow,
+    /// The effective priority fee at the maximum basefee is less than the min priority fee.
+    #[error("Priority fee is too low, need an effective tip of {0} wei at the maximum basefee")]
+    MaxPriorityFeePerGasTooLow(u128),
     /// The sender does not have enough balance to pay for the transaction.
     #[error("Not enough balance to pay for value + maximum fee")]
     InsufficientBalance,
     /// There are too many EIP-4844 transactions in the target block.
     #[error("Too many EIP-4844 transactions in target block")]
     Eip4844Limit,
+    /// The constraints could not be added to the block template.
+    #[error(transparent)]
+    BlockTemplate(#[from] BlockTemplateError),
     /// The maximum commitments have been reached for the slot.
     #[error("Already requested a preconfirmation for slot {0}. Slot must be >= {0}")]
     SlotTooLow(u64),
@@ -75,6 +96,12 @@ pub enum ValidationError {
     /// The maximum committed gas has been reached for the slot.
     #[error("Max committed gas reached for slot {0}: {1}")]
     MaxCommittedGasReachedForSlot(u64, u64),
+    /// The maximum committed value has been reached for the slot.
+    #[error("Max commitment value reached for slot {0}: {1}")]
+    MaxCommitmentValueReachedForSlot(u64, U256),
+    /// The maximum pending commitments have been reached for the request signer.
+    #[error("Max pending commitments reached for signer {0}: {1}")]
+    MaxPendingCommitmentsReachedForAccount(Address, usize),
     /// The signature is invalid.
     #[error("Invalid signature")]
     Signature(#[from] crate::primitives::commitment::SignatureError),
@@ -84,6 +111,18 @@ pub enum ValidationError {
     /// The transaction chain ID does not match the expected chain ID.
     #[error("Chain ID mismatch")]
     ChainIdMismatch,
+    /// A top-of-block commitment has already been made for the slot.
+    #[error("Top of block already committed for slot {0}")]
+    TopOfBlockTaken(u64),
+    /// An allowed revert doesn't refer to a transaction of the bundle.
+    #[error("Reverting transaction {0} is not part of the bundle")]
+    UnknownRevertingTx(TxHash),
+    /// A transaction of the bundle reverted when simulated in order on top of the pending state.
+    #[error("Bundle transaction {0} reverted in simulation")]
+    BundleReverted(TxHash),
+    /// An EIP-7702 authorization would bump the nonce of an account with pending commitments.
+    #[error("EIP-7702 authorization conflicts with the pending nonce of {0}")]
+    AuthorityNonceConflict(Address),
     /// NOTE: this should not be exposed to the user.
     #[error("Internal error: {0}")]
     Internal(String),
@@ -104,24 +143,48 @@ impl ValidationError {
             Self::MaxBaseFeeCalcOverflow => "max_base_fee_calc_overflow",
             Self::NonceTooLow(_, _) => "nonce_too_low",
             Self::NonceTooHigh(_, _) => "nonce_too_high",
+            Self::ContractCreation => "contract_creation",
             Self::AccountHasCode => "account_has_code",
             Self::GasLimitTooHigh => "gas_limit_too_high",
+            Self::GasLimitTooLow(_, _) => "gas_limit_too_low",
             Self::TransactionSizeTooHigh => "transaction_size_too_high",
             Self::MaxPriorityFeePerGasTooHigh => "max_priority_fee_per_gas_too_high",
-            Self::MaxPriorityFeePerGasTooLow => "max_priority_fee_per_gas_too_low",
+            Self::MaxPriorityFeePerGasTooLow(_) => "max_priority_fee_per_gas_too_low",
             Self::InsufficientBalance => "insufficient_balance",
             Self::Eip4844Limit => "eip4844_limit",
+            Self::BlockTemplate(_) => "block_template",
             Self::SlotTooLow(_) => "slot_too_low",
             Self::MaxCommitmentsReachedForSlot(_, _) => "max_commitments_reached_for_slot",
             Self::MaxCommittedGasReachedForSlot(_, _) => "max_committed_gas_reached_for_slot",
+            Self::MaxCommitmentValueReachedForSlot(_, _) => "max_commitment_value_reached_for_slot",
+            Self::MaxPendingCommitmentsReachedForAccount(_, _) => {
+                "max_pending_commitments_reached_for_account"
+            }
             Self::Signature(_) => "signature",
             Self::RecoverSigner => "recover_signer",
             Self::ChainIdMismatch => "chain_id_mismatch",
+            Self::TopOfBlockTaken(_) => "top_of_block_taken",
+            Self::UnknownRevertingTx(_) => "unknown_reverting_tx",
+            Self::BundleReverted(_) => "bundle_reverted",
+            Self::AuthorityNonceConflict(_) => "authority_nonce_conflict",
             Self::Internal(_) => "internal",
         }
     }
 }
 
+impl From<IncludeError> for ValidationError {
+    fn from(err: IncludeError) -> Self {
+        match err {
+            IncludeError::NonceTooLow { expected, got } => Self::NonceTooLow(expected, got),
+            IncludeError::NonceTooHigh { expected, got } => Self::NonceTooHigh(expected, got),
+            IncludeError::InsufficientBalance { .. } => Self::InsufficientBalance,
+            IncludeError::AccountHasCode => Self::AccountHasCode,
+            IncludeError::GasLimitTooHigh { .. } => Self::GasLimitTooHigh,
+            IncludeError::BaseFeeTooLow { base_fee, .. } => Self::BaseFeeTooLow(base_fee),
+        }
+    }
+}
+
 /// The minimal state of the execution layer at some block number (`head`).
 /// This is the state that is needed to simulate commitments.
 /// It contains per-address nonces and balances, as well as the minimum basefee.
@@ -140,6 +203,8 @@ pub struct ExecutionState<C> {
     slot: u64,
     /// The basefee at the head block.
     basefee: u128,
+    /// The basefee of the block following the head block, predicted by the execution client.
+    next_basefee: u128,
     /// The blob basefee at the head block.
     blob_basefee: u128,
     /// The cached account states. This should never be read directly.
@@ -164,6 +229,19 @@ pub struct ExecutionState<C> {
     client: C,
     /// Other values used for validation
     validation_params: ValidationParams,
+    /// The client broadcasting the committed transactions missed by their slot, if enabled.
+    post_slot_broadcast: Option<RpcClient>,
+    /// How nonce conflicts between constraints are resolved in the block templates.
+    conflict_policy: ConflictPolicy,
+    /// The notifier of the commitment webhooks, settled when the block templates are cleared.
+    webhooks: Option<WebhookNotifier>,
+    /// The pending inclusion reservations, counted in the slot limits.
+    reservations: Option<ReservationBook>,
+    /// The accounting of the fees of the commitments honored in the cleared block templates.
+    fee_accounting: Option<FeeAccounting>,
+    /// The commitments of each request signer that haven't landed yet, as the target slot
+    /// and transaction hashes of each commitment. Only tracked if the signers are capped.
+    pending_commitments: HashMap<Address, Vec<(Slot, Vec<TxHash>)>>,
 }
 
 /// Other values used for validation.
@@ -188,8 +266,9 @@ impl<C: StateFetcher> ExecutionState<C> {
     /// Creates a new state with the given client, initializing the
     /// basefee and head block number.
     pub async fn new(client: C, limits: LimitsOpts) -> Result<Self, TransportError> {
-        let (basefee, blob_basefee, block_number, chain_id) = tokio::try_join!(
+        let (basefee, next_basefee, blob_basefee, block_number, chain_id) = tokio::try_join!(
             client.get_basefee(None),
+            client.get_next_basefee(None),
             client.get_blob_basefee(None),
             client.get_head(),
             client.get_chain_id()
@@ -204,6 +283,7 @@ impl<C: StateFetcher> ExecutionState<C> {
 
         Ok(Self {
             basefee,
+            next_basefee,
             blob_basefee,
             block_number,
             chain_id,
@@ -216,14 +296,122 @@ impl<C: StateFetcher> ExecutionState<C> {
             kzg_settings: EnvKzgSettings::default(),
             // TODO: add a way to configure these values from CLI
             validation_params: ValidationParams::default(),
+            post_slot_broadcast: None,
+            conflict_policy: ConflictPolicy::default(),
+            webhooks: None,
+            reservations: None,
+            fee_accounting: None,
+            pending_commitments: HashMap::new(),
         })
     }
 
+    /// Broadcast the committed transactions that weren't included in their target slot to the
+    /// network with the given client once the slot has passed, when the block templates are
+    /// cleared on new heads.
+    pub fn with_post_slot_broadcast(mut self, client: RpcClient) -> Self {
+        self.post_slot_broadcast = Some(client);
+        self
+    }
+
+    /// Set the policy resolving nonce conflicts between the constraints of a block template.
+    pub fn with_conflict_policy(mut self, policy: ConflictPolicy) -> Self {
+        self.conflict_policy = policy;
+        self
+    }
+
+    /// Notify the webhooks of the commitments of their outcome once their slot has passed.
+    pub fn with_webhooks(mut self, webhooks: WebhookNotifier) -> Self {
+        self.webhooks = Some(webhooks);
+        self
+    }
+
+    /// Record the fees of the commitments honored in their target slot, once the block
+    /// templates are cleared. Evicted, drained or violated commitments aren't billed.
+    pub fn with_fee_accounting(mut self, fee_accounting: FeeAccounting) -> Self {
+        self.fee_accounting = Some(fee_accounting);
+        self
+    }
+
+    /// Count the pending inclusion reservations of the given book in the slot limits, so that
+    /// the capacity they hold isn't committed to other requests before their body arrives.
+    pub fn with_reservations(mut self, reservations: ReservationBook) -> Self {
+        self.reservations = Some(reservations);
+        self
+    }
+
+    /// Returns the number of pending reservations for the given slot, and the gas they hold.
+    fn reserved(&self, slot: u64) -> (usize, u64) {
+        self.reservations.as_ref().map_or((0, 0), |reservations| reservations.usage(slot))
+    }
+
     /// Returns the current base fee in gwei
     pub fn basefee(&self) -> u128 {
         self.basefee
     }
 
+    /// Returns the maximum basefee of the block of the given target slot: the basefee of the
+    /// next block predicted from the head block, increased by the maximum EIP-1559 increase
+    /// for each block in between.
+    pub fn max_basefee(&self, target_slot: u64) -> Result<u128, ValidationError> {
+        let blocks_after_next = target_slot.saturating_sub(self.slot).saturating_sub(1);
+        calculate_max_basefee(self.next_basefee, blocks_after_next)
+            .ok_or(ValidationError::MaxBaseFeeCalcOverflow)
+    }
+
+    /// Returns the fees and the remaining commitment capacity of the given target slot.
+    pub fn slot_info(&self, slot: u64) -> Result<SlotInfo, ValidationError> {
+        if slot < self.slot {
+            return Err(ValidationError::SlotTooLow(self.slot));
+        }
+
+        let max_base_fee = self.max_basefee(slot)?;
+
+        let (reserved_count, reserved_gas) = self.reserved(slot);
+        let template = self.block_templates.get(&slot);
+        let committed_gas = template.map(|t| t.committed_gas()).unwrap_or(0) + reserved_gas;
+        let commitments_count =
+            template.map(|t| t.transactions_len()).unwrap_or(0) + reserved_count;
+
+        Ok(SlotInfo {
+            slot,
+            base_fee: self.basefee,
+            max_base_fee,
+            min_priority_fee: self.limits.min_priority_fee,
+            remaining_gas: self
+                .limits
+                .max_committed_gas_per_slot
+                .get()
+                .saturating_sub(committed_gas),
+            commitments_count,
+            max_commitments: self.limits.max_commitments_per_slot.get(),
+        })
+    }
+
+    /// Validates the commitment request at each of the given candidate slots in order, up to
+    /// [MAX_VALIDATED_CANDIDATE_SLOTS] of them. Returns the first slot at which the request is
+    /// valid, or the validation error of the last candidate if none of them fits the request.
+    ///
+    /// The request itself is left unchanged, as its slot is covered by the signature of the
+    /// user. See [ExecutionState::validate_request] for the checks done at each slot.
+    pub async fn validate_request_in_slots(
+        &mut self,
+        req: &mut InclusionRequest,
+        slots: impl IntoIterator<Item = u64>,
+    ) -> Result<u64, ValidationError> {
+        let mut last_err = ValidationError::Internal("no candidate slots".to_string());
+        for slot in slots.into_iter().take(MAX_VALIDATED_CANDIDATE_SLOTS) {
+            match self.validate_request_at(req, slot).await {
+                Ok(()) => return Ok(slot),
+                Err(err) => {
+                    debug!(slot, ?err, "Request does not fit in candidate slot");
+                    last_err = err;
+                }
+            }
+        }
+
+        Err(last_err)
+    }
+
     /// Validates the commitment request against state (historical + intermediate).
     ///
     /// NOTE: This function only simulates against execution state, it does not consider
@@ -238,36 +426,106 @@ impl<C: StateFetcher> ExecutionState<C> {
     pub async fn validate_request(
         &mut self,
         req: &mut InclusionRequest,
+    ) -> Result<(), ValidationError> {
+        let target_slot = req.slot;
+        self.validate_request_at(req, target_slot).await
+    }
+
+    /// Validates the commitment request at the given target slot, which may differ from the
+    /// requested one for ranged requests. See [ExecutionState::validate_request].
+    async fn validate_request_at(
+        &mut self,
+        req: &mut InclusionRequest,
+        target_slot: u64,
     ) -> Result<(), ValidationError> {
         req.recover_signers()?;
 
-        let target_slot = req.slot;
+        // Check if the request signer has room for more pending commitments across all slots
+        if let (Some(max), Some(signer)) =
+            (self.limits.max_pending_commitments_per_account, req.signer())
+        {
+            if self.pending_commitments_of(signer) >= max.get() {
+                return Err(ValidationError::MaxPendingCommitmentsReachedForAccount(
+                    signer,
+                    max.get(),
+                ));
+            }
+        }
 
         // Validate the chain ID
         if !req.validate_chain_id(self.chain_id) {
             return Err(ValidationError::ChainIdMismatch);
         }
 
-        // Check if there is room for more commitments
-        if let Some(template) = self.get_block_template(target_slot) {
-            if template.transactions_len() >= self.limits.max_commitments_per_slot.get() {
+        // The allowed reverts must refer to transactions of the bundle
+        if let Some(hash) = req.unknown_reverting_tx_hash() {
+            return Err(ValidationError::UnknownRevertingTx(*hash));
+        }
+
+        // Check if contract creations are accepted
+        if self.limits.reject_contract_creation && req.has_contract_creation() {
+            return Err(ValidationError::ContractCreation);
+        }
+
+        let max_commitments = self.limits.max_commitments_per_slot.get();
+        let max_committed_gas = self.limits.max_committed_gas_per_slot.get();
+
+        // Check if there is room for more commitments and committed gas, possibly after
+        // evicting lower-priority commitments
+        let Some(evictions) = self.select_evictions(req, target_slot) else {
+            let (reserved_count, _) = self.reserved(target_slot);
+            let template = self.block_templates.get(&target_slot);
+            let count = template.map_or(0, |t| t.transactions_len()) + reserved_count;
+            if count >= max_commitments {
                 return Err(ValidationError::MaxCommitmentsReachedForSlot(
                     self.slot,
-                    self.limits.max_commitments_per_slot.get(),
+                    max_commitments,
                 ));
             }
-        }
-
-        // Check if the committed gas exceeds the maximum
-        let template_committed_gas =
-            self.get_block_template(target_slot).map(|t| t.committed_gas()).unwrap_or(0);
 
-        if template_committed_gas + req.gas_limit() >= self.limits.max_committed_gas_per_slot.get()
-        {
             return Err(ValidationError::MaxCommittedGasReachedForSlot(
                 self.slot,
-                self.limits.max_committed_gas_per_slot.get(),
+                max_committed_gas,
             ));
+        };
+
+        let evicted_gas = self.block_templates.get(&target_slot).map_or(0, |template| {
+            evictions.iter().fold(0, |acc, index| {
+                let transactions = &template.signed_constraints_list[*index].message.transactions;
+                acc + transactions.iter().fold(0, |acc, tx| acc + tx.gas_limit())
+            })
+        });
+
+        // Only a single top-of-block bundle is valid per slot
+        if req.top && self.get_block_template(target_slot).is_some_and(|t| t.has_top_of_block()) {
+            return Err(ValidationError::TopOfBlockTaken(target_slot));
+        }
+
+        // Check if the committed value exceeds the maximum, if any, at the maximum basefee of
+        // the target slot and without the commitments the request would evict
+        if let Some(max_value) = self.limits.max_commitment_value {
+            let max_basefee = self.max_basefee(target_slot)?;
+            let template = self.block_templates.get(&target_slot);
+            let template_value =
+                template.map(|t| t.max_priority_fees(max_basefee)).unwrap_or_default();
+            let evicted_value = template.map_or(U256::ZERO, |template| {
+                evictions.iter().fold(U256::ZERO, |acc, index| {
+                    let transactions =
+                        &template.signed_constraints_list[*index].message.transactions;
+                    transactions.iter().fold(acc, |acc, tx| {
+                        let tip = tx.effective_tip_per_gas(max_basefee).unwrap_or_default();
+                        acc + U256::from(tip) * U256::from(tx.gas_limit())
+                    })
+                })
+            });
+
+            let committed_value = template_value - evicted_value;
+            if committed_value + req.max_priority_fees(max_basefee) > max_value {
+                return Err(ValidationError::MaxCommitmentValueReachedForSlot(
+                    target_slot,
+                    max_value,
+                ));
+            }
         }
 
         // Check if the transaction size exceeds the maximum
@@ -286,6 +544,15 @@ impl<C: StateFetcher> ExecutionState<C> {
             return Err(ValidationError::GasLimitTooHigh);
         }
 
+        // Check if the gas limit of each transaction covers its intrinsic gas, including the
+        // EIP-7702 authorizations
+        for tx in &req.txs {
+            let intrinsic_gas = intrinsic_gas(tx);
+            if tx.gas_limit() < intrinsic_gas {
+                return Err(ValidationError::GasLimitTooLow(intrinsic_gas, tx.gas_limit()));
+            }
+        }
+
         // Ensure max_priority_fee_per_gas is less than max_fee_per_gas
         if !req.validate_max_priority_fee() {
             return Err(ValidationError::MaxPriorityFeePerGasTooHigh);
@@ -294,11 +561,11 @@ impl<C: StateFetcher> ExecutionState<C> {
         // Check if the max_fee_per_gas would cover the maximum possible basefee.
         let slot_diff = target_slot.saturating_sub(self.slot);
 
-        // Calculate the max possible basefee given the slot diff
-        let max_basefee = calculate_max_basefee(self.basefee, slot_diff)
-            .ok_or(ValidationError::MaxBaseFeeCalcOverflow)?;
+        // Calculate the max possible basefee given the slot diff, from the predicted basefee
+        // of the next block
+        let max_basefee = self.max_basefee(target_slot)?;
 
-        debug!(%slot_diff, basefee = self.basefee, %max_basefee, "Validating basefee");
+        debug!(%slot_diff, next_basefee = self.next_basefee, %max_basefee, "Validating basefee");
 
         // Validate the base fee
         if !req.validate_basefee(max_basefee) {
@@ -307,7 +574,7 @@ impl<C: StateFetcher> ExecutionState<C> {
 
         // Ensure max_priority_fee_per_gas is greater than or equal to min_priority_fee
         if !req.validate_min_priority_fee(max_basefee, self.limits.min_priority_fee) {
-            return Err(ValidationError::MaxPriorityFeePerGasTooLow);
+            return Err(ValidationError::MaxPriorityFeePerGasTooLow(self.limits.min_priority_fee));
         }
 
         if target_slot < self.slot {
@@ -315,6 +582,17 @@ impl<C: StateFetcher> ExecutionState<C> {
             return Err(ValidationError::SlotTooLow(self.slot));
         }
 
+        // Check that the blobs of the whole request fit in the block, on top of the ones
+        // already committed for the target slot
+        let committed_blobs =
+            self.block_templates.get(&target_slot).map(|t| t.blob_count()).unwrap_or(0);
+        if committed_blobs + req.blob_count() > MAX_BLOBS_PER_BLOCK {
+            return Err(ValidationError::Eip4844Limit);
+        }
+
+        // Commitments replaced because of a nonce conflict are left out of the diffs
+        let (replaced_diffs, replaced_gas) = self.replaced_diffs(req, target_slot)?;
+
         // Validate each transaction in the request against the account state,
         // keeping track of the nonce and balance diffs, including:
         // - any existing state in the account trie
@@ -331,34 +609,33 @@ impl<C: StateFetcher> ExecutionState<C> {
 
             let (nonce_diff, balance_diff, highest_slot_for_account) =
                 compute_diffs(&self.block_templates, sender);
+            let (nonce_diff, balance_diff) = match replaced_diffs.get(sender) {
+                Some((nonce, balance)) => {
+                    (nonce_diff.saturating_sub(*nonce), balance_diff.saturating_sub(*balance))
+                }
+                None => (nonce_diff, balance_diff),
+            };
 
             if target_slot < highest_slot_for_account {
                 debug!(%target_slot, %highest_slot_for_account, "There is a request for a higher slot");
                 return Err(ValidationError::SlotTooLow(highest_slot_for_account));
             }
 
-            let account_state = match self.account_states.get(sender).copied() {
-                Some(account) => account,
-                None => {
-                    // Fetch the account state from the client if it does not exist
-                    let account = match self.client.get_account_state(sender, None).await {
-                        Ok(account) => account,
-                        Err(err) => {
-                            return Err(ValidationError::Internal(format!(
-                                "Error fetching account state: {:?}",
-                                err
-                            )))
-                        }
-                    };
-
-                    self.account_states.insert(*sender, account);
-                    account
-                }
-            };
+            // The nonce of an account authorized by another transaction of the slot depends on
+            // the order in which they are included
+            if self
+                .block_templates
+                .get(&target_slot)
+                .is_some_and(|t| t.is_authorized_by_others(sender))
+            {
+                return Err(ValidationError::AuthorityNonceConflict(*sender));
+            }
+
+            let account_state = self.account_state(sender).await?;
 
             debug!(?account_state, ?nonce_diff, ?balance_diff, "Validating transaction");
 
-            let sender_nonce_diff = bundle_nonce_diff_map.entry(sender).or_insert(0);
+            let sender_nonce_diff = bundle_nonce_diff_map.entry(*sender).or_insert(0);
             let sender_balance_diff = bundle_balance_diff_map.entry(sender).or_insert(U256::ZERO);
 
             // Apply the diffs to this account according to the info fetched from the templates
@@ -374,20 +651,22 @@ impl<C: StateFetcher> ExecutionState<C> {
                     .saturating_sub(balance_diff)
                     .saturating_sub(*sender_balance_diff),
 
-                has_code: account_state.has_code,
+                // Senders with code are only accepted if allowed in the limits
+                has_code: account_state.has_code && !self.limits.allow_contract_senders,
             };
 
             // Validate the transaction against the account state with existing diffs
-            validate_transaction(&account_state_with_diffs, tx)?;
+            let empty_template = BlockTemplate::default();
+            let template = self.block_templates.get(&target_slot).unwrap_or(&empty_template);
+            template.is_includable(
+                tx,
+                &account_state_with_diffs,
+                max_committed_gas + evicted_gas + replaced_gas,
+                max_basefee,
+            )?;
 
             // Check EIP-4844-specific limits
             if let Some(transaction) = tx.as_eip4844() {
-                if let Some(template) = self.block_templates.get(&target_slot) {
-                    if template.blob_count() >= MAX_BLOBS_PER_BLOCK {
-                        return Err(ValidationError::Eip4844Limit);
-                    }
-                }
-
                 let PooledTransactionsElement::BlobTransaction(ref blob_transaction) = tx.deref()
                 else {
                     unreachable!("EIP-4844 transaction should be a blob transaction")
@@ -402,7 +681,8 @@ impl<C: StateFetcher> ExecutionState<C> {
                     return Err(ValidationError::BlobBaseFeeTooLow(max_blob_basefee));
                 }
 
-                // Validate blob against KZG settings
+                // Validate blob against KZG settings. This also checks that the versioned
+                // hashes of the transaction are the ones of the sidecar commitments.
                 transaction.validate_blob(
                     &blob_transaction.transaction.sidecar,
                     self.kzg_settings.get(),
@@ -412,21 +692,306 @@ impl<C: StateFetcher> ExecutionState<C> {
             // Increase the bundle nonce and balance diffs for this sender for the next iteration
             *sender_nonce_diff += 1;
             *sender_balance_diff += max_transaction_cost(tx);
+
+            // The EIP-7702 authorizations bump the nonces of their authorities. The ones of the
+            // sender itself are only kept if they follow its nonce.
+            for (authority, nonce) in tx.authority_nonce_bumps() {
+                let bundle_diff = bundle_nonce_diff_map.get(&authority).copied().unwrap_or(0);
+                if authority != *sender {
+                    let replaced = replaced_diffs.get(&authority).map_or(0, |(nonce, _)| *nonce);
+                    self.validate_authority_nonce(
+                        authority,
+                        nonce,
+                        target_slot,
+                        bundle_diff,
+                        replaced,
+                    )
+                    .await?;
+                }
+
+                bundle_nonce_diff_map.insert(authority, bundle_diff + 1);
+            }
+        }
+
+        // Later transactions of a bundle may depend on the effects of earlier ones, so the
+        // whole bundle is simulated in order rather than each transaction independently
+        if self.limits.simulate_bundles && req.txs.len() > 1 {
+            self.simulate_bundle(req, target_slot).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Validates the nonce of an EIP-7702 authorization of another account than the sender of
+    /// its transaction, on top of the diffs of the committed transactions and of the earlier
+    /// transactions of the bundle.
+    ///
+    /// The authority must not have commitments in the target slot or a later one, as their
+    /// nonces would be invalidated by the bump.
+    async fn validate_authority_nonce(
+        &mut self,
+        authority: Address,
+        nonce: u64,
+        target_slot: u64,
+        bundle_diff: u64,
+        replaced_diff: u64,
+    ) -> Result<(), ValidationError> {
+        let has_later_commitments = self.block_templates.iter().any(|(slot, template)| {
+            *slot >= target_slot && template.get_diff(&authority).is_some_and(|(n, _)| n > 0)
+        });
+        if has_later_commitments {
+            return Err(ValidationError::AuthorityNonceConflict(authority));
+        }
+
+        let (nonce_diff, _, _) = compute_diffs(&self.block_templates, &authority);
+        let account_state = self.account_state(&authority).await?;
+        let expected = account_state.transaction_count +
+            nonce_diff.saturating_sub(replaced_diff) +
+            bundle_diff;
+
+        if nonce != expected {
+            debug!(%authority, expected, nonce, "Authorization nonce doesn't match");
+            return Err(ValidationError::AuthorityNonceConflict(authority));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the account state of the given address at the head block, fetching it from the
+    /// client if it isn't cached.
+    async fn account_state(&mut self, address: &Address) -> Result<AccountState, ValidationError> {
+        if let Some(account) = self.account_states.get(address).copied() {
+            return Ok(account);
+        }
+
+        let account = self.client.get_account_state(address, None).await.map_err(|err| {
+            ValidationError::Internal(format!("Error fetching account state: {:?}", err))
+        })?;
+
+        self.account_states.insert(*address, account);
+        Ok(account)
+    }
+
+    /// Simulates the transactions of the request in order on top of the pending state of the
+    /// target slot, i.e. after the transactions committed up to that slot. Returns an error if
+    /// any of them reverts, unless it is allowed to.
+    async fn simulate_bundle(
+        &self,
+        req: &InclusionRequest,
+        target_slot: u64,
+    ) -> Result<(), ValidationError> {
+        let mut slots =
+            self.block_templates.keys().filter(|slot| **slot <= target_slot).collect::<Vec<_>>();
+        slots.sort_unstable();
+
+        let mut txs = slots
+            .into_iter()
+            .flat_map(|slot| self.block_templates[slot].transactions())
+            .collect::<Vec<_>>();
+        let pending = txs.len();
+        txs.extend(req.txs.iter().cloned());
+
+        let results = self.client.simulate_bundle(&txs, self.chain_id).await.map_err(|err| {
+            ValidationError::Internal(format!("Error simulating bundle: {:?}", err))
+        })?;
+
+        if results.len() != txs.len() {
+            return Err(ValidationError::Internal(format!(
+                "Simulated {} transactions out of {}",
+                results.len(),
+                txs.len()
+            )));
+        }
+
+        for (tx, result) in req.txs.iter().zip(&results[pending..]) {
+            if !result.is_success() && !req.allows_revert(tx.hash()) {
+                debug!(hash = %tx.hash(), ?result, "Bundle transaction reverted");
+                return Err(ValidationError::BundleReverted(*tx.hash()));
+            }
         }
 
         Ok(())
     }
 
+    /// Returns the indexes of the lowest-priority commitments of the target slot to evict to
+    /// make room for the request, if the slot is full and eviction is enabled in the limits.
+    /// Must be called after the request has been validated.
+    ///
+    /// Nothing is evicted until the constraints of the request are committed with
+    /// [ExecutionState::commit_constraints].
+    pub fn evictions_for_request(&self, req: &InclusionRequest, target_slot: u64) -> Vec<usize> {
+        self.select_evictions(req, target_slot).unwrap_or_default()
+    }
+
+    /// Evicts the commitments at the given indexes of the target slot, as selected by
+    /// [ExecutionState::evictions_for_request], and adds the constraints of the request to the
+    /// block template. Initializes a new block template if none exists for the slot.
+    ///
+    /// Returns the constraints removed from the template, i.e. the evicted ones and the ones
+    /// replaced because of a nonce conflict. The whole request is committed or none of it:
+    /// on error, the block template is left untouched.
+    pub fn commit_constraints(
+        &mut self,
+        target_slot: u64,
+        evictions: Vec<usize>,
+        constraints: Vec<SignedConstraints>,
+    ) -> Result<Vec<SignedConstraints>, BlockTemplateError> {
+        let mut template = match self.block_templates.get(&target_slot) {
+            Some(template) => template.clone(),
+            None => BlockTemplate::default().with_conflict_policy(self.conflict_policy),
+        };
+
+        let mut removed = template.evict(evictions);
+        for signed_constraints in constraints {
+            removed.extend(template.add_constraints(signed_constraints)?);
+        }
+
+        self.block_templates.insert(target_slot, template);
+        Ok(removed)
+    }
+
+    /// Returns the nonce and balance diffs of the commitments the request would replace in its
+    /// target slot because of a nonce conflict, per sender, along with their gas.
+    ///
+    /// Commitments are only replaced with [ConflictPolicy::PreferHigherFee]: with the default
+    /// policy, conflicting requests are rejected by the nonce check.
+    fn replaced_diffs(
+        &self,
+        req: &InclusionRequest,
+        target_slot: u64,
+    ) -> Result<(HashMap<Address, (u64, U256)>, u64), ValidationError> {
+        let mut diffs = HashMap::new();
+        let mut gas = 0;
+
+        let template = match self.block_templates.get(&target_slot) {
+            Some(template) if self.conflict_policy == ConflictPolicy::PreferHigherFee => template,
+            _ => return Ok((diffs, gas)),
+        };
+
+        for index in template.resolve_conflicts(&req.txs)? {
+            for tx in &template.signed_constraints_list[index].message.transactions {
+                let Some(sender) = tx.sender() else { continue };
+
+                // Replacing a commitment of a sender with commitments in later slots would
+                // invalidate their nonces
+                let (_, _, highest_slot) = compute_diffs(&self.block_templates, sender);
+                if highest_slot > target_slot {
+                    let nonce = tx.nonce();
+                    return Err(BlockTemplateError::NonceConflict { sender: *sender, nonce }.into());
+                }
+
+                let (nonce, balance) = diffs.entry(*sender).or_insert((0, U256::ZERO));
+                *nonce += 1;
+                *balance += max_transaction_cost(tx);
+                gas += tx.gas_limit();
+
+                for (authority, _) in tx.authority_nonce_bumps() {
+                    diffs.entry(authority).or_insert((0, U256::ZERO)).0 += 1;
+                }
+            }
+        }
+
+        Ok((diffs, gas))
+    }
+
+    /// Selects the commitments to evict from the target slot to make room for the request.
+    /// Returns `None` if the slot is full and not enough room can be made.
+    fn select_evictions(&self, req: &InclusionRequest, target_slot: u64) -> Option<Vec<usize>> {
+        let max_commitments = self.limits.max_commitments_per_slot.get();
+        let max_committed_gas = self.limits.max_committed_gas_per_slot.get();
+
+        // The pending reservations hold their capacity until their body is submitted
+        let (reserved_count, reserved_gas) = self.reserved(target_slot);
+        let fits = |count: usize, gas: u64| {
+            count + reserved_count < max_commitments &&
+                gas + reserved_gas + req.gas_limit() < max_committed_gas
+        };
+
+        let Some(template) = self.block_templates.get(&target_slot) else {
+            return fits(0, 0).then(Vec::new);
+        };
+
+        if !self.limits.evict_lower_priority {
+            return fits(template.transactions_len(), template.committed_gas()).then(Vec::new);
+        }
+
+        // Evicting the commitments of a sender of the request, or of a sender with commitments
+        // in later slots, would invalidate the nonces of the remaining ones.
+        let senders = req.txs.iter().filter_map(|tx| tx.sender()).collect::<Vec<_>>();
+        let evictable = |constraints: &SignedConstraints| {
+            constraints.message.transactions.iter().all(|tx| {
+                let sender = tx.sender().expect("recovered sender");
+                let (_, _, highest_slot) = compute_diffs(&self.block_templates, sender);
+                !senders.contains(&sender) && highest_slot <= target_slot
+            })
+        };
+
+        template.select_evictions(req.priority(self.basefee), self.basefee, evictable, fits)
+    }
+
     /// Commits the transaction to the target block. Initializes a new block template
     /// if one does not exist for said block number.
-    pub fn add_constraint(&mut self, target_slot: u64, signed_constraints: SignedConstraints) {
+    ///
+    /// Returns the constraints replaced because of a nonce conflict, if any.
+    pub fn add_constraint(
+        &mut self,
+        target_slot: u64,
+        signed_constraints: SignedConstraints,
+    ) -> Result<Vec<SignedConstraints>, BlockTemplateError> {
         if let Some(template) = self.block_templates.get_mut(&target_slot) {
-            template.add_constraints(signed_constraints);
+            template.add_constraints(signed_constraints)
         } else {
-            let mut template = BlockTemplate::default();
-            template.add_constraints(signed_constraints);
+            let mut template = BlockTemplate::default().with_conflict_policy(self.conflict_policy);
+            let replaced = template.add_constraints(signed_constraints)?;
             self.block_templates.insert(target_slot, template);
+            Ok(replaced)
+        }
+    }
+
+    /// Records a commitment accepted for the given slot as pending for the signer of its
+    /// request, until its constraints are evicted or its slot is cleared.
+    pub fn record_pending_commitment(&mut self, target_slot: Slot, request: &InclusionRequest) {
+        let (Some(_), Some(signer)) =
+            (self.limits.max_pending_commitments_per_account, request.signer())
+        else {
+            return;
+        };
+
+        let tx_hashes = request.txs.iter().map(|tx| *tx.hash()).collect();
+        self.pending_commitments.entry(signer).or_default().push((target_slot, tx_hashes));
+    }
+
+    /// Returns the number of pending commitments of the given request signer, forgetting the
+    /// ones that landed or were evicted since they were recorded.
+    fn pending_commitments_of(&mut self, signer: Address) -> usize {
+        let Some(commitments) = self.pending_commitments.get_mut(&signer) else { return 0 };
+
+        let templates = &self.block_templates;
+        commitments.retain(|(slot, tx_hashes)| {
+            templates.get(slot).is_some_and(|template| {
+                template
+                    .signed_constraints_list
+                    .iter()
+                    .flat_map(|sc| sc.message.transactions.iter())
+                    .any(|tx| tx_hashes.contains(tx.hash()))
+            })
+        });
+
+        let pending = commitments.len();
+        if pending == 0 {
+            self.pending_commitments.remove(&signer);
         }
+        pending
+    }
+
+    /// Assigns the sequence number of the next commitment accepted for the given slot.
+    /// Sequence numbers start from 0 for each slot.
+    pub fn next_commitment_sequence(&mut self, target_slot: u64) -> u64 {
+        let policy = self.conflict_policy;
+        self.block_templates
+            .entry(target_slot)
+            .or_insert_with(|| BlockTemplate::default().with_conflict_policy(policy))
+            .next_commitment_sequence()
     }
 
     /// Updates the state corresponding to the provided block number and slot.
@@ -446,11 +1011,16 @@ impl<C: StateFetcher> ExecutionState<C> {
         // Remove any block templates that are no longer valid
         // NOTE: this needs to be called BEFORE applying the state update or we might remove
         // constraints for which we need to get the receipts.
-        for template in self.remove_block_templates_until(slot) {
+        let mut included = Vec::new();
+        for (template_slot, template) in self.remove_block_templates_until(slot) {
             debug!(%slot, "Removed block template for slot");
             let hashes = template.transaction_hashes();
             let receipts = self.client.get_receipts_unordered(hashes.as_ref()).await?;
 
+            if let Some(client) = &self.post_slot_broadcast {
+                broadcast_missed_transactions(client, &template, &receipts).await;
+            }
+
             let mut receipts_len = 0;
             for receipt in receipts.iter().flatten() {
                 // Calculate the total tip revenue for this transaction:
@@ -461,6 +1031,7 @@ impl<C: StateFetcher> ExecutionState<C> {
                 trace!(hash = %receipt.transaction_hash, total_tip, "Receipt found");
 
                 ApiMetrics::increment_gross_tip_revenue(total_tip);
+                included.push(receipt.transaction_hash);
                 receipts_len += 1;
             }
 
@@ -478,6 +1049,21 @@ impl<C: StateFetcher> ExecutionState<C> {
                     }
                 });
             }
+
+            // Only the commitments with all their transactions included are billed
+            if let Some(fee_accounting) = &self.fee_accounting {
+                let landed = &included[included.len() - receipts_len..];
+                for constraints in &template.signed_constraints_list {
+                    let txs = &constraints.message.transactions;
+                    if txs.iter().all(|tx| landed.contains(tx.hash())) {
+                        fee_accounting.record_honored(template_slot, txs);
+                    }
+                }
+            }
+        }
+
+        if let Some(webhooks) = &self.webhooks {
+            webhooks.settle_until(slot, &included);
         }
 
         self.apply_state_update(update);
@@ -489,6 +1075,7 @@ impl<C: StateFetcher> ExecutionState<C> {
         // Update head and basefee
         self.block_number = update.block_number;
         self.basefee = update.min_basefee;
+        self.next_basefee = update.next_basefee;
 
         for (address, state) in update.account_states {
             let Some(prev_state) = self.account_states.get_mut(&address) else {
@@ -529,7 +1116,61 @@ impl<C: StateFetcher> ExecutionState<C> {
         self.block_templates.get(&slot)
     }
 
-    /// Removes all the block templates which slot is less then or equal `slot`, and returns them.
+    /// Removes the block template of the given slot, along with the constraints of later
+    /// slots depending on it, and returns the removed signed constraints by slot. They won't
+    /// be submitted to the relays at the commitment deadline anymore.
+    ///
+    /// The nonces and balances of the commitments of a sender in a slot are validated on top
+    /// of its commitments in the previous slots, so the constraints of the senders of the
+    /// drained transactions in later slots can't be honored anymore and are removed as well.
+    pub fn drain_slot(&mut self, slot: u64) -> Vec<(u64, Vec<SignedConstraints>)> {
+        let Some(template) = self.block_templates.remove(&slot) else {
+            return Vec::new();
+        };
+
+        let senders_of = |constraints: &[SignedConstraints]| {
+            let txs = constraints.iter().flat_map(|sc| &sc.message.transactions);
+            txs.filter_map(|tx| tx.sender().copied()).collect::<Vec<_>>()
+        };
+
+        let mut senders =
+            senders_of(&template.signed_constraints_list).into_iter().collect::<HashSet<_>>();
+        let mut drained = vec![(slot, template.signed_constraints_list)];
+
+        let mut later_slots =
+            self.block_templates.keys().filter(|s| **s > slot).copied().collect::<Vec<_>>();
+        later_slots.sort_unstable();
+
+        for later_slot in later_slots {
+            let template = self.block_templates.get_mut(&later_slot).expect("template exists");
+            let dependents = template
+                .signed_constraints_list
+                .iter()
+                .enumerate()
+                .filter(|(_, sc)| {
+                    sc.message
+                        .transactions
+                        .iter()
+                        .any(|tx| tx.sender().is_some_and(|sender| senders.contains(sender)))
+                })
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>();
+
+            if dependents.is_empty() {
+                continue;
+            }
+
+            // The other senders of the removed bundles now depend on them as well
+            let removed = template.evict(dependents);
+            senders.extend(senders_of(&removed));
+            drained.push((later_slot, removed));
+        }
+
+        drained
+    }
+
+    /// Removes all the block templates which slot is less then or equal `slot`, and returns them
+    /// along with their slot, in order.
     ///
     /// This should be called when we need to propose a block for the given slot, or when a new
     /// head comes in which makes an older block templates useless.
@@ -537,7 +1178,7 @@ impl<C: StateFetcher> ExecutionState<C> {
     /// NOTE: We remove all previous block templates to ensure that, when a new head is received
     /// from the beacon client, all stale template are cleared. This prevents outdated templates
     /// from persisting in cases of missed slots, where such events are not emitted.
-    pub fn remove_block_templates_until(&mut self, slot: u64) -> Vec<BlockTemplate> {
+    pub fn remove_block_templates_until(&mut self, slot: u64) -> Vec<(Slot, BlockTemplate)> {
         let mut slots_to_remove =
             self.block_templates.keys().filter(|s| **s <= slot).copied().collect::<Vec<_>>();
         slots_to_remove.sort();
@@ -545,7 +1186,7 @@ impl<C: StateFetcher> ExecutionState<C> {
         let mut templates = Vec::with_capacity(slots_to_remove.len());
         for s in slots_to_remove {
             if let Some(template) = self.block_templates.remove(&s) {
-                templates.push(template);
+                templates.push((s, template));
             }
         }
 
@@ -557,6 +1198,7 @@ impl<C: StateFetcher> ExecutionState<C> {
 pub struct StateUpdate {
     pub account_states: HashMap<Address, AccountState>,
     pub min_basefee: u128,
+    pub next_basefee: u128,
     pub min_blob_basefee: u128,
     pub block_number: u64,
 }
@@ -594,30 +1236,73 @@ fn compute_diffs(
     )
 }
 
+/// Broadcast the committed transactions of a past `template` which weren't included on
+/// chain according to their `receipts`, as a fallback to get them included later.
+/// Failures are only logged, as the transactions may be invalid by now.
+///
+/// Returns the hashes of the broadcast transactions.
+async fn broadcast_missed_transactions(
+    client: &RpcClient,
+    template: &BlockTemplate,
+    receipts: &[Option<TransactionReceipt>],
+) -> Vec<TxHash> {
+    let included =
+        receipts.iter().flatten().map(|receipt| receipt.transaction_hash).collect::<Vec<_>>();
+
+    let mut broadcast = Vec::new();
+    for tx in template.signed_constraints_list.iter().flat_map(|sc| &sc.message.transactions) {
+        if included.contains(tx.hash()) {
+            continue;
+        }
+
+        match client.send_raw_transaction(tx.encoded_2718().into()).await {
+            Ok(hash) => {
+                info!(%hash, "Broadcast committed transaction missed by its slot");
+                broadcast.push(hash);
+            }
+            Err(err) => warn!(?err, hash = %tx.hash(), "Failed to broadcast missed transaction"),
+        }
+    }
+
+    broadcast
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        builder::template::StateDiff, config::limits::DEFAULT_MAX_COMMITTED_GAS,
+        builder::template::StateDiff,
+        common::transactions::predict_next_basefee,
+        config::limits::{RelayLimits, DEFAULT_MAX_COMMITTED_GAS},
         signer::local::LocalSigner,
     };
     use std::{num::NonZero, str::FromStr, time::Duration};
 
     use alloy::{
-        consensus::constants::{ETH_TO_WEI, GWEI_TO_WEI},
-        eips::eip2718::Encodable2718,
-        network::EthereumWallet,
-        primitives::{uint, Uint},
+        consensus::{
+            constants::{ETH_TO_WEI, GWEI_TO_WEI},
+            BlobTransactionSidecar, SidecarBuilder, SimpleCoder,
+        },
+        eips::{eip2718::Encodable2718, eip7702::Authorization},
+        network::{EthereumWallet, TransactionBuilder4844},
+        primitives::{uint, Bytes, Signature, TxHash, Uint},
         providers::{network::TransactionBuilder, Provider, ProviderBuilder},
-        signers::local::PrivateKeySigner,
+        signers::{local::PrivateKeySigner, SignerSync},
     };
     use fetcher::{StateClient, StateFetcher};
+    use reth_primitives::TxType;
 
     use crate::{
         crypto::SignableBLS,
-        primitives::{ConstraintsMessage, SignedConstraints},
-        state::fetcher,
-        test_util::{create_signed_inclusion_request, default_test_transaction, launch_anvil},
+        primitives::{
+            ConstraintsMessage, FullTransaction, ReserveInclusionRequest, SignedConstraints,
+            TransactionExt, EIP7702_DELEGATION_PREFIX,
+        },
+        state::{fetcher, ReservationBook},
+        test_util::{
+            create_signed_inclusion_request, default_test_7702_transaction,
+            default_test_transaction, launch_anvil,
+        },
     };
 
     #[test]
@@ -646,7 +1331,7 @@ mod tests {
 
         // Create BlockTemplate with StateDiff
         let mut block_templates = HashMap::new();
-        let block_template = BlockTemplate { state_diff, signed_constraints_list: vec![] };
+        let block_template = BlockTemplate { state_diff, ..Default::default() };
         block_templates.insert(10, block_template);
 
         let (nonce_diff, balance_diff, highest_slot) = compute_diffs(&block_templates, &sender);
@@ -682,7 +1367,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_invalid_inclusion_slot() -> eyre::Result<()> {
+    async fn test_invalid_inclusion_request_unknown_reverting_tx() -> eyre::Result<()> {
         let _ = tracing_subscriber::fmt::try_init();
 
         let anvil = launch_anvil();
@@ -697,30 +1382,22 @@ mod tests {
         let slot = client.get_head().await?;
         state.update_head(None, slot).await?;
 
-        // Create a transaction with a nonce that is too high
-        let tx = default_test_transaction(*sender, Some(1));
+        let tx = default_test_transaction(*sender, None);
 
         let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
-
-        // Insert a constraint diff for slot 11
-        let mut diffs = HashMap::new();
-        diffs.insert(*sender, (1, U256::ZERO));
-        state.block_templates.insert(
-            11,
-            BlockTemplate { state_diff: StateDiff { diffs }, signed_constraints_list: vec![] },
-        );
-        state.update_head(None, 11).await?;
+        let unknown = TxHash::random();
+        request.reverting_tx_hashes = vec![*request.txs[0].hash(), unknown];
 
         assert!(matches!(
             state.validate_request(&mut request).await,
-            Err(ValidationError::SlotTooLow(11))
+            Err(ValidationError::UnknownRevertingTx(hash)) if hash == unknown
         ));
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_invalid_inclusion_request_nonce() -> eyre::Result<()> {
+    async fn test_valid_eip7702_inclusion_request() -> eyre::Result<()> {
         let _ = tracing_subscriber::fmt::try_init();
 
         let anvil = launch_anvil();
@@ -728,48 +1405,34 @@ mod tests {
 
         let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
 
-        let sender = anvil.addresses().first().unwrap();
         let sender_pk = anvil.keys().first().unwrap();
+        let signer: PrivateKeySigner = sender_pk.clone().into();
 
-        // initialize the state by updating the head once
         let slot = client.get_head().await?;
         state.update_head(None, slot).await?;
 
-        // Insert a constraint diff for slot 9 to simulate nonce increment
-        let mut diffs = HashMap::new();
-        diffs.insert(*sender, (1, U256::ZERO));
-        state.block_templates.insert(
-            9,
-            BlockTemplate { state_diff: StateDiff { diffs }, signed_constraints_list: vec![] },
-        );
-
-        // Create a transaction with a nonce that is too low
-        let tx = default_test_transaction(*sender, Some(0));
+        let tx = default_test_7702_transaction(&signer, None, Address::random())?;
 
         let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        assert_eq!(request.txs[0].tx_type(), TxType::Eip7702);
 
-        assert!(matches!(
-            state.validate_request(&mut request).await,
-            Err(ValidationError::NonceTooLow(1, 0))
-        ));
-
-        assert!(state.account_states.get(sender).unwrap().transaction_count == 0);
-
-        // Create a transaction with a nonce that is too high
-        let tx = default_test_transaction(*sender, Some(2));
+        assert!(state.validate_request(&mut request).await.is_ok());
 
+        // The gas limit must cover the 25000 gas of the authorization on top of the base cost
+        let tx =
+            default_test_7702_transaction(&signer, None, Address::random())?.with_gas_limit(21_000);
         let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
 
         assert!(matches!(
             state.validate_request(&mut request).await,
-            Err(ValidationError::NonceTooHigh(1, 2))
+            Err(ValidationError::GasLimitTooLow(46_000, 21_000))
         ));
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_invalid_inclusion_request_balance() -> eyre::Result<()> {
+    async fn test_eip7702_self_authorization_bumps_sender_nonce() -> eyre::Result<()> {
         let _ = tracing_subscriber::fmt::try_init();
 
         let anvil = launch_anvil();
@@ -777,50 +1440,767 @@ mod tests {
 
         let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
 
-        let sender = anvil.addresses().first().unwrap();
         let sender_pk = anvil.keys().first().unwrap();
+        let signer: PrivateKeySigner = sender_pk.clone().into();
 
-        // initialize the state by updating the head once
         let slot = client.get_head().await?;
         state.update_head(None, slot).await?;
 
-        // Create a transaction with a value that is too high
-        let tx = default_test_transaction(*sender, None)
-            .with_value(uint!(11_000_U256 * Uint::from(ETH_TO_WEI)));
+        let target_slot = 10;
 
-        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        // Commit to nonce 0, authorizing the delegation of the sender at nonce 1
+        let tx = default_test_7702_transaction(&signer, Some(0), Address::random())?;
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
 
+        let bls_signer = LocalSigner::random();
+        let message = ConstraintsMessage::build(Default::default(), request);
+        let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+        state.add_constraint(target_slot, SignedConstraints { message, signature })?;
+
+        // The authorization bumped the nonce of the sender once more
+        let tx = default_test_transaction(signer.address(), Some(1));
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
         assert!(matches!(
             state.validate_request(&mut request).await,
-            Err(ValidationError::InsufficientBalance)
+            Err(ValidationError::NonceTooLow(2, 1))
         ));
 
+        let tx = default_test_transaction(signer.address(), Some(2));
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_invalid_inclusion_request_balance_multiple() -> eyre::Result<()> {
+    async fn test_eip7702_authority_nonce_conflicts() -> eyre::Result<()> {
         let _ = tracing_subscriber::fmt::try_init();
 
         let anvil = launch_anvil();
         let client = StateClient::new(anvil.endpoint_url());
 
-        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
-
-        let sender = anvil.addresses().first().unwrap();
-        let sender_pk = anvil.keys().first().unwrap();
-        let signer = LocalSigner::random();
+        let sender_pk = &anvil.keys()[0];
+        let sender: PrivateKeySigner = sender_pk.clone().into();
+        let authority_pk = &anvil.keys()[1];
+        let authority: PrivateKeySigner = authority_pk.clone().into();
 
-        // initialize the state by updating the head once
         let slot = client.get_head().await?;
-        state.update_head(None, slot).await?;
+        let target_slot = 10;
 
-        // Set the sender balance to just enough to pay for 1 transaction
-        let balance = U256::from_str("500000000000000").unwrap(); // leave just 0.0005 ETH
-        let sender_account = client.get_account_state(sender, None).await.unwrap();
-        let balance_to_burn = sender_account.balance - balance;
+        let bls_signer = LocalSigner::random();
+        let sign = |request: InclusionRequest| {
+            let message = ConstraintsMessage::build(Default::default(), request);
+            let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+            SignedConstraints { message, signature }
+        };
 
-        // burn the balance
+        // A transaction of the sender carrying an authorization of another account
+        let authorized_tx = || -> eyre::Result<_> {
+            let authorization =
+                Authorization { chain_id: U256::from(1337), address: Address::random(), nonce: 0 };
+            let signature = authority.sign_hash_sync(&authorization.signature_hash())?;
+            let mut tx = default_test_transaction(sender.address(), None).with_gas_limit(46_000);
+            tx.authorization_list = Some(vec![authorization.into_signed(signature)]);
+            Ok(tx)
+        };
+
+        // The authorization would invalidate the nonces of the commitments of the authority
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+        state.update_head(None, slot).await?;
+
+        let tx = default_test_transaction(authority.address(), None);
+        let mut request = create_signed_inclusion_request(&[tx], authority_pk, target_slot).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+        state.add_constraint(target_slot, sign(request))?;
+
+        let mut request =
+            create_signed_inclusion_request(&[authorized_tx()?], sender_pk, target_slot).await?;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::AuthorityNonceConflict(address)) if address == authority.address()
+        ));
+
+        // Without commitments, the authorization is accepted, but then the nonce of the
+        // authority depends on the order of the transactions in the slot
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+        state.update_head(None, slot).await?;
+
+        let mut request =
+            create_signed_inclusion_request(&[authorized_tx()?], sender_pk, target_slot).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+        state.add_constraint(target_slot, sign(request))?;
+
+        let tx = default_test_transaction(authority.address(), Some(1));
+        let mut request = create_signed_inclusion_request(&[tx], authority_pk, target_slot).await?;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::AuthorityNonceConflict(address)) if address == authority.address()
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_inclusion_slot() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        // Create a transaction with a nonce that is too high
+        let tx = default_test_transaction(*sender, Some(1));
+
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+
+        // Insert a constraint diff for slot 11
+        let mut diffs = HashMap::new();
+        diffs.insert(*sender, (1, U256::ZERO));
+        state
+            .block_templates
+            .insert(11, BlockTemplate { state_diff: StateDiff { diffs }, ..Default::default() });
+        state.update_head(None, 11).await?;
+
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::SlotTooLow(11))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_inclusion_request_nonce() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        // Insert a constraint diff for slot 9 to simulate nonce increment
+        let mut diffs = HashMap::new();
+        diffs.insert(*sender, (1, U256::ZERO));
+        state
+            .block_templates
+            .insert(9, BlockTemplate { state_diff: StateDiff { diffs }, ..Default::default() });
+
+        // Create a transaction with a nonce that is too low
+        let tx = default_test_transaction(*sender, Some(0));
+
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::NonceTooLow(1, 0))
+        ));
+
+        assert!(state.account_states.get(sender).unwrap().transaction_count == 0);
+
+        // Create a transaction with a nonce that is too high
+        let tx = default_test_transaction(*sender, Some(2));
+
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::NonceTooHigh(1, 2))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_inclusion_request_nonce_gap() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        let target_slot = 10;
+
+        // The on-chain nonce is 0, so nonce 2 can't be included until 0 and 1 land
+        let tx = default_test_transaction(*sender, Some(2));
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::NonceTooHigh(0, 2))
+        ));
+
+        // Commit to nonce 0: nonce 2 is still gapped
+        let tx = default_test_transaction(*sender, Some(0));
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        let bls_signer = LocalSigner::random();
+        let message = ConstraintsMessage::build(Default::default(), request);
+        let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+        state.add_constraint(target_slot, SignedConstraints { message, signature })?;
+
+        let tx = default_test_transaction(*sender, Some(2));
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::NonceTooHigh(1, 2))
+        ));
+
+        // Nonce 1 fills the gap
+        let tx = default_test_transaction(*sender, Some(1));
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_inclusion_request_nonce_conflict_policy() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+        let slot = client.get_head().await?;
+        let target_slot = 10;
+
+        let bls_signer = LocalSigner::random();
+        let sign = |request: InclusionRequest| {
+            let message = ConstraintsMessage::build(Default::default(), request);
+            let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+            SignedConstraints { message, signature }
+        };
+
+        // A request for nonce 0 with the given priority fee
+        let request = |priority_fee: u128| {
+            let tx = default_test_transaction(*sender, Some(0))
+                .with_max_priority_fee_per_gas(priority_fee);
+            create_signed_inclusion_request(&[tx], sender_pk, target_slot)
+        };
+
+        // By default, the nonce check rejects the later request
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+        state.update_head(None, slot).await?;
+
+        let mut first = request(GWEI_TO_WEI as u128).await?;
+        assert!(state.validate_request(&mut first).await.is_ok());
+        state.add_constraint(target_slot, sign(first.clone()))?;
+
+        let mut higher_fee = request(2 * GWEI_TO_WEI as u128).await?;
+        assert!(matches!(
+            state.validate_request(&mut higher_fee).await,
+            Err(ValidationError::NonceTooLow(1, 0))
+        ));
+
+        // Preferring the higher fee validates the request as if the conflicting commitment
+        // were already replaced
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default())
+            .await?
+            .with_conflict_policy(ConflictPolicy::PreferHigherFee);
+        state.update_head(None, slot).await?;
+
+        assert!(state.validate_request(&mut first).await.is_ok());
+        state.add_constraint(target_slot, sign(first.clone()))?;
+
+        assert!(state.validate_request(&mut higher_fee).await.is_ok());
+        let replaced = state.add_constraint(target_slot, sign(higher_fee.clone()))?;
+        assert_eq!(replaced.len(), 1);
+        assert_eq!(replaced[0].message.transactions[0].hash(), first.txs[0].hash());
+
+        // ...but not a request paying less
+        assert!(matches!(
+            state.validate_request(&mut first).await,
+            Err(ValidationError::BlockTemplate(BlockTemplateError::NonceConflict { nonce: 0, .. }))
+        ));
+
+        // ...nor one whose commitment later ones depend on
+        let mut next = create_signed_inclusion_request(
+            &[default_test_transaction(*sender, Some(1))],
+            sender_pk,
+            target_slot,
+        )
+        .await?;
+        assert!(state.validate_request(&mut next).await.is_ok());
+        state.add_constraint(target_slot, sign(next))?;
+
+        let mut highest_fee = request(3 * GWEI_TO_WEI as u128).await?;
+        assert!(matches!(
+            state.validate_request(&mut highest_fee).await,
+            Err(ValidationError::BlockTemplate(BlockTemplateError::NonceConflict { nonce: 0, .. }))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_inclusion_request_max_pending_per_account() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let max_pending = NonZero::new(1).unwrap();
+        let limits = LimitsOpts {
+            max_pending_commitments_per_account: Some(max_pending),
+            ..Default::default()
+        };
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
+
+        let sender = anvil.addresses()[0];
+        let other = anvil.addresses()[1];
+
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        // A bundle of several transactions is a single commitment
+        let txs =
+            [default_test_transaction(sender, Some(0)), default_test_transaction(sender, Some(1))];
+        let mut request = create_signed_inclusion_request(&txs, &anvil.keys()[0], 10).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        let bls_signer = LocalSigner::random();
+        state.record_pending_commitment(10, &request);
+        let message = ConstraintsMessage::build(Default::default(), request);
+        let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+        state.add_constraint(10, SignedConstraints { message, signature })?;
+
+        // The signer is capped across slots until its commitment lands
+        let tx = default_test_transaction(sender, Some(2));
+        let mut request = create_signed_inclusion_request(&[tx], &anvil.keys()[0], 11).await?;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::MaxPendingCommitmentsReachedForAccount(account, 1))
+                if account == sender
+        ));
+
+        // The cap is keyed on the request signer, not on the transaction senders
+        let tx = default_test_transaction(other, Some(0));
+        let mut request = create_signed_inclusion_request(&[tx], &anvil.keys()[1], 10).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+        request.set_signer(sender);
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::MaxPendingCommitmentsReachedForAccount(account, 1))
+                if account == sender
+        ));
+
+        // The signer can commit again once its commitment is cleared with its slot
+        state.remove_block_templates_until(10);
+        let tx = default_test_transaction(sender, Some(0));
+        let mut request = create_signed_inclusion_request(&[tx], &anvil.keys()[0], 11).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_inclusion_request_slot_range() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let limits =
+            LimitsOpts { max_commitments_per_slot: NonZero::new(1).unwrap(), ..Default::default() };
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
+
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        // Fill slot 10 with a commitment
+        let tx = default_test_transaction(anvil.addresses()[0], None);
+        let mut request = create_signed_inclusion_request(&[tx], &anvil.keys()[0], 10).await?;
+        assert_eq!(state.validate_request_in_slots(&mut request, 10..=10).await?, 10);
+
+        let bls_signer = LocalSigner::random();
+        let message = ConstraintsMessage::build(Default::default(), request);
+        let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+        state.add_constraint(10, SignedConstraints { message, signature })?;
+
+        // A request for slots 10 to 12 lands in slot 11, the first one with capacity
+        let tx = default_test_transaction(anvil.addresses()[1], None);
+        let mut request = create_signed_inclusion_request(&[tx], &anvil.keys()[1], 10).await?;
+        request.max_slot = Some(12);
+        assert_eq!(request.slot_range(), 10..=12);
+
+        let slots = request.slot_range();
+        assert_eq!(state.validate_request_in_slots(&mut request, slots).await?, 11);
+
+        // The request keeps the slot signed by the user
+        assert_eq!(request.slot, 10);
+
+        // Only the first candidate slots are validated
+        let slots = [10; MAX_VALIDATED_CANDIDATE_SLOTS].into_iter().chain([11]);
+        assert!(matches!(
+            state.validate_request_in_slots(&mut request, slots).await,
+            Err(ValidationError::MaxCommitmentsReachedForSlot(_, 1))
+        ));
+
+        // A range without capacity reports the error of its last slot
+        let tx = default_test_transaction(anvil.addresses()[2], None);
+        let mut request = create_signed_inclusion_request(&[tx], &anvil.keys()[2], 10).await?;
+        assert!(matches!(
+            state.validate_request_in_slots(&mut request, 10..=10).await,
+            Err(ValidationError::MaxCommitmentsReachedForSlot(_, 1))
+        ));
+        assert_eq!(request.slot, 10);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_relay_limits_restrict_commitments() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        // The Constraints API server accepts a single constraint per slot, fewer than the
+        // local limit, so it is the one enforced
+        let relay_limits = RelayLimits {
+            relay_max_constraints_per_slot: NonZero::new(1).unwrap(),
+            relay_max_committed_gas_per_slot: None,
+        };
+        let limits = LimitsOpts::default().with_relay_limits(relay_limits);
+        assert_eq!(limits.max_commitments_per_slot.get(), 1);
+        assert_eq!(
+            limits.max_committed_gas_per_slot,
+            LimitsOpts::default().max_committed_gas_per_slot
+        );
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
+        state.update_head(None, client.get_head().await?).await?;
+
+        let sender = anvil.addresses()[0];
+        let sender_pk = &anvil.keys()[0];
+        let target_slot = 10;
+
+        let tx = default_test_transaction(sender, None);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+        state.validate_request(&mut request).await?;
+
+        let signer = LocalSigner::random();
+        let message = ConstraintsMessage::build(signer.pubkey(), request);
+        let signature = signer.sign_commit_boost_root(message.digest())?;
+        state.add_constraint(target_slot, SignedConstraints { message, signature })?;
+
+        // A second commitment would exceed the server limit, and is rejected before signing
+        let tx = default_test_transaction(sender, Some(1));
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::MaxCommitmentsReachedForSlot(_, 1))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_max_basefee_from_predicted_next_basefee() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let limits = LimitsOpts { min_priority_fee: 0, ..Default::default() };
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        let header = RpcClient::new(anvil.endpoint_url()).get_block(None, false).await?.header;
+        let basefee = header.base_fee_per_gas.unwrap_or_default();
+        let next_basefee = predict_next_basefee(header.gas_used, header.gas_limit, basefee) as u128;
+
+        // The next block pays exactly the predicted basefee, and later ones at most the
+        // maximum increase from it
+        assert_eq!(state.max_basefee(slot + 1)?, next_basefee);
+        assert_eq!(state.max_basefee(slot + 2)?, calculate_max_basefee(next_basefee, 1).unwrap());
+
+        // A transaction paying the predicted basefee is accepted for the next block, even below
+        // the maximum increase from the head basefee
+        assert!(next_basefee < calculate_max_basefee(state.basefee(), 1).unwrap());
+        let tx = default_test_transaction(*sender, None)
+            .with_max_fee_per_gas(next_basefee)
+            .with_max_priority_fee_per_gas(0);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, slot + 1).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        // But underpriced for the predicted basefee of the block after
+        let tx = default_test_transaction(*sender, None)
+            .with_max_fee_per_gas(next_basefee)
+            .with_max_priority_fee_per_gas(0);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, slot + 2).await?;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::BaseFeeTooLow(_))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_slot_info() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let limits = LimitsOpts::default();
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        let target_slot = 10;
+        let info = state.slot_info(target_slot)?;
+        assert_eq!(info.commitments_count, 0);
+        assert_eq!(info.remaining_gas, limits.max_committed_gas_per_slot.get());
+
+        // Commit to a transaction in the target slot
+        let tx = default_test_transaction(*sender, None);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+        let gas_limit = request.gas_limit();
+
+        let bls_signer = LocalSigner::random();
+        let message = ConstraintsMessage::build(Default::default(), request);
+        let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+        state.add_constraint(target_slot, SignedConstraints { message, signature })?;
+
+        let info = state.slot_info(target_slot)?;
+        assert_eq!(info.slot, target_slot);
+        assert_eq!(info.base_fee, state.basefee());
+        assert!(info.max_base_fee >= info.base_fee);
+        assert_eq!(info.min_priority_fee, limits.min_priority_fee);
+        assert_eq!(info.commitments_count, 1);
+        assert_eq!(info.max_commitments, limits.max_commitments_per_slot.get());
+        assert_eq!(info.remaining_gas, limits.max_committed_gas_per_slot.get() - gas_limit);
+
+        // Other slots are not affected
+        assert_eq!(state.slot_info(target_slot + 1)?.commitments_count, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reservations_count_in_slot_limits() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let limits = LimitsOpts {
+            max_committed_gas_per_slot: NonZero::new(50_000).unwrap(),
+            ..Default::default()
+        };
+        let reservations = ReservationBook::new(Duration::from_secs(60), limits);
+        let mut state = ExecutionState::new(client.clone(), limits)
+            .await?
+            .with_reservations(reservations.clone());
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        // A pending reservation holds most of the gas of the slot
+        let target_slot = 10;
+        let reservation = ReserveInclusionRequest {
+            slot: target_slot,
+            tx_hash: TxHash::random(),
+            gas_limit: 40_000,
+        };
+        let signature = Signature::from_rs_and_parity(U256::from(1), U256::from(1), false)?;
+        reservations.reserve(reservation, *sender, signature).unwrap();
+
+        let info = state.slot_info(target_slot)?;
+        assert_eq!(info.commitments_count, 1);
+        assert_eq!(info.remaining_gas, 10_000);
+
+        // So a transaction of 21000 gas doesn't fit in the slot anymore
+        let tx = default_test_transaction(*sender, None);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::MaxCommittedGasReachedForSlot(_, 50_000))
+        ));
+
+        // While it still fits in other slots
+        let tx = default_test_transaction(*sender, None);
+        let mut request =
+            create_signed_inclusion_request(&[tx], sender_pk, target_slot + 1).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_inclusion_request_balance() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        // Create a transaction with a value that is too high
+        let tx = default_test_transaction(*sender, None)
+            .with_value(uint!(11_000_U256 * Uint::from(ETH_TO_WEI)));
+
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::InsufficientBalance)
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_inclusion_request_contract_sender() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+        let provider = ProviderBuilder::new().on_http(anvil.endpoint_url());
+
+        let contract = anvil.addresses()[0];
+        let delegated = anvil.addresses()[1];
+
+        // Deploy some code on the first sender, and delegate the second one with EIP-7702
+        let code = Bytes::from_static(&[0x60, 0x00, 0x60, 0x00, 0xf3]);
+        provider.raw_request::<_, ()>("anvil_setCode".into(), (contract, code)).await?;
+        let designator = [EIP7702_DELEGATION_PREFIX.as_slice(), Address::random().as_slice()];
+        let designator = Bytes::from(designator.concat());
+        provider.raw_request::<_, ()>("anvil_setCode".into(), (delegated, designator)).await?;
+
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        let tx = default_test_transaction(contract, None);
+        let mut request = create_signed_inclusion_request(&[tx], &anvil.keys()[0], 10).await?;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::AccountHasCode)
+        ));
+
+        // Delegated EOAs are still EOAs
+        let tx = default_test_transaction(delegated, None);
+        let mut request = create_signed_inclusion_request(&[tx], &anvil.keys()[1], 10).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        // The check can be disabled
+        let limits = LimitsOpts { allow_contract_senders: true, ..Default::default() };
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
+        state.update_head(None, slot).await?;
+
+        let tx = default_test_transaction(contract, None);
+        let mut request = create_signed_inclusion_request(&[tx], &anvil.keys()[0], 10).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_inclusion_request_contract_creation() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let limits = LimitsOpts { reject_contract_creation: true, ..Default::default() };
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        // Deploy a contract returning empty code
+        let tx = default_test_transaction(*sender, None)
+            .with_deploy_code(Bytes::from_static(&[0x60, 0x00, 0x60, 0x00, 0xf3]))
+            .with_gas_limit(100_000);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::ContractCreation)
+        ));
+
+        // Transfers are still accepted
+        let tx = default_test_transaction(*sender, None);
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_inclusion_request_balance_multiple() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+        let signer = LocalSigner::random();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        // Set the sender balance to just enough to pay for 1 transaction
+        let balance = U256::from_str("500000000000000").unwrap(); // leave just 0.0005 ETH
+        let sender_account = client.get_account_state(sender, None).await.unwrap();
+        let balance_to_burn = sender_account.balance - balance;
+
+        // burn the balance
         let tx = default_test_transaction(*sender, Some(0)).with_value(uint!(balance_to_burn));
         let request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
         let tx_bytes = request.txs.first().unwrap().encoded_2718();
@@ -831,42 +2211,105 @@ mod tests {
         let slot = client.get_head().await?;
         state.update_head(None, slot).await?;
 
-        // create a new transaction and request a preconfirmation for it
-        let tx = default_test_transaction(*sender, Some(1));
-        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
-
-        assert!(state.validate_request(&mut request).await.is_ok());
-
-        let message = ConstraintsMessage::build(Default::default(), request.clone());
-        let signature = signer.sign_commit_boost_root(message.digest())?;
-        let signed_constraints = SignedConstraints { message, signature };
-        state.add_constraint(10, signed_constraints);
+        // create a new transaction and request a preconfirmation for it
+        let tx = default_test_transaction(*sender, Some(1));
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        let message = ConstraintsMessage::build(Default::default(), request.clone());
+        let signature = signer.sign_commit_boost_root(message.digest())?;
+        let signed_constraints = SignedConstraints { message, signature };
+        state.add_constraint(10, signed_constraints)?;
+
+        // create a new transaction and request a preconfirmation for it
+        let tx = default_test_transaction(*sender, Some(2));
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+
+        // this should fail because the balance is insufficient as we spent
+        // all of it on the previous preconfirmation
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::InsufficientBalance)
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_inclusion_request_basefee() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let limits = LimitsOpts::default();
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
+
+        let basefee = state.basefee();
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        // Create a transaction with a basefee that is too low
+        let tx = default_test_transaction(*sender, None)
+            .with_max_fee_per_gas(basefee - 1)
+            .with_max_priority_fee_per_gas(basefee / 2);
+
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::BaseFeeTooLow(_))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_inclusion_request_with_excess_gas() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let limits = LimitsOpts {
+            max_committed_gas_per_slot: NonZero::new(5_000_000).unwrap(),
+            ..Default::default()
+        };
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        let tx = default_test_transaction(*sender, None).with_gas_limit(6_000_000);
 
-        // create a new transaction and request a preconfirmation for it
-        let tx = default_test_transaction(*sender, Some(2));
         let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
 
-        // this should fail because the balance is insufficient as we spent
-        // all of it on the previous preconfirmation
         assert!(matches!(
             state.validate_request(&mut request).await,
-            Err(ValidationError::InsufficientBalance)
+            Err(ValidationError::MaxCommittedGasReachedForSlot(_, 5_000_000))
         ));
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_invalid_inclusion_request_basefee() -> eyre::Result<()> {
+    async fn test_invalid_inclusion_request_with_excess_blobs() -> eyre::Result<()> {
         let _ = tracing_subscriber::fmt::try_init();
 
         let anvil = launch_anvil();
         let client = StateClient::new(anvil.endpoint_url());
 
-        let limits = LimitsOpts::default();
-        let mut state = ExecutionState::new(client.clone(), limits).await?;
-
-        let basefee = state.basefee();
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
 
         let sender = anvil.addresses().first().unwrap();
         let sender_pk = anvil.keys().first().unwrap();
@@ -875,33 +2318,37 @@ mod tests {
         let slot = client.get_head().await?;
         state.update_head(None, slot).await?;
 
-        // Create a transaction with a basefee that is too low
-        let tx = default_test_transaction(*sender, None)
-            .with_max_fee_per_gas(basefee - 1)
-            .with_max_priority_fee_per_gas(basefee / 2);
+        // Each transaction carries a single blob, but together they exceed the block limit
+        let mut txs = Vec::with_capacity(MAX_BLOBS_PER_BLOCK + 1);
+        for nonce in 0..=MAX_BLOBS_PER_BLOCK as u64 {
+            let sidecar: BlobTransactionSidecar =
+                SidecarBuilder::<SimpleCoder>::from_slice(b"Blobs are fun!").build()?;
+            txs.push(
+                default_test_transaction(*sender, Some(nonce))
+                    .with_blob_sidecar(sidecar)
+                    .with_max_fee_per_blob_gas(3_000_000),
+            );
+        }
 
-        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        let mut request = create_signed_inclusion_request(&txs, sender_pk, 10).await?;
+        assert_eq!(request.blob_count(), MAX_BLOBS_PER_BLOCK + 1);
 
         assert!(matches!(
             state.validate_request(&mut request).await,
-            Err(ValidationError::BaseFeeTooLow(_))
+            Err(ValidationError::Eip4844Limit)
         ));
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_invalid_inclusion_request_with_excess_gas() -> eyre::Result<()> {
+    async fn test_invalid_inclusion_request_blob_versioned_hash_mismatch() -> eyre::Result<()> {
         let _ = tracing_subscriber::fmt::try_init();
 
         let anvil = launch_anvil();
         let client = StateClient::new(anvil.endpoint_url());
 
-        let limits = LimitsOpts {
-            max_committed_gas_per_slot: NonZero::new(5_000_000).unwrap(),
-            ..Default::default()
-        };
-        let mut state = ExecutionState::new(client.clone(), limits).await?;
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
 
         let sender = anvil.addresses().first().unwrap();
         let sender_pk = anvil.keys().first().unwrap();
@@ -910,13 +2357,28 @@ mod tests {
         let slot = client.get_head().await?;
         state.update_head(None, slot).await?;
 
-        let tx = default_test_transaction(*sender, None).with_gas_limit(6_000_000);
+        let sidecar: BlobTransactionSidecar =
+            SidecarBuilder::<SimpleCoder>::from_slice(b"Blobs are fun!").build()?;
+        let tx = default_test_transaction(*sender, None)
+            .with_blob_sidecar(sidecar)
+            .with_max_fee_per_blob_gas(3_000_000);
 
         let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        // Swap the sidecar for another valid one, whose commitments don't match the hashes
+        let other: BlobTransactionSidecar =
+            SidecarBuilder::<SimpleCoder>::from_slice(b"Other blobs").build()?;
+        let PooledTransactionsElement::BlobTransaction(ref mut blob_tx) = request.txs[0].tx else {
+            unreachable!("EIP-4844 transaction should be a blob transaction")
+        };
+        blob_tx.transaction.sidecar = other;
 
         assert!(matches!(
             state.validate_request(&mut request).await,
-            Err(ValidationError::MaxCommittedGasReachedForSlot(_, 5_000_000))
+            Err(ValidationError::BlobValidation(
+                BlobTransactionValidationError::WrongVersionedHash { .. }
+            ))
         ));
 
         Ok(())
@@ -946,7 +2408,7 @@ mod tests {
 
         assert!(matches!(
             state.validate_request(&mut request).await,
-            Err(ValidationError::MaxPriorityFeePerGasTooLow)
+            Err(ValidationError::MaxPriorityFeePerGasTooLow(_))
         ));
 
         // Create a transaction with a max priority fee that is correct
@@ -976,10 +2438,7 @@ mod tests {
         let slot = client.get_head().await?;
         state.update_head(None, slot).await?;
 
-        let base_fee = state.basefee();
-        let Some(max_base_fee) = calculate_max_basefee(base_fee, 10 - slot) else {
-            return Err(eyre::eyre!("Failed to calculate max base fee"));
-        };
+        let max_base_fee = state.max_basefee(10)?;
 
         // Create a transaction with a gas price that is too low
         let tx = default_test_transaction(*sender, None)
@@ -989,7 +2448,7 @@ mod tests {
 
         assert!(matches!(
             state.validate_request(&mut request).await,
-            Err(ValidationError::MaxPriorityFeePerGasTooLow)
+            Err(ValidationError::MaxPriorityFeePerGasTooLow(_))
         ));
 
         // Create a transaction with a gas price that is correct
@@ -1003,6 +2462,46 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_min_priority_fee_at_floor() -> eyre::Result<()> {
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let min_priority_fee = 2 * GWEI_TO_WEI as u128;
+        let limits = LimitsOpts { min_priority_fee, ..Default::default() };
+
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        let max_base_fee = state.max_basefee(10)?;
+
+        // The effective tip is the max priority fee for EIP-1559 transactions, and the gas
+        // price minus the basefee for legacy ones
+        let eip1559 =
+            |tip: u128| default_test_transaction(*sender, None).with_max_priority_fee_per_gas(tip);
+        let legacy =
+            |tip: u128| default_test_transaction(*sender, None).with_gas_price(max_base_fee + tip);
+
+        for tx in [eip1559(min_priority_fee - 1), legacy(min_priority_fee - 1)] {
+            let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+            let err = state.validate_request(&mut request).await.unwrap_err();
+            assert!(matches!(err, ValidationError::MaxPriorityFeePerGasTooLow(2_000_000_000)));
+        }
+
+        for tx in [eip1559(min_priority_fee), legacy(min_priority_fee)] {
+            let mut request = create_signed_inclusion_request(&[tx], sender_pk, 10).await?;
+            assert!(state.validate_request(&mut request).await.is_ok());
+        }
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_invalid_inclusion_request_duplicate_batch() -> eyre::Result<()> {
         let anvil = launch_anvil();
@@ -1019,10 +2518,7 @@ mod tests {
         let slot = client.get_head().await?;
         state.update_head(None, slot).await?;
 
-        let base_fee = state.basefee();
-        let Some(max_base_fee) = calculate_max_basefee(base_fee, 10 - slot) else {
-            return Err(eyre::eyre!("Failed to calculate max base fee"));
-        };
+        let max_base_fee = state.max_basefee(10)?;
 
         // Create a transaction with a gas price that is too low
         let tx = default_test_transaction(*sender, None)
@@ -1073,7 +2569,7 @@ mod tests {
         let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
         let signed_constraints = SignedConstraints { message, signature };
 
-        state.add_constraint(target_slot, signed_constraints);
+        state.add_constraint(target_slot, signed_constraints)?;
 
         assert!(state.get_block_template(target_slot).unwrap().transactions_len() == 1);
 
@@ -1121,7 +2617,7 @@ mod tests {
         let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
         let signed_constraints = SignedConstraints { message, signature };
 
-        state.add_constraint(target_slot, signed_constraints);
+        state.add_constraint(target_slot, signed_constraints)?;
 
         assert!(state.get_block_template(target_slot).unwrap().transactions_len() == 1);
 
@@ -1134,6 +2630,55 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_drain_slot() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        let bls_signer = LocalSigner::random();
+        for (account, nonce, target_slot) in [(0, 0, 10), (1, 0, 10), (2, 0, 11), (0, 1, 11)] {
+            let sender = anvil.addresses()[account];
+            let sender_pk = &anvil.keys()[account];
+            let tx = default_test_transaction(sender, Some(nonce));
+            let mut request =
+                create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+            let inclusion_request = request.clone();
+
+            assert!(state.validate_request(&mut request).await.is_ok());
+
+            let message = ConstraintsMessage::build(Default::default(), inclusion_request);
+            let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+            state.add_constraint(target_slot, SignedConstraints { message, signature })?;
+        }
+
+        // Draining slot 10 removes its constraints, so that they are never submitted, along
+        // with the commitment of the first sender in slot 11 which depends on its nonce
+        let drained = state.drain_slot(10);
+        assert_eq!(drained.len(), 2);
+        assert_eq!(drained[0].0, 10);
+        assert_eq!(drained[0].1.len(), 2);
+        assert_eq!(drained[1].0, 11);
+        assert_eq!(drained[1].1.len(), 1);
+        assert_eq!(drained[1].1[0].message.transactions[0].sender(), Some(&anvil.addresses()[0]));
+        assert!(state.get_block_template(10).is_none());
+
+        // The independent commitments of the other slots are untouched
+        let template = state.get_block_template(11).unwrap();
+        assert_eq!(template.transactions_len(), 1);
+        assert_eq!(template.get_diff(&anvil.addresses()[0]), Some((0, U256::ZERO)));
+        assert!(state.drain_slot(10).is_empty());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_invalidate_inclusion_request_with_excess_gas() -> eyre::Result<()> {
         let _ = tracing_subscriber::fmt::try_init();
@@ -1165,7 +2710,7 @@ mod tests {
         let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
         let signed_constraints = SignedConstraints { message, signature };
 
-        state.add_constraint(target_slot, signed_constraints);
+        state.add_constraint(target_slot, signed_constraints)?;
 
         assert!(state.get_block_template(target_slot).unwrap().transactions_len() == 1);
 
@@ -1182,6 +2727,260 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_post_slot_broadcast_of_missed_transactions() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let provider = ProviderBuilder::new().on_http(anvil.endpoint_url());
+        let client = StateClient::new(anvil.endpoint_url());
+        let rpc_client = RpcClient::new(anvil.endpoint_url());
+
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default())
+            .await?
+            .with_post_slot_broadcast(rpc_client.clone());
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        // Commit to two transactions from different senders
+        let target_slot = slot + 10;
+        let bls_signer = LocalSigner::random();
+        let mut committed = Vec::new();
+        for (sender, sender_pk) in anvil.addresses().iter().zip(anvil.keys()).take(2) {
+            let tx = default_test_transaction(*sender, None);
+            let request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+            committed.push(request.txs[0].clone());
+
+            let message = ConstraintsMessage::build(Default::default(), request);
+            let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+            state.add_constraint(target_slot, SignedConstraints { message, signature })?;
+        }
+
+        // Only the first one gets included
+        let included = &committed[0];
+        let receipt =
+            provider.send_raw_transaction(&included.encoded_2718()).await?.get_receipt().await?;
+        assert_eq!(receipt.transaction_hash, *included.hash());
+
+        // Only the missed transaction is broadcast
+        let template = state.get_block_template(target_slot).unwrap();
+        let hashes = template.transaction_hashes();
+        let receipts = client.get_receipts_unordered(hashes.as_ref()).await?;
+        let broadcast = broadcast_missed_transactions(&rpc_client, template, &receipts).await;
+        assert_eq!(broadcast, vec![*committed[1].hash()]);
+
+        // Which the state does once the slot has passed, without failing on it
+        state.update_head(None, target_slot).await?;
+        assert!(state.get_block_template(target_slot).is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_higher_priority_request_evicts_lower_one() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let limits = LimitsOpts {
+            max_commitments_per_slot: NonZero::new(1).unwrap(),
+            evict_lower_priority: true,
+            ..Default::default()
+        };
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        let target_slot = 10;
+        let bls_signer = LocalSigner::random();
+        let senders = anvil.addresses().iter().zip(anvil.keys()).take(3).collect::<Vec<_>>();
+
+        // Fill the slot with a 1 gwei tip commitment
+        let (sender, sender_pk) = senders[0];
+        let tx = default_test_transaction(*sender, None);
+        let mut low = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+        state.validate_request(&mut low).await?;
+        assert!(state.evictions_for_request(&low, target_slot).is_empty());
+
+        let message = ConstraintsMessage::build(Default::default(), low.clone());
+        let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+        state.add_constraint(target_slot, SignedConstraints { message, signature })?;
+
+        // A request with the same tip can't make room in the full slot
+        let (sender, sender_pk) = senders[1];
+        let tx = default_test_transaction(*sender, None);
+        let mut same = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+        assert!(matches!(
+            state.validate_request(&mut same).await,
+            Err(ValidationError::MaxCommitmentsReachedForSlot(_, 1))
+        ));
+
+        // A request with a higher tip evicts the lower one
+        let (sender, sender_pk) = senders[2];
+        let tx = default_test_transaction(*sender, None)
+            .with_max_priority_fee_per_gas(5 * GWEI_TO_WEI as u128);
+        let mut high = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+        state.validate_request(&mut high).await?;
+
+        let evictions = state.evictions_for_request(&high, target_slot);
+        assert_eq!(evictions, vec![0]);
+
+        let message = ConstraintsMessage::build(Default::default(), high.clone());
+        let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+        let constraints = vec![SignedConstraints { message, signature }];
+        let evicted = state.commit_constraints(target_slot, evictions, constraints)?;
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].message.transactions[0].hash(), low.txs[0].hash());
+
+        let template = state.get_block_template(target_slot).unwrap();
+        assert_eq!(template.transaction_hashes(), vec![*high.txs[0].hash()]);
+        // The state diff of the evicted sender is reverted
+        assert_eq!(template.get_diff(senders[0].0).map(|(nonce, _)| nonce), Some(0));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_commit_constraints_leaves_template_untouched_on_error() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default()).await?;
+
+        let target_slot = 10;
+        let bls_signer = LocalSigner::random();
+        let sign = |request: InclusionRequest| {
+            let message = ConstraintsMessage::build(Default::default(), request);
+            let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+            SignedConstraints { message, signature }
+        };
+
+        let (senders, keys) = (anvil.addresses(), anvil.keys());
+        let tx = default_test_transaction(senders[0], None);
+        let committed = create_signed_inclusion_request(&[tx], &keys[0], target_slot).await?;
+        state.commit_constraints(target_slot, Vec::new(), vec![sign(committed.clone())])?;
+
+        // The second constraint of the request conflicts with the first one, after the
+        // committed constraint was evicted to make room for them
+        let tx = default_test_transaction(senders[1], None);
+        let request = create_signed_inclusion_request(&[tx], &keys[1], target_slot).await?;
+        let constraints = vec![sign(request.clone()), sign(request)];
+        assert!(matches!(
+            state.commit_constraints(target_slot, vec![0], constraints),
+            Err(BlockTemplateError::NonceConflict { .. })
+        ));
+
+        // Neither the eviction nor the first constraint were applied
+        let template = state.get_block_template(target_slot).unwrap();
+        assert_eq!(template.transaction_hashes(), vec![*committed.txs[0].hash()]);
+        assert_eq!(template.get_diff(&senders[0]).map(|(nonce, _)| nonce), Some(1));
+        assert!(template.get_diff(&senders[1]).is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_inclusion_request_with_excess_value() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        // Each default test transaction pays 21_000 gas * 1 gwei of priority fees, so only
+        // one fits under the cap
+        let max_value = U256::from(30_000 * GWEI_TO_WEI);
+        let limits = LimitsOpts { max_commitment_value: Some(max_value), ..Default::default() };
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
+
+        let sender = anvil.addresses().first().unwrap();
+        let sender_pk = anvil.keys().first().unwrap();
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        let tx = default_test_transaction(*sender, None);
+
+        let target_slot = 10;
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+        let inclusion_request = request.clone();
+
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        let bls_signer = LocalSigner::random();
+        let message = ConstraintsMessage::build(Default::default(), inclusion_request);
+        let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+        let signed_constraints = SignedConstraints { message, signature };
+
+        state.add_constraint(target_slot, signed_constraints)?;
+
+        // This tx will exceed the committed value cap
+        let tx = default_test_transaction(*sender, Some(1));
+
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, target_slot).await?;
+
+        assert!(matches!(
+            state.validate_request(&mut request).await,
+            Err(ValidationError::MaxCommitmentValueReachedForSlot(10, value)) if value == max_value
+        ));
+
+        // Other slots are not affected
+        let tx = default_test_transaction(*sender, Some(1));
+        let mut request = create_signed_inclusion_request(&[tx], sender_pk, 11).await?;
+        assert!(state.validate_request(&mut request).await.is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_evicting_request_accepted_at_max_value() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+
+        // The 1 gwei tip commitment and the 1.2 gwei tip one don't fit together under the cap
+        let max_value = U256::from(30_000 * GWEI_TO_WEI);
+        let limits = LimitsOpts {
+            max_commitments_per_slot: NonZero::new(1).unwrap(),
+            evict_lower_priority: true,
+            max_commitment_value: Some(max_value),
+            ..Default::default()
+        };
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
+
+        // initialize the state by updating the head once
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        let target_slot = 10;
+        let (senders, keys) = (anvil.addresses(), anvil.keys());
+
+        let tx = default_test_transaction(senders[0], None);
+        let mut low = create_signed_inclusion_request(&[tx], &keys[0], target_slot).await?;
+        state.validate_request(&mut low).await?;
+
+        let bls_signer = LocalSigner::random();
+        let message = ConstraintsMessage::build(Default::default(), low);
+        let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+        state.add_constraint(target_slot, SignedConstraints { message, signature })?;
+
+        // The request evicts the commitment in the slot, so its value replaces the evicted one
+        let tx = default_test_transaction(senders[1], None)
+            .with_max_priority_fee_per_gas(GWEI_TO_WEI as u128 * 6 / 5);
+        let mut high = create_signed_inclusion_request(&[tx], &keys[1], target_slot).await?;
+        state.validate_request(&mut high).await?;
+        assert_eq!(state.evictions_for_request(&high, target_slot), vec![0]);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_valid_bundle_inclusion_request() -> eyre::Result<()> {
         let _ = tracing_subscriber::fmt::try_init();
@@ -1209,6 +3008,68 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_simulate_dependent_bundle_in_order() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let client = StateClient::new(anvil.endpoint_url());
+        let provider = ProviderBuilder::new().on_http(anvil.endpoint_url());
+
+        // A contract that sets a flag when called with calldata, and otherwise reverts unless
+        // the flag is set
+        let contract = Address::random();
+        let code = Bytes::from_static(&[
+            0x36, 0x60, 0x0f, 0x57, 0x60, 0x00, 0x54, 0x60, 0x16, 0x57, 0x60, 0x00, 0x60, 0x00,
+            0xfd, 0x5b, 0x60, 0x01, 0x60, 0x00, 0x55, 0x00, 0x5b, 0x00,
+        ]);
+        provider.raw_request::<_, ()>("anvil_setCode".into(), (contract, code)).await?;
+
+        let limits = LimitsOpts { simulate_bundles: true, ..Default::default() };
+        let mut state = ExecutionState::new(client.clone(), limits).await?;
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        // The second transaction, from another sender, depends on the first one
+        let mut txs = Vec::new();
+        for (i, input) in [Bytes::from_static(&[0x01]), Bytes::new()].into_iter().enumerate() {
+            let wallet = EthereumWallet::from(PrivateKeySigner::from(anvil.keys()[i].clone()));
+            let tx = default_test_transaction(anvil.addresses()[i], None)
+                .with_to(contract)
+                .with_input(input)
+                .with_gas_limit(100_000);
+            txs.push(FullTransaction::decode_enveloped(tx.build(&wallet).await?.encoded_2718())?);
+        }
+        let (set_flag, check_flag) = (txs[0].clone(), txs[1].clone());
+
+        let request = |txs: Vec<FullTransaction>| InclusionRequest {
+            slot: 10,
+            txs,
+            top: false,
+            reverting_tx_hashes: Vec::new(),
+            max_slot: None,
+            webhook: None,
+            digest_version: Default::default(),
+            signature: None,
+            signer: None,
+        };
+
+        let mut in_order = request(vec![set_flag.clone(), check_flag.clone()]);
+        assert!(state.validate_request(&mut in_order).await.is_ok());
+
+        let mut reordered = request(vec![check_flag.clone(), set_flag.clone()]);
+        assert!(matches!(
+            state.validate_request(&mut reordered).await,
+            Err(ValidationError::BundleReverted(hash)) if hash == *check_flag.hash()
+        ));
+
+        // Unless the reverting transaction is allowed to revert
+        reordered.reverting_tx_hashes = vec![*check_flag.hash()];
+        assert!(state.validate_request(&mut reordered).await.is_ok());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_invalid_bundle_inclusion_request_nonce() -> eyre::Result<()> {
         let _ = tracing_subscriber::fmt::try_init();
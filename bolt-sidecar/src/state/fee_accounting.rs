@@ -0,0 +1,211 @@
+use std::{fmt, sync::Arc};
+
+use alloy::primitives::{Address, U256};
+use tracing::info;
+
+use crate::primitives::{FullTransaction, Slot, TransactionExt};
+
+/// The fee accrued by a sender for a commitment honored in a slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccruedFee {
+    /// The sender of the committed transactions.
+    pub sender: Address,
+    /// The target slot of the commitment.
+    pub slot: Slot,
+    /// The fee amount, in wei.
+    pub amount: U256,
+}
+
+/// Prices the transactions of the honored commitments.
+pub trait CommitmentPricing: Send + Sync {
+    /// Returns the fee owed for committing to the given transaction, in wei.
+    fn price(&self, tx: &FullTransaction) -> U256;
+}
+
+impl<F> CommitmentPricing for F
+where
+    F: Fn(&FullTransaction) -> U256 + Send + Sync,
+{
+    fn price(&self, tx: &FullTransaction) -> U256 {
+        self(tx)
+    }
+}
+
+/// A pricing charging a flat fee per transaction, plus a fee per unit of its gas limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasPricing {
+    /// The flat fee per transaction, in wei.
+    pub base_fee: u128,
+    /// The fee per unit of gas limit of the transaction, in wei.
+    pub fee_per_gas: u128,
+}
+
+impl CommitmentPricing for GasPricing {
+    fn price(&self, tx: &FullTransaction) -> U256 {
+        U256::from(self.base_fee) + U256::from(tx.gas_limit()) * U256::from(self.fee_per_gas)
+    }
+}
+
+/// Records the fees accrued by the honored commitments, e.g. in a database for later billing.
+pub trait FeeSink: Send + Sync {
+    /// Record the fee accrued by a sender.
+    fn record(&self, fee: AccruedFee);
+}
+
+impl<F> FeeSink for F
+where
+    F: Fn(AccruedFee) + Send + Sync,
+{
+    fn record(&self, fee: AccruedFee) {
+        self(fee)
+    }
+}
+
+/// A sink logging the accrued fees. This is the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogFeeSink;
+
+impl FeeSink for LogFeeSink {
+    fn record(&self, fee: AccruedFee) {
+        let AccruedFee { sender, slot, amount } = fee;
+        info!(%sender, slot, %amount, "Accrued commitment fee");
+    }
+}
+
+/// Accounting of the fees accrued by the honored commitments: each commitment is priced
+/// and its fee recorded to the sink.
+///
+/// The fees are recorded by the [ExecutionState](super::ExecutionState) once the target slot
+/// of the commitments has passed, for the ones with all their transactions included.
+#[derive(Clone)]
+pub struct FeeAccounting {
+    pricing: Arc<dyn CommitmentPricing>,
+    sink: Arc<dyn FeeSink>,
+}
+
+impl FeeAccounting {
+    /// Create a new accounting with the given pricing, logging the accrued fees.
+    pub fn new(pricing: Arc<dyn CommitmentPricing>) -> Self {
+        Self { pricing, sink: Arc::new(LogFeeSink) }
+    }
+
+    /// Set the sink the accrued fees are recorded to.
+    pub fn with_sink(mut self, sink: Arc<dyn FeeSink>) -> Self {
+        self.sink = sink;
+        self
+    }
+
+    /// Record the fees accrued by a commitment honored in the given target slot, one per
+    /// sender of its transactions. The senders must have been recovered, i.e. the request
+    /// validated.
+    pub fn record_honored(&self, slot: Slot, txs: &[FullTransaction]) {
+        let mut fees: Vec<(Address, U256)> = Vec::with_capacity(1);
+        for tx in txs {
+            let Some(sender) = tx.sender() else { continue };
+            let fee = self.pricing.price(tx);
+
+            match fees.iter_mut().find(|(s, _)| s == sender) {
+                Some((_, total)) => *total += fee,
+                None => fees.push((*sender, fee)),
+            }
+        }
+
+        for (sender, amount) in fees {
+            self.sink.record(AccruedFee { sender, slot, amount });
+        }
+    }
+}
+
+impl fmt::Debug for FeeAccounting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FeeAccounting").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use alloy::{
+        eips::eip2718::Encodable2718,
+        primitives::U256,
+        providers::{Provider, ProviderBuilder},
+    };
+
+    use super::{AccruedFee, FeeAccounting, GasPricing};
+    use crate::{
+        config::limits::LimitsOpts,
+        primitives::{ConstraintsMessage, SignedConstraints},
+        signer::local::LocalSigner,
+        state::{
+            fetcher::{StateClient, StateFetcher},
+            ExecutionState,
+        },
+        test_util::{create_signed_inclusion_request, default_test_transaction, launch_anvil},
+    };
+
+    #[tokio::test]
+    async fn test_state_records_fees_of_honored_commitments() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let anvil = launch_anvil();
+        let provider = ProviderBuilder::new().on_http(anvil.endpoint_url());
+        let client = StateClient::new(anvil.endpoint_url());
+
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let sink = {
+            let recorded = recorded.clone();
+            move |fee: AccruedFee| recorded.lock().unwrap().push(fee)
+        };
+
+        let pricing = GasPricing { base_fee: 1_000, fee_per_gas: 2 };
+        let accounting = FeeAccounting::new(Arc::new(pricing)).with_sink(Arc::new(sink));
+
+        let mut state = ExecutionState::new(client.clone(), LimitsOpts::default())
+            .await?
+            .with_fee_accounting(accounting);
+
+        let slot = client.get_head().await?;
+        state.update_head(None, slot).await?;
+
+        // The first sender commits to two transactions with a ranged request starting before
+        // the target slot, the second one to a single transaction which is never included
+        let target_slot = slot + 10;
+        let bls_signer = LocalSigner::random();
+        let (senders, keys) = (anvil.addresses(), anvil.keys());
+
+        let txs = [
+            default_test_transaction(senders[0], None),
+            default_test_transaction(senders[0], Some(1)),
+        ];
+        let honored = create_signed_inclusion_request(&txs, &keys[0], target_slot - 2).await?;
+        let violated = create_signed_inclusion_request(
+            &[default_test_transaction(senders[1], None)],
+            &keys[1],
+            target_slot,
+        )
+        .await?;
+
+        for request in [honored.clone(), violated] {
+            let message = ConstraintsMessage::build(Default::default(), request);
+            let signature = bls_signer.sign_commit_boost_root(message.digest()).unwrap();
+            state.add_constraint(target_slot, SignedConstraints { message, signature })?;
+        }
+
+        // Nothing is recorded until the commitments are settled
+        assert!(recorded.lock().unwrap().is_empty());
+
+        for tx in &honored.txs {
+            provider.send_raw_transaction(&tx.encoded_2718()).await?.get_receipt().await?;
+        }
+        state.update_head(None, target_slot).await?;
+
+        // Both transactions of 21000 gas are summed in a single fee for their sender, in the
+        // target slot of the commitment
+        let amount = U256::from(2 * (1_000 + 21_000 * 2));
+        let fee = AccruedFee { sender: senders[0], slot: target_slot, amount };
+        assert_eq!(*recorded.lock().unwrap(), vec![fee]);
+
+        Ok(())
+    }
+}
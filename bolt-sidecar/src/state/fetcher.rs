@@ -10,9 +10,12 @@ use futures::{stream::FuturesOrdered, StreamExt};
 use reqwest::Url;
 use tracing::error;
 
-use crate::{client::RpcClient, primitives::AccountState};
+use crate::{
+    client::RpcClient,
+    primitives::{is_contract_code, AccountState, FullTransaction},
+};
 
-use super::execution::StateUpdate;
+use super::{execution::StateUpdate, simulator, SimulationOutcome};
 
 /// Maximum retries for RPC requests.
 const MAX_RETRIES: u32 = 8;
@@ -36,6 +39,10 @@ pub trait StateFetcher {
     /// Get the basefee of the latest block or the block at the specified number.
     async fn get_basefee(&self, block_number: Option<u64>) -> Result<u128, TransportError>;
 
+    /// Get the basefee of the block following the latest block or the block at the specified
+    /// number.
+    async fn get_next_basefee(&self, block_number: Option<u64>) -> Result<u128, TransportError>;
+
     /// Get the blob basefee of the latest block or the block at the specified number.
     async fn get_blob_basefee(&self, block_number: Option<u64>) -> Result<u128, TransportError>;
 
@@ -55,6 +62,14 @@ pub trait StateFetcher {
         &self,
         hashes: &[TxHash],
     ) -> Result<Vec<Option<TransactionReceipt>>, TransportError>;
+
+    /// Simulate the given transactions in order on top of the latest block, returning the
+    /// outcome of each one.
+    async fn simulate_bundle(
+        &self,
+        txs: &[FullTransaction],
+        chain_id: u64,
+    ) -> Result<Vec<SimulationOutcome>, TransportError>;
 }
 
 /// A basic state fetcher that uses an RPC client to fetch state updates.
@@ -114,14 +129,16 @@ impl StateFetcher for StateClient {
         batch.send().await?;
 
         let basefee = self.client.get_basefee(None);
+        let next_basefee = self.client.get_next_basefee(None);
         let blob_basefee = self.client.get_blob_basefee(None);
 
         // Collect the results
-        let (nonce_vec, balance_vec, code_vec, basefee, blob_basefee) = tokio::join!(
+        let (nonce_vec, balance_vec, code_vec, basefee, next_basefee, blob_basefee) = tokio::join!(
             nonce_futs.collect::<Vec<_>>(),
             balance_futs.collect::<Vec<_>>(),
             code_futs.collect::<Vec<_>>(),
             basefee,
+            next_basefee,
             blob_basefee,
         );
 
@@ -158,18 +175,19 @@ impl StateFetcher for StateClient {
             account_states
                 .entry(**addr)
                 .and_modify(|s: &mut AccountState| {
-                    s.has_code = !code.is_empty();
+                    s.has_code = is_contract_code(&code);
                 })
                 .or_insert(AccountState {
                     transaction_count: 0,
                     balance: U256::ZERO,
-                    has_code: !code.is_empty(),
+                    has_code: is_contract_code(&code),
                 });
         }
 
         Ok(StateUpdate {
             account_states,
             min_basefee: basefee?,
+            next_basefee: next_basefee?,
             min_blob_basefee: blob_basefee?,
             block_number,
         })
@@ -183,6 +201,10 @@ impl StateFetcher for StateClient {
         self.client.get_basefee(block_number).await
     }
 
+    async fn get_next_basefee(&self, block_number: Option<u64>) -> Result<u128, TransportError> {
+        self.client.get_next_basefee(block_number).await
+    }
+
     async fn get_blob_basefee(&self, block_number: Option<u64>) -> Result<u128, TransportError> {
         self.client.get_blob_basefee(block_number).await
     }
@@ -220,6 +242,14 @@ impl StateFetcher for StateClient {
     ) -> Result<Vec<Option<TransactionReceipt>>, TransportError> {
         self.client.get_receipts(hashes).await
     }
+
+    async fn simulate_bundle(
+        &self,
+        txs: &[FullTransaction],
+        chain_id: u64,
+    ) -> Result<Vec<SimulationOutcome>, TransportError> {
+        simulator::simulate_bundle(&self.client, chain_id, txs).await
+    }
 }
 
 #[cfg(test)]
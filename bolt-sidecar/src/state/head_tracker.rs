@@ -1,7 +1,13 @@
-use alloy::rpc::types::beacon::events::HeadEvent;
-use beacon_api_client::Topic;
+use alloy::{primitives::B256, rpc::types::beacon::events::HeadEvent};
+use beacon_api_client::{BlockId, Topic};
 use futures::StreamExt;
-use std::time::Duration;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 use tokio::{sync::broadcast, task::AbortHandle, time::sleep};
 use tracing::warn;
 
@@ -14,11 +20,14 @@ const RETRY_DELAY: Duration = Duration::from_secs(1);
 /// and broadcast updates to its subscribers.
 ///
 /// Durability: the tracker will always attempt to reconnect to the provided
-/// beacon client URL in case of disconnection or other errors.
+/// beacon client URL in case of disconnection or other errors. While the event
+/// stream is down, the head is polled from the beacon client instead.
 #[derive(Debug)]
 pub struct HeadTracker {
     /// Channel to receive updates of the "Head" beacon topic
     new_heads_rx: broadcast::Receiver<HeadEvent>,
+    /// The slot of the most recent head received
+    latest_slot: Arc<AtomicU64>,
     /// Handle to the background task that listens for new head events.
     /// Kept to allow for graceful shutdown.
     quit: AbortHandle,
@@ -39,39 +48,54 @@ impl HeadTracker {
     /// start listening for new head events in the background
     pub fn start(beacon_client: BeaconClient) -> Self {
         let (new_heads_tx, new_heads_rx) = broadcast::channel(32);
+        let latest_slot = Arc::new(AtomicU64::new(0));
 
+        let slot = latest_slot.clone();
         let task = tokio::spawn(async move {
+            let broadcast_head = |event: HeadEvent| {
+                slot.fetch_max(event.slot, Ordering::Relaxed);
+                if let Err(err) = new_heads_tx.send(event) {
+                    warn!(?err, "failed to broadcast new head event to subscribers");
+                }
+            };
+
             loop {
-                let mut event_stream = match beacon_client.get_events::<NewHeadsTopic>().await {
-                    Ok(events) => events,
+                match beacon_client.get_events::<NewHeadsTopic>().await {
+                    Ok(mut event_stream) => loop {
+                        match event_stream.next().await {
+                            Some(Ok(event)) => broadcast_head(event),
+                            Some(Err(err)) => {
+                                warn!(?err, "error reading new head event stream, retrying...");
+                                break;
+                            }
+                            None => {
+                                warn!("new head event stream ended, retrying...");
+                                break;
+                            }
+                        }
+                    },
                     Err(err) => {
                         warn!(?err, "failed to subscribe to new heads topic, retrying...");
-                        sleep(RETRY_DELAY).await;
-                        continue;
-                    }
-                };
-
-                let event = match event_stream.next().await {
-                    Some(Ok(event)) => event,
-                    Some(Err(err)) => {
-                        warn!(?err, "error reading new head event stream, retrying...");
-                        sleep(RETRY_DELAY).await;
-                        continue;
-                    }
-                    None => {
-                        warn!("new head event stream ended, retrying...");
-                        sleep(RETRY_DELAY).await;
-                        continue;
                     }
-                };
+                }
 
-                if let Err(err) = new_heads_tx.send(event) {
-                    warn!(?err, "failed to broadcast new head event to subscribers");
+                // Poll the head until the stream is back, so that new heads are not missed
+                if let Some(event) = poll_head(&beacon_client).await {
+                    if event.slot > slot.load(Ordering::Relaxed) {
+                        broadcast_head(event);
+                    }
                 }
+
+                sleep(RETRY_DELAY).await;
             }
         });
 
-        Self { new_heads_rx, quit: task.abort_handle() }
+        Self { new_heads_rx, latest_slot, quit: task.abort_handle() }
+    }
+
+    /// Returns the slot of the most recent head received, or 0 if none was received yet.
+    pub fn latest_slot(&self) -> u64 {
+        self.latest_slot.load(Ordering::Relaxed)
     }
 
     /// Stop the tracker and cleanup resources
@@ -93,13 +117,81 @@ impl HeadTracker {
     }
 }
 
+/// Fetch the current head from the beacon client, as a fallback for the event stream.
+async fn poll_head(beacon_client: &BeaconClient) -> Option<HeadEvent> {
+    let summary = match beacon_client.get_beacon_header(BlockId::Head).await {
+        Ok(summary) => summary,
+        Err(err) => {
+            warn!(?err, "failed to poll the beacon head");
+            return None;
+        }
+    };
+
+    let header = summary.header.message;
+    Some(HeadEvent {
+        slot: header.slot,
+        block: B256::from_slice(summary.root.as_ref()),
+        state: B256::from_slice(header.state_root.as_ref()),
+        epoch_transition: false,
+        previous_duty_dependent_root: B256::ZERO,
+        current_duty_dependent_root: B256::ZERO,
+        execution_optimistic: false,
+    })
+}
+
 #[cfg(test)]
 mod tests {
+    use std::{convert::Infallible, time::Duration};
+
+    use alloy::primitives::B256;
+    use axum::{
+        response::sse::{Event, Sse},
+        routing::get,
+        Router,
+    };
+    use futures::{stream, Stream, StreamExt};
     use reqwest::Url;
+    use serde_json::json;
+    use tokio::net::TcpListener;
     use tracing::warn;
 
     use crate::{client::BeaconClient, state::HeadTracker, test_util::try_get_beacon_api_url};
 
+    #[tokio::test]
+    async fn test_follow_mock_head_events() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        async fn head_events() -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+            let head = json!({
+                "slot": "42",
+                "block": B256::repeat_byte(1),
+                "state": B256::repeat_byte(2),
+                "epoch_transition": false,
+                "previous_duty_dependent_root": B256::ZERO,
+                "current_duty_dependent_root": B256::ZERO,
+                "execution_optimistic": false
+            });
+            let event = Event::default().event("head").data(head.to_string());
+
+            // Keep the stream open after the event, like a beacon node does
+            Sse::new(stream::once(async move { Ok(event) }).chain(stream::pending()))
+        }
+
+        let router = Router::new().route("/eth/v1/events", get(head_events));
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = Url::parse(&format!("http://{}", listener.local_addr()?))?;
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let mut tracker = HeadTracker::start(BeaconClient::new(url));
+        let head = tokio::time::timeout(Duration::from_secs(5), tracker.next_head()).await??;
+
+        assert_eq!(head.slot, 42);
+        assert_eq!(head.block, B256::repeat_byte(1));
+        assert_eq!(tracker.latest_slot(), 42);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_fetch_next_beacon_head() -> eyre::Result<()> {
         let _ = tracing_subscriber::fmt::try_init();
@@ -15,6 +15,10 @@ pub use execution::{ExecutionState, ValidationError};
 pub mod fetcher;
 pub use fetcher::StateClient;
 
+/// Module to simulate bundles of transactions with revm.
+pub mod simulator;
+pub use simulator::SimulationOutcome;
+
 /// Module to track the consensus state.
 pub mod consensus;
 pub use consensus::ConsensusState;
@@ -23,6 +27,30 @@ pub use consensus::ConsensusState;
 pub mod head_tracker;
 pub use head_tracker::HeadTracker;
 
+/// Module to persist the signed constraints across restarts.
+pub mod constraints_store;
+pub use constraints_store::{replay_constraints, ConstraintsStore, ConstraintsStoreHandle};
+
+/// Module to hold the reservations of transactions committed to by hash.
+pub mod reservations;
+pub use reservations::{ReservationBook, ReservationError};
+
+/// Module to keep the relay responses to the constraints submissions for auditing.
+pub mod commitment_status;
+pub use commitment_status::{CommitmentStatus, CommitmentStatuses, RelaySubmission};
+
+/// Module to track the beacon chain slots over time.
+pub mod slot_clock;
+pub use slot_clock::SlotClock;
+
+/// Module to check the timing of the constraints submissions.
+pub mod submission_timing;
+pub use submission_timing::SubmissionTiming;
+
+/// Module to account for the fees accrued by the honored commitments.
+pub mod fee_accounting;
+pub use fee_accounting::{FeeAccounting, FeeSink, GasPricing};
+
 /// Module that defines the account state cache.
 pub mod account_state;
 pub use account_state::AccountStateCache;
@@ -0,0 +1,233 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use alloy::primitives::{Address, Signature, TxHash, B256};
+use parking_lot::Mutex;
+
+use crate::{
+    config::limits::LimitsOpts,
+    primitives::{FullTransaction, ReserveInclusionRequest},
+};
+
+use super::ValidationError;
+
+/// Errors that can occur when reserving an inclusion or submitting its body.
+#[derive(Debug, thiserror::Error)]
+pub enum ReservationError {
+    /// The same reservation is already pending.
+    #[error("Duplicate reservation")]
+    Duplicate,
+    /// The reservation doesn't exist or has expired.
+    #[error("Reservation not found or expired")]
+    NotFound,
+    /// The submitted transaction body doesn't match the reserved transaction hash.
+    #[error("Transaction body mismatch: reserved {expected}, got {got}")]
+    BodyMismatch {
+        /// The reserved transaction hash.
+        expected: TxHash,
+        /// The hash of the submitted transaction.
+        got: TxHash,
+    },
+    /// The reservation doesn't fit in the limits of the slot.
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
+}
+
+/// A pending reservation for the inclusion of a transaction, waiting for its body.
+#[derive(Debug, Clone)]
+pub struct Reservation {
+    /// The reservation request.
+    pub request: ReserveInclusionRequest,
+    /// The signer of the reservation request.
+    pub signer: Address,
+    /// The signature of the user over the reservation request digest.
+    pub signature: Signature,
+    /// The instant after which the reservation is dropped if the body wasn't submitted.
+    pub expires_at: Instant,
+}
+
+/// The book of pending inclusion reservations.
+///
+/// Reservations hold a position and some gas in the target slot until the transaction
+/// body is submitted or the reservation expires. Once the body is submitted, the transaction
+/// goes through the same validation as any other inclusion request.
+///
+/// The book is cheap to clone and all clones share the same state, so that the execution
+/// state counts the pending reservations in the slot limits while the commitments API
+/// makes and takes them.
+#[derive(Debug, Clone)]
+pub struct ReservationBook {
+    /// The pending reservations, indexed by their request digest.
+    reservations: Arc<Mutex<HashMap<B256, Reservation>>>,
+    /// How long a reservation is held for.
+    ttl: Duration,
+    /// The sidecar's operating limits, bounding the reservations per slot.
+    limits: LimitsOpts,
+}
+
+impl ReservationBook {
+    /// Creates a new empty reservation book.
+    pub fn new(ttl: Duration, limits: LimitsOpts) -> Self {
+        Self { reservations: Arc::new(Mutex::new(HashMap::new())), ttl, limits }
+    }
+
+    /// Returns how long a reservation is held for.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Returns the number of pending reservations for the given slot, and the gas they hold.
+    pub fn usage(&self, slot: u64) -> (usize, u64) {
+        let now = Instant::now();
+        self.reservations
+            .lock()
+            .values()
+            .filter(|reservation| reservation.request.slot == slot && reservation.expires_at > now)
+            .fold((0, 0), |(count, gas), reservation| {
+                (count + 1, gas + reservation.request.gas_limit)
+            })
+    }
+
+    /// Reserves the inclusion of the requested transaction hash. Returns the reservation id.
+    pub fn reserve(
+        &self,
+        request: ReserveInclusionRequest,
+        signer: Address,
+        signature: Signature,
+    ) -> Result<B256, ReservationError> {
+        let now = Instant::now();
+        let mut reservations = self.reservations.lock();
+        reservations.retain(|_, reservation| reservation.expires_at > now);
+
+        let id = request.digest();
+        if reservations.contains_key(&id) {
+            return Err(ReservationError::Duplicate);
+        }
+
+        let slot = request.slot;
+        let (count, gas) = reservations
+            .values()
+            .filter(|reservation| reservation.request.slot == slot)
+            .fold((0, 0), |(count, gas), reservation| {
+                (count + 1, gas + reservation.request.gas_limit)
+            });
+
+        let max_commitments = self.limits.max_commitments_per_slot.get();
+        if count >= max_commitments {
+            return Err(ValidationError::MaxCommitmentsReachedForSlot(slot, max_commitments).into());
+        }
+
+        let max_gas = self.limits.max_committed_gas_per_slot.get();
+        if gas + request.gas_limit > max_gas {
+            return Err(ValidationError::MaxCommittedGasReachedForSlot(slot, max_gas).into());
+        }
+
+        let reservation = Reservation { request, signer, signature, expires_at: now + self.ttl };
+        reservations.insert(id, reservation);
+
+        Ok(id)
+    }
+
+    /// Takes the reservation with the given id if `tx` matches the reserved transaction hash.
+    ///
+    /// If the body doesn't match, the reservation is kept so that the right body can still
+    /// be submitted before it expires.
+    pub fn take(&self, id: B256, tx: &FullTransaction) -> Result<Reservation, ReservationError> {
+        let mut reservations = self.reservations.lock();
+
+        let reservation = match reservations.get(&id) {
+            Some(reservation) if reservation.expires_at > Instant::now() => reservation,
+            Some(_) => {
+                reservations.remove(&id);
+                return Err(ReservationError::NotFound);
+            }
+            None => return Err(ReservationError::NotFound),
+        };
+
+        if reservation.request.tx_hash != *tx.hash() {
+            return Err(ReservationError::BodyMismatch {
+                expected: reservation.request.tx_hash,
+                got: *tx.hash(),
+            });
+        }
+
+        Ok(reservations.remove(&id).expect("reservation exists"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZero, time::Duration};
+
+    use alloy::primitives::{Address, Signature, B256, U256};
+
+    use crate::{
+        config::limits::LimitsOpts,
+        primitives::{FullTransaction, InclusionRequest, ReserveInclusionRequest},
+    };
+
+    use super::{ReservationBook, ReservationError};
+
+    fn test_tx() -> FullTransaction {
+        let raw_tx = "0x02f86c870c72dd9d5e883e4d0183408f2382520894d2e2adf7177b7a8afddbc12d1634cf23ea1a71020180c001a08556dcfea479b34675db3fe08e29486fe719c2b22f6b0c1741ecbbdce4575cc6a01cd48009ccafd6b9f1290bbe2ceea268f94101d1d322c787018423ebcbc87ab4";
+        FullTransaction::decode_enveloped(alloy::hex::decode(raw_tx).unwrap()).unwrap()
+    }
+
+    fn dummy_signature() -> Signature {
+        Signature::from_rs_and_parity(U256::from(1), U256::from(1), false).unwrap()
+    }
+
+    #[test]
+    fn test_reservation_digest_matches_inclusion_request() {
+        let tx = test_tx();
+        let reservation =
+            ReserveInclusionRequest { slot: 42, tx_hash: *tx.hash(), gas_limit: 21_000 };
+        let inclusion = InclusionRequest {
+            slot: 42,
+            txs: vec![tx],
+            top: false,
+            reverting_tx_hashes: Vec::new(),
+            max_slot: None,
+            webhook: None,
+            digest_version: Default::default(),
+            signature: None,
+            signer: None,
+        };
+
+        assert_eq!(reservation.digest(), inclusion.digest());
+    }
+
+    #[test]
+    fn test_reservation_limits_and_expiry() {
+        let limits =
+            LimitsOpts { max_commitments_per_slot: NonZero::new(1).unwrap(), ..Default::default() };
+        let book = ReservationBook::new(Duration::ZERO, limits);
+        let tx = test_tx();
+
+        let request = ReserveInclusionRequest { slot: 1, tx_hash: *tx.hash(), gas_limit: 21_000 };
+        let id = book.reserve(request.clone(), Address::ZERO, dummy_signature()).unwrap();
+
+        // The reservation expires immediately, so the body can't be submitted anymore
+        assert!(matches!(book.take(id, &tx), Err(ReservationError::NotFound)));
+
+        // Expired reservations don't count towards the slot limits
+        let other = ReserveInclusionRequest { slot: 1, tx_hash: B256::random(), gas_limit: 21_000 };
+        book.reserve(other.clone(), Address::ZERO, dummy_signature()).unwrap();
+
+        // Pending ones do
+        let book = ReservationBook::new(Duration::from_secs(60), limits);
+        book.reserve(request.clone(), Address::ZERO, dummy_signature()).unwrap();
+        assert!(matches!(
+            book.reserve(request, Address::ZERO, dummy_signature()),
+            Err(ReservationError::Duplicate)
+        ));
+        assert!(matches!(
+            book.reserve(other, Address::ZERO, dummy_signature()),
+            Err(ReservationError::Validation(_))
+        ));
+        assert_eq!(book.usage(1), (1, 21_000));
+    }
+}
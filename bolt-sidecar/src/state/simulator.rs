@@ -0,0 +1,263 @@
+use alloy::{
+    eips::eip2930::AccessList,
+    primitives::{Address, B256, U256},
+    transports::{TransportError, TransportErrorKind},
+};
+use reth_primitives::PooledTransactionsElement;
+use revm::{
+    db::CacheDB,
+    primitives::{
+        AccountInfo, AuthorizationList, BlobExcessGasAndPrice, BlockEnv, Bytecode, EVMError,
+        ExecutionResult, SpecId, TxEnv,
+    },
+    DatabaseRef, Evm,
+};
+use tokio::runtime::Handle;
+
+use crate::{
+    client::RpcClient,
+    primitives::{FullTransaction, TransactionExt},
+};
+
+/// The outcome of a transaction simulated as part of a bundle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimulationOutcome {
+    /// The transaction executed successfully.
+    Success,
+    /// The transaction reverted, halted, or was invalid on top of the state left by the
+    /// previous transactions of the bundle.
+    Failure(String),
+}
+
+impl SimulationOutcome {
+    /// Returns true if the transaction executed successfully.
+    pub const fn is_success(&self) -> bool {
+        matches!(self, Self::Success)
+    }
+}
+
+/// Simulates the given transactions in order with revm, on top of the state of the latest block
+/// of the execution client, and returns the outcome of each one.
+///
+/// The state touched by the transactions is read from the execution client as they execute,
+/// and the changes of each transaction are applied before executing the next one.
+pub async fn simulate_bundle(
+    client: &RpcClient,
+    chain_id: u64,
+    txs: &[FullTransaction],
+) -> Result<Vec<SimulationOutcome>, TransportError> {
+    let latest = client.get_block(None, false).await?;
+
+    let db = RpcDatabase {
+        client: client.clone(),
+        block_number: latest.header.number,
+        handle: Handle::current(),
+    };
+
+    // The fees are left out so that the simulation doesn't depend on the base fee of the block
+    let block = BlockEnv {
+        number: U256::from(latest.header.number + 1),
+        timestamp: U256::from(latest.header.timestamp),
+        gas_limit: U256::from(latest.header.gas_limit),
+        basefee: U256::ZERO,
+        blob_excess_gas_and_price: Some(BlobExcessGasAndPrice {
+            excess_blob_gas: 0,
+            blob_gasprice: 1,
+        }),
+        ..Default::default()
+    };
+
+    // EIP-7702 transactions are only valid from Prague onwards
+    let spec_id = if txs.iter().any(|tx| tx.authorization_list().is_some()) {
+        SpecId::PRAGUE
+    } else {
+        SpecId::CANCUN
+    };
+
+    let txs = txs.to_vec();
+
+    // The database blocks on the requests to the execution client
+    tokio::task::spawn_blocking(move || {
+        let mut db = CacheDB::new(db);
+        let mut evm = Evm::builder()
+            .with_db(&mut db)
+            .with_spec_id(spec_id)
+            .modify_cfg_env(|cfg| cfg.chain_id = chain_id)
+            .modify_block_env(|env| *env = block)
+            .build();
+
+        let mut outcomes = Vec::with_capacity(txs.len());
+        for tx in &txs {
+            let Some(sender) = tx.sender().copied().or_else(|| tx.recover_signer()) else {
+                outcomes.push(SimulationOutcome::Failure("unknown sender".to_string()));
+                continue;
+            };
+
+            *evm.tx_mut() = to_tx_env(tx, sender);
+
+            let outcome = match evm.transact_commit() {
+                Ok(ExecutionResult::Success { .. }) => SimulationOutcome::Success,
+                Ok(ExecutionResult::Revert { output, .. }) => {
+                    SimulationOutcome::Failure(format!("reverted: {output}"))
+                }
+                Ok(ExecutionResult::Halt { reason, .. }) => {
+                    SimulationOutcome::Failure(format!("halted: {reason:?}"))
+                }
+                Err(EVMError::Database(err)) => return Err(err),
+                Err(err) => SimulationOutcome::Failure(err.to_string()),
+            };
+
+            outcomes.push(outcome);
+        }
+
+        Ok(outcomes)
+    })
+    .await
+    .map_err(TransportErrorKind::custom)?
+}
+
+/// Converts a signed transaction into the environment of its execution by the given sender.
+///
+/// The fees are left out so that the execution doesn't depend on the base fee of the block,
+/// but everything else is kept: the access list, the blob hashes and the EIP-7702
+/// authorizations all affect the execution.
+fn to_tx_env(tx: &FullTransaction, sender: Address) -> TxEnv {
+    let (access_list, blob_hashes, max_fee_per_blob_gas) = match &tx.tx {
+        PooledTransactionsElement::Legacy { .. } => (AccessList::default(), Vec::new(), None),
+        PooledTransactionsElement::Eip2930 { transaction, .. } => {
+            (transaction.access_list.clone(), Vec::new(), None)
+        }
+        PooledTransactionsElement::Eip1559 { transaction, .. } => {
+            (transaction.access_list.clone(), Vec::new(), None)
+        }
+        PooledTransactionsElement::Eip7702 { transaction, .. } => {
+            (transaction.access_list.clone(), Vec::new(), None)
+        }
+        PooledTransactionsElement::BlobTransaction(blob_tx) => {
+            let tx = &blob_tx.transaction.tx;
+            let max_fee_per_blob_gas = Some(U256::from(tx.max_fee_per_blob_gas));
+            (tx.access_list.clone(), tx.blob_versioned_hashes.clone(), max_fee_per_blob_gas)
+        }
+    };
+
+    TxEnv {
+        caller: sender,
+        gas_limit: tx.gas_limit(),
+        transact_to: tx.tx_kind(),
+        value: tx.value(),
+        data: tx.input().clone(),
+        nonce: Some(tx.nonce()),
+        chain_id: tx.chain_id(),
+        access_list: access_list.0,
+        blob_hashes,
+        max_fee_per_blob_gas,
+        authorization_list: tx
+            .authorization_list()
+            .map(|authorizations| AuthorizationList::Signed(authorizations.to_vec())),
+        ..Default::default()
+    }
+}
+
+/// A database reading the state touched by a simulation from the execution client, at a
+/// fixed block.
+///
+/// The reads block the current thread on the given runtime handle, so the database must only
+/// be used from a blocking task.
+#[derive(Debug)]
+struct RpcDatabase {
+    client: RpcClient,
+    block_number: u64,
+    handle: Handle,
+}
+
+impl DatabaseRef for RpcDatabase {
+    type Error = TransportError;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let block_number = Some(self.block_number);
+        let (state, code) = self.handle.block_on(async {
+            tokio::try_join!(
+                self.client.get_account_state(&address, block_number),
+                self.client.get_code(&address, block_number)
+            )
+        })?;
+
+        let code = Bytecode::new_raw(code);
+        Ok(Some(AccountInfo::new(state.balance, state.transaction_count, code.hash_slow(), code)))
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        // The code is always loaded and cached along with its account in `basic_ref`
+        Err(TransportErrorKind::Custom(format!("code {code_hash} not loaded").into()).into())
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.handle.block_on(self.client.get_storage_at(&address, index, Some(self.block_number)))
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        let block = self.handle.block_on(self.client.get_block(Some(number), false))?;
+        Ok(block.header.hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        consensus::{BlobTransactionSidecar, SidecarBuilder, SimpleCoder},
+        eips::{eip2718::Encodable2718, eip2930::AccessListItem},
+        network::{EthereumWallet, TransactionBuilder, TransactionBuilder4844},
+        primitives::{Address, B256, U256},
+        signers::local::PrivateKeySigner,
+    };
+    use revm::primitives::AuthorizationList;
+
+    use super::{to_tx_env, AccessList};
+    use crate::{
+        primitives::{FullTransaction, TransactionExt},
+        test_util::{default_test_7702_transaction, default_test_transaction},
+    };
+
+    #[tokio::test]
+    async fn test_tx_env_keeps_access_list_blobs_and_authorizations() -> eyre::Result<()> {
+        let sender = PrivateKeySigner::random();
+        let wallet = EthereumWallet::from(sender.clone());
+
+        let access_list = AccessList(vec![AccessListItem {
+            address: Address::random(),
+            storage_keys: vec![B256::random()],
+        }]);
+        let tx =
+            default_test_transaction(sender.address(), None).with_access_list(access_list.clone());
+        let tx = FullTransaction::decode_enveloped(tx.build(&wallet).await?.encoded_2718())?;
+
+        let env = to_tx_env(&tx, sender.address());
+        assert_eq!(env.caller, sender.address());
+        assert_eq!(env.nonce, Some(0));
+        assert_eq!(env.access_list, access_list.0);
+        assert!(env.authorization_list.is_none());
+
+        let sidecar: BlobTransactionSidecar =
+            SidecarBuilder::<SimpleCoder>::from_slice(b"Blobs are fun!").build()?;
+        let tx = default_test_transaction(sender.address(), Some(1))
+            .with_blob_sidecar(sidecar.clone())
+            .with_max_fee_per_blob_gas(3_000_000);
+        let tx = FullTransaction::decode_enveloped(tx.build(&wallet).await?.encoded_2718())?;
+
+        let env = to_tx_env(&tx, sender.address());
+        assert_eq!(env.blob_hashes, sidecar.versioned_hashes().collect::<Vec<_>>());
+        assert_eq!(env.max_fee_per_blob_gas, Some(U256::from(3_000_000)));
+
+        let tx = default_test_7702_transaction(&sender, None, Address::random())?;
+        let tx = FullTransaction::decode_enveloped(tx.build(&wallet).await?.encoded_2718())?;
+
+        let env = to_tx_env(&tx, sender.address());
+        let authorizations = tx.authorization_list().expect("eip-7702 authorization list");
+        assert_eq!(
+            env.authorization_list,
+            Some(AuthorizationList::Signed(authorizations.to_vec()))
+        );
+
+        Ok(())
+    }
+}
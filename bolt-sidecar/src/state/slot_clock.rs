@@ -0,0 +1,123 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A source of the current UNIX time.
+pub trait TimeSource: Clone + Send + Sync + 'static {
+    /// Returns the time elapsed since the UNIX epoch.
+    fn now(&self) -> Duration;
+}
+
+/// The system clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemTimeSource;
+
+impl TimeSource for SystemTimeSource {
+    fn now(&self) -> Duration {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default()
+    }
+}
+
+/// A clock of the beacon chain slots, following the given time source.
+///
+/// Before genesis, the clock stays at the start of slot 0.
+#[derive(Debug, Clone)]
+pub struct SlotClock<T = SystemTimeSource> {
+    /// The genesis time of the chain, in seconds.
+    genesis_time: u64,
+    /// The slot time of the chain, in seconds.
+    slot_time: u64,
+    /// The number of slots in an epoch.
+    slots_per_epoch: u64,
+    /// The source of the current time.
+    time: T,
+}
+
+impl SlotClock {
+    /// Create a new slot clock following the system clock.
+    pub fn new(genesis_time: u64, slot_time: u64, slots_per_epoch: u64) -> Self {
+        Self::with_time_source(genesis_time, slot_time, slots_per_epoch, SystemTimeSource)
+    }
+}
+
+impl<T: TimeSource> SlotClock<T> {
+    /// Create a new slot clock following the given time source.
+    pub fn with_time_source(
+        genesis_time: u64,
+        slot_time: u64,
+        slots_per_epoch: u64,
+        time: T,
+    ) -> Self {
+        Self { genesis_time, slot_time, slots_per_epoch, time }
+    }
+
+    /// Returns the current UNIX time of the time source.
+    pub fn now(&self) -> Duration {
+        self.time.now()
+    }
+
+    /// Returns the current slot.
+    pub fn current_slot(&self) -> u64 {
+        self.now().as_secs().saturating_sub(self.genesis_time) / self.slot_time
+    }
+
+    /// Returns the UNIX time at which the given slot starts.
+    pub fn slot_start(&self, slot: u64) -> Duration {
+        Duration::from_secs(self.genesis_time + slot * self.slot_time)
+    }
+
+    /// Returns the time elapsed since the start of the current slot.
+    pub fn duration_into_slot(&self) -> Duration {
+        self.now().saturating_sub(self.slot_start(self.current_slot()))
+    }
+
+    /// Returns the instant at which the time source reaches the given UNIX time, to be used
+    /// as a deadline by the timers of the runtime.
+    pub fn instant_at(&self, time: Duration) -> tokio::time::Instant {
+        tokio::time::Instant::now() + time.saturating_sub(self.now())
+    }
+
+    /// Returns the number of slots left in the current epoch after the current slot.
+    pub fn slots_remaining_in_current(&self) -> u64 {
+        self.slots_per_epoch - 1 - self.current_slot() % self.slots_per_epoch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{config::ChainConfig, test_util::MockClock};
+
+    use super::SlotClock;
+
+    #[test]
+    fn test_duration_into_slot() {
+        let clock = MockClock::default();
+        let slot_clock = SlotClock::with_time_source(1_000, 12, 32, clock.clone());
+
+        // Slot 10 starts at 1_120s
+        clock.set(Duration::from_millis(1_125_500));
+        assert_eq!(slot_clock.current_slot(), 10);
+        assert_eq!(slot_clock.slot_start(10), Duration::from_secs(1_120));
+        assert_eq!(slot_clock.duration_into_slot(), Duration::from_millis(5_500));
+        assert_eq!(slot_clock.slots_remaining_in_current(), 21);
+
+        // Right at the start of the last slot of the epoch
+        clock.set(Duration::from_secs(1_000 + 31 * 12));
+        assert_eq!(slot_clock.current_slot(), 31);
+        assert_eq!(slot_clock.duration_into_slot(), Duration::ZERO);
+        assert_eq!(slot_clock.slots_remaining_in_current(), 0);
+
+        // Before genesis
+        clock.set(Duration::from_secs(900));
+        assert_eq!(slot_clock.current_slot(), 0);
+        assert_eq!(slot_clock.duration_into_slot(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_chain_slot_clock() {
+        let chain = ChainConfig::mainnet();
+        let slot_clock = chain.slot_clock(1_606_824_023);
+        assert_eq!(slot_clock.slot_start(1), Duration::from_secs(1_606_824_035));
+        assert!(slot_clock.duration_into_slot() < Duration::from_secs(chain.slot_time()));
+    }
+}
@@ -0,0 +1,129 @@
+use std::{sync::Arc, time::Duration};
+
+use parking_lot::RwLock;
+use tracing::warn;
+
+use super::slot_clock::{SlotClock, SystemTimeSource, TimeSource};
+
+/// Constraints posted to the relays after the cutoff of their target slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Constraints for slot {slot} posted {late_by:?} after the cutoff, unlikely to be honored")]
+pub struct LateSubmission {
+    /// The target slot of the constraints.
+    pub slot: u64,
+    /// How late the constraints are posted.
+    pub late_by: Duration,
+}
+
+/// The timing of the constraints submissions, based on the beacon chain slot times and on
+/// the arrival of the beacon heads.
+///
+/// The proposer of a slot requests a header from the relays at the start of the slot, or
+/// once it received the head of the previous slot if it is late, and must propagate its block
+/// before the attestation deadline to get the proposer boost. Constraints posted after the
+/// cutoff are unlikely to make it into that block, so they aren't posted at all.
+#[derive(Debug, Clone)]
+pub struct SubmissionTiming<T = SystemTimeSource> {
+    /// The clock of the beacon chain slots.
+    clock: SlotClock<T>,
+    /// The offset from the start of the target slot by which constraints must be posted.
+    cutoff: Duration,
+    /// The slot of the latest beacon head and its arrival time, shared between the clones.
+    latest_head: Arc<RwLock<Option<(u64, Duration)>>>,
+}
+
+impl<T: TimeSource> SubmissionTiming<T> {
+    /// Create a new submission timing following the given slot clock.
+    pub fn new(clock: SlotClock<T>, cutoff: Duration) -> Self {
+        Self { clock, cutoff, latest_head: Default::default() }
+    }
+
+    /// Record the arrival of the beacon head of the given slot.
+    pub fn on_head(&self, slot: u64) {
+        *self.latest_head.write() = Some((slot, self.clock.now()));
+    }
+
+    /// Returns the UNIX time by which the constraints for the given slot must be posted:
+    /// the cutoff after the start of the slot, or after the arrival of the head of the
+    /// previous slot if it arrived later.
+    pub fn cutoff_time(&self, slot: u64) -> Duration {
+        let slot_start = self.clock.slot_start(slot);
+        let start = match *self.latest_head.read() {
+            Some((head, arrival)) if head + 1 == slot => slot_start.max(arrival),
+            _ => slot_start,
+        };
+
+        start + self.cutoff
+    }
+
+    /// Check that constraints for the given slot posted now are in time, returning the time
+    /// left before the cutoff. If they are late, a warning is logged and they must not be
+    /// posted.
+    pub fn check(&self, slot: u64) -> Result<Duration, LateSubmission> {
+        let now = self.clock.now();
+        let cutoff_time = self.cutoff_time(slot);
+
+        cutoff_time.checked_sub(now).ok_or_else(|| {
+            let late = LateSubmission { slot, late_by: now - cutoff_time };
+            let late_by = late.late_by;
+            warn!(slot, ?late_by, "Constraints past the cutoff, unlikely to be honored");
+            late
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{state::slot_clock::SlotClock, test_util::MockClock};
+
+    use super::{LateSubmission, SubmissionTiming};
+
+    #[test]
+    fn test_late_constraints_submission() {
+        let clock = MockClock::default();
+        let cutoff = Duration::from_millis(1_000);
+        let slot_clock = SlotClock::with_time_source(1_000, 12, 32, clock.clone());
+        let timing = SubmissionTiming::new(slot_clock, cutoff);
+
+        // Slot 10 starts at 1_120s, so its constraints must be posted by 1_121s
+        assert_eq!(timing.cutoff_time(10), Duration::from_secs(1_121));
+
+        // At the commitment deadline of the previous slot
+        clock.set(Duration::from_secs(1_116));
+        assert_eq!(timing.check(10), Ok(Duration::from_secs(5)));
+
+        clock.set(Duration::from_secs(1_121));
+        assert_eq!(timing.check(10), Ok(Duration::ZERO));
+
+        // Past the cutoff, but still before the attestation deadline
+        clock.set(Duration::from_millis(1_122_500));
+        assert_eq!(
+            timing.check(10),
+            Err(LateSubmission { slot: 10, late_by: Duration::from_millis(1_500) })
+        );
+    }
+
+    #[test]
+    fn test_cutoff_follows_late_head() {
+        let clock = MockClock::default();
+        let cutoff = Duration::from_millis(1_000);
+        let slot_clock = SlotClock::with_time_source(1_000, 12, 32, clock.clone());
+        let timing = SubmissionTiming::new(slot_clock, cutoff);
+
+        // The head of slot 9 arrives in time, before the start of slot 10
+        clock.set(Duration::from_secs(1_112));
+        timing.on_head(9);
+        assert_eq!(timing.cutoff_time(10), Duration::from_secs(1_121));
+
+        // The head of slot 9 arrives 2s into slot 10, which moves the cutoff with it
+        clock.set(Duration::from_secs(1_122));
+        timing.on_head(9);
+        assert_eq!(timing.cutoff_time(10), Duration::from_secs(1_123));
+        assert_eq!(timing.check(10), Ok(Duration::from_secs(1)));
+
+        // The head of another slot doesn't move it
+        assert_eq!(timing.cutoff_time(11), Duration::from_secs(1_133));
+    }
+}
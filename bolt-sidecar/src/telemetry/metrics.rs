@@ -16,8 +16,12 @@ const REMOTE_BLOCKS_PROPOSED: &str = "bolt_sidecar_remote_blocks_proposed";
 const INCLUSION_COMMITMENTS_RECEIVED: &str = "bolt_sidecar_inclusion_commitments_received";
 /// Counter for the number of inclusion commitments accepted.
 const INCLUSION_COMMITMENTS_ACCEPTED: &str = "bolt_sidecar_inclusion_commitments_accepted";
+/// Counter for the number of commitment requests rejected, labeled by rejection reason.
+const COMMITMENTS_REJECTED: &str = "bolt_sidecar_commitments_rejected";
 /// Counter for the number of transactions preconfirmed
 const TRANSACTIONS_PRECONFIRMED: &str = "bolt_sidecar_transactions_preconfirmed";
+/// Counter for the number of preconfirmed transactions evicted by higher-priority commitments
+const TRANSACTIONS_EVICTED: &str = "bolt_sidecar_transactions_evicted";
 /// Counter for the number of validation errors; to spot most the most common ones
 const VALIDATION_ERRORS: &str = "bolt_sidecar_validation_errors";
 /// Counter that tracks the gross tip revenue. Effective tip per gas * gas used.
@@ -34,6 +38,36 @@ const ACCOUNT_STATES: &str = "bolt_sidecar_account_states";
 //  Histograms --------------------------------------------------------------
 /// Histogram for the total duration of HTTP requests in seconds.
 const HTTP_REQUESTS_DURATION_SECONDS: &str = "bolt_sidecar_http_requests_duration_seconds";
+/// Histogram for the duration of each stage of the commitment path in seconds, labeled by stage.
+const COMMITMENT_STAGE_DURATION_SECONDS: &str = "bolt_sidecar_commitment_stage_duration_seconds";
+
+/// The stages of the commitment path, from the request to the submission of the constraints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommitmentStage {
+    /// Validation of the request against the consensus state.
+    ConsensusValidation,
+    /// Validation of the request against the execution state.
+    ExecutionValidation,
+    /// Signing of the constraints with the BLS constraint signer.
+    ConstraintsSigning,
+    /// Signing of the commitment with the ECDSA commitment signer.
+    CommitmentSigning,
+    /// Submission of the constraints to the Constraints API.
+    ConstraintsSubmission,
+}
+
+impl CommitmentStage {
+    /// Returns the value of the `stage` label of the stage.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::ConsensusValidation => "consensus_validation",
+            Self::ExecutionValidation => "execution_validation",
+            Self::ConstraintsSigning => "constraints_signing",
+            Self::CommitmentSigning => "commitment_signing",
+            Self::ConstraintsSubmission => "constraints_submission",
+        }
+    }
+}
 
 /// Metrics for the commitments API.
 #[derive(Debug, Clone, Copy)]
@@ -48,7 +82,9 @@ impl ApiMetrics {
         describe_counter!(REMOTE_BLOCKS_PROPOSED, "Remote blocks proposed");
         describe_counter!(INCLUSION_COMMITMENTS_ACCEPTED, "Inclusion commitments");
         describe_counter!(INCLUSION_COMMITMENTS_ACCEPTED, "Inclusion commitments accepted");
+        describe_counter!(COMMITMENTS_REJECTED, "Commitment requests rejected by reason");
         describe_counter!(TRANSACTIONS_PRECONFIRMED, "Transactions preconfirmed");
+        describe_counter!(TRANSACTIONS_EVICTED, "Preconfirmed transactions evicted");
         describe_counter!(VALIDATION_ERRORS, "Validation errors");
         describe_counter!(GROSS_TIP_REVENUE, "Gross tip revenue");
 
@@ -61,6 +97,10 @@ impl ApiMetrics {
             HTTP_REQUESTS_DURATION_SECONDS,
             "Total duration of HTTP requests in seconds"
         );
+        describe_histogram!(
+            COMMITMENT_STAGE_DURATION_SECONDS,
+            "Duration of each stage of the commitment path in seconds"
+        );
     }
 
     /// Counters ----------------------------------------------------------------
@@ -89,6 +129,10 @@ impl ApiMetrics {
         counter!(INCLUSION_COMMITMENTS_ACCEPTED).increment(1);
     }
 
+    pub fn increment_commitments_rejected(reason: &'static str) {
+        counter!(COMMITMENTS_REJECTED, &[("reason", reason)]).increment(1);
+    }
+
     pub fn increment_gross_tip_revenue(mut tip: u128) {
         // If the tip is too large, we need to split it into multiple u64 parts
         if tip > u64::MAX as u128 {
@@ -112,6 +156,10 @@ impl ApiMetrics {
         counter!(TRANSACTIONS_PRECONFIRMED, &[("type", tx_type_str(tx_type))]).increment(1);
     }
 
+    pub fn increment_transactions_evicted(count: usize) {
+        counter!(TRANSACTIONS_EVICTED).increment(count as u64);
+    }
+
     pub fn increment_validation_errors(err_type: String) {
         counter!(VALIDATION_ERRORS, &[("type", err_type)]).increment(1);
     }
@@ -126,6 +174,13 @@ impl ApiMetrics {
         gauge!(ACCOUNT_STATES).set(count as f64);
     }
 
+    /// Histograms ----------------------------------------------------------------
+
+    pub fn observe_commitment_stage(stage: CommitmentStage, duration: Duration) {
+        histogram!(COMMITMENT_STAGE_DURATION_SECONDS, &[("stage", stage.as_str())])
+            .record(duration.as_secs_f64());
+    }
+
     /// Mixed ----------------------------------------------------------------
 
     /// Observes the duration of an HTTP request by storing it in a histogram,
@@ -136,3 +191,42 @@ impl ApiMetrics {
         histogram!(HTTP_REQUESTS_DURATION_SECONDS, &labels,).record(duration.as_secs_f64());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    use super::{ApiMetrics, CommitmentStage, COMMITMENT_STAGE_DURATION_SECONDS};
+
+    #[test]
+    fn test_observe_commitment_stage() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        metrics::with_local_recorder(&recorder, || {
+            ApiMetrics::observe_commitment_stage(
+                CommitmentStage::CommitmentSigning,
+                Duration::from_millis(5),
+            );
+        });
+
+        let observed =
+            snapshotter.snapshot().into_vec().into_iter().find_map(|(key, _, _, value)| {
+                let key = key.key();
+                let is_signing_stage = key.name() == COMMITMENT_STAGE_DURATION_SECONDS &&
+                    key.labels()
+                        .any(|l| l.key() == "stage" && l.value() == "commitment_signing");
+
+                match value {
+                    DebugValue::Histogram(values) if is_signing_stage => Some(values),
+                    _ => None,
+                }
+            });
+
+        let values = observed.expect("commitment signing stage histogram");
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0].into_inner(), 0.005);
+    }
+}
@@ -2,29 +2,36 @@ use std::net::SocketAddr;
 
 use eyre::{bail, Result};
 use metrics_exporter_prometheus::PrometheusBuilder;
-use tracing::info;
+use tracing::{info, Subscriber};
 use tracing_subscriber::{
-    fmt::Layer as FmtLayer, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer,
-    Registry,
+    fmt::{Layer as FmtLayer, MakeWriter},
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    EnvFilter, Layer, Registry,
 };
 
+use crate::config::telemetry::{LogFormat, TelemetryOpts};
+
 mod metrics;
-pub use metrics::ApiMetrics;
+pub use metrics::{ApiMetrics, CommitmentStage};
+
+mod redaction;
+pub use redaction::log_commitment_request;
 
 /// Initialize the tracing stack and Prometheus metrics recorder.
 ///
 /// **This function should be called at the beginning of the program.**
-pub fn init_telemetry_stack(metrics_port: Option<u16>) -> Result<()> {
-    let std_layer = FmtLayer::default().with_writer(std::io::stdout).with_filter(
-        EnvFilter::builder()
-            .with_default_directive("bolt_sidecar=info".parse()?)
-            .from_env_lossy()
-            .add_directive("reqwest=error".parse()?)
-            .add_directive("alloy_transport_http=error".parse()?),
-    );
+pub fn init_telemetry_stack(opts: &TelemetryOpts) -> Result<()> {
+    let filter = EnvFilter::builder()
+        .with_default_directive(format!("bolt_sidecar={}", opts.log_level()).parse()?)
+        .from_env_lossy()
+        .add_directive("reqwest=error".parse()?)
+        .add_directive("alloy_transport_http=error".parse()?);
+    let std_layer = log_layer(opts.log_format(), std::io::stdout).with_filter(filter);
 
     Registry::default().with(std_layer).try_init()?;
-    if let Some(metrics_port) = metrics_port {
+    if let Some(metrics_port) = opts.metrics_port() {
         let prometheus_addr = SocketAddr::from(([0, 0, 0, 0], metrics_port));
         let builder = PrometheusBuilder::new().with_http_listener(prometheus_addr);
 
@@ -42,3 +49,70 @@ pub fn init_telemetry_stack(metrics_port: Option<u16>) -> Result<()> {
 
     Ok(())
 }
+
+/// Returns the layer formatting the logs in the given format to the given writer.
+fn log_layer<S, W>(format: LogFormat, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Pretty => FmtLayer::default().with_writer(writer).boxed(),
+        LogFormat::Json => FmtLayer::default().json().with_writer(writer).boxed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::{Arc, Mutex},
+    };
+
+    use tracing::{info, warn};
+    use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+    use crate::config::telemetry::LogFormat;
+
+    use super::log_layer;
+
+    /// A writer keeping the logs in memory.
+    #[derive(Clone, Default)]
+    struct MemoryWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for MemoryWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_logs_are_parseable() {
+        let writer = MemoryWriter::default();
+        let logs = writer.clone();
+        let layer = log_layer(LogFormat::Json, move || writer.clone());
+
+        tracing::subscriber::with_default(Registry::default().with(layer), || {
+            info!(slot = 42, "Commitment deadline reached");
+            warn!(err = "timeout", "Failed to submit constraints");
+        });
+
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let lines = logs
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["level"], "INFO");
+        assert_eq!(lines[0]["fields"]["message"], "Commitment deadline reached");
+        assert_eq!(lines[0]["fields"]["slot"], 42);
+        assert_eq!(lines[1]["level"], "WARN");
+        assert_eq!(lines[1]["fields"]["err"], "timeout");
+    }
+}
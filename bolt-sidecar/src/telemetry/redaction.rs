@@ -0,0 +1,114 @@
+use alloy::primitives::TxHash;
+use tracing::{debug, info};
+
+use crate::primitives::CommitmentRequest;
+
+/// Log a new commitment request.
+///
+/// With `redact` set, only the slot, the signer and the transaction hashes of the
+/// request are logged at info level, while the full request (including the raw signed
+/// transactions and the signature) is only logged at debug level.
+pub fn log_commitment_request(request: &CommitmentRequest, redact: bool) {
+    if !redact {
+        info!("Received new commitment request: {:?}", request);
+        return;
+    }
+
+    let CommitmentRequest::Inclusion(inclusion_request) = request;
+    let tx_hashes = inclusion_request.txs.iter().map(|tx| *tx.hash()).collect::<Vec<TxHash>>();
+    info!(
+        slot = inclusion_request.slot,
+        signer = ?inclusion_request.signer,
+        ?tx_hashes,
+        "Received new commitment request"
+    );
+    debug!(?request, "Full commitment request");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::{Arc, Mutex},
+    };
+
+    use tracing::Level;
+    use tracing_subscriber::fmt::MakeWriter;
+
+    use crate::primitives::{CommitmentRequest, FullTransaction, InclusionRequest};
+
+    use super::log_commitment_request;
+
+    /// A log writer that keeps everything in memory.
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl CapturedLogs {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    impl io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for CapturedLogs {
+        type Writer = Self;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    /// Log the request with an info level subscriber and return the output.
+    fn capture_info_logs(request: &CommitmentRequest, redact: bool) -> String {
+        let logs = CapturedLogs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(logs.clone())
+            .with_max_level(Level::INFO)
+            .with_ansi(false)
+            .finish();
+
+        tracing::subscriber::with_default(subscriber, || log_commitment_request(request, redact));
+        logs.contents()
+    }
+
+    #[test]
+    fn test_redacted_commitment_request_logs() -> eyre::Result<()> {
+        let raw_tx = "0x02f86c870c72dd9d5e883e4d0183408f2382520894d2e2adf7177b7a8afddbc12d1634cf23ea1a71020180c001a08556dcfea479b34675db3fe08e29486fe719c2b22f6b0c1741ecbbdce4575cc6a01cd48009ccafd6b9f1290bbe2ceea268f94101d1d322c787018423ebcbc87ab4";
+        let tx = FullTransaction::decode_enveloped(alloy::hex::decode(raw_tx)?)?;
+        let tx_hash = tx.hash().to_string();
+        let request = CommitmentRequest::Inclusion(InclusionRequest {
+            slot: 10,
+            txs: vec![tx],
+            top: false,
+            reverting_tx_hashes: Vec::new(),
+            max_slot: None,
+            webhook: None,
+            digest_version: Default::default(),
+            signature: None,
+            signer: None,
+        });
+
+        // Without redaction the full transaction is logged
+        let logs = capture_info_logs(&request, false);
+        assert!(logs.contains("FullTransaction"));
+
+        // With redaction only the hash is logged at info level
+        let logs = capture_info_logs(&request, true);
+        assert!(logs.contains(&tx_hash));
+        assert!(logs.contains("slot=10"));
+        assert!(!logs.contains("FullTransaction"));
+        assert!(!logs.contains("signature"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,720 @@
+//! End-to-end harness for the self-build path of the sidecar.
+//!
+//! The harness runs a real [SidecarDriver] on top of an Anvil instance and mock engine,
+//! beacon and relay servers, with its commitments API and builder proxy listening on local
+//! ports. Tests step through the event loop of the driver one event at a time, which allows a
+//! commitment request to be followed all the way to the `getPayload` response served to the
+//! beacon node.
+
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use alloy::{
+    primitives::{Address, B256},
+    signers::local::PrivateKeySigner,
+};
+use alloy_node_bindings::AnvilInstance;
+use axum::{
+    extract::{Path, Request, State},
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use clap::Parser;
+use reqwest::Url;
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::{
+    api::{
+        commitments::spec::SIGNATURE_HEADER,
+        spec::{ErrorResponse, SUBMIT_CONSTRAINTS_PATH},
+    },
+    builder::payload_builder::REQUIRED_ENGINE_CAPABILITIES,
+    client::ConstraintsClient,
+    common::secrets::{BlsSecretKeyWrapper, EcdsaSecretKeyWrapper, JwtSecretConfig},
+    config::Opts,
+    driver::SidecarDriver,
+    primitives::{commitment::ECDSASignatureExt, InclusionRequest, SignedConstraints},
+    signer::local::LocalSigner,
+    state::{fetcher::StateFetcher, StateClient},
+    test_util::{create_signed_inclusion_request, default_test_transaction, launch_anvil},
+};
+
+/// The maximum time waited for the background tasks of the driver, e.g. the submission of
+/// the constraints to the relay.
+const BACKGROUND_TASK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The header values returned by the mock engine as `engine_newPayloadV3` hints.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct EngineHints {
+    pub gas_used: u64,
+    pub receipts_root: B256,
+    pub state_root: B256,
+}
+
+impl Default for EngineHints {
+    fn default() -> Self {
+        Self {
+            gas_used: 21_000,
+            receipts_root: B256::repeat_byte(0x22),
+            state_root: B256::repeat_byte(0x11),
+        }
+    }
+}
+
+/// A switch taking a mock service down while set, making its requests fail.
+pub(crate) type Outage = Arc<AtomicBool>;
+
+/// Spawns an axum server on an ephemeral local port and returns its URL.
+async fn serve(router: Router) -> Url {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind local listener");
+    let addr = listener.local_addr().expect("local address");
+    tokio::spawn(async move { axum::serve(listener, router).await.expect("serve mock server") });
+    Url::parse(&format!("http://{addr}")).expect("valid URL")
+}
+
+/// Spawns a mock engine API that replies to `engine_newPayloadV3` like Geth does: with a
+/// validation error hinting at the first wrong header value, or `VALID` once all of them
+/// match the expected `hints`.
+pub(crate) async fn spawn_mock_engine(hints: EngineHints, outage: Outage) -> Url {
+    async fn new_payload(State(hints): State<EngineHints>, Json(body): Json<Value>) -> Json<Value> {
+        let payload = &body["params"][0];
+        let field = |name: &str| payload[name].as_str().unwrap_or_default().to_lowercase();
+
+        let gas_used = u64::from_str_radix(field("gasUsed").trim_start_matches("0x"), 16)
+            .expect("valid gasUsed quantity");
+
+        let validation_error = if gas_used != hints.gas_used {
+            Some(format!("invalid gas used (remote: {gas_used} local: {})", hints.gas_used))
+        } else if field("receiptsRoot") != hints.receipts_root.to_string() {
+            Some(format!(
+                "invalid receipt root hash (remote: {} local: {})",
+                field("receiptsRoot"),
+                hints.receipts_root
+            ))
+        } else if field("stateRoot") != hints.state_root.to_string() {
+            Some(format!(
+                "invalid merkle root (remote: {} local: {})",
+                field("stateRoot"),
+                hints.state_root
+            ))
+        } else {
+            None
+        };
+
+        let result = match validation_error {
+            Some(error) => json!({ "status": "INVALID", "validationError": error }),
+            None => json!({ "status": "VALID", "validationError": null }),
+        };
+
+        Json(json!({ "jsonrpc": "2.0", "id": body["id"], "result": result }))
+    }
+
+    async fn engine(
+        State((hints, outage)): State<(EngineHints, Outage)>,
+        Json(body): Json<Value>,
+    ) -> Json<Value> {
+        if outage.load(Ordering::Relaxed) {
+            return Json(json!({
+                "jsonrpc": "2.0",
+                "id": body["id"],
+                "error": { "code": -32000, "message": "engine unavailable" },
+            }));
+        }
+
+        match body["method"].as_str() {
+            Some("engine_newPayloadV3") => new_payload(State(hints), Json(body)).await,
+            Some("engine_exchangeCapabilities") => Json(json!({
+                "jsonrpc": "2.0",
+                "id": body["id"],
+                "result": REQUIRED_ENGINE_CAPABILITIES,
+            })),
+            _ => Json(json!({
+                "jsonrpc": "2.0",
+                "id": body["id"],
+                "error": { "code": -32601, "message": "method not found" },
+            })),
+        }
+    }
+
+    serve(Router::new().route("/", post(engine)).with_state((hints, outage))).await
+}
+
+/// Spawns a mock beacon node serving the genesis of the chain and the endpoints used by the
+/// fallback payload builder. The latter fail while the `outage` is set.
+///
+/// The chain started 5 slots ago, so that the slots targeted by the tests are yet to come.
+pub(crate) async fn spawn_mock_beacon(outage: Outage) -> Url {
+    async fn unless_down(State(outage): State<Outage>, request: Request, next: Next) -> Response {
+        if outage.load(Ordering::Relaxed) {
+            return StatusCode::SERVICE_UNAVAILABLE.into_response();
+        }
+        next.run(request).await
+    }
+
+    let builder_routes = Router::new()
+        .route(
+            "/eth/v1/beacon/states/head/randao",
+            get(|| async { Json(json!({ "data": { "randao": B256::repeat_byte(0x33) } })) }),
+        )
+        .route(
+            "/eth/v1/builder/states/head/expected_withdrawals",
+            get(|| async { Json(json!({ "data": [] })) }),
+        )
+        .route(
+            "/eth/v1/beacon/blocks/head/root",
+            get(|| async { Json(json!({ "data": { "root": B256::repeat_byte(0x44) } })) }),
+        )
+        .route_layer(middleware::from_fn_with_state(outage, unless_down));
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).expect("after epoch").as_secs();
+    let genesis_time = now - 5 * 12;
+
+    let router = Router::new()
+        .route(
+            "/eth/v1/beacon/genesis",
+            get(move || async move {
+                Json(json!({ "data": {
+                    "genesis_time": genesis_time.to_string(),
+                    "genesis_validators_root": B256::ZERO,
+                    "genesis_fork_version": "0x00000000",
+                }}))
+            }),
+        )
+        .merge(builder_routes);
+
+    serve(router).await
+}
+
+/// The constraints received by the mock relay, one entry per submitted batch.
+pub(crate) type ReceivedConstraints = Arc<Mutex<Vec<Vec<SignedConstraints>>>>;
+
+/// Spawns a mock relay that records the submitted constraints, acknowledging each batch with
+/// its index, and has no bids to offer, so that the locally built payload is always selected
+/// by the builder proxy.
+pub(crate) async fn spawn_mock_relay() -> (Url, ReceivedConstraints) {
+    async fn submit_constraints(
+        State(received): State<ReceivedConstraints>,
+        Json(constraints): Json<Vec<SignedConstraints>>,
+    ) -> Json<Value> {
+        let mut received = received.lock().unwrap();
+        received.push(constraints);
+        Json(json!({ "accepted": true, "batch": received.len() - 1 }))
+    }
+
+    async fn get_header_with_proofs(
+        Path(_params): Path<(u64, String, String)>,
+    ) -> (StatusCode, Json<ErrorResponse>) {
+        (StatusCode::NOT_FOUND, Json(ErrorResponse::new(404, "no bid available")))
+    }
+
+    let received = ReceivedConstraints::default();
+    let router = Router::new()
+        .route(SUBMIT_CONSTRAINTS_PATH, post(submit_constraints))
+        .route(
+            "/eth/v1/builder/header_with_proofs/:slot/:parent_hash/:pubkey",
+            get(get_header_with_proofs),
+        )
+        .with_state(received.clone());
+
+    (serve(router).await, received)
+}
+
+/// Returns a local port that is free at the time of the call.
+fn free_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind local listener");
+    listener.local_addr().expect("local address").port()
+}
+
+/// Wait until a local server accepts connections on the given port.
+async fn wait_for_server(port: u16) -> eyre::Result<()> {
+    wait_until(|| async move { TcpStream::connect(("127.0.0.1", port)).await.is_ok() }).await
+}
+
+/// Poll the given condition until it holds, or fail after [BACKGROUND_TASK_TIMEOUT].
+pub(crate) async fn wait_until<F, Fut>(mut condition: F) -> eyre::Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = bool>,
+{
+    tokio::time::timeout(BACKGROUND_TASK_TIMEOUT, async {
+        while !condition().await {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .map_err(|_| eyre::eyre!("condition not met in {BACKGROUND_TASK_TIMEOUT:?}"))
+}
+
+/// A sidecar driver wired to mock external services, with the self-build path enabled.
+pub(crate) struct SelfBuildHarness {
+    /// The Anvil instance backing the execution state. Kept alive for the harness lifetime.
+    pub anvil: AnvilInstance,
+    pub opts: Opts,
+    /// The driver under test. Its event loop is stepped through by the tests.
+    pub driver: SidecarDriver<StateClient, PrivateKeySigner>,
+    /// A client of the mock relay, with the same target as the one of the driver.
+    pub constraints_client: ConstraintsClient,
+    /// The constraints received by the mock relay.
+    pub relay_constraints: ReceivedConstraints,
+    /// The header values hinted by the mock engine.
+    pub engine_hints: EngineHints,
+    /// Takes the mock engine down while set.
+    pub engine_outage: Outage,
+    /// Takes the payload building endpoints of the mock beacon node down while set.
+    pub beacon_outage: Outage,
+}
+
+impl SelfBuildHarness {
+    /// Launch Anvil and the mock services, and start a sidecar driver using them.
+    pub async fn new() -> eyre::Result<Self> {
+        Self::with_args(std::iter::empty::<String>()).await
+    }
+
+    /// Like [Self::new], with additional command-line arguments for the sidecar.
+    pub async fn with_args(
+        args: impl IntoIterator<Item = impl Into<String>>,
+    ) -> eyre::Result<Self> {
+        let anvil = launch_anvil();
+        let engine_hints = EngineHints::default();
+        let engine_outage = Outage::default();
+        let engine_url = spawn_mock_engine(engine_hints, engine_outage.clone()).await;
+        let beacon_outage = Outage::default();
+        let beacon_url = spawn_mock_beacon(beacon_outage.clone()).await;
+        let (relay_url, relay_constraints) = spawn_mock_relay().await;
+
+        let defaults = [
+            "bolt-sidecar".to_string(),
+            format!("--port={}", free_port()),
+            format!("--constraints-proxy-port={}", free_port()),
+            format!("--execution-api-url={}", anvil.endpoint_url()),
+            format!("--beacon-api-url={beacon_url}"),
+            format!("--engine-api-url={engine_url}"),
+            format!("--constraints-api-url={relay_url}"),
+            format!("--engine-jwt-hex={}", JwtSecretConfig::default()),
+            format!("--fee-recipient={}", Address::ZERO),
+            format!("--builder-private-key={}", BlsSecretKeyWrapper::random()),
+            format!("--commitment-private-key={}", EcdsaSecretKeyWrapper::random()),
+            format!("--constraint-private-key={}", BlsSecretKeyWrapper::random()),
+            "--unsafe-disable-consensus-checks".to_string(),
+            "--unsafe-disable-onchain-checks".to_string(),
+        ];
+        let opts =
+            Opts::try_parse_from(defaults.into_iter().chain(args.into_iter().map(Into::into)))?;
+
+        let mut driver = SidecarDriver::with_local_signer(&opts).await?;
+        let client = StateClient::new(anvil.endpoint_url());
+        driver.execution_mut().update_head(None, client.get_head().await?).await?;
+
+        wait_for_server(opts.port).await?;
+        wait_for_server(opts.constraints_proxy_port).await?;
+
+        Ok(Self {
+            anvil,
+            constraints_client: ConstraintsClient::new(opts.constraints_api_url.clone())?,
+            opts,
+            driver,
+            relay_constraints,
+            engine_hints,
+            engine_outage,
+            beacon_outage,
+        })
+    }
+
+    /// The URL of the commitments JSON-RPC API.
+    pub fn commitments_url(&self) -> Url {
+        Url::parse(&format!("http://127.0.0.1:{}", self.opts.port)).expect("valid URL")
+    }
+
+    /// The URL of the builder proxy, serving the beacon node.
+    pub fn builder_proxy_url(&self) -> Url {
+        Url::parse(&format!("http://127.0.0.1:{}", self.opts.constraints_proxy_port))
+            .expect("valid URL")
+    }
+
+    /// A signer with the same constraint signing key as the driver.
+    pub fn constraint_signer(&self) -> LocalSigner {
+        let key = self.opts.constraint_signing.constraint_private_key.clone();
+        LocalSigner::new(key.expect("constraint private key").0, self.opts.chain)
+    }
+
+    /// Handle the next commitment request received by the API.
+    pub async fn handle_next_commitment(&mut self) -> eyre::Result<()> {
+        self.driver.handle_next_api_event().await
+    }
+
+    /// Request the inclusion of a transfer from the given Anvil account in `target_slot`
+    /// through the commitments API, and let the driver handle it. Returns the JSON-RPC
+    /// response of the API.
+    pub async fn request_inclusion(
+        &mut self,
+        account: usize,
+        nonce: Option<u64>,
+        target_slot: u64,
+    ) -> eyre::Result<Value> {
+        let (_, response) = self.post_inclusion_request(account, nonce, target_slot, &[]).await?;
+        let response = tokio::spawn(response);
+
+        self.handle_next_commitment().await?;
+        Ok(response.await??.json::<Value>().await?)
+    }
+
+    /// Returns the request of inclusion of a transfer from the given Anvil account in
+    /// `target_slot`, and a future posting it to the commitments API with the given extra
+    /// headers, resolving to the HTTP response. Requests rejected by the API itself never
+    /// reach the driver.
+    pub async fn post_inclusion_request(
+        &self,
+        account: usize,
+        nonce: Option<u64>,
+        target_slot: u64,
+        headers: &[(&str, &str)],
+    ) -> eyre::Result<(
+        InclusionRequest,
+        impl Future<Output = eyre::Result<reqwest::Response>> + Send + 'static,
+    )> {
+        let sender = self.anvil.addresses()[account];
+        let sender_sk = self.anvil.keys()[account].clone();
+        let tx = default_test_transaction(sender, nonce);
+        let request = create_signed_inclusion_request(&[tx], &sender_sk, target_slot).await?;
+        let signature_header =
+            format!("{sender}:{}", request.signature.expect("signed request").to_hex());
+
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "bolt_requestInclusion",
+            "params": [request]
+        });
+        let mut http_request = reqwest::Client::new()
+            .post(self.commitments_url())
+            .header(SIGNATURE_HEADER, signature_header)
+            .json(&payload);
+        for (name, value) in headers {
+            http_request = http_request.header(*name, *value);
+        }
+
+        Ok((request, async move { Ok(http_request.send().await?) }))
+    }
+
+    /// Reach the commitment deadline of `slot`, and wait for the relay to receive the
+    /// constraints of the slot if there are any, as they are submitted in the background.
+    pub async fn handle_commitment_deadline(&mut self, slot: u64) -> eyre::Result<()> {
+        let tx_hashes = self
+            .driver
+            .execution_mut()
+            .get_block_template(slot)
+            .map(|template| template.transaction_hashes())
+            .unwrap_or_default();
+
+        self.driver.reach_commitment_deadline(slot).await;
+
+        let statuses = self.driver.commitment_statuses().clone();
+        wait_until(|| {
+            let statuses = statuses.clone();
+            let tx_hashes = tx_hashes.clone();
+            async move { tx_hashes.iter().all(|hash| statuses.get(slot, *hash).is_some()) }
+        })
+        .await
+    }
+
+    /// Answer the next local payload request of the builder proxy.
+    pub async fn handle_next_payload_request(&mut self) -> eyre::Result<()> {
+        self.driver.handle_next_payload_request().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        hex,
+        primitives::{keccak256, B256},
+    };
+    use beacon_api_client::VersionedValue;
+    use ethereum_consensus::{
+        deneb::mainnet::SignedBlindedBeaconBlock, types::mainnet::ExecutionPayload,
+    };
+    use serde_json::{json, Value};
+
+    use crate::{
+        api::{
+            commitments::spec::{CommitmentError, RESPONSE_MODE_HEADER, SIGNATURE_HEADER},
+            spec::GET_PAYLOAD_PATH,
+        },
+        builder::{signature::verify_builder_bid, BlockTemplate},
+        crypto::SignableBLS,
+        primitives::{
+            commitment::ECDSASignatureExt, BlsPublicKey, GetPayloadResponse, PayloadAndBlobs,
+            SignedBuilderBid,
+        },
+        test_util::{create_signed_inclusion_request, default_test_transaction},
+    };
+
+    use super::SelfBuildHarness;
+
+    #[tokio::test]
+    async fn test_self_build_end_to_end() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut harness = SelfBuildHarness::new().await?;
+        let target_slot = 10;
+
+        // 1. A user requests the inclusion of a transaction through the JSON-RPC API
+        let sender = harness.anvil.addresses()[0];
+        let sender_sk = harness.anvil.keys()[0].clone();
+        let tx = default_test_transaction(sender, None);
+        let request = create_signed_inclusion_request(&[tx], &sender_sk, target_slot).await?;
+        let committed_tx_hash = *request.txs[0].hash();
+        let signature_header =
+            format!("{sender}:{}", request.signature.expect("signed request").to_hex());
+
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "bolt_requestInclusion",
+            "params": [request]
+        });
+        let response = tokio::spawn(
+            reqwest::Client::new()
+                .post(harness.commitments_url())
+                .header(SIGNATURE_HEADER, signature_header)
+                .json(&payload)
+                .send(),
+        );
+
+        harness.handle_next_commitment().await?;
+        let response = response.await??.json::<Value>().await?;
+        assert!(response.get("error").is_none(), "unexpected error: {response}");
+
+        // 2. At the deadline, the fallback payload is built and the constraints submitted
+        harness.handle_commitment_deadline(target_slot).await?;
+
+        let relay_constraints = harness.relay_constraints.lock().unwrap().clone();
+        assert_eq!(relay_constraints.len(), 1);
+        assert_eq!(relay_constraints[0][0].message.transactions[0].hash(), &committed_tx_hash);
+
+        // 3. The beacon node asks the proxy for a header. The relay has no bid, so the
+        // locally built one is returned.
+        let proxy_url = harness.builder_proxy_url();
+        let pubkey = hex::encode_prefixed(harness.constraint_signer().pubkey().as_ref());
+        let header_url = proxy_url
+            .join(&format!("/eth/v1/builder/header/{target_slot}/{}/{pubkey}", B256::ZERO))?;
+        let header_response = tokio::spawn(reqwest::get(header_url));
+
+        harness.handle_next_payload_request().await?;
+        let header_response = header_response.await??;
+        assert!(header_response.status().is_success());
+        let bid = header_response.json::<VersionedValue<SignedBuilderBid>>().await?.data;
+
+        let builder_pubkey = harness.opts.builder_private_key.sk_to_pk().to_bytes();
+        let builder_pubkey = BlsPublicKey::try_from(builder_pubkey.as_slice())?;
+        verify_builder_bid(&bid, &builder_pubkey, &harness.opts.chain)?;
+
+        // The payload is kept until the beacon node fetches it
+        assert!(harness.driver.local_builder_mut().peek_cached_payload(target_slot).is_some());
+
+        // The header was completed with the engine hints
+        let hints = harness.engine_hints;
+        let header = &bid.message.header;
+        assert_eq!(header.gas_used, hints.gas_used);
+        assert_eq!(header.state_root.as_ref(), hints.state_root.as_slice());
+        assert_eq!(header.receipts_root.as_ref(), hints.receipts_root.as_slice());
+
+        // 4. The beacon node signs the blinded block and asks for the full payload
+        let mut blinded_block = SignedBlindedBeaconBlock::default();
+        blinded_block.message.slot = target_slot;
+        blinded_block.message.body.execution_payload_header = header.clone();
+        blinded_block.message.body.blob_kzg_commitments = bid.message.blob_kzg_commitments.clone();
+
+        let payload_response = reqwest::Client::new()
+            .post(proxy_url.join(GET_PAYLOAD_PATH)?)
+            .json(&blinded_block)
+            .send()
+            .await?;
+        assert!(payload_response.status().is_success());
+
+        let payload = payload_response.json::<GetPayloadResponse>().await?;
+        assert_eq!(payload.block_hash(), &header.block_hash);
+
+        let GetPayloadResponse::Deneb(PayloadAndBlobs {
+            execution_payload: ExecutionPayload::Deneb(execution_payload),
+            ..
+        }) = payload
+        else {
+            eyre::bail!("unexpected payload fork");
+        };
+        assert_eq!(execution_payload.transactions.len(), 1);
+        assert_eq!(keccak256(execution_payload.transactions[0].as_ref()), committed_tx_hash);
+
+        // Once served, the payload is removed from the local builder
+        harness.handle_next_payload_request().await?;
+        assert!(harness.driver.local_builder_mut().peek_cached_payload(target_slot).is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_build_empty_local_payload() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut harness = SelfBuildHarness::new().await?;
+        let target_slot = 10;
+
+        // No commitments were issued for the slot
+        let local_builder = harness.driver.local_builder_mut();
+        local_builder.build_new_local_payload(target_slot, None, &BlockTemplate::default()).await?;
+
+        let payload_and_bid =
+            local_builder.get_cached_payload(target_slot).expect("payload is built");
+        let bid = payload_and_bid.bid;
+
+        let builder_pubkey = harness.opts.builder_private_key.sk_to_pk().to_bytes();
+        let builder_pubkey = BlsPublicKey::try_from(builder_pubkey.as_slice())?;
+        verify_builder_bid(&bid, &builder_pubkey, &harness.opts.chain)?;
+        assert!(bid.message.blob_kzg_commitments.is_empty());
+        assert_eq!(payload_and_bid.payload.block_hash(), &bid.message.header.block_hash);
+
+        let GetPayloadResponse::Deneb(PayloadAndBlobs {
+            execution_payload: ExecutionPayload::Deneb(execution_payload),
+            blobs_bundle,
+        }) = payload_and_bid.payload
+        else {
+            eyre::bail!("unexpected payload fork");
+        };
+        assert!(execution_payload.transactions.is_empty());
+        assert!(blobs_bundle.blobs.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_commitment_sequence_numbers_per_slot() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut harness = SelfBuildHarness::new().await?;
+
+        // Three senders commit to slot 10 through the driver, then the first one to slot 11
+        let mut sequences = Vec::new();
+        for (account, target_slot) in [(0, 10), (1, 10), (2, 10), (0, 11)] {
+            let nonce = (target_slot == 11).then_some(1);
+            let response = harness.request_inclusion(account, nonce, target_slot).await?;
+            assert!(response.get("error").is_none(), "unexpected error: {response}");
+            sequences.push(response["result"]["sequence"].as_u64());
+        }
+
+        // Sequence numbers increase within a slot and restart from 0 for the next one
+        assert_eq!(sequences, vec![Some(0), Some(1), Some(2), Some(0)]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_commitment_response_modes() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut harness = SelfBuildHarness::new().await?;
+        let target_slot = 10;
+
+        let mut results = Vec::new();
+        for (account, headers) in [
+            (0, vec![]),
+            (1, vec![(RESPONSE_MODE_HEADER, "full")]),
+            (2, vec![(RESPONSE_MODE_HEADER, "compact")]),
+        ] {
+            let (request, response) =
+                harness.post_inclusion_request(account, None, target_slot, &headers).await?;
+            let response = tokio::spawn(response);
+
+            harness.handle_next_commitment().await?;
+            let response = response.await??.json::<Value>().await?;
+            assert!(response.get("error").is_none(), "unexpected error: {response}");
+            results.push((request, response["result"].clone()));
+        }
+
+        // The full commitment is returned by default, including the raw transactions
+        for (request, result) in &results[..2] {
+            assert_eq!(result["slot"], target_slot);
+            assert_eq!(result["txs"], json!(request)["txs"]);
+            assert!(result["signature"].is_string());
+        }
+
+        // The compact receipt only has the slot, the transaction hashes, signature and digest
+        let (request, receipt) = &results[2];
+        let mut fields = receipt.as_object().expect("receipt object").keys().collect::<Vec<_>>();
+        fields.sort();
+        assert_eq!(fields, ["digest", "signature", "slot", "txHashes"]);
+        assert_eq!(receipt["slot"], target_slot);
+        assert_eq!(receipt["txHashes"], json!([request.txs[0].hash()]));
+        assert_eq!(receipt["digest"], json!(request.digest()));
+
+        // Unknown modes are rejected as malformed, even for an otherwise valid request
+        let headers = [(RESPONSE_MODE_HEADER, "verbose")];
+        let (_, response) = harness.post_inclusion_request(3, None, target_slot, &headers).await?;
+        let response = response.await?;
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let response = response.json::<Value>().await?;
+        assert_eq!(response["error"]["code"], -32007, "unexpected response: {response}");
+        assert_eq!(response["error"]["message"], CommitmentError::MalformedHeader.to_string());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_commitment_status_after_submission() -> eyre::Result<()> {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let mut harness = SelfBuildHarness::new().await?;
+        let target_slot = 10;
+
+        let response = harness.request_inclusion(0, None, target_slot).await?;
+        assert!(response.get("error").is_none(), "unexpected error: {response}");
+        let raw_tx = response["result"]["txs"][0].as_str().expect("raw transaction");
+        let committed_tx_hash = keccak256(hex::decode(raw_tx)?);
+
+        let commitments_url = harness.commitments_url();
+        let get_status = |slot: u64| {
+            let payload = json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "bolt_getCommitmentStatus",
+                "params": [slot, committed_tx_hash]
+            });
+            reqwest::Client::new().post(commitments_url.clone()).json(&payload).send()
+        };
+
+        // The status is unknown until the constraints are submitted
+        let response = get_status(target_slot).await?.json::<Value>().await?;
+        assert_eq!(response["error"]["code"], -32014);
+
+        harness.handle_commitment_deadline(target_slot).await?;
+
+        // The response of the relay that accepted the constraints is returned
+        let response = get_status(target_slot).await?.json::<Value>().await?;
+        assert!(response.get("error").is_none(), "unexpected error: {response}");
+        let status = &response["result"];
+        assert_eq!(status["slot"], target_slot);
+        assert_eq!(status["txHash"], json!(committed_tx_hash));
+
+        let relays = status["relays"].as_array().expect("relays array");
+        assert_eq!(relays.len(), 1);
+        let relay = harness.constraints_client.url().origin().ascii_serialization();
+        assert_eq!(relays[0]["relay"], relay);
+        assert_eq!(relays[0]["accepted"], true);
+        assert_eq!(relays[0]["response"], json!({ "accepted": true, "batch": 0 }));
+
+        // The commitment isn't known in other slots
+        let response = get_status(target_slot + 1).await?.json::<Value>().await?;
+        assert_eq!(response["error"]["code"], -32014);
+
+        Ok(())
+    }
+}
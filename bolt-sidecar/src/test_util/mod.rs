@@ -1,14 +1,18 @@
-use std::env;
+use std::{
+    env,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use alloy::{
-    eips::eip2718::Encodable2718,
+    eips::{eip2718::Encodable2718, eip7702::Authorization},
     network::{EthereumWallet, TransactionBuilder},
     primitives::{Address, Signature, U256},
     rpc::types::TransactionRequest,
     signers::{
         k256::{ecdsa::SigningKey as K256SigningKey, SecretKey as K256SecretKey},
         local::PrivateKeySigner,
-        Signer,
+        Signer, SignerSync,
     },
 };
 use alloy_node_bindings::{Anvil, AnvilInstance};
@@ -28,8 +32,12 @@ use crate::{
         RevocationMessage, SignedConstraints, SignedDelegation, SignedRevocation,
     },
     signer::local::LocalSigner,
+    state::slot_clock::TimeSource,
 };
 
+/// End-to-end harness for the self-build path, with mock engine, beacon and relay servers.
+pub(crate) mod harness;
+
 /// The URL of the test execution client HTTP API.
 ///
 /// NOTE: this DNS is only available through the Chainbound Tailnet
@@ -133,6 +141,25 @@ pub(crate) fn default_test_transaction(sender: Address, nonce: Option<u64>) -> T
         .with_max_fee_per_gas(20_000_000_000)
 }
 
+/// Create a default EIP-7702 transaction template to use for tests, in which the sender
+/// delegates its account to the code of `delegate`. Its gas limit covers the intrinsic gas of
+/// the authorization.
+pub(crate) fn default_test_7702_transaction(
+    sender: &PrivateKeySigner,
+    nonce: Option<u64>,
+    delegate: Address,
+) -> eyre::Result<TransactionRequest> {
+    // The authorization is processed after the nonce of the transaction is incremented
+    let nonce = nonce.unwrap_or(0);
+    let authorization =
+        Authorization { chain_id: U256::from(1337), address: delegate, nonce: nonce + 1 };
+    let signature = sender.sign_hash_sync(&authorization.signature_hash())?;
+
+    let mut tx = default_test_transaction(sender.address(), Some(nonce)).with_gas_limit(46_000);
+    tx.authorization_list = Some(vec![authorization.into_signed(signature)]);
+    Ok(tx)
+}
+
 /// Arbitrary bytes that can be signed with both ECDSA and BLS keys
 pub(crate) struct TestSignableData {
     pub data: [u8; 32],
@@ -177,7 +204,17 @@ pub(crate) async fn create_signed_inclusion_request(
         let full_tx = FullTransaction::decode_enveloped(raw_encoded.as_slice())?;
         full_txs.push(full_tx);
     }
-    let mut request = InclusionRequest { txs: full_txs, slot, signature: None, signer: None };
+    let mut request = InclusionRequest {
+        txs: full_txs,
+        slot,
+        top: false,
+        reverting_tx_hashes: Vec::new(),
+        max_slot: None,
+        webhook: None,
+        digest_version: Default::default(),
+        signature: None,
+        signer: None,
+    };
 
     request.recover_signers()?;
 
@@ -281,3 +318,19 @@ async fn generate_test_data_kurtosis() {
     // Output SignedConstraints
     println!("{}", serde_json::to_string_pretty(&signed_constraints).unwrap());
 }
+
+/// A clock that only moves when told to.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MockClock(Arc<Mutex<Duration>>);
+
+impl MockClock {
+    pub(crate) fn set(&self, now: Duration) {
+        *self.0.lock().unwrap() = now;
+    }
+}
+
+impl TimeSource for MockClock {
+    fn now(&self) -> Duration {
+        *self.0.lock().unwrap()
+    }
+}